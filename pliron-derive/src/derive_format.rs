@@ -1,6 +1,6 @@
 use proc_macro2::{Span, TokenStream};
 use quote::{ToTokens, format_ident, quote};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use syn::{Data, DeriveInput, LitStr, Result, spanned::Spanned};
 
 use crate::irfmt::{
@@ -417,7 +417,9 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
     ) -> Result<TokenStream> {
         if d.name == "canonical" {
             state.is_canonical = true;
-            Ok(quote! { ::pliron::op::canonical_syntax_print(Box::new(*self), ctx, state, fmt)?; })
+            Ok(
+                quote! { ::pliron::op::canonical_syntax_print(::pliron::op::OpObj::new(*self), ctx, state, fmt)?; },
+            )
         } else if d.name == "type" {
             let err = Err(syn::Error::new_spanned(
                     input.ident.clone(),
@@ -434,6 +436,24 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
             } else {
                 return err;
             }
+        } else if d.name == "functional_type" {
+            if !d.args.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    input.ident.clone(),
+                    "The `functional_type` directive takes no arguments".to_string(),
+                ));
+            }
+            Ok(quote! {
+                let op = self.operation().deref(ctx);
+                let sep = ::pliron::printable::ListSeparator::CharSpace(',');
+                let opd_types = op.operands().map(|opd| ::pliron::r#type::Typed::get_type(&opd, ctx));
+                let res_types = op.results().map(|res| ::pliron::r#type::Typed::get_type(&res, ctx));
+                let fty = ::pliron::irfmt::printers::functional_type(
+                    ::pliron::irfmt::printers::iter_with_sep(opd_types, sep),
+                    ::pliron::irfmt::printers::iter_with_sep(res_types, sep),
+                );
+                ::pliron::printable::Printable::fmt(&fty, ctx, state, fmt)?;
+            })
         } else if d.name == "region" {
             let err = Err(syn::Error::new_spanned(
                 input.ident.clone(),
@@ -477,8 +497,8 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
             };
             Ok(quote! {
                 let succ = self.operation().deref(ctx).successor(#index);
-                let succ_name = "^".to_string() + &succ.unique_name(ctx);
-                ::pliron::printable::Printable::fmt(&succ_name, ctx, state, fmt)?;
+                let succ_label = ::pliron::irfmt::printers::block_label(succ);
+                ::pliron::printable::Printable::fmt(&succ_label, ctx, state, fmt)?;
             })
         } else if d.name == "successors" {
             let err = Err(syn::Error::new_spanned(
@@ -496,7 +516,7 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
             let sep = directive_to_list_separator(sep, true, input.ident.span())?;
             Ok(quote! {
                 let op = self.operation().deref(ctx);
-                let succs = op.successors().map(|succ| "^".to_string() + &succ.unique_name(ctx));
+                let succs = op.successors().map(::pliron::irfmt::printers::block_label);
                 let succs = ::pliron::irfmt::printers::iter_with_sep(succs, #sep);
                 ::pliron::printable::Printable::fmt(&succs, ctx, state, fmt)?;
             })
@@ -545,6 +565,21 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
                 let self_op = self.operation().deref(ctx);
                 ::pliron::printable::Printable::fmt(&self_op.attributes, ctx, state, fmt)?;
             })
+        } else if d.name == "opt" {
+            let attr_name_str = parse_opt_directive_args(d, input)?;
+            let group = d
+                .args
+                .iter()
+                .map(|elem| Self::build_elem(input, state, elem))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(quote! {
+                let has_attr = self.operation().deref(ctx).attributes.0.contains_key(
+                    &::pliron::identifier::Identifier::try_from(#attr_name_str).unwrap()
+                );
+                if has_attr {
+                    #(#group)*
+                }
+            })
         } else {
             unimplemented!("Unknown directive {}", d.name)
         }
@@ -556,15 +591,14 @@ impl PrintableBuilder<OpPrinterState> for DeriveOpPrintable {
         if !state.is_canonical {
             output.extend(quote! {
                 use ::pliron::op::Op;
-                use ::pliron::irfmt::printers::iter_with_sep;
                 use ::pliron::common_traits::Named;
-                let op = self.operation().deref(ctx);
-                if op.num_results() > 0 {
-                    let sep = ::pliron::printable::ListSeparator::CharSpace(',');
-                    let results = iter_with_sep(op.results(), sep);
-                    write!(fmt, "{} = ", results.disp(ctx))?;
+                if self.operation().deref(ctx).num_results() > 0 {
+                    let results = ::pliron::irfmt::printers::op::results(self);
+                    ::pliron::printable::Printable::fmt(&results, ctx, state, fmt)?;
+                    write!(fmt, " = ")?;
                 }
-                write!(fmt, "{} ", self.opid())?;
+                ::pliron::printable::Printable::fmt(&self.opid(), ctx, state, fmt)?;
+                write!(fmt, " ")?;
             });
         }
         output.extend(formatted_tokens);
@@ -651,7 +685,15 @@ trait ParsableBuilder<State: Default> {
         if let FmtData::Enum(r#enum) = &input.data {
             let enum_name = r#enum.name.clone();
             assert!(!r#enum.variants.is_empty(), "Enum has no variants");
+            let variant_names = r#enum
+                .variants
+                .iter()
+                .map(|(_, r#struct)| r#struct.name.to_string());
             let variant_name_parsed = quote! {
+                ::pliron::completion::expect_here(
+                    state_stream,
+                    ::pliron::completion::Expected::Keywords(&[#(#variant_names),*]),
+                );
                 let variant_name_parsed =
                     ::pliron::identifier::Identifier::parser(()).
                     parse_stream(state_stream).into_result()?.0.to_string();
@@ -939,7 +981,11 @@ struct OpParserState {
     operands: ElementSpec<usize>,
     successors: ElementSpec<usize>,
     result_types: FxHashMap<usize, syn::Ident>,
+    // The type specified by the `functional_type` directive, and its location.
+    functional_type: Option<(syn::Ident, syn::Ident)>,
     attributes: ElementSpec<String>,
+    // Attributes specified in `opt` directives, and hence parsed as [Option]s.
+    optional_attributes: FxHashSet<String>,
     regions: ElementSpec<usize>,
 }
 
@@ -1003,15 +1049,38 @@ impl ParsableBuilder<OpParserState> for DeriveOpParsable {
             }
         }
 
-        let results_check = quote! {
-            if arg.len() != #num_result_types {
-                return
-                    input_err!(cur_loc,
-                        "expected {} results as per spec, got {} during parsing",
-                        #num_result_types,
-                        arg.len()
-                    )?;
+        let results_check = match &state.functional_type {
+            Some(_) if num_result_types != 0 => {
+                return Err(syn::Error::new_spanned(
+                    input.ident.clone(),
+                    "Cannot mix functional_type directive with type directives".to_string(),
+                ));
             }
+            Some((fty, fty_loc)) => quote! {
+                let (functional_type_inputs, functional_type_results) = {
+                    let fty = #fty.deref(state_stream.state.ctx);
+                    (fty.inputs().to_vec(), fty.results().to_vec())
+                };
+                if functional_type_results.len() != arg.len() {
+                    return input_err!(
+                        #fty_loc,
+                        ::pliron::op::CanonicalSyntaxParseError::ResultsMismatch {
+                            num_res_ty: functional_type_results.len(),
+                            num_res: arg.len()
+                        }
+                    )?;
+                }
+            },
+            None => quote! {
+                if arg.len() != #num_result_types {
+                    return
+                        input_err!(cur_loc,
+                            "expected {} results as per spec, got {} during parsing",
+                            #num_result_types,
+                            arg.len()
+                        )?;
+                }
+            },
         };
 
         output.extend(results_check);
@@ -1089,21 +1158,37 @@ impl ParsableBuilder<OpParserState> for DeriveOpParsable {
             }
         };
 
-        let result_indices = (0..num_result_types).map(|i| state.result_types[&i].clone());
-        let results = quote! {
-            vec![#( #result_indices ),*]
+        let results = match &state.functional_type {
+            Some(_) => quote! {
+                functional_type_results
+            },
+            None => {
+                let result_indices = (0..num_result_types).map(|i| state.result_types[&i].clone());
+                quote! {
+                    vec![#( #result_indices ),*]
+                }
+            }
         };
 
         let mut attribute_sets = quote! {};
         match &state.attributes {
             ElementSpec::Individual(attributes) => {
                 for (attr_name, attr_ident) in attributes {
-                    attribute_sets.extend(quote! {
+                    let attr_set = quote! {
                         op.deref_mut(state_stream.state.ctx).attributes.0.insert(
                             ::pliron::identifier::Identifier::try_from(#attr_name).unwrap(),
                             #attr_ident,
                         );
-                    });
+                    };
+                    if state.optional_attributes.contains(attr_name) {
+                        attribute_sets.extend(quote! {
+                            if let Some(#attr_ident) = #attr_ident {
+                                #attr_set
+                            }
+                        });
+                    } else {
+                        attribute_sets.extend(attr_set);
+                    }
                 }
             }
             ElementSpec::All(attr_sets_name) => {
@@ -1113,12 +1198,29 @@ impl ParsableBuilder<OpParserState> for DeriveOpParsable {
             }
         }
 
+        output.extend(quote! {
+            let parsed_operands: Vec<::pliron::value::Value> = #operands;
+        });
+        if let Some((_, fty_loc)) = &state.functional_type {
+            output.extend(quote! {
+                if functional_type_inputs.len() != parsed_operands.len() {
+                    return input_err!(
+                        #fty_loc,
+                        ::pliron::op::CanonicalSyntaxParseError::OperandsMismatch {
+                            num_opd_ty: functional_type_inputs.len(),
+                            num_opd: parsed_operands.len()
+                        }
+                    )?;
+                }
+            });
+        }
+
         output.extend(quote! {
             let op = ::pliron::operation::Operation::new(
                 state_stream.state.ctx,
                 Self::opid_static(),
                 #results,
-                #operands,
+                parsed_operands,
                 #successors,
                 0,        // regions
             );
@@ -1229,6 +1331,31 @@ impl ParsableBuilder<OpParserState> for DeriveOpParsable {
             } else {
                 return err;
             }
+        } else if d.name == "functional_type" {
+            if !d.args.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    input.ident.clone(),
+                    "The `functional_type` directive takes no arguments".to_string(),
+                ));
+            }
+            if state.functional_type.is_some() {
+                return Err(syn::Error::new_spanned(
+                    input.ident.clone(),
+                    "The `functional_type` directive can only be specified once".to_string(),
+                ));
+            }
+            let fty = format_ident!("functional_type");
+            let fty_loc = format_ident!("functional_type_loc");
+            state.functional_type = Some((fty.clone(), fty_loc.clone()));
+            Ok(quote! {
+                let (#fty_loc, #fty) = (
+                    ::pliron::irfmt::parsers::location(),
+                    <::pliron::builtin::types::FunctionType as ::pliron::parsable::Parsable>::parser(()),
+                )
+                    .parse_stream(state_stream)
+                    .into_result()?
+                    .0;
+            })
         } else if d.name == "region" {
             let Some(Elem::UnnamedVar(UnnamedVar { index: reg_idx, .. })) = &d.args.first() else {
                 return Err(syn::Error::new_spanned(
@@ -1276,11 +1403,39 @@ impl ParsableBuilder<OpParserState> for DeriveOpParsable {
             }
 
             Ok(quote! {
-                let #attr_name_ident = Box::new(#attr_type_path::parser(())
+                let #attr_name_ident = ::pliron::attribute::AttrObj::from(#attr_type_path::parser(())
                     .parse_stream(state_stream)
                     .into_result()?
                     .0);
             })
+        } else if d.name == "opt" {
+            let attr_name_str = parse_opt_directive_args(d, input)?;
+            let Some(Elem::Lit(Lit { lit, .. })) = d.args.first() else {
+                unreachable!("The first argument to `opt` directive must be a literal");
+            };
+            let trimmed_lit = lit.trim();
+            let group = d.args[1..]
+                .iter()
+                .map(|elem| Self::build_elem(input, state, elem))
+                .collect::<Result<Vec<_>>>()?;
+            state.optional_attributes.insert(attr_name_str.clone());
+            let attr_name_ident = format_ident!("{}", attr_name_str);
+            // The group is parsed only if its leading literal is present.
+            Ok(quote! {
+                let #attr_name_ident = if ::combine::optional(::combine::attempt(
+                    ::pliron::irfmt::parsers::spaced(::combine::parser::char::string(#trimmed_lit)),
+                ))
+                .parse_stream(state_stream)
+                .into_result()?
+                .0
+                .is_some()
+                {
+                    #(#group)*
+                    Some(#attr_name_ident)
+                } else {
+                    None
+                };
+            })
         } else if d.name == "succ" {
             let Some(Elem::UnnamedVar(UnnamedVar { index, .. })) = &d.args.first() else {
                 return Err(syn::Error::new_spanned(
@@ -1481,6 +1636,43 @@ fn parse_attr_directive_args(d: &Directive, input: &FmtInput) -> Result<(String,
     Ok((attr_name_str, attr_type_path))
 }
 
+/// Validate the arguments of an `opt` directive, and get the name of its anchor attribute.
+/// The first argument must be a literal, and exactly one of the remaining arguments must be
+/// an attribute (either a named variable or an `attr` directive). The rest must be literals.
+fn parse_opt_directive_args(d: &Directive, input: &FmtInput) -> Result<String> {
+    let err = |msg: &str| {
+        Err(syn::Error::new_spanned(
+            input.ident.clone(),
+            msg.to_string(),
+        ))
+    };
+    if !matches!(d.args.first(), Some(Elem::Lit(_))) {
+        return err("The first argument to `opt` directive must be a literal");
+    }
+    let mut anchor = None;
+    for arg in &d.args[1..] {
+        let attr_name = match arg {
+            Elem::Lit(_) => continue,
+            Elem::Var(Var { name, .. }) => name.clone(),
+            Elem::Directive(attr_d) if attr_d.name == "attr" => {
+                parse_attr_directive_args(attr_d, input)?.0
+            }
+            _ => {
+                return err(
+                    "Arguments to `opt` directive can only be literals and a single attribute",
+                );
+            }
+        };
+        if anchor.replace(attr_name).is_some() {
+            return err("The `opt` directive must contain exactly one attribute");
+        }
+    }
+    match anchor {
+        Some(anchor) => Ok(anchor),
+        None => err("The `opt` directive must contain exactly one attribute"),
+    }
+}
+
 use syn::{
     AngleBracketedGenericArguments, GenericArgument, Path, PathArguments, PathSegment, Type,
     TypePath,
@@ -108,7 +108,7 @@ impl ToTokens for ImplOp {
                 }
 
                 fn wrap_operation(op: ::pliron::context::Ptr<::pliron::operation::Operation>) -> ::pliron::op::OpObj {
-                    Box::new(#name { op })
+                    ::pliron::op::OpObj::new(#name { op })
                 }
 
                 fn opid(&self) -> ::pliron::op::OpId {
@@ -164,7 +164,7 @@ mod tests {
                 fn wrap_operation(
                     op: ::pliron::context::Ptr<::pliron::operation::Operation>,
                 ) -> ::pliron::op::OpObj {
-                    Box::new(TestOp { op })
+                    ::pliron::op::OpObj::new(TestOp { op })
                 }
                 fn opid(&self) -> ::pliron::op::OpId {
                     Self::opid_static()
@@ -15,8 +15,8 @@ use syn::{ItemTrait, Result};
 
 /// This macro does two things:
 /// 1. Prepends `supertrait` as a super trait.
-/// 2. Adds an entry in `interface_deps_slice` for each
-///    super interface, so that verifiers of those can be run prior to this.
+/// 2. Adds an entry in `interface_deps_slice`, with the interface's name and
+///    its super interfaces, so that verifiers of those can be run prior to this.
 pub(crate) fn interface_define(
     input: proc_macro::TokenStream,
     supertrait: Path,
@@ -44,9 +44,10 @@ pub(crate) fn interface_define(
     let deps_entry = quote! {
         const _: () = {
             #[linkme::distributed_slice(#interface_deps_slice)]
-            static INTERFACE_DEP: std::sync::LazyLock<(std::any::TypeId, Vec<std::any::TypeId>)>
+            static INTERFACE_DEP: std::sync::LazyLock<(std::any::TypeId, &'static str, Vec<std::any::TypeId>)>
                 = std::sync::LazyLock::new(|| {
-                    (std::any::TypeId::of::<dyn #intr_name>(), vec![#(std::any::TypeId::of::<dyn #dep_interfaces>(),)*])
+                    (std::any::TypeId::of::<dyn #intr_name>(), stringify!(#intr_name),
+                     vec![#(std::any::TypeId::of::<dyn #dep_interfaces>(),)*])
              });
         };
     };
@@ -116,12 +117,15 @@ impl Parse for PathList {
 
 /// For each interface specified in the list, expand it to
 /// ```no_compile
-/// #[op_interface_impl]
-/// impl Interface for OpStruct { }
+/// #[interface_impl_macro]
+/// impl Interface for Struct { }
 /// ```
-pub(crate) fn derive_op_interface_impl(
+/// where `interface_impl_macro` is one of `op_interface_impl`,
+/// `attr_interface_impl` or `type_interface_impl`.
+pub(crate) fn derive_interface_impl(
     attr: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
+    interface_impl_macro: Path,
 ) -> Result<proc_macro2::TokenStream> {
     let intrs = syn::parse2::<PathList>(attr.into())?;
     let input = syn::parse2::<DeriveInput>(input.into())?;
@@ -129,7 +133,7 @@ pub(crate) fn derive_op_interface_impl(
 
     let impls = intrs.paths.into_iter().map(|path| {
         quote! {
-            #[::pliron::derive::op_interface_impl]
+            #[#interface_impl_macro]
             impl #path for #struct_name {}
         }
     });
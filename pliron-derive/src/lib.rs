@@ -223,6 +223,17 @@ pub fn format(args: TokenStream, input: TokenStream) -> TokenStream {
 ///      as that for "operands" above. This cannot be combined with the "region" directive.
 ///  10. The "attr_dict" directive specifies an [AttributeDict](../pliron/attribute/struct.AttributeDict.html).
 ///      It cannot be combined with either the "attr" directive or a named variable (`$name`).
+///  11. The "functional_type" directive specifies the types of all operands and results,
+///      as a [FunctionType](../pliron/builtin/types/struct.FunctionType.html), printed as
+///      `<(t1, t2) -> (t3)>`, just like in the canonical syntax. It takes no arguments.
+///      The result types are determined by it, and the number of operand types must match
+///      the number of operands. This cannot be combined with the "type" directive.
+///  12. The "opt" directive specifies an optional group, keyed on the presence of an attribute.
+///      Its arguments are a leading literal, followed by literals and exactly one attribute
+///      (a named variable `$name` or an "attr" directive). The group is printed only if
+///      the operation has the attribute, and is parsed only if its leading literal is present.
+///      For example, ``opt(`[`, attr($count, $IntegerAttr), `]`)`` prints (and parses) an
+///      optional `[<1: si64>]`.
 ///
 /// Examples:
 /// 1. Derive for a struct, with no format string (default format):
@@ -398,7 +409,12 @@ pub fn op_interface_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_attribute]
 pub fn derive_op_interface_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
-    to_token_stream(interfaces::derive_op_interface_impl(attr, item))
+    let interface_impl_macro = parse_quote! { ::pliron::derive::op_interface_impl };
+    to_token_stream(interfaces::derive_interface_impl(
+        attr,
+        item,
+        interface_impl_macro,
+    ))
 }
 
 /// Declare an [Attribute](../pliron/attribute/trait.Attribute.html) interface,
@@ -514,13 +530,58 @@ pub fn attr_interface_impl(_attr: TokenStream, item: TokenStream) -> TokenStream
     ))
 }
 
+/// Derive implementation of an [Attribute](../pliron/attribute/trait.Attribute.html)
+/// Interface for an Attribute. Note that an impl can be derived only for those
+/// interfaces that do not require any methods to be defined during the impl.
+///
+/// Usage:
+/// ```
+/// # use pliron::derive::{attr_interface, derive_attr_interface_impl};
+///
+/// #[def_attribute("dialect.name")]
+/// #[derive_attr_interface_impl(MyAttrInterface)]
+/// #[derive(PartialEq, Eq, Clone, Debug)]
+/// struct MyAttr { }
+///
+/// #[attr_interface]
+/// pub trait MyAttrInterface {
+///     fn gubbi(&self) { println!("gubbi"); }
+///     fn verify(attr: &dyn Attribute, ctx: &Context) -> Result<()>
+///     where Self: Sized,
+///     {
+///         Ok(())
+///     }
+/// }
+/// # use pliron::derive::def_attribute;
+/// # use pliron::{
+/// #     printable::{self, Printable},
+/// #     context::Context, result::Result, common_traits::Verify,
+/// #     attribute::Attribute
+/// # };
+/// # impl Printable for MyAttr {
+/// #    fn fmt(&self, _ctx: &Context, _state: &printable::State, _f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+/// #        unimplemented!()
+/// #    }
+/// # }
+/// # pliron::impl_verify_succ!(MyAttr);
+/// ```
+#[proc_macro_attribute]
+pub fn derive_attr_interface_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let interface_impl_macro = parse_quote! { ::pliron::derive::attr_interface_impl };
+    to_token_stream(interfaces::derive_interface_impl(
+        attr,
+        item,
+        interface_impl_macro,
+    ))
+}
+
 /// Declare a [Type](../pliron/type/trait.Type.html) interface,
 /// which can be implemented by any `Type`.
 ///
 /// If the interface requires any other interface to be already implemented,
 /// they can be specified super-traits.
 ///
-/// When an `Attribute` is verified, its interfaces are also automatically verified,
+/// When a `Type` is verified, its interfaces are also automatically verified,
 /// with guarantee that a super-interface is verified before an interface itself is.
 ///
 /// Example: Here `Super1` and `Super2` are super interfaces for the interface `MyTypeIntr`.
@@ -625,3 +686,48 @@ pub fn type_interface_impl(_attr: TokenStream, item: TokenStream) -> TokenStream
         get_id_static,
     ))
 }
+
+/// Derive implementation of a [Type](../pliron/type/trait.Type.html) Interface for a Type.
+/// Note that an impl can be derived only for those interfaces that do not require any
+/// methods to be defined during the impl.
+///
+/// Usage:
+/// ```
+/// # use pliron::derive::{type_interface, derive_type_interface_impl};
+///
+/// #[def_type("dialect.name")]
+/// #[derive_type_interface_impl(MyTypeInterface)]
+/// #[derive(PartialEq, Eq, Clone, Debug, Hash)]
+/// struct MyType { }
+///
+/// #[type_interface]
+/// pub trait MyTypeInterface {
+///     fn gubbi(&self) { println!("gubbi"); }
+///     fn verify(r#type: &dyn Type, ctx: &Context) -> Result<()>
+///     where Self: Sized,
+///     {
+///         Ok(())
+///     }
+/// }
+/// # use pliron::derive::def_type;
+/// # use pliron::{
+/// #     printable::{self, Printable},
+/// #     context::Context, result::Result, common_traits::Verify,
+/// #     r#type::Type
+/// # };
+/// # impl Printable for MyType {
+/// #    fn fmt(&self, _ctx: &Context, _state: &printable::State, _f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+/// #        unimplemented!()
+/// #    }
+/// # }
+/// # pliron::impl_verify_succ!(MyType);
+/// ```
+#[proc_macro_attribute]
+pub fn derive_type_interface_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let interface_impl_macro = parse_quote! { ::pliron::derive::type_interface_impl };
+    to_token_stream(interfaces::derive_interface_impl(
+        attr,
+        item,
+        interface_impl_macro,
+    ))
+}
@@ -80,7 +80,7 @@ fn one_result_zero_operands() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.one_result_zero_operands :builtin.integer si64;
             test.return res0_op_2v1_res0
         }"#]]
@@ -120,7 +120,7 @@ fn one_result_one_operand() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.one_result_zero_operands :builtin.integer si64;
             res1_op_3v1_res0 = test.one_result_one_operand res0_op_2v1_res0:builtin.integer si64;
             test.return res1_op_3v1_res0
@@ -160,7 +160,7 @@ fn two_result_two_operands() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.one_result_zero_operands :builtin.integer si64;
             res1a_op_3v1_res0, res1b_op_3v1_res1 = test.two_results_two_operands res0_op_2v1_res0,res0_op_2v1_res0:(builtin.integer si64,builtin.integer si64);
             test.return res1a_op_3v1_res0
@@ -170,6 +170,68 @@ fn two_result_two_operands() {
     assert!(res.verify(ctx).is_ok());
 }
 
+#[format_op("operands(CharSpace(`,`)) `:` functional_type")]
+#[def_op("test.functional_type_op")]
+struct FunctionalTypeOp {}
+impl_verify_succ!(FunctionalTypeOp);
+
+#[test]
+fn functional_type_op() {
+    let ctx = &mut setup_context_dialects();
+    OneResultZeroOperandsOp::register(ctx, OneResultZeroOperandsOp::parser_fn);
+    FunctionalTypeOp::register(ctx, FunctionalTypeOp::parser_fn);
+
+    let printed = "builtin.func @testfunc: builtin.function <() -> ()> {
+          ^entry():
+            res0 = test.one_result_zero_operands :builtin.integer si64;
+            res1a, res1b = test.functional_type_op res0, res0 :
+              <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64, builtin.integer si32)>;
+            test.return res1a
+        }";
+
+    let state_stream = state_stream_from_iterator(
+        printed.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+
+    let (res, _) = Operation::parser(())
+        .parse(state_stream)
+        .expect("FunctionalTypeOp parser failed");
+
+    expect![[r#"
+        builtin.func @testfunc: builtin.function <()->()> 
+        {
+          ^entry():
+            res0_op_2v1_res0 = test.one_result_zero_operands :builtin.integer si64;
+            res1a_op_3v1_res0, res1b_op_3v1_res1 = test.functional_type_op res0_op_2v1_res0, res0_op_2v1_res0:<(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64, builtin.integer si32)>;
+            test.return res1a_op_3v1_res0
+        }"#]]
+    .assert_eq(&res.disp(ctx).to_string());
+
+    assert!(res.verify(ctx).is_ok());
+
+    // The number of operand types must match the number of operands.
+    let printed = "builtin.func @testfunc: builtin.function <() -> ()> {
+          ^entry():
+            res0 = test.one_result_zero_operands :builtin.integer si64;
+            res1 = test.functional_type_op res0 : <() -> (builtin.integer si64)>;
+            test.return res1
+        }";
+
+    let state_stream = state_stream_from_iterator(
+        printed.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+
+    let res = Operation::parser(()).parse(state_stream);
+    let err_msg = format!("{}", res.err().unwrap());
+    expect![[r#"
+        Parse error at line: 4, column: 51
+        Type specifies 0 operands, but operation has 1 operands
+    "#]]
+    .assert_eq(&err_msg);
+}
+
 use pliron::builtin::attributes::IntegerAttr;
 
 #[format_op("attr($attr, $IntegerAttr) `:` type($0)")]
@@ -200,7 +262,7 @@ fn attr_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
             test.return res0_op_2v1_res0
         }"#]]
@@ -209,6 +271,49 @@ fn attr_op() {
     assert!(res.verify(ctx).is_ok());
 }
 
+#[format_op("opt(`[`, attr($count, $IntegerAttr), `]`) opt(` tag `, $tag) `:` type($0)")]
+#[def_op("test.opt_attr_op")]
+struct OptAttrOp {}
+impl_verify_succ!(OptAttrOp);
+
+#[test]
+fn opt_attr_op() {
+    let ctx = &mut setup_context_dialects();
+    OptAttrOp::register(ctx, OptAttrOp::parser_fn);
+
+    let printed = "builtin.func @testfunc: builtin.function <() -> ()> {
+          ^entry():
+            res0 = test.opt_attr_op :builtin.integer si64;
+            res1 = test.opt_attr_op [<1: si64>] :builtin.integer si64;
+            res2 = test.opt_attr_op tag builtin.unit :builtin.integer si64;
+            res3 = test.opt_attr_op [<2: si64>] tag builtin.unit :builtin.integer si64;
+            test.return res0
+        }";
+
+    let state_stream = state_stream_from_iterator(
+        printed.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+
+    let (res, _) = Operation::parser(())
+        .parse(state_stream)
+        .expect("OptAttrOp parser failed");
+
+    expect![[r#"
+        builtin.func @testfunc: builtin.function <()->()> 
+        {
+          ^entry():
+            res0_op_2v1_res0 = test.opt_attr_op :builtin.integer si64;
+            res1_op_3v1_res0 = test.opt_attr_op [<1: si64>]:builtin.integer si64;
+            res2_op_4v1_res0 = test.opt_attr_op  tag builtin.unit :builtin.integer si64;
+            res3_op_5v1_res0 = test.opt_attr_op [<2: si64>] tag builtin.unit :builtin.integer si64;
+            test.return res0_op_2v1_res0
+        }"#]]
+    .assert_eq(&res.disp(ctx).to_string());
+
+    assert!(res.verify(ctx).is_ok());
+}
+
 #[format_op("attr($attr, `pliron::builtin::attributes::StringAttr`) `:` type($0)")]
 #[def_op("test.attr_op2")]
 struct AttrOp2 {}
@@ -237,7 +342,7 @@ fn attr_op2() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op2 "Hello World":builtin.integer si64;
             test.return res0_op_2v1_res0
         }"#]]
@@ -274,7 +379,7 @@ fn attr_op3() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op3 builtin.integer <0: si64>:builtin.integer si64;
             test.return res0_op_2v1_res0
         }"#]]
@@ -318,11 +423,11 @@ fn if_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_2v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
             test.if_op (res0_op_2v1_res0)
             {
-              ^then_block_1v1():
+              ^then():
                 res1_op_4v1_res0 = test.attr_op <1: si64>:builtin.integer si64;
                 test.return res1_op_4v1_res0
             };
@@ -372,16 +477,16 @@ fn if_else_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_3v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
             test.if_else_op (res0_op_2v1_res0)
             {
-              ^then_block_1v1():
+              ^then():
                 res1_op_4v1_res0 = test.attr_op <1: si64>:builtin.integer si64;
                 test.return res1_op_4v1_res0
             }else
             {
-              ^else_block_2v1():
+              ^else():
                 res2_op_6v1_res0 = test.attr_op <2: si64>:builtin.integer si64;
                 test.return res2_op_6v1_res0
             };
@@ -424,10 +529,10 @@ fn br_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_2v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
-            test.br ^bb1_block_3v1(res0_op_2v1_res0)
-          ^bb1_block_3v1(arg0_block_3v1_arg0:builtin.integer si64):
+            test.br ^bb1(res0_op_2v1_res0)
+          ^bb1(arg0_block_3v1_arg0:builtin.integer si64):
             test.return arg0_block_3v1_arg0
         }"#]]
     .assert_eq(&res.disp(ctx).to_string());
@@ -469,12 +574,12 @@ fn multiple_successors_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_3v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
-            test.multiple_successors [^bb1_block_4v1, ^bb2_block_1v3]
-          ^bb1_block_4v1():
+            test.multiple_successors [^bb1, ^bb2]
+          ^bb1():
             test.return res0_op_2v1_res0
-          ^bb2_block_1v3():
+          ^bb2():
             test.return res0_op_2v1_res0
         }"#]]
     .assert_eq(&res.disp(ctx).to_string());
@@ -523,16 +628,16 @@ fn multiple_regions_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_3v1():
+          ^entry():
             res_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
             test.multiple_regions [
             {
-              ^reg1_entry_block_1v1():
+              ^reg1_entry():
                 res0_op_4v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
                 test.return res0_op_4v1_res0
             }, 
             {
-              ^reg2_entry_block_2v1():
+              ^reg2_entry():
                 res1_op_6v1_res0 = test.attr_op <1: si64>:builtin.integer si64;
                 test.return res1_op_6v1_res0
             }];
@@ -576,7 +681,7 @@ fn attr_dict_op() {
     expect![[r#"
         builtin.func @testfunc: builtin.function <()->()> 
         {
-          ^entry_block_1v1():
+          ^entry():
             res0_op_2v1_res0 = test.attr_op <3: si64>:builtin.integer si64;
             test.attr_dict [(attr1: builtin.integer <0: si64>), (attr2: builtin.integer <1: si64>)];
             test.return res0_op_2v1_res0
@@ -695,12 +800,12 @@ fn multiple_regions4_op() {
     expect![[r#"
         test.multiple_regions4 [
         {
-          ^reg1_entry_block_1v1():
+          ^reg1_entry():
             res0_op_2v1_res0 = test.attr_op <0: si64>:builtin.integer si64;
             test.return res0_op_2v1_res0
         }, 
         {
-          ^reg2_entry_block_2v1():
+          ^reg2_entry():
             res1_op_4v1_res0 = test.attr_op <1: si64>:builtin.integer si64;
             test.return res1_op_4v1_res0
         }]"#]]
@@ -1,11 +1,27 @@
 //! Attributes belonging to the LLVM dialect.
 
+use pliron::arg_err_noloc;
 use pliron::attribute::Attribute;
-use pliron::context::Context;
-use pliron::derive::{def_attribute, format, format_attribute};
+use pliron::builtin::{
+    attr_interfaces::{ElidableAttrInterface, TypedAttrInterface},
+    attributes::IntegerAttr,
+    type_interfaces::DataLayout,
+    types::IntegerType,
+};
+use pliron::common_traits::Verify;
+use pliron::context::{Context, Ptr};
+use pliron::derive::{attr_interface_impl, def_attribute, format, format_attribute};
 
 use pliron::impl_verify_succ;
 use pliron::parsable::Parsable;
+use pliron::printable::Printable;
+use pliron::result::Result;
+use pliron::r#type::{TypeObj, TypePtr};
+use pliron::utils::apint::{APInt, bw};
+use pliron::verify_err_noloc;
+use thiserror::Error;
+
+use crate::types::{FuncType, VectorType, VoidType};
 
 /// Integer overflow flags for arithmetic operations.
 /// The description below is from LLVM's
@@ -43,6 +59,43 @@ pub enum ICmpPredicateAttr {
 
 impl_verify_succ!(ICmpPredicateAttr);
 
+/// Atomic ordering of a memory access, as described in LLVM's
+/// [LangRef](https://llvm.org/docs/LangRef.html#atomic-memory-ordering-constraints).
+#[def_attribute("llvm.atomic_ordering")]
+#[format_attribute]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AtomicOrderingAttr {
+    NotAtomic,
+    Unordered,
+    Monotonic,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+impl_verify_succ!(AtomicOrderingAttr);
+
+/// The operation performed by an atomic read-modify-write.
+#[def_attribute("llvm.atomic_rmw_bin_op")]
+#[format_attribute]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AtomicRmwBinOpAttr {
+    Xchg,
+    Add,
+    Sub,
+    And,
+    Nand,
+    Or,
+    Xor,
+    Max,
+    Min,
+    UMax,
+    UMin,
+}
+
+impl_verify_succ!(AtomicRmwBinOpAttr);
+
 /// An index for a GEP can be either a constant or an SSA operand.
 /// Contrary to its name, this isn't an [Attribute][pliron::attribute::Attribute].
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -61,10 +114,239 @@ pub enum GepIndexAttr {
 pub struct GepIndicesAttr(pub Vec<GepIndexAttr>);
 impl_verify_succ!(GepIndicesAttr);
 
+/// An element of a shuffle mask: either an index into the
+/// concatenation of the two source vectors, or poison.
+/// Contrary to its name, this isn't an [Attribute][pliron::attribute::Attribute].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[format]
+pub enum ShuffleMaskElemAttr {
+    /// Pick the element at this index.
+    Index(u32),
+    /// The result element is poison.
+    Poison,
+}
+
+#[def_attribute("llvm.shuffle_mask")]
+#[format_attribute("`[` vec($0, CharSpace(`,`)) `]`")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ShuffleMaskAttr(pub Vec<ShuffleMaskElemAttr>);
+impl_verify_succ!(ShuffleMaskAttr);
+
+/// A constant fixed length vector of integers.
+#[def_attribute("llvm.constant_vector")]
+#[format_attribute("`[` vec($elems, CharSpace(`,`)) `]` `: ` $ty")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ConstantVectorAttr {
+    elems: Vec<IntegerAttr>,
+    ty: Ptr<TypeObj>,
+}
+
+impl ConstantVectorAttr {
+    /// Create a new [ConstantVectorAttr] of type `ty`, which must be a [VectorType].
+    pub fn new(ty: TypePtr<VectorType>, elems: Vec<IntegerAttr>) -> Self {
+        ConstantVectorAttr {
+            elems,
+            ty: ty.into(),
+        }
+    }
+
+    /// Get the elements of this vector.
+    pub fn elems(&self) -> &[IntegerAttr] {
+        &self.elems
+    }
+
+    /// The bytes of this vector, as stored in memory on a target with `layout`:
+    /// the [bytes](DataLayout::int_to_bytes) of each element, in order. Fails if the
+    /// elements aren't a whole number of bytes wide, since LLVM packs their bits instead.
+    pub fn to_bytes(&self, layout: &DataLayout) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        for elem in &self.elems {
+            let elem = APInt::from(elem.clone());
+            if !elem.bw().is_multiple_of(8) {
+                return arg_err_noloc!(ConstantVectorAttrErr::NotByteSized(elem.bw()));
+            }
+            bytes.extend(layout.int_to_bytes(&elem));
+        }
+        Ok(bytes)
+    }
+
+    /// Reinterpret `bytes`, as stored in memory on a target with `layout`,
+    /// as a constant vector of type `ty`. See [to_bytes](Self::to_bytes).
+    /// Fails if the elements of `ty` aren't integers a whole number of bytes wide,
+    /// or if `bytes` doesn't hold exactly as many elements as `ty` has.
+    pub fn from_bytes(
+        ctx: &Context,
+        ty: TypePtr<VectorType>,
+        bytes: &[u8],
+        layout: &DataLayout,
+    ) -> Result<Self> {
+        let (elem_ty, num_elements) = {
+            let vec_ty = ty.deref(ctx);
+            (vec_ty.elem_type(), vec_ty.num_elements() as usize)
+        };
+        let Ok(elem_ty) = TypePtr::<IntegerType>::from_ptr(elem_ty, ctx) else {
+            return arg_err_noloc!(ConstantVectorAttrErr::ElemTypeMismatch);
+        };
+        let width = elem_ty.deref(ctx).width() as usize;
+        if !width.is_multiple_of(8) {
+            return arg_err_noloc!(ConstantVectorAttrErr::NotByteSized(width));
+        }
+        let elem_size = width / 8;
+        if bytes.len() != elem_size * num_elements {
+            return arg_err_noloc!(ConstantVectorAttrErr::ByteLengthMismatch(
+                bytes.len(),
+                elem_size * num_elements
+            ));
+        }
+        let elems = bytes
+            .chunks(elem_size)
+            .map(|elem| {
+                let value = layout.int_from_bytes(elem, bw(width))?;
+                Ok(IntegerAttr::new(elem_ty, value))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::new(ty, elems))
+    }
+}
+
+#[attr_interface_impl]
+impl TypedAttrInterface for ConstantVectorAttr {
+    fn get_type(&self) -> Ptr<TypeObj> {
+        self.ty
+    }
+}
+
+#[attr_interface_impl]
+impl ElidableAttrInterface for ConstantVectorAttr {
+    fn num_elements(&self) -> usize {
+        self.elems.len()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConstantVectorAttrErr {
+    #[error("Constant vector must have a vector type")]
+    NotVectorType,
+    #[error("Constant vector has {0} elements, but its type has {1} elements")]
+    NumElemsMismatch(usize, u32),
+    #[error("Constant vector element type doesn't match its type")]
+    ElemTypeMismatch,
+    #[error("Constant vector elements of {0} bits aren't a whole number of bytes")]
+    NotByteSized(usize),
+    #[error("{0} bytes can't be reinterpreted as a constant vector of {1} bytes")]
+    ByteLengthMismatch(usize, usize),
+}
+
+impl Verify for ConstantVectorAttr {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let ty = self.ty.deref(ctx);
+        let Some(vec_ty) = ty.downcast_ref::<VectorType>() else {
+            return verify_err_noloc!(ConstantVectorAttrErr::NotVectorType);
+        };
+        if self.elems.len() != vec_ty.num_elements() as usize {
+            return verify_err_noloc!(ConstantVectorAttrErr::NumElemsMismatch(
+                self.elems.len(),
+                vec_ty.num_elements()
+            ));
+        }
+        if self
+            .elems
+            .iter()
+            .any(|elem| elem.get_type() != vec_ty.elem_type())
+        {
+            return verify_err_noloc!(ConstantVectorAttrErr::ElemTypeMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("{0} value can't be of type {1}")]
+pub struct UndefPoisonTypeErr(pub &'static str, pub String);
+
+/// Verify that there can be an `undef` or `poison` (as per `what`) value of type `ty`.
+/// There can't be values of [VoidType] or [FuncType].
+pub(crate) fn verify_undef_poison_type(
+    ctx: &Context,
+    ty: Ptr<TypeObj>,
+    what: &'static str,
+) -> Result<()> {
+    if ty.isa::<VoidType>(ctx) || ty.isa::<FuncType>(ctx) {
+        return verify_err_noloc!(UndefPoisonTypeErr(what, ty.disp(ctx).to_string()));
+    }
+    Ok(())
+}
+
+/// An undefined value of a type, as in LLVM's
+/// [undef](https://llvm.org/docs/LangRef.html#undefined-values):
+/// each use of it may observe a different (arbitrary) value.
+#[def_attribute("llvm.undef")]
+#[format_attribute("`<` $ty `>`")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct UndefAttr {
+    ty: Ptr<TypeObj>,
+}
+
+impl UndefAttr {
+    /// Create a new [UndefAttr] of type `ty`.
+    pub fn new(ty: Ptr<TypeObj>) -> Self {
+        UndefAttr { ty }
+    }
+}
+
+#[attr_interface_impl]
+impl TypedAttrInterface for UndefAttr {
+    fn get_type(&self) -> Ptr<TypeObj> {
+        self.ty
+    }
+}
+
+impl Verify for UndefAttr {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        verify_undef_poison_type(ctx, self.ty, "Undef")
+    }
+}
+
+/// A poison value of a type, as in LLVM's
+/// [poison](https://llvm.org/docs/LangRef.html#poison-values):
+/// the result of an erroneous operation, that most operations propagate.
+#[def_attribute("llvm.poison")]
+#[format_attribute("`<` $ty `>`")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PoisonAttr {
+    ty: Ptr<TypeObj>,
+}
+
+impl PoisonAttr {
+    /// Create a new [PoisonAttr] of type `ty`.
+    pub fn new(ty: Ptr<TypeObj>) -> Self {
+        PoisonAttr { ty }
+    }
+}
+
+#[attr_interface_impl]
+impl TypedAttrInterface for PoisonAttr {
+    fn get_type(&self) -> Ptr<TypeObj> {
+        self.ty
+    }
+}
+
+impl Verify for PoisonAttr {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        verify_undef_poison_type(ctx, self.ty, "Poison")
+    }
+}
+
 pub fn register(ctx: &mut Context) {
     IntegerOverflowFlagsAttr::register_attr_in_dialect(ctx, IntegerOverflowFlagsAttr::parser_fn);
     ICmpPredicateAttr::register_attr_in_dialect(ctx, ICmpPredicateAttr::parser_fn);
     GepIndicesAttr::register_attr_in_dialect(ctx, GepIndicesAttr::parser_fn);
+    AtomicOrderingAttr::register_attr_in_dialect(ctx, AtomicOrderingAttr::parser_fn);
+    AtomicRmwBinOpAttr::register_attr_in_dialect(ctx, AtomicRmwBinOpAttr::parser_fn);
+    ShuffleMaskAttr::register_attr_in_dialect(ctx, ShuffleMaskAttr::parser_fn);
+    ConstantVectorAttr::register_attr_in_dialect(ctx, ConstantVectorAttr::parser_fn);
+    UndefAttr::register_attr_in_dialect(ctx, UndefAttr::parser_fn);
+    PoisonAttr::register_attr_in_dialect(ctx, PoisonAttr::parser_fn);
 }
 
 #[def_attribute("llvm.insert_extract_value_indices")]
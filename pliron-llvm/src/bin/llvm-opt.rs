@@ -2,8 +2,8 @@ use std::{path::PathBuf, process::ExitCode};
 
 use clap::Parser;
 use pliron::{
-    arg_error_noloc, common_traits::Verify, context::Context, op::Op, printable::Printable,
-    result::Result, verify_error_noloc,
+    arg_error_noloc, common_traits::Verify, context::Context, diagnostics::DiagnosticFormat,
+    op::Op, result::Result, verify_error_noloc,
 };
 use pliron_llvm::{
     from_llvm_ir,
@@ -25,6 +25,10 @@ struct Cli {
     /// Emit text assembly LLVM-IR
     #[arg(short = 'S', default_value_t = false)]
     text_output: bool,
+
+    /// Format of the reported errors: human or json
+    #[arg(long, value_name = "FORMAT", default_value_t = DiagnosticFormat::Human)]
+    diagnostics: DiagnosticFormat,
 }
 
 fn run(cli: Cli, ctx: &mut Context) -> Result<()> {
@@ -59,10 +63,11 @@ pub fn main() -> ExitCode {
     pliron::builtin::register(ctx);
     pliron_llvm::register(ctx);
 
+    let diagnostics = cli.diagnostics;
     match run(cli, ctx) {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
-            eprintln!("{}", e.disp(ctx));
+            eprintln!("{}", diagnostics.render_error(ctx, &e));
             ExitCode::FAILURE
         }
     }
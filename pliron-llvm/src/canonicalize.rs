@@ -0,0 +1,48 @@
+//! Canonicalizations of LLVM dialect operations.
+//!
+//! [CanonicalizeInterface] is implemented for `add` and `mul`, which, with
+//! (only) a constant left operand, have their operands swapped, so that
+//! constants are on the right. Operations on constants are [folded](crate::fold).
+
+use pliron::{
+    context::{Context, Ptr},
+    derive::op_interface_impl,
+    op::Op,
+    operation::Operation,
+    pattern_match::PatternRewriter,
+    result::Result,
+    transforms::canonicalize::{CanonicalizeInterface, is_constant},
+};
+
+use crate::ops::{AddOp, MulOp};
+
+/// Move a constant left operand of the commutative binary operation `op`
+/// to the right, unless both operands are constants.
+fn constant_to_rhs(
+    ctx: &mut Context,
+    rewriter: &mut PatternRewriter,
+    op: Ptr<Operation>,
+) -> Result<bool> {
+    let (lhs, rhs) = (op.deref(ctx).operand(0), op.deref(ctx).operand(1));
+    if !is_constant(ctx, &lhs) || is_constant(ctx, &rhs) {
+        return Ok(false);
+    }
+    rewriter.modify_op_in_place(ctx, op, |ctx| {
+        Operation::set_operands(op, ctx, vec![rhs, lhs]);
+    });
+    Ok(true)
+}
+
+#[op_interface_impl]
+impl CanonicalizeInterface for AddOp {
+    fn canonicalize(&self, ctx: &mut Context, rewriter: &mut PatternRewriter) -> Result<bool> {
+        constant_to_rhs(ctx, rewriter, self.operation())
+    }
+}
+
+#[op_interface_impl]
+impl CanonicalizeInterface for MulOp {
+    fn canonicalize(&self, ctx: &mut Context, rewriter: &mut PatternRewriter) -> Result<bool> {
+        constant_to_rhs(ctx, rewriter, self.operation())
+    }
+}
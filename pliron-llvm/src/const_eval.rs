@@ -0,0 +1,157 @@
+//! Evaluating LLVM dialect integer operations on constants.
+//!
+//! [const_eval] computes the result of an integer operation from the values of
+//! its operands. Binary operations are evaluated by [eval_int_bin_op], with
+//! their `nsw` or `nuw` [flag](IntegerOverflowFlagsAttr), following the semantics
+//! in LLVM's [LangRef](https://llvm.org/docs/LangRef.html).
+//!
+//! The [folds](crate::fold) of these operations, and so
+//! [constant folding](pliron::transforms::const_fold), are built on it.
+
+use pliron::{
+    builtin::types::IntegerType,
+    context::Context,
+    op::Op,
+    r#type::{TypePtr, Typed},
+    utils::{
+        apint::{APInt, bw},
+        const_eval::{ConstValue, IntBinOpcode, OverflowFlags, eval_int_bin_op},
+    },
+};
+
+use crate::{
+    attributes::{ICmpPredicateAttr, IntegerOverflowFlagsAttr},
+    op_interfaces::ATTR_KEY_INTEGER_OVERFLOW_FLAGS,
+    ops::{
+        AShrOp, AddOp, AndOp, ICmpOp, LShrOp, MulOp, OrOp, SDivOp, SExtOp, SRemOp, ShlOp, SubOp,
+        UDivOp, URemOp, XorOp, ZExtOp,
+    },
+};
+
+/// The [IntBinOpcode] of `op`, if it's an integer binary operation.
+pub fn int_bin_opcode(op: &dyn Op) -> Option<IntBinOpcode> {
+    let opcode = if op.is::<AddOp>() {
+        IntBinOpcode::Add
+    } else if op.is::<SubOp>() {
+        IntBinOpcode::Sub
+    } else if op.is::<MulOp>() {
+        IntBinOpcode::Mul
+    } else if op.is::<ShlOp>() {
+        IntBinOpcode::Shl
+    } else if op.is::<UDivOp>() {
+        IntBinOpcode::UDiv
+    } else if op.is::<SDivOp>() {
+        IntBinOpcode::SDiv
+    } else if op.is::<URemOp>() {
+        IntBinOpcode::URem
+    } else if op.is::<SRemOp>() {
+        IntBinOpcode::SRem
+    } else if op.is::<AndOp>() {
+        IntBinOpcode::And
+    } else if op.is::<OrOp>() {
+        IntBinOpcode::Or
+    } else if op.is::<XorOp>() {
+        IntBinOpcode::Xor
+    } else if op.is::<LShrOp>() {
+        IntBinOpcode::LShr
+    } else if op.is::<AShrOp>() {
+        IntBinOpcode::AShr
+    } else {
+        return None;
+    };
+    Some(opcode)
+}
+
+/// The [OverflowFlags] of `flag`.
+fn overflow_flags(flag: &IntegerOverflowFlagsAttr) -> OverflowFlags {
+    OverflowFlags {
+        nsw: *flag == IntegerOverflowFlagsAttr::Nsw,
+        nuw: *flag == IntegerOverflowFlagsAttr::Nuw,
+    }
+}
+
+/// Evaluate the integer comparison `pred` of `lhs` and `rhs`.
+pub fn eval_icmp(pred: &ICmpPredicateAttr, lhs: &APInt, rhs: &APInt) -> bool {
+    use std::cmp::Ordering::*;
+    match pred {
+        ICmpPredicateAttr::EQ => lhs == rhs,
+        ICmpPredicateAttr::NE => lhs != rhs,
+        ICmpPredicateAttr::SLT => lhs.cmp(rhs, true) == Less,
+        ICmpPredicateAttr::SLE => lhs.cmp(rhs, true) != Greater,
+        ICmpPredicateAttr::SGT => lhs.cmp(rhs, true) == Greater,
+        ICmpPredicateAttr::SGE => lhs.cmp(rhs, true) != Less,
+        ICmpPredicateAttr::ULT => lhs.cmp(rhs, false) == Less,
+        ICmpPredicateAttr::ULE => lhs.cmp(rhs, false) != Greater,
+        ICmpPredicateAttr::UGT => lhs.cmp(rhs, false) == Greater,
+        ICmpPredicateAttr::UGE => lhs.cmp(rhs, false) != Less,
+    }
+}
+
+/// Evaluate the integer operation `op` (a binary operation, `icmp`, `zext` or `sext`)
+/// on the values `operands`. Returns [None] if `op` can't be evaluated.
+/// See [module](self) documentation.
+pub fn const_eval(ctx: &Context, op: &dyn Op, operands: &[APInt]) -> Option<ConstValue> {
+    if let Some(opcode) = int_bin_opcode(op) {
+        let [lhs, rhs] = operands else {
+            return None;
+        };
+        if lhs.bw() != rhs.bw() {
+            return None;
+        }
+        let flags = op
+            .operation()
+            .deref(ctx)
+            .attributes
+            .get::<IntegerOverflowFlagsAttr>(&ATTR_KEY_INTEGER_OVERFLOW_FLAGS)
+            .map_or_else(OverflowFlags::default, overflow_flags);
+        return eval_int_bin_op(opcode, lhs, rhs, flags);
+    }
+    if let Some(icmp) = op.downcast_ref::<ICmpOp>() {
+        let [lhs, rhs] = operands else {
+            return None;
+        };
+        if lhs.bw() != rhs.bw() {
+            return None;
+        }
+        let result = eval_icmp(&icmp.predicate(ctx), lhs, rhs);
+        return Some(ConstValue::Int(APInt::from_u8(result.into(), bw(1))));
+    }
+    let signed = op.is::<SExtOp>();
+    if !signed && !op.is::<ZExtOp>() {
+        return None;
+    }
+    let [value] = operands else {
+        return None;
+    };
+    let result_ty = op.operation().deref(ctx).result(0).get_type(ctx);
+    let result_ty = TypePtr::<IntegerType>::from_ptr(result_ty, ctx).ok()?;
+    let width = result_ty.deref(ctx).width() as usize;
+    Some(ConstValue::Int(value.resize(bw(width), signed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use pliron::utils::{
+        apint::{APInt, bw},
+        const_eval::OverflowFlags,
+    };
+
+    use super::{eval_icmp, overflow_flags};
+    use crate::attributes::{ICmpPredicateAttr, IntegerOverflowFlagsAttr};
+
+    #[test]
+    fn test_eval_icmp() {
+        let (minus_one, one) = (APInt::from_i64(-1, bw(8)), APInt::from_i64(1, bw(8)));
+        assert!(eval_icmp(&ICmpPredicateAttr::SLT, &minus_one, &one));
+        assert!(eval_icmp(&ICmpPredicateAttr::UGT, &minus_one, &one));
+        assert!(eval_icmp(&ICmpPredicateAttr::SGE, &one, &one));
+        assert!(!eval_icmp(&ICmpPredicateAttr::NE, &one, &one));
+
+        assert_eq!(
+            overflow_flags(&IntegerOverflowFlagsAttr::None),
+            OverflowFlags::default()
+        );
+        assert!(overflow_flags(&IntegerOverflowFlagsAttr::Nsw).nsw);
+        assert!(overflow_flags(&IntegerOverflowFlagsAttr::Nuw).nuw);
+    }
+}
@@ -0,0 +1,398 @@
+//! Folding LLVM dialect operations.
+//!
+//! [Foldable] is implemented for integer binary operations, `icmp`, `zext` and `sext`
+//! with [IntegerAttr] constant operands, which fold to the constant (or poison)
+//! they [evaluate](crate::const_eval) to. Those with undefined behaviour,
+//! such as division by zero, aren't folded.
+//!
+//! `bitcast`s of integer and integer vector constants fold by reinterpreting their
+//! bytes, as stored in memory according to the [DataLayout] (and so, the endianness)
+//! of the enclosing module. Vectors whose elements aren't a whole number of bytes wide,
+//! which LLVM packs bitwise, aren't folded.
+//!
+//! Constants are [materialized](materialize_constant) as [ConstantOp]s,
+//! or [PoisonOp]s and [UndefOp]s for poison and undef values.
+
+use pliron::{
+    attribute::{AttrObj, attr_cast},
+    builtin::{
+        attr_interfaces::TypedAttrInterface, attributes::IntegerAttr,
+        op_interfaces::OneResultInterface, type_interfaces::DataLayout, types::IntegerType,
+    },
+    context::{Context, Ptr},
+    derive::op_interface_impl,
+    op::Op,
+    operation::Operation,
+    transforms::fold::{Foldable, OpFoldResult},
+    r#type::{TypeObj, TypePtr},
+    utils::{
+        apint::{APInt, bw},
+        const_eval::ConstValue,
+    },
+};
+
+use crate::{
+    attributes::{ConstantVectorAttr, PoisonAttr, UndefAttr},
+    const_eval::const_eval,
+    ops::{
+        AShrOp, AddOp, AndOp, BitcastOp, ConstantOp, ICmpOp, LShrOp, MulOp, OrOp, PoisonOp, SDivOp,
+        SExtOp, SRemOp, ShlOp, SubOp, UDivOp, URemOp, UndefOp, XorOp, ZExtOp,
+    },
+    types::VectorType,
+};
+
+/// Create an operation defining the constant `value` of type `ty`.
+/// This is the constant materializer of the LLVM dialect.
+pub fn materialize_constant(
+    ctx: &mut Context,
+    value: AttrObj,
+    ty: Ptr<TypeObj>,
+) -> Option<Ptr<Operation>> {
+    if value.is::<PoisonAttr>() {
+        return Some(PoisonOp::new(ctx, ty).operation());
+    }
+    if value.is::<UndefAttr>() {
+        return Some(UndefOp::new(ctx, ty).operation());
+    }
+    let typed = attr_cast::<dyn TypedAttrInterface>(&*value)?;
+    (typed.get_type() == ty).then(|| ConstantOp::new(ctx, value).operation())
+}
+
+/// Fold the integer operation `op` with [const_eval], if all its operands are
+/// [IntegerAttr] constants, to an [IntegerAttr] of its result type, or poison.
+fn fold_const_eval<T: OneResultInterface>(
+    ctx: &Context,
+    op: &T,
+    operands: &[Option<AttrObj>],
+) -> Vec<OpFoldResult> {
+    let int_value = |operand: &Option<AttrObj>| {
+        let operand = operand.as_ref()?.downcast_ref::<IntegerAttr>()?;
+        Some(APInt::from(operand.clone()))
+    };
+    let Some(operands) = operands.iter().map(int_value).collect::<Option<Vec<_>>>() else {
+        return vec![];
+    };
+    let result_ty = op.result_type(ctx);
+    let folded: AttrObj = match const_eval(ctx, op, &operands) {
+        Some(ConstValue::Poison) => PoisonAttr::new(result_ty).into(),
+        Some(ConstValue::Int(result)) => {
+            let Ok(int_ty) = TypePtr::<IntegerType>::from_ptr(result_ty, ctx) else {
+                return vec![];
+            };
+            IntegerAttr::new(int_ty, result).into()
+        }
+        None => return vec![],
+    };
+    vec![OpFoldResult::Attribute(folded)]
+}
+
+/// Implement [Foldable] with [fold_const_eval] for each of the given ops.
+macro_rules! impl_const_eval_fold {
+    ($($op_name:ident),*) => {
+        $(
+            #[op_interface_impl]
+            impl Foldable for $op_name {
+                fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult> {
+                    fold_const_eval(ctx, self, operands)
+                }
+            }
+        )*
+    }
+}
+
+impl_const_eval_fold!(
+    AddOp, SubOp, MulOp, ShlOp, UDivOp, SDivOp, URemOp, SRemOp, AndOp, OrOp, XorOp, LShrOp, AShrOp,
+    ICmpOp, ZExtOp, SExtOp
+);
+
+/// The bytes of the integer or integer vector constant `value`, as stored in memory.
+fn constant_bytes(value: &AttrObj, layout: &DataLayout) -> Option<Vec<u8>> {
+    if let Some(int_value) = value.downcast_ref::<IntegerAttr>() {
+        return Some(layout.int_to_bytes(&int_value.clone().into()));
+    }
+    value
+        .downcast_ref::<ConstantVectorAttr>()?
+        .to_bytes(layout)
+        .ok()
+}
+
+#[op_interface_impl]
+impl Foldable for BitcastOp {
+    fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult> {
+        let Some(value) = &operands[0] else {
+            return vec![];
+        };
+        let result_ty = self.result_type(ctx);
+        if value.is::<PoisonAttr>() {
+            return vec![OpFoldResult::Attribute(PoisonAttr::new(result_ty).into())];
+        }
+        let Ok(layout) = DataLayout::of(ctx, self.operation()) else {
+            return vec![];
+        };
+        let Some(bytes) = constant_bytes(value, &layout) else {
+            return vec![];
+        };
+        let folded: AttrObj = if let Ok(int_ty) = TypePtr::<IntegerType>::from_ptr(result_ty, ctx) {
+            let width = int_ty.deref(ctx).width() as usize;
+            let Ok(result) = layout.int_from_bytes(&bytes, bw(width)) else {
+                return vec![];
+            };
+            IntegerAttr::new(int_ty, result).into()
+        } else if let Ok(vec_ty) = TypePtr::<VectorType>::from_ptr(result_ty, ctx) {
+            let Ok(result) = ConstantVectorAttr::from_bytes(ctx, vec_ty, &bytes, &layout) else {
+                return vec![];
+            };
+            result.into()
+        } else {
+            return vec![];
+        };
+        vec![OpFoldResult::Attribute(folded)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pliron::{
+        attribute::AttrObj,
+        builtin::{
+            self,
+            attributes::IntegerAttr,
+            op_interfaces::OneResultInterface,
+            ops::FuncOp,
+            type_interfaces::DataLayout,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::{Context, Ptr},
+        linked_list::ContainsLinkedList,
+        op::Op,
+        operation::Operation,
+        pass::Pass,
+        transforms::{
+            canonicalize::CanonicalizePass,
+            const_fold::ConstFoldPass,
+            fold::{Foldable, OpFoldResult, constant_value},
+        },
+        r#type::TypeObj,
+        utils::apint::{APInt, bw},
+        value::Value,
+    };
+
+    use crate::{
+        self as llvm,
+        attributes::{ConstantVectorAttr, ICmpPredicateAttr, IntegerOverflowFlagsAttr},
+        op_interfaces::{BinArithOp, CastOpInterface, IntBinArithOpWithOverflowFlag},
+        ops::{
+            AddOp, BitcastOp, ConstantOp, ICmpOp, MulOp, PoisonOp, ReturnOp, SDivOp, SExtOp, ShlOp,
+            SubOp, UDivOp, ZExtOp,
+        },
+        types::VectorType,
+    };
+
+    #[test]
+    fn test_fold_int_arith() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i8_ty = IntegerType::get(ctx, 8, Signedness::Signless);
+        let func_ty = FunctionType::get(ctx, vec![i8_ty.into()], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let entry = func.get_entry_block(ctx);
+        let x = entry.deref(ctx).argument(0);
+
+        let append = |ctx: &mut Context, op: Ptr<Operation>| {
+            op.insert_at_back(entry, ctx);
+            op.deref(ctx).result(0)
+        };
+        let constant = |ctx: &mut Context, value: i64| {
+            let value = IntegerAttr::new(i8_ty, APInt::from_i64(value, bw(8)));
+            let op = ConstantOp::new(ctx, value.into()).operation();
+            append(ctx, op)
+        };
+        let c100 = constant(ctx, 100);
+        let c28 = constant(ctx, 28);
+        let c3 = constant(ctx, 3);
+        let flagged = |ctx: &mut Context, flag, lhs, rhs| {
+            let op = AddOp::new_with_overflow_flag(ctx, lhs, rhs, flag).operation();
+            append(ctx, op)
+        };
+        // 100 + 28 wraps around to -128, and is poison with nsw, but not with nuw.
+        let wrapped = flagged(ctx, IntegerOverflowFlagsAttr::None, c100, c28);
+        let nsw = flagged(ctx, IntegerOverflowFlagsAttr::Nsw, c100, c28);
+        let nuw = flagged(ctx, IntegerOverflowFlagsAttr::Nuw, c100, c28);
+        // 3 - 28 = -25
+        let diff = SubOp::new_with_overflow_flag(ctx, c3, c28, IntegerOverflowFlagsAttr::None);
+        let diff = append(ctx, diff.operation());
+        // 3 * x becomes x * 3.
+        let mul =
+            MulOp::new_with_overflow_flag(ctx, c3, x, IntegerOverflowFlagsAttr::None).operation();
+        let product = append(ctx, mul);
+        let uses = [wrapped, nsw, nuw, diff, product];
+        let sink = ReturnOp::new(ctx, None).operation();
+        sink.insert_at_back(entry, ctx);
+        // Keep the results used.
+        Operation::set_operands(sink, ctx, uses.to_vec());
+
+        let mut pass = CanonicalizePass::default();
+        pass.run(ctx, func.operation()).unwrap();
+        let operands: Vec<_> = sink.deref(ctx).operands().collect();
+        let value = |value: &Value| {
+            let value = constant_value(ctx, value)?;
+            let value = value.downcast_ref::<IntegerAttr>()?.clone();
+            Some(APInt::from(value).to_i64())
+        };
+        assert_eq!(value(&operands[0]), Some(-128));
+        let Value::OpResult { op: poison, .. } = operands[1] else {
+            panic!("Expected an op result");
+        };
+        assert!(Operation::op(poison, ctx).is::<PoisonOp>());
+        // 128 as an unsigned 8-bit integer.
+        assert_eq!(value(&operands[2]), Some(-128));
+        assert_eq!(value(&operands[3]), Some(-25));
+        assert!(operands[4] == product);
+        assert!(mul.deref(ctx).operand(0) == x && mul.deref(ctx).operand(1) == c3);
+        assert_eq!(pass.statistics().counter("fold.applied"), 4);
+        assert_eq!(pass.statistics().counter("op-canonicalize.applied"), 1);
+        // 100 and 28 are erased, 3 is still used.
+        let num_constants = entry
+            .deref(ctx)
+            .iter(ctx)
+            .filter(|op| Operation::op(*op, ctx).is::<ConstantOp>())
+            .count();
+        assert_eq!(num_constants, 4);
+    }
+    #[test]
+    fn test_const_fold_pass() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i8_ty = IntegerType::get(ctx, 8, Signedness::Signless);
+        let i16_ty = IntegerType::get(ctx, 16, Signedness::Signless);
+        let func_ty = FunctionType::get(ctx, vec![i8_ty.into()], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let entry = func.get_entry_block(ctx);
+        let x = entry.deref(ctx).argument(0);
+
+        let append = |ctx: &mut Context, op: Ptr<Operation>| {
+            op.insert_at_back(entry, ctx);
+            op.deref(ctx).result(0)
+        };
+        let constant = |ctx: &mut Context, value: i64| {
+            let value = IntegerAttr::new(i8_ty, APInt::from_i64(value, bw(8)));
+            let op = ConstantOp::new(ctx, value.into()).operation();
+            append(ctx, op)
+        };
+        let c0 = constant(ctx, 0);
+        let c1 = constant(ctx, 1);
+        let c3 = constant(ctx, 3);
+        let c6 = constant(ctx, 6);
+        // 3 << 6 = -64, which nuw allows, -64 / 3 = -21, sext to i16.
+        let shl = ShlOp::new_with_overflow_flag(ctx, c3, c6, IntegerOverflowFlagsAttr::Nuw);
+        let shifted = append(ctx, shl.operation());
+        let quotient = SDivOp::new(ctx, shifted, c3).operation();
+        let quotient = append(ctx, quotient);
+        let sext = SExtOp::new(ctx, quotient, i16_ty.into()).operation();
+        let extended = append(ctx, sext);
+        // -64 <u 1 is false, zext to i16.
+        let icmp = ICmpOp::new(ctx, ICmpPredicateAttr::ULT, shifted, c1).operation();
+        let compared = append(ctx, icmp);
+        let zext = ZExtOp::new(ctx, compared, i16_ty.into()).operation();
+        let zero = append(ctx, zext);
+        // Division by zero isn't folded, and neither is x * 1.
+        let udiv = UDivOp::new(ctx, c1, c0).operation();
+        let undefined = append(ctx, udiv);
+        let mul = MulOp::new_with_overflow_flag(ctx, x, c1, IntegerOverflowFlagsAttr::None);
+        let product = append(ctx, mul.operation());
+        let sink = ReturnOp::new(ctx, None).operation();
+        sink.insert_at_back(entry, ctx);
+        // Keep the results used.
+        Operation::set_operands(sink, ctx, vec![extended, zero, undefined, product]);
+
+        let mut pass = ConstFoldPass::new();
+        pass.run(ctx, func.operation()).unwrap();
+        assert_eq!(pass.statistics().counter("folded"), 5);
+        let operands: Vec<_> = sink.deref(ctx).operands().collect();
+        let value = |value: &Value| {
+            let value = constant_value(ctx, value)?;
+            let value = value.downcast_ref::<IntegerAttr>()?.clone();
+            Some(APInt::from(value))
+        };
+        assert_eq!(value(&operands[0]), Some(APInt::from_i64(-21, bw(16))));
+        assert_eq!(value(&operands[1]), Some(APInt::from_i64(0, bw(16))));
+        assert!(operands[2] == undefined && operands[3] == product);
+        // Only 0 and 1 (still used), and the two results, remain as constants.
+        let num_constants = entry
+            .deref(ctx)
+            .iter(ctx)
+            .filter(|op| Operation::op(*op, ctx).is::<ConstantOp>())
+            .count();
+        assert_eq!(num_constants, 4);
+    }
+
+    #[test]
+    fn test_fold_bitcast() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i16_ty = IntegerType::get(ctx, 16, Signedness::Signless);
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless);
+        let v2i16_ty = VectorType::get(ctx, i16_ty.into(), 2);
+        let v4i8_ty = {
+            let i8_ty = IntegerType::get(ctx, 8, Signedness::Signless);
+            VectorType::get(ctx, i8_ty.into(), 4)
+        };
+        let func_ty = FunctionType::get(ctx, vec![], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let entry = func.get_entry_block(ctx);
+
+        // [1, 2] : <2 x i16>
+        let elems = [1, 2].map(|elem| IntegerAttr::new(i16_ty, APInt::from_u16(elem, bw(16))));
+        let vector: AttrObj = ConstantVectorAttr::new(v2i16_ty, elems.to_vec()).into();
+        let constant = ConstantOp::new(ctx, vector.clone());
+        constant.operation().insert_at_back(entry, ctx);
+        let bitcast = |ctx: &mut Context, ty: Ptr<TypeObj>| {
+            let op = BitcastOp::new(ctx, ty, constant.result(ctx));
+            op.operation().insert_at_back(entry, ctx);
+            op
+        };
+        let to_int = bitcast(ctx, i32_ty.into());
+        let to_bytes = bitcast(ctx, v4i8_ty.into());
+        let folded = |ctx: &Context, op: BitcastOp| {
+            let [OpFoldResult::Attribute(attr)] = &op.fold(ctx, &[Some(vector.clone())])[..] else {
+                panic!("Bitcast of a constant must fold");
+            };
+            attr.clone()
+        };
+        let int_value = |attr: AttrObj| {
+            APInt::from(attr.downcast_ref::<IntegerAttr>().unwrap().clone()).to_u32()
+        };
+        let byte_values = |attr: AttrObj| {
+            let attr = attr.downcast_ref::<ConstantVectorAttr>().unwrap().clone();
+            attr.elems()
+                .iter()
+                .map(|elem| APInt::from(elem.clone()).to_u8())
+                .collect::<Vec<_>>()
+        };
+
+        // The first element is at the lower address, which is
+        // the least significant byte only on little-endian targets.
+        assert_eq!(int_value(folded(ctx, to_int)), 0x0002_0001);
+        assert_eq!(byte_values(folded(ctx, to_bytes)), vec![1, 0, 2, 0]);
+        DataLayout::parse("E").unwrap().set(ctx, func.operation());
+        assert_eq!(int_value(folded(ctx, to_int)), 0x0001_0002);
+        assert_eq!(byte_values(folded(ctx, to_bytes)), vec![0, 1, 0, 2]);
+
+        // Round trip through the bytes of a big-endian target.
+        let layout = DataLayout::of(ctx, func.operation()).unwrap();
+        let vector = vector.downcast_ref::<ConstantVectorAttr>().unwrap();
+        let bytes = vector.to_bytes(&layout).unwrap();
+        let round_trip = ConstantVectorAttr::from_bytes(ctx, v2i16_ty, &bytes, &layout).unwrap();
+        assert!(&round_trip == vector);
+        assert!(ConstantVectorAttr::from_bytes(ctx, v2i16_ty, &bytes[1..], &layout).is_err());
+    }
+}
@@ -2,15 +2,20 @@
 
 use std::num::NonZero;
 
-use llvm_sys::{LLVMIntPredicate, LLVMOpcode, LLVMTypeKind, LLVMValueKind};
+use llvm_sys::{
+    LLVMAtomicOrdering, LLVMAtomicRMWBinOp, LLVMIntPredicate, LLVMOpcode, LLVMTypeKind,
+    LLVMValueKind,
+};
 use pliron::{
     basic_block::BasicBlock,
     builtin::{
-        attributes::IntegerAttr,
+        attributes::{IdentifierAttr, IntegerAttr, StringAttr},
         op_interfaces::{
-            CallOpCallable, OneRegionInterface, OneResultInterface, SingleBlockRegionInterface,
+            CallOpCallable, OneRegionInterface, OneResultInterface, OperandBundle,
+            OperandBundleInterface, SingleBlockRegionInterface,
         },
         ops::{FuncOp, ModuleOp},
+        type_interfaces::ATTR_KEY_DATA_LAYOUT,
         types::{FunctionType, IntegerType, Signedness},
     },
     context::{Context, Ptr},
@@ -27,29 +32,40 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use thiserror::Error;
 
 use crate::{
-    attributes::{ICmpPredicateAttr, IntegerOverflowFlagsAttr},
+    attributes::{
+        AtomicOrderingAttr, AtomicRmwBinOpAttr, ConstantVectorAttr, ICmpPredicateAttr,
+        IntegerOverflowFlagsAttr, ShuffleMaskElemAttr,
+    },
     llvm_sys::core::{
         LLVMBasicBlock, LLVMModule, LLVMType, LLVMValue, basic_block_iter, function_iter,
-        incoming_iter, instruction_iter, llvm_const_int_get_zext_value, llvm_get_allocated_type,
-        llvm_get_array_length2, llvm_get_basic_block_name, llvm_get_basic_block_terminator,
-        llvm_get_called_function_type, llvm_get_called_value, llvm_get_element_type,
+        incoming_iter, instruction_iter, llvm_const_int_get_zext_value, llvm_get_aggregate_element,
+        llvm_get_allocated_type, llvm_get_array_length2, llvm_get_atomic_rmw_bin_op,
+        llvm_get_basic_block_name, llvm_get_basic_block_terminator, llvm_get_called_function_type,
+        llvm_get_called_value, llvm_get_cmp_xchg_failure_ordering,
+        llvm_get_cmp_xchg_success_ordering, llvm_get_data_layout_str, llvm_get_element_type,
         llvm_get_gep_source_element_type, llvm_get_icmp_predicate, llvm_get_indices,
         llvm_get_instruction_opcode, llvm_get_instruction_parent, llvm_get_int_type_width,
-        llvm_get_module_identifier, llvm_get_nsw, llvm_get_num_arg_operands, llvm_get_num_operands,
-        llvm_get_nuw, llvm_get_operand, llvm_get_param_types, llvm_get_return_type,
+        llvm_get_mask_value, llvm_get_module_identifier, llvm_get_normal_dest, llvm_get_nsw,
+        llvm_get_num_arg_operands, llvm_get_num_mask_elements, llvm_get_num_operand_bundles,
+        llvm_get_num_operands, llvm_get_nuw, llvm_get_operand, llvm_get_operand_bundle_at_index,
+        llvm_get_ordering, llvm_get_param_types, llvm_get_personality_fn, llvm_get_return_type,
         llvm_get_struct_element_types, llvm_get_struct_name, llvm_get_type_kind,
-        llvm_get_value_kind, llvm_get_value_name, llvm_global_get_value_type, llvm_is_a,
-        llvm_is_opaque_struct, llvm_type_of, llvm_value_as_basic_block, llvm_value_is_basic_block,
-        param_iter,
+        llvm_get_unwind_dest, llvm_get_value_kind, llvm_get_value_name, llvm_get_vector_size,
+        llvm_get_volatile, llvm_global_get_value_type, llvm_is_a, llvm_is_cleanup,
+        llvm_is_opaque_struct, llvm_print_value_to_string, llvm_type_of, llvm_value_as_basic_block,
+        llvm_value_is_basic_block, param_iter,
+    },
+    op_interfaces::{
+        BinArithOp, CastOpInterface, IntBinArithOpWithOverflowFlag, MemoryAccessOpInterface,
     },
-    op_interfaces::{BinArithOp, CastOpInterface, IntBinArithOpWithOverflowFlag},
     ops::{
-        AShrOp, AddOp, AllocaOp, AndOp, BitcastOp, BrOp, CallOp, CondBrOp, ConstantOp,
-        ExtractValueOp, GepIndex, GetElementPtrOp, ICmpOp, InsertValueOp, LShrOp, LoadOp, MulOp,
-        OrOp, ReturnOp, SDivOp, SExtOp, SRemOp, SelectOp, ShlOp, StoreOp, SubOp, UDivOp, URemOp,
-        UndefOp, XorOp, ZExtOp,
+        AShrOp, AddOp, AllocaOp, AndOp, AtomicCmpXchgOp, AtomicRmwOp, BitcastOp, BrOp, CallOp,
+        CondBrOp, ConstantOp, ExtractElementOp, ExtractValueOp, GepIndex, GetElementPtrOp, ICmpOp,
+        InsertElementOp, InsertValueOp, InvokeOp, LShrOp, LandingPadOp, LoadOp, MulOp, OrOp,
+        PoisonOp, ResumeOp, ReturnOp, SDivOp, SExtOp, SRemOp, SelectOp, ShlOp, ShuffleVectorOp,
+        StoreOp, SubOp, UDivOp, URemOp, UndefOp, UnreachableOp, XorOp, ZExtOp, landing_pad_op,
     },
-    types::{ArrayType, PointerType, StructErr, StructType, VoidType},
+    types::{ArrayType, PointerType, StructErr, StructType, VectorType, VoidType},
 };
 
 fn convert_type(
@@ -64,7 +80,9 @@ fn convert_type(
             let elem = convert_type(ctx, cctx, element_ty)?;
             Ok(ArrayType::get(ctx, elem, len).into())
         }
-        LLVMTypeKind::LLVMFloatTypeKind => todo!(),
+        LLVMTypeKind::LLVMFloatTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
         LLVMTypeKind::LLVMFunctionTypeKind => {
             let return_type = convert_type(ctx, cctx, llvm_get_return_type(ty))?;
             let param_types = llvm_get_param_types(ty)
@@ -102,20 +120,50 @@ fn convert_type(
             }
         }
         LLVMTypeKind::LLVMVoidTypeKind => Ok(VoidType::get(ctx).into()),
-        LLVMTypeKind::LLVMVectorTypeKind => todo!(),
-        LLVMTypeKind::LLVMHalfTypeKind => todo!(),
-        LLVMTypeKind::LLVMDoubleTypeKind => todo!(),
-        LLVMTypeKind::LLVMX86_FP80TypeKind => todo!(),
-        LLVMTypeKind::LLVMFP128TypeKind => todo!(),
-        LLVMTypeKind::LLVMPPC_FP128TypeKind => todo!(),
-        LLVMTypeKind::LLVMLabelTypeKind => todo!(),
-        LLVMTypeKind::LLVMMetadataTypeKind => todo!(),
-        LLVMTypeKind::LLVMX86_MMXTypeKind => todo!(),
-        LLVMTypeKind::LLVMTokenTypeKind => todo!(),
-        LLVMTypeKind::LLVMScalableVectorTypeKind => todo!(),
-        LLVMTypeKind::LLVMBFloatTypeKind => todo!(),
-        LLVMTypeKind::LLVMX86_AMXTypeKind => todo!(),
-        LLVMTypeKind::LLVMTargetExtTypeKind => todo!(),
+        LLVMTypeKind::LLVMVectorTypeKind => {
+            let (element_ty, len) = (llvm_get_element_type(ty), llvm_get_vector_size(ty));
+            let elem = convert_type(ctx, cctx, element_ty)?;
+            Ok(VectorType::get(ctx, elem, len).into())
+        }
+        LLVMTypeKind::LLVMHalfTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMDoubleTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMX86_FP80TypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMFP128TypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMPPC_FP128TypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMLabelTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMMetadataTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMX86_MMXTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMTokenTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMScalableVectorTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMBFloatTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMX86_AMXTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
+        LLVMTypeKind::LLVMTargetExtTypeKind => {
+            input_err_noloc!(ConversionErr::UnsupportedType(format!("{kind:?}")))
+        }
     }
 }
 
@@ -134,6 +182,42 @@ pub fn convert_ipredicate(ipred: LLVMIntPredicate) -> ICmpPredicateAttr {
     }
 }
 
+pub fn convert_atomic_ordering(ordering: LLVMAtomicOrdering) -> AtomicOrderingAttr {
+    match ordering {
+        LLVMAtomicOrdering::LLVMAtomicOrderingNotAtomic => AtomicOrderingAttr::NotAtomic,
+        LLVMAtomicOrdering::LLVMAtomicOrderingUnordered => AtomicOrderingAttr::Unordered,
+        LLVMAtomicOrdering::LLVMAtomicOrderingMonotonic => AtomicOrderingAttr::Monotonic,
+        LLVMAtomicOrdering::LLVMAtomicOrderingAcquire => AtomicOrderingAttr::Acquire,
+        LLVMAtomicOrdering::LLVMAtomicOrderingRelease => AtomicOrderingAttr::Release,
+        LLVMAtomicOrdering::LLVMAtomicOrderingAcquireRelease => AtomicOrderingAttr::AcqRel,
+        LLVMAtomicOrdering::LLVMAtomicOrderingSequentiallyConsistent => AtomicOrderingAttr::SeqCst,
+    }
+}
+
+pub fn convert_atomic_rmw_bin_op(bin_op: LLVMAtomicRMWBinOp) -> Result<AtomicRmwBinOpAttr> {
+    Ok(match bin_op {
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpXchg => AtomicRmwBinOpAttr::Xchg,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpAdd => AtomicRmwBinOpAttr::Add,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpSub => AtomicRmwBinOpAttr::Sub,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpAnd => AtomicRmwBinOpAttr::And,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpNand => AtomicRmwBinOpAttr::Nand,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpOr => AtomicRmwBinOpAttr::Or,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpXor => AtomicRmwBinOpAttr::Xor,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpMax => AtomicRmwBinOpAttr::Max,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpMin => AtomicRmwBinOpAttr::Min,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpUMax => AtomicRmwBinOpAttr::UMax,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpUMin => AtomicRmwBinOpAttr::UMin,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFAdd
+        | LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFSub
+        | LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFMax
+        | LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFMin => {
+            return input_err_noloc!(ConversionErr::UnsupportedAtomicRmwBinOp(format!(
+                "{bin_op:?}"
+            )));
+        }
+    })
+}
+
 /// Mapping from LLVM entities to pliron entities.
 #[derive(Default)]
 struct ConversionContext {
@@ -176,6 +260,7 @@ fn successors(block: LLVMBasicBlock) -> Vec<LLVMBasicBlock> {
                 ]
             }
         }
+        LLVMOpcode::LLVMInvoke => vec![llvm_get_normal_dest(term), llvm_get_unwind_dest(term)],
         _ => vec![],
     }
 }
@@ -224,6 +309,38 @@ pub enum ConversionErr {
     UndefinedBlock(String),
     #[error("Integer constant has bit-width 0")]
     ZeroWidthIntConst,
+    #[error("Only vector constants with integer elements are supported")]
+    NonIntVectorConst,
+    #[error("Type kind {0} is not supported")]
+    UnsupportedType(String),
+    #[error("Constant {0} is not supported")]
+    UnsupportedConstant(String),
+    #[error("Instruction {0} is not supported")]
+    UnsupportedInstruction(String),
+    #[error("Atomic RMW operation {0} is not supported")]
+    UnsupportedAtomicRmwBinOp(String),
+}
+
+fn unsupported_instruction(inst: LLVMValue) -> Result<Ptr<Operation>> {
+    let inst = llvm_print_value_to_string(inst);
+    input_err_noloc!(ConversionErr::UnsupportedInstruction(
+        inst.trim().to_string()
+    ))
+}
+
+/// Convert an LLVM integer constant `val`, of (converted) type `ty`.
+fn convert_const_int(ctx: &Context, ty: Ptr<TypeObj>, val: LLVMValue) -> Result<IntegerAttr> {
+    // TODO: Zero extend or sign extend?
+    let u64 = llvm_const_int_get_zext_value(val);
+    let int_ty = TypePtr::<IntegerType>::downcast(ctx, ty)?;
+    let width = int_ty.deref(ctx).width() as usize;
+    if width == 0 {
+        return input_err_noloc!(ConversionErr::ZeroWidthIntConst);
+    }
+    Ok(IntegerAttr::new(
+        int_ty,
+        APInt::from_u64(u64, NonZero::new(width).unwrap()),
+    ))
 }
 
 /// Checks if a constant has been processed already, and if not
@@ -242,27 +359,53 @@ fn process_constant(ctx: &mut Context, cctx: &mut ConversionContext, val: LLVMVa
                 .insert_at_front(cctx.entry_block.unwrap(), ctx);
             cctx.value_map.insert(val, undef_op.result(ctx));
         }
+        LLVMValueKind::LLVMPoisonValueKind => {
+            let poison_op = PoisonOp::new(ctx, ty);
+            // Insert at the beginning of the entry block.
+            poison_op
+                .operation()
+                .insert_at_front(cctx.entry_block.unwrap(), ctx);
+            cctx.value_map.insert(val, poison_op.result(ctx));
+        }
         LLVMValueKind::LLVMConstantIntValueKind => {
-            // TODO: Zero extend or sign extend?
-            let u64 = llvm_const_int_get_zext_value(val);
-            let int_ty = TypePtr::<IntegerType>::from_ptr(ty, ctx)?;
-            let width = int_ty.deref(ctx).width() as usize;
-            if width == 0 {
-                return input_err_noloc!(ConversionErr::ZeroWidthIntConst);
-            }
-            let val_attr =
-                IntegerAttr::new(int_ty, APInt::from_u64(u64, NonZero::new(width).unwrap()));
-            let const_op = ConstantOp::new(ctx, Box::new(val_attr));
+            let val_attr = convert_const_int(ctx, ty, val)?;
+            let const_op = ConstantOp::new(ctx, val_attr.into());
+            // Insert at the beginning of the entry block.
+            const_op
+                .operation()
+                .insert_at_front(cctx.entry_block.unwrap(), ctx);
+            cctx.value_map.insert(val, const_op.result(ctx));
+        }
+        LLVMValueKind::LLVMConstantFPValueKind
+        | LLVMValueKind::LLVMConstantArrayValueKind
+        | LLVMValueKind::LLVMConstantStructValueKind => {
+            let val = llvm_print_value_to_string(val);
+            return input_err_noloc!(ConversionErr::UnsupportedConstant(val));
+        }
+        LLVMValueKind::LLVMConstantVectorValueKind
+        | LLVMValueKind::LLVMConstantDataVectorValueKind => {
+            let vec_ty = TypePtr::<VectorType>::downcast(ctx, ty)?;
+            let (elem_ty, num_elements) = {
+                let vec_ty = vec_ty.deref(ctx);
+                (vec_ty.elem_type(), vec_ty.num_elements())
+            };
+            let elems = (0..num_elements)
+                .map(|idx| {
+                    let elem = llvm_get_aggregate_element(val, idx)
+                        .filter(|elem| llvm_is_a::constant_int(*elem));
+                    let Some(elem) = elem else {
+                        return input_err_noloc!(ConversionErr::NonIntVectorConst);
+                    };
+                    convert_const_int(ctx, elem_ty, elem)
+                })
+                .collect::<Result<_>>()?;
+            let const_op = ConstantOp::new(ctx, ConstantVectorAttr::new(vec_ty, elems).into());
             // Insert at the beginning of the entry block.
             const_op
                 .operation()
                 .insert_at_front(cctx.entry_block.unwrap(), ctx);
             cctx.value_map.insert(val, const_op.result(ctx));
         }
-        LLVMValueKind::LLVMConstantFPValueKind => todo!(),
-        LLVMValueKind::LLVMConstantArrayValueKind => todo!(),
-        LLVMValueKind::LLVMConstantStructValueKind => todo!(),
-        LLVMValueKind::LLVMConstantVectorValueKind => todo!(),
         _ => (),
     }
     Ok(())
@@ -337,11 +480,12 @@ fn convert_branch_args(
     Ok(args)
 }
 
-fn convert_call(
+/// Convert the callee, its type, and the arguments of a call or invoke instruction.
+fn convert_callee(
     ctx: &mut Context,
     cctx: &mut ConversionContext,
     inst: LLVMValue,
-) -> Result<Ptr<Operation>> {
+) -> Result<(CallOpCallable, TypePtr<FunctionType>, Vec<Value>)> {
     let llvm_get_operands: Vec<_> = (0..llvm_get_num_arg_operands(inst))
         .map(|opd_idx| llvm_get_operand(inst, opd_idx))
         .collect();
@@ -360,8 +504,68 @@ fn convert_call(
 
     let callee_ty = llvm_get_called_function_type(inst);
     let callee_ty: TypePtr<FunctionType> =
-        convert_type(ctx, cctx, callee_ty).and_then(|ty| TypePtr::from_ptr(ty, ctx))?;
-    Ok(CallOp::new(ctx, callee, callee_ty, args).operation())
+        convert_type(ctx, cctx, callee_ty).and_then(|ty| TypePtr::downcast(ctx, ty))?;
+    Ok((callee, callee_ty, args))
+}
+
+/// Convert the operand bundles of a call or invoke instruction.
+fn convert_operand_bundles(
+    ctx: &mut Context,
+    cctx: &mut ConversionContext,
+    inst: LLVMValue,
+) -> Result<Vec<OperandBundle>> {
+    (0..llvm_get_num_operand_bundles(inst))
+        .map(|bundle_idx| {
+            let bundle = llvm_get_operand_bundle_at_index(inst, bundle_idx);
+            let (operands, _) = convert_operands(ctx, cctx, &bundle.args())?;
+            Ok(OperandBundle::new(&bundle.tag(), operands))
+        })
+        .collect()
+}
+
+fn convert_call(
+    ctx: &mut Context,
+    cctx: &mut ConversionContext,
+    inst: LLVMValue,
+) -> Result<Ptr<Operation>> {
+    let (callee, callee_ty, args) = convert_callee(ctx, cctx, inst)?;
+    let bundles = convert_operand_bundles(ctx, cctx, inst)?;
+    let call_op = CallOp::new(ctx, callee, callee_ty, args);
+    call_op.set_operand_bundles(ctx, bundles);
+    Ok(call_op.operation())
+}
+
+fn convert_invoke(
+    ctx: &mut Context,
+    cctx: &mut ConversionContext,
+    inst: LLVMValue,
+) -> Result<Ptr<Operation>> {
+    let (callee, callee_ty, args) = convert_callee(ctx, cctx, inst)?;
+
+    let src_block = llvm_get_instruction_parent(inst).unwrap();
+    let (normal_dest, unwind_dest) = (llvm_get_normal_dest(inst), llvm_get_unwind_dest(inst));
+    let normal_dest_opds = convert_branch_args(ctx, cctx, src_block, normal_dest)?;
+    let unwind_dest_opds = convert_branch_args(ctx, cctx, src_block, unwind_dest)?;
+    let m_block = |block: LLVMBasicBlock| {
+        cctx.block_map.get(&block).copied().ok_or_else(|| {
+            input_error_noloc!(ConversionErr::UndefinedBlock(
+                llvm_get_basic_block_name(block).unwrap_or_default()
+            ))
+        })
+    };
+    let (m_normal_dest, m_unwind_dest) = (m_block(normal_dest)?, m_block(unwind_dest)?);
+    let bundles = convert_operand_bundles(ctx, cctx, inst)?;
+
+    let invoke_op = InvokeOp::new(
+        ctx,
+        callee,
+        callee_ty,
+        args,
+        (m_normal_dest, normal_dest_opds),
+        (m_unwind_dest, unwind_dest_opds),
+    );
+    invoke_op.set_operand_bundles(ctx, bundles);
+    Ok(invoke_op.operation())
 }
 
 fn convert_instruction(
@@ -372,6 +576,9 @@ fn convert_instruction(
     if llvm_is_a::call_inst(inst) {
         return convert_call(ctx, cctx, inst);
     }
+    if llvm_is_a::invoke_inst(inst) {
+        return convert_invoke(ctx, cctx, inst);
+    }
 
     fn get_integer_overflow_flag(inst: LLVMValue) -> IntegerOverflowFlagsAttr {
         if llvm_get_nsw(inst) {
@@ -396,7 +603,7 @@ fn convert_instruction(
                     .operation(),
             )
         }
-        LLVMOpcode::LLVMAddrSpaceCast => todo!(),
+        LLVMOpcode::LLVMAddrSpaceCast => unsupported_instruction(inst),
         LLVMOpcode::LLVMAlloca => {
             let elem_type = convert_type(ctx, cctx, llvm_get_allocated_type(inst))?;
             let size = operand(opds, 0)?;
@@ -410,8 +617,31 @@ fn convert_instruction(
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
             Ok(AShrOp::new(ctx, lhs, rhs).operation())
         }
-        LLVMOpcode::LLVMAtomicCmpXchg => todo!(),
-        LLVMOpcode::LLVMAtomicRMW => todo!(),
+        LLVMOpcode::LLVMAtomicCmpXchg => {
+            let (ptr, cmp, new) = (operand(opds, 0)?, operand(opds, 1)?, operand(opds, 2)?);
+            let op = AtomicCmpXchgOp::new(
+                ctx,
+                ptr,
+                cmp,
+                new,
+                convert_atomic_ordering(llvm_get_cmp_xchg_success_ordering(inst)),
+                convert_atomic_ordering(llvm_get_cmp_xchg_failure_ordering(inst)),
+            );
+            op.set_volatile(ctx, llvm_get_volatile(inst));
+            Ok(op.operation())
+        }
+        LLVMOpcode::LLVMAtomicRMW => {
+            let (ptr, val) = (operand(opds, 0)?, operand(opds, 1)?);
+            let op = AtomicRmwOp::new(
+                ctx,
+                convert_atomic_rmw_bin_op(llvm_get_atomic_rmw_bin_op(inst))?,
+                ptr,
+                val,
+                convert_atomic_ordering(llvm_get_ordering(inst)),
+            );
+            op.set_volatile(ctx, llvm_get_volatile(inst));
+            Ok(op.operation())
+        }
         LLVMOpcode::LLVMBitCast => {
             let arg = operand(opds, 0)?;
             let res_ty = convert_type(ctx, cctx, llvm_type_of(inst))?;
@@ -457,26 +687,29 @@ fn convert_instruction(
         LLVMOpcode::LLVMCall => {
             unreachable!("Should've already been processed separately")
         }
-        LLVMOpcode::LLVMCallBr => todo!(),
-        LLVMOpcode::LLVMCatchPad => todo!(),
-        LLVMOpcode::LLVMCatchRet => todo!(),
-        LLVMOpcode::LLVMCatchSwitch => todo!(),
-        LLVMOpcode::LLVMCleanupPad => todo!(),
-        LLVMOpcode::LLVMCleanupRet => todo!(),
-        LLVMOpcode::LLVMExtractElement => todo!(),
-        LLVMOpcode::LLVMFNeg => todo!(),
-        LLVMOpcode::LLVMFAdd => todo!(),
-        LLVMOpcode::LLVMFCmp => todo!(),
-        LLVMOpcode::LLVMFDiv => todo!(),
-        LLVMOpcode::LLVMFence => todo!(),
-        LLVMOpcode::LLVMFMul => todo!(),
-        LLVMOpcode::LLVMFPExt => todo!(),
-        LLVMOpcode::LLVMFPToSI => todo!(),
-        LLVMOpcode::LLVMFPToUI => todo!(),
-        LLVMOpcode::LLVMFPTrunc => todo!(),
-        LLVMOpcode::LLVMFreeze => todo!(),
-        LLVMOpcode::LLVMFRem => todo!(),
-        LLVMOpcode::LLVMFSub => todo!(),
+        LLVMOpcode::LLVMCallBr => unsupported_instruction(inst),
+        LLVMOpcode::LLVMCatchPad => unsupported_instruction(inst),
+        LLVMOpcode::LLVMCatchRet => unsupported_instruction(inst),
+        LLVMOpcode::LLVMCatchSwitch => unsupported_instruction(inst),
+        LLVMOpcode::LLVMCleanupPad => unsupported_instruction(inst),
+        LLVMOpcode::LLVMCleanupRet => unsupported_instruction(inst),
+        LLVMOpcode::LLVMExtractElement => {
+            let (vector, index) = (operand(opds, 0)?, operand(opds, 1)?);
+            Ok(ExtractElementOp::new(ctx, vector, index)?.operation())
+        }
+        LLVMOpcode::LLVMFNeg => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFAdd => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFCmp => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFDiv => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFence => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFMul => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFPExt => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFPToSI => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFPToUI => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFPTrunc => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFreeze => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFRem => unsupported_instruction(inst),
+        LLVMOpcode::LLVMFSub => unsupported_instruction(inst),
         LLVMOpcode::LLVMGetElementPtr => {
             let mut opds = opds.iter();
             let base = opds
@@ -491,8 +724,11 @@ fn convert_instruction(
             let pred = convert_ipredicate(llvm_get_icmp_predicate(inst));
             Ok(ICmpOp::new(ctx, pred, operand(opds, 0)?, operand(opds, 1)?).operation())
         }
-        LLVMOpcode::LLVMIndirectBr => todo!(),
-        LLVMOpcode::LLVMInsertElement => todo!(),
+        LLVMOpcode::LLVMIndirectBr => unsupported_instruction(inst),
+        LLVMOpcode::LLVMInsertElement => {
+            let (vector, value, index) = (operand(opds, 0)?, operand(opds, 1)?, operand(opds, 2)?);
+            Ok(InsertElementOp::new(ctx, vector, value, index).operation())
+        }
         LLVMOpcode::LLVMInsertValue => {
             let (aggr, val) = (operand(opds, 0)?, operand(opds, 1)?);
             let indices = llvm_get_indices(inst);
@@ -503,12 +739,21 @@ fn convert_instruction(
             let indices = llvm_get_indices(inst);
             Ok(ExtractValueOp::new(ctx, aggr, indices)?.operation())
         }
-        LLVMOpcode::LLVMIntToPtr => todo!(),
-        LLVMOpcode::LLVMInvoke => todo!(),
-        LLVMOpcode::LLVMLandingPad => todo!(),
+        LLVMOpcode::LLVMIntToPtr => unsupported_instruction(inst),
+        LLVMOpcode::LLVMInvoke => {
+            unreachable!("Should've already been processed separately")
+        }
+        LLVMOpcode::LLVMLandingPad => {
+            // The operands of a landing pad are its clauses.
+            let res_ty = convert_type(ctx, cctx, llvm_type_of(inst))?;
+            Ok(LandingPadOp::new(ctx, res_ty, llvm_is_cleanup(inst), opds.clone()).operation())
+        }
         LLVMOpcode::LLVMLoad => {
             let res_ty = convert_type(ctx, cctx, llvm_type_of(inst))?;
-            Ok(LoadOp::new(ctx, operand(opds, 0)?, res_ty).operation())
+            let op = LoadOp::new(ctx, operand(opds, 0)?, res_ty);
+            op.set_volatile(ctx, llvm_get_volatile(inst));
+            op.set_atomic_ordering(ctx, convert_atomic_ordering(llvm_get_ordering(inst)));
+            Ok(op.operation())
         }
         LLVMOpcode::LLVMLShr => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
@@ -528,8 +773,8 @@ fn convert_instruction(
         LLVMOpcode::LLVMPHI => {
             unreachable!("PHI nodes must already be handled")
         }
-        LLVMOpcode::LLVMPtrToInt => todo!(),
-        LLVMOpcode::LLVMResume => todo!(),
+        LLVMOpcode::LLVMPtrToInt => unsupported_instruction(inst),
+        LLVMOpcode::LLVMResume => Ok(ResumeOp::new(ctx, operand(opds, 0)?).operation()),
         LLVMOpcode::LLVMRet => {
             let retval = if llvm_get_num_operands(inst) == 1 {
                 Some(operand(opds, 0)?)
@@ -564,15 +809,27 @@ fn convert_instruction(
                     .operation(),
             )
         }
-        LLVMOpcode::LLVMShuffleVector => todo!(),
-        LLVMOpcode::LLVMSIToFP => todo!(),
+        LLVMOpcode::LLVMShuffleVector => {
+            let (v1, v2) = (operand(opds, 0)?, operand(opds, 1)?);
+            let mask = (0..llvm_get_num_mask_elements(inst))
+                .map(|idx| match llvm_get_mask_value(inst, idx) {
+                    Some(idx) => ShuffleMaskElemAttr::Index(idx),
+                    None => ShuffleMaskElemAttr::Poison,
+                })
+                .collect();
+            Ok(ShuffleVectorOp::new(ctx, v1, v2, mask)?.operation())
+        }
+        LLVMOpcode::LLVMSIToFP => unsupported_instruction(inst),
         LLVMOpcode::LLVMSRem => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
             Ok(SRemOp::new(ctx, lhs, rhs).operation())
         }
         LLVMOpcode::LLVMStore => {
             let (value_opd, ptr_opd) = (operand(opds, 0)?, operand(opds, 1)?);
-            Ok(StoreOp::new(ctx, value_opd, ptr_opd).operation())
+            let op = StoreOp::new(ctx, value_opd, ptr_opd);
+            op.set_volatile(ctx, llvm_get_volatile(inst));
+            op.set_atomic_ordering(ctx, convert_atomic_ordering(llvm_get_ordering(inst)));
+            Ok(op.operation())
         }
         LLVMOpcode::LLVMSub => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
@@ -581,21 +838,21 @@ fn convert_instruction(
                     .operation(),
             )
         }
-        LLVMOpcode::LLVMSwitch => todo!(),
-        LLVMOpcode::LLVMTrunc => todo!(),
+        LLVMOpcode::LLVMSwitch => unsupported_instruction(inst),
+        LLVMOpcode::LLVMTrunc => unsupported_instruction(inst),
         LLVMOpcode::LLVMUDiv => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
             Ok(UDivOp::new(ctx, lhs, rhs).operation())
         }
-        LLVMOpcode::LLVMUIToFP => todo!(),
-        LLVMOpcode::LLVMUnreachable => todo!(),
+        LLVMOpcode::LLVMUIToFP => unsupported_instruction(inst),
+        LLVMOpcode::LLVMUnreachable => Ok(UnreachableOp::new(ctx).operation()),
         LLVMOpcode::LLVMURem => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
             Ok(URemOp::new(ctx, lhs, rhs).operation())
         }
-        LLVMOpcode::LLVMUserOp1 => todo!(),
-        LLVMOpcode::LLVMUserOp2 => todo!(),
-        LLVMOpcode::LLVMVAArg => todo!(),
+        LLVMOpcode::LLVMUserOp1 => unsupported_instruction(inst),
+        LLVMOpcode::LLVMUserOp2 => unsupported_instruction(inst),
+        LLVMOpcode::LLVMVAArg => unsupported_instruction(inst),
         LLVMOpcode::LLVMXor => {
             let (lhs, rhs) = (operand(opds, 0)?, operand(opds, 1)?);
             Ok(XorOp::new(ctx, lhs, rhs).operation())
@@ -639,9 +896,18 @@ fn convert_function(
         .map(|name| cctx.id_legaliser.legalise(&name))
         .expect("Expected functions to have names");
     let fn_ty = convert_type(ctx, cctx, llvm_global_get_value_type(function))?;
-    let fn_ty = TypePtr::from_ptr(fn_ty, ctx)?;
+    let fn_ty = TypePtr::downcast(ctx, fn_ty)?;
     // Create a new FuncOp, which also creates an entry block with the right parameters.
     let m_func = FuncOp::new(ctx, &name, fn_ty);
+    if let Some(personality) = llvm_get_personality_fn(function) {
+        let personality = llvm_get_value_name(personality)
+            .map(|name| cctx.id_legaliser.legalise(&name))
+            .expect("Expected personality functions to have names");
+        m_func.operation().deref_mut(ctx).attributes.set(
+            *landing_pad_op::ATTR_KEY_PERSONALITY,
+            IdentifierAttr::new(personality),
+        );
+    }
     let m_func_reg = m_func.region(ctx);
 
     let m_entry_block = m_func.get_entry_block(ctx);
@@ -692,6 +958,12 @@ pub fn convert_module(ctx: &mut Context, module: &LLVMModule) -> Result<ModuleOp
     let module_name = cctx.id_legaliser.legalise(&module_name);
 
     let m = ModuleOp::new(ctx, &module_name);
+    if let Some(data_layout) = llvm_get_data_layout_str(module).filter(|dl| !dl.is_empty()) {
+        m.operation()
+            .deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_DATA_LAYOUT, StringAttr::new(data_layout));
+    }
     // TODO: Convert globals.
     // ...
     // Convert functions.
@@ -0,0 +1,372 @@
+//! Inlining of [CallOp]s.
+//!
+//! [inline] replaces a direct [CallOp] with a copy of the body of its callee:
+//!   - The block containing the call is split right after the call.
+//!   - The callee's blocks are [cloned](Region::clone_into) in between the two halves,
+//!     with the arguments of the callee's entry block replaced by the call's arguments.
+//!   - Every [ReturnOp] in the copy becomes a [BrOp] to the second half of the split
+//!     block, which receives the returned value (if any) as a block argument,
+//!     replacing the result of the call.
+//!
+//! Ops that must not be copied into another function implement [InlinerInterface],
+//! and a callee containing any op that isn't [legal](InlinerInterface::is_legal_to_inline)
+//! to inline is left alone. Ops not implementing [InlinerInterface] are legal to inline.
+
+use pliron::{
+    arg_err,
+    basic_block::BasicBlock,
+    builtin::{
+        op_interfaces::{
+            CallOpCallable, CallOpInterface, OneRegionInterface, OneResultInterface,
+            SymbolTableInterface,
+        },
+        ops::FuncOp,
+    },
+    context::{Context, Ptr},
+    derive::op_interface,
+    identifier::Identifier,
+    ir_mapping::IRMapping,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{Op, op_cast},
+    operation::Operation,
+    region::Region,
+    result::Result,
+    transforms::structured_cfg::split_block,
+};
+use thiserror::Error;
+
+use crate::{
+    ops::{BrOp, CallOp, ReturnOp},
+    types::VoidType,
+};
+
+/// An [Op] that may not be legal to inline, i.e., to be copied from
+/// a callee into its caller. See [module](self) documentation.
+#[op_interface]
+pub trait InlinerInterface {
+    /// Can this [Op] be inlined into a caller?
+    fn is_legal_to_inline(&self, ctx: &Context) -> bool;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum InlineErr {
+    #[error("Indirect calls cannot be inlined")]
+    IndirectCall,
+    #[error("Callee {0} is not a function defined in an enclosing symbol table")]
+    CalleeNotFound(String),
+    #[error("Callee {0} has no body to inline")]
+    CalleeWithoutBody(String),
+    #[error("Callee {0} cannot be inlined into itself")]
+    RecursiveCall(String),
+    #[error("Callee {callee} contains {op}, which cannot be inlined")]
+    IllegalOp { callee: String, op: String },
+}
+
+/// Find the [FuncOp] named `name`, in the symbol tables enclosing `op`, innermost first.
+fn lookup_func(ctx: &Context, op: Ptr<Operation>, name: &Identifier) -> Option<FuncOp> {
+    let mut anchor = op;
+    loop {
+        let parent = anchor
+            .deref(ctx)
+            .container()
+            .and_then(|block| block.deref(ctx).container())
+            .map(|region| region.deref(ctx).parent_op())?;
+        let parent_op = Operation::op(parent, ctx);
+        if let Some(found) = op_cast::<dyn SymbolTableInterface>(&*parent_op)
+            .and_then(|table| table.lookup(ctx, name))
+        {
+            return Operation::op(found, ctx).downcast::<FuncOp>().ok();
+        }
+        anchor = parent;
+    }
+}
+
+/// Find an [Operation], in `region` (at any depth), that isn't legal to inline.
+fn find_illegal_op(ctx: &Context, region: Ptr<Region>) -> Option<Ptr<Operation>> {
+    for block in region.deref(ctx).iter(ctx) {
+        for op in block.deref(ctx).iter(ctx) {
+            let is_legal = op_cast::<dyn InlinerInterface>(&*Operation::op(op, ctx))
+                .is_none_or(|inliner| inliner.is_legal_to_inline(ctx));
+            if !is_legal {
+                return Some(op);
+            }
+            let nested = op
+                .deref(ctx)
+                .regions()
+                .find_map(|r| find_illegal_op(ctx, r));
+            if nested.is_some() {
+                return nested;
+            }
+        }
+    }
+    None
+}
+
+/// Inline the body of the callee of `call`, a direct call to a function
+/// defined in a symbol table enclosing `call`, replacing `call`.
+/// See [module](self) documentation. Fails, without modifying the IR,
+/// if the callee can't be found or can't be inlined.
+pub fn inline(ctx: &mut Context, call: CallOp) -> Result<()> {
+    let call_op = call.operation();
+    let loc = call_op.deref(ctx).loc();
+    let CallOpCallable::Direct(callee_name) = call.callee(ctx) else {
+        return arg_err!(loc, InlineErr::IndirectCall);
+    };
+    let Some(callee) = lookup_func(ctx, call_op, &callee_name) else {
+        return arg_err!(loc, InlineErr::CalleeNotFound(callee_name.to_string()));
+    };
+    let callee_region = callee.region(ctx);
+    if callee.get_entry_block(ctx).deref(ctx).head().is_none() {
+        return arg_err!(loc, InlineErr::CalleeWithoutBody(callee_name.to_string()));
+    }
+    if call_op.deref(ctx).is_in_region(ctx, callee_region) {
+        return arg_err!(loc, InlineErr::RecursiveCall(callee_name.to_string()));
+    }
+    if let Some(illegal) = find_illegal_op(ctx, callee_region) {
+        let op = Operation::op(illegal, ctx).opid().to_string();
+        return arg_err!(
+            illegal.deref(ctx).loc(),
+            InlineErr::IllegalOp {
+                callee: callee_name.to_string(),
+                op
+            }
+        );
+    }
+
+    // Split the block, with the second half receiving the returned value.
+    let block = call_op
+        .deref(ctx)
+        .container()
+        .expect("Call to inline must be in a block");
+    let next = call_op
+        .deref(ctx)
+        .next()
+        .expect("Call to inline must be followed by a terminator");
+    let after = split_block(ctx, next);
+    let result_ty = call.result_type(ctx);
+    if !result_ty.deref(ctx).is::<VoidType>() {
+        let arg_idx = after.deref_mut(ctx).add_argument(result_ty);
+        let returned = after.deref(ctx).argument(arg_idx);
+        call.result(ctx)
+            .replace_some_uses_with(ctx, |_, _| true, &returned);
+    }
+
+    // Copy the callee's body in between the two halves.
+    let caller_region = block
+        .deref(ctx)
+        .container()
+        .expect("Block containing the call must be in a region");
+    let mut mapping = IRMapping::new();
+    Region::clone_into(callee_region, ctx, caller_region, &mut mapping);
+    let new_blocks: Vec<_> = callee_region
+        .deref(ctx)
+        .blocks_snapshot(ctx)
+        .into_iter()
+        .map(|callee_block| mapping.block(callee_block))
+        .collect();
+    for new_block in &new_blocks {
+        new_block.unlink(ctx);
+        new_block.insert_before(ctx, after);
+        let term = new_block
+            .deref(ctx)
+            .tail()
+            .expect("A well formed BasicBlock must have a terminator");
+        if Operation::op(term, ctx).is::<ReturnOp>() {
+            let returned = term.deref(ctx).operands().collect();
+            BrOp::new(ctx, after, returned)
+                .operation()
+                .insert_at_back(*new_block, ctx);
+            Operation::erase(term, ctx);
+        }
+    }
+
+    // Pass the call's arguments to the entry block, merging it into the first half
+    // (from where the call is removed), unless the entry block has predecessors.
+    let args = call.args(ctx);
+    Operation::erase(call_op, ctx);
+    let entry = new_blocks[0];
+    if entry.has_pred(ctx) {
+        BrOp::new(ctx, entry, args)
+            .operation()
+            .insert_at_back(block, ctx);
+        return Ok(());
+    }
+    let entry_args: Vec<_> = entry.deref(ctx).arguments().collect();
+    for (entry_arg, arg) in entry_args.iter().zip(&args) {
+        entry_arg.replace_some_uses_with(ctx, |_, _| true, arg);
+    }
+    let entry_ops = entry.deref(ctx).ops_snapshot(ctx);
+    for op in entry_ops {
+        op.unlink(ctx);
+        op.insert_at_back(block, ctx);
+    }
+    BasicBlock::erase(entry, ctx);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use pliron::{
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            op_interfaces::{
+                CallOpCallable, CallOpInterface, OneRegionInterface, OneResultInterface,
+                SingleBlockRegionInterface, SymbolTableInterface, ZeroOpdInterface,
+                ZeroResultInterface,
+            },
+            ops::{FuncOp, ModuleOp},
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::Context,
+        derive::{def_op, derive_op_interface_impl, op_interface_impl},
+        impl_canonical_syntax, impl_verify_succ,
+        op::Op,
+        operation::Operation,
+        parsable::Parsable,
+        printable::Printable,
+    };
+
+    use super::{InlinerInterface, inline};
+    use crate::{
+        self as llvm,
+        attributes::IntegerOverflowFlagsAttr,
+        op_interfaces::IntBinArithOpWithOverflowFlag,
+        ops::{AddOp, CallOp, CondBrOp, ReturnOp},
+    };
+
+    #[def_op("llvm.test_no_inline")]
+    #[derive_op_interface_impl(ZeroOpdInterface, ZeroResultInterface)]
+    struct NoInlineOp;
+    impl_canonical_syntax!(NoInlineOp);
+    impl_verify_succ!(NoInlineOp);
+
+    impl NoInlineOp {
+        fn new(ctx: &mut Context) -> Self {
+            NoInlineOp {
+                op: Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0),
+            }
+        }
+    }
+
+    #[op_interface_impl]
+    impl InlinerInterface for NoInlineOp {
+        fn is_legal_to_inline(&self, _ctx: &Context) -> bool {
+            false
+        }
+    }
+
+    /// A module with `pick(c, x, y)`, returning `x` or `y` based on `c`,
+    /// and `main(c, a)`, computing `pick(c, a, a + a) + a` with a [CallOp].
+    fn setup(ctx: &mut Context) -> (ModuleOp, FuncOp, CallOp) {
+        builtin::register(ctx);
+        llvm::register(ctx);
+        NoInlineOp::register(ctx, NoInlineOp::parser_fn);
+
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless).into();
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless).into();
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+
+        let pick_ty = FunctionType::get(ctx, vec![i1_ty, i32_ty, i32_ty], vec![i32_ty]);
+        let pick = FuncOp::new(ctx, &"pick".try_into().unwrap(), pick_ty);
+        module.append_operation(ctx, pick.operation(), 0);
+        let entry = pick.get_entry_block(ctx);
+        let args: Vec<_> = entry.deref(ctx).arguments().collect();
+        let mut dests = vec![];
+        for arg in &args[1..] {
+            let dest = BasicBlock::new(ctx, None, vec![]);
+            dest.insert_at_back(pick.region(ctx), ctx);
+            ReturnOp::new(ctx, Some(*arg))
+                .operation()
+                .insert_at_back(dest, ctx);
+            dests.push(dest);
+        }
+        CondBrOp::new(ctx, args[0], dests[0], vec![], dests[1], vec![])
+            .operation()
+            .insert_at_back(entry, ctx);
+
+        let main_ty = FunctionType::get(ctx, vec![i1_ty, i32_ty], vec![i32_ty]);
+        let main = FuncOp::new(ctx, &"main".try_into().unwrap(), main_ty);
+        module.append_operation(ctx, main.operation(), 0);
+        let entry = main.get_entry_block(ctx);
+        let (c, a) = (entry.deref(ctx).argument(0), entry.deref(ctx).argument(1));
+        let add = |ctx: &mut Context, lhs, rhs| {
+            let add = AddOp::new_with_overflow_flag(ctx, lhs, rhs, IntegerOverflowFlagsAttr::None);
+            add.operation().insert_at_back(entry, ctx);
+            add.result(ctx)
+        };
+        let sum = add(ctx, a, a);
+        let callee = CallOpCallable::Direct("pick".try_into().unwrap());
+        let call = CallOp::new(ctx, callee, pick_ty, vec![c, a, sum]);
+        call.operation().insert_at_back(entry, ctx);
+        let picked = call.result(ctx);
+        let res = add(ctx, picked, a);
+        ReturnOp::new(ctx, Some(res))
+            .operation()
+            .insert_at_back(entry, ctx);
+        (module, pick, call)
+    }
+
+    #[test]
+    fn test_inline() {
+        let ctx = &mut Context::new();
+        let (module, _pick, call) = setup(ctx);
+
+        inline(ctx, call).unwrap();
+        module.operation().verify(ctx).unwrap();
+        let main = module.lookup(ctx, &"main".try_into().unwrap()).unwrap();
+        expect![[r#"
+            builtin.func @main: builtin.function <(builtin.integer i1, builtin.integer i32)->(builtin.integer i32)> 
+            {
+              ^entry(block_5v1_arg0:builtin.integer i1,block_5v1_arg1:builtin.integer i32):
+                op_7v1_res0 = llvm.add block_5v1_arg1, block_5v1_arg1 <None>: builtin.integer i32;
+                llvm.cond_br if block_5v1_arg0 ^bb1() else ^bb2()
+              ^bb1():
+                llvm.br ^bb3(block_5v1_arg1)
+              ^bb2():
+                llvm.br ^bb3(op_7v1_res0)
+              ^bb3(block_6v1_arg0:builtin.integer i32):
+                op_9v1_res0 = llvm.add block_6v1_arg0, block_5v1_arg1 <None>: builtin.integer i32;
+                llvm.return op_9v1_res0
+            }"#]]
+        .assert_eq(&main.disp(ctx).to_string());
+    }
+
+    #[test]
+    fn test_inline_illegal() {
+        let ctx = &mut Context::new();
+        let (module, pick, call) = setup(ctx);
+
+        // Inlining `pick` into itself isn't possible.
+        let pick_entry = pick.get_entry_block(ctx);
+        let pick_args: Vec<_> = pick_entry.deref(ctx).arguments().collect();
+        let pick_ty = call.callee_type(ctx);
+        let callee = CallOpCallable::Direct("pick".try_into().unwrap());
+        let self_call = CallOp::new(ctx, callee, pick_ty, pick_args);
+        self_call.operation().insert_at_front(pick_entry, ctx);
+        let err = inline(ctx, self_call).unwrap_err();
+        assert!(err.to_string().contains("cannot be inlined into itself"));
+        Operation::erase(self_call.operation(), ctx);
+
+        // Nor is inlining `pick` once it contains an op illegal to inline.
+        NoInlineOp::new(ctx)
+            .operation()
+            .insert_at_front(pick_entry, ctx);
+        let err = inline(ctx, call).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("llvm.test_no_inline, which cannot be inlined")
+        );
+        module.operation().verify(ctx).unwrap();
+    }
+}
@@ -1,23 +1,49 @@
 //! LLVM Dialect for [pliron]
 
 use pliron::{
+    builtin::BuiltinDialect,
     context::Context,
-    dialect::{Dialect, DialectName},
+    dialect::{Dialect, DialectName, DialectPlugin},
 };
 
 pub mod attributes;
+pub mod canonicalize;
+pub mod const_eval;
+pub mod fold;
 pub mod from_llvm_ir;
+pub mod inline;
 pub mod llvm_sys;
 pub mod op_interfaces;
 pub mod ops;
+pub mod simplify_cfg;
 pub mod to_llvm_ir;
+pub mod to_llvm_text;
 pub mod types;
+pub mod undef_poison;
 
-/// Register LLVM dialect, its ops, types and attributes into context.
+/// [DialectPlugin] for the LLVM dialect.
+pub struct LLVMDialect;
+
+impl DialectPlugin for LLVMDialect {
+    fn name(&self) -> DialectName {
+        DialectName::new("llvm")
+    }
+
+    fn dependencies(&self) -> Vec<Box<dyn DialectPlugin>> {
+        vec![Box::new(BuiltinDialect)]
+    }
+
+    fn register(&self, ctx: &mut Context) {
+        let mut dialect = Dialect::new(self.name());
+        dialect.set_constant_materializer(fold::materialize_constant);
+        dialect.register(ctx);
+        ops::register(ctx);
+        types::register(ctx);
+        attributes::register(ctx);
+    }
+}
+
+/// Load the LLVM dialect (and the builtin dialect it depends on) into context.
 pub fn register(ctx: &mut Context) {
-    let dialect = Dialect::new(DialectName::new("llvm"));
-    dialect.register(ctx);
-    ops::register(ctx);
-    types::register(ctx);
-    attributes::register(ctx);
+    LLVMDialect.load(ctx);
 }
@@ -6,44 +6,61 @@ use std::{
 };
 
 use llvm_sys::{
-    LLVMIntPredicate, LLVMOpcode, LLVMTypeKind, LLVMValueKind,
+    LLVMAtomicOrdering, LLVMAtomicRMWBinOp, LLVMIntPredicate, LLVMLinkage, LLVMOpcode,
+    LLVMTypeKind, LLVMValueKind,
     analysis::LLVMVerifyModule,
     bit_writer::LLVMWriteBitcodeToFile,
     core::{
-        LLVMAddFunction, LLVMAddIncoming, LLVMAppendBasicBlockInContext, LLVMArrayType2,
-        LLVMBasicBlockAsValue, LLVMBuildAdd, LLVMBuildAnd, LLVMBuildArrayAlloca, LLVMBuildBitCast,
-        LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr, LLVMBuildExtractValue, LLVMBuildGEP2,
-        LLVMBuildICmp, LLVMBuildInsertValue, LLVMBuildLoad2, LLVMBuildMul, LLVMBuildOr,
-        LLVMBuildPhi, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildSDiv, LLVMBuildSExt, LLVMBuildSRem,
-        LLVMBuildSelect, LLVMBuildShl, LLVMBuildStore, LLVMBuildSub, LLVMBuildUDiv, LLVMBuildURem,
-        LLVMBuildXor, LLVMBuildZExt, LLVMClearInsertionPosition, LLVMConstInt,
-        LLVMConstIntGetZExtValue, LLVMContextCreate, LLVMContextDispose, LLVMCountIncoming,
-        LLVMCountParamTypes, LLVMCountParams, LLVMCountStructElementTypes,
+        LLVMAddClause, LLVMAddFunction, LLVMAddGlobal, LLVMAddIncoming,
+        LLVMAppendBasicBlockInContext, LLVMArrayType2, LLVMBFloatTypeInContext,
+        LLVMBasicBlockAsValue, LLVMBuildAdd, LLVMBuildAnd, LLVMBuildArrayAlloca,
+        LLVMBuildAtomicCmpXchg, LLVMBuildAtomicRMW, LLVMBuildBitCast, LLVMBuildBr, LLVMBuildCall2,
+        LLVMBuildCallWithOperandBundles, LLVMBuildCondBr, LLVMBuildExtractElement,
+        LLVMBuildExtractValue, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildInsertElement,
+        LLVMBuildInsertValue, LLVMBuildInvoke2, LLVMBuildInvokeWithOperandBundles,
+        LLVMBuildLandingPad, LLVMBuildLoad2, LLVMBuildMul, LLVMBuildOr, LLVMBuildPhi,
+        LLVMBuildResume, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildSDiv, LLVMBuildSExt,
+        LLVMBuildSRem, LLVMBuildSelect, LLVMBuildShl, LLVMBuildShuffleVector, LLVMBuildStore,
+        LLVMBuildSub, LLVMBuildUDiv, LLVMBuildURem, LLVMBuildUnreachable, LLVMBuildXor,
+        LLVMBuildZExt, LLVMClearInsertionPosition, LLVMConstBitCast, LLVMConstInt,
+        LLVMConstIntGetZExtValue, LLVMConstVector, LLVMContextCreate, LLVMContextDispose,
+        LLVMCountIncoming, LLVMCountParamTypes, LLVMCountParams, LLVMCountStructElementTypes,
         LLVMCreateBuilderInContext, LLVMCreateMemoryBufferWithContentsOfFile,
-        LLVMDisposeMemoryBuffer, LLVMDisposeMessage, LLVMDisposeModule, LLVMDumpModule,
-        LLVMDumpType, LLVMDumpValue, LLVMFunctionType, LLVMGetAllocatedType, LLVMGetArrayLength2,
-        LLVMGetBasicBlockName, LLVMGetBasicBlockTerminator, LLVMGetCalledFunctionType,
-        LLVMGetCalledValue, LLVMGetConstOpcode, LLVMGetElementType, LLVMGetFirstBasicBlock,
-        LLVMGetFirstFunction, LLVMGetFirstInstruction, LLVMGetFirstParam,
-        LLVMGetGEPSourceElementType, LLVMGetICmpPredicate, LLVMGetIncomingBlock,
-        LLVMGetIncomingValue, LLVMGetIndices, LLVMGetInsertBlock, LLVMGetInstructionOpcode,
-        LLVMGetInstructionParent, LLVMGetIntTypeWidth, LLVMGetModuleIdentifier, LLVMGetNSW,
-        LLVMGetNUW, LLVMGetNextBasicBlock, LLVMGetNextFunction, LLVMGetNextInstruction,
-        LLVMGetNextParam, LLVMGetNumArgOperands, LLVMGetNumIndices, LLVMGetNumOperands,
-        LLVMGetOperand, LLVMGetParam, LLVMGetParamTypes, LLVMGetPreviousBasicBlock,
-        LLVMGetPreviousFunction, LLVMGetPreviousInstruction, LLVMGetPreviousParam,
-        LLVMGetReturnType, LLVMGetStructElementTypes, LLVMGetStructName, LLVMGetTypeKind,
-        LLVMGetUndef, LLVMGetValueKind, LLVMGetValueName2, LLVMGlobalGetValueType,
-        LLVMIntTypeInContext, LLVMIsAFunction, LLVMIsATerminatorInst, LLVMIsAUser,
-        LLVMIsOpaqueStruct, LLVMModuleCreateWithNameInContext, LLVMPointerTypeInContext,
-        LLVMPositionBuilderAtEnd, LLVMPositionBuilderBefore, LLVMPrintModuleToFile,
+        LLVMCreateOperandBundle, LLVMDisposeMemoryBuffer, LLVMDisposeMessage, LLVMDisposeModule,
+        LLVMDisposeOperandBundle, LLVMDoubleTypeInContext, LLVMDumpModule, LLVMDumpType,
+        LLVMDumpValue, LLVMFloatTypeInContext, LLVMFunctionType, LLVMGetAggregateElement,
+        LLVMGetAllocatedType, LLVMGetArrayLength2, LLVMGetAtomicRMWBinOp, LLVMGetBasicBlockName,
+        LLVMGetBasicBlockTerminator, LLVMGetCalledFunctionType, LLVMGetCalledValue, LLVMGetClause,
+        LLVMGetCmpXchgFailureOrdering, LLVMGetCmpXchgSuccessOrdering, LLVMGetConstOpcode,
+        LLVMGetDataLayoutStr, LLVMGetElementType, LLVMGetFirstBasicBlock, LLVMGetFirstFunction,
+        LLVMGetFirstInstruction, LLVMGetFirstParam, LLVMGetGEPSourceElementType,
+        LLVMGetICmpPredicate, LLVMGetIncomingBlock, LLVMGetIncomingValue, LLVMGetIndices,
+        LLVMGetInsertBlock, LLVMGetInstructionOpcode, LLVMGetInstructionParent,
+        LLVMGetIntTypeWidth, LLVMGetLastInstruction, LLVMGetMaskValue, LLVMGetModuleIdentifier,
+        LLVMGetNSW, LLVMGetNUW, LLVMGetNextBasicBlock, LLVMGetNextFunction, LLVMGetNextInstruction,
+        LLVMGetNextParam, LLVMGetNormalDest, LLVMGetNumArgOperands, LLVMGetNumClauses,
+        LLVMGetNumIndices, LLVMGetNumMaskElements, LLVMGetNumOperandBundleArgs,
+        LLVMGetNumOperandBundles, LLVMGetNumOperands, LLVMGetOperand,
+        LLVMGetOperandBundleArgAtIndex, LLVMGetOperandBundleAtIndex, LLVMGetOperandBundleTag,
+        LLVMGetOrdering, LLVMGetParam, LLVMGetParamTypes, LLVMGetPersonalityFn, LLVMGetPoison,
+        LLVMGetPreviousBasicBlock, LLVMGetPreviousFunction, LLVMGetPreviousInstruction,
+        LLVMGetPreviousParam, LLVMGetReturnType, LLVMGetStructElementTypes, LLVMGetStructName,
+        LLVMGetTypeKind, LLVMGetUndef, LLVMGetUndefMaskElem, LLVMGetUnwindDest, LLVMGetValueKind,
+        LLVMGetValueName2, LLVMGetVectorSize, LLVMGetVolatile, LLVMGlobalGetValueType,
+        LLVMHalfTypeInContext, LLVMHasPersonalityFn, LLVMIntTypeInContext, LLVMIsAFunction,
+        LLVMIsATerminatorInst, LLVMIsAUser, LLVMIsCleanup, LLVMIsOpaqueStruct,
+        LLVMModuleCreateWithNameInContext, LLVMPointerTypeInContext, LLVMPositionBuilderAtEnd,
+        LLVMPositionBuilderBefore, LLVMPrintModuleToFile, LLVMPrintModuleToString,
+        LLVMPrintValueToString, LLVMSetCleanup, LLVMSetDataLayout, LLVMSetGlobalConstant,
+        LLVMSetInitializer, LLVMSetLinkage, LLVMSetOrdering, LLVMSetPersonalityFn, LLVMSetVolatile,
         LLVMStructCreateNamed, LLVMStructSetBody, LLVMStructTypeInContext, LLVMTypeIsSized,
-        LLVMTypeOf, LLVMValueAsBasicBlock, LLVMValueIsBasicBlock, LLVMVoidTypeInContext,
+        LLVMTypeOf, LLVMValueAsBasicBlock, LLVMValueIsBasicBlock, LLVMVectorType,
+        LLVMVoidTypeInContext,
     },
     ir_reader::LLVMParseIRInContext,
     prelude::{
         LLVMBasicBlockRef, LLVMBuilderRef, LLVMContextRef, LLVMMemoryBufferRef, LLVMModuleRef,
-        LLVMTypeRef, LLVMValueRef,
+        LLVMOperandBundleRef, LLVMTypeRef, LLVMValueRef,
     },
 };
 
@@ -143,11 +160,31 @@ pub fn llvm_get_module_identifier(module: &LLVMModule) -> Option<String> {
     sized_cstr_to_string(buf_ptr, len)
 }
 
+/// LLVMGetDataLayoutStr
+pub fn llvm_get_data_layout_str(module: &LLVMModule) -> Option<String> {
+    cstr_to_string(unsafe { LLVMGetDataLayoutStr(module.0) })
+}
+
+/// LLVMSetDataLayout
+pub fn llvm_set_data_layout(module: &LLVMModule, data_layout: &str) {
+    unsafe { LLVMSetDataLayout(module.0, to_c_str(data_layout).as_ptr()) }
+}
+
 /// LLVMDumpValue
 pub fn llvm_dump_value(val: LLVMValue) {
     unsafe { LLVMDumpValue(val.into()) }
 }
 
+/// LLVMPrintValueToString
+pub fn llvm_print_value_to_string(val: LLVMValue) -> String {
+    unsafe {
+        let str = LLVMPrintValueToString(val.into());
+        let string = cstr_to_string(str).expect("LLVM failed to print value");
+        LLVMDisposeMessage(str);
+        string
+    }
+}
+
 /// LLVMDumpType
 pub fn llvm_dump_type(ty: LLVMType) {
     unsafe { LLVMDumpType(ty.into()) }
@@ -161,10 +198,11 @@ pub fn llvm_dump_module(module: &LLVMModule) {
 /// The family of LLVMIsA* functions for Value
 pub mod llvm_is_a {
     use llvm_sys::core::{
-        LLVMIsAAllocaInst, LLVMIsAArgument, LLVMIsACallInst, LLVMIsAConstantExpr,
-        LLVMIsAConstantInt, LLVMIsAExtractValueInst, LLVMIsAGetElementPtrInst, LLVMIsAGlobalValue,
-        LLVMIsAICmpInst, LLVMIsAInsertValueInst, LLVMIsAInstruction, LLVMIsAInvokeInst,
-        LLVMIsAPHINode,
+        LLVMIsAAllocaInst, LLVMIsAArgument, LLVMIsAAtomicCmpXchgInst, LLVMIsAAtomicRMWInst,
+        LLVMIsACallInst, LLVMIsAConstantExpr, LLVMIsAConstantInt, LLVMIsAExtractValueInst,
+        LLVMIsAGetElementPtrInst, LLVMIsAGlobalValue, LLVMIsAICmpInst, LLVMIsAInsertValueInst,
+        LLVMIsAInstruction, LLVMIsAInvokeInst, LLVMIsALandingPadInst, LLVMIsAPHINode,
+        LLVMIsAShuffleVectorInst,
     };
 
     use super::*;
@@ -229,11 +267,31 @@ pub mod llvm_is_a {
         unsafe { !LLVMIsAInvokeInst(val.into()).is_null() }
     }
 
+    /// LLVMIsALandingPadInst
+    pub fn landing_pad_inst(val: LLVMValue) -> bool {
+        unsafe { !LLVMIsALandingPadInst(val.into()).is_null() }
+    }
+
+    /// LLVMIsAAtomicRMWInst
+    pub fn atomic_rmw_inst(val: LLVMValue) -> bool {
+        unsafe { !LLVMIsAAtomicRMWInst(val.into()).is_null() }
+    }
+
+    /// LLVMIsAAtomicCmpXchgInst
+    pub fn atomic_cmp_xchg_inst(val: LLVMValue) -> bool {
+        unsafe { !LLVMIsAAtomicCmpXchgInst(val.into()).is_null() }
+    }
+
     /// LLVMIsAGetElementPtrInst
     pub fn get_element_ptr_inst(val: LLVMValue) -> bool {
         unsafe { !LLVMIsAGetElementPtrInst(val.into()).is_null() }
     }
 
+    /// LLVMIsAShuffleVectorInst
+    pub fn shuffle_vector_inst(val: LLVMValue) -> bool {
+        unsafe { !LLVMIsAShuffleVectorInst(val.into()).is_null() }
+    }
+
     /// LLVMIsAInsertValueInst
     pub fn insert_value_inst(val: LLVMValue) -> bool {
         unsafe { !LLVMIsAInsertValueInst(val.into()).is_null() }
@@ -289,6 +347,12 @@ pub fn llvm_get_array_length2(ty: LLVMType) -> u64 {
     unsafe { LLVMGetArrayLength2(ty.into()) }
 }
 
+/// LLVMGetVectorSize
+pub fn llvm_get_vector_size(ty: LLVMType) -> u32 {
+    assert!(llvm_get_type_kind(ty) == LLVMTypeKind::LLVMVectorTypeKind);
+    unsafe { LLVMGetVectorSize(ty.into()) }
+}
+
 /// LLVMGetReturnType
 pub fn llvm_get_return_type(ty: LLVMType) -> LLVMType {
     assert!(llvm_get_type_kind(ty) == LLVMTypeKind::LLVMFunctionTypeKind);
@@ -484,6 +548,14 @@ pub fn llvm_get_next_instruction(instr: LLVMValue) -> Option<LLVMValue> {
     }
 }
 
+/// LLVMGetLastInstruction
+pub fn llvm_get_last_instruction(bb: LLVMBasicBlock) -> Option<LLVMValue> {
+    unsafe {
+        let last_instr = LLVMGetLastInstruction(bb.into());
+        (!last_instr.is_null()).then_some(last_instr.into())
+    }
+}
+
 /// LLVMGetPreviousInstruction
 pub fn llvm_get_previous_instruction(instr: LLVMValue) -> Option<LLVMValue> {
     assert!(llvm_is_a::instruction(instr));
@@ -720,6 +792,26 @@ pub fn llvm_int_type_in_context(context: &LLVMContext, width: u32) -> LLVMType {
     unsafe { LLVMIntTypeInContext(context.0, width).into() }
 }
 
+/// LLVMHalfTypeInContext
+pub fn llvm_half_type_in_context(context: &LLVMContext) -> LLVMType {
+    unsafe { LLVMHalfTypeInContext(context.0).into() }
+}
+
+/// LLVMBFloatTypeInContext
+pub fn llvm_bfloat_type_in_context(context: &LLVMContext) -> LLVMType {
+    unsafe { LLVMBFloatTypeInContext(context.0).into() }
+}
+
+/// LLVMFloatTypeInContext
+pub fn llvm_float_type_in_context(context: &LLVMContext) -> LLVMType {
+    unsafe { LLVMFloatTypeInContext(context.0).into() }
+}
+
+/// LLVMDoubleTypeInContext
+pub fn llvm_double_type_in_context(context: &LLVMContext) -> LLVMType {
+    unsafe { LLVMDoubleTypeInContext(context.0).into() }
+}
+
 /// ArrayType::isValidElementType
 pub fn llvm_is_valid_array_element_type(ty: LLVMType) -> bool {
     !matches!(
@@ -739,6 +831,11 @@ pub fn llvm_array_type2(elem_ty: LLVMType, element_count: u64) -> LLVMType {
     unsafe { LLVMArrayType2(elem_ty.into(), element_count).into() }
 }
 
+/// LLVMVectorType
+pub fn llvm_vector_type(elem_ty: LLVMType, element_count: u32) -> LLVMType {
+    unsafe { LLVMVectorType(elem_ty.into(), element_count).into() }
+}
+
 /// isFirstClassType
 pub fn llvm_is_first_class_type(ty: LLVMType) -> bool {
     !matches!(
@@ -1099,6 +1196,85 @@ pub fn llvm_build_extract_value(
     }
 }
 
+/// LLVMBuildExtractElement
+pub fn llvm_build_extract_element(
+    builder: &LLVMBuilder,
+    vec_val: LLVMValue,
+    index: LLVMValue,
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe {
+        LLVMBuildExtractElement(
+            builder.0,
+            vec_val.into(),
+            index.into(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMBuildInsertElement
+pub fn llvm_build_insert_element(
+    builder: &LLVMBuilder,
+    vec_val: LLVMValue,
+    element_val: LLVMValue,
+    index: LLVMValue,
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe {
+        LLVMBuildInsertElement(
+            builder.0,
+            vec_val.into(),
+            element_val.into(),
+            index.into(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMBuildShuffleVector
+pub fn llvm_build_shuffle_vector(
+    builder: &LLVMBuilder,
+    v1: LLVMValue,
+    v2: LLVMValue,
+    mask: LLVMValue,
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe {
+        LLVMBuildShuffleVector(
+            builder.0,
+            v1.into(),
+            v2.into(),
+            mask.into(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMGetNumMaskElements
+pub fn llvm_get_num_mask_elements(shuffle_vector_inst: LLVMValue) -> u32 {
+    assert!(llvm_is_a::shuffle_vector_inst(shuffle_vector_inst));
+    unsafe { LLVMGetNumMaskElements(shuffle_vector_inst.into()) }
+}
+
+/// LLVMGetMaskValue.
+/// Returns [None] for an undefined (poison) mask element.
+pub fn llvm_get_mask_value(shuffle_vector_inst: LLVMValue, elt: u32) -> Option<u32> {
+    assert!(llvm_is_a::shuffle_vector_inst(shuffle_vector_inst));
+    let mask_val = unsafe { LLVMGetMaskValue(shuffle_vector_inst.into(), elt) };
+    if mask_val == unsafe { LLVMGetUndefMaskElem() } {
+        None
+    } else {
+        Some(mask_val.try_into().unwrap())
+    }
+}
+
 /// LLVMBuildSelect
 pub fn llvm_build_select(
     builder: &LLVMBuilder,
@@ -1191,6 +1367,80 @@ pub fn llvm_build_store(builder: &LLVMBuilder, val: LLVMValue, ptr: LLVMValue) -
     unsafe { LLVMBuildStore(builder.0, val.into(), ptr.into()).into() }
 }
 
+/// LLVMGetVolatile
+pub fn llvm_get_volatile(memory_access_inst: LLVMValue) -> bool {
+    unsafe { LLVMGetVolatile(memory_access_inst.into()).to_bool() }
+}
+
+/// LLVMSetVolatile
+pub fn llvm_set_volatile(memory_access_inst: LLVMValue, is_volatile: bool) {
+    unsafe { LLVMSetVolatile(memory_access_inst.into(), is_volatile as i32) }
+}
+
+/// LLVMGetOrdering
+pub fn llvm_get_ordering(memory_access_inst: LLVMValue) -> LLVMAtomicOrdering {
+    unsafe { LLVMGetOrdering(memory_access_inst.into()) }
+}
+
+/// LLVMSetOrdering
+pub fn llvm_set_ordering(memory_access_inst: LLVMValue, ordering: LLVMAtomicOrdering) {
+    unsafe { LLVMSetOrdering(memory_access_inst.into(), ordering) }
+}
+
+/// LLVMBuildAtomicRMW
+pub fn llvm_build_atomic_rmw(
+    builder: &LLVMBuilder,
+    op: LLVMAtomicRMWBinOp,
+    ptr: LLVMValue,
+    val: LLVMValue,
+    ordering: LLVMAtomicOrdering,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe { LLVMBuildAtomicRMW(builder.0, op, ptr.into(), val.into(), ordering, 0).into() }
+}
+
+/// LLVMGetAtomicRMWBinOp
+pub fn llvm_get_atomic_rmw_bin_op(atomic_rmw_inst: LLVMValue) -> LLVMAtomicRMWBinOp {
+    assert!(llvm_is_a::atomic_rmw_inst(atomic_rmw_inst));
+    unsafe { LLVMGetAtomicRMWBinOp(atomic_rmw_inst.into()) }
+}
+
+/// LLVMBuildAtomicCmpXchg
+pub fn llvm_build_atomic_cmp_xchg(
+    builder: &LLVMBuilder,
+    ptr: LLVMValue,
+    cmp: LLVMValue,
+    new: LLVMValue,
+    success_ordering: LLVMAtomicOrdering,
+    failure_ordering: LLVMAtomicOrdering,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe {
+        LLVMBuildAtomicCmpXchg(
+            builder.0,
+            ptr.into(),
+            cmp.into(),
+            new.into(),
+            success_ordering,
+            failure_ordering,
+            0,
+        )
+        .into()
+    }
+}
+
+/// LLVMGetCmpXchgSuccessOrdering
+pub fn llvm_get_cmp_xchg_success_ordering(cmp_xchg_inst: LLVMValue) -> LLVMAtomicOrdering {
+    assert!(llvm_is_a::atomic_cmp_xchg_inst(cmp_xchg_inst));
+    unsafe { LLVMGetCmpXchgSuccessOrdering(cmp_xchg_inst.into()) }
+}
+
+/// LLVMGetCmpXchgFailureOrdering
+pub fn llvm_get_cmp_xchg_failure_ordering(cmp_xchg_inst: LLVMValue) -> LLVMAtomicOrdering {
+    assert!(llvm_is_a::atomic_cmp_xchg_inst(cmp_xchg_inst));
+    unsafe { LLVMGetCmpXchgFailureOrdering(cmp_xchg_inst.into()) }
+}
+
 /// LLVMBuildICmp
 pub fn llvm_build_icmp(
     builder: &LLVMBuilder,
@@ -1253,23 +1503,305 @@ pub fn llvm_build_call2(
     }
 }
 
+/// LLVMBuildInvoke2
+pub fn llvm_build_invoke2(
+    builder: &LLVMBuilder,
+    ty: LLVMType,
+    callee: LLVMValue,
+    args: &[LLVMValue],
+    then_block: LLVMBasicBlock,
+    catch_block: LLVMBasicBlock,
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    let mut args: Vec<_> = args.iter().cloned().map(Into::into).collect();
+    unsafe {
+        LLVMBuildInvoke2(
+            builder.0,
+            ty.into(),
+            callee.into(),
+            args.as_mut_ptr(),
+            args.len().try_into().unwrap(),
+            then_block.into(),
+            catch_block.into(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// Managed LLVMOperandBundleRef.
+pub struct LLVMOperandBundle(LLVMOperandBundleRef);
+
+impl LLVMOperandBundle {
+    /// LLVMCreateOperandBundle
+    pub fn new(tag: &str, args: &[LLVMValue]) -> Self {
+        let mut args: Vec<_> = args.iter().cloned().map(Into::into).collect();
+        unsafe {
+            LLVMOperandBundle(LLVMCreateOperandBundle(
+                tag.as_ptr() as *const ::core::ffi::c_char,
+                tag.len(),
+                args.as_mut_ptr(),
+                args.len().try_into().unwrap(),
+            ))
+        }
+    }
+
+    /// LLVMGetOperandBundleTag
+    pub fn tag(&self) -> String {
+        let mut len = 0;
+        let tag = unsafe { LLVMGetOperandBundleTag(self.0, &mut len) };
+        sized_cstr_to_string(tag, len).expect("Operand bundle without a tag")
+    }
+
+    /// LLVMGetNumOperandBundleArgs and LLVMGetOperandBundleArgAtIndex
+    pub fn args(&self) -> Vec<LLVMValue> {
+        let num_args = unsafe { LLVMGetNumOperandBundleArgs(self.0) };
+        (0..num_args)
+            .map(|idx| unsafe { LLVMGetOperandBundleArgAtIndex(self.0, idx).into() })
+            .collect()
+    }
+}
+
+impl Drop for LLVMOperandBundle {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeOperandBundle(self.0) }
+    }
+}
+
+/// LLVMGetNumOperandBundles
+pub fn llvm_get_num_operand_bundles(inst: LLVMValue) -> u32 {
+    assert!(llvm_is_a::call_inst(inst) || llvm_is_a::invoke_inst(inst));
+    unsafe { LLVMGetNumOperandBundles(inst.into()) }
+}
+
+/// LLVMGetOperandBundleAtIndex
+pub fn llvm_get_operand_bundle_at_index(inst: LLVMValue, index: u32) -> LLVMOperandBundle {
+    assert!(index < llvm_get_num_operand_bundles(inst));
+    unsafe { LLVMOperandBundle(LLVMGetOperandBundleAtIndex(inst.into(), index)) }
+}
+
+/// LLVMBuildCallWithOperandBundles
+pub fn llvm_build_call_with_operand_bundles(
+    builder: &LLVMBuilder,
+    ty: LLVMType,
+    callee: LLVMValue,
+    args: &[LLVMValue],
+    bundles: &[LLVMOperandBundle],
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    let mut args: Vec<_> = args.iter().cloned().map(Into::into).collect();
+    let mut bundles: Vec<_> = bundles.iter().map(|bundle| bundle.0).collect();
+    unsafe {
+        LLVMBuildCallWithOperandBundles(
+            builder.0,
+            ty.into(),
+            callee.into(),
+            args.as_mut_ptr(),
+            args.len().try_into().unwrap(),
+            bundles.as_mut_ptr(),
+            bundles.len().try_into().unwrap(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMBuildInvokeWithOperandBundles
+#[allow(clippy::too_many_arguments)]
+pub fn llvm_build_invoke_with_operand_bundles(
+    builder: &LLVMBuilder,
+    ty: LLVMType,
+    callee: LLVMValue,
+    args: &[LLVMValue],
+    then_block: LLVMBasicBlock,
+    catch_block: LLVMBasicBlock,
+    bundles: &[LLVMOperandBundle],
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    let mut args: Vec<_> = args.iter().cloned().map(Into::into).collect();
+    let mut bundles: Vec<_> = bundles.iter().map(|bundle| bundle.0).collect();
+    unsafe {
+        LLVMBuildInvokeWithOperandBundles(
+            builder.0,
+            ty.into(),
+            callee.into(),
+            args.as_mut_ptr(),
+            args.len().try_into().unwrap(),
+            then_block.into(),
+            catch_block.into(),
+            bundles.as_mut_ptr(),
+            bundles.len().try_into().unwrap(),
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMBuildLandingPad
+pub fn llvm_build_landing_pad(
+    builder: &LLVMBuilder,
+    ty: LLVMType,
+    personality_fn: Option<LLVMValue>,
+    num_clauses: u32,
+    name: &str,
+) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    let personality_fn = personality_fn.map_or(ptr::null_mut(), Into::into);
+    unsafe {
+        LLVMBuildLandingPad(
+            builder.0,
+            ty.into(),
+            personality_fn,
+            num_clauses,
+            to_c_str(name).as_ptr(),
+        )
+        .into()
+    }
+}
+
+/// LLVMAddClause
+pub fn llvm_add_clause(landing_pad: LLVMValue, clause: LLVMValue) {
+    assert!(llvm_is_a::landing_pad_inst(landing_pad));
+    unsafe { LLVMAddClause(landing_pad.into(), clause.into()) }
+}
+
+/// LLVMGetNumClauses
+pub fn llvm_get_num_clauses(landing_pad: LLVMValue) -> u32 {
+    assert!(llvm_is_a::landing_pad_inst(landing_pad));
+    unsafe { LLVMGetNumClauses(landing_pad.into()) }
+}
+
+/// LLVMGetClause
+pub fn llvm_get_clause(landing_pad: LLVMValue, idx: u32) -> LLVMValue {
+    assert!(llvm_is_a::landing_pad_inst(landing_pad));
+    unsafe { LLVMGetClause(landing_pad.into(), idx).into() }
+}
+
+/// LLVMSetCleanup
+pub fn llvm_set_cleanup(landing_pad: LLVMValue, cleanup: bool) {
+    assert!(llvm_is_a::landing_pad_inst(landing_pad));
+    unsafe { LLVMSetCleanup(landing_pad.into(), cleanup as i32) }
+}
+
+/// LLVMIsCleanup
+pub fn llvm_is_cleanup(landing_pad: LLVMValue) -> bool {
+    assert!(llvm_is_a::landing_pad_inst(landing_pad));
+    unsafe { LLVMIsCleanup(landing_pad.into()).to_bool() }
+}
+
+/// LLVMBuildResume
+pub fn llvm_build_resume(builder: &LLVMBuilder, exn: LLVMValue) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe { LLVMBuildResume(builder.0, exn.into()).into() }
+}
+
+/// LLVMBuildUnreachable
+pub fn llvm_build_unreachable(builder: &LLVMBuilder) -> LLVMValue {
+    assert!(llvm_get_insert_block(builder).is_some());
+    unsafe { LLVMBuildUnreachable(builder.0).into() }
+}
+
+/// LLVMGetNormalDest
+pub fn llvm_get_normal_dest(invoke_inst: LLVMValue) -> LLVMBasicBlock {
+    assert!(llvm_is_a::invoke_inst(invoke_inst));
+    unsafe { LLVMGetNormalDest(invoke_inst.into()).into() }
+}
+
+/// LLVMGetUnwindDest
+pub fn llvm_get_unwind_dest(invoke_inst: LLVMValue) -> LLVMBasicBlock {
+    assert!(llvm_is_a::invoke_inst(invoke_inst));
+    unsafe { LLVMGetUnwindDest(invoke_inst.into()).into() }
+}
+
+/// LLVMHasPersonalityFn
+pub fn llvm_has_personality_fn(func: LLVMValue) -> bool {
+    assert!(llvm_is_a::function(func));
+    unsafe { LLVMHasPersonalityFn(func.into()).to_bool() }
+}
+
+/// LLVMGetPersonalityFn
+pub fn llvm_get_personality_fn(func: LLVMValue) -> Option<LLVMValue> {
+    if !llvm_has_personality_fn(func) {
+        return None;
+    }
+    Some(unsafe { LLVMGetPersonalityFn(func.into()).into() })
+}
+
+/// LLVMSetPersonalityFn
+pub fn llvm_set_personality_fn(func: LLVMValue, personality_fn: LLVMValue) {
+    assert!(llvm_is_a::function(func));
+    unsafe { LLVMSetPersonalityFn(func.into(), personality_fn.into()) }
+}
+
 /// LLVMConstInt
 pub fn llvm_const_int(int_ty: LLVMType, val: u64, sign_extend: bool) -> LLVMValue {
     assert!(llvm_get_type_kind(int_ty) == LLVMTypeKind::LLVMIntegerTypeKind);
     unsafe { LLVMConstInt(int_ty.into(), val, sign_extend as i32).into() }
 }
 
+/// LLVMConstBitCast
+pub fn llvm_const_bit_cast(val: LLVMValue, to_ty: LLVMType) -> LLVMValue {
+    unsafe { LLVMConstBitCast(val.into(), to_ty.into()).into() }
+}
+
+/// LLVMConstVector
+pub fn llvm_const_vector(scalar_constant_vals: &[LLVMValue]) -> LLVMValue {
+    let mut vals: Vec<_> = scalar_constant_vals
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect();
+    unsafe { LLVMConstVector(vals.as_mut_ptr(), vals.len().try_into().unwrap()).into() }
+}
+
+/// LLVMGetAggregateElement
+pub fn llvm_get_aggregate_element(c: LLVMValue, idx: u32) -> Option<LLVMValue> {
+    let elem = unsafe { LLVMGetAggregateElement(c.into(), idx) };
+    (!elem.is_null()).then_some(elem.into())
+}
+
 /// LLVMGetUndef
 pub fn llvm_get_undef(ty: LLVMType) -> LLVMValue {
     unsafe { LLVMGetUndef(ty.into()).into() }
 }
 
+/// LLVMGetPoison
+pub fn llvm_get_poison(ty: LLVMType) -> LLVMValue {
+    unsafe { LLVMGetPoison(ty.into()).into() }
+}
+
 /// LLVMAddFunction
 pub fn llvm_add_function(module: &LLVMModule, name: &str, fn_ty: LLVMType) -> LLVMValue {
     assert!(llvm_get_type_kind(fn_ty) == LLVMTypeKind::LLVMFunctionTypeKind);
     unsafe { LLVMAddFunction(module.0, to_c_str(name).as_ptr(), fn_ty.into()).into() }
 }
 
+/// LLVMAddGlobal
+pub fn llvm_add_global(module: &LLVMModule, ty: LLVMType, name: &str) -> LLVMValue {
+    unsafe { LLVMAddGlobal(module.0, ty.into(), to_c_str(name).as_ptr()).into() }
+}
+
+/// LLVMSetInitializer
+pub fn llvm_set_initializer(global: LLVMValue, val: LLVMValue) {
+    assert!(llvm_is_a::global_value(global));
+    unsafe { LLVMSetInitializer(global.into(), val.into()) }
+}
+
+/// LLVMSetGlobalConstant
+pub fn llvm_set_global_constant(global: LLVMValue, is_constant: bool) {
+    assert!(llvm_is_a::global_value(global));
+    unsafe { LLVMSetGlobalConstant(global.into(), is_constant as i32) }
+}
+
+/// LLVMSetLinkage
+pub fn llvm_set_linkage(global: LLVMValue, linkage: LLVMLinkage) {
+    assert!(llvm_is_a::global_value(global));
+    unsafe { LLVMSetLinkage(global.into(), linkage) }
+}
+
 /// LLVMAppendBasicBlockInContext
 pub fn llvm_append_basic_block_in_context(
     context: &LLVMContext,
@@ -1401,6 +1933,16 @@ impl LLVMModule {
         Ok(())
     }
 
+    /// Print [LLVMModule] to text assembly
+    pub fn asm_to_string(&self) -> String {
+        unsafe {
+            let str = LLVMPrintModuleToString(self.0);
+            let string = cstr_to_string(str).expect("LLVM failed to print module");
+            LLVMDisposeMessage(str);
+            string
+        }
+    }
+
     /// Print this [LLVMModule] to a bitcode file
     pub fn bitcode_to_file(&self, filename: &str) -> Result<(), String> {
         unsafe {
@@ -7,6 +7,7 @@ use thiserror::Error;
 
 use pliron::{
     builtin::{
+        attributes::UnitAttr,
         op_interfaces::{OneResultInterface, SameOperandsAndResultType},
         types::{IntegerType, Signedness},
     },
@@ -21,7 +22,10 @@ use pliron::{
     verify_err,
 };
 
-use super::{attributes::IntegerOverflowFlagsAttr, types::PointerType};
+use super::{
+    attributes::{AtomicOrderingAttr, IntegerOverflowFlagsAttr},
+    types::PointerType,
+};
 
 #[derive(Error, Debug)]
 #[error("Binary Arithmetic Op must have exactly two operands and one result")]
@@ -43,7 +47,7 @@ pub trait BinArithOp: SameOperandsAndResultType + OneResultInterface {
             vec![],
             0,
         );
-        *Operation::op(op, ctx).downcast::<Self>().ok().unwrap()
+        Operation::op(op, ctx).downcast::<Self>().ok().unwrap()
     }
 
     fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
@@ -133,7 +137,7 @@ pub trait IntBinArithOpWithOverflowFlag: IntBinArithOp {
         self.operation()
             .deref_mut(ctx)
             .attributes
-            .set(ATTR_KEY_INTEGER_OVERFLOW_FLAGS.clone(), flag);
+            .set(*ATTR_KEY_INTEGER_OVERFLOW_FLAGS, flag);
     }
 
     fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
@@ -161,7 +165,8 @@ pub struct PointerTypeResultVerifyErr;
 #[op_interface]
 pub trait PointerTypeResult: OneResultInterface {
     /// Get the pointee type of the result pointer.
-    fn result_pointee_type(&self, ctx: &Context) -> Ptr<TypeObj>;
+    /// Fails, with an error located at the op, if the op doesn't determine it.
+    fn result_pointee_type(&self, ctx: &Context) -> Result<Ptr<TypeObj>>;
 
     fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
     where
@@ -196,7 +201,7 @@ pub trait CastOpInterface: OneResultInterface + OneOpdInterface {
             vec![],
             0,
         );
-        *Operation::op(op, ctx).downcast::<Self>().ok().unwrap()
+        Operation::op(op, ctx).downcast::<Self>().ok().unwrap()
     }
 
     fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
@@ -206,3 +211,118 @@ pub trait CastOpInterface: OneResultInterface + OneOpdInterface {
         Ok(())
     }
 }
+
+/// Attribute key for the atomic ordering of a memory access.
+pub static ATTR_KEY_ATOMIC_ORDERING: LazyLock<Identifier> =
+    LazyLock::new(|| "llvm_atomic_ordering".try_into().unwrap());
+
+/// Attribute key marking a memory access as volatile.
+pub static ATTR_KEY_VOLATILE: LazyLock<Identifier> =
+    LazyLock::new(|| "llvm_volatile".try_into().unwrap());
+
+#[derive(Error, Debug)]
+pub enum MemoryAccessOpErr {
+    #[error("Memory access must have a pointer as its address operand")]
+    AddressNotPointer,
+    #[error("Atomic ordering attribute is of incorrect type")]
+    OrderingAttrType,
+    #[error("Volatile attribute is of incorrect type")]
+    VolatileAttrType,
+}
+
+/// An [Op] that reads and / or writes memory through a pointer, possibly
+/// [atomically](AtomicOrderingAttr) or [volatilely](ATTR_KEY_VOLATILE).
+///
+/// Optimizations must treat accesses that aren't [simple](Self::is_simple)
+/// conservatively: they may not be removed, duplicated, merged,
+/// or reordered with respect to each other.
+#[op_interface]
+pub trait MemoryAccessOpInterface {
+    /// Get the pointer operand that is accessed.
+    fn address_opd(&self, ctx: &Context) -> Value;
+
+    /// Is this a volatile access?
+    fn is_volatile(&self, ctx: &Context) -> bool {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&ATTR_KEY_VOLATILE)
+            .is_some()
+    }
+
+    /// Mark this access as volatile (or not).
+    fn set_volatile(&self, ctx: &Context, volatile: bool) {
+        let attributes = &mut self.operation().deref_mut(ctx).attributes;
+        if volatile {
+            attributes.set(*ATTR_KEY_VOLATILE, UnitAttr::new());
+        } else {
+            attributes.0.remove(&*ATTR_KEY_VOLATILE);
+        }
+    }
+
+    /// Get the atomic ordering of this access.
+    /// [NotAtomic](AtomicOrderingAttr::NotAtomic) if none is specified.
+    fn atomic_ordering(&self, ctx: &Context) -> AtomicOrderingAttr {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<AtomicOrderingAttr>(&ATTR_KEY_ATOMIC_ORDERING)
+            .copied()
+            .unwrap_or(AtomicOrderingAttr::NotAtomic)
+    }
+
+    /// Set the atomic ordering of this access.
+    fn set_atomic_ordering(&self, ctx: &Context, ordering: AtomicOrderingAttr) {
+        let attributes = &mut self.operation().deref_mut(ctx).attributes;
+        if ordering == AtomicOrderingAttr::NotAtomic {
+            attributes.0.remove(&*ATTR_KEY_ATOMIC_ORDERING);
+        } else {
+            attributes.set(*ATTR_KEY_ATOMIC_ORDERING, ordering);
+        }
+    }
+
+    /// Is this access atomic?
+    fn is_atomic(&self, ctx: &Context) -> bool {
+        self.atomic_ordering(ctx) != AtomicOrderingAttr::NotAtomic
+    }
+
+    /// A simple access is neither atomic nor volatile,
+    /// and can be freely optimized (subject to aliasing).
+    fn is_simple(&self, ctx: &Context) -> bool {
+        !self.is_atomic(ctx) && !self.is_volatile(ctx)
+    }
+
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let access = op_cast::<dyn MemoryAccessOpInterface>(op)
+            .expect("Op must impl MemoryAccessOpInterface");
+        if !access
+            .address_opd(ctx)
+            .get_type(ctx)
+            .isa::<PointerType>(ctx)
+        {
+            return verify_err!(op.loc(ctx), MemoryAccessOpErr::AddressNotPointer);
+        }
+
+        let op = op.operation().deref(ctx);
+        if op
+            .attributes
+            .0
+            .get(&*ATTR_KEY_ATOMIC_ORDERING)
+            .is_some_and(|attr| !attr.is::<AtomicOrderingAttr>())
+        {
+            return verify_err!(op.loc(), MemoryAccessOpErr::OrderingAttrType);
+        }
+        if op
+            .attributes
+            .0
+            .get(&*ATTR_KEY_VOLATILE)
+            .is_some_and(|attr| !attr.is::<UnitAttr>())
+        {
+            return verify_err!(op.loc(), MemoryAccessOpErr::VolatileAttrType);
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,27 @@
 //! [Op]s defined in the LLVM dialect
 
+use std::num::NonZero;
+
 use pliron::{
     arg_err_noloc,
     attribute::{AttrObj, attr_cast},
     basic_block::BasicBlock,
     builtin::{
         attr_interfaces::TypedAttrInterface,
-        attributes::{FloatAttr, IdentifierAttr, IntegerAttr, TypeAttr},
+        attributes::{FloatAttr, IdentifierAttr, IntegerAttr, TypeAttr, UnitAttr},
         op_interfaces::{
             self, ATTR_KEY_CALLEE_TYPE, BranchOpInterface, CallOpCallable, CallOpInterface,
-            IsTerminatorInterface, OneOpdInterface, OneResultInterface, SameOperandsAndResultType,
-            SameOperandsType, SameResultsType, ZeroOpdInterface, ZeroResultInterface,
+            ConditionalBranchInterface, ConstantLikeInterface, HoistableConstantInterface,
+            IsTerminatorInterface, MemoryEffect, MemoryEffectKind, MemoryEffectOn,
+            MemoryEffectOpInterface, OneOpdInterface, OneResultInterface, OperandBundleInterface,
+            PureInterface, SameOperandsAndResultType, SameOperandsType, SameResultsType,
+            SymbolOpInterface, SymbolUserOpInterface, UnreachableInterface, ZeroOpdInterface,
+            ZeroResultInterface,
         },
+        type_interfaces::{DataLayout, align_of, size_of},
         types::{FunctionType, IntegerType, Signedness},
     },
-    common_traits::{Named, Verify},
+    common_traits::Verify,
     context::{Context, Ptr},
     derive::{format, format_op},
     identifier::Identifier,
@@ -22,11 +29,15 @@ use pliron::{
     irfmt::{
         self,
         parsers::{
-            block_opd_parser, delimited_list_parser, process_parsed_ssa_defs, spaced,
-            ssa_opd_parser,
+            block_opd_parser, call_like_parse, delimited_list_parser, process_parsed_ssa_defs,
+            spaced, ssa_opd_parser, type_parser,
+        },
+        printers::{
+            iter_with_sep,
+            op::{call_like, results},
         },
-        printers::iter_with_sep,
     },
+    linked_list::{ContainsLinkedList, LinkedList},
     location::{Located, Location},
     op::{Op, OpObj},
     operation::Operation,
@@ -34,21 +45,28 @@ use pliron::{
     printable::Printable,
     result::{Error, ErrorKind, Result},
     r#type::{TypeObj, TypePtr},
-    utils::vec_exns::VecExtns,
+    utils::{apint::APInt, vec_exns::VecExtns},
     value::Value,
-    verify_err,
+    verify_err, verify_error,
 };
 
 use crate::{
-    attributes::InsertExtractValueIndicesAttr,
+    attributes::{
+        AtomicOrderingAttr, AtomicRmwBinOpAttr, ConstantVectorAttr, InsertExtractValueIndicesAttr,
+        PoisonAttr, ShuffleMaskAttr, ShuffleMaskElemAttr, UndefAttr, verify_undef_poison_type,
+    },
     op_interfaces::{
         BinArithOp, CastOpInterface, IntBinArithOp, IntBinArithOpWithOverflowFlag,
-        PointerTypeResult,
+        MemoryAccessOpInterface, PointerTypeResult,
     },
-    types::{ArrayType, StructType},
+    types::{ArrayType, StructType, VectorType},
 };
 
-use combine::parser::Parser;
+use combine::{
+    attempt, optional,
+    parser::{Parser, char::string},
+    token,
+};
 use pliron::derive::{def_op, derive_op_interface_impl, op_interface_impl};
 use thiserror::Error;
 
@@ -114,7 +132,7 @@ macro_rules! new_int_bin_op_without_format {
         /// | `res` | Signless integer |
         #[pliron::derive::derive_op_interface_impl(
             OneResultInterface, SameOperandsType, SameResultsType,
-            SameOperandsAndResultType, BinArithOp, IntBinArithOp
+            SameOperandsAndResultType, BinArithOp, IntBinArithOp, PureInterface
         )]
         pub struct $op_name;
 
@@ -262,7 +280,7 @@ pub enum ICmpOpVerifyErr {
 /// | [ATTR_KEY_PREDICATE](icmp_op::ATTR_KEY_PREDICATE) | [ICmpPredicateAttr](ICmpPredicateAttr) | N/A |
 #[def_op("llvm.icmp")]
 #[format_op("$0 ` <` attr($llvm_icmp_predicate, $ICmpPredicateAttr) `> ` $1 ` : ` type($0)")]
-#[derive_op_interface_impl(SameOperandsType, OneResultInterface)]
+#[derive_op_interface_impl(SameOperandsType, OneResultInterface, PureInterface)]
 pub struct ICmpOp;
 
 pub mod icmp_op {
@@ -288,7 +306,7 @@ impl ICmpOp {
         );
         op.deref_mut(ctx)
             .attributes
-            .set(icmp_op::ATTR_KEY_PREDICATE.clone(), pred);
+            .set(*icmp_op::ATTR_KEY_PREDICATE, pred);
         ICmpOp { op }
     }
 
@@ -317,10 +335,7 @@ impl Verify for ICmpOp {
         }
 
         let res_ty: TypePtr<IntegerType> =
-            TypePtr::from_ptr(self.result_type(ctx), ctx).map_err(|mut err| {
-                err.set_loc(loc.clone());
-                err
-            })?;
+            TypePtr::downcast_at(ctx, self.result_type(ctx), loc.clone())?;
 
         if res_ty.deref(ctx).width() != 1 {
             return verify_err!(loc, ICmpOpVerifyErr::ResultNotBool);
@@ -368,7 +383,7 @@ impl Verify for AllocaOp {
     fn verify(&self, ctx: &Context) -> Result<()> {
         let loc = self.loc(ctx);
         // Ensure correctness of operand type.
-        if !self.operand_type(ctx).deref(ctx).is::<IntegerType>() {
+        if !self.operand_type(ctx).isa::<IntegerType>(ctx) {
             return verify_err!(loc, AllocaOpVerifyErr::OperandType);
         }
         let op = &*self.op.deref(ctx);
@@ -385,15 +400,27 @@ impl Verify for AllocaOp {
     }
 }
 
+#[op_interface_impl]
+impl MemoryEffectOpInterface for AllocaOp {
+    fn memory_effects(&self, _ctx: &Context) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::new(
+            MemoryEffectKind::Allocate,
+            MemoryEffectOn::Result(0),
+        )]
+    }
+}
+
 #[op_interface_impl]
 impl PointerTypeResult for AllocaOp {
-    fn result_pointee_type(&self, ctx: &Context) -> Ptr<TypeObj> {
-        self.op
-            .deref(ctx)
+    fn result_pointee_type(&self, ctx: &Context) -> Result<Ptr<TypeObj>> {
+        let op = self.op.deref(ctx);
+        let Some(elem_type) = op
             .attributes
             .get::<TypeAttr>(&alloca_op::ATTR_KEY_ELEM_TYPE)
-            .expect("AllocaOp missing or incorrect type for elem_type attribute")
-            .get_type()
+        else {
+            return verify_err!(op.loc(), AllocaOpVerifyErr::ElemTypeAttr);
+        };
+        Ok(elem_type.get_type())
     }
 }
 
@@ -417,10 +444,9 @@ impl AllocaOp {
             vec![],
             0,
         );
-        op.deref_mut(ctx).attributes.set(
-            alloca_op::ATTR_KEY_ELEM_TYPE.clone(),
-            TypeAttr::new(elem_type),
-        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*alloca_op::ATTR_KEY_ELEM_TYPE, TypeAttr::new(elem_type));
         AllocaOp { op }
     }
 }
@@ -438,7 +464,7 @@ impl AllocaOp {
 /// | `res` | non-aggregate LLVM type |
 #[def_op("llvm.bitcast")]
 #[format_op("$0 ` : ` type($0)")]
-#[derive_op_interface_impl(OneResultInterface, OneOpdInterface)]
+#[derive_op_interface_impl(OneResultInterface, OneOpdInterface, PureInterface)]
 pub struct BitcastOp;
 impl_verify_succ!(BitcastOp);
 
@@ -549,13 +575,13 @@ impl Printable for CondBrOp {
             "{} if {} ^{}({}) else ^{}({})",
             op.opid(),
             condition.disp(ctx),
-            op.successor(0).deref(ctx).unique_name(ctx),
+            op.successor(0).deref(ctx).printed_label(ctx),
             iter_with_sep(
                 true_dest_opds.iter(),
                 pliron::printable::ListSeparator::CharSpace(',')
             )
             .disp(ctx),
-            op.successor(1).deref(ctx).unique_name(ctx),
+            op.successor(1).deref(ctx).printed_label(ctx),
             iter_with_sep(
                 false_dest_opds.iter(),
                 pliron::printable::ListSeparator::CharSpace(',')
@@ -617,7 +643,7 @@ impl Parsable for CondBrOp {
                         );
 
                         process_parsed_ssa_defs(parsable_state, &results, op)?;
-                        let op: OpObj = Box::new(CondBrOp { op });
+                        let op = OpObj::new(CondBrOp { op });
                         Ok(op).into_parse_result()
                     })
                 },
@@ -661,6 +687,20 @@ impl BranchOpInterface for CondBrOp {
     }
 }
 
+#[op_interface_impl]
+impl ConditionalBranchInterface for CondBrOp {
+    fn taken_successor(&self, _ctx: &Context, operands: &[Option<AttrObj>]) -> Option<usize> {
+        let condition = operands[0].as_ref()?.downcast_ref::<IntegerAttr>()?;
+        Some(usize::from(APInt::from(condition.clone()).is_zero()))
+    }
+
+    fn build_branch_to(&self, ctx: &mut Context, succ_idx: usize) -> Ptr<Operation> {
+        let dest = self.operation().deref(ctx).successor(succ_idx);
+        let dest_opds = self.successor_operands(ctx, succ_idx);
+        BrOp::new(ctx, dest, dest_opds).operation()
+    }
+}
+
 /// A way to express whether a GEP index is a constant or an SSA value
 #[derive(Clone)]
 pub enum GepIndex {
@@ -699,14 +739,16 @@ pub enum GetElementPtrOpErr {
 #[format_op(
     "`<` attr($llvm_gep_src_elem_type, $TypeAttr) `>` ` (` operands(CharSpace(`,`)) `)` attr($llvm_gep_indices, $GepIndicesAttr) ` : ` type($0)"
 )]
-#[derive_op_interface_impl(OneResultInterface)]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
 pub struct GetElementPtrOp;
 
 #[op_interface_impl]
 impl PointerTypeResult for GetElementPtrOp {
-    fn result_pointee_type(&self, ctx: &Context) -> Ptr<TypeObj> {
-        Self::indexed_type(ctx, self.src_elem_type(ctx), &self.indices(ctx))
-            .expect("Invalid indices for GEP")
+    fn result_pointee_type(&self, ctx: &Context) -> Result<Ptr<TypeObj>> {
+        Self::indexed_type(ctx, self.src_elem_type(ctx), &self.indices(ctx)).map_err(|mut err| {
+            err.set_loc(self.loc(ctx));
+            err
+        })
     }
 }
 
@@ -774,10 +816,10 @@ impl GetElementPtrOp {
         let src_elem_type = TypeAttr::new(src_elem_type);
         op.deref_mut(ctx)
             .attributes
-            .set(gep_op::ATTR_KEY_INDICES.clone(), GepIndicesAttr(attr));
+            .set(*gep_op::ATTR_KEY_INDICES, GepIndicesAttr(attr));
         op.deref_mut(ctx)
             .attributes
-            .set(gep_op::ATTR_KEY_SRC_ELEM_TYPE.clone(), src_elem_type);
+            .set(*gep_op::ATTR_KEY_SRC_ELEM_TYPE, src_elem_type);
         Ok(GetElementPtrOp { op })
     }
 
@@ -846,10 +888,64 @@ impl GetElementPtrOp {
     }
 }
 
+/// Print the volatility and atomic ordering of a memory access,
+/// each preceded by a space, if they're specified.
+fn fmt_memory_access_flags(
+    access: &dyn MemoryAccessOpInterface,
+    ctx: &Context,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    if access.is_volatile(ctx) {
+        write!(f, " volatile")?;
+    }
+    if access.is_atomic(ctx) {
+        write!(f, " atomic {}", access.atomic_ordering(ctx).disp(ctx))?;
+    }
+    Ok(())
+}
+
+/// The [MemoryEffect]s of a memory access of `kinds` through its operand `addr_idx`.
+/// Accesses that aren't [simple](MemoryAccessOpInterface::is_simple) also read and
+/// write ambient state, so that they're neither removed nor reordered.
+fn memory_access_effects(
+    access: &dyn MemoryAccessOpInterface,
+    ctx: &Context,
+    addr_idx: usize,
+    kinds: &[MemoryEffectKind],
+) -> Vec<MemoryEffect> {
+    let mut effects: Vec<_> = kinds
+        .iter()
+        .map(|kind| MemoryEffect::new(*kind, MemoryEffectOn::Operand(addr_idx)))
+        .collect();
+    if !access.is_simple(ctx) {
+        effects.push(MemoryEffect::new(
+            MemoryEffectKind::Read,
+            MemoryEffectOn::Ambient,
+        ));
+        effects.push(MemoryEffect::new(
+            MemoryEffectKind::Write,
+            MemoryEffectOn::Ambient,
+        ));
+    }
+    effects
+}
+
+/// Parse the (optional) volatility and atomic ordering of a memory access,
+/// as printed by [fmt_memory_access_flags].
+fn memory_access_flags_parser<'a>()
+-> impl Parser<StateStream<'a>, Output = (bool, AtomicOrderingAttr)> {
+    let volatile = optional(attempt(spaced(string("volatile")))).map(|v| v.is_some());
+    let ordering = optional(attempt(spaced(string("atomic"))).with(AtomicOrderingAttr::parser(())))
+        .map(|ordering| ordering.unwrap_or(AtomicOrderingAttr::NotAtomic));
+    volatile.and(ordering)
+}
+
 #[derive(Error, Debug)]
 pub enum LoadOpVerifyErr {
     #[error("Load operand must be a pointer")]
     OperandTypeErr,
+    #[error("Load cannot have release or acquire-release ordering")]
+    OrderingErr,
 }
 
 /// Equivalent to LLVM's Load opcode.
@@ -866,8 +962,11 @@ pub enum LoadOpVerifyErr {
 ///
 /// ### Attributes:
 ///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_ATOMIC_ORDERING](crate::op_interfaces::ATTR_KEY_ATOMIC_ORDERING) | [AtomicOrderingAttr] | [MemoryAccessOpInterface] |
+/// | [ATTR_KEY_VOLATILE](crate::op_interfaces::ATTR_KEY_VOLATILE) | [UnitAttr] | [MemoryAccessOpInterface] |
 #[def_op("llvm.load")]
-#[format_op("$0 ` : ` type($0)")]
 #[derive_op_interface_impl(OneResultInterface, OneOpdInterface)]
 pub struct LoadOp;
 impl LoadOp {
@@ -879,23 +978,95 @@ impl LoadOp {
     }
 }
 
+#[op_interface_impl]
+impl MemoryAccessOpInterface for LoadOp {
+    fn address_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(0)
+    }
+}
+
+#[op_interface_impl]
+impl MemoryEffectOpInterface for LoadOp {
+    fn memory_effects(&self, ctx: &Context) -> Vec<MemoryEffect> {
+        memory_access_effects(self, ctx, 0, &[MemoryEffectKind::Read])
+    }
+}
+
 impl Verify for LoadOp {
     fn verify(&self, ctx: &Context) -> Result<()> {
         let loc = self.loc(ctx);
         // Ensure correctness of operand type.
-        if !self.operand_type(ctx).deref(ctx).is::<PointerType>() {
+        if !self.operand_type(ctx).isa::<PointerType>(ctx) {
             return verify_err!(loc, LoadOpVerifyErr::OperandTypeErr);
         }
+        if matches!(
+            self.atomic_ordering(ctx),
+            AtomicOrderingAttr::Release | AtomicOrderingAttr::AcqRel
+        ) {
+            return verify_err!(loc, LoadOpVerifyErr::OrderingErr);
+        }
         Ok(())
     }
 }
 
+impl Printable for LoadOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        _state: &pliron::printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {} {}",
+            results(self).disp(ctx),
+            self.opid(),
+            self.address_opd(ctx).disp(ctx)
+        )?;
+        fmt_memory_access_flags(self, ctx, f)?;
+        write!(f, " : {}", self.result_type(ctx).disp(ctx))
+    }
+}
+
+impl Parsable for LoadOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        results: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        if results.len() != 1 {
+            input_err!(
+                state_stream.loc(),
+                op_interfaces::OneResultVerifyErr(Self::opid_static().to_string())
+            )?
+        }
+
+        let ((ptr, (volatile, ordering)), res_ty) = ssa_opd_parser()
+            .and(memory_access_flags_parser())
+            .skip(spaced(token(':')))
+            .and(type_parser())
+            .parse_stream(state_stream)
+            .into_result()?
+            .0;
+
+        let ctx = &mut state_stream.state.ctx;
+        let op = LoadOp::new(ctx, ptr, res_ty);
+        op.set_volatile(ctx, volatile);
+        op.set_atomic_ordering(ctx, ordering);
+        process_parsed_ssa_defs(state_stream, &results, op.operation())?;
+        Ok(OpObj::new(op)).into_parse_result()
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum StoreOpVerifyErr {
     #[error("Store operand must have two operands")]
     NumOpdsErr,
     #[error("Store operand must have a pointer as its second argument")]
     AddrOpdTypeErr,
+    #[error("Store cannot have acquire or acquire-release ordering")]
+    OrderingErr,
 }
 
 /// Equivalent to LLVM's Store opcode.
@@ -907,8 +1078,11 @@ pub enum StoreOpVerifyErr {
 ///
 /// ### Attributes:
 ///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_ATOMIC_ORDERING](crate::op_interfaces::ATTR_KEY_ATOMIC_ORDERING) | [AtomicOrderingAttr] | [MemoryAccessOpInterface] |
+/// | [ATTR_KEY_VOLATILE](crate::op_interfaces::ATTR_KEY_VOLATILE) | [UnitAttr] | [MemoryAccessOpInterface] |
 #[def_op("llvm.store")]
-#[format_op("`*` $1 ` <- ` $0")]
 #[derive_op_interface_impl(ZeroResultInterface)]
 pub struct StoreOp;
 impl StoreOp {
@@ -930,13 +1104,22 @@ impl StoreOp {
     pub fn value_opd(&self, ctx: &Context) -> Value {
         self.op.deref(ctx).operand(0)
     }
+}
 
-    /// Get the address operand
-    pub fn address_opd(&self, ctx: &Context) -> Value {
+#[op_interface_impl]
+impl MemoryAccessOpInterface for StoreOp {
+    fn address_opd(&self, ctx: &Context) -> Value {
         self.op.deref(ctx).operand(1)
     }
 }
 
+#[op_interface_impl]
+impl MemoryEffectOpInterface for StoreOp {
+    fn memory_effects(&self, ctx: &Context) -> Vec<MemoryEffect> {
+        memory_access_effects(self, ctx, 1, &[MemoryEffectKind::Write])
+    }
+}
+
 impl Verify for StoreOp {
     fn verify(&self, ctx: &Context) -> Result<()> {
         let loc = self.loc(ctx);
@@ -948,117 +1131,1098 @@ impl Verify for StoreOp {
 
         use pliron::r#type::Typed;
         // Ensure correctness of the address operand.
-        if !op.operand(1).get_type(ctx).deref(ctx).is::<PointerType>() {
+        if !op.operand(1).get_type(ctx).isa::<PointerType>(ctx) {
             return verify_err!(loc, StoreOpVerifyErr::AddrOpdTypeErr);
         }
+        if matches!(
+            self.atomic_ordering(ctx),
+            AtomicOrderingAttr::Acquire | AtomicOrderingAttr::AcqRel
+        ) {
+            return verify_err!(loc, StoreOpVerifyErr::OrderingErr);
+        }
         Ok(())
     }
 }
 
-/// Equivalent to LLVM's Store opcode.
+impl Printable for StoreOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        _state: &pliron::printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{} *{} <- {}",
+            self.opid(),
+            self.address_opd(ctx).disp(ctx),
+            self.value_opd(ctx).disp(ctx)
+        )?;
+        fmt_memory_access_flags(self, ctx, f)
+    }
+}
+
+impl Parsable for StoreOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        results: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        if !results.is_empty() {
+            input_err!(
+                state_stream.loc(),
+                op_interfaces::ZeroResultVerifyErr(Self::opid_static().to_string())
+            )?
+        }
+
+        let ((ptr, value), (volatile, ordering)) = token('*')
+            .with(ssa_opd_parser())
+            .skip(spaced(string("<-")))
+            .and(ssa_opd_parser())
+            .and(memory_access_flags_parser())
+            .parse_stream(state_stream)
+            .into_result()?
+            .0;
+
+        let ctx = &mut state_stream.state.ctx;
+        let op = StoreOp::new(ctx, value, ptr);
+        op.set_volatile(ctx, volatile);
+        op.set_atomic_ordering(ctx, ordering);
+        Ok(OpObj::new(op)).into_parse_result()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AtomicRmwOpVerifyErr {
+    #[error("Atomic read-modify-write must be at least monotonic")]
+    OrderingErr,
+    #[error("Missing or incorrect operation attribute")]
+    BinOpAttrErr,
+    #[error("Value operand and result must be of the same integer type")]
+    TypeErr,
+}
+
+/// Equivalent to LLVM's AtomicRMW opcode: atomically read the value at
+/// `addr`, combine it with `val` and write the result back to `addr`.
+///
 /// ### Operands
 /// | operand | description |
 /// |-----|-------|
-/// | `callee_operands` | Optional function pointer followed by any number of parameters |
+/// | `addr` | [PointerType] |
+/// | `val` | Signless integer |
 ///
-////// ### Result(s):
+/// ### Result(s):
 ///
 /// | result | description |
 /// |-----|-------|
-/// | `res` | LLVM type |
+/// | `res` | The original value at `addr`, same type as `val` |
 ///
 /// ### Attributes:
+///
 /// | key | value | via Interface |
 /// |-----|-------| --------------|
-/// | [ATTR_KEY_CALLEE](call_op::ATTR_KEY_CALLEE) | [IdentifierAttr] | N/A |
-/// | [ATTR_KEY_CALLEE_TYPE](pliron::builtin::op_interfaces::ATTR_KEY_CALLEE_TYPE) | [TypeAttr] | [CallOpInterface] |
-///
-#[def_op("llvm.call")]
+/// | [ATTR_KEY_BIN_OP](atomic_rmw_op::ATTR_KEY_BIN_OP) | [AtomicRmwBinOpAttr] | N/A |
+/// | [ATTR_KEY_ATOMIC_ORDERING](crate::op_interfaces::ATTR_KEY_ATOMIC_ORDERING) | [AtomicOrderingAttr] | [MemoryAccessOpInterface] |
+/// | [ATTR_KEY_VOLATILE](crate::op_interfaces::ATTR_KEY_VOLATILE) | [UnitAttr] | [MemoryAccessOpInterface] |
+#[def_op("llvm.atomic_rmw")]
 #[derive_op_interface_impl(OneResultInterface)]
-pub struct CallOp;
+pub struct AtomicRmwOp;
 
-pub mod call_op {
+pub mod atomic_rmw_op {
     use std::sync::LazyLock;
 
     use super::*;
-    pub static ATTR_KEY_CALLEE: LazyLock<Identifier> =
-        LazyLock::new(|| "llvm_call_callee".try_into().unwrap());
+    pub static ATTR_KEY_BIN_OP: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_atomic_rmw_bin_op".try_into().unwrap());
 }
 
-impl CallOp {
-    /// Get a new [CallOp].
+impl AtomicRmwOp {
+    /// Create a new [AtomicRmwOp].
     pub fn new(
         ctx: &mut Context,
-        callee: CallOpCallable,
-        callee_ty: TypePtr<FunctionType>,
-        mut args: Vec<Value>,
+        bin_op: AtomicRmwBinOpAttr,
+        addr: Value,
+        val: Value,
+        ordering: AtomicOrderingAttr,
     ) -> Self {
-        let res_ty = callee_ty.deref(ctx).results()[0];
-        let op = match callee {
-            CallOpCallable::Direct(cval) => {
-                let op = Operation::new(ctx, Self::opid_static(), vec![res_ty], args, vec![], 0);
-                op.deref_mut(ctx)
-                    .attributes
-                    .set(call_op::ATTR_KEY_CALLEE.clone(), IdentifierAttr::new(cval));
-                op
-            }
-            CallOpCallable::Indirect(csym) => {
-                args.insert(0, csym);
-                Operation::new(ctx, Self::opid_static(), vec![res_ty], args, vec![], 0)
-            }
+        use pliron::r#type::Typed;
+        let res_ty = val.get_type(ctx);
+        let op = AtomicRmwOp {
+            op: Operation::new(
+                ctx,
+                Self::opid_static(),
+                vec![res_ty],
+                vec![addr, val],
+                vec![],
+                0,
+            ),
         };
-        op.deref_mut(ctx).attributes.set(
-            ATTR_KEY_CALLEE_TYPE.clone(),
-            TypeAttr::new(callee_ty.into()),
-        );
-        CallOp { op }
+        op.op
+            .deref_mut(ctx)
+            .attributes
+            .set(*atomic_rmw_op::ATTR_KEY_BIN_OP, bin_op);
+        op.set_atomic_ordering(ctx, ordering);
+        op
+    }
+
+    /// Get the operation performed.
+    pub fn bin_op(&self, ctx: &Context) -> AtomicRmwBinOpAttr {
+        *self
+            .op
+            .deref(ctx)
+            .attributes
+            .get::<AtomicRmwBinOpAttr>(&atomic_rmw_op::ATTR_KEY_BIN_OP)
+            .expect("AtomicRmwOp missing or incorrect operation attribute")
+    }
+
+    /// Get the value operand.
+    pub fn value_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(1)
     }
 }
 
-impl CallOpInterface for CallOp {
-    fn callee(&self, ctx: &Context) -> CallOpCallable {
-        let op = self.op.deref(ctx);
-        if let Some(callee_sym) = op
+#[op_interface_impl]
+impl MemoryAccessOpInterface for AtomicRmwOp {
+    fn address_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(0)
+    }
+}
+
+#[op_interface_impl]
+impl MemoryEffectOpInterface for AtomicRmwOp {
+    fn memory_effects(&self, ctx: &Context) -> Vec<MemoryEffect> {
+        memory_access_effects(
+            self,
+            ctx,
+            0,
+            &[MemoryEffectKind::Read, MemoryEffectKind::Write],
+        )
+    }
+}
+
+impl Verify for AtomicRmwOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        if self
+            .op
+            .deref(ctx)
             .attributes
-            .get::<IdentifierAttr>(&call_op::ATTR_KEY_CALLEE)
+            .get::<AtomicRmwBinOpAttr>(&atomic_rmw_op::ATTR_KEY_BIN_OP)
+            .is_none()
         {
-            CallOpCallable::Direct(callee_sym.clone().into())
-        } else {
-            assert!(
-                op.num_operands() > 0,
-                "Indirect call must have function pointer operand"
-            );
-            CallOpCallable::Indirect(op.operand(0))
+            return verify_err!(loc, AtomicRmwOpVerifyErr::BinOpAttrErr);
         }
+        if matches!(
+            self.atomic_ordering(ctx),
+            AtomicOrderingAttr::NotAtomic | AtomicOrderingAttr::Unordered
+        ) {
+            return verify_err!(loc, AtomicRmwOpVerifyErr::OrderingErr);
+        }
+
+        use pliron::r#type::Typed;
+        let val_ty = self.value_opd(ctx).get_type(ctx);
+        if val_ty != self.result_type(ctx) || !val_ty.isa::<IntegerType>(ctx) {
+            return verify_err!(loc, AtomicRmwOpVerifyErr::TypeErr);
+        }
+        Ok(())
     }
+}
 
-    fn args(&self, ctx: &Context) -> Vec<Value> {
-        let op = self.op.deref(ctx);
-        // If this is an indirect call, the first operand is the callee value.
-        let skip = if matches!(self.callee(ctx), CallOpCallable::Direct(_)) {
-            0
-        } else {
-            1
-        };
-        op.operands().skip(skip).collect()
+impl Printable for AtomicRmwOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        _state: &pliron::printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {} {} {}, {}",
+            results(self).disp(ctx),
+            self.opid(),
+            self.bin_op(ctx).disp(ctx),
+            self.address_opd(ctx).disp(ctx),
+            self.value_opd(ctx).disp(ctx),
+        )?;
+        fmt_memory_access_flags(self, ctx, f)?;
+        write!(f, " : {}", self.result_type(ctx).disp(ctx))
     }
 }
-impl_canonical_syntax!(CallOp);
-impl_verify_succ!(CallOp);
 
-/// Undefined value of a type.
-/// See MLIR's [llvm.mlir.undef](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmlirundef-llvmundefop).
+impl Parsable for AtomicRmwOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        results: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        if results.len() != 1 {
+            input_err!(
+                state_stream.loc(),
+                op_interfaces::OneResultVerifyErr(Self::opid_static().to_string())
+            )?
+        }
+
+        let ((((bin_op, ptr), val), (volatile, ordering)), res_ty) =
+            spaced(AtomicRmwBinOpAttr::parser(()))
+                .and(ssa_opd_parser())
+                .and(spaced(token(',')).with(ssa_opd_parser()))
+                .and(memory_access_flags_parser())
+                .skip(spaced(token(':')))
+                .and(type_parser())
+                .parse_stream(state_stream)
+                .into_result()?
+                .0;
+
+        let ctx = &mut state_stream.state.ctx;
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            vec![ptr, val],
+            vec![],
+            0,
+        );
+        let op = AtomicRmwOp { op };
+        op.op
+            .deref_mut(ctx)
+            .attributes
+            .set(*atomic_rmw_op::ATTR_KEY_BIN_OP, bin_op);
+        op.set_volatile(ctx, volatile);
+        op.set_atomic_ordering(ctx, ordering);
+        process_parsed_ssa_defs(state_stream, &results, op.operation())?;
+        Ok(OpObj::new(op)).into_parse_result()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AtomicCmpXchgOpVerifyErr {
+    #[error("Compare-exchange orderings must be at least monotonic")]
+    OrderingErr,
+    #[error("Compare-exchange failure ordering cannot be release or acquire-release")]
+    FailureOrderingErr,
+    #[error("Missing or incorrect failure ordering attribute")]
+    FailureOrderingAttrErr,
+    #[error("Compared and new values must be of the same type, and result must be {{type, i1}}")]
+    TypeErr,
+}
+
+/// Equivalent to LLVM's AtomicCmpXchg opcode: atomically read the value at
+/// `addr`, compare it with `cmp` and if equal, write `new` to `addr`.
 ///
-/// Results:
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `addr` | [PointerType] |
+/// | `cmp` | Integer or pointer |
+/// | `new` | Same type as `cmp` |
+///
+/// ### Result(s):
 ///
 /// | result | description |
 /// |-----|-------|
-/// | `result` | any type |
-#[def_op("llvm.undef")]
+/// | `res` | [StructType] of the original value at `addr` and a 1-bit success flag |
+///
+/// ### Attributes:
+///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_ATOMIC_ORDERING](crate::op_interfaces::ATTR_KEY_ATOMIC_ORDERING) | [AtomicOrderingAttr], ordering on success | [MemoryAccessOpInterface] |
+/// | [ATTR_KEY_FAILURE_ORDERING](atomic_cmp_xchg_op::ATTR_KEY_FAILURE_ORDERING) | [AtomicOrderingAttr] | N/A |
+/// | [ATTR_KEY_VOLATILE](crate::op_interfaces::ATTR_KEY_VOLATILE) | [UnitAttr] | [MemoryAccessOpInterface] |
+#[def_op("llvm.cmpxchg")]
 #[derive_op_interface_impl(OneResultInterface)]
-pub struct UndefOp;
-impl_canonical_syntax!(UndefOp);
-impl_verify_succ!(UndefOp);
+pub struct AtomicCmpXchgOp;
+
+pub mod atomic_cmp_xchg_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    pub static ATTR_KEY_FAILURE_ORDERING: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_cmpxchg_failure_ordering".try_into().unwrap());
+}
+
+impl AtomicCmpXchgOp {
+    /// Create a new [AtomicCmpXchgOp].
+    pub fn new(
+        ctx: &mut Context,
+        addr: Value,
+        cmp: Value,
+        new: Value,
+        success_ordering: AtomicOrderingAttr,
+        failure_ordering: AtomicOrderingAttr,
+    ) -> Self {
+        use pliron::r#type::Typed;
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless).into();
+        let res_ty = StructType::get_unnamed(ctx, vec![cmp.get_type(ctx), i1_ty]).into();
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            vec![addr, cmp, new],
+            vec![],
+            0,
+        );
+        let op = AtomicCmpXchgOp { op };
+        op.set_atomic_ordering(ctx, success_ordering);
+        op.set_failure_ordering(ctx, failure_ordering);
+        op
+    }
+
+    /// Get the value compared against.
+    pub fn cmp_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(1)
+    }
+
+    /// Get the value written on success.
+    pub fn new_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(2)
+    }
+
+    /// Atomic ordering when the comparison fails.
+    pub fn failure_ordering(&self, ctx: &Context) -> AtomicOrderingAttr {
+        *self
+            .op
+            .deref(ctx)
+            .attributes
+            .get::<AtomicOrderingAttr>(&atomic_cmp_xchg_op::ATTR_KEY_FAILURE_ORDERING)
+            .expect("AtomicCmpXchgOp missing or incorrect failure ordering attribute")
+    }
+
+    /// Set the atomic ordering when the comparison fails.
+    pub fn set_failure_ordering(&self, ctx: &Context, ordering: AtomicOrderingAttr) {
+        self.op
+            .deref_mut(ctx)
+            .attributes
+            .set(*atomic_cmp_xchg_op::ATTR_KEY_FAILURE_ORDERING, ordering);
+    }
+}
+
+#[op_interface_impl]
+impl MemoryAccessOpInterface for AtomicCmpXchgOp {
+    fn address_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(0)
+    }
+}
+
+#[op_interface_impl]
+impl MemoryEffectOpInterface for AtomicCmpXchgOp {
+    fn memory_effects(&self, ctx: &Context) -> Vec<MemoryEffect> {
+        memory_access_effects(
+            self,
+            ctx,
+            0,
+            &[MemoryEffectKind::Read, MemoryEffectKind::Write],
+        )
+    }
+}
+
+impl Verify for AtomicCmpXchgOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        if self
+            .op
+            .deref(ctx)
+            .attributes
+            .get::<AtomicOrderingAttr>(&atomic_cmp_xchg_op::ATTR_KEY_FAILURE_ORDERING)
+            .is_none()
+        {
+            return verify_err!(loc, AtomicCmpXchgOpVerifyErr::FailureOrderingAttrErr);
+        }
+        let (success, failure) = (self.atomic_ordering(ctx), self.failure_ordering(ctx));
+        let at_least_monotonic = |ordering| {
+            !matches!(
+                ordering,
+                AtomicOrderingAttr::NotAtomic | AtomicOrderingAttr::Unordered
+            )
+        };
+        if !at_least_monotonic(success) || !at_least_monotonic(failure) {
+            return verify_err!(loc, AtomicCmpXchgOpVerifyErr::OrderingErr);
+        }
+        if matches!(
+            failure,
+            AtomicOrderingAttr::Release | AtomicOrderingAttr::AcqRel
+        ) {
+            return verify_err!(loc, AtomicCmpXchgOpVerifyErr::FailureOrderingErr);
+        }
+
+        use pliron::r#type::Typed;
+        let cmp_ty = self.cmp_opd(ctx).get_type(ctx);
+        let res_ty = self.result_type(ctx);
+        let res_ok = res_ty
+            .deref(ctx)
+            .downcast_ref::<StructType>()
+            .is_some_and(|st| {
+                !st.is_opaque()
+                    && st.num_fields() == 2
+                    && st.field_type(0) == cmp_ty
+                    && st
+                        .field_type(1)
+                        .deref(ctx)
+                        .downcast_ref::<IntegerType>()
+                        .is_some_and(|int_ty| int_ty.width() == 1)
+            });
+        if cmp_ty != self.new_opd(ctx).get_type(ctx) || !res_ok {
+            return verify_err!(loc, AtomicCmpXchgOpVerifyErr::TypeErr);
+        }
+        Ok(())
+    }
+}
+
+impl Printable for AtomicCmpXchgOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        _state: &pliron::printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {} {}, {}, {}",
+            results(self).disp(ctx),
+            self.opid(),
+            self.address_opd(ctx).disp(ctx),
+            self.cmp_opd(ctx).disp(ctx),
+            self.new_opd(ctx).disp(ctx),
+        )?;
+        fmt_memory_access_flags(self, ctx, f)?;
+        write!(
+            f,
+            " {} : {}",
+            self.failure_ordering(ctx).disp(ctx),
+            self.result_type(ctx).disp(ctx)
+        )
+    }
+}
+
+impl Parsable for AtomicCmpXchgOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        results: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        if results.len() != 1 {
+            input_err!(
+                state_stream.loc(),
+                op_interfaces::OneResultVerifyErr(Self::opid_static().to_string())
+            )?
+        }
+
+        let (((((ptr, cmp), new), (volatile, ordering)), failure_ordering), res_ty) =
+            ssa_opd_parser()
+                .and(spaced(token(',')).with(ssa_opd_parser()))
+                .and(spaced(token(',')).with(ssa_opd_parser()))
+                .and(memory_access_flags_parser())
+                .and(spaced(AtomicOrderingAttr::parser(())))
+                .skip(spaced(token(':')))
+                .and(type_parser())
+                .parse_stream(state_stream)
+                .into_result()?
+                .0;
+
+        let ctx = &mut state_stream.state.ctx;
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            vec![ptr, cmp, new],
+            vec![],
+            0,
+        );
+        let op = AtomicCmpXchgOp { op };
+        op.set_volatile(ctx, volatile);
+        op.set_atomic_ordering(ctx, ordering);
+        op.set_failure_ordering(ctx, failure_ordering);
+        process_parsed_ssa_defs(state_stream, &results, op.operation())?;
+        Ok(OpObj::new(op)).into_parse_result()
+    }
+}
+
+/// Equivalent to LLVM's Store opcode.
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `callee_operands` | Optional function pointer followed by any number of parameters |
+/// | `bundle_operands` | Operands of the [operand bundles](OperandBundleInterface), if any |
+///
+////// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | LLVM type |
+///
+/// ### Attributes:
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_CALLEE](call_op::ATTR_KEY_CALLEE) | [IdentifierAttr] | N/A |
+/// | [ATTR_KEY_CALLEE_TYPE](pliron::builtin::op_interfaces::ATTR_KEY_CALLEE_TYPE) | [TypeAttr] | [CallOpInterface] |
+/// | [ATTR_KEY_OPERAND_BUNDLES](pliron::builtin::op_interfaces::ATTR_KEY_OPERAND_BUNDLES) | [OperandBundlesAttr](pliron::builtin::attributes::OperandBundlesAttr) | [OperandBundleInterface] |
+///
+#[def_op("llvm.call")]
+#[derive_op_interface_impl(OneResultInterface)]
+pub struct CallOp;
+
+pub mod call_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    pub static ATTR_KEY_CALLEE: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_call_callee".try_into().unwrap());
+}
+
+impl CallOp {
+    /// Get a new [CallOp].
+    pub fn new(
+        ctx: &mut Context,
+        callee: CallOpCallable,
+        callee_ty: TypePtr<FunctionType>,
+        mut args: Vec<Value>,
+    ) -> Self {
+        let res_ty = callee_ty.deref(ctx).results()[0];
+        let op = match callee {
+            CallOpCallable::Direct(cval) => {
+                let op = Operation::new(ctx, Self::opid_static(), vec![res_ty], args, vec![], 0);
+                op.deref_mut(ctx)
+                    .attributes
+                    .set(*call_op::ATTR_KEY_CALLEE, IdentifierAttr::new(cval));
+                op
+            }
+            CallOpCallable::Indirect(csym) => {
+                args.insert(0, csym);
+                Operation::new(ctx, Self::opid_static(), vec![res_ty], args, vec![], 0)
+            }
+        };
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_CALLEE_TYPE, TypeAttr::new(callee_ty.into()));
+        CallOp { op }
+    }
+}
+
+impl CallOpInterface for CallOp {
+    fn callee(&self, ctx: &Context) -> CallOpCallable {
+        let op = self.op.deref(ctx);
+        if let Some(callee_sym) = op
+            .attributes
+            .get::<IdentifierAttr>(&call_op::ATTR_KEY_CALLEE)
+        {
+            CallOpCallable::Direct(callee_sym.clone().into())
+        } else {
+            assert!(
+                op.num_operands() > 0,
+                "Indirect call must have function pointer operand"
+            );
+            CallOpCallable::Indirect(op.operand(0))
+        }
+    }
+
+    fn args(&self, ctx: &Context) -> Vec<Value> {
+        let op = self.op.deref(ctx);
+        // If this is an indirect call, the first operand is the callee value.
+        let skip = if matches!(self.callee(ctx), CallOpCallable::Direct(_)) {
+            0
+        } else {
+            1
+        };
+        let num_opds = op.num_operands() - self.num_bundle_operands(ctx);
+        op.operands().take(num_opds).skip(skip).collect()
+    }
+
+    fn set_args(&self, ctx: &mut Context, args: Vec<Value>) {
+        let num_args = self.args(ctx).len();
+        let bundles = self.operand_bundles(ctx);
+        let mut operands = self.non_bundle_operands(ctx);
+        operands.truncate(operands.len() - num_args);
+        operands.extend(args);
+        operands.extend(bundles.into_iter().flat_map(|bundle| bundle.operands));
+        Operation::set_operands(self.op, ctx, operands);
+    }
+}
+
+#[op_interface_impl]
+impl OperandBundleInterface for CallOp {}
+
+#[op_interface_impl]
+impl SymbolUserOpInterface for CallOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        match self.callee(ctx) {
+            CallOpCallable::Direct(callee_sym) => vec![callee_sym],
+            CallOpCallable::Indirect(_) => vec![],
+        }
+    }
+
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if matches!(self.callee(ctx), CallOpCallable::Direct(callee_sym) if &callee_sym == from) {
+            self.op
+                .deref_mut(ctx)
+                .attributes
+                .set(*call_op::ATTR_KEY_CALLEE, IdentifierAttr::new(*to));
+        }
+    }
+}
+
+impl Printable for CallOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &pliron::printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{} = {} ", results(self).disp(ctx), self.opid())?;
+        call_like(self, std::slice::from_ref(&call_op::ATTR_KEY_CALLEE)).fmt(ctx, state, f)
+    }
+}
+
+impl Parsable for CallOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        results: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        let loc = state_stream.loc();
+        if results.len() != 1 {
+            input_err!(
+                loc.clone(),
+                op_interfaces::OneResultVerifyErr(Self::opid_static().to_string())
+            )?
+        }
+
+        call_like_parse(state_stream, results, |ctx, callee, callee_ty, args| {
+            if callee_ty.deref(ctx).results().len() != 1 {
+                input_err!(
+                    loc,
+                    op_interfaces::OneResultVerifyErr(Self::opid_static().to_string())
+                )?
+            }
+            Ok(OpObj::new(CallOp::new(ctx, callee, callee_ty, args)))
+        })
+    }
+}
+
+impl_verify_succ!(CallOp);
+
+#[derive(Error, Debug)]
+pub enum InvokeOpVerifyErr {
+    #[error("Invoke has {provided} operands, but its callee and successors need {expected}")]
+    NumOperands { provided: usize, expected: usize },
+    #[error("Unwind destination of an invoke must start with a landing pad")]
+    UnwindDestNotLandingPad,
+    #[error("Result of an invoke is not available in its unwind destination")]
+    ResultUsedInUnwindDest,
+}
+
+/// Equivalent to LLVM's Invoke opcode: call a function, and then
+/// branch to `normal_dest` if the callee returns normally, or to
+/// `unwind_dest` if the callee unwinds (for example, throws an exception).
+///
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `callee_operands` | Optional function pointer followed by any number of parameters |
+/// | `normal_dest_opds` | Any number of operands with any LLVM type |
+/// | `unwind_dest_opds` | Any number of operands with any LLVM type |
+/// | `bundle_operands` | Operands of the [operand bundles](OperandBundleInterface), if any |
+///
+/// ### Successors:
+///
+/// | Successor | description |
+/// |-----|-------|
+/// | `normal_dest` | Any successor |
+/// | `unwind_dest` | Successor starting with a [LandingPadOp] |
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | LLVM type, available only in `normal_dest` |
+///
+/// ### Attributes:
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_CALLEE](invoke_op::ATTR_KEY_CALLEE) | [IdentifierAttr] | N/A |
+/// | [ATTR_KEY_CALLEE_TYPE](pliron::builtin::op_interfaces::ATTR_KEY_CALLEE_TYPE) | [TypeAttr] | [CallOpInterface] |
+/// | [ATTR_KEY_OPERAND_BUNDLES](pliron::builtin::op_interfaces::ATTR_KEY_OPERAND_BUNDLES) | [OperandBundlesAttr](pliron::builtin::attributes::OperandBundlesAttr) | [OperandBundleInterface] |
+///
+#[def_op("llvm.invoke")]
+#[derive_op_interface_impl(IsTerminatorInterface, OneResultInterface)]
+pub struct InvokeOp;
+
+pub mod invoke_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    pub static ATTR_KEY_CALLEE: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_invoke_callee".try_into().unwrap());
+}
+
+impl InvokeOp {
+    /// Get a new [InvokeOp]. `normal_dest` and `unwind_dest` specify
+    /// the successors along with the operands passed to them.
+    pub fn new(
+        ctx: &mut Context,
+        callee: CallOpCallable,
+        callee_ty: TypePtr<FunctionType>,
+        args: Vec<Value>,
+        normal_dest: (Ptr<BasicBlock>, Vec<Value>),
+        unwind_dest: (Ptr<BasicBlock>, Vec<Value>),
+    ) -> Self {
+        let res_ty = callee_ty.deref(ctx).results()[0];
+        let (normal_dest, normal_dest_opds) = normal_dest;
+        let (unwind_dest, unwind_dest_opds) = unwind_dest;
+        let mut operands = match &callee {
+            CallOpCallable::Direct(_) => vec![],
+            CallOpCallable::Indirect(csym) => vec![*csym],
+        };
+        operands.extend(args);
+        operands.extend(normal_dest_opds);
+        operands.extend(unwind_dest_opds);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            operands,
+            vec![normal_dest, unwind_dest],
+            0,
+        );
+        if let CallOpCallable::Direct(cval) = callee {
+            op.deref_mut(ctx)
+                .attributes
+                .set(*invoke_op::ATTR_KEY_CALLEE, IdentifierAttr::new(cval));
+        }
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_CALLEE_TYPE, TypeAttr::new(callee_ty.into()));
+        InvokeOp { op }
+    }
+
+    /// The block that control reaches if the callee returns normally.
+    pub fn normal_dest(&self, ctx: &Context) -> Ptr<BasicBlock> {
+        self.op.deref(ctx).successor(0)
+    }
+
+    /// The block that control reaches if the callee unwinds.
+    pub fn unwind_dest(&self, ctx: &Context) -> Ptr<BasicBlock> {
+        self.op.deref(ctx).successor(1)
+    }
+
+    /// Number of operands that precede the successor operands:
+    /// the optional function pointer and the arguments.
+    fn num_callee_operands(&self, ctx: &Context) -> usize {
+        let fn_ptr = if self
+            .op
+            .deref(ctx)
+            .attributes
+            .get::<IdentifierAttr>(&invoke_op::ATTR_KEY_CALLEE)
+            .is_some()
+        {
+            0
+        } else {
+            1
+        };
+        fn_ptr + self.callee_type(ctx).deref(ctx).inputs().len()
+    }
+}
+
+#[op_interface_impl]
+impl CallOpInterface for InvokeOp {
+    fn callee(&self, ctx: &Context) -> CallOpCallable {
+        let op = self.op.deref(ctx);
+        if let Some(callee_sym) = op
+            .attributes
+            .get::<IdentifierAttr>(&invoke_op::ATTR_KEY_CALLEE)
+        {
+            CallOpCallable::Direct(callee_sym.clone().into())
+        } else {
+            assert!(
+                op.num_operands() > 0,
+                "Indirect invoke must have function pointer operand"
+            );
+            CallOpCallable::Indirect(op.operand(0))
+        }
+    }
+
+    fn args(&self, ctx: &Context) -> Vec<Value> {
+        let num_callee_opds = self.num_callee_operands(ctx);
+        // If this is an indirect invoke, the first operand is the callee value.
+        let skip = if matches!(self.callee(ctx), CallOpCallable::Direct(_)) {
+            0
+        } else {
+            1
+        };
+        self.op
+            .deref(ctx)
+            .operands()
+            .take(num_callee_opds)
+            .skip(skip)
+            .collect()
+    }
+
+    fn set_args(&self, ctx: &mut Context, args: Vec<Value>) {
+        let num_callee_opds = self.num_callee_operands(ctx);
+        let num_args = self.args(ctx).len();
+        let mut operands: Vec<_> = self.op.deref(ctx).operands().collect();
+        operands.splice(num_callee_opds - num_args..num_callee_opds, args);
+        Operation::set_operands(self.op, ctx, operands);
+    }
+}
+
+#[op_interface_impl]
+impl BranchOpInterface for InvokeOp {
+    fn successor_operands(&self, ctx: &Context, succ_idx: usize) -> Vec<Value> {
+        assert!(
+            succ_idx == 0 || succ_idx == 1,
+            "InvokeOp has exactly two successors"
+        );
+        let op = self.op.deref(ctx);
+        let num_opds_succ0 = op.successor(0).deref(ctx).num_arguments();
+        // Skip the callee operands. The normal destination's operands follow,
+        // and then the unwind destination's operands.
+        let opds = op.operands().skip(self.num_callee_operands(ctx));
+        if succ_idx == 0 {
+            opds.take(num_opds_succ0).collect()
+        } else {
+            let num_opds_succ1 = op.successor(1).deref(ctx).num_arguments();
+            opds.skip(num_opds_succ0).take(num_opds_succ1).collect()
+        }
+    }
+}
+
+#[op_interface_impl]
+impl OperandBundleInterface for InvokeOp {}
+
+#[op_interface_impl]
+impl SymbolUserOpInterface for InvokeOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        match self.callee(ctx) {
+            CallOpCallable::Direct(callee_sym) => vec![callee_sym],
+            CallOpCallable::Indirect(_) => vec![],
+        }
+    }
+
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if matches!(self.callee(ctx), CallOpCallable::Direct(callee_sym) if &callee_sym == from) {
+            self.op
+                .deref_mut(ctx)
+                .attributes
+                .set(*invoke_op::ATTR_KEY_CALLEE, IdentifierAttr::new(*to));
+        }
+    }
+}
+impl_canonical_syntax!(InvokeOp);
+
+impl Verify for InvokeOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        let op = &*self.op.deref(ctx);
+        let expected = self.num_callee_operands(ctx)
+            + op.successor(0).deref(ctx).num_arguments()
+            + op.successor(1).deref(ctx).num_arguments()
+            + self.num_bundle_operands(ctx);
+        if op.num_operands() != expected {
+            return verify_err!(
+                loc,
+                InvokeOpVerifyErr::NumOperands {
+                    provided: op.num_operands(),
+                    expected
+                }
+            );
+        }
+
+        let unwind_dest_head = op.successor(1).deref(ctx).head();
+        if !unwind_dest_head.is_some_and(|head| Operation::op(head, ctx).is::<LandingPadOp>()) {
+            return verify_err!(loc, InvokeOpVerifyErr::UnwindDestNotLandingPad);
+        }
+
+        if self.successor_operands(ctx, 1).contains(&op.result(0)) {
+            return verify_err!(loc, InvokeOpVerifyErr::ResultUsedInUnwindDest);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LandingPadOpVerifyErr {
+    #[error("Landing pad must either be a cleanup or have at least one clause")]
+    NoClauses,
+    #[error("Landing pad must be the first operation in its block")]
+    NotFirstInBlock,
+    #[error("Landing pad block can only be reached via the unwind edge of an invoke")]
+    NonUnwindPred,
+    #[error("Function containing a landing pad must specify a personality function")]
+    MissingPersonality,
+}
+
+/// Equivalent to LLVM's LandingPad opcode. Must be the first
+/// operation in the unwind destination of an [InvokeOp].
+///
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `clauses` | Any number of catch or filter clauses |
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | LLVM type, as expected by the personality function |
+///
+/// ### Attributes:
+///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_CLEANUP](landing_pad_op::ATTR_KEY_CLEANUP) | [UnitAttr], if this is a cleanup landing pad | N/A |
+///
+/// The [FuncOp](pliron::builtin::ops::FuncOp) that contains a landing pad must specify
+/// its personality function, in an [IdentifierAttr] with key
+/// [ATTR_KEY_PERSONALITY](landing_pad_op::ATTR_KEY_PERSONALITY).
+#[def_op("llvm.landingpad")]
+#[derive_op_interface_impl(OneResultInterface)]
+pub struct LandingPadOp;
+
+pub mod landing_pad_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    pub static ATTR_KEY_CLEANUP: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_landingpad_cleanup".try_into().unwrap());
+    pub static ATTR_KEY_PERSONALITY: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_personality".try_into().unwrap());
+}
+
+impl LandingPadOp {
+    /// Create a new [LandingPadOp].
+    pub fn new(
+        ctx: &mut Context,
+        res_ty: Ptr<TypeObj>,
+        cleanup: bool,
+        clauses: Vec<Value>,
+    ) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![res_ty], clauses, vec![], 0);
+        if cleanup {
+            op.deref_mut(ctx)
+                .attributes
+                .set(*landing_pad_op::ATTR_KEY_CLEANUP, UnitAttr::new());
+        }
+        LandingPadOp { op }
+    }
+
+    /// Is this a cleanup landing pad?
+    pub fn is_cleanup(&self, ctx: &Context) -> bool {
+        self.op
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&landing_pad_op::ATTR_KEY_CLEANUP)
+            .is_some()
+    }
+
+    /// Get the catch or filter clauses of this landing pad.
+    pub fn clauses(&self, ctx: &Context) -> Vec<Value> {
+        self.op.deref(ctx).operands().collect()
+    }
+}
+impl_canonical_syntax!(LandingPadOp);
+
+impl Verify for LandingPadOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        if !self.is_cleanup(ctx) && self.clauses(ctx).is_empty() {
+            return verify_err!(loc, LandingPadOpVerifyErr::NoClauses);
+        }
+
+        let Some(block) = self.op.deref(ctx).container() else {
+            return Ok(());
+        };
+        if block.deref(ctx).head() != Some(self.op) {
+            return verify_err!(loc, LandingPadOpVerifyErr::NotFirstInBlock);
+        }
+
+        // Every predecessor must reach us only via an unwind edge.
+        for pred in block.preds(ctx) {
+            let is_unwind_edge = pred.deref(ctx).tail().is_some_and(|term| {
+                Operation::op(term, ctx)
+                    .downcast_ref::<InvokeOp>()
+                    .is_some_and(|invoke| {
+                        invoke.unwind_dest(ctx) == block && invoke.normal_dest(ctx) != block
+                    })
+            });
+            if !is_unwind_edge {
+                return verify_err!(loc, LandingPadOpVerifyErr::NonUnwindPred);
+            }
+        }
+
+        let missing_personality = block.deref(ctx).container().is_some_and(|region| {
+            region
+                .deref(ctx)
+                .parent_op()
+                .deref(ctx)
+                .attributes
+                .get::<IdentifierAttr>(&landing_pad_op::ATTR_KEY_PERSONALITY)
+                .is_none()
+        });
+        if missing_personality {
+            return verify_err!(loc, LandingPadOpVerifyErr::MissingPersonality);
+        }
+        Ok(())
+    }
+}
+
+/// Equivalent to LLVM's Resume opcode: resume propagation
+/// of an exception whose unwinding was interrupted by a [LandingPadOp].
+///
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `value` | Value produced by a [LandingPadOp] |
+#[def_op("llvm.resume")]
+#[format_op("$0")]
+#[derive_op_interface_impl(IsTerminatorInterface, ZeroResultInterface, OneOpdInterface)]
+pub struct ResumeOp;
+impl_verify_succ!(ResumeOp);
+
+impl ResumeOp {
+    /// Create a new [ResumeOp].
+    pub fn new(ctx: &mut Context, value: Value) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![value], vec![], 0);
+        ResumeOp { op }
+    }
+}
+
+/// Equivalent to LLVM's Unreachable opcode: control never reaches here.
+/// See [UnreachableInterface].
+#[def_op("llvm.unreachable")]
+#[format_op("")]
+#[derive_op_interface_impl(
+    IsTerminatorInterface,
+    UnreachableInterface,
+    ZeroResultInterface,
+    ZeroOpdInterface
+)]
+pub struct UnreachableOp;
+impl_verify_succ!(UnreachableOp);
+
+impl UnreachableOp {
+    /// Create a new [UnreachableOp].
+    pub fn new(ctx: &mut Context) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        UnreachableOp { op }
+    }
+}
+
+/// Undefined value of a type.
+/// See MLIR's [llvm.mlir.undef](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmlirundef-llvmundefop).
+///
+/// Results:
+///
+/// | result | description |
+/// |-----|-------|
+/// | `result` | any type, other than void or function |
+#[def_op("llvm.undef")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface, PureInterface)]
+pub struct UndefOp;
+impl_canonical_syntax!(UndefOp);
 
 impl UndefOp {
     /// Create a new [UndefOp].
@@ -1068,6 +2232,59 @@ impl UndefOp {
     }
 }
 
+#[op_interface_impl]
+impl ConstantLikeInterface for UndefOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        UndefAttr::new(self.result_type(ctx)).into()
+    }
+}
+
+impl Verify for UndefOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        verify_undef_poison_type(ctx, self.result_type(ctx), "Undef").map_err(|mut err| {
+            err.set_loc(self.loc(ctx));
+            err
+        })
+    }
+}
+
+/// Poison value of a type.
+/// See MLIR's [llvm.mlir.poison](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmlirpoison-llvmpoisonop).
+///
+/// Results:
+///
+/// | result | description |
+/// |-----|-------|
+/// | `result` | any type, other than void or function |
+#[def_op("llvm.poison")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface, PureInterface)]
+pub struct PoisonOp;
+impl_canonical_syntax!(PoisonOp);
+
+impl PoisonOp {
+    /// Create a new [PoisonOp].
+    pub fn new(ctx: &mut Context, result_ty: Ptr<TypeObj>) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![result_ty], vec![], vec![], 0);
+        PoisonOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl ConstantLikeInterface for PoisonOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        PoisonAttr::new(self.result_type(ctx)).into()
+    }
+}
+
+impl Verify for PoisonOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        verify_undef_poison_type(ctx, self.result_type(ctx), "Poison").map_err(|mut err| {
+            err.set_loc(self.loc(ctx));
+            err
+        })
+    }
+}
+
 /// Numeric constant.
 /// See MLIR's [llvm.mlir.constant](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmlirconstant-llvmconstantop).
 ///
@@ -1075,74 +2292,362 @@ impl UndefOp {
 ///
 /// | key | value |
 /// |-----|-------|
-/// |[ATTR_KEY_VALUE](constant_op::ATTR_KEY_VALUE) | [IntegerAttr] or [FloatAttr] |
+/// |[ATTR_KEY_VALUE](constant_op::ATTR_KEY_VALUE) | [IntegerAttr], [FloatAttr] or [ConstantVectorAttr] |
+///
+/// Results:
+///
+/// | result | description |
+/// |-----|-------|
+/// | `result` | any type |
+#[def_op("llvm.constant")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface, PureInterface)]
+pub struct ConstantOp;
+
+pub mod constant_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    /// Attribute key for the constant value.
+    pub static ATTR_KEY_VALUE: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_constant_value".try_into().unwrap());
+}
+
+#[op_interface_impl]
+impl ConstantLikeInterface for ConstantOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        self.get_value(ctx)
+    }
+}
+
+impl ConstantOp {
+    /// Get the constant value that this Op defines.
+    pub fn get_value(&self, ctx: &Context) -> AttrObj {
+        let op = self.operation().deref(ctx);
+        op.attributes
+            .0
+            .get(&constant_op::ATTR_KEY_VALUE)
+            .unwrap()
+            .clone()
+    }
+
+    /// Create a new [ConstantOp].
+    pub fn new(ctx: &mut Context, value: AttrObj) -> Self {
+        let result_type = attr_cast::<dyn TypedAttrInterface>(&*value)
+            .expect("ConstantOp const value must provide TypedAttrInterface")
+            .get_type();
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![result_type],
+            vec![],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .0
+            .insert(*constant_op::ATTR_KEY_VALUE, value);
+        ConstantOp { op }
+    }
+
+    /// Create an `i64` [ConstantOp] with the size, in bytes, of `ty`,
+    /// as per the [DataLayout] of (the module enclosing) `anchor`.
+    /// Fails if `ty` has no size. See [size_of].
+    pub fn new_size_of(
+        ctx: &mut Context,
+        ty: Ptr<TypeObj>,
+        anchor: Ptr<Operation>,
+    ) -> Result<Self> {
+        let size = size_of(ctx, ty, &DataLayout::of(ctx, anchor)?)?;
+        Ok(Self::new_i64(ctx, size))
+    }
+
+    /// Create an `i64` [ConstantOp] with the alignment, in bytes, of `ty`,
+    /// as per the [DataLayout] of (the module enclosing) `anchor`.
+    /// Fails if `ty` has no size. See [align_of].
+    pub fn new_align_of(
+        ctx: &mut Context,
+        ty: Ptr<TypeObj>,
+        anchor: Ptr<Operation>,
+    ) -> Result<Self> {
+        let align = align_of(ctx, ty, &DataLayout::of(ctx, anchor)?)?;
+        Ok(Self::new_i64(ctx, align))
+    }
+
+    fn new_i64(ctx: &mut Context, value: u64) -> Self {
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless);
+        let value = IntegerAttr::new(i64_ty, APInt::from_u64(value, NonZero::new(64).unwrap()));
+        Self::new(ctx, value.into())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("{}: Unexpected type", ConstantOp::opid_static())]
+pub struct ConstantOpVerifyErr;
+
+impl Verify for ConstantOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        let value = self.get_value(ctx);
+        if !(value.is::<IntegerAttr>()
+            || value.is::<FloatAttr>()
+            || value.is::<ConstantVectorAttr>())
+        {
+            return verify_err!(loc, ConstantOpVerifyErr);
+        }
+        Ok(())
+    }
+}
+
+impl_canonical_syntax!(ConstantOp);
+
+#[op_interface_impl]
+impl HoistableConstantInterface for ConstantOp {
+    /// Size as per the [DataLayout] of (the module enclosing) this [Op].
+    /// Zero, so that it's never promoted, if that can't be determined.
+    fn constant_size(&self, ctx: &Context) -> usize {
+        DataLayout::of(ctx, self.operation())
+            .and_then(|layout| size_of(ctx, self.result_type(ctx), &layout))
+            .map_or(0, |size| size as usize)
+    }
+
+    fn build_global(&self, ctx: &mut Context, name: &Identifier) -> Ptr<Operation> {
+        let value = self.get_value(ctx);
+        let global = GlobalOp::new(ctx, name, self.result_type(ctx), Some(value));
+        global.set_constant(ctx);
+        global.set_private(ctx, true);
+        global.operation()
+    }
+
+    fn build_global_load(
+        &self,
+        ctx: &mut Context,
+        name: &Identifier,
+    ) -> (Vec<Ptr<Operation>>, Value) {
+        let address_of = AddressOfOp::new(ctx, name);
+        let load = LoadOp::new(ctx, address_of.result(ctx), self.result_type(ctx));
+        let loaded = load.result(ctx);
+        (vec![address_of.operation(), load.operation()], loaded)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GlobalOpVerifyErr {
+    #[error("Missing or incorrect type of attribute for the type of the global")]
+    TypeAttr,
+    #[error("Initializer must be an integer, float or constant vector of the global's type")]
+    InitializerErr,
+}
+
+/// A global variable, or constant, in a module.
+/// See MLIR's [llvm.mlir.global](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmlirglobal-llvmglobalop).
+///
+/// ### Attributes:
+///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_SYM_NAME](op_interfaces::ATTR_KEY_SYM_NAME) | [IdentifierAttr] | [SymbolOpInterface] |
+/// | [ATTR_KEY_GLOBAL_TYPE](global_op::ATTR_KEY_GLOBAL_TYPE) | [TypeAttr] | N/A |
+/// | [ATTR_KEY_INITIALIZER](global_op::ATTR_KEY_INITIALIZER) | Same as [ConstantOp]'s value | N/A |
+/// | [ATTR_KEY_CONSTANT](global_op::ATTR_KEY_CONSTANT) | [UnitAttr] | N/A |
+#[def_op("llvm.global")]
+#[derive_op_interface_impl(SymbolOpInterface, ZeroOpdInterface, ZeroResultInterface)]
+pub struct GlobalOp;
+
+pub mod global_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    /// Attribute key for the type of the global.
+    pub static ATTR_KEY_GLOBAL_TYPE: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_global_type".try_into().unwrap());
+    /// Attribute key for the initial value of the global.
+    pub static ATTR_KEY_INITIALIZER: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_global_initializer".try_into().unwrap());
+    /// Attribute key marking the global as a constant.
+    pub static ATTR_KEY_CONSTANT: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_global_constant".try_into().unwrap());
+}
+
+impl GlobalOp {
+    /// Create a new [GlobalOp] of type `ty`, optionally with an initial value.
+    pub fn new(
+        ctx: &mut Context,
+        name: &Identifier,
+        ty: Ptr<TypeObj>,
+        initializer: Option<AttrObj>,
+    ) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        let global = GlobalOp { op };
+        global.set_symbol_name(ctx, name);
+        let attributes = &mut op.deref_mut(ctx).attributes;
+        attributes.set(*global_op::ATTR_KEY_GLOBAL_TYPE, TypeAttr::new(ty));
+        if let Some(initializer) = initializer {
+            attributes
+                .0
+                .insert(*global_op::ATTR_KEY_INITIALIZER, initializer);
+        }
+        global
+    }
+
+    /// Get the type of the global.
+    pub fn get_type(&self, ctx: &Context) -> Ptr<TypeObj> {
+        self.op
+            .deref(ctx)
+            .attributes
+            .get::<TypeAttr>(&global_op::ATTR_KEY_GLOBAL_TYPE)
+            .expect("GlobalOp missing or incorrect type attribute for its type")
+            .get_type()
+    }
+
+    /// Get the initial value of the global, if it has one.
+    pub fn get_initializer(&self, ctx: &Context) -> Option<AttrObj> {
+        self.op
+            .deref(ctx)
+            .attributes
+            .0
+            .get(&global_op::ATTR_KEY_INITIALIZER)
+            .cloned()
+    }
+
+    /// Is this global a constant?
+    pub fn is_constant(&self, ctx: &Context) -> bool {
+        self.op
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&global_op::ATTR_KEY_CONSTANT)
+            .is_some()
+    }
+
+    /// Mark this global as a constant.
+    pub fn set_constant(&self, ctx: &mut Context) {
+        self.op
+            .deref_mut(ctx)
+            .attributes
+            .set(*global_op::ATTR_KEY_CONSTANT, UnitAttr::new());
+    }
+}
+
+impl Verify for GlobalOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        let op = &*self.op.deref(ctx);
+        let Some(ty) = op
+            .attributes
+            .get::<TypeAttr>(&global_op::ATTR_KEY_GLOBAL_TYPE)
+        else {
+            return verify_err!(loc, GlobalOpVerifyErr::TypeAttr);
+        };
+        if let Some(initializer) = op.attributes.0.get(&global_op::ATTR_KEY_INITIALIZER) {
+            let is_constant_value = initializer.is::<IntegerAttr>()
+                || initializer.is::<FloatAttr>()
+                || initializer.is::<ConstantVectorAttr>();
+            let init_ty =
+                attr_cast::<dyn TypedAttrInterface>(&**initializer).map(|typed| typed.get_type());
+            if !is_constant_value || init_ty != Some(ty.get_type()) {
+                return verify_err!(loc, GlobalOpVerifyErr::InitializerErr);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl_canonical_syntax!(GlobalOp);
+
+#[derive(Error, Debug)]
+pub enum AddressOfOpVerifyErr {
+    #[error("Missing or incorrect type of attribute for the global name")]
+    GlobalNameAttr,
+    #[error("Result must be a pointer")]
+    ResultTypeErr,
+}
+
+/// The address of a [GlobalOp] or function.
+/// See MLIR's [llvm.mlir.addressof](https://mlir.llvm.org/docs/Dialects/LLVM/#llvmmliraddressof-llvmaddressofop).
+///
+/// ### Attributes:
+///
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_GLOBAL_NAME](address_of_op::ATTR_KEY_GLOBAL_NAME) | [IdentifierAttr] | N/A |
 ///
-/// Results:
+/// ### Result(s):
 ///
 /// | result | description |
 /// |-----|-------|
-/// | `result` | any type |
-#[def_op("llvm.constant")]
-#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface)]
-pub struct ConstantOp;
+/// | `res` | [PointerType] |
+#[def_op("llvm.address_of")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface, PureInterface)]
+pub struct AddressOfOp;
 
-pub mod constant_op {
+pub mod address_of_op {
     use std::sync::LazyLock;
 
     use super::*;
-    /// Attribute key for the constant value.
-    pub static ATTR_KEY_VALUE: LazyLock<Identifier> =
-        LazyLock::new(|| "llvm_constant_value".try_into().unwrap());
+    /// Attribute key for the name of the global whose address this is.
+    pub static ATTR_KEY_GLOBAL_NAME: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_address_of_global_name".try_into().unwrap());
 }
 
-impl ConstantOp {
-    /// Get the constant value that this Op defines.
-    pub fn get_value(&self, ctx: &Context) -> AttrObj {
-        let op = self.operation().deref(ctx);
-        op.attributes
-            .0
-            .get(&constant_op::ATTR_KEY_VALUE)
-            .unwrap()
-            .clone()
+impl AddressOfOp {
+    /// Create a new [AddressOfOp], for the global `global_name`.
+    pub fn new(ctx: &mut Context, global_name: &Identifier) -> Self {
+        let ptr_ty = PointerType::get(ctx).into();
+        let op = Operation::new(ctx, Self::opid_static(), vec![ptr_ty], vec![], vec![], 0);
+        op.deref_mut(ctx).attributes.set(
+            *address_of_op::ATTR_KEY_GLOBAL_NAME,
+            IdentifierAttr::new(*global_name),
+        );
+        AddressOfOp { op }
     }
 
-    /// Create a new [ConstantOp].
-    pub fn new(ctx: &mut Context, value: AttrObj) -> Self {
-        let result_type = attr_cast::<dyn TypedAttrInterface>(&*value)
-            .expect("ConstantOp const value must provide TypedAttrInterface")
-            .get_type();
-        let op = Operation::new(
-            ctx,
-            Self::opid_static(),
-            vec![result_type],
-            vec![],
-            vec![],
-            0,
-        );
-        op.deref_mut(ctx)
+    /// Get the name of the global whose address this is.
+    pub fn get_global_name(&self, ctx: &Context) -> Identifier {
+        self.op
+            .deref(ctx)
             .attributes
-            .0
-            .insert(constant_op::ATTR_KEY_VALUE.clone(), value);
-        ConstantOp { op }
+            .get::<IdentifierAttr>(&address_of_op::ATTR_KEY_GLOBAL_NAME)
+            .expect("AddressOfOp missing or incorrect type attribute for the global name")
+            .clone()
+            .into()
     }
 }
 
-#[derive(Error, Debug)]
-#[error("{}: Unexpected type", ConstantOp::opid_static())]
-pub struct ConstantOpVerifyErr;
+#[op_interface_impl]
+impl SymbolUserOpInterface for AddressOfOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        vec![self.get_global_name(ctx)]
+    }
 
-impl Verify for ConstantOp {
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if &self.get_global_name(ctx) == from {
+            self.op.deref_mut(ctx).attributes.set(
+                *address_of_op::ATTR_KEY_GLOBAL_NAME,
+                IdentifierAttr::new(*to),
+            );
+        }
+    }
+}
+
+impl Verify for AddressOfOp {
     fn verify(&self, ctx: &Context) -> Result<()> {
         let loc = self.loc(ctx);
-        let value = self.get_value(ctx);
-        if !(value.is::<IntegerAttr>() || value.is::<FloatAttr>()) {
-            return verify_err!(loc, ConstantOpVerifyErr);
+        let op = &*self.op.deref(ctx);
+        if op
+            .attributes
+            .get::<IdentifierAttr>(&address_of_op::ATTR_KEY_GLOBAL_NAME)
+            .is_none()
+        {
+            return verify_err!(loc, AddressOfOpVerifyErr::GlobalNameAttr);
+        }
+        if !self.result_type(ctx).deref(ctx).is::<PointerType>() {
+            return verify_err!(loc, AddressOfOpVerifyErr::ResultTypeErr);
         }
         Ok(())
     }
 }
 
-impl_canonical_syntax!(ConstantOp);
+impl_canonical_syntax!(AddressOfOp);
 
 #[derive(Error, Debug)]
 enum IntExtVerifyErr {
@@ -1180,7 +2685,7 @@ fn integer_ext_verify(op: &Operation, ctx: &Context) -> Result<()> {
 /// |-----|-------|
 /// | `res` | Signless integer |
 #[def_op("llvm.sext")]
-#[derive_op_interface_impl(CastOpInterface, OneResultInterface, OneOpdInterface)]
+#[derive_op_interface_impl(CastOpInterface, OneResultInterface, OneOpdInterface, PureInterface)]
 #[format_op("$0 ` to ` type($0)")]
 pub struct SExtOp;
 impl Verify for SExtOp {
@@ -1199,7 +2704,7 @@ impl Verify for SExtOp {
 /// |-----|-------|
 /// | `res` | Signless integer |
 #[def_op("llvm.zext")]
-#[derive_op_interface_impl(CastOpInterface, OneResultInterface, OneOpdInterface)]
+#[derive_op_interface_impl(CastOpInterface, OneResultInterface, OneOpdInterface, PureInterface)]
 #[format_op("$0 ` to ` type($0)")]
 pub struct ZExtOp;
 
@@ -1228,7 +2733,7 @@ impl Verify for ZExtOp {
 #[format_op(
     "$0 attr($llvm_insert_extract_value_indices, $InsertExtractValueIndicesAttr) `, ` $1 ` : ` type($0)"
 )]
-#[derive_op_interface_impl(OneResultInterface)]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
 pub struct InsertValueOp;
 
 impl InsertValueOp {
@@ -1254,7 +2759,7 @@ impl InsertValueOp {
             0,
         );
         op.deref_mut(ctx).attributes.set(
-            insert_extract_value_op::ATTR_KEY_INDICES.clone(),
+            *insert_extract_value_op::ATTR_KEY_INDICES,
             InsertExtractValueIndicesAttr(indices),
         );
         Ok(InsertValueOp { op })
@@ -1327,7 +2832,7 @@ impl Verify for InsertValueOp {
 #[format_op(
     "$0 attr($llvm_insert_extract_value_indices, $InsertExtractValueIndicesAttr) ` : ` type($0)"
 )]
-#[derive_op_interface_impl(OneResultInterface, OneOpdInterface)]
+#[derive_op_interface_impl(OneResultInterface, OneOpdInterface, PureInterface)]
 pub struct ExtractValueOp;
 
 impl Verify for ExtractValueOp {
@@ -1384,7 +2889,7 @@ impl ExtractValueOp {
             0,
         );
         op.deref_mut(ctx).attributes.set(
-            insert_extract_value_op::ATTR_KEY_INDICES.clone(),
+            *insert_extract_value_op::ATTR_KEY_INDICES,
             InsertExtractValueIndicesAttr(indices),
         );
         Ok(ExtractValueOp { op })
@@ -1452,6 +2957,339 @@ pub mod insert_extract_value_op {
         LazyLock::new(|| "llvm_insert_extract_value_indices".try_into().unwrap());
 }
 
+/// If `val` is defined by a [ConstantOp] with an integer value, get that value.
+fn constant_index(ctx: &Context, val: Value) -> Option<u64> {
+    let Value::OpResult { op, .. } = val else {
+        return None;
+    };
+    let op = Operation::op(op, ctx);
+    let value = op.downcast_ref::<ConstantOp>()?.get_value(ctx);
+    value
+        .downcast_ref::<IntegerAttr>()
+        .and_then(|int| int.as_u64(ctx).ok())
+}
+
+#[derive(Error, Debug)]
+pub enum VectorOpVerifyErr {
+    #[error("Operand must be a vector")]
+    NotVectorErr,
+    #[error("Vector index must be an integer")]
+    IndexTypeErr,
+    #[error("Vector index {0} out of bounds for vector of length {1}")]
+    IndexOutOfBoundsErr(u64, u32),
+    #[error("Element type does not match the vector's element type")]
+    ElemTypeErr,
+    #[error("Incorrect result type")]
+    ResultTypeErr,
+    #[error("Shuffle vector instruction has no or incorrect mask attribute")]
+    MaskAttrErr,
+    #[error("Shuffle mask index {0} out of bounds for source vectors of length {1}")]
+    MaskIndexOutOfBoundsErr(u32, u32),
+    #[error("Source vectors of a shuffle must have the same type")]
+    SourceTypesErr,
+}
+
+/// Get `ty` as a [VectorType], or fail verification.
+fn verify_vector_type(
+    ctx: &Context,
+    loc: Location,
+    ty: Ptr<TypeObj>,
+) -> Result<TypePtr<VectorType>> {
+    TypePtr::<VectorType>::downcast(ctx, ty)
+        .map_err(|_| verify_error!(loc, VectorOpVerifyErr::NotVectorErr))
+}
+
+/// Verify that `idx` is an integer, and if it is a constant, that it is within bounds.
+fn verify_vector_index(
+    ctx: &Context,
+    loc: Location,
+    vec_ty: TypePtr<VectorType>,
+    idx: Value,
+) -> Result<()> {
+    use pliron::r#type::Typed;
+
+    if !idx.get_type(ctx).isa::<IntegerType>(ctx) {
+        return verify_err!(loc, VectorOpVerifyErr::IndexTypeErr);
+    }
+    let num_elements = vec_ty.deref(ctx).num_elements();
+    if let Some(idx) = constant_index(ctx, idx).filter(|idx| *idx >= num_elements as u64) {
+        return verify_err!(
+            loc,
+            VectorOpVerifyErr::IndexOutOfBoundsErr(idx, num_elements)
+        );
+    }
+    Ok(())
+}
+
+/// Equivalent to LLVM's ExtractElement opcode.
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `vector` | LLVM vector type |
+/// | `index` | Signless integer |
+/// ### Result(s):
+/// | result | description |
+/// |-----|-------|
+/// | `res` | Element type of `vector` |
+#[def_op("llvm.extract_element")]
+#[format_op("$0 `[` $1 `]` ` : ` type($0)")]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
+pub struct ExtractElementOp;
+
+impl ExtractElementOp {
+    /// Create a new [ExtractElementOp].
+    /// `vector` must be of [VectorType].
+    pub fn new(ctx: &mut Context, vector: Value, index: Value) -> Result<Self> {
+        use pliron::r#type::Typed;
+
+        let vec_ty = TypePtr::<VectorType>::downcast(ctx, vector.get_type(ctx))?;
+        let elem_ty = vec_ty.deref(ctx).elem_type();
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![elem_ty],
+            vec![vector, index],
+            vec![],
+            0,
+        );
+        Ok(ExtractElementOp { op })
+    }
+
+    /// Get the vector operand.
+    pub fn vector_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(0)
+    }
+
+    /// Get the index operand.
+    pub fn index_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(1)
+    }
+}
+
+impl Verify for ExtractElementOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        use pliron::r#type::Typed;
+
+        let loc = self.loc(ctx);
+        let vec_ty = verify_vector_type(ctx, loc.clone(), self.vector_opd(ctx).get_type(ctx))?;
+        verify_vector_index(ctx, loc.clone(), vec_ty, self.index_opd(ctx))?;
+        if self.result_type(ctx) != vec_ty.deref(ctx).elem_type() {
+            return verify_err!(loc, VectorOpVerifyErr::ResultTypeErr);
+        }
+        Ok(())
+    }
+}
+
+/// Equivalent to LLVM's InsertElement opcode.
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `vector` | LLVM vector type |
+/// | `value` | Element type of `vector` |
+/// | `index` | Signless integer |
+/// ### Result(s):
+/// | result | description |
+/// |-----|-------|
+/// | `res` | Type of `vector` |
+#[def_op("llvm.insert_element")]
+#[format_op("$0 `[` $2 `]` `, ` $1 ` : ` type($0)")]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
+pub struct InsertElementOp;
+
+impl InsertElementOp {
+    /// Create a new [InsertElementOp].
+    pub fn new(ctx: &mut Context, vector: Value, value: Value, index: Value) -> Self {
+        use pliron::r#type::Typed;
+
+        let vec_ty = vector.get_type(ctx);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![vec_ty],
+            vec![vector, value, index],
+            vec![],
+            0,
+        );
+        InsertElementOp { op }
+    }
+
+    /// Get the vector operand.
+    pub fn vector_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(0)
+    }
+
+    /// Get the value operand.
+    pub fn value_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(1)
+    }
+
+    /// Get the index operand.
+    pub fn index_opd(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(2)
+    }
+}
+
+impl Verify for InsertElementOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        use pliron::r#type::Typed;
+
+        let loc = self.loc(ctx);
+        let vec_ty_ptr = self.vector_opd(ctx).get_type(ctx);
+        let vec_ty = verify_vector_type(ctx, loc.clone(), vec_ty_ptr)?;
+        verify_vector_index(ctx, loc.clone(), vec_ty, self.index_opd(ctx))?;
+        if self.value_opd(ctx).get_type(ctx) != vec_ty.deref(ctx).elem_type() {
+            return verify_err!(loc, VectorOpVerifyErr::ElemTypeErr);
+        }
+        if self.result_type(ctx) != vec_ty_ptr {
+            return verify_err!(loc, VectorOpVerifyErr::ResultTypeErr);
+        }
+        Ok(())
+    }
+}
+
+/// Equivalent to LLVM's ShuffleVector opcode.
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `v1` | LLVM vector type |
+/// | `v2` | Type of `v1` |
+/// ### Result(s):
+/// | result | description |
+/// |-----|-------|
+/// | `res` | Vector of the element type of `v1`, with as many elements as the mask |
+/// ### Attributes:
+/// | key | value | via Interface |
+/// |-----|-------| --------------|
+/// | [ATTR_KEY_MASK](shuffle_vector_op::ATTR_KEY_MASK) | [ShuffleMaskAttr] | N/A |
+///
+/// Each mask element selects an element from the concatenation of `v1` and `v2`
+/// (so it must be less than twice the length of `v1`), or is poison.
+#[def_op("llvm.shuffle_vector")]
+#[format_op("$0 `, ` $1 ` ` attr($llvm_shuffle_vector_mask, $ShuffleMaskAttr) ` : ` type($0)")]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
+pub struct ShuffleVectorOp;
+
+pub mod shuffle_vector_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+    /// Attribute key for the shuffle mask.
+    pub static ATTR_KEY_MASK: LazyLock<Identifier> =
+        LazyLock::new(|| "llvm_shuffle_vector_mask".try_into().unwrap());
+}
+
+impl ShuffleVectorOp {
+    /// Create a new [ShuffleVectorOp].
+    /// `v1` and `v2` must be of the same [VectorType].
+    pub fn new(
+        ctx: &mut Context,
+        v1: Value,
+        v2: Value,
+        mask: Vec<ShuffleMaskElemAttr>,
+    ) -> Result<Self> {
+        use pliron::r#type::Typed;
+
+        let vec_ty = TypePtr::<VectorType>::downcast(ctx, v1.get_type(ctx))?;
+        let elem_ty = vec_ty.deref(ctx).elem_type();
+        let res_ty = VectorType::get(ctx, elem_ty, mask.len().try_into().unwrap());
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty.into()],
+            vec![v1, v2],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*shuffle_vector_op::ATTR_KEY_MASK, ShuffleMaskAttr(mask));
+        Ok(ShuffleVectorOp { op })
+    }
+
+    /// Get the shuffle mask.
+    pub fn mask(&self, ctx: &Context) -> Vec<ShuffleMaskElemAttr> {
+        self.op
+            .deref(ctx)
+            .attributes
+            .get::<ShuffleMaskAttr>(&shuffle_vector_op::ATTR_KEY_MASK)
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    /// Evaluate this shuffle, if both sources are constant vectors
+    /// and the mask has no poison elements.
+    pub fn fold(&self, ctx: &Context) -> Option<ConstantVectorAttr> {
+        let const_elems = |opd_idx: usize| {
+            let Value::OpResult { op, .. } = self.op.deref(ctx).operand(opd_idx) else {
+                return None;
+            };
+            let op = Operation::op(op, ctx);
+            let value = op.downcast_ref::<ConstantOp>()?.get_value(ctx);
+            value
+                .downcast_ref::<ConstantVectorAttr>()
+                .map(|v| v.elems().to_vec())
+        };
+        let mut sources = const_elems(0)?;
+        sources.extend(const_elems(1)?);
+
+        let elems = self
+            .mask(ctx)
+            .into_iter()
+            .map(|elem| match elem {
+                ShuffleMaskElemAttr::Index(idx) => sources.get(idx as usize).cloned(),
+                ShuffleMaskElemAttr::Poison => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let res_ty = TypePtr::<VectorType>::downcast(ctx, self.result_type(ctx)).ok()?;
+        Some(ConstantVectorAttr::new(res_ty, elems))
+    }
+}
+
+impl Verify for ShuffleVectorOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        use pliron::r#type::Typed;
+
+        let loc = self.loc(ctx);
+        let op = &*self.op.deref(ctx);
+        let Some(mask) = op
+            .attributes
+            .get::<ShuffleMaskAttr>(&shuffle_vector_op::ATTR_KEY_MASK)
+        else {
+            return verify_err!(loc, VectorOpVerifyErr::MaskAttrErr);
+        };
+
+        let v1_ty = op.operand(0).get_type(ctx);
+        if v1_ty != op.operand(1).get_type(ctx) {
+            return verify_err!(loc, VectorOpVerifyErr::SourceTypesErr);
+        }
+        let v1_ty = verify_vector_type(ctx, loc.clone(), v1_ty)?;
+        let (elem_ty, num_elements) = {
+            let v1_ty = v1_ty.deref(ctx);
+            (v1_ty.elem_type(), v1_ty.num_elements())
+        };
+
+        for elem in &mask.0 {
+            match *elem {
+                ShuffleMaskElemAttr::Index(idx) if idx >= 2 * num_elements => {
+                    return verify_err!(
+                        loc,
+                        VectorOpVerifyErr::MaskIndexOutOfBoundsErr(idx, num_elements)
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let res_ty = verify_vector_type(ctx, loc.clone(), op.get_type(0))?;
+        let res_ty = res_ty.deref(ctx);
+        if res_ty.elem_type() != elem_ty || res_ty.num_elements() as usize != mask.0.len() {
+            return verify_err!(loc, VectorOpVerifyErr::ResultTypeErr);
+        }
+        Ok(())
+    }
+}
+
 /// Equivalent to LLVM's Select opcode.
 /// ### Operands
 /// | operand | description |
@@ -1464,7 +3302,7 @@ pub mod insert_extract_value_op {
 /// |-----|-------|
 /// | `res` | any type |
 #[def_op("llvm.select")]
-#[derive_op_interface_impl(OneResultInterface)]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
 #[format_op("$0 ` ? ` $1 ` : ` $2 ` : ` type($0)")]
 pub struct SelectOp;
 
@@ -1540,13 +3378,236 @@ pub fn register(ctx: &mut Context) {
     GetElementPtrOp::register(ctx, GetElementPtrOp::parser_fn);
     LoadOp::register(ctx, LoadOp::parser_fn);
     StoreOp::register(ctx, StoreOp::parser_fn);
+    AtomicRmwOp::register(ctx, AtomicRmwOp::parser_fn);
+    AtomicCmpXchgOp::register(ctx, AtomicCmpXchgOp::parser_fn);
     CallOp::register(ctx, CallOp::parser_fn);
+    InvokeOp::register(ctx, InvokeOp::parser_fn);
+    LandingPadOp::register(ctx, LandingPadOp::parser_fn);
+    ResumeOp::register(ctx, ResumeOp::parser_fn);
+    UnreachableOp::register(ctx, UnreachableOp::parser_fn);
     ConstantOp::register(ctx, ConstantOp::parser_fn);
+    GlobalOp::register(ctx, GlobalOp::parser_fn);
+    AddressOfOp::register(ctx, AddressOfOp::parser_fn);
     SExtOp::register(ctx, SExtOp::parser_fn);
     ZExtOp::register(ctx, ZExtOp::parser_fn);
     InsertValueOp::register(ctx, InsertValueOp::parser_fn);
     ExtractValueOp::register(ctx, ExtractValueOp::parser_fn);
+    ExtractElementOp::register(ctx, ExtractElementOp::parser_fn);
+    InsertElementOp::register(ctx, InsertElementOp::parser_fn);
+    ShuffleVectorOp::register(ctx, ShuffleVectorOp::parser_fn);
     SelectOp::register(ctx, SelectOp::parser_fn);
     UndefOp::register(ctx, UndefOp::parser_fn);
+    PoisonOp::register(ctx, PoisonOp::parser_fn);
     ReturnOp::register(ctx, ReturnOp::parser_fn);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use pliron::{
+        builtin::{
+            self,
+            attributes::IntegerAttr,
+            op_interfaces::{
+                CallOpCallable, CallOpInterface, OneResultInterface, OperandBundle,
+                OperandBundleInterface, SingleBlockRegionInterface,
+            },
+            ops::{FuncOp, ModuleOp},
+            type_interfaces::DataLayout,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::{Context, Ptr},
+        op::Op,
+        operation::Operation,
+        printable::Printable,
+        transforms::promote_constants::promote_constants,
+        r#type::{TypeObj, TypePtr},
+        utils::apint::APInt,
+    };
+
+    use crate::{
+        self as llvm,
+        attributes::{ConstantVectorAttr, ShuffleMaskElemAttr},
+        ops::{CallOp, ConstantOp, ExtractElementOp, ReturnOp, ShuffleVectorOp},
+        to_llvm_text,
+        types::{PointerType, StructType, VectorType},
+    };
+
+    fn const_vector(ctx: &mut Context, elems: &[u64]) -> ConstantOp {
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless);
+        let vec_ty = VectorType::get(ctx, i32_ty.into(), elems.len().try_into().unwrap());
+        let elems = elems
+            .iter()
+            .map(|elem| IntegerAttr::new(i32_ty, APInt::from_u64(*elem, NonZero::new(32).unwrap())))
+            .collect();
+        ConstantOp::new(ctx, ConstantVectorAttr::new(vec_ty, elems).into())
+    }
+
+    #[test]
+    fn test_shuffle_vector_fold() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+
+        let v1 = const_vector(&mut ctx, &[1, 2, 3]).result(&ctx);
+        let v2 = const_vector(&mut ctx, &[10, 20, 30]).result(&ctx);
+        let mask = [5, 0, 4, 1].map(ShuffleMaskElemAttr::Index).to_vec();
+        let shuffle = ShuffleVectorOp::new(&mut ctx, v1, v2, mask).unwrap();
+        shuffle.verify(&ctx).unwrap();
+
+        let folded = shuffle.fold(&ctx).unwrap();
+        assert_eq!(
+            folded.disp(&ctx).to_string(),
+            "[<30: i32>, <1: i32>, <20: i32>, <2: i32>]: llvm.vector <4 x builtin.integer i32>"
+        );
+        folded.verify(&ctx).unwrap();
+
+        // Shuffles with poison elements aren't folded.
+        let mask = vec![ShuffleMaskElemAttr::Index(0), ShuffleMaskElemAttr::Poison];
+        let shuffle = ShuffleVectorOp::new(&mut ctx, v1, v2, mask).unwrap();
+        shuffle.verify(&ctx).unwrap();
+        assert!(shuffle.fold(&ctx).is_none());
+
+        // Mask indices can't be beyond the concatenation of both sources.
+        let mask = vec![ShuffleMaskElemAttr::Index(6)];
+        let shuffle = ShuffleVectorOp::new(&mut ctx, v1, v2, mask).unwrap();
+        assert!(shuffle.verify(&ctx).is_err());
+    }
+
+    #[test]
+    fn test_promote_constant_vector() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        // A 16 byte vector constant is promoted, a 4 byte one isn't.
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+        let large = const_vector(ctx, &[1, 2, 3, 4]);
+        let small = const_vector(ctx, &[5]);
+        for (name, constant) in [("large", large), ("small", small)] {
+            let ty = constant.result_type(ctx);
+            let func_ty = FunctionType::get(ctx, vec![], vec![ty]);
+            let func = FuncOp::new(ctx, &name.try_into().unwrap(), func_ty);
+            module.append_operation(ctx, func.operation(), 0);
+            let entry = func.get_entry_block(ctx);
+            constant.operation().insert_at_back(entry, ctx);
+            ReturnOp::new(ctx, Some(constant.result(ctx)))
+                .operation()
+                .insert_at_back(entry, ctx);
+        }
+
+        assert_eq!(promote_constants(ctx, &module, 16), 1);
+        module.operation().verify(ctx).unwrap();
+        let text = to_llvm_text::convert_module(ctx, module).unwrap();
+        assert!(text.contains(
+            "@promoted_const_0 = private constant <4 x i32> <i32 1, i32 2, i32 3, i32 4>"
+        ));
+        assert!(text.contains("load <4 x i32>, ptr @promoted_const_0"));
+        assert!(text.contains("ret <1 x i32> <i32 5>"));
+    }
+
+    #[test]
+    fn test_extract_element_bounds() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+
+        let vector = const_vector(&mut ctx, &[1, 2]).result(&ctx);
+        let i64_ty = IntegerType::get(&mut ctx, 64, Signedness::Signless);
+        let mut index = |idx| {
+            let attr = IntegerAttr::new(i64_ty, APInt::from_u64(idx, NonZero::new(64).unwrap()));
+            ConstantOp::new(&mut ctx, attr.into()).result(&ctx)
+        };
+        let (in_bounds, out_of_bounds) = (index(1), index(2));
+
+        let extract = ExtractElementOp::new(&mut ctx, vector, in_bounds).unwrap();
+        extract.verify(&ctx).unwrap();
+        assert!(
+            TypePtr::<IntegerType>::downcast(&ctx, extract.result_type(&ctx))
+                .is_ok_and(|ty| ty.deref(&ctx).width() == 32)
+        );
+        let extract = ExtractElementOp::new(&mut ctx, vector, out_of_bounds).unwrap();
+        assert!(extract.verify(&ctx).is_err());
+    }
+
+    /// Fold the size (or alignment, if not `size`) of `ty` and print the constant.
+    fn fold(ctx: &mut Context, ty: Ptr<TypeObj>, anchor: Ptr<Operation>, size: bool) -> String {
+        let constant = if size {
+            ConstantOp::new_size_of(ctx, ty, anchor)
+        } else {
+            ConstantOp::new_align_of(ctx, ty, anchor)
+        };
+        constant.unwrap().get_value(ctx).disp(ctx).to_string()
+    }
+
+    #[test]
+    fn test_size_align_fold() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+
+        let i8_ty = IntegerType::get(&mut ctx, 8, Signedness::Signless).into();
+        let i64_ty = IntegerType::get(&mut ctx, 64, Signedness::Signless).into();
+        let ptr_ty = PointerType::get(&mut ctx).into();
+        let struct_ty = StructType::get_unnamed(&mut ctx, vec![i8_ty, i64_ty, ptr_ty]).into();
+        let vec_ty = VectorType::get(&mut ctx, i8_ty, 3).into();
+        let opaque_name = "opaque".try_into().unwrap();
+        let opaque_ty = StructType::get_named(&mut ctx, opaque_name, None)
+            .unwrap()
+            .into();
+        let module = ModuleOp::new(&mut ctx, &"m".try_into().unwrap());
+        let anchor = module.operation();
+
+        assert_eq!(
+            fold(&mut ctx, struct_ty, anchor, true),
+            "builtin.integer <24: i64>"
+        );
+        assert_eq!(
+            fold(&mut ctx, struct_ty, anchor, false),
+            "builtin.integer <8: i64>"
+        );
+        assert_eq!(
+            fold(&mut ctx, vec_ty, anchor, true),
+            "builtin.integer <4: i64>"
+        );
+
+        DataLayout::parse("e-p:32:32-i64:32")
+            .unwrap()
+            .set(&ctx, anchor);
+        assert_eq!(
+            fold(&mut ctx, struct_ty, anchor, true),
+            "builtin.integer <16: i64>"
+        );
+        assert!(ConstantOp::new_size_of(&mut ctx, opaque_ty, anchor).is_err());
+    }
+
+    #[test]
+    fn test_call_operand_bundles() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+
+        let i32_ty = IntegerType::get(&mut ctx, 32, Signedness::Signless);
+        let mut constant = |val| {
+            let attr = IntegerAttr::new(i32_ty, APInt::from_u64(val, NonZero::new(32).unwrap()));
+            ConstantOp::new(&mut ctx, attr.into()).result(&ctx)
+        };
+        let (c1, c2, c3) = (constant(1), constant(2), constant(3));
+        let callee_ty = FunctionType::get(&mut ctx, vec![i32_ty.into()], vec![i32_ty.into()]);
+        let callee = CallOpCallable::Direct("f".try_into().unwrap());
+        let call = CallOp::new(&mut ctx, callee, callee_ty, vec![c1]);
+
+        call.set_operand_bundles(&mut ctx, vec![OperandBundle::new("deopt", vec![c2, c3])]);
+        assert!(call.args(&ctx) == vec![c1]);
+        assert!(call.operand_bundle(&ctx, "deopt").unwrap().operands == vec![c2, c3]);
+        call.verify(&ctx).unwrap();
+
+        // Replacing the arguments keeps the bundles.
+        call.set_args(&mut ctx, vec![c3]);
+        assert!(call.args(&ctx) == vec![c3]);
+        assert!(call.operand_bundle(&ctx, "deopt").unwrap().operands == vec![c2, c3]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Simplifying control flow around [UnreachableOp]s.
+//!
+//! Control reaching an [unreachable](UnreachableInterface) terminator is undefined
+//! behaviour, so [simplify_cfg] assumes that branches to a block doing nothing other
+//! than that are never taken:
+//!   - a [CondBrOp] with one such successor becomes a [BrOp] to the other,
+//!   - a [BrOp] (or a [CondBrOp] with both successors such) becomes an [UnreachableOp].
+//!
+//! This is repeated until no branch changes, propagating unreachability backwards
+//! through the CFG. Blocks that are then not reachable from the entry block of their
+//! region, including all code that could only be reached through an unreachable block,
+//! are erased. The operations before an [UnreachableOp] are kept, since they
+//! may not return (such as a call to `exit`).
+
+use pliron::{
+    basic_block::BasicBlock,
+    builtin::op_interfaces::{BranchOpInterface, UnreachableInterface},
+    context::{Context, Ptr},
+    linked_list::ContainsLinkedList,
+    location::Located,
+    op::{Op, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    region::Region,
+    result::Result,
+    transforms::dce::erase_unreachable_blocks,
+};
+
+use crate::ops::{BrOp, CondBrOp, UnreachableOp};
+
+/// Does `block` do nothing other than reaching an [unreachable](UnreachableInterface) terminator?
+pub fn is_unreachable_block(ctx: &Context, block: Ptr<BasicBlock>) -> bool {
+    let block_ref = block.deref(ctx);
+    block_ref.head().is_some_and(|head| {
+        block_ref.tail() == Some(head)
+            && op_impls::<dyn UnreachableInterface>(&*Operation::op(head, ctx))
+    })
+}
+
+/// Rewrite the terminator of `block`, if it branches to an [unreachable block](is_unreachable_block).
+/// Returns whether it was rewritten.
+fn simplify_terminator(ctx: &mut Context, block: Ptr<BasicBlock>) -> bool {
+    let Some(term) = block.deref(ctx).tail() else {
+        return false;
+    };
+    let term_obj = Operation::op(term, ctx);
+    let replacement = if let Some(cond_br) = term_obj.downcast_ref::<CondBrOp>() {
+        let succs: Vec<_> = term.deref(ctx).successors().collect();
+        match [0, 1].map(|idx| is_unreachable_block(ctx, succs[idx])) {
+            [true, true] => UnreachableOp::new(ctx).operation(),
+            [false, false] => return false,
+            [first_unreachable, _] => {
+                let live = usize::from(first_unreachable);
+                let opds = cond_br.successor_operands(ctx, live);
+                BrOp::new(ctx, succs[live], opds).operation()
+            }
+        }
+    } else if term_obj.is::<BrOp>() && is_unreachable_block(ctx, term.deref(ctx).successor(0)) {
+        UnreachableOp::new(ctx).operation()
+    } else {
+        return false;
+    };
+
+    let loc = term.deref(ctx).loc();
+    replacement.deref_mut(ctx).set_loc(loc);
+    replacement.insert_before(ctx, term);
+    Operation::erase(term, ctx);
+    true
+}
+
+fn collect_regions(ctx: &Context, op: Ptr<Operation>, regions: &mut Vec<Ptr<Region>>) {
+    for region in op.deref(ctx).regions() {
+        regions.push(region);
+        for block in region.deref(ctx).iter(ctx) {
+            for nested in block.deref(ctx).iter(ctx) {
+                collect_regions(ctx, nested, regions);
+            }
+        }
+    }
+}
+
+/// Simplify the control flow of regions nested (at any depth) in `root`.
+/// See [module](self) documentation. Values defined in blocks that are erased
+/// must not be used outside them (which holds when definitions dominate their uses).
+/// Returns the number of branches rewritten and blocks erased.
+pub fn simplify_cfg(ctx: &mut Context, root: Ptr<Operation>) -> usize {
+    let mut regions = vec![];
+    collect_regions(ctx, root, &mut regions);
+    let mut num_simplified = 0;
+    // Inner regions first, as they may be in blocks erased from outer ones.
+    for region in regions.into_iter().rev() {
+        loop {
+            let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+            let num_rewritten = blocks
+                .into_iter()
+                .filter(|block| simplify_terminator(ctx, *block))
+                .count();
+            if num_rewritten == 0 {
+                break;
+            }
+            num_simplified += num_rewritten;
+        }
+
+        num_simplified += erase_unreachable_blocks(ctx, region);
+    }
+    num_simplified
+}
+
+/// A [Pass] running [simplify_cfg].
+/// Its [statistics](Pass::statistics) count the branches and blocks `simplified`.
+#[derive(Default)]
+pub struct SimplifyCfgPass {
+    num_simplified: u64,
+}
+
+impl Pass for SimplifyCfgPass {
+    fn name(&self) -> &str {
+        "llvm-simplify-cfg"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_simplified += simplify_cfg(ctx, op) as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("simplified", self.num_simplified);
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pliron::{
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            op_interfaces::OneRegionInterface,
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::Context,
+        linked_list::ContainsLinkedList,
+        op::Op,
+        operation::Operation,
+    };
+
+    use crate::{
+        self as llvm,
+        ops::{BrOp, CondBrOp, ReturnOp, UnreachableOp},
+    };
+
+    use super::simplify_cfg;
+
+    #[test]
+    fn test_simplify_cfg() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        // entry: cond_br c, live, dead; dead: br unr; unr: unreachable; live: ret x
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless).into();
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless).into();
+        let func_ty = FunctionType::get(ctx, vec![i1_ty, i32_ty], vec![i32_ty]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let region = func.region(ctx);
+        let entry = func.get_entry_block(ctx);
+        let [dead, unr, live] = [(); 3].map(|_| {
+            let block = BasicBlock::new(ctx, None, vec![]);
+            block.insert_at_back(region, ctx);
+            block
+        });
+        let (c, x) = (entry.deref(ctx).argument(0), entry.deref(ctx).argument(1));
+        CondBrOp::new(ctx, c, live, vec![], dead, vec![])
+            .operation()
+            .insert_at_back(entry, ctx);
+        BrOp::new(ctx, unr, vec![])
+            .operation()
+            .insert_at_back(dead, ctx);
+        UnreachableOp::new(ctx).operation().insert_at_back(unr, ctx);
+        ReturnOp::new(ctx, Some(x))
+            .operation()
+            .insert_at_back(live, ctx);
+        func.operation().verify(ctx).unwrap();
+
+        // Two branches rewritten, and two blocks erased.
+        assert_eq!(simplify_cfg(ctx, func.operation()), 4);
+        func.operation().verify(ctx).unwrap();
+        let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+        assert_eq!(blocks, vec![entry, live]);
+        let term = entry.deref(ctx).tail().unwrap();
+        assert!(Operation::op(term, ctx).is::<BrOp>());
+        assert_eq!(term.deref(ctx).successor(0), live);
+        assert_eq!(simplify_cfg(ctx, func.operation()), 0);
+
+        // A branch to an unreachable block is itself unreachable.
+        let unr = BasicBlock::new(ctx, None, vec![]);
+        unr.insert_at_back(region, ctx);
+        UnreachableOp::new(ctx).operation().insert_at_back(unr, ctx);
+        Operation::erase(term, ctx);
+        BrOp::new(ctx, unr, vec![])
+            .operation()
+            .insert_at_back(entry, ctx);
+        assert_eq!(simplify_cfg(ctx, func.operation()), 3);
+        let term = entry.deref(ctx).tail().unwrap();
+        assert!(Operation::op(term, ctx).is::<UnreachableOp>());
+        assert_eq!(region.deref(ctx).iter(ctx).count(), 1);
+    }
+}
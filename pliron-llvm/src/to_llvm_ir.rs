@@ -1,16 +1,19 @@
 //! Translate from pliron's LLVM dialect to LLVM-IR
 
-use llvm_sys::LLVMIntPredicate;
+use llvm_sys::{LLVMAtomicOrdering, LLVMAtomicRMWBinOp, LLVMIntPredicate, LLVMLinkage};
 use pliron::{
+    attribute::AttrObj,
     basic_block::BasicBlock,
     builtin::{
-        attributes::{FloatAttr, IntegerAttr},
+        attributes::{FloatAttr, IdentifierAttr, IntegerAttr, StringAttr},
         op_interfaces::{
             BranchOpInterface, CallOpCallable, CallOpInterface, OneOpdInterface,
-            OneRegionInterface, OneResultInterface, SingleBlockRegionInterface, SymbolOpInterface,
+            OneRegionInterface, OneResultInterface, OperandBundleInterface,
+            SingleBlockRegionInterface, SymbolOpInterface,
         },
         ops::{FuncOp, ModuleOp},
-        types::{FunctionType, IntegerType},
+        type_interfaces::ATTR_KEY_DATA_LAYOUT,
+        types::{FloatType, FunctionType, IntegerType},
     },
     common_traits::Named,
     context::{Context, Ptr},
@@ -18,12 +21,15 @@ use pliron::{
     identifier::Identifier,
     input_err, input_err_noloc, input_error_noloc,
     linked_list::{ContainsLinkedList, LinkedList},
-    location::Located,
+    location::{Located, Location, SourceMap},
     op::{Op, op_cast},
     operation::Operation,
     result::Result,
     r#type::{Type, TypeObj, TypePtr, Typed, type_cast},
-    utils::apint::APInt,
+    utils::{
+        apfloat::{APFloat, FloatSemantics},
+        apint::APInt,
+    },
     value::Value,
 };
 
@@ -32,28 +38,44 @@ use rustc_hash::FxHashMap;
 use thiserror::Error;
 
 use crate::{
-    attributes::ICmpPredicateAttr,
+    attributes::{
+        AtomicOrderingAttr, AtomicRmwBinOpAttr, ConstantVectorAttr, ICmpPredicateAttr,
+        ShuffleMaskElemAttr,
+    },
     llvm_sys::core::{
-        LLVMBasicBlock, LLVMBuilder, LLVMContext, LLVMModule, LLVMType, LLVMValue,
-        instruction_iter, llvm_add_function, llvm_add_incoming, llvm_append_basic_block_in_context,
-        llvm_array_type2, llvm_build_add, llvm_build_and, llvm_build_array_alloca,
-        llvm_build_bitcast, llvm_build_br, llvm_build_call2, llvm_build_cond_br,
-        llvm_build_extract_value, llvm_build_gep2, llvm_build_icmp, llvm_build_insert_value,
-        llvm_build_load2, llvm_build_mul, llvm_build_or, llvm_build_phi, llvm_build_ret,
+        InstructionIter, LLVMBasicBlock, LLVMBuilder, LLVMContext, LLVMModule, LLVMOperandBundle,
+        LLVMType, LLVMValue, basic_block_iter, function_iter, instruction_iter, llvm_add_clause,
+        llvm_add_function, llvm_add_global, llvm_add_incoming, llvm_append_basic_block_in_context,
+        llvm_array_type2, llvm_bfloat_type_in_context, llvm_build_add, llvm_build_and,
+        llvm_build_array_alloca, llvm_build_atomic_cmp_xchg, llvm_build_atomic_rmw,
+        llvm_build_bitcast, llvm_build_br, llvm_build_call_with_operand_bundles,
+        llvm_build_cond_br, llvm_build_extract_element, llvm_build_extract_value, llvm_build_gep2,
+        llvm_build_icmp, llvm_build_insert_element, llvm_build_insert_value,
+        llvm_build_invoke_with_operand_bundles, llvm_build_landing_pad, llvm_build_load2,
+        llvm_build_mul, llvm_build_or, llvm_build_phi, llvm_build_resume, llvm_build_ret,
         llvm_build_ret_void, llvm_build_sdiv, llvm_build_select, llvm_build_sext, llvm_build_shl,
-        llvm_build_srem, llvm_build_store, llvm_build_sub, llvm_build_udiv, llvm_build_urem,
-        llvm_build_xor, llvm_clear_insertion_position, llvm_const_int, llvm_function_type,
-        llvm_get_param, llvm_get_undef, llvm_int_type_in_context, llvm_is_a,
-        llvm_pointer_type_in_context, llvm_position_builder_at_end, llvm_struct_create_named,
-        llvm_struct_set_body, llvm_struct_type_in_context, llvm_void_type_in_context,
+        llvm_build_shuffle_vector, llvm_build_srem, llvm_build_store, llvm_build_sub,
+        llvm_build_udiv, llvm_build_unreachable, llvm_build_urem, llvm_build_xor,
+        llvm_clear_insertion_position, llvm_const_bit_cast, llvm_const_int, llvm_const_vector,
+        llvm_double_type_in_context, llvm_float_type_in_context, llvm_function_type,
+        llvm_get_basic_block_name, llvm_get_first_basic_block, llvm_get_first_instruction,
+        llvm_get_last_instruction, llvm_get_next_instruction, llvm_get_param, llvm_get_poison,
+        llvm_get_undef, llvm_half_type_in_context, llvm_int_type_in_context, llvm_is_a,
+        llvm_pointer_type_in_context, llvm_position_builder_at_end, llvm_print_value_to_string,
+        llvm_set_cleanup, llvm_set_data_layout, llvm_set_global_constant, llvm_set_initializer,
+        llvm_set_linkage, llvm_set_ordering, llvm_set_personality_fn, llvm_set_volatile,
+        llvm_struct_create_named, llvm_struct_set_body, llvm_struct_type_in_context,
+        llvm_vector_type, llvm_void_type_in_context,
     },
-    op_interfaces::PointerTypeResult,
+    op_interfaces::{MemoryAccessOpInterface, PointerTypeResult},
     ops::{
-        AddOp, AllocaOp, AndOp, BitcastOp, BrOp, CallOp, CondBrOp, ConstantOp, ExtractValueOp,
-        GetElementPtrOp, ICmpOp, InsertValueOp, LoadOp, MulOp, OrOp, ReturnOp, SDivOp, SExtOp,
-        SRemOp, SelectOp, ShlOp, StoreOp, SubOp, UDivOp, URemOp, UndefOp, XorOp, ZExtOp,
+        AddOp, AddressOfOp, AllocaOp, AndOp, AtomicCmpXchgOp, AtomicRmwOp, BitcastOp, BrOp, CallOp,
+        CondBrOp, ConstantOp, ExtractElementOp, ExtractValueOp, GetElementPtrOp, GlobalOp, ICmpOp,
+        InsertElementOp, InsertValueOp, InvokeOp, LandingPadOp, LoadOp, MulOp, OrOp, PoisonOp,
+        ResumeOp, ReturnOp, SDivOp, SExtOp, SRemOp, SelectOp, ShlOp, ShuffleVectorOp, StoreOp,
+        SubOp, UDivOp, URemOp, UndefOp, UnreachableOp, XorOp, ZExtOp, landing_pad_op,
     },
-    types::{ArrayType, PointerType, StructType, VoidType},
+    types::{ArrayType, PointerType, StructType, VectorType, VoidType},
 };
 
 /// Mapping from pliron entities to LLVM entities.
@@ -64,8 +86,14 @@ pub struct ConversionContext {
     block_map: FxHashMap<Ptr<BasicBlock>, LLVMBasicBlock>,
     // A map from pliron functions to LLVM functions.
     function_map: FxHashMap<Identifier, LLVMValue>,
+    // A map from pliron globals to LLVM globals.
+    global_map: FxHashMap<Identifier, LLVMValue>,
     // The active LLVM builder.
     builder: LLVMBuilder,
+    // Locations of the pliron entities that LLVM functions and instructions are converted from.
+    value_locs: FxHashMap<LLVMValue, Location>,
+    // Locations of the pliron basic blocks that LLVM basic blocks are converted from.
+    block_locs: FxHashMap<LLVMBasicBlock, Location>,
 }
 
 impl ConversionContext {
@@ -74,7 +102,10 @@ impl ConversionContext {
             value_map: FxHashMap::default(),
             block_map: FxHashMap::default(),
             function_map: FxHashMap::default(),
+            global_map: FxHashMap::default(),
             builder: LLVMBuilder::new(llvm_ctx),
+            value_locs: FxHashMap::default(),
+            block_locs: FxHashMap::default(),
         }
     }
 
@@ -97,7 +128,7 @@ pub enum ToLLVMErr {
     UndefinedBlock(String),
     #[error("Number of block args in the source dialect equal the number of PHIs in target IR")]
     NumBlockArgsNumPhisMismatch,
-    #[error("ConstantOp must have integer or float value")]
+    #[error("ConstantOp must have integer, float or constant vector value")]
     ConstOpNotIntOrFloat,
     #[error(
         "Insert/Extract value instructions must specify exactly one index, an LLVM-C API limitation"
@@ -120,6 +151,34 @@ pub fn convert_ipredicate(pred: ICmpPredicateAttr) -> LLVMIntPredicate {
     }
 }
 
+pub fn convert_atomic_ordering(ordering: AtomicOrderingAttr) -> LLVMAtomicOrdering {
+    match ordering {
+        AtomicOrderingAttr::NotAtomic => LLVMAtomicOrdering::LLVMAtomicOrderingNotAtomic,
+        AtomicOrderingAttr::Unordered => LLVMAtomicOrdering::LLVMAtomicOrderingUnordered,
+        AtomicOrderingAttr::Monotonic => LLVMAtomicOrdering::LLVMAtomicOrderingMonotonic,
+        AtomicOrderingAttr::Acquire => LLVMAtomicOrdering::LLVMAtomicOrderingAcquire,
+        AtomicOrderingAttr::Release => LLVMAtomicOrdering::LLVMAtomicOrderingRelease,
+        AtomicOrderingAttr::AcqRel => LLVMAtomicOrdering::LLVMAtomicOrderingAcquireRelease,
+        AtomicOrderingAttr::SeqCst => LLVMAtomicOrdering::LLVMAtomicOrderingSequentiallyConsistent,
+    }
+}
+
+pub fn convert_atomic_rmw_bin_op(bin_op: AtomicRmwBinOpAttr) -> LLVMAtomicRMWBinOp {
+    match bin_op {
+        AtomicRmwBinOpAttr::Xchg => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpXchg,
+        AtomicRmwBinOpAttr::Add => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpAdd,
+        AtomicRmwBinOpAttr::Sub => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpSub,
+        AtomicRmwBinOpAttr::And => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpAnd,
+        AtomicRmwBinOpAttr::Nand => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpNand,
+        AtomicRmwBinOpAttr::Or => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpOr,
+        AtomicRmwBinOpAttr::Xor => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpXor,
+        AtomicRmwBinOpAttr::Max => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpMax,
+        AtomicRmwBinOpAttr::Min => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpMin,
+        AtomicRmwBinOpAttr::UMax => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpUMax,
+        AtomicRmwBinOpAttr::UMin => LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpUMin,
+    }
+}
+
 /// A type that implements this is convertible to an [LLVMType].
 #[type_interface]
 trait ToLLVMType {
@@ -160,6 +219,18 @@ impl ToLLVMType for IntegerType {
     }
 }
 
+#[type_interface_impl]
+impl ToLLVMType for FloatType {
+    fn convert(&self, _ctx: &Context, llvm_ctx: &LLVMContext) -> Result<LLVMType> {
+        Ok(match self.semantics() {
+            FloatSemantics::Half => llvm_half_type_in_context(llvm_ctx),
+            FloatSemantics::BFloat => llvm_bfloat_type_in_context(llvm_ctx),
+            FloatSemantics::Single => llvm_float_type_in_context(llvm_ctx),
+            FloatSemantics::Double => llvm_double_type_in_context(llvm_ctx),
+        })
+    }
+}
+
 #[type_interface_impl]
 impl ToLLVMType for ArrayType {
     fn convert(&self, ctx: &Context, llvm_ctx: &LLVMContext) -> Result<LLVMType> {
@@ -168,6 +239,14 @@ impl ToLLVMType for ArrayType {
     }
 }
 
+#[type_interface_impl]
+impl ToLLVMType for VectorType {
+    fn convert(&self, ctx: &Context, llvm_ctx: &LLVMContext) -> Result<LLVMType> {
+        let elem_ty = convert_type(ctx, llvm_ctx, self.elem_type())?;
+        Ok(llvm_vector_type(elem_ty, self.num_elements()))
+    }
+}
+
 #[type_interface_impl]
 impl ToLLVMType for FunctionType {
     fn convert(&self, ctx: &Context, llvm_ctx: &LLVMContext) -> Result<LLVMType> {
@@ -223,7 +302,7 @@ impl ToLLVMType for StructType {
 
 /// Convert a pliron [Type] to [LLVMType].
 pub fn convert_type(ctx: &Context, llvm_ctx: &LLVMContext, ty: Ptr<TypeObj>) -> Result<LLVMType> {
-    if let Some(converter) = type_cast::<dyn ToLLVMType>(&**ty.deref(ctx)) {
+    if let Some(converter) = ty.cast::<dyn ToLLVMType>(ctx) {
         return converter.convert(ctx, llvm_ctx);
     }
     input_err_noloc!(ToLLVMErr::MissingTypeConversion(
@@ -304,7 +383,7 @@ impl ToLLVMValue for AllocaOp {
         llvm_ctx: &LLVMContext,
         cctx: &mut ConversionContext,
     ) -> Result<LLVMValue> {
-        let ty = convert_type(ctx, llvm_ctx, self.result_pointee_type(ctx))?;
+        let ty = convert_type(ctx, llvm_ctx, self.result_pointee_type(ctx)?)?;
         let size = convert_value_operand(cctx, ctx, &self.operand(ctx))?;
         let alloca_op =
             llvm_build_array_alloca(&cctx.builder, ty, size, &self.result(ctx).unique_name(ctx));
@@ -437,6 +516,8 @@ impl ToLLVMValue for LoadOp {
             ptr,
             &self.result(ctx).unique_name(ctx),
         );
+        llvm_set_volatile(load_op, self.is_volatile(ctx));
+        llvm_set_ordering(load_op, convert_atomic_ordering(self.atomic_ordering(ctx)));
         Ok(load_op)
     }
 }
@@ -452,10 +533,58 @@ impl ToLLVMValue for StoreOp {
         let value = convert_value_operand(cctx, ctx, &self.value_opd(ctx))?;
         let ptr = convert_value_operand(cctx, ctx, &self.address_opd(ctx))?;
         let store_op = llvm_build_store(&cctx.builder, value, ptr);
+        llvm_set_volatile(store_op, self.is_volatile(ctx));
+        llvm_set_ordering(store_op, convert_atomic_ordering(self.atomic_ordering(ctx)));
         Ok(store_op)
     }
 }
 
+#[op_interface_impl]
+impl ToLLVMValue for AtomicRmwOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let ptr = convert_value_operand(cctx, ctx, &self.address_opd(ctx))?;
+        let value = convert_value_operand(cctx, ctx, &self.value_opd(ctx))?;
+        let rmw_op = llvm_build_atomic_rmw(
+            &cctx.builder,
+            convert_atomic_rmw_bin_op(self.bin_op(ctx)),
+            ptr,
+            value,
+            convert_atomic_ordering(self.atomic_ordering(ctx)),
+        );
+        llvm_set_volatile(rmw_op, self.is_volatile(ctx));
+        Ok(rmw_op)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for AtomicCmpXchgOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let ptr = convert_value_operand(cctx, ctx, &self.address_opd(ctx))?;
+        let cmp = convert_value_operand(cctx, ctx, &self.cmp_opd(ctx))?;
+        let new = convert_value_operand(cctx, ctx, &self.new_opd(ctx))?;
+        let cmp_xchg_op = llvm_build_atomic_cmp_xchg(
+            &cctx.builder,
+            ptr,
+            cmp,
+            new,
+            convert_atomic_ordering(self.atomic_ordering(ctx)),
+            convert_atomic_ordering(self.failure_ordering(ctx)),
+        );
+        llvm_set_volatile(cmp_xchg_op, self.is_volatile(ctx));
+        Ok(cmp_xchg_op)
+    }
+}
+
 #[op_interface_impl]
 impl ToLLVMValue for ICmpOp {
     fn convert(
@@ -497,6 +626,56 @@ impl ToLLVMValue for ReturnOp {
     }
 }
 
+/// Convert an [IntegerAttr] to an LLVM integer constant.
+fn convert_integer_attr(
+    ctx: &Context,
+    llvm_ctx: &LLVMContext,
+    int_val: &IntegerAttr,
+) -> Result<LLVMValue> {
+    let int_ty = TypePtr::<IntegerType>::downcast(ctx, int_val.get_type(ctx))?;
+    let int_ty_llvm = convert_type(ctx, llvm_ctx, int_ty.into())?;
+    let ap_int_val: APInt = int_val.clone().into();
+    Ok(llvm_const_int(int_ty_llvm, ap_int_val.to_u64(), false))
+}
+
+/// Convert a [FloatAttr] to an LLVM floating point constant.
+/// The constant is built from the value's bit pattern, so that NaN payloads are preserved.
+fn convert_float_attr(
+    ctx: &Context,
+    llvm_ctx: &LLVMContext,
+    float_val: &FloatAttr,
+) -> Result<LLVMValue> {
+    let float_ty_llvm = convert_type(ctx, llvm_ctx, float_val.get_type(ctx))?;
+    let ap_float_val: APFloat = float_val.clone().into();
+    let width = ap_float_val.semantics().width();
+    let bits_ty_llvm = llvm_int_type_in_context(llvm_ctx, width.try_into().unwrap());
+    let bits = llvm_const_int(bits_ty_llvm, ap_float_val.to_bits(), false);
+    Ok(llvm_const_bit_cast(bits, float_ty_llvm))
+}
+
+/// Convert the value of a [ConstantOp] (or [GlobalOp] initializer) to an LLVM constant.
+fn convert_constant_value(
+    ctx: &Context,
+    llvm_ctx: &LLVMContext,
+    value: &AttrObj,
+    loc: Location,
+) -> Result<LLVMValue> {
+    if let Some(int_val) = value.downcast_ref::<IntegerAttr>() {
+        convert_integer_attr(ctx, llvm_ctx, int_val)
+    } else if let Some(vec_val) = value.downcast_ref::<ConstantVectorAttr>() {
+        let elems = vec_val
+            .elems()
+            .iter()
+            .map(|elem| convert_integer_attr(ctx, llvm_ctx, elem))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(llvm_const_vector(&elems))
+    } else if let Some(float_val) = value.downcast_ref::<FloatAttr>() {
+        convert_float_attr(ctx, llvm_ctx, float_val)
+    } else {
+        input_err!(loc, ToLLVMErr::ConstOpNotIntOrFloat)
+    }
+}
+
 #[op_interface_impl]
 impl ToLLVMValue for ConstantOp {
     fn convert(
@@ -505,18 +684,26 @@ impl ToLLVMValue for ConstantOp {
         llvm_ctx: &LLVMContext,
         _cctx: &mut ConversionContext,
     ) -> Result<LLVMValue> {
-        let op = self.operation().deref(ctx);
-        let value = self.get_value(ctx);
-        if let Some(int_val) = value.downcast_ref::<IntegerAttr>() {
-            let int_ty = TypePtr::<IntegerType>::from_ptr(int_val.get_type(ctx), ctx).unwrap();
-            let int_ty_llvm = convert_type(ctx, llvm_ctx, int_ty.into())?;
-            let ap_int_val: APInt = int_val.clone().into();
-            let const_val = llvm_const_int(int_ty_llvm, ap_int_val.to_u64(), false);
-            Ok(const_val)
-        } else if let Some(_float_val) = value.downcast_ref::<FloatAttr>() {
-            todo!()
-        } else {
-            input_err!(op.loc(), ToLLVMErr::ConstOpNotIntOrFloat)
+        convert_constant_value(ctx, llvm_ctx, &self.get_value(ctx), self.loc(ctx))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for AddressOfOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let name = self.get_global_name(ctx);
+        match cctx
+            .global_map
+            .get(&name)
+            .or_else(|| cctx.function_map.get(&name))
+        {
+            Some(global) => Ok(*global),
+            None => input_err!(self.loc(ctx), ToLLVMErr::UndefinedValue(name.to_string())),
         }
     }
 }
@@ -534,6 +721,38 @@ impl ToLLVMValue for UndefOp {
     }
 }
 
+#[op_interface_impl]
+impl ToLLVMValue for PoisonOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        llvm_ctx: &LLVMContext,
+        _cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let ty = convert_type(ctx, llvm_ctx, self.result_type(ctx))?;
+        Ok(llvm_get_poison(ty))
+    }
+}
+
+/// Convert the operand bundles of `op`.
+fn convert_operand_bundles(
+    ctx: &Context,
+    cctx: &mut ConversionContext,
+    op: &dyn OperandBundleInterface,
+) -> Result<Vec<LLVMOperandBundle>> {
+    op.operand_bundles(ctx)
+        .into_iter()
+        .map(|bundle| {
+            let operands: Vec<_> = bundle
+                .operands
+                .iter()
+                .map(|v| convert_value_operand(cctx, ctx, v))
+                .collect::<Result<_>>()?;
+            Ok(LLVMOperandBundle::new(&bundle.tag, &operands))
+        })
+        .collect()
+}
+
 #[op_interface_impl]
 impl ToLLVMValue for CallOp {
     fn convert(
@@ -542,27 +761,142 @@ impl ToLLVMValue for CallOp {
         llvm_ctx: &LLVMContext,
         cctx: &mut ConversionContext,
     ) -> Result<LLVMValue> {
-        if let CallOpCallable::Direct(callee_sym) = self.callee(ctx) {
-            let callee = *cctx.function_map.get(&callee_sym).ok_or_else(|| {
-                input_error_noloc!(ToLLVMErr::UndefinedValue(callee_sym.to_string()))
-            })?;
-            let args: Vec<_> = self
-                .args(ctx)
-                .into_iter()
-                .map(|v| convert_value_operand(cctx, ctx, &v))
-                .collect::<Result<_>>()?;
-            let ty = convert_type(ctx, llvm_ctx, self.callee_type(ctx).into())?;
-            let call_val = llvm_build_call2(
-                &cctx.builder,
-                ty,
-                callee,
-                &args,
-                &self.result(ctx).unique_name(ctx),
-            );
-            Ok(call_val)
-        } else {
-            todo!()
+        let callee = match self.callee(ctx) {
+            CallOpCallable::Direct(callee_sym) => {
+                *cctx.function_map.get(&callee_sym).ok_or_else(|| {
+                    input_error_noloc!(ToLLVMErr::UndefinedValue(callee_sym.to_string()))
+                })?
+            }
+            CallOpCallable::Indirect(callee_val) => convert_value_operand(cctx, ctx, &callee_val)?,
+        };
+        let args: Vec<_> = self
+            .args(ctx)
+            .into_iter()
+            .map(|v| convert_value_operand(cctx, ctx, &v))
+            .collect::<Result<_>>()?;
+        let ty = convert_type(ctx, llvm_ctx, self.callee_type(ctx).into())?;
+        let bundles = convert_operand_bundles(ctx, cctx, self)?;
+        let call_val = llvm_build_call_with_operand_bundles(
+            &cctx.builder,
+            ty,
+            callee,
+            &args,
+            &bundles,
+            &self.result(ctx).unique_name(ctx),
+        );
+        Ok(call_val)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for InvokeOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let op = self.operation().deref(ctx);
+        let callee = match self.callee(ctx) {
+            CallOpCallable::Direct(callee_sym) => {
+                *cctx.function_map.get(&callee_sym).ok_or_else(|| {
+                    input_error_noloc!(ToLLVMErr::UndefinedValue(callee_sym.to_string()))
+                })?
+            }
+            CallOpCallable::Indirect(callee_val) => convert_value_operand(cctx, ctx, &callee_val)?,
+        };
+        let args: Vec<_> = self
+            .args(ctx)
+            .into_iter()
+            .map(|v| convert_value_operand(cctx, ctx, &v))
+            .collect::<Result<_>>()?;
+        let ty = convert_type(ctx, llvm_ctx, self.callee_type(ctx).into())?;
+        let normal_dest_llvm = convert_block_operand(cctx, ctx, self.normal_dest(ctx))?;
+        let unwind_dest_llvm = convert_block_operand(cctx, ctx, self.unwind_dest(ctx))?;
+        let bundles = convert_operand_bundles(ctx, cctx, self)?;
+        let invoke_op = llvm_build_invoke_with_operand_bundles(
+            &cctx.builder,
+            ty,
+            callee,
+            &args,
+            normal_dest_llvm,
+            unwind_dest_llvm,
+            &bundles,
+            &self.result(ctx).unique_name(ctx),
+        );
+
+        // Link the arguments we pass to both successors with the PHIs there.
+        link_succ_operands_with_phis(
+            ctx,
+            cctx,
+            op.container().expect("Unlinked operation"),
+            normal_dest_llvm,
+            self.successor_operands(ctx, 0),
+        )?;
+        link_succ_operands_with_phis(
+            ctx,
+            cctx,
+            op.container().expect("Unlinked operation"),
+            unwind_dest_llvm,
+            self.successor_operands(ctx, 1),
+        )?;
+
+        Ok(invoke_op)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for LandingPadOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let ty = convert_type(ctx, llvm_ctx, self.result_type(ctx))?;
+        let clauses: Vec<_> = self
+            .clauses(ctx)
+            .iter()
+            .map(|clause| convert_value_operand(cctx, ctx, clause))
+            .collect::<Result<_>>()?;
+        // The personality function is set on the parent function.
+        let landing_pad_op = llvm_build_landing_pad(
+            &cctx.builder,
+            ty,
+            None,
+            clauses.len().try_into().unwrap(),
+            &self.result(ctx).unique_name(ctx),
+        );
+        for clause in clauses {
+            llvm_add_clause(landing_pad_op, clause);
         }
+        llvm_set_cleanup(landing_pad_op, self.is_cleanup(ctx));
+        Ok(landing_pad_op)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for UnreachableOp {
+    fn convert(
+        &self,
+        _ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        Ok(llvm_build_unreachable(&cctx.builder))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for ResumeOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let exn = convert_value_operand(cctx, ctx, &self.operand(ctx))?;
+        Ok(llvm_build_resume(&cctx.builder, exn))
     }
 }
 
@@ -680,6 +1014,80 @@ impl ToLLVMValue for ExtractValueOp {
     }
 }
 
+#[op_interface_impl]
+impl ToLLVMValue for ExtractElementOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let vector = convert_value_operand(cctx, ctx, &self.vector_opd(ctx))?;
+        let index = convert_value_operand(cctx, ctx, &self.index_opd(ctx))?;
+        let extract_op = llvm_build_extract_element(
+            &cctx.builder,
+            vector,
+            index,
+            &self.result(ctx).unique_name(ctx),
+        );
+        Ok(extract_op)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for InsertElementOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        _llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let vector = convert_value_operand(cctx, ctx, &self.vector_opd(ctx))?;
+        let value = convert_value_operand(cctx, ctx, &self.value_opd(ctx))?;
+        let index = convert_value_operand(cctx, ctx, &self.index_opd(ctx))?;
+        let insert_op = llvm_build_insert_element(
+            &cctx.builder,
+            vector,
+            value,
+            index,
+            &self.result(ctx).unique_name(ctx),
+        );
+        Ok(insert_op)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMValue for ShuffleVectorOp {
+    fn convert(
+        &self,
+        ctx: &Context,
+        llvm_ctx: &LLVMContext,
+        cctx: &mut ConversionContext,
+    ) -> Result<LLVMValue> {
+        let op = self.operation().deref(ctx);
+        let v1 = convert_value_operand(cctx, ctx, &op.operand(0))?;
+        let v2 = convert_value_operand(cctx, ctx, &op.operand(1))?;
+        // The mask is a constant vector of i32s, with undef for poison elements.
+        let i32_ty = llvm_int_type_in_context(llvm_ctx, 32);
+        let mask: Vec<_> = self
+            .mask(ctx)
+            .into_iter()
+            .map(|elem| match elem {
+                ShuffleMaskElemAttr::Index(idx) => llvm_const_int(i32_ty, idx.into(), false),
+                ShuffleMaskElemAttr::Poison => llvm_get_undef(i32_ty),
+            })
+            .collect();
+        let shuffle_op = llvm_build_shuffle_vector(
+            &cctx.builder,
+            v1,
+            v2,
+            llvm_const_vector(&mask),
+            &self.result(ctx).unique_name(ctx),
+        );
+        Ok(shuffle_op)
+    }
+}
+
 #[op_interface_impl]
 impl ToLLVMValue for SelectOp {
     fn convert(
@@ -721,7 +1129,16 @@ fn convert_block(
         let Some(op_conv) = op_cast::<dyn ToLLVMValue>(&*op) else {
             return input_err!(loc, ToLLVMErr::MissingOpConversion(op.opid().to_string()));
         };
+        let prev_last = llvm_get_last_instruction(block_llvm);
         let op_iw = op_conv.convert(ctx, llvm_ctx, cctx)?;
+        // Map all instructions that the operation is converted to, to its location.
+        let first_new = match prev_last {
+            Some(prev_last) => llvm_get_next_instruction(prev_last),
+            None => llvm_get_first_instruction(block_llvm),
+        };
+        for inst in InstructionIter(first_new) {
+            cctx.value_locs.insert(inst, loc.clone());
+        }
         let op_ref = &*op.operation().deref(ctx);
         // LLVM instructions have at most one result.
         if op_ref.num_results() == 1 {
@@ -741,6 +1158,21 @@ fn convert_function(
 ) -> Result<LLVMValue> {
     cctx.clear_per_function_data();
     let func_llvm = cctx.function_map[&func_op.symbol_name(ctx)];
+    cctx.value_locs
+        .insert(func_llvm, func_op.operation().deref(ctx).loc());
+
+    if let Some(personality) = func_op
+        .operation()
+        .deref(ctx)
+        .attributes
+        .get::<IdentifierAttr>(&landing_pad_op::ATTR_KEY_PERSONALITY)
+    {
+        let personality: Identifier = personality.clone().into();
+        let personality_llvm = *cctx.function_map.get(&personality).ok_or_else(|| {
+            input_error_noloc!(ToLLVMErr::UndefinedValue(personality.to_string()))
+        })?;
+        llvm_set_personality_fn(func_llvm, personality_llvm);
+    }
 
     // Map all blocks, staring with entry.
     let mut block_iter = func_op.region(ctx).deref(ctx).iter(ctx);
@@ -758,6 +1190,8 @@ fn convert_function(
             &entry.deref(ctx).unique_name(ctx),
         );
         cctx.block_map.insert(entry, llvm_entry_block);
+        cctx.block_locs
+            .insert(llvm_entry_block, entry.deref(ctx).loc());
     }
     for block in block_iter {
         let llvm_block = llvm_append_basic_block_in_context(
@@ -770,8 +1204,10 @@ fn convert_function(
             let arg_type = convert_type(ctx, llvm_ctx, arg.get_type(ctx))?;
             let phi = llvm_build_phi(&cctx.builder, arg_type, &arg.unique_name(ctx));
             cctx.value_map.insert(arg, phi);
+            cctx.value_locs.insert(phi, block.deref(ctx).loc());
         }
         cctx.block_map.insert(block, llvm_block);
+        cctx.block_locs.insert(llvm_block, block.deref(ctx).loc());
     }
 
     // Convert within every block.
@@ -787,10 +1223,88 @@ pub fn convert_module(
     ctx: &Context,
     llvm_ctx: &LLVMContext,
     module: ModuleOp,
+) -> Result<LLVMModule> {
+    let cctx = &mut ConversionContext::new(llvm_ctx);
+    convert_module_with_context(ctx, llvm_ctx, cctx, module)
+}
+
+/// Convert pliron [ModuleOp] to [LLVMModule], along with a [SourceMap] from the
+/// lines of its [text assembly](LLVMModule::asm_to_string) to the [Location]s of
+/// the functions, blocks and operations that they're converted from.
+pub fn convert_module_with_source_map(
+    ctx: &Context,
+    llvm_ctx: &LLVMContext,
+    module: ModuleOp,
+) -> Result<(LLVMModule, SourceMap)> {
+    let cctx = &mut ConversionContext::new(llvm_ctx);
+    let llvm_module = convert_module_with_context(ctx, llvm_ctx, cctx, module)?;
+    let source_map = build_source_map(cctx, &llvm_module);
+    Ok((llvm_module, source_map))
+}
+
+/// Map the lines of the text assembly of `llvm_module` to the locations
+/// recorded in `cctx`. Functions, blocks and instructions are printed in
+/// order, so each is searched for after the line of the previous one.
+fn build_source_map(cctx: &ConversionContext, llvm_module: &LLVMModule) -> SourceMap {
+    let asm = llvm_module.asm_to_string();
+    let lines: Vec<_> = asm.lines().collect();
+    let mut source_map = SourceMap::new();
+    let mut next_line = 0;
+    let mut map_next_line = |is_line: &dyn Fn(&str) -> bool, loc: Option<&Location>| {
+        let Some(idx) = lines[next_line..].iter().position(|line| is_line(line)) else {
+            return;
+        };
+        next_line += idx + 1;
+        if let Some(loc) = loc {
+            // Lines are numbered from 1.
+            source_map.add(next_line, loc.clone());
+        }
+    };
+
+    for func in function_iter(llvm_module) {
+        if llvm_get_first_basic_block(func).is_none() {
+            continue;
+        }
+        map_next_line(
+            &|line| line.starts_with("define "),
+            cctx.value_locs.get(&func),
+        );
+        for block in basic_block_iter(func) {
+            let label = llvm_get_basic_block_name(block).unwrap_or_default();
+            if !label.is_empty() {
+                let label = format!("{label}:");
+                map_next_line(
+                    &|line| line.starts_with(&label),
+                    cctx.block_locs.get(&block),
+                );
+            }
+            for inst in instruction_iter(block) {
+                let inst_str = llvm_print_value_to_string(inst);
+                let inst_line = inst_str.lines().next().unwrap_or_default().trim();
+                map_next_line(&|line| line.trim() == inst_line, cctx.value_locs.get(&inst));
+            }
+        }
+    }
+    source_map
+}
+
+fn convert_module_with_context(
+    ctx: &Context,
+    llvm_ctx: &LLVMContext,
+    cctx: &mut ConversionContext,
+    module: ModuleOp,
 ) -> Result<LLVMModule> {
     let mod_name = module.symbol_name(ctx);
     let llvm_module = LLVMModule::new(&mod_name, llvm_ctx);
-    let cctx = &mut ConversionContext::new(llvm_ctx);
+
+    if let Some(data_layout) = module
+        .operation()
+        .deref(ctx)
+        .attributes
+        .get::<StringAttr>(&ATTR_KEY_DATA_LAYOUT)
+    {
+        llvm_set_data_layout(&llvm_module, &String::from(data_layout.clone()));
+    }
 
     // Create new functions and map them.
     for op in module.body(ctx, 0).deref(ctx).iter(ctx) {
@@ -804,15 +1318,28 @@ pub fn convert_module(
             let func_llvm = llvm_add_function(&llvm_module, &func_op.symbol_name(ctx), fn_ty_llvm);
             cctx.function_map
                 .insert(func_op.symbol_name(ctx), func_llvm);
+        } else if let Some(global_op) = Operation::op(op, ctx).downcast_ref::<GlobalOp>() {
+            let ty_llvm = convert_type(ctx, llvm_ctx, global_op.get_type(ctx))?;
+            let global_llvm = llvm_add_global(&llvm_module, ty_llvm, &global_op.symbol_name(ctx));
+            llvm_set_global_constant(global_llvm, global_op.is_constant(ctx));
+            if global_op.is_private(ctx) {
+                llvm_set_linkage(global_llvm, LLVMLinkage::LLVMPrivateLinkage);
+            }
+            cctx.value_locs.insert(global_llvm, global_op.loc(ctx));
+            cctx.global_map
+                .insert(global_op.symbol_name(ctx), global_llvm);
         }
-        // TODO: Globals?
     }
 
     for op in module.body(ctx, 0).deref(ctx).iter(ctx) {
         if let Some(func_op) = Operation::op(op, ctx).downcast_ref::<FuncOp>() {
             convert_function(ctx, llvm_ctx, cctx, *func_op)?;
+        } else if let Some(global_op) = Operation::op(op, ctx).downcast_ref::<GlobalOp>()
+            && let Some(init) = global_op.get_initializer(ctx)
+        {
+            let init_llvm = convert_constant_value(ctx, llvm_ctx, &init, global_op.loc(ctx))?;
+            llvm_set_initializer(cctx.global_map[&global_op.symbol_name(ctx)], init_llvm);
         }
-        // TODO: Globals
     }
 
     Ok(llvm_module)
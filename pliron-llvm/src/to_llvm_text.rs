@@ -0,0 +1,1072 @@
+//! Translate from pliron's LLVM dialect to textual LLVM-IR (`.ll`).
+//!
+//! Unlike [to_llvm_ir](crate::to_llvm_ir), this doesn't go through the LLVM-C API,
+//! so it works without linking LLVM. The emitted text can be fed to any LLVM tool.
+//!
+//! Values are named after their [unique_name](Named::unique_name)s, and blocks
+//! are labelled likewise. Constants ([ConstantOp], [UndefOp], [PoisonOp] and the
+//! [addresses](AddressOfOp) of globals) aren't instructions in LLVM-IR, so they're
+//! printed inline at their uses. The arguments of the entry block are the function
+//! parameters, and those of other blocks are `phi`s, with incoming values from the
+//! [BranchOpInterface] terminators of their predecessors. A function whose body is a
+//! single empty block is a declaration, as is a [GlobalOp] without an initializer.
+
+use pliron::{
+    attribute::AttrObj,
+    basic_block::BasicBlock,
+    builtin::{
+        attributes::{IdentifierAttr, IntegerAttr, StringAttr},
+        op_interfaces::{
+            BranchOpInterface, CallOpCallable, CallOpInterface, OneOpdInterface,
+            OneRegionInterface, OneResultInterface, OperandBundleInterface,
+            SingleBlockRegionInterface, SymbolOpInterface,
+        },
+        ops::{FuncOp, ModuleOp},
+        type_interfaces::{ATTR_KEY_DATA_LAYOUT, DataLayout, align_of},
+        types::{FunctionType, IntegerType},
+    },
+    common_traits::Named,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    input_err, input_err_noloc,
+    linked_list::ContainsLinkedList,
+    location::{Located, Location},
+    op::{Op, op_cast},
+    operation::Operation,
+    result::Result,
+    r#type::{Type, TypeObj, Typed},
+    utils::apint::APInt,
+    value::Value,
+};
+
+use pliron::derive::{op_interface, op_interface_impl, type_interface, type_interface_impl};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    attributes::{
+        AtomicOrderingAttr, AtomicRmwBinOpAttr, ConstantVectorAttr, ICmpPredicateAttr,
+        IntegerOverflowFlagsAttr, ShuffleMaskElemAttr,
+    },
+    op_interfaces::{IntBinArithOpWithOverflowFlag, MemoryAccessOpInterface, PointerTypeResult},
+    ops::{
+        AShrOp, AddOp, AddressOfOp, AllocaOp, AndOp, AtomicCmpXchgOp, AtomicRmwOp, BitcastOp, BrOp,
+        CallOp, CondBrOp, ConstantOp, ExtractElementOp, ExtractValueOp, GepIndex, GetElementPtrOp,
+        GlobalOp, ICmpOp, InsertElementOp, InsertValueOp, InvokeOp, LShrOp, LandingPadOp, LoadOp,
+        MulOp, OrOp, PoisonOp, ResumeOp, ReturnOp, SDivOp, SExtOp, SRemOp, SelectOp, ShlOp,
+        ShuffleVectorOp, StoreOp, SubOp, UDivOp, URemOp, UndefOp, UnreachableOp, XorOp, ZExtOp,
+        landing_pad_op,
+    },
+    to_llvm_ir::ToLLVMErr,
+    types::{ArrayType, PointerType, StructType, VectorType, VoidType},
+};
+
+/// Text of the pliron entities being emitted.
+#[derive(Default)]
+pub struct TextContext {
+    // Values, as they're referred to: names, or inline constants.
+    value_map: FxHashMap<Value, String>,
+    // Labels of the basic blocks.
+    block_map: FxHashMap<Ptr<BasicBlock>, String>,
+    // Definitions of the named struct types used so far.
+    type_defs: Vec<String>,
+    // Names of the struct types in `type_defs`.
+    defined_types: FxHashSet<Identifier>,
+}
+
+impl TextContext {
+    fn clear_per_function_data(&mut self) {
+        self.value_map.clear();
+        self.block_map.clear();
+    }
+}
+
+/// LLVM-IR text of an integer comparison predicate.
+pub fn ipredicate_text(pred: ICmpPredicateAttr) -> &'static str {
+    match pred {
+        ICmpPredicateAttr::EQ => "eq",
+        ICmpPredicateAttr::NE => "ne",
+        ICmpPredicateAttr::UGT => "ugt",
+        ICmpPredicateAttr::UGE => "uge",
+        ICmpPredicateAttr::ULT => "ult",
+        ICmpPredicateAttr::ULE => "ule",
+        ICmpPredicateAttr::SGT => "sgt",
+        ICmpPredicateAttr::SGE => "sge",
+        ICmpPredicateAttr::SLT => "slt",
+        ICmpPredicateAttr::SLE => "sle",
+    }
+}
+
+/// LLVM-IR text of an atomic ordering. Empty for [AtomicOrderingAttr::NotAtomic].
+pub fn atomic_ordering_text(ordering: AtomicOrderingAttr) -> &'static str {
+    match ordering {
+        AtomicOrderingAttr::NotAtomic => "",
+        AtomicOrderingAttr::Unordered => "unordered",
+        AtomicOrderingAttr::Monotonic => "monotonic",
+        AtomicOrderingAttr::Acquire => "acquire",
+        AtomicOrderingAttr::Release => "release",
+        AtomicOrderingAttr::AcqRel => "acq_rel",
+        AtomicOrderingAttr::SeqCst => "seq_cst",
+    }
+}
+
+/// LLVM-IR text of an `atomicrmw` operation.
+pub fn atomic_rmw_bin_op_text(bin_op: AtomicRmwBinOpAttr) -> &'static str {
+    match bin_op {
+        AtomicRmwBinOpAttr::Xchg => "xchg",
+        AtomicRmwBinOpAttr::Add => "add",
+        AtomicRmwBinOpAttr::Sub => "sub",
+        AtomicRmwBinOpAttr::And => "and",
+        AtomicRmwBinOpAttr::Nand => "nand",
+        AtomicRmwBinOpAttr::Or => "or",
+        AtomicRmwBinOpAttr::Xor => "xor",
+        AtomicRmwBinOpAttr::Max => "max",
+        AtomicRmwBinOpAttr::Min => "min",
+        AtomicRmwBinOpAttr::UMax => "umax",
+        AtomicRmwBinOpAttr::UMin => "umin",
+    }
+}
+
+/// A type that implements this can be written as LLVM-IR text.
+#[type_interface]
+trait ToLLVMTextType {
+    /// Emit the LLVM-IR text of this [Type].
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String>;
+
+    fn verify(_type: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// An [Op] that implements this can be written as an LLVM-IR instruction.
+#[op_interface]
+trait ToLLVMTextInst {
+    /// Emit the LLVM-IR text of this [Op], without the name of the result.
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// An [Op] that implements this is an LLVM-IR constant, written at its uses.
+#[op_interface]
+trait ToLLVMTextConst {
+    /// Emit the LLVM-IR text of the constant this [Op] defines, without its type.
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for IntegerType {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok(format!("i{}", self.width()))
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for ArrayType {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let elem_ty = convert_type(ctx, tctx, self.elem_type())?;
+        Ok(format!("[{} x {}]", self.size(), elem_ty))
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for VectorType {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let elem_ty = convert_type(ctx, tctx, self.elem_type())?;
+        Ok(format!("<{} x {}>", self.num_elements(), elem_ty))
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for FunctionType {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let args_tys = self
+            .inputs()
+            .iter()
+            .map(|ty| convert_type(ctx, tctx, *ty))
+            .collect::<Result<Vec<_>>>()?;
+        let ret_ty = return_type(ctx, tctx, self)?;
+        Ok(format!("{} ({})", ret_ty, args_tys.join(", ")))
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for VoidType {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok("void".to_string())
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for PointerType {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok("ptr".to_string())
+    }
+}
+
+#[type_interface_impl]
+impl ToLLVMTextType for StructType {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let body = |tctx: &mut TextContext| -> Result<String> {
+            let field_types = self
+                .fields()
+                .map(|fty| convert_type(ctx, tctx, fty))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("{{ {} }}", field_types.join(", ")))
+        };
+        let Some(name) = self.name() else {
+            return body(tctx);
+        };
+        // Define a named struct on its first use. Mark it defined before converting
+        // the fields, in case they refer to it.
+        if tctx.defined_types.insert(name) {
+            let def = if self.is_opaque() {
+                "opaque".to_string()
+            } else {
+                body(tctx)?
+            };
+            tctx.type_defs.push(format!("%{name} = type {def}"));
+        }
+        Ok(format!("%{name}"))
+    }
+}
+
+/// Convert a pliron [Type] to LLVM-IR text.
+pub fn convert_type(ctx: &Context, tctx: &mut TextContext, ty: Ptr<TypeObj>) -> Result<String> {
+    if let Some(converter) = ty.cast::<dyn ToLLVMTextType>(ctx) {
+        return converter.emit(ctx, tctx);
+    }
+    input_err_noloc!(ToLLVMErr::MissingTypeConversion(
+        ty.deref(ctx).get_type_id().to_string()
+    ))
+}
+
+/// The return type, in LLVM-IR text, of functions of type `func_ty`.
+fn return_type(ctx: &Context, tctx: &mut TextContext, func_ty: &FunctionType) -> Result<String> {
+    match func_ty.results().first() {
+        Some(ty) => convert_type(ctx, tctx, *ty),
+        None => Ok("void".to_string()),
+    }
+}
+
+/// Does `ty` stand for the lack of a value?
+fn is_void(ctx: &Context, ty: Ptr<TypeObj>) -> bool {
+    ty.deref(ctx).is::<VoidType>()
+}
+
+fn convert_value_operand(ctx: &Context, tctx: &TextContext, value: &Value) -> Result<String> {
+    match tctx.value_map.get(value) {
+        Some(v) => Ok(v.clone()),
+        None => {
+            input_err_noloc!(ToLLVMErr::UndefinedValue(value.unique_name(ctx).into()))
+        }
+    }
+}
+
+/// Convert `value` to the text of an operand along with its type, as in `i32 %x`.
+fn convert_typed_operand(ctx: &Context, tctx: &mut TextContext, value: &Value) -> Result<String> {
+    let ty = convert_type(ctx, tctx, value.get_type(ctx))?;
+    Ok(format!(
+        "{} {}",
+        ty,
+        convert_value_operand(ctx, tctx, value)?
+    ))
+}
+
+fn convert_typed_operands(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    values: &[Value],
+) -> Result<String> {
+    let values = values
+        .iter()
+        .map(|v| convert_typed_operand(ctx, tctx, v))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(values.join(", "))
+}
+
+fn convert_block_operand(
+    ctx: &Context,
+    tctx: &TextContext,
+    block: Ptr<BasicBlock>,
+) -> Result<String> {
+    match tctx.block_map.get(&block) {
+        Some(label) => Ok(format!("label %{label}")),
+        None => {
+            input_err_noloc!(ToLLVMErr::UndefinedBlock(block.unique_name(ctx).into()))
+        }
+    }
+}
+
+macro_rules! to_llvm_text_int_bin_op {
+    (
+        $op_name:ident, $opcode:literal
+    ) => {
+        #[pliron::derive::op_interface_impl]
+        impl ToLLVMTextInst for $op_name {
+            fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+                let op = self.operation().deref(ctx);
+                let lhs = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+                let rhs = convert_value_operand(ctx, tctx, &op.operand(1))?;
+                Ok(format!("{} {}, {}", $opcode, lhs, rhs))
+            }
+        }
+    };
+}
+
+macro_rules! to_llvm_text_int_bin_op_with_overflow {
+    (
+        $op_name:ident, $opcode:literal
+    ) => {
+        #[pliron::derive::op_interface_impl]
+        impl ToLLVMTextInst for $op_name {
+            fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+                let op = self.operation().deref(ctx);
+                let flag = match self.integer_overflow_flag(ctx) {
+                    IntegerOverflowFlagsAttr::None => "",
+                    IntegerOverflowFlagsAttr::Nsw => " nsw",
+                    IntegerOverflowFlagsAttr::Nuw => " nuw",
+                };
+                let lhs = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+                let rhs = convert_value_operand(ctx, tctx, &op.operand(1))?;
+                Ok(format!("{}{} {}, {}", $opcode, flag, lhs, rhs))
+            }
+        }
+    };
+}
+
+to_llvm_text_int_bin_op_with_overflow!(AddOp, "add");
+to_llvm_text_int_bin_op_with_overflow!(SubOp, "sub");
+to_llvm_text_int_bin_op_with_overflow!(MulOp, "mul");
+to_llvm_text_int_bin_op_with_overflow!(ShlOp, "shl");
+to_llvm_text_int_bin_op!(SDivOp, "sdiv");
+to_llvm_text_int_bin_op!(UDivOp, "udiv");
+to_llvm_text_int_bin_op!(URemOp, "urem");
+to_llvm_text_int_bin_op!(SRemOp, "srem");
+to_llvm_text_int_bin_op!(AndOp, "and");
+to_llvm_text_int_bin_op!(OrOp, "or");
+to_llvm_text_int_bin_op!(XorOp, "xor");
+to_llvm_text_int_bin_op!(LShrOp, "lshr");
+to_llvm_text_int_bin_op!(AShrOp, "ashr");
+
+macro_rules! to_llvm_text_cast_op {
+    (
+        $op_name:ident, $opcode:literal
+    ) => {
+        #[pliron::derive::op_interface_impl]
+        impl ToLLVMTextInst for $op_name {
+            fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+                let arg = convert_typed_operand(ctx, tctx, &self.operand(ctx))?;
+                let ty = convert_type(ctx, tctx, self.result_type(ctx))?;
+                Ok(format!("{} {} to {}", $opcode, arg, ty))
+            }
+        }
+    };
+}
+
+to_llvm_text_cast_op!(BitcastOp, "bitcast");
+to_llvm_text_cast_op!(SExtOp, "sext");
+to_llvm_text_cast_op!(ZExtOp, "zext");
+
+#[op_interface_impl]
+impl ToLLVMTextInst for AllocaOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let ty = convert_type(ctx, tctx, self.result_pointee_type(ctx)?)?;
+        let size = convert_typed_operand(ctx, tctx, &self.operand(ctx))?;
+        Ok(format!("alloca {ty}, {size}"))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for BrOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let succ = self.operation().deref(ctx).successor(0);
+        Ok(format!("br {}", convert_block_operand(ctx, tctx, succ)?))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for CondBrOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let cond = convert_typed_operand(ctx, tctx, &self.condition(ctx))?;
+        let true_succ = convert_block_operand(ctx, tctx, op.successor(0))?;
+        let false_succ = convert_block_operand(ctx, tctx, op.successor(1))?;
+        Ok(format!("br {cond}, {true_succ}, {false_succ}"))
+    }
+}
+
+/// The `, align N` suffix that atomic loads and stores of type `ty`
+/// need, as per the [DataLayout] of the module enclosing `op`.
+fn atomic_align(
+    ctx: &Context,
+    op: &dyn MemoryAccessOpInterface,
+    anchor: Ptr<Operation>,
+    ty: Ptr<TypeObj>,
+) -> Result<String> {
+    if op.atomic_ordering(ctx) == AtomicOrderingAttr::NotAtomic {
+        return Ok(String::new());
+    }
+    let align = align_of(ctx, ty, &DataLayout::of(ctx, anchor)?)?;
+    Ok(format!(", align {align}"))
+}
+
+/// The ` atomic` and ` volatile` markers of `op`.
+fn memory_access_markers(ctx: &Context, op: &dyn MemoryAccessOpInterface) -> String {
+    let mut markers = String::new();
+    if op.atomic_ordering(ctx) != AtomicOrderingAttr::NotAtomic {
+        markers.push_str(" atomic");
+    }
+    if op.is_volatile(ctx) {
+        markers.push_str(" volatile");
+    }
+    markers
+}
+
+/// The ` <ordering>` of `op`, if it is atomic.
+fn ordering_suffix(ordering: AtomicOrderingAttr) -> String {
+    match ordering {
+        AtomicOrderingAttr::NotAtomic => String::new(),
+        ordering => format!(" {}", atomic_ordering_text(ordering)),
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for LoadOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let ty = self.result_type(ctx);
+        let pointee_ty = convert_type(ctx, tctx, ty)?;
+        let ptr = convert_typed_operand(ctx, tctx, &self.operand(ctx))?;
+        Ok(format!(
+            "load{} {}, {}{}{}",
+            memory_access_markers(ctx, self),
+            pointee_ty,
+            ptr,
+            ordering_suffix(self.atomic_ordering(ctx)),
+            atomic_align(ctx, self, self.operation(), ty)?
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for StoreOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let value = self.value_opd(ctx);
+        let value_text = convert_typed_operand(ctx, tctx, &value)?;
+        let ptr = convert_typed_operand(ctx, tctx, &self.address_opd(ctx))?;
+        Ok(format!(
+            "store{} {}, {}{}{}",
+            memory_access_markers(ctx, self),
+            value_text,
+            ptr,
+            ordering_suffix(self.atomic_ordering(ctx)),
+            atomic_align(ctx, self, self.operation(), value.get_type(ctx))?
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for AtomicRmwOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let volatile = if self.is_volatile(ctx) {
+            " volatile"
+        } else {
+            ""
+        };
+        let ptr = convert_typed_operand(ctx, tctx, &self.address_opd(ctx))?;
+        let value = convert_typed_operand(ctx, tctx, &self.value_opd(ctx))?;
+        Ok(format!(
+            "atomicrmw{} {} {}, {}{}",
+            volatile,
+            atomic_rmw_bin_op_text(self.bin_op(ctx)),
+            ptr,
+            value,
+            ordering_suffix(self.atomic_ordering(ctx))
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for AtomicCmpXchgOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let volatile = if self.is_volatile(ctx) {
+            " volatile"
+        } else {
+            ""
+        };
+        let ptr = convert_typed_operand(ctx, tctx, &self.address_opd(ctx))?;
+        let cmp = convert_typed_operand(ctx, tctx, &self.cmp_opd(ctx))?;
+        let new = convert_typed_operand(ctx, tctx, &self.new_opd(ctx))?;
+        Ok(format!(
+            "cmpxchg{} {}, {}, {}{}{}",
+            volatile,
+            ptr,
+            cmp,
+            new,
+            ordering_suffix(self.atomic_ordering(ctx)),
+            ordering_suffix(self.failure_ordering(ctx))
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ICmpOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let lhs = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+        let rhs = convert_value_operand(ctx, tctx, &op.operand(1))?;
+        Ok(format!(
+            "icmp {} {}, {}",
+            ipredicate_text(self.predicate(ctx)),
+            lhs,
+            rhs
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ReturnOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        match self.retval(ctx) {
+            Some(retval) => Ok(format!(
+                "ret {}",
+                convert_typed_operand(ctx, tctx, &retval)?
+            )),
+            None => Ok("ret void".to_string()),
+        }
+    }
+}
+
+/// Convert an [IntegerAttr] to an LLVM-IR integer constant, without its type.
+fn convert_integer_attr(int_val: &IntegerAttr) -> String {
+    let ap_int_val: APInt = int_val.clone().into();
+    if ap_int_val.bw() == 1 {
+        let val = if ap_int_val.is_zero() {
+            "false"
+        } else {
+            "true"
+        };
+        return val.to_string();
+    }
+    ap_int_val.to_string_signed_decimal()
+}
+
+/// Convert the value of a [ConstantOp] (or [GlobalOp] initializer)
+/// to an LLVM-IR constant, without its type.
+fn convert_constant_value(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    value: &AttrObj,
+    loc: Location,
+) -> Result<String> {
+    if let Some(int_val) = value.downcast_ref::<IntegerAttr>() {
+        Ok(convert_integer_attr(int_val))
+    } else if let Some(vec_val) = value.downcast_ref::<ConstantVectorAttr>() {
+        let elems = vec_val
+            .elems()
+            .iter()
+            .map(|elem| {
+                let ty = convert_type(ctx, tctx, elem.get_type(ctx))?;
+                Ok(format!("{} {}", ty, convert_integer_attr(elem)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(format!("<{}>", elems.join(", ")))
+    } else {
+        input_err!(loc, ToLLVMErr::ConstOpNotIntOrFloat)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextConst for ConstantOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        convert_constant_value(ctx, tctx, &self.get_value(ctx), self.loc(ctx))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextConst for AddressOfOp {
+    fn emit(&self, ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok(format!("@{}", self.get_global_name(ctx)))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextConst for UndefOp {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok("undef".to_string())
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextConst for PoisonOp {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok("poison".to_string())
+    }
+}
+
+/// Convert the operand bundles of `op`, as in ` [ "deopt"(i32 %x) ]`.
+fn convert_operand_bundles(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    op: &dyn OperandBundleInterface,
+) -> Result<String> {
+    let bundles = op
+        .operand_bundles(ctx)
+        .into_iter()
+        .map(|bundle| {
+            let operands = convert_typed_operands(ctx, tctx, &bundle.operands)?;
+            Ok(format!("\"{}\"({})", bundle.tag, operands))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if bundles.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!(" [ {} ]", bundles.join(", ")))
+}
+
+/// Convert the callee, arguments and operand bundles of a call-like `op`,
+/// as in `i32 @f(i32 %x) [ "deopt"() ]`.
+fn convert_call<T: CallOpInterface + OperandBundleInterface>(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    op: &T,
+) -> Result<String> {
+    let callee = match op.callee(ctx) {
+        CallOpCallable::Direct(callee_sym) => format!("@{callee_sym}"),
+        CallOpCallable::Indirect(callee_val) => convert_value_operand(ctx, tctx, &callee_val)?,
+    };
+    let args = convert_typed_operands(ctx, tctx, &op.args(ctx))?;
+    let ret_ty = return_type(ctx, tctx, &op.callee_type(ctx).deref(ctx))?;
+    let bundles = convert_operand_bundles(ctx, tctx, op)?;
+    Ok(format!("{ret_ty} {callee}({args}){bundles}"))
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for CallOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        Ok(format!("call {}", convert_call(ctx, tctx, self)?))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for InvokeOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let call = convert_call(ctx, tctx, self)?;
+        let normal_dest = convert_block_operand(ctx, tctx, self.normal_dest(ctx))?;
+        let unwind_dest = convert_block_operand(ctx, tctx, self.unwind_dest(ctx))?;
+        Ok(format!(
+            "invoke {call} to {normal_dest} unwind {unwind_dest}"
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for LandingPadOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let mut text = format!(
+            "landingpad {}",
+            convert_type(ctx, tctx, self.result_type(ctx))?
+        );
+        if self.is_cleanup(ctx) {
+            text.push_str(" cleanup");
+        }
+        // As in LLVM, array typed clauses are filters, and others catch.
+        for clause in self.clauses(ctx) {
+            let kind = if clause.get_type(ctx).deref(ctx).is::<ArrayType>() {
+                "filter"
+            } else {
+                "catch"
+            };
+            text.push_str(&format!(
+                " {} {}",
+                kind,
+                convert_typed_operand(ctx, tctx, &clause)?
+            ));
+        }
+        Ok(text)
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for UnreachableOp {
+    fn emit(&self, _ctx: &Context, _tctx: &mut TextContext) -> Result<String> {
+        Ok("unreachable".to_string())
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ResumeOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let exn = convert_typed_operand(ctx, tctx, &self.operand(ctx))?;
+        Ok(format!("resume {exn}"))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for GetElementPtrOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let src_elem_type = convert_type(ctx, tctx, self.src_elem_type(ctx))?;
+        let mut operands = vec![convert_typed_operand(ctx, tctx, &self.src_ptr(ctx))?];
+        for index in self.indices(ctx) {
+            operands.push(match index {
+                GepIndex::Constant(c) => format!("i32 {c}"),
+                GepIndex::Value(v) => convert_typed_operand(ctx, tctx, &v)?,
+            });
+        }
+        Ok(format!(
+            "getelementptr {}, {}",
+            src_elem_type,
+            operands.join(", ")
+        ))
+    }
+}
+
+/// The `, i, j, ...` suffix of aggregate `indices`.
+fn aggregate_indices(indices: &[u32]) -> String {
+    indices.iter().map(|idx| format!(", {idx}")).collect()
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for InsertValueOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let base = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+        let value = convert_typed_operand(ctx, tctx, &op.operand(1))?;
+        Ok(format!(
+            "insertvalue {}, {}{}",
+            base,
+            value,
+            aggregate_indices(&self.indices(ctx))
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ExtractValueOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let base = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+        Ok(format!(
+            "extractvalue {}{}",
+            base,
+            aggregate_indices(&self.indices(ctx))
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ExtractElementOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let vector = convert_typed_operand(ctx, tctx, &self.vector_opd(ctx))?;
+        let index = convert_typed_operand(ctx, tctx, &self.index_opd(ctx))?;
+        Ok(format!("extractelement {vector}, {index}"))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for InsertElementOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let vector = convert_typed_operand(ctx, tctx, &self.vector_opd(ctx))?;
+        let value = convert_typed_operand(ctx, tctx, &self.value_opd(ctx))?;
+        let index = convert_typed_operand(ctx, tctx, &self.index_opd(ctx))?;
+        Ok(format!("insertelement {vector}, {value}, {index}"))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for ShuffleVectorOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let v1 = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+        let v2 = convert_typed_operand(ctx, tctx, &op.operand(1))?;
+        // The mask is a constant vector of i32s, with undef for poison elements.
+        let mask = self.mask(ctx);
+        let mask_elems: Vec<_> = mask
+            .iter()
+            .map(|elem| match elem {
+                ShuffleMaskElemAttr::Index(idx) => format!("i32 {idx}"),
+                ShuffleMaskElemAttr::Poison => "i32 undef".to_string(),
+            })
+            .collect();
+        Ok(format!(
+            "shufflevector {}, {}, <{} x i32> <{}>",
+            v1,
+            v2,
+            mask.len(),
+            mask_elems.join(", ")
+        ))
+    }
+}
+
+#[op_interface_impl]
+impl ToLLVMTextInst for SelectOp {
+    fn emit(&self, ctx: &Context, tctx: &mut TextContext) -> Result<String> {
+        let op = self.operation().deref(ctx);
+        let cond = convert_typed_operand(ctx, tctx, &op.operand(0))?;
+        let true_val = convert_typed_operand(ctx, tctx, &op.operand(1))?;
+        let false_val = convert_typed_operand(ctx, tctx, &op.operand(2))?;
+        Ok(format!("select {cond}, {true_val}, {false_val}"))
+    }
+}
+
+/// Map the values and blocks of `func_op` to their text:
+/// names, labels, or (for constants) their inline text.
+fn map_function(ctx: &Context, tctx: &mut TextContext, func_op: FuncOp) -> Result<()> {
+    for block in func_op.region(ctx).deref(ctx).iter(ctx) {
+        let block_ref = block.deref(ctx);
+        tctx.block_map
+            .insert(block, block_ref.unique_name(ctx).to_string());
+        for arg in block_ref.arguments() {
+            tctx.value_map
+                .insert(arg, format!("%{}", arg.unique_name(ctx)));
+        }
+        for op in block_ref.iter(ctx) {
+            let op_obj = Operation::op(op, ctx);
+            let text = match op_cast::<dyn ToLLVMTextConst>(&*op_obj) {
+                Some(const_conv) => const_conv.emit(ctx, tctx)?,
+                None => {
+                    let op_ref = op.deref(ctx);
+                    // LLVM instructions have at most one result.
+                    if op_ref.num_results() != 1 {
+                        continue;
+                    }
+                    format!("%{}", op_ref.result(0).unique_name(ctx))
+                }
+            };
+            for result in op.deref(ctx).results() {
+                tctx.value_map.insert(result, text.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `phi`s for the arguments of `block`, with incoming values
+/// from the [BranchOpInterface] terminators of its predecessors.
+fn convert_block_args(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    block: Ptr<BasicBlock>,
+) -> Result<Vec<String>> {
+    let mut incoming = vec![vec![]; block.deref(ctx).num_arguments()];
+    // A predecessor branching to `block` more than once is listed as often.
+    let mut seen = FxHashSet::default();
+    for pred in block.preds(ctx) {
+        if !seen.insert(pred) {
+            continue;
+        }
+        let pred_label = tctx.block_map[&pred].clone();
+        let Some(term) = pred.deref(ctx).tail() else {
+            continue;
+        };
+        let term_op = Operation::op(term, ctx);
+        let Some(branch) = op_cast::<dyn BranchOpInterface>(&*term_op) else {
+            continue;
+        };
+        let num_successors = term.deref(ctx).num_successors();
+        for succ_idx in 0..num_successors {
+            if term.deref(ctx).successor(succ_idx) != block {
+                continue;
+            }
+            for (arg_idx, value) in branch.successor_operands(ctx, succ_idx).iter().enumerate() {
+                let value = convert_value_operand(ctx, tctx, value)?;
+                incoming[arg_idx].push(format!("[ {value}, %{pred_label} ]"));
+            }
+        }
+    }
+
+    let args: Vec<_> = block.deref(ctx).arguments().collect();
+    args.into_iter()
+        .zip(incoming)
+        .map(|(arg, incoming)| {
+            let ty = convert_type(ctx, tctx, arg.get_type(ctx))?;
+            Ok(format!(
+                "%{} = phi {} {}",
+                arg.unique_name(ctx),
+                ty,
+                incoming.join(", ")
+            ))
+        })
+        .collect()
+}
+
+/// Convert a pliron [BasicBlock] to LLVM-IR text, appended to `text`.
+fn convert_block(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    block: Ptr<BasicBlock>,
+    is_entry: bool,
+    text: &mut String,
+) -> Result<()> {
+    text.push_str(&format!("{}:\n", tctx.block_map[&block]));
+    if !is_entry {
+        for phi in convert_block_args(ctx, tctx, block)? {
+            text.push_str(&format!("  {phi}\n"));
+        }
+    }
+
+    for op in block.deref(ctx).iter(ctx) {
+        let op_obj = Operation::op(op, ctx);
+        if op_cast::<dyn ToLLVMTextConst>(&*op_obj).is_some() {
+            continue;
+        }
+        let Some(op_conv) = op_cast::<dyn ToLLVMTextInst>(&*op_obj) else {
+            return input_err!(
+                op.deref(ctx).loc(),
+                ToLLVMErr::MissingOpConversion(op_obj.opid().to_string())
+            );
+        };
+        let inst = op_conv.emit(ctx, tctx)?;
+        let op_ref = op.deref(ctx);
+        match op_ref.results().next() {
+            Some(result) if !is_void(ctx, result.get_type(ctx)) => {
+                text.push_str(&format!("  %{} = {}\n", result.unique_name(ctx), inst));
+            }
+            _ => text.push_str(&format!("  {inst}\n")),
+        }
+    }
+    Ok(())
+}
+
+/// Convert a pliron [FuncOp] to LLVM-IR text, appended to `text`.
+fn convert_function(
+    ctx: &Context,
+    tctx: &mut TextContext,
+    func_op: FuncOp,
+    text: &mut String,
+) -> Result<()> {
+    tctx.clear_per_function_data();
+    let func_ty = func_op.get_type(ctx).deref(ctx);
+    let func_ty = func_ty
+        .downcast_ref::<FunctionType>()
+        .expect("FuncOp must have a function type");
+    let ret_ty = return_type(ctx, tctx, func_ty)?;
+    let entry = func_op.get_entry_block(ctx);
+    let name = func_op.symbol_name(ctx);
+
+    // A single empty block is the body of a declaration.
+    let region = func_op.region(ctx);
+    if region.deref(ctx).iter(ctx).count() == 1 && entry.deref(ctx).iter(ctx).next().is_none() {
+        let arg_tys = func_ty
+            .inputs()
+            .iter()
+            .map(|ty| convert_type(ctx, tctx, *ty))
+            .collect::<Result<Vec<_>>>()?;
+        text.push_str(&format!(
+            "declare {} @{}({})\n",
+            ret_ty,
+            name,
+            arg_tys.join(", ")
+        ));
+        return Ok(());
+    }
+
+    map_function(ctx, tctx, func_op)?;
+    let args: Vec<_> = entry.deref(ctx).arguments().collect();
+    let args = convert_typed_operands(ctx, tctx, &args)?;
+    let mut header = format!("define {ret_ty} @{name}({args})");
+    if let Some(personality) = func_op
+        .operation()
+        .deref(ctx)
+        .attributes
+        .get::<IdentifierAttr>(&landing_pad_op::ATTR_KEY_PERSONALITY)
+    {
+        let personality: Identifier = personality.clone().into();
+        header.push_str(&format!(" personality ptr @{personality}"));
+    }
+    text.push_str(&format!("{header} {{\n"));
+
+    for block in region.deref(ctx).iter(ctx) {
+        convert_block(ctx, tctx, block, block == entry, text)?;
+    }
+    text.push_str("}\n");
+    Ok(())
+}
+
+/// Convert a pliron [GlobalOp] to (a line of) LLVM-IR text.
+fn convert_global(ctx: &Context, tctx: &mut TextContext, global_op: GlobalOp) -> Result<String> {
+    let ty = convert_type(ctx, tctx, global_op.get_type(ctx))?;
+    let linkage = if global_op.is_private(ctx) {
+        "private "
+    } else {
+        ""
+    };
+    let kind = if global_op.is_constant(ctx) {
+        "constant"
+    } else {
+        "global"
+    };
+    let name = global_op.symbol_name(ctx);
+    match global_op.get_initializer(ctx) {
+        Some(init) => {
+            let loc = global_op.loc(ctx);
+            let init = convert_constant_value(ctx, tctx, &init, loc)?;
+            Ok(format!("@{name} = {linkage}{kind} {ty} {init}"))
+        }
+        None => Ok(format!("@{name} = external {kind} {ty}")),
+    }
+}
+
+/// Convert pliron [ModuleOp] to LLVM-IR text.
+pub fn convert_module(ctx: &Context, module: ModuleOp) -> Result<String> {
+    let tctx = &mut TextContext::default();
+    let mut header = format!("; ModuleID = '{}'\n", module.symbol_name(ctx));
+    if let Some(data_layout) = module
+        .operation()
+        .deref(ctx)
+        .attributes
+        .get::<StringAttr>(&ATTR_KEY_DATA_LAYOUT)
+    {
+        header.push_str(&format!(
+            "target datalayout = \"{}\"\n",
+            String::from(data_layout.clone())
+        ));
+    }
+
+    let mut globals = vec![];
+    let mut functions = vec![];
+    for op in module.body(ctx, 0).deref(ctx).iter(ctx) {
+        let op_obj = Operation::op(op, ctx);
+        if let Some(func_op) = op_obj.downcast_ref::<FuncOp>() {
+            let mut text = String::new();
+            convert_function(ctx, tctx, *func_op, &mut text)?;
+            functions.push(text);
+        } else if let Some(global_op) = op_obj.downcast_ref::<GlobalOp>() {
+            globals.push(convert_global(ctx, tctx, *global_op)?);
+        }
+    }
+
+    // Named struct types are defined once they're all seen.
+    let mut text = header;
+    if !tctx.type_defs.is_empty() {
+        text.push('\n');
+        for type_def in &tctx.type_defs {
+            text.push_str(&format!("{type_def}\n"));
+        }
+    }
+    if !globals.is_empty() {
+        text.push('\n');
+        for global in &globals {
+            text.push_str(&format!("{global}\n"));
+        }
+    }
+    for function in functions {
+        text.push('\n');
+        text.push_str(&function);
+    }
+    Ok(text)
+}
@@ -1,8 +1,14 @@
 //! [Type]s defined in the LLVM dialect.
 
 use combine::{Parser, between, optional, token};
-use pliron::derive::{def_type, format_type};
+use pliron::derive::{def_type, format_type, type_interface_impl};
 use pliron::{
+    builtin::{
+        type_interfaces::{
+            DataLayout, SizedTypeInterface, UnsizedTypeErr, align_to, size_and_align,
+        },
+        types::IntegerType,
+    },
     common_traits::Verify,
     context::{Context, Ptr},
     identifier::Identifier,
@@ -57,7 +63,7 @@ impl StructType {
     ) -> Result<TypePtr<Self>> {
         let self_ptr = Type::register_instance(
             StructType {
-                name: Some(name.clone()),
+                name: Some(name),
                 // Uniquing happens only on the name, so this doesn't matter.
                 fields: None,
             },
@@ -98,7 +104,7 @@ impl StructType {
     pub fn get_existing_named(ctx: &Context, name: &Identifier) -> Option<TypePtr<Self>> {
         Type::instance(
             StructType {
-                name: Some(name.clone()),
+                name: Some(*name),
                 // Named structs are uniqued only on the name.
                 fields: None,
             },
@@ -129,7 +135,7 @@ impl StructType {
 
     /// Get this struct's name, if it has one.
     pub fn name(&self) -> Option<Identifier> {
-        self.name.clone()
+        self.name
     }
 
     /// Get type of the idx'th field.
@@ -196,7 +202,7 @@ impl Printable for StructType {
             if in_printing {
                 return write!(f, "{}>", name.clone());
             }
-            IN_PRINTING.with(|f| f.borrow_mut().push(name.clone()));
+            IN_PRINTING.with(|f| f.borrow_mut().push(*name));
             write!(f, "{name}")?;
             if !self.is_opaque() {
                 write!(f, " ")?;
@@ -293,6 +299,24 @@ impl Parsable for StructType {
 
 impl Eq for StructType {}
 
+#[type_interface_impl]
+impl SizedTypeInterface for StructType {
+    /// Fields are laid out in order, each at the next offset aligned for it.
+    /// Opaque structs have no size.
+    fn size_and_align(&self, ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)> {
+        let Some(fields) = &self.fields else {
+            return input_err_noloc!(UnsizedTypeErr(self.disp(ctx).to_string()));
+        };
+        let (mut size, mut align) = (0, 1);
+        for field in fields {
+            let (field_size, field_align) = size_and_align(ctx, *field, layout)?;
+            size = align_to(size, field_align) + field_size;
+            align = align.max(field_align);
+        }
+        Ok((align_to(size, align), align))
+    }
+}
+
 /// An opaque pointer, corresponding to LLVM's pointer type.
 #[def_type("llvm.ptr")]
 #[derive(Hash, PartialEq, Eq, Debug)]
@@ -312,6 +336,13 @@ impl PointerType {
 
 impl_verify_succ!(PointerType);
 
+#[type_interface_impl]
+impl SizedTypeInterface for PointerType {
+    fn size_and_align(&self, _ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)> {
+        Ok((layout.pointer_size(), layout.pointer_align()))
+    }
+}
+
 /// Array type, corresponding to LLVM's array type.
 #[def_type("llvm.array")]
 #[derive(Hash, PartialEq, Eq, Debug)]
@@ -344,6 +375,79 @@ impl ArrayType {
 
 impl_verify_succ!(ArrayType);
 
+#[type_interface_impl]
+impl SizedTypeInterface for ArrayType {
+    fn size_and_align(&self, ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)> {
+        let (elem_size, elem_align) = size_and_align(ctx, self.elem, layout)?;
+        Ok((elem_size * self.size, elem_align))
+    }
+}
+
+/// Fixed length vector type, corresponding to LLVM's (non-scalable) vector type.
+#[def_type("llvm.vector")]
+#[derive(Hash, PartialEq, Eq, Debug)]
+#[format_type("`<` $num_elements ` x ` $elem `>`")]
+pub struct VectorType {
+    elem: Ptr<TypeObj>,
+    num_elements: u32,
+}
+
+impl VectorType {
+    /// Get or create a new vector type.
+    pub fn get(ctx: &mut Context, elem: Ptr<TypeObj>, num_elements: u32) -> TypePtr<Self> {
+        Type::register_instance(VectorType { elem, num_elements }, ctx)
+    }
+    /// Get, if it already exists, a vector type.
+    pub fn get_existing(
+        ctx: &Context,
+        elem: Ptr<TypeObj>,
+        num_elements: u32,
+    ) -> Option<TypePtr<Self>> {
+        Type::instance(VectorType { elem, num_elements }, ctx)
+    }
+
+    /// Get vector element type.
+    pub fn elem_type(&self) -> Ptr<TypeObj> {
+        self.elem
+    }
+
+    /// Get the number of elements in the vector.
+    pub fn num_elements(&self) -> u32 {
+        self.num_elements
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum VectorTypeErr {
+    #[error("vector must have at least one element")]
+    ZeroElements,
+    #[error("vector elements must be integers or pointers")]
+    ElemTypeErr,
+}
+
+impl Verify for VectorType {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        if self.num_elements == 0 {
+            verify_err_noloc!(VectorTypeErr::ZeroElements)?
+        }
+        let elem = self.elem.deref(ctx);
+        if !(elem.is::<IntegerType>() || elem.is::<PointerType>()) {
+            verify_err_noloc!(VectorTypeErr::ElemTypeErr)?
+        }
+        Ok(())
+    }
+}
+
+#[type_interface_impl]
+impl SizedTypeInterface for VectorType {
+    /// As in LLVM, vectors are aligned to their size, rounded up to a power of two.
+    fn size_and_align(&self, ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)> {
+        let (elem_size, _) = size_and_align(ctx, self.elem, layout)?;
+        let align = (elem_size * u64::from(self.num_elements)).next_power_of_two();
+        Ok((align, align))
+    }
+}
+
 #[def_type("llvm.void")]
 #[derive(Hash, PartialEq, Eq, Debug)]
 #[format_type]
@@ -378,6 +482,7 @@ impl_verify_succ!(FuncType);
 pub fn register(ctx: &mut Context) {
     VoidType::register_type_in_dialect(ctx, VoidType::parser_fn);
     ArrayType::register_type_in_dialect(ctx, ArrayType::parser_fn);
+    VectorType::register_type_in_dialect(ctx, VectorType::parser_fn);
     StructType::register_type_in_dialect(ctx, StructType::parser_fn);
     PointerType::register_type_in_dialect(ctx, PointerType::parser_fn);
     FuncType::register_type_in_dialect(ctx, FuncType::parser_fn);
@@ -391,12 +496,13 @@ mod tests {
     use expect_test::expect;
     use pliron::derive::def_type;
 
-    use crate::types::{FuncType, StructType, VoidType};
+    use crate::types::{FuncType, StructType, VectorType, VoidType};
     use pliron::{
         builtin::{
             self,
             types::{IntegerType, Signedness},
         },
+        common_traits::Verify,
         context::{Context, Ptr},
         identifier::Identifier,
         impl_verify_succ,
@@ -417,7 +523,7 @@ mod tests {
 
         // Create an opaque struct since we want a recursive type.
         let list_struct: Ptr<TypeObj> =
-            StructType::get_named(&mut ctx, linked_list_id.clone(), None)?.into();
+            StructType::get_named(&mut ctx, linked_list_id, None)?.into();
         assert!(
             list_struct
                 .deref(&ctx)
@@ -428,7 +534,7 @@ mod tests {
         let list_struct_ptr = TypedPointerType::get(&mut ctx, list_struct).into();
         let fields = vec![int64_ptr, list_struct_ptr];
         // Set the struct body now.
-        StructType::get_named(&mut ctx, linked_list_id.clone(), Some(fields))?;
+        StructType::get_named(&mut ctx, linked_list_id, Some(fields))?;
         assert!(
             !list_struct
                 .deref(&ctx)
@@ -560,6 +666,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vector_type_parsing() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+
+        let state_stream = state_stream_from_iterator(
+            "llvm.vector <4 x builtin.integer i32>".chars(),
+            parsable::State::new(&mut ctx, location::Source::InMemory),
+        );
+
+        let res = type_parser().parse(state_stream).unwrap().0;
+        assert_eq!(
+            &res.disp(&ctx).to_string(),
+            "llvm.vector <4 x builtin.integer i32>"
+        );
+        res.verify(&ctx).unwrap();
+
+        let i32_ty = IntegerType::get(&mut ctx, 32, Signedness::Signless).into();
+        let vec_ty = VectorType::get(&mut ctx, i32_ty, 4);
+        assert!(Ptr::<TypeObj>::from(vec_ty) == res);
+        assert!(VectorType::get(&mut ctx, i32_ty, 0).verify(&ctx).is_err());
+        let vec_of_vec = VectorType::get(&mut ctx, vec_ty.into(), 2);
+        assert!(vec_of_vec.verify(&ctx).is_err());
+    }
+
     #[test]
     fn test_struct_type_parsing() {
         let mut ctx = Context::new();
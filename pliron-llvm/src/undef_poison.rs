@@ -0,0 +1,267 @@
+//! Folding operations on `undef` and `poison` values.
+//!
+//! LLVM's [undef](https://llvm.org/docs/LangRef.html#undefined-values) and
+//! [poison](https://llvm.org/docs/LangRef.html#poison-values) values are defined
+//! by [UndefOp] and [PoisonOp], whose [constant values](ConstantLikeInterface)
+//! are [UndefAttr] and [PoisonAttr]. [fold_undef_poison] simplifies operations
+//! on them, following (a subset of) LLVM's `InstSimplify` rules. Writing `P` for
+//! poison and `U` for undef:
+//!   - integer binary arithmetic ([IntBinArithOp]), `icmp` and casts
+//!     ([CastOpInterface]) with a `P` operand are `P`,
+//!   - division and remainder by `U` are `P` (since `U` may be zero),
+//!   - `add`, `sub` and `xor` with a `U` operand are `U`,
+//!   - `select P, x, y` is `P`, and `select c, x, P` (or `select c, P, x`) is `x`.
+//!
+//! Operations whose results become unused aren't erased, that's left to
+//! dead code elimination.
+
+use pliron::{
+    attribute::Attribute,
+    builtin::op_interfaces::{ConstantLikeInterface, OneResultInterface},
+    context::{Context, Ptr},
+    graph::walkers::collect_ops,
+    op::{Op, op_cast, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    result::Result,
+    r#type::Typed,
+    value::Value,
+};
+
+use crate::{
+    attributes::{PoisonAttr, UndefAttr},
+    op_interfaces::{CastOpInterface, IntBinArithOp},
+    ops::{
+        AddOp, ICmpOp, PoisonOp, SDivOp, SRemOp, SelectOp, SubOp, UDivOp, URemOp, UndefOp, XorOp,
+    },
+};
+
+/// What an operation folds to. See [fold_undef_poison].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UndefPoisonFold {
+    /// A poison value of the result type.
+    Poison,
+    /// An undef value of the result type.
+    Undef,
+    /// An existing value.
+    Value(Value),
+}
+
+/// Is `value` defined by a [ConstantLikeInterface] operation whose value is an `A`?
+fn is_constant<A: Attribute>(ctx: &Context, value: &Value) -> bool {
+    let Value::OpResult { op, .. } = value else {
+        return false;
+    };
+    op_cast::<dyn ConstantLikeInterface>(&*Operation::op(*op, ctx))
+        .is_some_and(|constant| constant.constant_value(ctx).is::<A>())
+}
+
+/// Is `value` a poison value?
+pub fn is_poison(ctx: &Context, value: &Value) -> bool {
+    is_constant::<PoisonAttr>(ctx, value)
+}
+
+/// Is `value` an undef value?
+pub fn is_undef(ctx: &Context, value: &Value) -> bool {
+    is_constant::<UndefAttr>(ctx, value)
+}
+
+/// What the (single) result of `op` folds to, given its undef and poison operands.
+/// See [module](self) documentation for the rules.
+pub fn fold_undef_poison(ctx: &Context, op: Ptr<Operation>) -> Option<UndefPoisonFold> {
+    let op_obj = Operation::op(op, ctx);
+    let op_ref = op.deref(ctx);
+    if op_ref.num_results() != 1 {
+        return None;
+    }
+    let operands: Vec<_> = op_ref.operands().collect();
+    let any_poison = operands.iter().any(|opd| is_poison(ctx, opd));
+
+    if op_obj.is::<SelectOp>() {
+        let [cond, true_val, false_val] = operands[..] else {
+            return None;
+        };
+        return if is_poison(ctx, &cond) {
+            Some(UndefPoisonFold::Poison)
+        } else if is_poison(ctx, &false_val) {
+            Some(UndefPoisonFold::Value(true_val))
+        } else if is_poison(ctx, &true_val) {
+            Some(UndefPoisonFold::Value(false_val))
+        } else {
+            None
+        };
+    }
+
+    let propagates_poison = op_impls::<dyn IntBinArithOp>(&*op_obj)
+        || op_obj.is::<ICmpOp>()
+        || op_impls::<dyn CastOpInterface>(&*op_obj);
+    if propagates_poison && any_poison {
+        return Some(UndefPoisonFold::Poison);
+    }
+
+    let is_div = op_obj.is::<UDivOp>()
+        || op_obj.is::<SDivOp>()
+        || op_obj.is::<URemOp>()
+        || op_obj.is::<SRemOp>();
+    if is_div && operands.get(1).is_some_and(|rhs| is_undef(ctx, rhs)) {
+        return Some(UndefPoisonFold::Poison);
+    }
+
+    let undef_absorbing = op_obj.is::<AddOp>() || op_obj.is::<SubOp>() || op_obj.is::<XorOp>();
+    if undef_absorbing && operands.iter().any(|opd| is_undef(ctx, opd)) {
+        return Some(UndefPoisonFold::Undef);
+    }
+    None
+}
+
+/// Fold (see [fold_undef_poison]) the operations nested (at any depth) in `root`,
+/// replacing the uses of their results. Undef and poison values are created, as
+/// needed, right before the operation folded. Operations whose results are unused
+/// are skipped. Returns the number of operations folded.
+pub fn fold_undef_poison_ops(ctx: &mut Context, root: Ptr<Operation>) -> usize {
+    let mut num_folded = 0;
+    for op in collect_ops(ctx, root).into_iter().skip(1) {
+        let result = op.deref(ctx).results().next();
+        let Some(result) = result.filter(|result| result.is_used(ctx)) else {
+            continue;
+        };
+        let Some(folded) = fold_undef_poison(ctx, op) else {
+            continue;
+        };
+        let result_ty = result.get_type(ctx);
+        let folded = match folded {
+            UndefPoisonFold::Poison => {
+                let poison = PoisonOp::new(ctx, result_ty);
+                poison.operation().insert_before(ctx, op);
+                poison.result(ctx)
+            }
+            UndefPoisonFold::Undef => {
+                let undef = UndefOp::new(ctx, result_ty);
+                undef.operation().insert_before(ctx, op);
+                undef.result(ctx)
+            }
+            UndefPoisonFold::Value(value) => value,
+        };
+        result.replace_some_uses_with(ctx, |_, _| true, &folded);
+        num_folded += 1;
+    }
+    num_folded
+}
+
+/// A [Pass] running [fold_undef_poison_ops].
+/// Its [statistics](Pass::statistics) count the operations `folded`.
+#[derive(Default)]
+pub struct UndefPoisonFoldPass {
+    num_folded: u64,
+}
+
+impl Pass for UndefPoisonFoldPass {
+    fn name(&self) -> &str {
+        "llvm-undef-poison-fold"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_folded += fold_undef_poison_ops(ctx, op) as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("folded", self.num_folded);
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pliron::{
+        builtin::{
+            self,
+            op_interfaces::OneResultInterface,
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::Context,
+        op::Op,
+        printable::Printable,
+    };
+
+    use crate::{
+        self as llvm,
+        attributes::{ICmpPredicateAttr, PoisonAttr},
+        op_interfaces::BinArithOp,
+        ops::{AddOp, ICmpOp, PoisonOp, SelectOp, UDivOp, UndefOp, XorOp},
+        types::VoidType,
+    };
+
+    use super::{UndefPoisonFold, fold_undef_poison, fold_undef_poison_ops};
+
+    #[test]
+    fn test_undef_poison_fold() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        llvm::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless).into();
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless).into();
+        let func_ty = FunctionType::get(ctx, vec![i32_ty], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let entry = func.get_entry_block(ctx);
+        let x = entry.deref(ctx).argument(0);
+
+        let append = |ctx: &mut Context, op: &dyn Op| {
+            op.operation().insert_at_back(entry, ctx);
+            op.operation()
+        };
+        let poison = PoisonOp::new(ctx, i32_ty);
+        append(ctx, &poison);
+        let poison = poison.result(ctx);
+        let undef = UndefOp::new(ctx, i32_ty);
+        append(ctx, &undef);
+        let undef = undef.result(ctx);
+        let undef_cond = UndefOp::new(ctx, i1_ty);
+        append(ctx, &undef_cond);
+        let undef_cond = undef_cond.result(ctx);
+
+        let add_poison = AddOp::new(ctx, x, poison);
+        let add_poison = append(ctx, &add_poison);
+        let div_undef = UDivOp::new(ctx, x, undef);
+        let div_undef = append(ctx, &div_undef);
+        let xor_undef = XorOp::new(ctx, undef, x);
+        let xor_undef = append(ctx, &xor_undef);
+        let div_by_x = UDivOp::new(ctx, undef, x);
+        let div_by_x = append(ctx, &div_by_x);
+        let cmp = ICmpOp::new(ctx, ICmpPredicateAttr::EQ, poison, x);
+        let cmp = append(ctx, &cmp);
+        let select = SelectOp::new(ctx, undef_cond, poison, x);
+        let select = append(ctx, &select);
+
+        let fold = |ctx: &Context, op| fold_undef_poison(ctx, op);
+        assert!(fold(ctx, add_poison) == Some(UndefPoisonFold::Poison));
+        assert!(fold(ctx, div_undef) == Some(UndefPoisonFold::Poison));
+        assert!(fold(ctx, xor_undef) == Some(UndefPoisonFold::Undef));
+        assert!(fold(ctx, div_by_x).is_none());
+        assert!(fold(ctx, cmp) == Some(UndefPoisonFold::Poison));
+        assert!(fold(ctx, select) == Some(UndefPoisonFold::Value(x)));
+
+        // Only operations whose results are used are folded.
+        let add_res = add_poison.deref(ctx).result(0);
+        let xor_res = xor_undef.deref(ctx).result(0);
+        let sink = AddOp::new(ctx, add_res, xor_res);
+        append(ctx, &sink);
+        assert_eq!(fold_undef_poison_ops(ctx, func.operation()), 2);
+        let sink_opds: Vec<_> = sink.operation().deref(ctx).operands().collect();
+        assert!(super::is_poison(ctx, &sink_opds[0]) && super::is_undef(ctx, &sink_opds[1]));
+        assert!(!add_res.is_used(ctx) && !xor_res.is_used(ctx));
+
+        // There are no void (or function) undef and poison values.
+        let void_ty = VoidType::get(ctx).into();
+        assert!(PoisonOp::new(ctx, void_ty).verify(ctx).is_err());
+        assert!(PoisonAttr::new(void_ty).verify(ctx).is_err());
+        let poison_attr = PoisonAttr::new(i32_ty);
+        poison_attr.verify(ctx).unwrap();
+        assert_eq!(poison_attr.disp(ctx).to_string(), "<builtin.integer i32>");
+    }
+}
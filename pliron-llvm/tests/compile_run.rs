@@ -5,20 +5,21 @@ use std::{path::PathBuf, sync::LazyLock};
 use assert_cmd::Command;
 use expect_test::expect;
 use pliron::{
-    arg_error_noloc, builtin,
+    arg_error_noloc,
+    builtin::{self, ops::ModuleOp},
     common_traits::Verify,
     context::Context,
-    location,
+    location::{self, Location},
     op::Op,
     operation::Operation,
-    parsable::{self, Parsable, state_stream_from_file},
+    parsable::{self, Parsable, state_stream_from_file, state_stream_from_iterator},
     printable::Printable,
     result::Result,
 };
 use pliron_llvm::{
     from_llvm_ir,
     llvm_sys::core::{LLVMContext, LLVMModule},
-    to_llvm_ir,
+    to_llvm_ir, to_llvm_text,
 };
 use tempfile::{TempDir, tempdir};
 
@@ -298,3 +299,257 @@ fn test_insert_extract_value_via_pliron() {
 fn test_select_via_pliron() {
     test_llvm_ir_via_pliron(RESOURCES_DIR.join("select.ll").to_str().unwrap(), 100);
 }
+
+/// Test InvokeOp, LandingPadOp and ResumeOp by compiling invoke.ll via pliron.
+#[test]
+fn test_invoke_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("invoke.ll").to_str().unwrap(), 43);
+}
+
+/// Test volatile and atomic memory accesses by compiling atomics.ll via pliron.
+#[test]
+fn test_atomics_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("atomics.ll").to_str().unwrap(), 71);
+}
+
+/// Test vector element and shuffle ops by compiling vector.ll via pliron.
+#[test]
+fn test_vector_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("vector.ll").to_str().unwrap(), 69);
+}
+
+/// Test UndefOp and PoisonOp by compiling undef_poison.ll via pliron.
+#[test]
+fn test_undef_poison_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("undef_poison.ll").to_str().unwrap(), 42);
+}
+
+/// Test UnreachableOp by compiling unreachable.ll via pliron.
+#[test]
+fn test_unreachable_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("unreachable.ll").to_str().unwrap(), 7);
+}
+
+/// Test indirect calls by compiling indirect_call.ll via pliron.
+#[test]
+fn test_indirect_call_via_pliron() {
+    test_llvm_ir_via_pliron(RESOURCES_DIR.join("indirect_call.ll").to_str().unwrap(), 13);
+}
+
+/// Emit textual LLVM-IR for `input_file`, imported into pliron, and run it.
+fn test_llvm_text_via_pliron(input_file: &str, expected_output: i32) {
+    let llvm_context = LLVMContext::default();
+    let module = LLVMModule::from_ir_in_file(&llvm_context, input_file)
+        .map_err(|err| arg_error_noloc!("{}", err))
+        .unwrap();
+    let ctx = &mut setup_context_dialects();
+    let pliron_module = from_llvm_ir::convert_module(ctx, &module)
+        .map_err(|err| arg_error_noloc!("{}", err))
+        .unwrap();
+    pliron_module.operation().verify(ctx).unwrap();
+
+    let text = to_llvm_text::convert_module(ctx, pliron_module)
+        .map_err(|err| arg_error_noloc!("{}", err.disp(ctx)))
+        .unwrap();
+    let tmp_dir = tempdir().unwrap();
+    let ll_path = tmp_dir.path().join("output.ll");
+    std::fs::write(&ll_path, &text).unwrap();
+
+    let run_output = Command::new(LLI_BINARY)
+        .current_dir(&*RESOURCES_DIR)
+        .args([ll_path.to_str().unwrap()])
+        .output()
+        .expect("failed to execute LLi to execute output.ll");
+    assert_eq!(
+        run_output.status.code(),
+        Some(expected_output),
+        "{}\n{}",
+        text,
+        String::from_utf8(run_output.stderr).unwrap()
+    );
+}
+
+/// Test emitting textual LLVM-IR, without going through the LLVM-C API.
+#[test]
+fn test_llvm_text_emission() {
+    for (input_file, expected_output) in [
+        ("simple-loop.ll", 15),
+        ("insert_extract_value.ll", 103),
+        ("select.ll", 100),
+        ("invoke.ll", 43),
+        ("atomics.ll", 71),
+        ("vector.ll", 69),
+        ("undef_poison.ll", 42),
+        ("unreachable.ll", 7),
+    ] {
+        test_llvm_text_via_pliron(
+            RESOURCES_DIR.join(input_file).to_str().unwrap(),
+            expected_output,
+        );
+    }
+}
+
+/// Test that importing unsupported LLVM-IR fails with an error, rather than panicking.
+#[test]
+fn test_unsupported_import() {
+    let tmp_dir = tempdir().unwrap();
+    let ll_path = tmp_dir.path().join("unsupported.ll");
+    std::fs::write(
+        &ll_path,
+        "define i32 @f(i32 %x) {\n  %y = freeze i32 %x\n  ret i32 %y\n}\n",
+    )
+    .unwrap();
+    let llvm_context = LLVMContext::default();
+    let module = LLVMModule::from_ir_in_file(&llvm_context, ll_path.to_str().unwrap()).unwrap();
+    let ctx = &mut setup_context_dialects();
+    let Err(err) = from_llvm_ir::convert_module(ctx, &module) else {
+        panic!("Importing freeze must fail");
+    };
+    assert!(
+        err.to_string()
+            .contains("Instruction %y = freeze i32 %x is not supported")
+    );
+}
+
+/// Test that operand bundles survive a round trip through pliron.
+#[test]
+fn test_operand_bundles_via_pliron() {
+    let llvm_context = LLVMContext::default();
+    let input_file = RESOURCES_DIR.join("operand_bundles.ll");
+    let module = LLVMModule::from_ir_in_file(&llvm_context, input_file.to_str().unwrap()).unwrap();
+    let ctx = &mut setup_context_dialects();
+    let pliron_module = from_llvm_ir::convert_module(ctx, &module)
+        .map_err(|err| arg_error_noloc!("{}", err))
+        .unwrap();
+    pliron_module.operation().verify(ctx).unwrap();
+    let printed = pliron_module.disp(ctx).to_string();
+    assert!(printed.contains(
+        r#"llvm.call @add_one(op_10v1_res0) ["deopt"(op_11v1_res0, op_12v1_res0), "gc-live"()] : (builtin.integer i32) -> builtin.integer i32"#
+    ));
+    assert!(printed.contains(r#"["deopt": 1]"#));
+
+    // The call syntax, with its bundles, parses back (renaming values).
+    let state_stream = state_stream_from_iterator(
+        printed.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+    let parsed = Operation::parser(()).parse(state_stream).unwrap().0;
+    parsed.verify(ctx).unwrap();
+    let reprinted = parsed.disp(ctx).to_string();
+    assert!(reprinted.contains(r#"llvm.call @add_one("#));
+    assert!(
+        reprinted.contains(r#"), "gc-live"()] : (builtin.integer i32) -> builtin.integer i32"#)
+    );
+
+    let module = to_llvm_ir::convert_module(ctx, &llvm_context, pliron_module)
+        .map_err(|err| arg_error_noloc!("{}", err))
+        .unwrap();
+    module.verify().unwrap();
+    let tmp_dir = tempdir().unwrap();
+    let ll_path = tmp_dir.path().join("output.ll");
+    module.asm_to_file(ll_path.to_str().unwrap()).unwrap();
+    let output = std::fs::read_to_string(ll_path).unwrap();
+    assert!(output.contains(r#"[ "deopt"(i32 7, i32 8), "gc-live"() ]"#));
+    assert!(output.contains(r#"[ "deopt"(i32 %"#));
+}
+
+#[test]
+fn test_json_diagnostics() {
+    // llvm-opt --diagnostics json -i missing.ll -o $tmp/missing.out.ll
+    let tmp_dir = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("llvm-opt").unwrap();
+    let compile_missing = cmd
+        .current_dir(&*RESOURCES_DIR)
+        .args([
+            "--diagnostics",
+            "json",
+            "-i",
+            "missing.ll",
+            "-o",
+            tmp_dir.path().join("missing.out.ll").to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to execute llvm-opt");
+    assert!(!compile_missing.status.success());
+    let stderr = String::from_utf8(compile_missing.stderr).unwrap();
+    assert!(stderr.starts_with(r#"{"severity":"error","message":"#));
+    assert!(
+        stderr
+            .trim_end()
+            .ends_with(r#""file":null,"line":null,"col":null,"span":null,"notes":[]}"#)
+    );
+}
+
+/// Test that the source map of emitted LLVM-IR maps its lines back to the plir they're from.
+#[test]
+fn test_source_map() {
+    let llvm_context = LLVMContext::default();
+    let input_file = RESOURCES_DIR.join("fib.mem2reg.ll");
+    let module = LLVMModule::from_ir_in_file(&llvm_context, input_file.to_str().unwrap()).unwrap();
+    let ctx = &mut setup_context_dialects();
+    let pliron_module = from_llvm_ir::convert_module(ctx, &module)
+        .map_err(|err| arg_error_noloc!("{}", err))
+        .unwrap();
+
+    // Parse the printed plir back, for its operations to have source locations.
+    let plir = pliron_module.disp(ctx).to_string();
+    let state_stream = state_stream_from_iterator(
+        plir.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+    let parsed = Operation::parser(()).parse(state_stream).unwrap().0;
+    let parsed_module = *Operation::op(parsed, ctx)
+        .downcast_ref::<ModuleOp>()
+        .unwrap();
+
+    let (module, source_map) =
+        to_llvm_ir::convert_module_with_source_map(ctx, &llvm_context, parsed_module)
+            .map_err(|err| arg_error_noloc!("{}", err))
+            .unwrap();
+    module.verify().unwrap();
+    let asm = module.asm_to_string();
+    let plir_lines: Vec<_> = plir.lines().collect();
+    let plir_line = |loc: &Location| match loc {
+        Location::SrcPos { pos, .. } => plir_lines[pos.line as usize - 1].trim(),
+        _ => panic!("Unexpected location {}", loc.disp(ctx)),
+    };
+
+    let mut num_insts = 0;
+    for (idx, line) in asm.lines().enumerate() {
+        let loc = source_map.lookup(idx + 1);
+        if line.starts_with("define ") {
+            assert!(plir_line(loc.unwrap()).starts_with("builtin.func"));
+        } else if line.starts_with("  ") {
+            // Instructions are mapped to their operations, and PHIs to their blocks.
+            let plir_line = plir_line(loc.unwrap());
+            if line.contains(" = phi ") {
+                assert!(plir_line.starts_with('^'), "{line} mapped to {plir_line}");
+            } else {
+                assert!(plir_line.contains("llvm."), "{line} mapped to {plir_line}");
+            }
+            if line.contains(" = add ") {
+                assert!(
+                    plir_line.contains("llvm.add"),
+                    "{line} mapped to {plir_line}"
+                );
+            }
+            num_insts += 1;
+        } else if line.ends_with(':') || line.contains(":  ") {
+            assert!(plir_line(loc.unwrap()).starts_with('^'));
+        } else {
+            assert!(loc.is_none(), "{line} is mapped");
+        }
+    }
+    assert!(num_insts > 0);
+
+    // Going the other way, from an operation to the line it's converted to.
+    let (line, loc) = source_map
+        .iter()
+        .find(|(_, loc)| plir_line(loc).contains("llvm.add"))
+        .unwrap();
+    assert_eq!(
+        source_map.lines_generated_from(loc).collect::<Vec<_>>(),
+        vec![line]
+    );
+    assert!(asm.lines().nth(line - 1).unwrap().contains(" = add "));
+}
@@ -0,0 +1,23 @@
+; Main function
+define i32 @main() {
+entry:
+  %p = alloca i32, i32 1
+  store volatile i32 10, ptr %p
+  store atomic i32 20, ptr %p release, align 4
+  %a = load atomic i32, ptr %p acquire, align 4
+  ; %p = 20 + 5 = 25, %old = 20
+  %old = atomicrmw add ptr %p, i32 5 seq_cst
+  ; %p = 25 - 3 = 22
+  %old2 = atomicrmw volatile sub ptr %p, i32 3 monotonic
+  ; Succeeds, %p = 30
+  %pair = cmpxchg ptr %p, i32 22, i32 30 acq_rel monotonic
+  %success = extractvalue { i32, i1 } %pair, 1
+  %b = load volatile i32, ptr %p
+  ; 20 + 20 + 30 = 70
+  %s1 = add i32 %a, %old
+  %s2 = add i32 %s1, %b
+  ; 70 + 1 = 71 when the compare-exchange succeeds
+  %inc = select i1 %success, i32 1, i32 0
+  %res = add i32 %s2, %inc
+  ret i32 %res
+}
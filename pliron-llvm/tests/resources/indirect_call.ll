@@ -0,0 +1,14 @@
+define i32 @add_one(i32 %x) {
+entry:
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @main() {
+entry:
+  %f = alloca ptr
+  store ptr @add_one, ptr %f
+  %callee = load ptr, ptr %f
+  %r = call i32 %callee(i32 12)
+  ret i32 %r
+}
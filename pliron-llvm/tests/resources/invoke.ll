@@ -0,0 +1,29 @@
+; Personality function. Never called, since nothing unwinds.
+define i32 @personality() {
+entry:
+  ret i32 0
+}
+
+define i32 @add_one(i32 %x) {
+entry:
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+; Main function
+define i32 @main() personality ptr @personality {
+entry:
+  %v = invoke i32 @add_one(i32 41) to label %normal unwind label %lpad
+
+normal:
+  %w = invoke i32 @add_one(i32 %v) to label %done unwind label %lpad
+
+done:
+  ret i32 %w
+
+lpad:
+  ; Which invoke unwound.
+  %stage = phi i32 [ 0, %entry ], [ 1, %normal ]
+  %lp = landingpad { ptr, i32 } cleanup
+  resume { ptr, i32 } %lp
+}
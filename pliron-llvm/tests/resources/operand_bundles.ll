@@ -0,0 +1,26 @@
+; Calls and invokes carrying operand bundles.
+define i32 @personality() {
+entry:
+  ret i32 0
+}
+
+define i32 @add_one(i32 %x) {
+entry:
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @main() personality ptr @personality {
+entry:
+  %v = call i32 @add_one(i32 41) [ "deopt"(i32 7, i32 8), "gc-live"() ]
+  %w = invoke i32 @add_one(i32 %v) [ "deopt"(i32 %v) ] to label %done unwind label %lpad
+
+done:
+  %prev = phi i32 [ %v, %entry ]
+  %res = add i32 %prev, %w
+  ret i32 %res
+
+lpad:
+  %lp = landingpad { ptr, i32 } cleanup
+  resume { ptr, i32 } %lp
+}
@@ -0,0 +1,17 @@
+; Function returning %a, with a poison value in the arm of the select never taken
+define i32 @pick(i1 %cond, i32 %a) {
+entry:
+  %result = select i1 %cond, i32 %a, i32 poison
+  ret i32 %result
+}
+
+; Main function
+define i32 @main() {
+entry:
+  %call = call i32 @pick(i1 true, i32 40)
+  ; Only the defined element of a vector, built from undef, is used
+  %vec = insertelement <2 x i32> undef, i32 2, i32 1
+  %elem = extractelement <2 x i32> %vec, i32 1
+  %sum = add i32 %call, %elem
+  ret i32 %sum
+}
@@ -0,0 +1,11 @@
+define i32 @main() {
+entry:
+  %c = icmp eq i32 7, 7
+  br i1 %c, label %ok, label %bad
+
+bad:
+  unreachable
+
+ok:
+  ret i32 7
+}
@@ -0,0 +1,26 @@
+; Build vectors element by element, and from constants, then shuffle them.
+define i32 @main() {
+entry:
+  %v0 = insertelement <4 x i32> undef, i32 1, i32 0
+  %v1 = insertelement <4 x i32> %v0, i32 2, i32 1
+  %v2 = insertelement <4 x i32> %v1, i32 3, i32 2
+  %v3 = insertelement <4 x i32> %v2, i32 4, i32 3
+
+  ; Select elements from both %v3 and a constant vector: <4, 20, 1, 40>
+  %s = shufflevector <4 x i32> %v3, <4 x i32> <i32 10, i32 20, i32 30, i32 40>, <4 x i32> <i32 3, i32 5, i32 0, i32 7>
+
+  ; Broadcast the first element, leaving the rest poison.
+  %b = shufflevector <4 x i32> %s, <4 x i32> undef, <2 x i32> <i32 0, i32 undef>
+
+  %e0 = extractelement <4 x i32> %s, i32 0
+  %e1 = extractelement <4 x i32> %s, i64 1
+  %e2 = extractelement <4 x i32> %s, i32 2
+  %e3 = extractelement <4 x i32> %s, i32 3
+  %b0 = extractelement <2 x i32> %b, i32 0
+
+  %sum0 = add i32 %e0, %e1
+  %sum1 = add i32 %sum0, %e2
+  %sum2 = add i32 %sum1, %e3
+  %sum3 = add i32 %sum2, %b0
+  ret i32 %sum3
+}
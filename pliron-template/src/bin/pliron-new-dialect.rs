@@ -0,0 +1,49 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use pliron::result::Result;
+use pliron_template::{DialectTemplate, PlironDep};
+
+#[derive(Parser)]
+#[command(version, about="Create a new pliron dialect crate", long_about = None)]
+struct Cli {
+    /// Name of the dialect
+    dialect: String,
+
+    /// Name of the crate (defaults to `pliron-<dialect>`)
+    #[arg(long, value_name = "NAME")]
+    crate_name: Option<String>,
+
+    /// Directory to create the crate in (defaults to the crate name)
+    #[arg(short, value_name = "DIR")]
+    output: Option<PathBuf>,
+
+    /// Depend on a local checkout of pliron, instead of the published crate
+    #[arg(long, value_name = "DIR")]
+    pliron_path: Option<PathBuf>,
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut template = DialectTemplate::new(&cli.dialect)?;
+    if let Some(crate_name) = &cli.crate_name {
+        template = template.with_crate_name(crate_name)?;
+    }
+    if let Some(pliron_path) = cli.pliron_path {
+        template = template.with_pliron_dep(PlironDep::Path(pliron_path));
+    }
+    let output = cli
+        .output
+        .unwrap_or_else(|| PathBuf::from(template.crate_name()));
+    template.generate(&output)
+}
+
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e.err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Generate the skeleton of an out-of-tree dialect crate for [pliron].
+//!
+//! The generated crate has modules for the dialect's ops, types and attributes,
+//! each with an example entity defined using pliron's derive macros, a
+//! [DialectPlugin](pliron::dialect::DialectPlugin) registering all of them,
+//! and a test that parses and prints IR using the dialect.
+//!
+//! The `pliron-new-dialect` binary is a command line interface to [DialectTemplate].
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use pliron::{arg_err_noloc, identifier::Identifier, result::Result};
+use thiserror::Error;
+
+/// How the generated crate depends on pliron.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlironDep {
+    /// Depend on this version of pliron from crates.io.
+    Version(String),
+    /// Depend on a local checkout of pliron.
+    Path(PathBuf),
+}
+
+impl Default for PlironDep {
+    fn default() -> Self {
+        PlironDep::Version(env!("CARGO_PKG_VERSION").to_string())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateErr {
+    #[error("Dialect name \"{0}\" is not a valid identifier")]
+    InvalidDialectName(String),
+    #[error("Crate name \"{0}\" is invalid")]
+    InvalidCrateName(String),
+    #[error("Output directory {0} already exists")]
+    OutputExists(String),
+    #[error("Error writing {0}: {1}")]
+    Io(String, std::io::Error),
+}
+
+/// Files in the generated crate, paired with their templates.
+const TEMPLATES: [(&str, &str); 6] = [
+    ("Cargo.toml", include_str!("../templates/Cargo.toml.in")),
+    ("src/lib.rs", include_str!("../templates/lib.rs.in")),
+    ("src/ops.rs", include_str!("../templates/ops.rs.in")),
+    ("src/types.rs", include_str!("../templates/types.rs.in")),
+    (
+        "src/attributes.rs",
+        include_str!("../templates/attributes.rs.in"),
+    ),
+    (
+        "tests/round_trip.rs",
+        include_str!("../templates/round_trip.rs.in"),
+    ),
+];
+
+/// Skeleton of a dialect crate. See [module](self) documentation.
+#[derive(Clone, Debug)]
+pub struct DialectTemplate {
+    dialect: Identifier,
+    crate_name: String,
+    pliron_dep: PlironDep,
+}
+
+impl DialectTemplate {
+    /// Template for dialect `dialect`, in a crate named `pliron-<dialect>`.
+    pub fn new(dialect: &str) -> Result<Self> {
+        let Ok(dialect_id) = Identifier::try_from(dialect) else {
+            return arg_err_noloc!(TemplateErr::InvalidDialectName(dialect.to_string()));
+        };
+        Ok(DialectTemplate {
+            dialect: dialect_id,
+            crate_name: format!("pliron-{}", dialect.replace('_', "-")),
+            pliron_dep: PlironDep::default(),
+        })
+    }
+
+    /// Use `crate_name` as the name of the generated crate.
+    pub fn with_crate_name(mut self, crate_name: &str) -> Result<Self> {
+        let valid = crate_name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && crate_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return arg_err_noloc!(TemplateErr::InvalidCrateName(crate_name.to_string()));
+        }
+        self.crate_name = crate_name.to_string();
+        Ok(self)
+    }
+
+    /// Specify how the generated crate depends on pliron.
+    pub fn with_pliron_dep(mut self, pliron_dep: PlironDep) -> Self {
+        self.pliron_dep = pliron_dep;
+        self
+    }
+
+    /// Name of the generated crate.
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+
+    /// Instantiate a template.
+    fn instantiate(&self, template: &str) -> String {
+        let dialect_camel: String = self
+            .dialect
+            .split('_')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase())
+                    .into_iter()
+                    .chain(chars)
+                    .collect::<String>()
+            })
+            .collect();
+        let pliron_dep = match &self.pliron_dep {
+            PlironDep::Version(version) => format!("\"{}\"", version),
+            PlironDep::Path(path) => format!("{{ path = {:?} }}", path.display().to_string()),
+        };
+        template
+            .replace("{{dialect}}", &self.dialect)
+            .replace("{{Dialect}}", &dialect_camel)
+            .replace("{{crate}}", &self.crate_name)
+            .replace("{{crate_ident}}", &self.crate_name.replace('-', "_"))
+            .replace("{{pliron_dep}}", &pliron_dep)
+    }
+
+    /// The generated files, as (path relative to the crate root, contents) pairs.
+    pub fn files(&self) -> Vec<(PathBuf, String)> {
+        TEMPLATES
+            .iter()
+            .map(|(path, template)| (PathBuf::from(path), self.instantiate(template)))
+            .collect()
+    }
+
+    /// Generate the crate in (a new) directory `dir`.
+    pub fn generate(&self, dir: &Path) -> Result<()> {
+        if dir.exists() {
+            return arg_err_noloc!(TemplateErr::OutputExists(dir.display().to_string()));
+        }
+        for (path, contents) in self.files() {
+            let path = dir.join(path);
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, contents));
+            if let Err(err) = written {
+                return arg_err_noloc!(TemplateErr::Io(path.display().to_string(), err));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use pliron::result::{Error, ErrorKind};
+use pliron_template::{DialectTemplate, PlironDep, TemplateErr};
+
+#[test]
+fn generate_dialect() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("my_dsl");
+    let template = DialectTemplate::new("my_dsl")
+        .unwrap()
+        .with_pliron_dep(PlironDep::Path("/path/to/pliron".into()));
+    assert_eq!(template.crate_name(), "pliron-my-dsl");
+    template.generate(&out).unwrap();
+
+    for (path, contents) in template.files() {
+        let written = fs::read_to_string(out.join(&path)).unwrap();
+        assert_eq!(written, contents);
+        assert!(
+            !written.contains("{{"),
+            "{} not fully instantiated",
+            path.display()
+        );
+    }
+    let manifest = fs::read_to_string(out.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"pliron-my-dsl\""));
+    assert!(manifest.contains("pliron = { path = \"/path/to/pliron\" }"));
+    let lib = fs::read_to_string(out.join("src/lib.rs")).unwrap();
+    assert!(lib.contains("pub struct MyDslDialect;"));
+
+    // Existing directories are not overwritten.
+    assert!(matches!(
+        template.generate(&out),
+        Err(Error { kind: ErrorKind::InvalidArgument, err, .. })
+            if matches!(err.downcast_ref::<TemplateErr>(), Some(TemplateErr::OutputExists(_)))
+    ));
+}
+
+#[test]
+fn invalid_names() {
+    assert!(matches!(
+        DialectTemplate::new("my.dsl"),
+        Err(Error { err, .. })
+            if matches!(err.downcast_ref::<TemplateErr>(), Some(TemplateErr::InvalidDialectName(_)))
+    ));
+    assert!(matches!(
+        DialectTemplate::new("my_dsl").unwrap().with_crate_name("my dsl"),
+        Err(Error { err, .. })
+            if matches!(err.downcast_ref::<TemplateErr>(), Some(TemplateErr::InvalidCrateName(_)))
+    ));
+}
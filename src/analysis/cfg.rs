@@ -0,0 +1,296 @@
+//! Control-flow graph of the [BasicBlock]s in a [Region].
+//!
+//! The successors of a block are the blocks its terminator branches to, and its
+//! predecessors are the blocks branching to it. [successors] and [predecessors]
+//! query the IR directly. A [Cfg] is a snapshot of the graph of a region, with
+//! each edge recorded once, answering repeated queries (traversal orders and
+//! reachability) without going through the IR again. Like other analyses, it
+//! isn't updated as the IR changes.
+//!
+//! Traversals start at the entry block of the region: blocks not reachable
+//! from it aren't in the [post-order](Cfg::post_order) or the
+//! [reverse post-order](Cfg::reverse_post_order). In reverse post-order, a block
+//! comes before its successors, except when the edge to a successor is a back edge.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    basic_block::BasicBlock,
+    context::{Context, IrView, Ptr},
+    linked_list::{ContainsLinkedList, LinkedList},
+    region::Region,
+};
+
+/// The successors of `block`, in the order of its terminator's successors,
+/// or none, if it has no terminator.
+pub fn successors(ctx: &impl IrView, block: Ptr<BasicBlock>) -> Vec<Ptr<BasicBlock>> {
+    let term = ctx.get(block).tail();
+    term.map(|term| ctx.get(term).successors().collect())
+        .unwrap_or_default()
+}
+
+/// The predecessors of `block`, each listed once,
+/// in the order they appear in the region (as in a [Cfg]).
+pub fn predecessors(ctx: &Context, block: Ptr<BasicBlock>) -> Vec<Ptr<BasicBlock>> {
+    let preds: FxHashSet<_> = block.preds(ctx).into_iter().collect();
+    if preds.is_empty() {
+        return vec![];
+    }
+    let region = block
+        .deref(ctx)
+        .container()
+        .expect("Block with predecessors must be in a region");
+    region
+        .deref(ctx)
+        .iter(ctx)
+        .filter(|block| preds.contains(block))
+        .collect()
+}
+
+/// The control-flow graph of the blocks in a [Region].
+/// See [module](self) documentation.
+pub struct Cfg {
+    region: Ptr<Region>,
+    /// The blocks of the region, in their order in it.
+    blocks: Vec<Ptr<BasicBlock>>,
+    /// Index of each block in `blocks`.
+    index: FxHashMap<Ptr<BasicBlock>, usize>,
+    /// The successors and predecessors of each block, each listed once.
+    succs: Vec<Vec<usize>>,
+    preds: Vec<Vec<usize>>,
+    /// The blocks reachable from the entry block, in post-order.
+    post_order: Vec<usize>,
+    /// Is each block reachable from the entry block?
+    reachable: Vec<bool>,
+}
+
+impl Cfg {
+    /// Compute the control-flow graph of the blocks in `region`.
+    pub fn new(ctx: &impl IrView, region: Ptr<Region>) -> Cfg {
+        let blocks: Vec<_> = ctx.region_blocks(region).collect();
+        let index: FxHashMap<_, _> = blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, block)| (*block, idx))
+            .collect();
+
+        let mut succs = vec![vec![]; blocks.len()];
+        let mut preds = vec![vec![]; blocks.len()];
+        for (idx, block) in blocks.iter().enumerate() {
+            for succ in successors(ctx, *block) {
+                let succ = *index
+                    .get(&succ)
+                    .expect("Successor of a block must be in the same region");
+                if !succs[idx].contains(&succ) {
+                    succs[idx].push(succ);
+                    preds[succ].push(idx);
+                }
+            }
+        }
+
+        // Depth-first from the entry block, for the post-order.
+        let mut post_order = vec![];
+        let mut visited = vec![false; blocks.len()];
+        if !blocks.is_empty() {
+            visited[0] = true;
+            let mut stack = vec![(0, 0)];
+            while let Some((idx, next_succ)) = stack.last_mut() {
+                let idx = *idx;
+                match succs[idx].get(*next_succ) {
+                    Some(&succ) => {
+                        *next_succ += 1;
+                        if !visited[succ] {
+                            visited[succ] = true;
+                            stack.push((succ, 0));
+                        }
+                    }
+                    None => {
+                        post_order.push(idx);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        Cfg {
+            region,
+            blocks,
+            index,
+            succs,
+            preds,
+            post_order,
+            reachable: visited,
+        }
+    }
+
+    /// The region whose blocks are in this graph.
+    pub fn region(&self) -> Ptr<Region> {
+        self.region
+    }
+
+    /// The entry block of the region, if it has any blocks.
+    pub fn entry(&self) -> Option<Ptr<BasicBlock>> {
+        self.blocks.first().copied()
+    }
+
+    /// All blocks of the region, in their order in it.
+    pub fn blocks(&self) -> &[Ptr<BasicBlock>] {
+        &self.blocks
+    }
+
+    fn blocks_at<'a>(&'a self, indices: &'a [usize]) -> impl Iterator<Item = Ptr<BasicBlock>> + 'a {
+        indices.iter().map(|&idx| self.blocks[idx])
+    }
+
+    fn edges<'a>(
+        &'a self,
+        edges: &'a [Vec<usize>],
+        block: Ptr<BasicBlock>,
+    ) -> impl Iterator<Item = Ptr<BasicBlock>> + 'a {
+        let indices = self.index.get(&block).map_or(&[][..], |&idx| &edges[idx]);
+        self.blocks_at(indices)
+    }
+
+    /// The successors of `block`.
+    pub fn succs(&self, block: Ptr<BasicBlock>) -> impl Iterator<Item = Ptr<BasicBlock>> + '_ {
+        self.edges(&self.succs, block)
+    }
+
+    /// The predecessors of `block`.
+    pub fn preds(&self, block: Ptr<BasicBlock>) -> impl Iterator<Item = Ptr<BasicBlock>> + '_ {
+        self.edges(&self.preds, block)
+    }
+
+    /// The blocks reachable from the entry block, each after its successors
+    /// (other than those it's reached from).
+    pub fn post_order(&self) -> impl DoubleEndedIterator<Item = Ptr<BasicBlock>> + '_ {
+        self.post_order.iter().map(|&idx| self.blocks[idx])
+    }
+
+    /// The blocks reachable from the entry block, each before its successors
+    /// (other than through back edges).
+    pub fn reverse_post_order(&self) -> impl DoubleEndedIterator<Item = Ptr<BasicBlock>> + '_ {
+        self.post_order().rev()
+    }
+
+    /// Is `block` reachable from the entry block? The entry block always is.
+    pub fn is_reachable(&self, block: Ptr<BasicBlock>) -> bool {
+        self.index
+            .get(&block)
+            .is_some_and(|&idx| self.reachable[idx])
+    }
+
+    /// The blocks not reachable from the entry block, in their order in the region.
+    pub fn unreachable_blocks(&self) -> Vec<Ptr<BasicBlock>> {
+        self.blocks
+            .iter()
+            .zip(&self.reachable)
+            .filter_map(|(block, reachable)| (!reachable).then_some(*block))
+            .collect()
+    }
+
+    /// The blocks reachable from `from`, including `from` itself.
+    pub fn reachable_from(&self, from: Ptr<BasicBlock>) -> FxHashSet<Ptr<BasicBlock>> {
+        let mut reached = FxHashSet::default();
+        let mut worklist: Vec<_> = self.index.get(&from).copied().into_iter().collect();
+        while let Some(idx) = worklist.pop() {
+            if reached.insert(self.blocks[idx]) {
+                worklist.extend(&self.succs[idx]);
+            }
+        }
+        reached
+    }
+
+    /// Is there a path from `from` to `to`? There's always one from a block to itself.
+    pub fn reaches(&self, from: Ptr<BasicBlock>, to: Ptr<BasicBlock>) -> bool {
+        from == to || self.reachable_from(from).contains(&to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            op_interfaces::OneResultInterface,
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::{Context, Ptr},
+        op::Op,
+        test_dialect::{
+            self,
+            ops::{BrOp, CondBrOp, ProduceOp, TerminatorOp},
+        },
+    };
+
+    use super::{Cfg, predecessors, successors};
+
+    // entry -> (a | a), a -> (loop | exit), loop -> a, and dead -> exit.
+    #[test]
+    fn test_cfg() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless);
+        let func_ty = FunctionType::get(ctx, vec![], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let region = func.operation().deref(ctx).region(0);
+        let entry = func.get_entry_block(ctx);
+        let new_block = |ctx: &mut Context| {
+            let block = BasicBlock::new(ctx, None, vec![]);
+            block.insert_at_back(region, ctx);
+            block
+        };
+        let [a, dead, exit, r#loop] = [(); 4].map(|_| new_block(ctx));
+
+        let cond_br = |ctx: &mut Context, block: Ptr<BasicBlock>, t, f| {
+            let cond = ProduceOp::new(ctx, i1_ty.into());
+            cond.operation().insert_at_back(block, ctx);
+            let cond_val = cond.result(ctx);
+            CondBrOp::new(ctx, cond_val, t, f)
+                .operation()
+                .insert_at_back(block, ctx);
+        };
+        cond_br(ctx, entry, a, a);
+        cond_br(ctx, a, r#loop, exit);
+        BrOp::new(ctx, a, vec![])
+            .operation()
+            .insert_at_back(r#loop, ctx);
+        BrOp::new(ctx, exit, vec![])
+            .operation()
+            .insert_at_back(dead, ctx);
+        TerminatorOp::new(ctx, vec![])
+            .operation()
+            .insert_at_back(exit, ctx);
+
+        assert_eq!(successors(ctx, entry), vec![a, a]);
+        assert_eq!(predecessors(ctx, a), vec![entry, r#loop]);
+        assert!(successors(ctx, exit).is_empty());
+
+        let cfg = Cfg::new(ctx, region);
+        assert_eq!(cfg.entry(), Some(entry));
+        assert_eq!(cfg.succs(entry).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(cfg.preds(a).collect::<Vec<_>>(), vec![entry, r#loop]);
+        assert_eq!(cfg.preds(exit).collect::<Vec<_>>(), vec![a, dead]);
+        assert_eq!(
+            cfg.post_order().collect::<Vec<_>>(),
+            vec![r#loop, exit, a, entry]
+        );
+        assert_eq!(
+            cfg.reverse_post_order().collect::<Vec<_>>(),
+            vec![entry, a, exit, r#loop]
+        );
+
+        assert!(cfg.is_reachable(entry) && cfg.is_reachable(r#loop));
+        assert!(!cfg.is_reachable(dead));
+        assert_eq!(cfg.unreachable_blocks(), vec![dead]);
+        assert!(cfg.reaches(r#loop, r#loop) && cfg.reaches(r#loop, exit));
+        assert!(cfg.reaches(dead, exit) && !cfg.reaches(exit, a));
+        assert!(!cfg.reaches(a, entry));
+        assert_eq!(cfg.reachable_from(r#loop).len(), 3);
+    }
+}
@@ -0,0 +1,607 @@
+//! Dominance and post-dominance of the [BasicBlock]s in a [Region].
+//!
+//! A block `a` dominates a block `b` if every path from the entry block to `b`
+//! goes through `a`. Dually, `a` post-dominates `b` if every path from `b` to an
+//! exit block (one without successors) goes through `a`. A [DominatorTree] is
+//! computed for either relation, using the iterative algorithm of Cooper, Harvey
+//! and Kennedy ("A Simple, Fast Dominance Algorithm").
+//!
+//! Blocks that can't be reached from the entry (or that can't reach an exit, for
+//! post-dominance) aren't in the tree. Like in LLVM, they are dominated by every
+//! block, and dominate no other (reachable) block.
+//!
+//! The dominance frontier of a block `a` is the set of blocks `b` such that `a`
+//! dominates a predecessor of `b`, but doesn't properly dominate `b`. The
+//! [iterated dominance frontier](DominatorTree::iterated_dominance_frontier)
+//! of the blocks defining a variable is where SSA construction places its
+//! block arguments (phi nodes).
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    analysis::cfg::successors,
+    basic_block::BasicBlock,
+    builtin::op_interfaces::RegionKindInterface,
+    context::{Context, IrView, Ptr},
+    linked_list::LinkedList,
+    op::op_cast,
+    operation::Operation,
+    region::Region,
+    value::Value,
+};
+
+/// Index of the (virtual) root of a [DominatorTree]. Its children are the entry
+/// block, or, for post-dominance, every exit block.
+const VIRTUAL_ROOT: usize = 0;
+
+/// The (post-)dominator tree of the blocks in a [Region].
+/// See [module](self) documentation.
+pub struct DominatorTree {
+    region: Ptr<Region>,
+    post: bool,
+    /// The blocks in the tree, in reverse post-order from the (virtual) root,
+    /// which is at [VIRTUAL_ROOT] (with no block).
+    blocks: Vec<Option<Ptr<BasicBlock>>>,
+    /// Index of each block in `blocks`.
+    index: FxHashMap<Ptr<BasicBlock>, usize>,
+    /// The immediate dominator of each block. The root is its own.
+    idom: Vec<usize>,
+    /// The children of each block in the tree.
+    children: Vec<Vec<usize>>,
+    /// Pre-order and post-order numbers of each block in the tree,
+    /// for constant-time dominance queries.
+    pre_order: Vec<usize>,
+    post_order: Vec<usize>,
+    /// The dominance frontier of each block.
+    frontier: Vec<Vec<usize>>,
+}
+
+/// The ancestor of `op` (or `op` itself) that's immediately in `region`, and its block.
+fn ancestor_in_region(
+    ctx: &impl IrView,
+    mut op: Ptr<Operation>,
+    region: Ptr<Region>,
+) -> Option<(Ptr<Operation>, Ptr<BasicBlock>)> {
+    loop {
+        let block = ctx.get(op).container()?;
+        let op_region = ctx.get(block).container()?;
+        if op_region == region {
+            return Some((op, block));
+        }
+        op = ctx.get(op_region).parent_op();
+    }
+}
+
+impl DominatorTree {
+    /// Compute the dominator tree of the blocks in `region`.
+    pub fn new(ctx: &impl IrView, region: Ptr<Region>) -> DominatorTree {
+        Self::compute(ctx, region, false)
+    }
+
+    /// Compute the post-dominator tree of the blocks in `region`.
+    pub fn new_post(ctx: &impl IrView, region: Ptr<Region>) -> DominatorTree {
+        Self::compute(ctx, region, true)
+    }
+
+    fn compute(ctx: &impl IrView, region: Ptr<Region>, post: bool) -> DominatorTree {
+        // The control-flow graph, with edges reversed for post-dominance.
+        let mut edges: FxHashMap<Ptr<BasicBlock>, Vec<Ptr<BasicBlock>>> = FxHashMap::default();
+        let mut roots = vec![];
+        for block in ctx.region_blocks(region) {
+            let succs = successors(ctx, block);
+            if post {
+                if succs.is_empty() {
+                    roots.push(block);
+                }
+                for succ in succs {
+                    edges.entry(succ).or_default().push(block);
+                }
+            } else {
+                if roots.is_empty() {
+                    roots.push(block);
+                }
+                edges.entry(block).or_default().extend(succs);
+            }
+        }
+
+        // Number the blocks in reverse post-order from the virtual root.
+        let mut post_order_blocks = vec![];
+        let mut visited = FxHashSet::default();
+        for &root in &roots {
+            if !visited.insert(root) {
+                continue;
+            }
+            let mut stack = vec![(root, 0)];
+            while let Some((block, next_edge)) = stack.last_mut() {
+                let block = *block;
+                let next = edges.get(&block).and_then(|edges| edges.get(*next_edge));
+                *next_edge += 1;
+                match next {
+                    Some(&next) => {
+                        if visited.insert(next) {
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => {
+                        post_order_blocks.push(block);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        let mut blocks = vec![None];
+        blocks.extend(post_order_blocks.into_iter().rev().map(Some));
+        let index: FxHashMap<_, _> = blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, block)| block.map(|block| (block, idx)))
+            .collect();
+
+        // Predecessors of each block (in the direction of dominance).
+        let mut preds = vec![vec![]; blocks.len()];
+        for root in roots {
+            preds[index[&root]].push(VIRTUAL_ROOT);
+        }
+        for (idx, block) in blocks.iter().enumerate().skip(1) {
+            for next in edges.get(&block.unwrap()).into_iter().flatten() {
+                preds[index[next]].push(idx);
+            }
+        }
+
+        // Cooper, Harvey and Kennedy's iterative algorithm. Blocks are numbered
+        // in reverse post-order, so a dominator has a smaller number.
+        const UNDEFINED: usize = usize::MAX;
+        let mut idom = vec![UNDEFINED; blocks.len()];
+        idom[VIRTUAL_ROOT] = VIRTUAL_ROOT;
+        let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+            while a != b {
+                while a > b {
+                    a = idom[a];
+                }
+                while b > a {
+                    b = idom[b];
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in 1..blocks.len() {
+                let new_idom = preds[idx]
+                    .iter()
+                    .filter(|&&pred| idom[pred] != UNDEFINED)
+                    .copied()
+                    .reduce(|new_idom, pred| intersect(&idom, pred, new_idom))
+                    .expect("A numbered block must have a processed predecessor");
+                if idom[idx] != new_idom {
+                    idom[idx] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut children = vec![vec![]; blocks.len()];
+        for idx in 1..blocks.len() {
+            children[idom[idx]].push(idx);
+        }
+
+        // Number the tree in pre-order and post-order.
+        let mut pre_order = vec![0; blocks.len()];
+        let mut post_order = vec![0; blocks.len()];
+        let (mut pre_num, mut post_num) = (0, 0);
+        let mut stack = vec![(VIRTUAL_ROOT, 0)];
+        pre_order[VIRTUAL_ROOT] = pre_num;
+        while let Some((node, next_child)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&child) = children[node].get(*next_child) {
+                *next_child += 1;
+                pre_num += 1;
+                pre_order[child] = pre_num;
+                stack.push((child, 0));
+            } else {
+                post_order[node] = post_num;
+                post_num += 1;
+                stack.pop();
+            }
+        }
+
+        // Dominance frontiers, by walking up from the predecessors of join points.
+        let mut frontier = vec![vec![]; blocks.len()];
+        for (idx, preds) in preds.iter().enumerate().skip(1) {
+            if preds.len() < 2 {
+                continue;
+            }
+            for &pred in preds {
+                let mut runner = pred;
+                while runner != idom[idx] {
+                    if !frontier[runner].contains(&idx) {
+                        frontier[runner].push(idx);
+                    }
+                    runner = idom[runner];
+                }
+            }
+        }
+
+        DominatorTree {
+            region,
+            post,
+            blocks,
+            index,
+            idom,
+            children,
+            pre_order,
+            post_order,
+            frontier,
+        }
+    }
+
+    /// The region whose blocks are in this tree.
+    pub fn region(&self) -> Ptr<Region> {
+        self.region
+    }
+
+    /// Is this a post-dominator tree?
+    pub fn is_post_dominator_tree(&self) -> bool {
+        self.post
+    }
+
+    fn block(&self, idx: usize) -> Ptr<BasicBlock> {
+        self.blocks[idx].expect("Virtual root of a dominator tree has no block")
+    }
+
+    fn blocks_at(&self, indices: &[usize]) -> Vec<Ptr<BasicBlock>> {
+        indices.iter().map(|&idx| self.block(idx)).collect()
+    }
+
+    /// The roots of the tree: the entry block or, for post-dominance, the exit blocks.
+    pub fn roots(&self) -> Vec<Ptr<BasicBlock>> {
+        self.blocks_at(&self.children[VIRTUAL_ROOT])
+    }
+
+    /// Is `block` in the tree? See [module](self) documentation.
+    pub fn is_reachable(&self, block: Ptr<BasicBlock>) -> bool {
+        self.index.contains_key(&block)
+    }
+
+    /// The immediate (post-)dominator of `block`, if it's reachable and not a root.
+    pub fn immediate_dominator(&self, block: Ptr<BasicBlock>) -> Option<Ptr<BasicBlock>> {
+        let idom = self.idom[*self.index.get(&block)?];
+        self.blocks[idom]
+    }
+
+    /// The blocks immediately (post-)dominated by `block`.
+    pub fn children(&self, block: Ptr<BasicBlock>) -> Vec<Ptr<BasicBlock>> {
+        self.index
+            .get(&block)
+            .map(|&idx| self.blocks_at(&self.children[idx]))
+            .unwrap_or_default()
+    }
+
+    /// Does `a` (post-)dominate `b`? Every block dominates itself.
+    pub fn dominates(&self, a: Ptr<BasicBlock>, b: Ptr<BasicBlock>) -> bool {
+        if a == b {
+            return true;
+        }
+        let Some(&b) = self.index.get(&b) else {
+            return true;
+        };
+        let Some(&a) = self.index.get(&a) else {
+            return false;
+        };
+        self.pre_order[a] <= self.pre_order[b] && self.post_order[a] >= self.post_order[b]
+    }
+
+    /// Does `a` (post-)dominate `b`, and isn't the same block?
+    pub fn properly_dominates(&self, a: Ptr<BasicBlock>, b: Ptr<BasicBlock>) -> bool {
+        a != b && self.dominates(a, b)
+    }
+
+    /// The closest block (post-)dominating both `a` and `b`, if both are
+    /// reachable and (for post-dominance) there's a single such block.
+    pub fn nearest_common_dominator(
+        &self,
+        a: Ptr<BasicBlock>,
+        b: Ptr<BasicBlock>,
+    ) -> Option<Ptr<BasicBlock>> {
+        let (mut a, mut b) = (*self.index.get(&a)?, *self.index.get(&b)?);
+        while a != b {
+            while a > b {
+                a = self.idom[a];
+            }
+            while b > a {
+                b = self.idom[b];
+            }
+        }
+        self.blocks[a]
+    }
+
+    /// The (post-)dominance frontier of `block`.
+    pub fn dominance_frontier(&self, block: Ptr<BasicBlock>) -> Vec<Ptr<BasicBlock>> {
+        self.index
+            .get(&block)
+            .map(|&idx| self.blocks_at(&self.frontier[idx]))
+            .unwrap_or_default()
+    }
+
+    /// The iterated (post-)dominance frontier of `blocks`: the least fixed point
+    /// of adding the dominance frontiers of the blocks (and of the blocks added).
+    pub fn iterated_dominance_frontier(
+        &self,
+        blocks: impl IntoIterator<Item = Ptr<BasicBlock>>,
+    ) -> Vec<Ptr<BasicBlock>> {
+        let mut worklist: Vec<_> = blocks
+            .into_iter()
+            .filter_map(|block| self.index.get(&block).copied())
+            .collect();
+        let mut in_frontier = FxHashSet::default();
+        let mut result = vec![];
+        while let Some(idx) = worklist.pop() {
+            for &df in &self.frontier[idx] {
+                if in_frontier.insert(df) {
+                    result.push(self.block(df));
+                    worklist.push(df);
+                }
+            }
+        }
+        result
+    }
+
+    /// Does `a`, an operation in this region, properly (post-)dominate `b`,
+    /// an operation in (or nested in) this region? In the same block, `a` must
+    /// come before (after, for post-dominance) `b`, or the operation that `b`
+    /// is nested in. An operation doesn't dominate the operations nested in it.
+    pub fn properly_dominates_op(
+        &self,
+        ctx: &impl IrView,
+        a: Ptr<Operation>,
+        b: Ptr<Operation>,
+    ) -> bool {
+        let Some((a, a_block)) = ancestor_in_region(ctx, a, self.region) else {
+            return false;
+        };
+        let Some((b, b_block)) = ancestor_in_region(ctx, b, self.region) else {
+            return false;
+        };
+        if a_block != b_block {
+            return self.properly_dominates(a_block, b_block);
+        }
+        if a == b {
+            return false;
+        }
+        let (first, second) = if self.post { (b, a) } else { (a, b) };
+        let mut cur = ctx.get(first).next();
+        while let Some(op) = cur {
+            if op == second {
+                return true;
+            }
+            cur = ctx.get(op).next();
+        }
+        false
+    }
+}
+
+/// The [DominatorTree]s of the regions in the IR, computed as they're needed,
+/// answering whether definitions dominate their uses.
+///
+/// Regions whose parent op has a [RegionKindInterface] saying that they don't
+/// need [SSA dominance](RegionKindInterface::has_ssa_dominance) (graph regions)
+/// are exempt: a value defined in one is visible anywhere in it. Asking an operation
+/// for its [RegionKindInterface] takes a [Context], so on a
+/// [FrozenContext](crate::context::FrozenContext), which regions are exempt is told to
+/// [properly_dominates_value_with](Self::properly_dominates_value_with) instead.
+#[derive(Default)]
+pub struct DominanceInfo {
+    trees: FxHashMap<Ptr<Region>, DominatorTree>,
+    /// The position of operations in the blocks numbered so far.
+    op_positions: FxHashMap<Ptr<Operation>, usize>,
+    numbered_blocks: FxHashSet<Ptr<BasicBlock>>,
+}
+
+impl DominanceInfo {
+    pub fn new() -> DominanceInfo {
+        Self::default()
+    }
+
+    /// The dominator tree of `region`.
+    pub fn tree(&mut self, ctx: &impl IrView, region: Ptr<Region>) -> &DominatorTree {
+        self.trees
+            .entry(region)
+            .or_insert_with(|| DominatorTree::new(ctx, region))
+    }
+
+    /// Does `region` need its definitions to dominate their uses?
+    pub fn has_ssa_dominance(ctx: &Context, region: Ptr<Region>) -> bool {
+        let parent = region.deref(ctx).parent_op();
+        let parent_op = Operation::op(parent, ctx);
+        let Some(region_kind) = op_cast::<dyn RegionKindInterface>(&*parent_op) else {
+            return true;
+        };
+        let idx = parent
+            .deref(ctx)
+            .regions()
+            .position(|parent_region| parent_region == region)
+            .expect("Region must be in its parent operation");
+        region_kind.has_ssa_dominance(idx)
+    }
+
+    /// The position of `op` in its `block`.
+    fn position(&mut self, ctx: &impl IrView, op: Ptr<Operation>, block: Ptr<BasicBlock>) -> usize {
+        if self.numbered_blocks.insert(block) {
+            for (pos, op) in ctx.block_ops(block).enumerate() {
+                self.op_positions.insert(op, pos);
+            }
+        }
+        self.op_positions[&op]
+    }
+
+    /// Is `value` defined before `op`, and visible to it? That is, is `value`
+    /// defined by an operation (not `op` itself) that properly dominates `op`,
+    /// or is it an argument of a block that dominates `op`?
+    pub fn properly_dominates_value(
+        &mut self,
+        ctx: &Context,
+        value: Value,
+        op: Ptr<Operation>,
+    ) -> bool {
+        self.properly_dominates_value_with(ctx, value, op, |region| {
+            Self::has_ssa_dominance(ctx, region)
+        })
+    }
+
+    /// Like [properly_dominates_value](Self::properly_dominates_value), on any [IrView],
+    /// with `has_ssa_dominance` telling whether a region needs its definitions to dominate
+    /// their uses (as [DominanceInfo::has_ssa_dominance] does).
+    pub fn properly_dominates_value_with(
+        &mut self,
+        ctx: &impl IrView,
+        value: Value,
+        op: Ptr<Operation>,
+        has_ssa_dominance: impl Fn(Ptr<Region>) -> bool,
+    ) -> bool {
+        let (def_op, def_block) = match value {
+            Value::OpResult { op: def_op, .. } => {
+                let Some(def_block) = ctx.get(def_op).container() else {
+                    return false;
+                };
+                (Some(def_op), def_block)
+            }
+            Value::BlockArgument { block, .. } => (None, block),
+        };
+        let Some(region) = ctx.get(def_block).container() else {
+            return false;
+        };
+        let Some((user, user_block)) = ancestor_in_region(ctx, op, region) else {
+            return false;
+        };
+        if Some(user) == def_op {
+            return false;
+        }
+        if !has_ssa_dominance(region) {
+            return true;
+        }
+        if def_block != user_block {
+            return self
+                .tree(ctx, region)
+                .properly_dominates(def_block, user_block);
+        }
+        def_op.is_none_or(|def_op| {
+            self.position(ctx, def_op, def_block) < self.position(ctx, user, user_block)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            op_interfaces::OneResultInterface,
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::{Context, Ptr},
+        linked_list::ContainsLinkedList,
+        op::Op,
+        test_dialect::{
+            self,
+            ops::{BrOp, CondBrOp, NoopOp, ProduceOp, SingleBlockRegionOp, TerminatorOp},
+        },
+    };
+
+    use super::DominatorTree;
+
+    // entry -> (a | b), a -> join, b -> (join | exit), join -> (entry | exit),
+    // and an unreachable block.
+    #[test]
+    fn test_dominance() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless);
+        let func_ty = FunctionType::get(ctx, vec![], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let region = func.operation().deref(ctx).region(0);
+        let entry = func.get_entry_block(ctx);
+        let new_block = |ctx: &mut Context| {
+            let block = BasicBlock::new(ctx, None, vec![]);
+            block.insert_at_back(region, ctx);
+            block
+        };
+        let [a, b, join, exit, dead] = [(); 5].map(|_| new_block(ctx));
+
+        let cond_br = |ctx: &mut Context, block: Ptr<BasicBlock>, t, f| {
+            let cond = ProduceOp::new(ctx, i1_ty.into());
+            cond.operation().insert_at_back(block, ctx);
+            let cond_val = cond.result(ctx);
+            CondBrOp::new(ctx, cond_val, t, f)
+                .operation()
+                .insert_at_back(block, ctx);
+            cond.operation()
+        };
+        let cond_op = cond_br(ctx, entry, a, b);
+        BrOp::new(ctx, join, vec![])
+            .operation()
+            .insert_at_back(a, ctx);
+        cond_br(ctx, b, join, exit);
+        cond_br(ctx, join, entry, exit);
+        TerminatorOp::new(ctx, vec![])
+            .operation()
+            .insert_at_back(exit, ctx);
+        BrOp::new(ctx, exit, vec![])
+            .operation()
+            .insert_at_back(dead, ctx);
+
+        let dom = DominatorTree::new(ctx, region);
+        assert_eq!(dom.roots(), vec![entry]);
+        assert!(!dom.is_reachable(dead));
+        for block in [a, b, join, exit] {
+            assert_eq!(dom.immediate_dominator(block), Some(entry));
+        }
+        assert!(dom.immediate_dominator(entry).is_none());
+        assert!(dom.dominates(entry, join) && dom.properly_dominates(entry, join));
+        assert!(dom.dominates(join, join) && !dom.properly_dominates(join, join));
+        assert!(!dom.dominates(a, join) && !dom.dominates(b, exit));
+        // Unreachable blocks are dominated by all blocks, and dominate none.
+        assert!(dom.dominates(a, dead) && !dom.dominates(dead, a));
+        assert_eq!(dom.nearest_common_dominator(a, exit), Some(entry));
+        let mut frontier = dom.dominance_frontier(b);
+        frontier.sort_by_key(|block| dom.index[block]);
+        assert_eq!(frontier, vec![join, exit]);
+        assert_eq!(dom.dominance_frontier(join), vec![entry, exit]);
+        let mut idf = dom.iterated_dominance_frontier([a]);
+        idf.sort_by_key(|block| dom.index[block]);
+        assert_eq!(idf, vec![entry, join, exit]);
+
+        // Operations in the same block, and nested in other operations.
+        let noop = NoopOp::new(ctx).operation();
+        noop.insert_at_front(a, ctx);
+        assert!(dom.properly_dominates_op(ctx, cond_op, noop));
+        assert!(!dom.properly_dominates_op(ctx, noop, cond_op));
+        let entry_term = entry.deref(ctx).tail().unwrap();
+        assert!(dom.properly_dominates_op(ctx, cond_op, entry_term));
+        assert!(!dom.properly_dominates_op(ctx, entry_term, cond_op));
+        let nested = NoopOp::new(ctx).operation();
+        let nested_in = SingleBlockRegionOp::new(ctx);
+        let nested_block = nested_in.operation().deref(ctx).region(0).deref(ctx).head();
+        nested.insert_at_back(nested_block.unwrap(), ctx);
+        nested_in.operation().insert_before(ctx, noop);
+        assert!(dom.properly_dominates_op(ctx, cond_op, nested));
+        assert!(!dom.properly_dominates_op(ctx, nested_in.operation(), nested));
+
+        let post_dom = DominatorTree::new_post(ctx, region);
+        assert_eq!(post_dom.roots(), vec![exit]);
+        // Unlike for dominance, the block unreachable from the entry can reach the exit.
+        assert_eq!(post_dom.immediate_dominator(dead), Some(exit));
+        assert_eq!(post_dom.immediate_dominator(a), Some(join));
+        assert_eq!(post_dom.immediate_dominator(entry), Some(exit));
+        assert!(post_dom.dominates(exit, a) && !post_dom.dominates(join, b));
+        let mut frontier = post_dom.dominance_frontier(join);
+        frontier.sort_by_key(|block| dom.index[block]);
+        assert_eq!(frontier, vec![entry, b]);
+        assert!(post_dom.properly_dominates_op(ctx, entry_term, cond_op));
+    }
+}
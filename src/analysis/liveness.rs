@@ -0,0 +1,426 @@
+//! Liveness of the SSA [Value]s used in a [Region].
+//!
+//! A value is live at a point if it's used after it (along some path in the
+//! [control-flow graph](crate::analysis::cfg)), without being defined again.
+//! [Liveness] computes, for each block of a region, the values live when it's
+//! entered (live-in) and when it's exited (live-out), by the usual backward
+//! data-flow iteration. Values used by the operations nested in an operation
+//! (but not defined in it) are considered used by that operation, so that the
+//! liveness of values across nested regions is accounted for at the level of
+//! the region. The liveness of values defined and used only in nested regions
+//! isn't computed: it can be, by computing the liveness of those regions.
+//!
+//! Within a block, [is_live_after](Liveness::is_live_after) and
+//! [live_after](Liveness::live_after) tell what's live right after an operation,
+//! and [last_uses](Liveness::last_uses) where a value stops being live, for
+//! transformations such as register allocation or the reuse of stack slots.
+//! Like other analyses, it isn't updated as the IR changes.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    analysis::cfg::Cfg,
+    basic_block::BasicBlock,
+    context::{IrView, Ptr},
+    linked_list::LinkedList,
+    operation::Operation,
+    region::Region,
+    value::Value,
+};
+
+/// The values used by `op`, or by the operations nested in it, other than those
+/// defined in it.
+fn op_uses(ctx: &impl IrView, op: Ptr<Operation>) -> FxHashSet<Value> {
+    let op_ref = ctx.get(op);
+    let mut uses: FxHashSet<_> = op_ref.operands().collect();
+    let mut defined = FxHashSet::default();
+    let mut nested_uses = vec![];
+    let mut worklist: Vec<_> = op_ref.regions().collect();
+    while let Some(region) = worklist.pop() {
+        for block in ctx.region_blocks(region) {
+            defined.extend(ctx.get(block).arguments());
+            for nested in ctx.block_ops(block) {
+                let nested_ref = ctx.get(nested);
+                defined.extend(nested_ref.results());
+                nested_uses.extend(nested_ref.operands());
+                worklist.extend(nested_ref.regions());
+            }
+        }
+    }
+    uses.extend(
+        nested_uses
+            .into_iter()
+            .filter(|used| !defined.contains(used)),
+    );
+    uses
+}
+
+/// The liveness of values in a [BasicBlock].
+struct BlockLiveness {
+    live_in: FxHashSet<Value>,
+    live_out: FxHashSet<Value>,
+    /// The position, in the block, of the last operation using each value used in it.
+    last_use: FxHashMap<Value, usize>,
+    /// The operations in the block, in order.
+    ops: Vec<Ptr<Operation>>,
+}
+
+/// The liveness of the values used in a [Region].
+/// See [module](self) documentation.
+pub struct Liveness {
+    region: Ptr<Region>,
+    blocks: FxHashMap<Ptr<BasicBlock>, BlockLiveness>,
+    /// The position of each operation in its block.
+    positions: FxHashMap<Ptr<Operation>, usize>,
+}
+
+impl Liveness {
+    /// Compute the liveness of the values used in `region`.
+    pub fn new(ctx: &impl IrView, region: Ptr<Region>) -> Liveness {
+        let cfg = Cfg::new(ctx, region);
+        let mut positions = FxHashMap::default();
+        let mut liveness = vec![];
+        // The values used in each block before being defined in it, and those defined in it.
+        let mut used_before_def = vec![];
+        let mut defined = vec![];
+        for block in cfg.blocks() {
+            let block_ref = ctx.get(*block);
+            let ops: Vec<_> = ctx.block_ops(*block).collect();
+            let uses: Vec<_> = ops.iter().map(|op| op_uses(ctx, *op)).collect();
+
+            let mut last_use = FxHashMap::default();
+            for (pos, op_uses) in uses.iter().enumerate() {
+                positions.insert(ops[pos], pos);
+                last_use.extend(op_uses.iter().map(|used| (*used, pos)));
+            }
+            let mut block_used = FxHashSet::default();
+            let mut block_defined: FxHashSet<_> = block_ref.arguments().collect();
+            for (op, op_uses) in ops.iter().zip(uses).rev() {
+                let results: Vec<_> = ctx.get(*op).results().collect();
+                for result in &results {
+                    block_used.remove(result);
+                }
+                block_defined.extend(results);
+                block_used.extend(op_uses);
+            }
+            for arg in block_ref.arguments() {
+                block_used.remove(&arg);
+            }
+
+            liveness.push(BlockLiveness {
+                live_in: block_used.clone(),
+                live_out: FxHashSet::default(),
+                last_use,
+                ops,
+            });
+            used_before_def.push(block_used);
+            defined.push(block_defined);
+        }
+
+        // Iterate to a fixed point, going backwards, so that successors
+        // are mostly visited before their predecessors.
+        let index: FxHashMap<_, _> = cfg
+            .blocks()
+            .iter()
+            .enumerate()
+            .map(|(idx, block)| (*block, idx))
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, block) in cfg.blocks().iter().enumerate().rev() {
+                let live_out: FxHashSet<_> = cfg
+                    .succs(*block)
+                    .flat_map(|succ| liveness[index[&succ]].live_in.iter().copied())
+                    .collect();
+                let live_in: FxHashSet<_> = live_out
+                    .iter()
+                    .filter(|live| !defined[idx].contains(live))
+                    .chain(&used_before_def[idx])
+                    .copied()
+                    .collect();
+                let block_liveness = &mut liveness[idx];
+                if live_in != block_liveness.live_in {
+                    block_liveness.live_in = live_in;
+                    changed = true;
+                }
+                block_liveness.live_out = live_out;
+            }
+        }
+
+        Liveness {
+            region,
+            blocks: cfg.blocks().iter().copied().zip(liveness).collect(),
+            positions,
+        }
+    }
+
+    /// The region whose liveness this is.
+    pub fn region(&self) -> Ptr<Region> {
+        self.region
+    }
+
+    fn block(&self, block: Ptr<BasicBlock>) -> &BlockLiveness {
+        self.blocks
+            .get(&block)
+            .expect("Block must be in the region whose liveness was computed")
+    }
+
+    /// The block containing `op`, its liveness, and the position of `op` in it.
+    fn op_block(
+        &self,
+        ctx: &impl IrView,
+        op: Ptr<Operation>,
+    ) -> (Ptr<BasicBlock>, &BlockLiveness, usize) {
+        let block = ctx
+            .get(op)
+            .container()
+            .expect("Operation must be in a block");
+        let pos = *self
+            .positions
+            .get(&op)
+            .expect("Operation must be in the region whose liveness was computed");
+        (block, self.block(block), pos)
+    }
+
+    /// The values live when `block` is entered.
+    pub fn live_in(&self, block: Ptr<BasicBlock>) -> &FxHashSet<Value> {
+        &self.block(block).live_in
+    }
+
+    /// The values live when `block` is exited, i.e., live when one of its successors is entered.
+    pub fn live_out(&self, block: Ptr<BasicBlock>) -> &FxHashSet<Value> {
+        &self.block(block).live_out
+    }
+
+    /// Is `value` live right after `op`, which is in the region? It is if it's
+    /// defined before, and used after `op` (or by a successor of its block).
+    pub fn is_live_after(&self, ctx: &impl IrView, value: Value, op: Ptr<Operation>) -> bool {
+        let (block, block_liveness, pos) = self.op_block(ctx, op);
+        let defined_before = match value {
+            Value::OpResult { op: def, .. } if ctx.get(def).container() == Some(block) => {
+                self.positions[&def] <= pos
+            }
+            // Arguments of the block, and values defined in other blocks (which must
+            // then be live-in if they're used), are defined before `op`.
+            _ => true,
+        };
+        defined_before
+            && (block_liveness.live_out.contains(&value)
+                || block_liveness
+                    .last_use
+                    .get(&value)
+                    .is_some_and(|last| *last > pos))
+    }
+
+    /// The values live right after `op`, which is in the region.
+    pub fn live_after(&self, ctx: &impl IrView, op: Ptr<Operation>) -> FxHashSet<Value> {
+        let (_, block_liveness, pos) = self.op_block(ctx, op);
+        let mut live = block_liveness.live_out.clone();
+        for later in block_liveness.ops[pos + 1..].iter().rev() {
+            for result in ctx.get(*later).results() {
+                live.remove(&result);
+            }
+            live.extend(op_uses(ctx, *later));
+        }
+        live
+    }
+
+    /// Is `op`, which is in the region, the last use of `value`, after which it's dead?
+    pub fn is_last_use(&self, ctx: &impl IrView, value: Value, op: Ptr<Operation>) -> bool {
+        let (_, block_liveness, pos) = self.op_block(ctx, op);
+        !block_liveness.live_out.contains(&value)
+            && block_liveness.last_use.get(&value) == Some(&pos)
+    }
+
+    /// The operations, in the region, after which `value` is dead, having used it
+    /// last (in their blocks), in the order of their blocks. For a value used by
+    /// operations nested in others, those containing them are listed.
+    pub fn last_uses(&self, ctx: &impl IrView, value: Value) -> Vec<Ptr<Operation>> {
+        ctx.region_blocks(self.region)
+            .filter_map(|block| {
+                let block_liveness = self.block(block);
+                if block_liveness.live_out.contains(&value) {
+                    return None;
+                }
+                let last = block_liveness.last_use.get(&value)?;
+                Some(block_liveness.ops[*last])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{cfg::Cfg, dominance::DominatorTree},
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            op_interfaces::{OneRegionInterface, SingleBlockRegionInterface},
+            ops::FuncOp,
+        },
+        context::{Context, IrView, Ptr},
+        linked_list::ContainsLinkedList,
+        operation::Operation,
+        parse_source,
+        region::Region,
+        result::Result,
+        test_dialect,
+        value::Value,
+    };
+    use rustc_hash::FxHashSet;
+
+    use super::Liveness;
+
+    const INPUT: &str = r#"
+            builtin.module @m {
+              ^entry():
+                builtin.func @f: builtin.function <(builtin.integer i64)->()> {
+                  ^entry(a:builtin.integer i64):
+                    x = test.produce : builtin.integer i64;
+                    c = test.produce : builtin.integer i1;
+                    test.br ^loop()
+                  ^loop():
+                    n = test.binary x, a : builtin.integer i64;
+                    test.single_block_region () [] []: <() -> ()>
+                    {
+                      ^body():
+                        test.consume n
+                    };
+                    "test.cond_br" (c) [^loop, ^exit] []: <(builtin.integer i1) -> ()>
+                  ^exit():
+                    test.consume n;
+                    test.terminator
+                }
+            }"#;
+
+    /// Parse [INPUT], returning the body of `@f`.
+    fn parse_func_body(ctx: &mut Context) -> Result<Ptr<Region>> {
+        builtin::register(ctx);
+        test_dialect::register(ctx);
+        let module = parse_source(ctx, INPUT)?;
+        let func = module.body(ctx, 0).deref(ctx).head().unwrap();
+        let func = *Operation::op(func, ctx).downcast_ref::<FuncOp>().unwrap();
+        Ok(func.region(ctx))
+    }
+
+    #[test]
+    fn test_liveness() -> Result<()> {
+        let ctx = &mut Context::new();
+        let region = parse_func_body(ctx)?;
+        let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+        let [entry, r#loop, exit] = blocks[..] else {
+            panic!("Expected three blocks");
+        };
+        let ops = |block: Ptr<BasicBlock>| -> Vec<_> { block.deref(ctx).iter(ctx).collect() };
+        let (entry_ops, loop_ops, exit_ops) = (ops(entry), ops(r#loop), ops(exit));
+        let a = entry.deref(ctx).argument(0);
+        let result = |op: Ptr<Operation>| -> Value { op.deref(ctx).result(0) };
+        let (x, c, n) = (
+            result(entry_ops[0]),
+            result(entry_ops[1]),
+            result(loop_ops[0]),
+        );
+
+        let liveness = Liveness::new(ctx, region);
+        let set = |values: &[Value]| values.iter().copied().collect();
+        assert!(liveness.live_in(entry).is_empty());
+        assert_eq!(*liveness.live_out(entry), set(&[a, x, c]));
+        assert_eq!(*liveness.live_in(r#loop), set(&[a, x, c]));
+        assert_eq!(*liveness.live_out(r#loop), set(&[a, x, c, n]));
+        assert_eq!(*liveness.live_in(exit), set(&[n]));
+        assert!(liveness.live_out(exit).is_empty());
+
+        // `c` isn't defined yet after `x`.
+        assert!(liveness.is_live_after(ctx, x, entry_ops[0]));
+        assert!(!liveness.is_live_after(ctx, c, entry_ops[0]));
+        // `n` is used in the region nested in the op after its definition.
+        assert_eq!(liveness.live_after(ctx, loop_ops[0]), set(&[a, x, c, n]));
+        assert_eq!(liveness.live_after(ctx, exit_ops[0]), set(&[]));
+        assert!(liveness.is_live_after(ctx, n, loop_ops[1]));
+        assert!(!liveness.is_live_after(ctx, n, exit_ops[0]));
+
+        assert!(liveness.is_last_use(ctx, n, exit_ops[0]));
+        assert!(!liveness.is_last_use(ctx, n, loop_ops[1]));
+        assert_eq!(liveness.last_uses(ctx, n), vec![exit_ops[0]]);
+        assert!(liveness.last_uses(ctx, x).is_empty());
+        Ok(())
+    }
+
+    /// What the CFG, (post-)dominator trees and liveness of `region` say about `blocks`,
+    /// with the live values given by their position in `values`.
+    fn summary(
+        ctx: &impl IrView,
+        region: Ptr<Region>,
+        blocks: &[Ptr<BasicBlock>],
+        values: &[Value],
+    ) -> Vec<String> {
+        let cfg = Cfg::new(ctx, region);
+        let dom_tree = DominatorTree::new(ctx, region);
+        let post_dom_tree = DominatorTree::new_post(ctx, region);
+        let liveness = Liveness::new(ctx, region);
+        let positions = |live: &FxHashSet<Value>| {
+            let mut positions: Vec<_> = live
+                .iter()
+                .map(|value| values.iter().position(|v| v == value).unwrap())
+                .collect();
+            positions.sort();
+            positions
+        };
+        let mut summary = vec![format!("{:?}", cfg.post_order().collect::<Vec<_>>())];
+        for block in blocks {
+            summary.push(format!(
+                "{:?}: idom {:?}, ipdom {:?}, df {:?}, live-in {:?}, live-out {:?}",
+                block,
+                dom_tree.immediate_dominator(*block),
+                post_dom_tree.immediate_dominator(*block),
+                dom_tree.dominance_frontier(*block),
+                positions(liveness.live_in(*block)),
+                positions(liveness.live_out(*block)),
+            ));
+            for op in ctx.block_ops(*block) {
+                summary.push(format!(
+                    "{:?}: live-after {:?}, dominated {:?}",
+                    op,
+                    positions(&liveness.live_after(ctx, op)),
+                    ctx.block_ops(*block)
+                        .map(|other| dom_tree.properly_dominates_op(ctx, op, other))
+                        .collect::<Vec<_>>(),
+                ));
+            }
+        }
+        summary
+    }
+
+    // The analyses computed on a frozen view, from many threads at once,
+    // are the same as those computed on the context.
+    #[test]
+    fn test_analyses_on_frozen_context() -> Result<()> {
+        let ctx = &mut Context::new();
+        let region = parse_func_body(ctx)?;
+        let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+        let mut values = vec![];
+        for block in &blocks {
+            values.extend(block.deref(ctx).arguments());
+            for op in block.deref(ctx).iter(ctx) {
+                values.extend(op.deref(ctx).results());
+            }
+        }
+
+        let expected = summary(ctx, region, &blocks, &values);
+        let func = region.deref(ctx).parent_op();
+        let frozen = ctx.freeze(func);
+        let summaries: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| summary(&frozen, region, &blocks, &values)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert!(summaries.iter().all(|summary| *summary == expected));
+        Ok(())
+    }
+}
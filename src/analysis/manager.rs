@@ -0,0 +1,319 @@
+//! Caching analysis results, attached to values and operations.
+//!
+//! An [Analysis] computes some state (typically, an element of a lattice, such
+//! as "the constant value of `%x`" or "the range of `%y`") for the values and
+//! operations nested in a root operation, recording it in [AnalysisStates].
+//! An [AnalysisManager] computes an analysis on demand, and caches its states,
+//! keyed by the type of the analysis and the root. Later queries for the states
+//! of the same analysis (on the same root) don't recompute them, until the analysis
+//! is [invalidated](AnalysisManager::invalidate). States live as long as the
+//! [AnalysisManager] they're cached in.
+//!
+//! A [PassManager](crate::pass::PassManager) has an [AnalysisManager] for each
+//! [run](crate::pass::PassManager::run), which it passes to
+//! [Pass::run_with_analyses](crate::pass::Pass::run_with_analyses). After each pass,
+//! the analyses that the pass didn't [preserve](AnalysisManager::preserve) are invalidated.
+
+use std::{
+    any::{Any, TypeId},
+    collections::hash_map::Entry,
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    context::{Context, Ptr},
+    operation::Operation,
+    result::Result,
+    value::Value,
+};
+
+/// An analysis, attaching [State](Analysis::State) to values and operations.
+/// See [module](self) documentation.
+pub trait Analysis: 'static {
+    /// The state attached, by this analysis, to values and operations.
+    type State: 'static;
+
+    /// Compute the states of the values and operations nested in (or being) `root`.
+    fn compute(
+        ctx: &Context,
+        root: Ptr<Operation>,
+        states: &mut AnalysisStates<Self::State>,
+    ) -> Result<()>;
+}
+
+/// States attached by an [Analysis] to values and operations.
+pub struct AnalysisStates<S> {
+    values: FxHashMap<Value, S>,
+    ops: FxHashMap<Ptr<Operation>, S>,
+}
+
+impl<S> Default for AnalysisStates<S> {
+    fn default() -> Self {
+        AnalysisStates {
+            values: FxHashMap::default(),
+            ops: FxHashMap::default(),
+        }
+    }
+}
+
+impl<S> AnalysisStates<S> {
+    /// Set the state of `value`, returning its previous state, if any.
+    pub fn set_value(&mut self, value: Value, state: S) -> Option<S> {
+        self.values.insert(value, state)
+    }
+
+    /// Get the state of `value`.
+    pub fn value(&self, value: Value) -> Option<&S> {
+        self.values.get(&value)
+    }
+
+    /// Set the state of `op`, returning its previous state, if any.
+    pub fn set_op(&mut self, op: Ptr<Operation>, state: S) -> Option<S> {
+        self.ops.insert(op, state)
+    }
+
+    /// Get the state of `op`.
+    pub fn op(&self, op: Ptr<Operation>) -> Option<&S> {
+        self.ops.get(&op)
+    }
+}
+
+/// Computes [Analysis]es on demand, and caches their [AnalysisStates].
+/// See [module](self) documentation.
+#[derive(Default)]
+pub struct AnalysisManager {
+    /// States of an analysis (identified by its type) on a root operation.
+    cache: FxHashMap<(TypeId, Ptr<Operation>), Box<dyn Any>>,
+    /// Analyses to keep on [invalidate_unpreserved](Self::invalidate_unpreserved).
+    preserved: FxHashSet<TypeId>,
+    /// Keep all analyses on [invalidate_unpreserved](Self::invalidate_unpreserved).
+    preserved_all: bool,
+}
+
+impl AnalysisManager {
+    /// Create an empty [AnalysisManager].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the states of analysis `A` on `root`, computing them if not already cached.
+    pub fn get<A: Analysis>(
+        &mut self,
+        ctx: &Context,
+        root: Ptr<Operation>,
+    ) -> Result<&AnalysisStates<A::State>> {
+        let states = match self.cache.entry((TypeId::of::<A>(), root)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut states = AnalysisStates::<A::State>::default();
+                A::compute(ctx, root, &mut states)?;
+                entry.insert(Box::new(states))
+            }
+        };
+        Ok(states
+            .downcast_ref::<AnalysisStates<A::State>>()
+            .expect("Cached states must be of the analysis' state type"))
+    }
+
+    /// Get the states of analysis `A` on `root`, only if already cached.
+    pub fn cached<A: Analysis>(&self, root: Ptr<Operation>) -> Option<&AnalysisStates<A::State>> {
+        self.cache.get(&(TypeId::of::<A>(), root)).map(|states| {
+            states
+                .downcast_ref::<AnalysisStates<A::State>>()
+                .expect("Cached states must be of the analysis' state type")
+        })
+    }
+
+    /// Drop the cached states of analysis `A`, on all roots.
+    pub fn invalidate<A: Analysis>(&mut self) {
+        self.cache
+            .retain(|(analysis, _), _| *analysis != TypeId::of::<A>());
+    }
+
+    /// Drop the cached states of all analyses.
+    pub fn invalidate_all(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Keep the cached states of analysis `A` on the next
+    /// [invalidate_unpreserved](Self::invalidate_unpreserved).
+    /// A pass calls this when it doesn't change what `A` computes.
+    pub fn preserve<A: Analysis>(&mut self) {
+        self.preserved.insert(TypeId::of::<A>());
+    }
+
+    /// Keep the cached states of all analyses on the next
+    /// [invalidate_unpreserved](Self::invalidate_unpreserved).
+    /// A pass calls this when it doesn't change the IR.
+    pub fn preserve_all(&mut self) {
+        self.preserved_all = true;
+    }
+
+    /// Drop the cached states of analyses not [preserved](Self::preserve)
+    /// since the last call to this, and forget what was preserved.
+    pub fn invalidate_unpreserved(&mut self) {
+        if !self.preserved_all {
+            let preserved = &self.preserved;
+            self.cache
+                .retain(|(analysis, _), _| preserved.contains(analysis));
+        }
+        self.preserved.clear();
+        self.preserved_all = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::{
+        builtin::{
+            self,
+            op_interfaces::OneResultInterface,
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::{Context, Ptr},
+        linked_list::ContainsLinkedList,
+        op::Op,
+        operation::Operation,
+        pass::{Pass, PassManager},
+        result::Result,
+        test_dialect::{
+            self,
+            ops::{BinaryOp, ConsumeOp, ProduceOp},
+        },
+        value::Value,
+    };
+
+    use super::{Analysis, AnalysisManager, AnalysisStates};
+
+    thread_local! {
+        static NUM_COMPUTED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Length of the longest chain of `test.binary` operations computing a value.
+    struct Depth;
+
+    impl Analysis for Depth {
+        type State = usize;
+
+        fn compute(
+            ctx: &Context,
+            root: Ptr<Operation>,
+            states: &mut AnalysisStates<usize>,
+        ) -> Result<()> {
+            NUM_COMPUTED.set(NUM_COMPUTED.get() + 1);
+            let func = Operation::op(root, ctx).downcast_ref::<FuncOp>().copied();
+            let entry = func.unwrap().get_entry_block(ctx);
+            for op in entry.deref(ctx).iter(ctx) {
+                let op_ref = op.deref(ctx);
+                let depth = |value: Value| states.value(value).copied().unwrap_or_default();
+                let depth = if Operation::op(op, ctx).downcast_ref::<BinaryOp>().is_some() {
+                    op_ref.operands().map(depth).max().unwrap_or_default() + 1
+                } else {
+                    0
+                };
+                for result in op_ref.results() {
+                    states.set_value(result, depth);
+                }
+                states.set_op(op, depth);
+            }
+            Ok(())
+        }
+    }
+
+    /// Asserts the depth of the last `test.binary` operation in its function.
+    struct CheckDepth {
+        expected: usize,
+        preserve: bool,
+    }
+
+    impl Pass for CheckDepth {
+        fn name(&self) -> &str {
+            "check-depth"
+        }
+
+        fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+            self.run_with_analyses(ctx, op, &mut AnalysisManager::new())
+        }
+
+        fn run_with_analyses(
+            &mut self,
+            ctx: &mut Context,
+            op: Ptr<Operation>,
+            analyses: &mut AnalysisManager,
+        ) -> Result<()> {
+            let states = analyses.get::<Depth>(ctx, op)?;
+            let func = Operation::op(op, ctx).downcast_ref::<FuncOp>().copied();
+            let entry = func.unwrap().get_entry_block(ctx);
+            let consume = entry.deref(ctx).tail().unwrap();
+            let last = consume.deref(ctx).operand(0);
+            assert_eq!(states.value(last), Some(&self.expected));
+            if self.preserve {
+                analyses.preserve::<Depth>();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_analysis_manager() -> Result<()> {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        let ctx = &mut ctx;
+
+        // f(a) { p = produce; b1 = binary(a, p); b2 = binary(b1, a); consume(b2) }
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless).into();
+        let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let entry = func.get_entry_block(ctx);
+        let a = entry.deref(ctx).argument(0);
+        let p = ProduceOp::new(ctx, i64_ty);
+        p.operation().insert_at_back(entry, ctx);
+        let b1 = BinaryOp::new(ctx, a, p.result(ctx));
+        b1.operation().insert_at_back(entry, ctx);
+        let b2 = BinaryOp::new(ctx, b1.result(ctx), a);
+        b2.operation().insert_at_back(entry, ctx);
+        let b2_res = b2.result(ctx);
+        ConsumeOp::new(ctx, vec![b2_res])
+            .operation()
+            .insert_at_back(entry, ctx);
+        let root = func.operation();
+
+        let mut analyses = AnalysisManager::new();
+        assert!(analyses.cached::<Depth>(root).is_none());
+        let states = analyses.get::<Depth>(ctx, root)?;
+        assert_eq!(states.value(a), None);
+        assert_eq!(states.value(p.result(ctx)), Some(&0));
+        assert_eq!(states.op(b1.operation()), Some(&1));
+        assert_eq!(states.value(b2_res), Some(&2));
+        analyses.get::<Depth>(ctx, root)?;
+        assert_eq!(NUM_COMPUTED.get(), 1);
+
+        analyses.preserve_all();
+        analyses.invalidate_unpreserved();
+        assert!(analyses.cached::<Depth>(root).is_some());
+        analyses.invalidate_unpreserved();
+        assert!(analyses.cached::<Depth>(root).is_none());
+        analyses.get::<Depth>(ctx, root)?;
+        analyses.invalidate::<Depth>();
+        assert!(analyses.cached::<Depth>(root).is_none());
+        assert_eq!(NUM_COMPUTED.get(), 2);
+
+        // The second pass reuses the states computed for the first,
+        // and the third recomputes them, since the second didn't preserve them.
+        let mut pm = PassManager::new();
+        for preserve in [true, false, true] {
+            pm.add_pass(CheckDepth {
+                expected: 2,
+                preserve,
+            });
+        }
+        pm.run(ctx, root)?;
+        assert_eq!(NUM_COMPUTED.get(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+//! Analyses of the IR, computed on demand, and not updated as the IR changes.
+
+pub mod cfg;
+pub mod dominance;
+pub mod liveness;
+pub mod manager;
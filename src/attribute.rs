@@ -31,34 +31,64 @@
 //!
 //! [AttrObj]s can be downcasted to their concrete types using
 //! [downcast_rs](https://docs.rs/downcast-rs/1.2.0/downcast_rs/index.html#example-without-generics).
+//!
+//! Attributes with a grammar that's awkward to express with parser combinators
+//! (regular expressions, embedded languages etc.) can be written verbatim as
+//! `#dialect<payload>`, where the payload is any text with balanced brackets.
+//! The payload is parsed, as a plain string, by the dialect's [VerbatimAttrParserFn]
+//! (see [Dialect::set_verbatim_attr_parser](crate::dialect::Dialect::set_verbatim_attr_parser)).
+//! Attributes implementing [VerbatimAttrInterface] are printed in this form.
+//!
+//! Attributes implementing [ElidableAttrInterface] with more elements than the
+//! printer's [elision limit](printable::State::set_attr_elision_limit) are printed
+//! elided, as `attr_id [...N elements...]`. When printed with their module by
+//! [print_source_with](parsable::print_source_with), the elided attributes are
+//! printed exactly too, as resources: [aliases](crate::irfmt::aliases) defined after
+//! the module, that the elision markers refer to, as `attr_id [...N elements at #name...]`.
+//! Only markers that refer to a resource can be parsed back.
 
 use std::{
     fmt::{Debug, Display},
-    hash::Hash,
+    hash::{Hash, Hasher},
     ops::Deref,
     sync::LazyLock,
 };
 
-use combine::{Parser, between, parser, token};
+use combine::{
+    Parser, attempt, between, look_ahead, optional, parser,
+    parser::char::{spaces, string},
+    token,
+};
 use downcast_rs::{Downcast, impl_downcast};
 use dyn_clone::DynClone;
 use linkme::distributed_slice;
 use rustc_hash::FxHashMap;
+use thiserror::Error;
 
 use crate::{
+    builtin::{
+        attr_interfaces::{ElidableAttrInterface, VerbatimAttrInterface},
+        attributes::AliasPlaceholderAttr,
+    },
     common_traits::Verify,
+    completion,
     context::Context,
-    dialect::DialectName,
+    dialect::{DialectName, Registrant},
     identifier::Identifier,
     impl_printable_for_display, input_err,
     irfmt::{
-        parsers::{attr_parser, delimited_list_parser, spaced},
-        printers::iter_with_sep,
+        aliases::attr_alias_use,
+        parsers::{
+            attr_parser, delimited_list_parser, int_parser, spaced, verbatim_payload_parser,
+        },
     },
-    location::Located,
-    parsable::{Parsable, ParseResult, ParserFn, StateStream},
-    printable::{self, Printable},
+    limits::{self, LimitErr},
+    location::{Located, Location},
+    parsable::{self, IntoParseResult, Parsable, ParseResult, ParserFn, StateStream},
+    printable::{self, Highlight, Printable},
     result::Result,
+    unregistered::OpaqueAttr,
+    utils::small_box::{Coerce, SmallBox},
 };
 
 #[derive(Clone)]
@@ -66,16 +96,6 @@ struct AttributeDictKeyVal {
     key: Identifier,
     val: AttrObj,
 }
-impl Printable for AttributeDictKeyVal {
-    fn fmt(
-        &self,
-        ctx: &Context,
-        _state: &printable::State,
-        f: &mut std::fmt::Formatter<'_>,
-    ) -> std::fmt::Result {
-        write!(f, "({}: {})", self.key, self.val.disp(ctx))
-    }
-}
 
 impl Parsable for AttributeDictKeyVal {
     type Arg = ();
@@ -101,21 +121,21 @@ impl Printable for AttributeDict {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
-        write!(
-            f,
-            "[{}]",
-            iter_with_sep(
-                self.0.iter().map(|(key, val)| AttributeDictKeyVal {
-                    key: key.clone(),
-                    val: val.clone()
-                }),
-                printable::ListSeparator::CharSpace(','),
-            )
-            .disp(ctx)
-        )
+        // Print entries directly (rather than via a list printer over
+        // key-value objects) to avoid cloning every key and attribute.
+        write!(f, "[")?;
+        for (i, (key, val)) in self.iter_sorted().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "({key}: ")?;
+            Printable::fmt(val, ctx, state, f)?;
+            write!(f, ")")?;
+        }
+        write!(f, "]")
     }
 }
 
@@ -142,10 +162,33 @@ impl Parsable for AttributeDict {
 }
 
 /// A dictionary of attributes, mapping keys to attribute objects.
+///
+/// The dictionary is semantically a map sorted by its keys: equality and
+/// hashing do not depend on the order in which entries were inserted,
+/// and the dictionary is printed in sorted order (see [Self::iter_sorted]).
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct AttributeDict(pub FxHashMap<Identifier, AttrObj>);
 
+impl Hash for AttributeDict {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // [Attribute]s aren't hashable, so we only hash their [AttrId]s.
+        // Equal attributes have the same [AttrId], so this is consistent with [PartialEq].
+        self.0.len().hash(state);
+        for (key, val) in self.iter_sorted() {
+            key.hash(state);
+            val.attr_id().hash(state);
+        }
+    }
+}
+
 impl AttributeDict {
+    /// Iterate over the entries of this dictionary, sorted by their keys.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&Identifier, &AttrObj)> + Clone {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries.into_iter()
+    }
+
     /// Get reference to attribute value that is mapped to key `k`.
     pub fn get<T: Attribute>(&self, k: &Identifier) -> Option<&T> {
         self.0.get(k).and_then(|ao| ao.downcast_ref::<T>())
@@ -163,7 +206,7 @@ impl AttributeDict {
 
     /// Set the attribute value for key `k`.
     pub fn set<T: Attribute>(&mut self, k: Identifier, v: T) {
-        self.0.insert(k, Box::new(v));
+        self.0.insert(k, v.into());
     }
 }
 
@@ -187,6 +230,8 @@ pub trait Attribute: Printable + Verify + Downcast + Sync + Send + DynClone + De
     fn verify_interfaces(&self, ctx: &Context) -> Result<()>;
 
     /// Register this attribute's [AttrId] in the dialect it belongs to.
+    /// Panics if a different attribute is already registered with the same [AttrId].
+    #[track_caller]
     fn register_attr_in_dialect<A: Attribute>(ctx: &mut Context, attr_parser: ParserFn<(), A>)
     where
         Self: Sized,
@@ -207,7 +252,7 @@ pub trait Attribute: Printable + Verify + Downcast + Sync + Send + DynClone + De
             combine::parser(move |parsable_state: &mut StateStream<'_>| {
                 attr_parser(&(), ())
                     .parse_stream(parsable_state)
-                    .map(|attr| -> AttrObj { Box::new(attr) })
+                    .map(|attr| -> AttrObj { attr.into() })
                     .into_result()
             })
             .boxed()
@@ -217,14 +262,26 @@ pub trait Attribute: Printable + Verify + Downcast + Sync + Send + DynClone + De
             .dialects
             .get_mut(&attrid.dialect)
             .unwrap_or_else(|| panic!("Unregistered dialect {}", &attrid.dialect));
-        dialect.add_attr(Self::attr_id_static(), Box::new(attr_parser));
+        let registrant = Registrant::rust_type::<Self>();
+        if let Err(err) = dialect.add_attr(attrid, Box::new(attr_parser), registrant) {
+            panic!("{}", err);
+        }
     }
 }
 impl_downcast!(Attribute);
 dyn_clone::clone_trait_object!(Attribute);
 
 /// [Attribute] objects are boxed and stored in the IR.
-pub type AttrObj = Box<dyn Attribute>;
+/// Small attributes are stored in place, see [SmallBox].
+pub type AttrObj = SmallBox<dyn Attribute>;
+
+unsafe impl<A: Attribute> Coerce<A> for dyn Attribute {
+    fn coerce(ptr: *mut A) -> *mut Self {
+        ptr
+    }
+
+    const CLONE: Option<fn(&A) -> A> = Some(dyn_clone::clone);
+}
 
 /// A storable closure for parsing any [AttrId] followed by the full [Attribute].
 pub(crate) type AttrParserFn = Box<
@@ -234,6 +291,10 @@ pub(crate) type AttrParserFn = Box<
         -> Box<dyn Parser<StateStream<'a>, Output = AttrObj, PartialState = ()> + 'a>,
 >;
 
+/// Parse the payload of a verbatim attribute, `#dialect<payload>`, into an [Attribute].
+/// Errors without a location are reported at the beginning of the payload.
+pub type VerbatimAttrParserFn = fn(&mut Context, &str) -> Result<AttrObj>;
+
 impl PartialEq for AttrObj {
     fn eq(&self, other: &Self) -> bool {
         (**self).eq_attr(&**other)
@@ -242,7 +303,7 @@ impl PartialEq for AttrObj {
 
 impl<T: Attribute> From<T> for AttrObj {
     fn from(value: T) -> Self {
-        Box::new(value)
+        SmallBox::new(value)
     }
 }
 
@@ -255,8 +316,30 @@ impl Printable for AttrObj {
         state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(f, "{} ", self.attr_id())?;
-        Printable::fmt(self.deref(), ctx, state, f)
+        state.highlighted(Highlight::Attribute, f, |f| {
+            if let Some(verbatim) = attr_cast::<dyn VerbatimAttrInterface>(&**self) {
+                let payload = verbatim.verbatim_payload(ctx);
+                return write!(f, "#{}<{}>", self.attr_id().dialect, payload);
+            }
+            if let Some(elidable) = attr_cast::<dyn ElidableAttrInterface>(&**self)
+                && state
+                    .attr_elision_limit()
+                    .is_some_and(|limit| elidable.num_elements() > limit)
+            {
+                write!(
+                    f,
+                    "{} [...{} elements",
+                    self.attr_id(),
+                    elidable.num_elements()
+                )?;
+                if let Some(idx) = state.resource_index(self) {
+                    write!(f, " at #{}", resource_alias(idx))?;
+                }
+                return write!(f, "...]");
+            }
+            write!(f, "{} ", self.attr_id())?;
+            Printable::fmt(self.deref(), ctx, state, f)
+        })
     }
 }
 
@@ -271,32 +354,123 @@ impl Parsable for AttrObj {
         let loc = state_stream.loc();
         let attr_id_parser = spaced(AttrId::parser(()));
 
-        let mut attr_parser = attr_id_parser.then(move |attr_id: AttrId| {
+        let attr_id_parser = attr_id_parser.then(move |attr_id: AttrId| {
             let loc = loc.clone();
-            combine::parser(move |parsable_state: &mut StateStream<'a>| {
-                let state = &parsable_state.state;
-                let dialect = state
-                    .ctx
-                    .dialects
-                    .get(&attr_id.dialect)
-                    .expect("Dialect name parsed but dialect isn't registered");
-                let Some(attr_parser) = dialect.attributes.get(&attr_id) else {
-                    input_err!(
-                        loc.clone(),
-                        "Unregistered attribute {}",
-                        attr_id.disp(state.ctx)
-                    )?
-                };
-                attr_parser(&()).parse_stream(parsable_state).into_result()
-            })
+            combine::parser(elided_attr_parse).or(combine::parser(
+                move |parsable_state: &mut StateStream<'a>| {
+                    if attr_id == AliasPlaceholderAttr::attr_id_static() {
+                        return attr_alias_use(parsable_state);
+                    }
+                    let state = &parsable_state.state;
+                    let Some(dialect) = state.ctx.dialects.get(&attr_id.dialect) else {
+                        // Dialect names parse only if registered, or if that's allowed.
+                        return OpaqueAttr::parse(parsable_state, attr_id);
+                    };
+                    let Some(attr_parser) = dialect.attributes.get(&attr_id) else {
+                        input_err!(
+                            loc.clone(),
+                            "Unregistered attribute {}",
+                            attr_id.disp(state.ctx)
+                        )?
+                    };
+                    attr_parser(&()).parse_stream(parsable_state).into_result()
+                },
+            ))
         });
 
-        attr_parser.parse_stream(state_stream).into_result()
+        let mut parser = spaces()
+            .with(
+                combine::parser(verbatim_attr_parse)
+                    .or(combine::parser(attr_alias_use))
+                    .or(attr_id_parser),
+            )
+            .skip(spaces());
+        let attr_loc = state_stream.loc();
+        let (attr, consumed) = parsable::parse_nested(state_stream, |state_stream| {
+            parser.parse_stream(state_stream).into_result()
+        })?;
+        if limits::attr_exceeds_size(state_stream.state.ctx, &attr) {
+            let limit = state_stream.state.ctx.limits.max_attribute_size.unwrap();
+            input_err!(attr_loc, LimitErr::AttributeSize(limit))?
+        }
+        Ok((attr, consumed))
     }
 }
 
+#[derive(Error, Debug)]
+#[error("Attribute with {0} elements was elided when printed, and can't be parsed back")]
+pub struct ElidedAttrErr(pub usize);
+
+/// The name of the alias defining the resource with index `idx`.
+pub(crate) fn resource_alias(idx: usize) -> String {
+    format!("resource{idx}")
+}
+
+/// Parse an elided attribute, `[...N elements at #name...]`, that follows its [AttrId],
+/// into the resource (the attribute alias) `name`. Elided attributes without a
+/// resource, `[...N elements...]`, can't be parsed.
+fn elided_attr_parse<'a>(state_stream: &mut StateStream<'a>) -> ParseResult<'a, AttrObj> {
+    let loc = state_stream.loc();
+    let ((num_elements, resource), _) = attempt(string("[..."))
+        .with(int_parser::<usize>())
+        .skip(string(" elements"))
+        .and(optional(
+            string(" at ").with(combine::parser(attr_alias_use)),
+        ))
+        .skip(string("...]"))
+        .parse_stream(state_stream)
+        .into_result()?;
+    let Some(resource) = resource else {
+        input_err!(loc, ElidedAttrErr(num_elements))?
+    };
+    Ok(resource).into_parse_result()
+}
+
+#[derive(Error, Debug)]
+#[error("Dialect {0} doesn't support verbatim attributes")]
+pub struct VerbatimAttrUnsupportedErr(pub String);
+
+/// Parse a verbatim attribute, `#dialect<payload>`,
+/// using the dialect's [VerbatimAttrParserFn].
+fn verbatim_attr_parse<'a>(state_stream: &mut StateStream<'a>) -> ParseResult<'a, AttrObj> {
+    let loc = state_stream.loc();
+    // Distinguish from an alias use, `#name`.
+    let prefix = attempt(token('#').skip(look_ahead(Identifier::parser(()).with(token('<')))));
+    let dialect_name = prefix
+        .with(DialectName::parser(()))
+        .skip(token('<'))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+    let payload_loc = state_stream.loc();
+    let payload = verbatim_payload_parser()
+        .skip(token('>'))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+
+    let ctx = &mut *state_stream.state.ctx;
+    let verbatim_parser = ctx
+        .dialects
+        .get(&dialect_name)
+        .and_then(|dialect| dialect.verbatim_attr_parser);
+    let Some(verbatim_parser) = verbatim_parser else {
+        input_err!(loc, VerbatimAttrUnsupportedErr(dialect_name.to_string()))?
+    };
+    let attr = verbatim_parser(ctx, &payload).map_err(|mut err| {
+        if matches!(err.loc(), Location::Unknown) {
+            err.set_loc(payload_loc);
+        }
+        err
+    })?;
+    Ok(attr).into_parse_result()
+}
+
 impl Verify for AttrObj {
+    /// Verify the interfaces implemented by the [Attribute] (see
+    /// [verify_interfaces](Attribute::verify_interfaces)), and then the [Attribute] itself.
     fn verify(&self, ctx: &Context) -> Result<()> {
+        self.as_ref().verify_interfaces(ctx)?;
         self.as_ref().verify(ctx)
     }
 }
@@ -311,14 +485,14 @@ pub fn attr_impls<T: ?Sized + Attribute>(attr: &dyn Attribute) -> bool {
     attr_cast::<T>(attr).is_some()
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 /// An [Attribute]'s name (not including it's dialect).
-pub struct AttrName(String);
+pub struct AttrName(Identifier);
 
 impl AttrName {
     /// Create a new AttrName.
     pub fn new(name: &str) -> AttrName {
-        AttrName(name.to_string())
+        AttrName(name.try_into().expect("Invalid Identifier for AttrName"))
     }
 }
 
@@ -342,21 +516,21 @@ impl Parsable for AttrName {
         Self: Sized,
     {
         Identifier::parser(())
-            .map(|name| AttrName::new(&name))
+            .map(AttrName)
             .parse_stream(state_stream)
             .into()
     }
 }
 
 impl Deref for AttrName {
-    type Target = String;
+    type Target = Identifier;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 /// A combination of a Attr's name and its dialect.
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct AttrId {
     pub dialect: DialectName,
     pub name: AttrName,
@@ -382,6 +556,7 @@ impl Parsable for AttrId {
     where
         Self: Sized,
     {
+        completion::expect_here(state_stream, completion::Expected::Attribute);
         let mut parser = DialectName::parser(())
             .skip(parser::char::char('.'))
             .and(AttrName::parser(()))
@@ -400,9 +575,13 @@ pub static ATTR_INTERFACE_VERIFIERS: [LazyLock<(
     (std::any::TypeId, AttrInterfaceVerifier),
 )>];
 
-/// All interfaces mapped to their super-interfaces
+/// All interfaces (along with their names) mapped to their super-interfaces
 #[distributed_slice]
-pub static ATTR_INTERFACE_DEPS: [LazyLock<(std::any::TypeId, Vec<std::any::TypeId>)>];
+pub static ATTR_INTERFACE_DEPS: [LazyLock<(
+    std::any::TypeId,
+    &'static str,
+    Vec<std::any::TypeId>,
+)>];
 
 /// A map from every [Attribute] to its ordered (as per interface deps) list of interface verifiers.
 /// An interface's super-interfaces are to be verified before it itself is.
@@ -413,7 +592,7 @@ pub static ATTR_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect ATTR_INTERFACE_VERIFIERS into an [AttrId] indexed map.
     let mut attr_intr_verifiers = FxHashMap::default();
     for lazy in ATTR_INTERFACE_VERIFIERS {
-        let (attr_id, (type_id, verifier)) = (**lazy).clone();
+        let (attr_id, (type_id, verifier)) = **lazy;
         attr_intr_verifiers
             .entry(attr_id)
             .and_modify(|verifiers: &mut Vec<(TypeId, AttrInterfaceVerifier)>| {
@@ -425,7 +604,7 @@ pub static ATTR_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect interface deps into a map.
     let interface_deps: FxHashMap<_, _> = ATTR_INTERFACE_DEPS
         .iter()
-        .map(|lazy| (**lazy).clone())
+        .map(|lazy| (lazy.0, lazy.2.clone()))
         .collect();
 
     // Assign an integer to each interface, such that if y depends on x
@@ -458,7 +637,7 @@ pub static ATTR_INTERFACE_VERIFIERS_MAP: LazyLock<
 
     // Assign dep_sort_idx to every interface.
     for lazy in ATTR_INTERFACE_DEPS.iter() {
-        let (intr, _deps) = &**lazy;
+        let (intr, _name, _deps) = &**lazy;
         assign_idx_to_intr(&interface_deps, &mut dep_sort_idx, &mut sort_idx, intr);
     }
 
@@ -489,7 +668,7 @@ mod tests {
         // Collect interface deps into a map.
         let interface_deps: FxHashMap<_, _> = ATTR_INTERFACE_DEPS
             .iter()
-            .map(|lazy| (**lazy).clone())
+            .map(|lazy| (lazy.0, lazy.2.clone()))
             .collect();
 
         for (attr, intrs) in ATTR_INTERFACE_VERIFIERS_MAP.iter() {
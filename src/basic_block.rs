@@ -1,6 +1,7 @@
 //! A [BasicBlock] is a list of [Operation]s.
 
 use combine::{
+    optional,
     parser::{Parser, char::spaces},
     sep_by, token,
 };
@@ -10,9 +11,12 @@ use crate::{
     common_traits::{Named, Verify},
     context::{ArenaCell, Context, Ptr, private::ArenaObj},
     debug_info::{block_arg_name, set_block_arg_name},
+    graph::walkers::{self, Visitor, WalkConfig, interruptible::WalkResult},
     identifier::Identifier,
     indented_block,
+    ir_mapping::IRMapping,
     irfmt::{
+        aliases::alias_def_parser,
         parsers::{delimited_list_parser, location, spaced, type_parser},
         printers::{iter_with_sep, list_with_sep},
     },
@@ -20,7 +24,7 @@ use crate::{
     location::{Located, Location},
     operation::Operation,
     parsable::{self, IntoParseResult, Parsable, ParseResult},
-    printable::{self, ListSeparator, Printable, indented_nl},
+    printable::{self, Highlight, ListSeparator, Printable, indented_nl},
     region::Region,
     result::Result,
     r#type::{TypeObj, Typed},
@@ -38,6 +42,8 @@ pub(crate) struct BlockArgument {
     pub(crate) arg_idx: usize,
     /// The [Type](crate::type::Type) of this argument.
     pub(crate) ty: Ptr<TypeObj>,
+    /// Attributes of this argument (such as those of a function parameter).
+    pub(crate) attributes: AttributeDict,
 }
 
 impl Typed for BlockArgument {
@@ -70,10 +76,17 @@ impl Printable for BlockArgument {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(f, "{}:{}", self.unique_name(ctx), self.ty.disp(ctx))
+        state.highlighted(Highlight::Value, f, |f| {
+            write!(f, "{}", Value::from(self).printed_name(ctx, state))
+        })?;
+        write!(f, ":{}", self.ty.print(ctx, state))?;
+        if !self.attributes.0.is_empty() {
+            write!(f, " {}", self.attributes.print(ctx, state))?;
+        }
+        Ok(())
     }
 }
 
@@ -108,11 +121,21 @@ pub struct BasicBlock {
     /// A dictionary of attributes.
     pub attributes: AttributeDict,
     loc: Location,
+    /// The [modification epoch](Context::modification_epoch) this was last modified in.
+    modified_epoch: u64,
+}
+
+/// Is `label` of the form `bb<N>`, reserved for the numbering of
+/// blocks when printing? See [BasicBlock::printed_label].
+fn is_numbered_label(label: &Identifier) -> bool {
+    label
+        .strip_prefix("bb")
+        .is_some_and(|num| !num.is_empty() && num.bytes().all(|c| c.is_ascii_digit()))
 }
 
 impl Named for BasicBlock {
     fn given_name(&self, _ctx: &Context) -> Option<Identifier> {
-        self.label.clone()
+        self.label
     }
     fn id(&self, _ctx: &Context) -> Identifier {
         self.self_ptr.make_name("block")
@@ -126,6 +149,7 @@ impl BasicBlock {
         label: Option<Identifier>,
         arg_types: Vec<Ptr<TypeObj>>,
     ) -> Ptr<BasicBlock> {
+        let modified_epoch = ctx.next_modification_epoch();
         let f = |self_ptr: Ptr<BasicBlock>| BasicBlock {
             self_ptr,
             label,
@@ -135,6 +159,7 @@ impl BasicBlock {
             region_links: RegionLinks::default(),
             attributes: AttributeDict::default(),
             loc: Location::Unknown,
+            modified_epoch,
         };
         let newblock = Self::alloc(ctx, f);
         // Let's update the args of the new block. Easier to do it here than during creation.
@@ -146,6 +171,7 @@ impl BasicBlock {
                 def_block: newblock,
                 arg_idx,
                 ty,
+                attributes: AttributeDict::default(),
             })
             .collect();
         newblock.deref_mut(ctx).args = args;
@@ -153,6 +179,42 @@ impl BasicBlock {
         newblock
     }
 
+    /// The label given to this block, if any.
+    pub fn label(&self) -> Option<Identifier> {
+        self.label
+    }
+
+    /// Set (or clear, with [None]) the label of this block.
+    pub fn set_label(&mut self, label: Option<Identifier>) {
+        self.label = label;
+    }
+
+    /// The label that this block is printed with. This is its given [label](Self::label)
+    /// if no other block in its region has the same label, and otherwise, `bb<N>`,
+    /// `N` being the position of this block in its region. Labels of that form are
+    /// reserved for this numbering, so labels of the form `bb<N>` are ignored.
+    /// Printed labels thus stay the same as long as the blocks in a region
+    /// keep their labels, rather than depending on how the IR was built.
+    pub fn printed_label(&self, ctx: &Context) -> Identifier {
+        let label = self.label.filter(|label| !is_numbered_label(label));
+        let Some(region) = self.container() else {
+            return label.unwrap_or_else(|| self.id(ctx));
+        };
+        let mut position = 0;
+        let mut is_unique = true;
+        for (idx, block) in region.deref(ctx).iter(ctx).enumerate() {
+            if block == self.self_ptr {
+                position = idx;
+            } else if label.is_some() && block.deref(ctx).label == label {
+                is_unique = false;
+            }
+        }
+        match label {
+            Some(label) if is_unique => label,
+            _ => format!("bb{position}").try_into().unwrap(),
+        }
+    }
+
     /// Get idx'th argument as a Value.
     pub fn argument(&self, arg_idx: usize) -> Value {
         self.args
@@ -173,9 +235,20 @@ impl BasicBlock {
             def_block: self.self_ptr,
             arg_idx,
             ty,
+            attributes: AttributeDict::default(),
         })
     }
 
+    /// The attributes of the idx'th argument.
+    pub fn argument_attributes(&self, arg_idx: usize) -> &AttributeDict {
+        &self.argument_ref(arg_idx).attributes
+    }
+
+    /// The attributes of the idx'th argument, to modify them.
+    pub fn argument_attributes_mut(&mut self, arg_idx: usize) -> &mut AttributeDict {
+        &mut self.argument_mut(arg_idx).attributes
+    }
+
     /// Get a reference to the idx'th argument.
     pub(crate) fn argument_ref(&self, arg_idx: usize) -> &BlockArgument {
         self.args
@@ -195,6 +268,11 @@ impl BasicBlock {
         self.args.len()
     }
 
+    /// The [modification epoch](Context::modification_epoch) this block was last modified in.
+    pub fn modified_epoch(&self) -> u64 {
+        self.modified_epoch
+    }
+
     /// Get all successors of this block.
     pub fn succs(&self, ctx: &Context) -> Vec<Ptr<BasicBlock>> {
         self.tail()
@@ -204,14 +282,88 @@ impl BasicBlock {
             .collect()
     }
 
+    /// The [Operation]s in this block, in order. Unlike [iter](ContainsLinkedList::iter),
+    /// the snapshot stays unchanged as the block is changed, so it can be used to
+    /// erase or move ops while going through them.
+    /// See [iterator invalidation](crate::linked_list#iterator-invalidation).
+    pub fn ops_snapshot(&self, ctx: &Context) -> Vec<Ptr<Operation>> {
+        self.iter(ctx).collect()
+    }
+
     /// Drop all uses that this block holds.
     pub fn drop_all_uses(ptr: Ptr<Self>, ctx: &Context) {
-        let ops: Vec<_> = ptr.deref(ctx).iter(ctx).collect();
+        let ops = ptr.deref(ctx).ops_snapshot(ctx);
         for op in ops {
             Operation::drop_all_uses(op, ctx);
         }
     }
 
+    /// Create an empty copy of this block, with its label, arguments (and their
+    /// attributes), attributes and location, at the back of `dest`. The block, and its arguments, are
+    /// mapped to their copies in `mapping`.
+    pub(crate) fn clone_empty(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        dest: Ptr<Region>,
+        mapping: &mut IRMapping,
+    ) -> Ptr<BasicBlock> {
+        let (label, arg_types, arg_attrs, attributes, loc) = {
+            let block_ref = ptr.deref(ctx);
+            (
+                block_ref.label,
+                block_ref.arguments().map(|arg| arg.get_type(ctx)).collect(),
+                block_ref
+                    .args
+                    .iter()
+                    .map(|arg| arg.attributes.clone())
+                    .collect::<Vec<_>>(),
+                block_ref.attributes.clone(),
+                block_ref.loc(),
+            )
+        };
+        let new_block = BasicBlock::new(ctx, label, arg_types);
+        {
+            let mut new_block_ref = new_block.deref_mut(ctx);
+            new_block_ref.attributes = attributes;
+            new_block_ref.set_loc(loc);
+            for (arg, arg_attrs) in new_block_ref.args.iter_mut().zip(arg_attrs) {
+                arg.attributes = arg_attrs;
+            }
+        }
+        new_block.insert_at_back(dest, ctx);
+        let args: Vec<_> = ptr.deref(ctx).arguments().collect();
+        let new_args: Vec<_> = new_block.deref(ctx).arguments().collect();
+        mapping.map_values(args, new_args);
+        mapping.map_block(ptr, new_block);
+        new_block
+    }
+
+    /// Copy the [Operation]s of this block (see [Operation::clone]) to the back of `dest`.
+    pub(crate) fn clone_ops(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        dest: Ptr<BasicBlock>,
+        mapping: &mut IRMapping,
+    ) {
+        let ops = ptr.deref(ctx).ops_snapshot(ctx);
+        for op in ops {
+            Operation::clone(op, ctx, mapping).insert_at_back(dest, ctx);
+        }
+    }
+
+    /// Copy this block, along with its [Operation]s (see [Operation::clone]),
+    /// to the back of `dest`. See [ir_mapping](crate::ir_mapping).
+    pub fn clone_into(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        dest: Ptr<Region>,
+        mapping: &mut IRMapping,
+    ) -> Ptr<BasicBlock> {
+        let new_block = Self::clone_empty(ptr, ctx, dest, mapping);
+        Self::clone_ops(ptr, ctx, new_block, mapping);
+        new_block
+    }
+
     /// Unlink and deallocate this block and everything that it contains.
     /// There must not be any uses outside the block.
     pub fn erase(ptr: Ptr<Self>, ctx: &mut Context) {
@@ -229,6 +381,17 @@ impl BasicBlock {
         }
         ArenaObj::dealloc(ptr, ctx);
     }
+
+    /// Walk this block and everything nested in it, calling back to `visitor`
+    /// in the order specified by `config`. See [Visitor].
+    pub fn walk<V: Visitor>(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        config: &WalkConfig,
+        visitor: &mut V,
+    ) -> WalkResult<V::Break> {
+        walkers::interruptible::walk_block(ctx, visitor, config, ptr, walkers::visit)
+    }
 }
 
 impl Located for BasicBlock {
@@ -293,6 +456,9 @@ impl LinkedList for BasicBlock {
 }
 
 impl ArenaObj for BasicBlock {
+    const KIND: &'static str = "basic block";
+    const SHORT_KIND: &'static str = "block";
+
     fn arena(ctx: &Context) -> &ArenaCell<Self> {
         &ctx.basic_blocks
     }
@@ -300,7 +466,7 @@ impl ArenaObj for BasicBlock {
         &mut ctx.basic_blocks
     }
     fn dealloc_sub_objects(ptr: Ptr<Self>, ctx: &mut Context) {
-        let ops: Vec<_> = ptr.deref_mut(ctx).iter(ctx).collect();
+        let ops = ptr.deref(ctx).ops_snapshot(ctx);
         for op in ops {
             ArenaObj::dealloc(op, ctx);
         }
@@ -308,6 +474,22 @@ impl ArenaObj for BasicBlock {
     fn self_ptr(&self, _ctx: &Context) -> Ptr<Self> {
         self.self_ptr
     }
+    fn set_modified_epoch(&mut self, epoch: u64) {
+        self.modified_epoch = epoch;
+    }
+    fn notify_modified(ptr: Ptr<Self>, ctx: &Context) {
+        ctx.notify_listeners(|listener| listener.notify_block_modified(ctx, ptr));
+    }
+    fn fmt_debug_details(
+        ptr: Ptr<Self>,
+        ctx: &Context,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match ptr.try_deref(ctx) {
+            Some(block) => write!(f, " (^{})", block.unique_name(ctx)),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Verify for BasicBlock {
@@ -323,23 +505,28 @@ impl Printable for BasicBlock {
         state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(
-            f,
-            "^{}({}):",
-            self.unique_name(ctx),
-            list_with_sep(&self.args, ListSeparator::Char(',')).print(ctx, state),
-        )?;
-
-        indented_block!(state, {
+        state.naming_values(|| {
+            state.highlighted(Highlight::BlockLabel, f, |f| {
+                write!(f, "^{}", self.printed_label(ctx))
+            })?;
             write!(
                 f,
-                "{}{}",
-                indented_nl(state),
-                iter_with_sep(self.iter(ctx), ListSeparator::CharNewline(';')).print(ctx, state),
+                "({}):",
+                list_with_sep(&self.args, ListSeparator::Char(',')).print(ctx, state),
             )?;
-        });
 
-        Ok(())
+            indented_block!(state, {
+                write!(
+                    f,
+                    "{}{}",
+                    indented_nl(state),
+                    iter_with_sep(self.iter(ctx), ListSeparator::CharNewline(';'))
+                        .print(ctx, state),
+                )?;
+            });
+
+            Ok(())
+        })
     }
 }
 
@@ -348,7 +535,7 @@ impl Parsable for BasicBlock {
     type Parsed = Ptr<BasicBlock>;
 
     ///  A basic block is
-    ///  label(arg_1:type_1, ..., arg_n:type_n):
+    ///  label(arg_1:type_1 \[attrs_1\], ..., arg_n:type_n):
     ///    op_1;
     ///    ... ;
     ///    op_n
@@ -358,13 +545,19 @@ impl Parsable for BasicBlock {
     ) -> ParseResult<'a, Self::Parsed> {
         let loc = state_stream.loc();
 
+        // An argument's attributes follow its type, if it has any.
         let arg = (
             (location(), Identifier::parser(())).skip(spaced(token(':'))),
             type_parser().skip(spaces()),
+            optional(AttributeDict::parser(()).skip(spaces())),
         );
         let args = spaced(delimited_list_parser('(', ')', ',', arg)).skip(token(':'));
+        // Alias definitions may appear among the operations.
+        let statement = alias_def_parser()
+            .map(|_| None)
+            .or(Operation::parser(()).map(Some));
         let ops = spaces().with(sep_by::<Vec<_>, _, _, _>(
-            Operation::parser(()).skip(spaces()),
+            statement.skip(spaces()),
             token(';').skip(spaces()),
         ));
 
@@ -375,18 +568,29 @@ impl Parsable for BasicBlock {
             .0;
 
         // We've parsed the components. Now construct the result.
-        let (arg_names, arg_types): (Vec<_>, Vec<_>) = args.into_iter().unzip();
-        let block = BasicBlock::new(state_stream.state.ctx, Some(label.clone()), arg_types);
+        let (arg_names, (arg_types, arg_attrs)): (Vec<_>, (Vec<_>, Vec<_>)) = args
+            .into_iter()
+            .map(|(name, ty, attrs)| (name, (ty, attrs)))
+            .unzip();
+        // Numbered labels were assigned by the printer, the block isn't really named.
+        let given_label = Some(label).filter(|label| !is_numbered_label(label));
+        let block = BasicBlock::new(state_stream.state.ctx, given_label, arg_types);
+        {
+            let mut block_ref = block.deref_mut(state_stream.state.ctx);
+            block_ref.set_loc(loc.clone());
+            for (arg, attrs) in block_ref.args.iter_mut().zip(arg_attrs) {
+                arg.attributes = attrs.unwrap_or_default();
+            }
+        }
         for (arg_idx, (loc, name)) in arg_names.into_iter().enumerate() {
             let def: Value = (&block.deref(state_stream.state.ctx).args[arg_idx]).into();
-            state_stream.state.name_tracker.ssa_def(
-                state_stream.state.ctx,
-                &(name.clone(), loc),
-                def,
-            )?;
+            state_stream
+                .state
+                .name_tracker
+                .ssa_def(state_stream.state.ctx, &(name, loc), def)?;
             set_block_arg_name(state_stream.state.ctx, block, arg_idx, name);
         }
-        for op in ops {
+        for op in ops.into_iter().flatten() {
             op.insert_at_back(block, state_stream.state.ctx);
         }
         state_stream
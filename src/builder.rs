@@ -0,0 +1,306 @@
+//! Building IR at an insertion point.
+//!
+//! An [OpBuilder] inserts the operations it [creates](OpBuilder::create) (or is given)
+//! at its [InsertionPoint], which advances past each operation inserted, so that a
+//! sequence of operations ends up in the order it was built in:
+//! ```
+//! use pliron::{
+//!     basic_block::BasicBlock,
+//!     builder::{InsertionPoint, OpBuilder},
+//!     builtin::{self, ops::ModuleOp},
+//!     context::Context,
+//!     linked_list::ContainsLinkedList,
+//!     op::Op,
+//! };
+//! let ctx = &mut Context::new();
+//! builtin::register(ctx);
+//! let block = BasicBlock::new(ctx, None, vec![]);
+//! let mut builder = OpBuilder::new(InsertionPoint::AtBlockStart(block));
+//! let m1 = builder.create(ctx, |ctx| ModuleOp::new(ctx, &"m1".try_into().unwrap()));
+//! let m2 = builder.create(ctx, |ctx| ModuleOp::new(ctx, &"m2".try_into().unwrap()));
+//! let ops: Vec<_> = block.deref(ctx).iter(ctx).collect();
+//! assert!(ops == vec![m1.operation(), m2.operation()]);
+//! ```
+//!
+//! A [BuilderListener] given to the builder is notified of the operations
+//! inserted and the blocks created. [PatternRewriter]s and [ConversionRewriter]s
+//! are such listeners, so patterns can build their replacements with an [OpBuilder].
+//!
+//! Structured control flow (`if`, `while` and `for` ops, with regions for their bodies)
+//! is built in one call, with [build_if](OpBuilder::build_if),
+//! [build_while](OpBuilder::build_while) and [build_for](OpBuilder::build_for),
+//! which create the blocks of the regions (with the right arguments), build
+//! their bodies with callbacks, and terminate them. The ops themselves are
+//! created by the [StructuredOps] of the dialect being built.
+
+use crate::{
+    basic_block::BasicBlock,
+    context::{Context, Ptr},
+    conversion::ConversionRewriter,
+    identifier::Identifier,
+    linked_list::{ContainsLinkedList, LinkedList},
+    op::Op,
+    operation::Operation,
+    pattern_match::PatternRewriter,
+    region::Region,
+    r#type::{TypeObj, Typed},
+    value::Value,
+};
+
+/// Where an [OpBuilder] inserts operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertionPoint {
+    /// Before the first operation of the block.
+    AtBlockStart(Ptr<BasicBlock>),
+    /// After the last operation of the block.
+    AtBlockEnd(Ptr<BasicBlock>),
+    /// Before the (linked) operation.
+    BeforeOp(Ptr<Operation>),
+    /// After the (linked) operation.
+    AfterOp(Ptr<Operation>),
+}
+
+impl InsertionPoint {
+    /// The block that operations are inserted into.
+    pub fn block(&self, ctx: &Context) -> Ptr<BasicBlock> {
+        match self {
+            InsertionPoint::AtBlockStart(block) | InsertionPoint::AtBlockEnd(block) => *block,
+            InsertionPoint::BeforeOp(op) | InsertionPoint::AfterOp(op) => op
+                .deref(ctx)
+                .container()
+                .expect("Insertion point operation isn't in a block"),
+        }
+    }
+}
+
+/// Notified of the IR built by an [OpBuilder].
+pub trait BuilderListener {
+    /// `op` (and the operations nested in it) were inserted into the IR.
+    fn notify_op_inserted(&mut self, _ctx: &Context, _op: Ptr<Operation>) {}
+
+    /// `block` was created (and inserted into its region).
+    fn notify_block_created(&mut self, _ctx: &Context, _block: Ptr<BasicBlock>) {}
+}
+
+impl BuilderListener for PatternRewriter {
+    fn notify_op_inserted(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        PatternRewriter::notify_op_inserted(self, ctx, op);
+    }
+}
+
+impl BuilderListener for ConversionRewriter<'_> {
+    fn notify_op_inserted(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        ConversionRewriter::notify_op_inserted(self, ctx, op);
+    }
+}
+
+/// Creates the structured control flow ops of a dialect, for [OpBuilder::build_if],
+/// [OpBuilder::build_while] and [OpBuilder::build_for]. The ops are created unlinked,
+/// with empty regions, which the builder fills in.
+pub trait StructuredOps {
+    /// Create an `if` on `cond`, with results of `result_types`,
+    /// and two regions: one executed if `cond` holds, and the other otherwise.
+    fn create_if(ctx: &mut Context, cond: Value, result_types: Vec<Ptr<TypeObj>>)
+    -> Ptr<Operation>;
+
+    /// Create a `while` loop, with loop carried values initialized to `inits`
+    /// (and their final values as results), and two regions: one computing the
+    /// condition, and the body. Both have the loop carried values as arguments.
+    fn create_while(ctx: &mut Context, inits: Vec<Value>) -> Ptr<Operation>;
+
+    /// Create a `for` loop (see [ForLoopInterface](crate::builtin::op_interfaces::ForLoopInterface)),
+    /// from `lb` to `ub` (exclusive) by `step`, with loop carried values initialized to `inits`,
+    /// and one region: the body, with the induction variable and the loop carried values as arguments.
+    fn create_for(
+        ctx: &mut Context,
+        lb: Value,
+        ub: Value,
+        step: Value,
+        inits: Vec<Value>,
+    ) -> Ptr<Operation>;
+
+    /// Create the terminator yielding `values` from the regions of `if`s, and
+    /// from the bodies of loops (as the values of the next iteration).
+    fn create_yield(ctx: &mut Context, values: Vec<Value>) -> Ptr<Operation>;
+
+    /// Create the terminator of the condition region of a `while`, which continues
+    /// to the body with `values` if `cond` holds, and exits the loop with them otherwise.
+    fn create_condition(ctx: &mut Context, cond: Value, values: Vec<Value>) -> Ptr<Operation>;
+}
+
+/// Inserts operations at an [InsertionPoint]. See [module](self) documentation.
+pub struct OpBuilder<'a> {
+    insertion_point: InsertionPoint,
+    listener: Option<&'a mut dyn BuilderListener>,
+}
+
+impl<'a> OpBuilder<'a> {
+    /// A builder inserting at `insertion_point`.
+    pub fn new(insertion_point: InsertionPoint) -> Self {
+        OpBuilder {
+            insertion_point,
+            listener: None,
+        }
+    }
+
+    /// Notify `listener` of the IR built.
+    pub fn with_listener(mut self, listener: &'a mut dyn BuilderListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Where operations are inserted.
+    pub fn insertion_point(&self) -> InsertionPoint {
+        self.insertion_point
+    }
+
+    /// Insert operations at `insertion_point` from now on.
+    pub fn set_insertion_point(&mut self, insertion_point: InsertionPoint) {
+        self.insertion_point = insertion_point;
+    }
+
+    /// Insert operations at the start of the entry block of `region`,
+    /// creating the block (without arguments) if the region is empty.
+    pub fn set_insertion_point_to_region_start(&mut self, ctx: &mut Context, region: Ptr<Region>) {
+        let entry = region.deref(ctx).head();
+        match entry {
+            Some(entry) => self.insertion_point = InsertionPoint::AtBlockStart(entry),
+            None => {
+                self.create_block(ctx, region, None, vec![]);
+            }
+        }
+    }
+
+    /// Insert the unlinked `op` at the insertion point, and advance past it.
+    pub fn insert(&mut self, ctx: &Context, op: Ptr<Operation>) -> Ptr<Operation> {
+        match self.insertion_point {
+            InsertionPoint::AtBlockStart(block) => {
+                op.insert_at_front(block, ctx);
+                self.insertion_point = InsertionPoint::AfterOp(op);
+            }
+            InsertionPoint::AtBlockEnd(block) => op.insert_at_back(block, ctx),
+            InsertionPoint::BeforeOp(mark) => op.insert_before(ctx, mark),
+            InsertionPoint::AfterOp(mark) => {
+                op.insert_after(ctx, mark);
+                self.insertion_point = InsertionPoint::AfterOp(op);
+            }
+        }
+        if let Some(listener) = &mut self.listener {
+            listener.notify_op_inserted(ctx, op);
+        }
+        op
+    }
+
+    /// Create an [Op] with `build`, and [insert](Self::insert) it.
+    pub fn create<T: Op>(&mut self, ctx: &mut Context, build: impl FnOnce(&mut Context) -> T) -> T {
+        let op = build(ctx);
+        self.insert(ctx, op.operation());
+        op
+    }
+
+    /// Create a block at the end of `region`, and insert operations at its end.
+    pub fn create_block(
+        &mut self,
+        ctx: &mut Context,
+        region: Ptr<Region>,
+        label: Option<Identifier>,
+        arg_types: Vec<Ptr<TypeObj>>,
+    ) -> Ptr<BasicBlock> {
+        let block = BasicBlock::new(ctx, label, arg_types);
+        block.insert_at_back(region, ctx);
+        self.insertion_point = InsertionPoint::AtBlockEnd(block);
+        if let Some(listener) = &mut self.listener {
+            listener.notify_block_created(ctx, block);
+        }
+        block
+    }
+
+    /// Build, in region `region_idx` of `op`, a block with arguments of `arg_types`.
+    /// `body` builds the operations of the block, with a builder inserting at its end
+    /// (and notifying the same listener), given the block arguments. `terminate`
+    /// creates the terminator of the block from what `body` returns.
+    fn build_body<R>(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        region_idx: usize,
+        arg_types: Vec<Ptr<TypeObj>>,
+        body: impl FnOnce(&mut Context, &mut OpBuilder, &[Value]) -> R,
+        terminate: impl FnOnce(&mut Context, R) -> Ptr<Operation>,
+    ) {
+        let region = op.deref(ctx).region(region_idx);
+        let mut builder = OpBuilder {
+            insertion_point: self.insertion_point,
+            listener: self.listener.as_deref_mut().map(|listener| listener as _),
+        };
+        let block = builder.create_block(ctx, region, None, arg_types);
+        let args: Vec<_> = block.deref(ctx).arguments().collect();
+        let res = body(ctx, &mut builder, &args);
+        let terminator = terminate(ctx, res);
+        builder.insert(ctx, terminator);
+    }
+
+    /// Build an `if` on `cond`, with results of `result_types`. `then_body` and
+    /// `else_body` build the operations executed if `cond` holds, and otherwise,
+    /// returning the values to yield (as the results). Returns the `if` op.
+    pub fn build_if<S: StructuredOps>(
+        &mut self,
+        ctx: &mut Context,
+        cond: Value,
+        result_types: Vec<Ptr<TypeObj>>,
+        then_body: impl FnOnce(&mut Context, &mut OpBuilder) -> Vec<Value>,
+        else_body: impl FnOnce(&mut Context, &mut OpBuilder) -> Vec<Value>,
+    ) -> Ptr<Operation> {
+        let op = S::create_if(ctx, cond, result_types);
+        self.insert(ctx, op);
+        let then_body =
+            |ctx: &mut Context, builder: &mut OpBuilder, _: &[Value]| then_body(ctx, builder);
+        self.build_body(ctx, op, 0, vec![], then_body, S::create_yield);
+        let else_body =
+            |ctx: &mut Context, builder: &mut OpBuilder, _: &[Value]| else_body(ctx, builder);
+        self.build_body(ctx, op, 1, vec![], else_body, S::create_yield);
+        op
+    }
+
+    /// Build a `while` loop, with loop carried values initialized to `inits`.
+    /// `cond_body` is given the loop carried values, and returns the condition
+    /// and the values to continue to the body (or exit the loop) with. `body`
+    /// is given those values, and returns those of the next iteration.
+    /// Returns the `while` op, whose results are the final values.
+    pub fn build_while<S: StructuredOps>(
+        &mut self,
+        ctx: &mut Context,
+        inits: Vec<Value>,
+        cond_body: impl FnOnce(&mut Context, &mut OpBuilder, &[Value]) -> (Value, Vec<Value>),
+        body: impl FnOnce(&mut Context, &mut OpBuilder, &[Value]) -> Vec<Value>,
+    ) -> Ptr<Operation> {
+        let arg_types: Vec<_> = inits.iter().map(|init| init.get_type(ctx)).collect();
+        let op = S::create_while(ctx, inits);
+        self.insert(ctx, op);
+        let condition = |ctx: &mut Context, (cond, values)| S::create_condition(ctx, cond, values);
+        self.build_body(ctx, op, 0, arg_types.clone(), cond_body, condition);
+        self.build_body(ctx, op, 1, arg_types, body, S::create_yield);
+        op
+    }
+
+    /// Build a `for` loop from `lb` to `ub` (exclusive) by `step`, with loop carried
+    /// values initialized to `inits`. `body` is given the induction variable followed
+    /// by the loop carried values, and returns the values of the next iteration.
+    /// Returns the `for` op, whose results are the final values.
+    pub fn build_for<S: StructuredOps>(
+        &mut self,
+        ctx: &mut Context,
+        lb: Value,
+        ub: Value,
+        step: Value,
+        inits: Vec<Value>,
+        body: impl FnOnce(&mut Context, &mut OpBuilder, &[Value]) -> Vec<Value>,
+    ) -> Ptr<Operation> {
+        let mut arg_types = vec![lb.get_type(ctx)];
+        arg_types.extend(inits.iter().map(|init| init.get_type(ctx)));
+        let op = S::create_for(ctx, lb, ub, step, inits);
+        self.insert(ctx, op);
+        self.build_body(ctx, op, 0, arg_types, body, S::create_yield);
+        op
+    }
+}
@@ -21,3 +21,35 @@ pub trait TypedAttrInterface {
         Ok(())
     }
 }
+
+/// [Attribute]s that are printed verbatim, as `#dialect<payload>`, and parsed
+/// back by their dialect's [VerbatimAttrParserFn](crate::attribute::VerbatimAttrParserFn).
+/// See [attribute](crate::attribute) module documentation.
+#[attr_interface]
+pub trait VerbatimAttrInterface {
+    /// The payload to print. The brackets in it must be balanced.
+    fn verbatim_payload(&self, ctx: &Context) -> String;
+
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// [Attribute]s holding a (possibly large) number of elements, which can be elided
+/// when printed, as `[...N elements...]`. See [attribute](crate::attribute) module
+/// documentation, and [State::set_attr_elision_limit](crate::printable::State::set_attr_elision_limit).
+#[attr_interface]
+pub trait ElidableAttrInterface {
+    /// The number of elements in this attribute.
+    fn num_elements(&self) -> usize;
+
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
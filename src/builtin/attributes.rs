@@ -1,6 +1,6 @@
 use combine::{
     Parser, any, between, many, many1, none_of,
-    parser::char::{self, char, digit, spaces},
+    parser::char::{self, alpha_num, char, spaces},
     token,
 };
 use pliron::derive::{attr_interface_impl, def_attribute};
@@ -8,29 +8,38 @@ use pliron_derive::format_attribute;
 use thiserror::Error;
 
 use crate::{
+    arg_err_noloc,
     attribute::{AttrObj, Attribute, AttributeDict},
     common_traits::Verify,
     context::{Context, Ptr},
     identifier::Identifier,
     impl_verify_succ, input_err,
-    irfmt::{parsers::spaced, printers::quoted},
+    irfmt::{
+        aliases::AliasErr,
+        parsers::{attr_parser, delimited_list_parser, int_parser, spaced},
+        printers::quoted,
+    },
     location::Located,
     parsable::{IntoParseResult, Parsable, ParseResult, StateStream},
     printable::{self, Printable},
     result::Result,
     r#type::{TypeObj, TypePtr, Typed},
-    utils::apint::APInt,
+    utils::{
+        apfloat::{APFloat, FloatSemantics},
+        apint::{APInt, Radix, bw},
+    },
     verify_err_noloc,
 };
 
 use super::{
-    attr_interfaces::TypedAttrInterface,
-    types::{IntegerType, Signedness},
+    attr_interfaces::{ElidableAttrInterface, TypedAttrInterface},
+    types::{FloatType, IntegerType, Signedness},
 };
 
 #[def_attribute("builtin.identifier")]
 #[derive(PartialEq, Eq, Clone, Debug)]
 #[format_attribute]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IdentifierAttr(Identifier);
 
 impl IdentifierAttr {
@@ -52,6 +61,7 @@ impl From<IdentifierAttr> for Identifier {
 /// Similar to MLIR's [StringAttr](https://mlir.llvm.org/docs/Dialects/Builtin/#stringattr).
 #[def_attribute("builtin.string")]
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringAttr(String);
 
 impl StringAttr {
@@ -134,17 +144,17 @@ impl Printable for IntegerAttr {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
         let ty = &*self.ty.deref(ctx);
-        write!(
-            f,
-            "<{}: {}>",
-            self.val
+        let val = match state.int_radix() {
+            Radix::Decimal => self
+                .val
                 .to_string_decimal(ty.signedness() == Signedness::Signed),
-            ty.disp(ctx)
-        )
+            radix => self.val.to_string_bits(radix),
+        };
+        write!(f, "<{}: {}>", val, ty.disp(ctx))
     }
 }
 
@@ -161,11 +171,92 @@ impl Verify for IntegerAttr {
     }
 }
 
+#[derive(Debug, Error)]
+#[error("Integer {value} doesn't fit in {target}")]
+pub struct IntegerAttrRangeErr {
+    pub value: String,
+    pub target: String,
+}
+
 impl IntegerAttr {
     /// Create a new [IntegerAttr].
     pub fn new(ty: TypePtr<IntegerType>, val: APInt) -> Self {
         IntegerAttr { ty, val }
     }
+
+    /// Create an [IntegerAttr] of type `ty`, with value `val`,
+    /// which must be representable in `ty`.
+    pub fn from_u64(ctx: &Context, ty: TypePtr<IntegerType>, val: u64) -> Result<Self> {
+        let ty_ref = ty.deref(ctx);
+        let apint = APInt::from_u64(val, bw(ty_ref.width() as usize));
+        let signed = ty_ref.signedness() == Signedness::Signed;
+        if apint.try_to_u64(signed) != Some(val) {
+            return arg_err_noloc!(IntegerAttrRangeErr {
+                value: val.to_string(),
+                target: ty_ref.disp(ctx).to_string(),
+            });
+        }
+        Ok(IntegerAttr::new(ty, apint))
+    }
+
+    /// Create an [IntegerAttr] of type `ty`, with value `val`,
+    /// which must be representable in `ty`.
+    pub fn from_i64(ctx: &Context, ty: TypePtr<IntegerType>, val: i64) -> Result<Self> {
+        let ty_ref = ty.deref(ctx);
+        let apint = APInt::from_i64(val, bw(ty_ref.width() as usize));
+        let signed = ty_ref.signedness() != Signedness::Unsigned;
+        if apint.try_to_i64(signed) != Some(val) {
+            return arg_err_noloc!(IntegerAttrRangeErr {
+                value: val.to_string(),
+                target: ty_ref.disp(ctx).to_string(),
+            });
+        }
+        Ok(IntegerAttr::new(ty, apint))
+    }
+
+    /// Create an [IntegerAttr] of type `ty`, with value `val`,
+    /// which must be representable in `ty`.
+    pub fn from_usize(ctx: &Context, ty: TypePtr<IntegerType>, val: usize) -> Result<Self> {
+        Self::from_u64(ctx, ty, val as u64)
+    }
+
+    /// Error for a value that doesn't fit in `target`.
+    fn range_err<T>(&self, ctx: &Context, target: &str) -> Result<T> {
+        let signed = self.ty.deref(ctx).signedness() == Signedness::Signed;
+        arg_err_noloc!(IntegerAttrRangeErr {
+            value: self.val.to_string_decimal(signed),
+            target: target.to_string(),
+        })
+    }
+
+    /// Get the value as a [u64]. Signless values are interpreted as unsigned.
+    /// Fails if the value doesn't fit in a [u64].
+    pub fn as_u64(&self, ctx: &Context) -> Result<u64> {
+        let signed = self.ty.deref(ctx).signedness() == Signedness::Signed;
+        match self.val.try_to_u64(signed) {
+            Some(val) => Ok(val),
+            None => self.range_err(ctx, "u64"),
+        }
+    }
+
+    /// Get the value as an [i64]. Signless values are interpreted as signed.
+    /// Fails if the value doesn't fit in an [i64].
+    pub fn as_i64(&self, ctx: &Context) -> Result<i64> {
+        let signed = self.ty.deref(ctx).signedness() != Signedness::Unsigned;
+        match self.val.try_to_i64(signed) {
+            Some(val) => Ok(val),
+            None => self.range_err(ctx, "i64"),
+        }
+    }
+
+    /// Get the value as a [usize]. Signless values are interpreted as unsigned.
+    /// Fails if the value doesn't fit in a [usize].
+    pub fn as_usize(&self, ctx: &Context) -> Result<usize> {
+        match usize::try_from(self.as_u64(ctx)?) {
+            Ok(val) => Ok(val),
+            Err(_) => self.range_err(ctx, "usize"),
+        }
+    }
 }
 
 impl From<IntegerAttr> for APInt {
@@ -186,14 +277,16 @@ impl Parsable for IntegerAttr {
             token('<'),
             token('>'),
             spaces()
-                .with(many1::<String, _, _>(digit().or(char('-').or(char('+')))))
+                .with(many1::<String, _, _>(
+                    alpha_num().or(char('-').or(char('+'))),
+                ))
                 .skip(spaced(token(':')))
                 .and(IntegerType::parser(())),
         )
         .then(|(digits, ty)| {
             combine::parser(move |state_stream: &mut StateStream<'a>| {
                 let ty_ref = &*ty.deref(state_stream.state.ctx);
-                let apint = match APInt::from_str(&digits, ty_ref.width() as usize, 10) {
+                let apint = match APInt::from_str_prefixed(&digits, ty_ref.width() as usize) {
                     Ok(val) => Ok(val).into_parse_result(),
                     Err(err) => input_err!(state_stream.loc(), "{}", err).into_parse_result(),
                 }?;
@@ -218,100 +311,187 @@ impl TypedAttrInterface for IntegerAttr {
     }
 }
 
-/// A dummy implementation until we have a good one.
-#[derive(PartialEq, Clone, Debug)]
-pub struct APFloat;
-
 /// An attribute containing an floating point value.
 /// Similar to MLIR's [FloatAttr](https://mlir.llvm.org/docs/Dialects/Builtin/#floatattr).
-/// TODO: Use rustc's APFloat.
+/// Printed as `<value: type>`, with the value as described in [APFloat::from_str].
 #[def_attribute("builtin.float")]
-#[derive(PartialEq, Clone, Debug)]
-pub struct FloatAttr(APFloat);
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FloatAttr {
+    ty: TypePtr<FloatType>,
+    val: APFloat,
+}
 
 impl Printable for FloatAttr {
     fn fmt(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         _state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(f, "<unimplimented>")
+        write!(f, "<{}: {}>", self.val, self.ty.deref(ctx).disp(ctx))
     }
 }
 
+#[derive(Debug, Error)]
+#[error("The float type {ty} does not match the format {val} of the value.")]
+pub struct FloatAttrSemanticsErr {
+    pub ty: FloatSemantics,
+    pub val: FloatSemantics,
+}
+
 impl Verify for FloatAttr {
-    fn verify(&self, _ctx: &Context) -> Result<()> {
-        todo!()
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let ty = self.ty.deref(ctx).semantics();
+        if ty != self.val.semantics() {
+            return verify_err_noloc!(FloatAttrSemanticsErr {
+                ty,
+                val: self.val.semantics()
+            });
+        }
+        Ok(())
     }
 }
 
 impl FloatAttr {
     /// Create a new [FloatAttr].
-    pub fn new(value: APFloat) -> Self {
-        FloatAttr(value)
+    pub fn new(ty: TypePtr<FloatType>, val: APFloat) -> Self {
+        FloatAttr { ty, val }
+    }
+
+    /// Create a [FloatAttr] of type `ty`, with `val` rounded to the nearest value in `ty`.
+    pub fn from_f64(ctx: &Context, ty: TypePtr<FloatType>, val: f64) -> Self {
+        let semantics = ty.deref(ctx).semantics();
+        FloatAttr::new(ty, APFloat::from_f64(val, semantics))
+    }
+
+    /// Create a [FloatAttr] of type `ty`, with `val` rounded to the nearest value in `ty`.
+    pub fn from_f32(ctx: &Context, ty: TypePtr<FloatType>, val: f32) -> Self {
+        Self::from_f64(ctx, ty, val.into())
+    }
+
+    /// Get the value as an [f64]. This is exact.
+    pub fn to_f64(&self) -> f64 {
+        self.val.to_f64()
+    }
+
+    /// Get the value as an [f32], rounded to the nearest [f32].
+    pub fn to_f32(&self) -> f32 {
+        self.val.to_f32()
     }
 }
 
 impl From<FloatAttr> for APFloat {
     fn from(value: FloatAttr) -> Self {
-        value.0
+        value.val
     }
 }
 
 impl Typed for FloatAttr {
-    fn get_type(&self, _cfg: &Context) -> Ptr<TypeObj> {
-        TypedAttrInterface::get_type(self)
+    fn get_type(&self, _ctx: &Context) -> Ptr<TypeObj> {
+        self.ty.into()
     }
 }
 
 #[attr_interface_impl]
 impl TypedAttrInterface for FloatAttr {
     fn get_type(&self) -> Ptr<TypeObj> {
-        todo!()
+        self.ty.into()
     }
 }
 
 impl Parsable for FloatAttr {
     type Arg = ();
-    type Parsed = AttrObj;
+    type Parsed = Self;
 
     fn parse<'a>(
-        _state_stream: &mut StateStream<'a>,
+        state_stream: &mut StateStream<'a>,
         _arg: Self::Arg,
     ) -> ParseResult<'a, Self::Parsed> {
-        todo!()
+        let literal_char = char::alpha_num().or(combine::one_of(".+-".chars()));
+        between(
+            token('<'),
+            token('>'),
+            spaces()
+                .with(many1::<String, _, _>(literal_char))
+                .skip(spaced(token(':')))
+                .and(FloatType::parser(())),
+        )
+        .then(|(literal, ty)| {
+            combine::parser(move |state_stream: &mut StateStream<'a>| {
+                let semantics = ty.deref(state_stream.state.ctx).semantics();
+                let apfloat = match APFloat::from_str(&literal, semantics) {
+                    Ok(val) => Ok(val).into_parse_result(),
+                    Err(err) => input_err!(state_stream.loc(), "{}", err).into_parse_result(),
+                }?;
+                Ok(FloatAttr::new(ty, apfloat.0)).into_parse_result()
+            })
+        })
+        .parse_stream(state_stream)
+        .into_result()
     }
 }
 
 /// An attribute that is a dictionary of other attributes.
 /// Similar to MLIR's [DictionaryAttr](https://mlir.llvm.org/docs/Dialects/Builtin/#dictionaryattr),
+/// printed as `{key1 = attr1, key2 = attr2}`.
+///
+/// Equality, hashing and printing are independent of the insertion order of entries.
 #[def_attribute("builtin.dict")]
-#[derive(PartialEq, Clone, Eq, Debug)]
+#[derive(PartialEq, Clone, Eq, Debug, Hash)]
 pub struct DictAttr(AttributeDict);
 
 impl Printable for DictAttr {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(f, "{}", self.0.disp(ctx))
+        write!(f, "{{")?;
+        for (i, (key, val)) in self.iter_sorted().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} = {}", key, val.print(ctx, state))?;
+        }
+        write!(f, "}}")
     }
 }
 
 impl_verify_succ!(DictAttr);
 
+#[derive(Debug, Error)]
+#[error("Duplicate key {0} in dictionary attribute")]
+pub struct DictAttrDuplicateKeyErr(pub String);
+
 impl Parsable for DictAttr {
     type Arg = ();
     type Parsed = Self;
 
     fn parse<'a>(
-        _state_stream: &mut StateStream<'a>,
+        state_stream: &mut StateStream<'a>,
         _argg: Self::Arg,
     ) -> ParseResult<'a, Self::Parsed> {
-        todo!()
+        let entry = combine::parser(|state_stream: &mut StateStream<'a>| {
+            let loc = state_stream.loc();
+            Identifier::parser(())
+                .skip(spaced(token('=')))
+                .and(attr_parser())
+                .map(|(key, val)| (loc.clone(), key, val))
+                .parse_stream(state_stream)
+                .into_result()
+        });
+        let (entries, _) = delimited_list_parser('{', '}', ',', entry)
+            .parse_stream(state_stream)
+            .into_result()?;
+
+        let mut dict = AttributeDict::default();
+        for (loc, key, val) in entries {
+            if dict.0.insert(key, val).is_some() {
+                input_err!(loc, DictAttrDuplicateKeyErr(key.to_string()))?
+            }
+        }
+        Ok(DictAttr(dict)).into_parse_result()
     }
 }
 
@@ -327,7 +507,7 @@ impl DictAttr {
 
     /// Add an entry to the dictionary.
     pub fn insert(&mut self, key: &Identifier, val: AttrObj) {
-        self.0.0.insert(key.clone(), val);
+        self.0.0.insert(*key, val);
     }
 
     /// Remove an entry from the dictionary.
@@ -344,6 +524,11 @@ impl DictAttr {
     pub fn lookup_mut<'a>(&'a mut self, key: &Identifier) -> Option<&'a mut AttrObj> {
         self.0.0.get_mut(key)
     }
+
+    /// Iterate over the entries of the dictionary, sorted by their keys.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&Identifier, &AttrObj)> + Clone {
+        self.0.iter_sorted()
+    }
 }
 
 /// A vector of other attributes.
@@ -364,11 +549,72 @@ impl Verify for VecAttr {
     }
 }
 
+#[attr_interface_impl]
+impl ElidableAttrInterface for VecAttr {
+    fn num_elements(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// The tags, and the number of operands, of the [operand bundles](super::op_interfaces::OperandBundleInterface)
+/// of an operation, in order. Printed as `["deopt": 2, "gc-live": 1]`.
+#[def_attribute("builtin.operand_bundles")]
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperandBundlesAttr(pub Vec<(String, usize)>);
+
+impl OperandBundlesAttr {
+    /// Create a new [OperandBundlesAttr].
+    pub fn new(bundles: Vec<(String, usize)>) -> Self {
+        OperandBundlesAttr(bundles)
+    }
+}
+
+impl Printable for OperandBundlesAttr {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "[")?;
+        for (idx, (tag, num_opds)) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", quoted(tag).print(ctx, state), num_opds)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl_verify_succ!(OperandBundlesAttr);
+
+impl Parsable for OperandBundlesAttr {
+    type Arg = ();
+    type Parsed = Self;
+
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        _arg: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        let bundle = StringAttr::parser(())
+            .skip(spaced(token(':')))
+            .and(int_parser::<usize>())
+            .map(|(tag, num_opds)| (String::from(tag), num_opds));
+        delimited_list_parser('[', ']', ',', bundle)
+            .map(OperandBundlesAttr)
+            .parse_stream(state_stream)
+            .into_result()
+    }
+}
+
 /// Represent attributes that only have meaning from their existence.
 /// See [UnitAttr](https://mlir.llvm.org/docs/Dialects/Builtin/#unitattr) in MLIR.
 #[def_attribute("builtin.unit")]
 #[format_attribute]
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitAttr;
 
 impl UnitAttr {
@@ -407,18 +653,58 @@ impl TypedAttrInterface for TypeAttr {
     }
 }
 
+/// Stands for an attribute alias (`#name`) that is used before its definition
+/// during parsing. All placeholders are replaced once parsing completes.
+/// See [aliases](crate::irfmt::aliases).
+#[def_attribute("builtin.alias_placeholder")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct AliasPlaceholderAttr(Identifier);
+
+impl AliasPlaceholderAttr {
+    /// Create a placeholder for the attribute alias `name`.
+    pub fn new(name: Identifier) -> Self {
+        AliasPlaceholderAttr(name)
+    }
+
+    /// Name of the alias that this stands for.
+    pub fn name(&self) -> &Identifier {
+        &self.0
+    }
+}
+
+impl Printable for AliasPlaceholderAttr {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl Verify for AliasPlaceholderAttr {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        verify_err_noloc!(AliasErr::Unresolved(format!("#{}", self.0)))
+    }
+}
+
 pub fn register(ctx: &mut Context) {
     IdentifierAttr::register_attr_in_dialect(ctx, IdentifierAttr::parser_fn);
     StringAttr::register_attr_in_dialect(ctx, StringAttr::parser_fn);
     IntegerAttr::register_attr_in_dialect(ctx, IntegerAttr::parser_fn);
+    FloatAttr::register_attr_in_dialect(ctx, FloatAttr::parser_fn);
     DictAttr::register_attr_in_dialect(ctx, DictAttr::parser_fn);
     VecAttr::register_attr_in_dialect(ctx, VecAttr::parser_fn);
     UnitAttr::register_attr_in_dialect(ctx, UnitAttr::parser_fn);
+    OperandBundlesAttr::register_attr_in_dialect(ctx, OperandBundlesAttr::parser_fn);
     TypeAttr::register_attr_in_dialect(ctx, TypeAttr::parser_fn);
 }
 
 #[cfg(test)]
 mod tests {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
     use awint::bw;
     use expect_test::expect;
 
@@ -427,16 +713,20 @@ mod tests {
         builtin::{
             self,
             attr_interfaces::TypedAttrInterface,
-            attributes::{IntegerAttr, StringAttr},
-            types::{IntegerType, Signedness},
+            attributes::{FloatAttr, IntegerAttr, StringAttr},
+            types::{FloatType, IntegerType, Signedness},
         },
+        common_traits::Verify,
         context::Context,
         identifier::Identifier,
         irfmt::parsers::attr_parser,
         location,
         parsable::{self, state_stream_from_iterator},
-        printable::Printable,
-        utils::apint::APInt,
+        printable::{self, Printable},
+        utils::{
+            apfloat::{APFloat, FloatSemantics},
+            apint::{APInt, Radix},
+        },
     };
 
     use super::{DictAttr, TypeAttr, VecAttr};
@@ -467,6 +757,25 @@ mod tests {
                 )) == 15
         );
 
+        // Other radices print (and parse) the exact bit pattern.
+        let si8_ty = IntegerType::get(&mut ctx, 8, Signedness::Signed);
+        let minus_five: AttrObj = IntegerAttr::new(si8_ty, APInt::from_i8(-5, bw(8))).into();
+        let state = printable::State::default();
+        state.set_int_radix(Radix::Hex);
+        let hex = minus_five.print(&ctx, &state).to_string();
+        assert_eq!(hex, "builtin.integer <0xfb: si8>");
+        state.set_int_radix(Radix::Binary);
+        let binary = minus_five.print(&ctx, &state).to_string();
+        assert_eq!(binary, "builtin.integer <0b11111011: si8>");
+        for input in [hex.as_str(), binary.as_str(), "builtin.integer <-0x5: si8>"] {
+            let state_stream = state_stream_from_iterator(
+                input.chars(),
+                parsable::State::new(&mut ctx, location::Source::InMemory),
+            );
+            let attr = attr_parser().parse(state_stream).unwrap().0;
+            assert_eq!(attr.disp(&ctx).to_string(), "builtin.integer <-5: si8>");
+        }
+
         let attr_input = "builtin.integer <0: builtin.unit>";
         let state_stream = state_stream_from_iterator(
             attr_input.chars(),
@@ -485,6 +794,88 @@ mod tests {
         expected_err_msg.assert_eq(&parse_err.to_string());
     }
 
+    #[test]
+    fn test_integer_attribute_accessors() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        let si8_ty = IntegerType::get(&mut ctx, 8, Signedness::Signed);
+        let ui8_ty = IntegerType::get(&mut ctx, 8, Signedness::Unsigned);
+        let i8_ty = IntegerType::get(&mut ctx, 8, Signedness::Signless);
+        let ui128_ty = IntegerType::get(&mut ctx, 128, Signedness::Unsigned);
+
+        let minus_one = IntegerAttr::from_i64(&ctx, si8_ty, -1).unwrap();
+        assert_eq!(minus_one.as_i64(&ctx).unwrap(), -1);
+        assert!(minus_one.as_u64(&ctx).is_err());
+        assert!(minus_one.as_usize(&ctx).is_err());
+
+        let max = IntegerAttr::from_usize(&ctx, ui8_ty, 255).unwrap();
+        assert_eq!(max.as_u64(&ctx).unwrap(), 255);
+        assert_eq!(max.as_i64(&ctx).unwrap(), 255);
+        assert_eq!(max.as_usize(&ctx).unwrap(), 255);
+        assert!(IntegerAttr::from_usize(&ctx, ui8_ty, 256).is_err());
+        assert!(IntegerAttr::from_u64(&ctx, si8_ty, 128).is_err());
+        assert!(IntegerAttr::from_i64(&ctx, ui8_ty, -1).is_err());
+
+        // Signless values are unsigned or signed, as requested.
+        let signless = IntegerAttr::from_u64(&ctx, i8_ty, 255).unwrap();
+        assert_eq!(signless.as_u64(&ctx).unwrap(), 255);
+        assert_eq!(signless.as_i64(&ctx).unwrap(), -1);
+        assert!(IntegerAttr::from_i64(&ctx, i8_ty, -128).is_ok());
+        assert!(IntegerAttr::from_i64(&ctx, i8_ty, -129).is_err());
+
+        let wide = IntegerAttr::new(ui128_ty, APInt::from_u128(1 << 64, bw(128)));
+        let err = wide.as_u64(&ctx).unwrap_err();
+        expect!["Integer 18446744073709551616 doesn't fit in u64"].assert_eq(&err.err.to_string());
+    }
+
+    #[test]
+    fn test_float_attributes() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        let f32_ty = FloatType::get(&mut ctx, FloatSemantics::Single);
+        let f16_ty = FloatType::get(&mut ctx, FloatSemantics::Half);
+
+        let tenth = FloatAttr::from_f64(&ctx, f32_ty, 0.1);
+        assert_eq!(tenth.to_f32(), 0.1);
+        assert!(tenth.verify(&ctx).is_ok());
+        let mismatched = FloatAttr::new(f16_ty, APFloat::from(tenth.clone()));
+        assert!(mismatched.verify(&ctx).is_err());
+
+        let parse = |ctx: &mut Context, input: &str| -> AttrObj {
+            let state_stream = state_stream_from_iterator(
+                input.chars(),
+                parsable::State::new(ctx, location::Source::InMemory),
+            );
+            attr_parser().parse(state_stream).unwrap().0
+        };
+        for (input, printed) in [
+            ("builtin.float <0.1: f32>", "builtin.float <0.1: f32>"),
+            ("builtin.float <-0x1.8p1: f64>", "builtin.float <-3.0: f64>"),
+            (
+                "builtin.float <1e-3 : bf16>",
+                "builtin.float <0.0009994507: bf16>",
+            ),
+            ("builtin.float <0x7E00: f16>", "builtin.float <0x7E00: f16>"),
+            ("builtin.float <-inf: f16>", "builtin.float <-inf: f16>"),
+        ] {
+            let attr = parse(&mut ctx, input);
+            assert_eq!(attr.disp(&ctx).to_string(), printed);
+            assert!(parse(&mut ctx, printed) == attr);
+        }
+
+        let state_stream = state_stream_from_iterator(
+            "builtin.float <0x1FFFF: f16>".chars(),
+            parsable::State::new(&mut ctx, location::Source::InMemory),
+        );
+        let parse_err = attr_parser().parse(state_stream).err().unwrap();
+        expect![[r#"
+            Parse error at line: 1, column: 29
+            Compilation error: invalid argument.
+            Bit pattern 0x1FFFF is wider than f16
+        "#]]
+        .assert_eq(&parse_err.to_string());
+    }
+
     #[test]
     fn test_string_attributes() {
         let mut ctx = Context::new();
@@ -545,23 +936,44 @@ mod tests {
         let world_id: Identifier = "world".try_into().unwrap();
 
         let mut dict1: AttrObj = DictAttr::new(vec![
-            (hello_id.clone(), hello_attr.clone()),
-            (world_id.clone(), world_attr.clone()),
+            (hello_id, hello_attr.clone()),
+            (world_id, world_attr.clone()),
         ])
         .into();
         let mut dict2 = DictAttr::new(vec![(
-            hello_id.clone(),
+            hello_id,
             StringAttr::new("hello".to_string()).into(),
         )])
         .into();
         let dict1_rev = DictAttr::new(vec![
-            (world_id.clone(), world_attr.clone()),
-            (hello_id.clone(), hello_attr.clone()),
+            (world_id, world_attr.clone()),
+            (hello_id, hello_attr.clone()),
         ])
         .into();
         assert!(&dict1 != &dict2);
         assert!(dict1 == dict1_rev);
 
+        // Insertion order must not affect hashing, iteration order or printing.
+        let ctx = Context::new();
+        let dict1_ref = dict1.downcast_ref::<DictAttr>().unwrap();
+        let dict1_rev_ref = dict1_rev.downcast_ref::<DictAttr>().unwrap();
+        let hash = |dict: &DictAttr| {
+            let mut hasher = DefaultHasher::new();
+            dict.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(dict1_ref), hash(dict1_rev_ref));
+        let keys: Vec<_> = dict1_rev_ref.iter_sorted().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![hello_id, world_id]);
+        expect![[
+            r#"builtin.dict {hello = builtin.string "hello", world = builtin.string "world"}"#
+        ]]
+        .assert_eq(&dict1_rev.disp(&ctx).to_string());
+        assert_eq!(
+            dict1.disp(&ctx).to_string(),
+            dict1_rev.disp(&ctx).to_string()
+        );
+
         let dict1_attr = dict1.as_mut().downcast_mut::<DictAttr>().unwrap();
         let dict2_attr = dict2.as_mut().downcast_mut::<DictAttr>().unwrap();
         assert!(dict1_attr.lookup(&hello_id).unwrap() == &hello_attr);
@@ -579,6 +991,46 @@ mod tests {
         assert!(&dict1 == &dict2);
     }
 
+    #[test]
+    fn test_dictionary_attributes_parsing() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+
+        let parse = |ctx: &mut Context, input: &str| {
+            let state_stream = state_stream_from_iterator(
+                input.chars(),
+                parsable::State::new(ctx, location::Source::InMemory),
+            );
+            attr_parser().parse(state_stream).map(|(attr, _)| attr)
+        };
+
+        let input = "builtin.dict { world = builtin.string \"world\",hello=builtin.dict {inner = builtin.string \"x\"}, empty = builtin.dict {}}";
+        let attr = parse(&mut ctx, input).unwrap();
+        let printed = attr.disp(&ctx).to_string();
+        expect![[r#"builtin.dict {empty = builtin.dict {}, hello = builtin.dict {inner = builtin.string "x"}, world = builtin.string "world"}"#]]
+        .assert_eq(&printed);
+        let dict = attr.downcast_ref::<DictAttr>().unwrap();
+        let reparsed = parse(&mut ctx, &printed).unwrap();
+        assert!(reparsed.downcast_ref::<DictAttr>() == Some(dict));
+
+        let hello = dict.lookup(&"hello".try_into().unwrap()).unwrap();
+        assert!(
+            hello
+                .downcast_ref::<DictAttr>()
+                .unwrap()
+                .lookup(&"inner".try_into().unwrap())
+                .is_some()
+        );
+
+        let input = "builtin.dict {a = builtin.string \"x\",\n  a = builtin.string \"x\"}";
+        let err = parse(&mut ctx, input).err().unwrap();
+        expect![[r#"
+            Parse error at line: 2, column: 3
+            Duplicate key a in dictionary attribute
+        "#]]
+        .assert_eq(&err.to_string());
+    }
+
     #[test]
     fn test_vec_attributes() {
         let hello_attr: AttrObj = StringAttr::new("hello".to_string()).into();
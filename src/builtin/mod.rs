@@ -4,22 +4,41 @@ pub mod attr_interfaces;
 pub mod attributes;
 pub mod op_interfaces;
 pub mod ops;
+#[cfg(feature = "serde")]
+mod serializable;
+pub mod symbol_table;
+pub mod type_interfaces;
 pub mod types;
 
 use std::sync::LazyLock;
 
 use crate::{
     context::Context,
-    dialect::{Dialect, DialectName},
+    dialect::{Dialect, DialectName, DialectPlugin},
     identifier::Identifier,
 };
 
+/// [DialectPlugin] for the builtin dialect.
+pub struct BuiltinDialect;
+
+impl DialectPlugin for BuiltinDialect {
+    fn name(&self) -> DialectName {
+        DialectName::new("builtin")
+    }
+
+    fn register(&self, ctx: &mut Context) {
+        Dialect::new(self.name()).register(ctx);
+        ops::register(ctx);
+        types::register(ctx);
+        attributes::register(ctx);
+        #[cfg(feature = "serde")]
+        serializable::register(ctx);
+    }
+}
+
+/// Load the builtin dialect into context.
 pub fn register(ctx: &mut Context) {
-    let dialect = Dialect::new(DialectName::new("builtin"));
-    dialect.register(ctx);
-    ops::register(ctx);
-    types::register(ctx);
-    attributes::register(ctx);
+    BuiltinDialect.load(ctx);
 }
 
 /// Key for debug info related attributes.
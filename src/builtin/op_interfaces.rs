@@ -1,18 +1,28 @@
-use std::{collections::hash_map, sync::LazyLock};
+use std::{
+    collections::hash_map,
+    hash::{Hash, Hasher},
+    sync::LazyLock,
+};
 
 use pliron::derive::op_interface;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use thiserror::Error;
 
 use crate::{
+    arg_err,
+    attribute::AttrObj,
     basic_block::BasicBlock,
-    builtin::attributes::TypeAttr,
+    builtin::attributes::{IntegerAttr, OperandBundlesAttr, TypeAttr, UnitAttr},
     context::{Context, Ptr},
+    graph::walkers::{
+        IRNode, WALKCONFIG_PREORDER_FORWARD,
+        interruptible::{walk_advance, walk_skip},
+    },
     identifier::Identifier,
     linked_list::ContainsLinkedList,
     location::{Located, Location},
-    op::{Op, op_cast},
-    operation::Operation,
+    op::{Op, op_cast, op_impls},
+    operation::{Operation, OperationEquivalence},
     printable::Printable,
     region::Region,
     result::Result,
@@ -34,6 +44,29 @@ pub trait IsTerminatorInterface {
     }
 }
 
+/// A [terminator](IsTerminatorInterface) [Op] implementing this is never executed:
+/// control reaching it is undefined behaviour. It has no successors, and unlike other
+/// terminators without successors, doesn't return from the enclosing function.
+#[op_interface]
+pub trait UnreachableInterface: IsTerminatorInterface {
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let op = op.operation().deref(ctx);
+        if op.num_successors() != 0 {
+            return verify_err!(op.loc(), UnreachableInterfaceVerifyErr::HasSuccessors);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum UnreachableInterfaceVerifyErr {
+    #[error("Unreachable terminator must not have successors")]
+    HasSuccessors,
+}
+
 #[derive(Error, Debug)]
 pub enum BranchOpInterfaceVerifyErr {
     #[error("Branch Op is passing {provided} arguments, but target block expects {expected}")]
@@ -95,6 +128,27 @@ pub trait BranchOpInterface: IsTerminatorInterface {
     }
 }
 
+/// A [BranchOpInterface] [Op] choosing between its successors, where
+/// the one that is taken may be known from (constant) operands.
+#[op_interface]
+pub trait ConditionalBranchInterface: BranchOpInterface {
+    /// Get the index of the successor that is always taken when the operands of this
+    /// branch have the given constant values (`None` for those that aren't known).
+    fn taken_successor(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Option<usize>;
+
+    /// Build an unconditional branch to the successor `succ_idx`, passing it the
+    /// same [successor operands](BranchOpInterface::successor_operands) as this does.
+    /// The returned [Operation] isn't linked to any block.
+    fn build_branch_to(&self, ctx: &mut Context, succ_idx: usize) -> Ptr<Operation>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
 /// Describe the abstract semantics of [Regions](crate::region::Region).
 ///
 /// See MLIR's [RegionKind](https://mlir.llvm.org/docs/Interfaces/#regionkindinterfaces).
@@ -192,6 +246,11 @@ pub trait SingleBlockRegionInterface {
 pub static ATTR_KEY_SYM_NAME: LazyLock<Identifier> =
     LazyLock::new(|| "builtin_sym_name".try_into().unwrap());
 
+/// Marks a [SymbolOpInterface] op as private: Its symbol isn't visible outside the
+/// symbol table that defines it, so all of its uses are known. Symbols are public by default.
+pub static ATTR_KEY_SYM_PRIVATE: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_sym_private".try_into().unwrap());
+
 #[derive(Error, Debug)]
 #[error("Op implementing SymbolOpInterface does not have a symbol defined")]
 pub struct SymbolOpInterfaceErr;
@@ -211,9 +270,28 @@ pub trait SymbolOpInterface {
 
     /// Set a name for the symbol defined by this operation.
     fn set_symbol_name(&self, ctx: &mut Context, name: &Identifier) {
-        let name_attr = IdentifierAttr::new(name.clone());
+        let name_attr = IdentifierAttr::new(*name);
         let mut self_op = self.operation().deref_mut(ctx);
-        self_op.attributes.set(ATTR_KEY_SYM_NAME.clone(), name_attr);
+        self_op.attributes.set(*ATTR_KEY_SYM_NAME, name_attr);
+    }
+
+    /// Is this symbol marked (with [ATTR_KEY_SYM_PRIVATE]) as private?
+    fn is_private(&self, ctx: &Context) -> bool {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&ATTR_KEY_SYM_PRIVATE)
+            .is_some()
+    }
+
+    /// Make this symbol private, or public.
+    fn set_private(&self, ctx: &mut Context, private: bool) {
+        let attributes = &mut self.operation().deref_mut(ctx).attributes;
+        if private {
+            attributes.set(*ATTR_KEY_SYM_PRIVATE, UnitAttr::new());
+        } else {
+            attributes.0.remove(&*ATTR_KEY_SYM_PRIVATE);
+        }
     }
 
     fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
@@ -236,6 +314,8 @@ pub trait SymbolOpInterface {
 pub enum SymbolTableInterfaceErr {
     #[error("Multiple definitions of Symbol {0}")]
     SymbolRedefined(String),
+    #[error("Symbol {0} not found in symbol table")]
+    SymbolNotFound(String),
 }
 
 // Any [Op] that holds a symbol table.
@@ -253,6 +333,80 @@ pub trait SymbolTableInterface: SingleBlockRegionInterface + OneRegionInterface
         None
     }
 
+    /// Get all [Operation]s nested inside this symbol table that
+    /// use `sym`, as reported by [SymbolUserOpInterface].
+    /// Uses inside nested symbol tables that define `sym` themselves
+    /// refer to that definition, and aren't included.
+    fn symbol_uses(&self, ctx: &mut Context, sym: &Identifier) -> Vec<Ptr<Operation>> {
+        let mut uses = vec![];
+        let body = self.body(ctx, 0);
+        let _ = BasicBlock::walk(
+            body,
+            ctx,
+            &WALKCONFIG_PREORDER_FORWARD,
+            &mut |ctx: &mut Context, node| {
+                let IRNode::Operation(op) = node else {
+                    return walk_advance::<()>();
+                };
+                let op_obj = Operation::op(op, ctx);
+                if op_cast::<dyn SymbolUserOpInterface>(&*op_obj)
+                    .is_some_and(|user| user.used_symbols(ctx).contains(sym))
+                {
+                    uses.push(op);
+                }
+                // Nested symbol tables that (re)define `sym` shadow it.
+                if op_cast::<dyn SymbolTableInterface>(&*op_obj)
+                    .is_some_and(|table| table.lookup(ctx, sym).is_some())
+                {
+                    return walk_skip();
+                }
+                walk_advance()
+            },
+        );
+        uses
+    }
+
+    /// Is `sym` used anywhere inside this symbol table?
+    fn symbol_is_used(&self, ctx: &mut Context, sym: &Identifier) -> bool {
+        !self.symbol_uses(ctx, sym).is_empty()
+    }
+
+    /// Rename the symbol `from` defined in this table to `to`,
+    /// updating all of its uses inside this table.
+    /// Fails, without modifying the IR, if `from` isn't defined
+    /// or if `to` is already defined in this table.
+    fn rename_symbol(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) -> Result<()> {
+        let loc = self.loc(ctx);
+        let Some(def_op) = self.lookup(ctx, from) else {
+            return arg_err!(
+                loc,
+                SymbolTableInterfaceErr::SymbolNotFound(from.to_string())
+            );
+        };
+        if from == to {
+            return Ok(());
+        }
+        if let Some(existing) = self.lookup(ctx, to) {
+            return arg_err!(
+                existing.deref(ctx).loc(),
+                SymbolTableInterfaceErr::SymbolRedefined(to.to_string())
+            );
+        }
+
+        let users = self.symbol_uses(ctx, from);
+        let def_op = Operation::op(def_op, ctx);
+        op_cast::<dyn SymbolOpInterface>(&*def_op)
+            .expect("Symbol table lookup must return a SymbolOpInterface Op")
+            .set_symbol_name(ctx, to);
+        for user in users {
+            let user = Operation::op(user, ctx);
+            op_cast::<dyn SymbolUserOpInterface>(&*user)
+                .expect("Symbol use must be a SymbolUserOpInterface Op")
+                .replace_symbol_uses(ctx, from, to);
+        }
+        Ok(())
+    }
+
     fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
     where
         Self: Sized,
@@ -265,7 +419,7 @@ pub trait SymbolTableInterface: SingleBlockRegionInterface + OneRegionInterface
         for op in table_ops_block.deref(ctx).iter(ctx) {
             if let Some(sym_op) = op_cast::<dyn SymbolOpInterface>(&*Operation::op(op, ctx)) {
                 let sym = sym_op.symbol_name(ctx);
-                match seen.entry(sym.clone()) {
+                match seen.entry(sym) {
                     hash_map::Entry::Occupied(prev_loc) => {
                         return verify_err!(
                             op.deref(ctx).loc(),
@@ -286,6 +440,26 @@ pub trait SymbolTableInterface: SingleBlockRegionInterface + OneRegionInterface
     }
 }
 
+/// [Op] that references (uses) one or more [symbol](https://mlir.llvm.org/docs/SymbolsAndSymbolTables/#symbol)s,
+/// for example, a direct call. Symbol references are typically held in attributes.
+/// Implementing this interface allows [SymbolTableInterface] to find
+/// all users of a symbol and to safely rename symbols.
+#[op_interface]
+pub trait SymbolUserOpInterface {
+    /// Get all symbols referenced by this operation.
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier>;
+
+    /// Replace all references to symbol `from` in this operation with `to`.
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier);
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
 #[derive(Error, Debug)]
 #[error("Op {0} must have single result")]
 pub struct OneResultVerifyErr(pub String);
@@ -507,6 +681,323 @@ pub trait SameOperandsAndResultType: SameOperandsType + SameResultsType {
     }
 }
 
+/// An [Op] that defines a compile time constant value, held as an attribute.
+/// See MLIR's [ConstantLike](https://mlir.llvm.org/docs/Traits/#constantlike) trait.
+#[op_interface]
+pub trait ConstantLikeInterface: ZeroOpdInterface + OneResultInterface {
+    /// Get the constant value that this Op defines.
+    fn constant_value(&self, ctx: &Context) -> AttrObj;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// A [ConstantLikeInterface] [Op] whose value can be hoisted into a module level global,
+/// and loaded from there where needed. This reduces the stack usage of generated code
+/// for large constants. See [promote_constants](crate::transforms::promote_constants).
+#[op_interface]
+pub trait HoistableConstantInterface: ConstantLikeInterface {
+    /// Size, in bytes, of the constant value.
+    fn constant_size(&self, ctx: &Context) -> usize;
+
+    /// Create a global, with symbol name `name`, initialized to the constant value.
+    /// The returned [Operation] isn't linked to any block.
+    fn build_global(&self, ctx: &mut Context, name: &Identifier) -> Ptr<Operation>;
+
+    /// Create a sequence of [Operation]s that load the value of the global `name`,
+    /// returning them (in order) along with the loaded [Value], which must have
+    /// the same type as the result of this [Op].
+    /// The returned [Operation]s aren't linked to any block.
+    fn build_global_load(
+        &self,
+        ctx: &mut Context,
+        name: &Identifier,
+    ) -> (Vec<Ptr<Operation>>, Value);
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// An [Op] without side effects: it doesn't read or write memory, or otherwise
+/// affect the state of the program, so that it can be erased if its results are unused.
+/// An [Op] with regions is free of side effects only if the [Op]s nested in it are too.
+/// See MLIR's [Pure](https://mlir.llvm.org/docs/Traits/#pure) trait, and
+/// [dead code elimination](crate::transforms::dce).
+#[op_interface]
+pub trait PureInterface {
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// The kinds of [MemoryEffect]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryEffectKind {
+    /// Reads memory.
+    Read,
+    /// Writes memory.
+    Write,
+    /// Allocates memory.
+    Allocate,
+    /// Frees memory.
+    Free,
+}
+
+/// What a [MemoryEffect] acts on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryEffectOn {
+    /// The memory pointed to by the operand with the given index.
+    Operand(usize),
+    /// The memory pointed to by the result with the given index.
+    Result(usize),
+    /// State not identified by any value, such as global memory,
+    /// or anything else observable outside the program.
+    Ambient,
+}
+
+/// A side effect of an [Op] on memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MemoryEffect {
+    pub kind: MemoryEffectKind,
+    pub on: MemoryEffectOn,
+}
+
+impl MemoryEffect {
+    /// Create a new [MemoryEffect].
+    pub fn new(kind: MemoryEffectKind, on: MemoryEffectOn) -> Self {
+        MemoryEffect { kind, on }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MemoryEffectVerifyErr {
+    #[error("Memory effect on operand {0}, but the Op has only {1} operands")]
+    OperandOutOfRange(usize, usize),
+    #[error("Memory effect on result {0}, but the Op has only {1} results")]
+    ResultOutOfRange(usize, usize),
+}
+
+/// An [Op] declaring its [side effects on memory](MemoryEffect).
+/// [Op]s that neither implement this interface, nor are [pure](PureInterface)
+/// or [constants](ConstantLikeInterface), may have any side effect.
+/// See MLIR's [MemoryEffectOpInterface](https://mlir.llvm.org/docs/Interfaces/#side-effect-interfaces)
+/// and [is_pure].
+#[op_interface]
+pub trait MemoryEffectOpInterface {
+    /// The effects of this [Op] itself, not including those of the [Op]s nested in it.
+    /// An empty list means it has no side effects (like [PureInterface] [Op]s).
+    fn memory_effects(&self, ctx: &Context) -> Vec<MemoryEffect>;
+
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let effects = op_cast::<dyn MemoryEffectOpInterface>(op)
+            .expect("Op must impl MemoryEffectOpInterface")
+            .memory_effects(ctx);
+        let op = op.operation().deref(ctx);
+        for effect in effects {
+            match effect.on {
+                MemoryEffectOn::Operand(idx) if idx >= op.num_operands() => {
+                    return verify_err!(
+                        op.loc(),
+                        MemoryEffectVerifyErr::OperandOutOfRange(idx, op.num_operands())
+                    );
+                }
+                MemoryEffectOn::Result(idx) if idx >= op.num_results() => {
+                    return verify_err!(
+                        op.loc(),
+                        MemoryEffectVerifyErr::ResultOutOfRange(idx, op.num_results())
+                    );
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The [memory effects](MemoryEffectOpInterface) of `op` itself, not including those
+/// of the [Op]s nested in it. [Pure](PureInterface) and [constant](ConstantLikeInterface)
+/// [Op]s have none. [None] if the effects are unknown.
+pub fn memory_effects(ctx: &Context, op: Ptr<Operation>) -> Option<Vec<MemoryEffect>> {
+    let op_obj = Operation::op(op, ctx);
+    if let Some(effects_op) = op_cast::<dyn MemoryEffectOpInterface>(&*op_obj) {
+        return Some(effects_op.memory_effects(ctx));
+    }
+    (op_impls::<dyn PureInterface>(&*op_obj) || op_impls::<dyn ConstantLikeInterface>(&*op_obj))
+        .then(Vec::new)
+}
+
+/// Are the [memory effects](memory_effects) of `op`, and of the [Op]s nested in it, all known
+/// and `allowed`? `allowed` is called with the [Operation] having the effect.
+/// Nested terminators that don't declare their effects with [MemoryEffectOpInterface]
+/// are assumed to only transfer control within, or out of, their regions. Other
+/// terminators (such as calls that also branch) are queried like any other [Op].
+pub fn has_only_effects(
+    ctx: &Context,
+    op: Ptr<Operation>,
+    allowed: &dyn Fn(Ptr<Operation>, &MemoryEffect) -> bool,
+) -> bool {
+    let Some(effects) = memory_effects(ctx, op) else {
+        return false;
+    };
+    if !effects.iter().all(|effect| allowed(op, effect)) {
+        return false;
+    }
+    op.deref(ctx).regions().all(|region| {
+        region.deref(ctx).iter(ctx).all(|block| {
+            block.deref(ctx).iter(ctx).all(|nested| {
+                let nested_obj = Operation::op(nested, ctx);
+                (op_impls::<dyn IsTerminatorInterface>(&*nested_obj)
+                    && !op_impls::<dyn MemoryEffectOpInterface>(&*nested_obj))
+                    || has_only_effects(ctx, nested, allowed)
+            })
+        })
+    })
+}
+
+/// Is `op` free of side effects, along with the [Op]s nested in it?
+/// See [has_only_effects].
+pub fn is_pure(ctx: &Context, op: Ptr<Operation>) -> bool {
+    has_only_effects(ctx, op, &|_, _| false)
+}
+
+/// Customize when two [Op]s are considered equivalent, i.e., compute the same values,
+/// for example to eliminate common subexpressions.
+///
+/// [Op]s that don't implement this interface are compared structurally:
+/// see [op_equivalence_hash] and [ops_equivalent]. The default methods here
+/// also compare structurally, taking into account [is_commutative](Self::is_commutative)
+/// and [ignored_attributes](Self::ignored_attributes).
+/// Equivalence doesn't consider side effects, that is for the users to check.
+#[op_interface]
+pub trait OpEquivalence {
+    /// Is the result independent of the order of operands?
+    fn is_commutative(&self) -> bool {
+        false
+    }
+
+    /// Attributes that don't affect the semantics of this [Op],
+    /// and are ignored in comparisons. [ATTR_KEY_DEBUG_INFO] is always ignored.
+    ///
+    /// [ATTR_KEY_DEBUG_INFO]: super::ATTR_KEY_DEBUG_INFO
+    fn ignored_attributes(&self) -> Vec<Identifier> {
+        vec![]
+    }
+
+    /// Hash the parts of this [Op] that determine equivalence.
+    /// Equivalent [Op]s must have the same hash.
+    fn equivalence_hash(&self, ctx: &Context, state: &mut dyn Hasher) {
+        structural_hash(
+            ctx,
+            self.operation(),
+            self.is_commutative(),
+            &self.ignored_attributes(),
+            state,
+        )
+    }
+
+    /// Is this [Op] equivalent to `other`?
+    /// `other` is guaranteed to have the same [OpId](crate::op::OpId).
+    fn is_equivalent(&self, ctx: &Context, other: &dyn Op) -> bool {
+        structural_equivalent(
+            ctx,
+            self.operation(),
+            other.operation(),
+            self.is_commutative(),
+            &self.ignored_attributes(),
+        )
+    }
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Hash `op` structurally: its [OpId](crate::op::OpId), operands, result types,
+/// successors, number of regions and attributes (except `ignored_attrs` and
+/// [ATTR_KEY_DEBUG_INFO]). If `commutative`, the order of operands is ignored.
+/// See [OperationEquivalence].
+///
+/// [ATTR_KEY_DEBUG_INFO]: super::ATTR_KEY_DEBUG_INFO
+pub fn structural_hash(
+    ctx: &Context,
+    op: Ptr<Operation>,
+    commutative: bool,
+    ignored_attrs: &[Identifier],
+    state: &mut dyn Hasher,
+) {
+    OperationEquivalence {
+        commutative,
+        ignored_attrs,
+    }
+    .hash(ctx, op, state)
+}
+
+/// Are `op1` and `op2` structurally equivalent? i.e., they have the same
+/// [OpId](crate::op::OpId), operands, result types, successors and attributes
+/// (except `ignored_attrs` and [ATTR_KEY_DEBUG_INFO]), and have no regions.
+/// If `commutative`, the operands may be in any order. See [OperationEquivalence].
+///
+/// [ATTR_KEY_DEBUG_INFO]: super::ATTR_KEY_DEBUG_INFO
+pub fn structural_equivalent(
+    ctx: &Context,
+    op1: Ptr<Operation>,
+    op2: Ptr<Operation>,
+    commutative: bool,
+    ignored_attrs: &[Identifier],
+) -> bool {
+    OperationEquivalence {
+        commutative,
+        ignored_attrs,
+    }
+    .is_equivalent(ctx, op1, op2)
+}
+
+/// Hash `op` for equivalence: via [OpEquivalence] if it implements it,
+/// otherwise [structurally](structural_hash).
+/// Equivalent [Operation]s (see [ops_equivalent]) have the same hash.
+pub fn op_equivalence_hash(ctx: &Context, op: Ptr<Operation>) -> u64 {
+    let mut state = FxHasher::default();
+    let op_obj = Operation::op(op, ctx);
+    match op_cast::<dyn OpEquivalence>(&*op_obj) {
+        Some(op_eq) => op_eq.equivalence_hash(ctx, &mut state),
+        None => structural_hash(ctx, op, false, &[], &mut state),
+    }
+    state.finish()
+}
+
+/// Are `op1` and `op2` equivalent? Decided via [OpEquivalence]
+/// if they implement it, otherwise [structurally](structural_equivalent).
+pub fn ops_equivalent(ctx: &Context, op1: Ptr<Operation>, op2: Ptr<Operation>) -> bool {
+    if op1 == op2 {
+        return true;
+    }
+    if op1.deref(ctx).opid() != op2.deref(ctx).opid() {
+        return false;
+    }
+    let op1_obj = Operation::op(op1, ctx);
+    match op_cast::<dyn OpEquivalence>(&*op1_obj) {
+        Some(op_eq) => op_eq.is_equivalent(ctx, &*Operation::op(op2, ctx)),
+        None => structural_equivalent(ctx, op1, op2, false, &[]),
+    }
+}
+
 /// A callable object is either a
 ///   - direct callee, expressed as a symbol)
 ///   - indirect callee, a [Value] pointing to the function to be called.
@@ -526,6 +1017,11 @@ pub enum CallOpInterfaceErr {
 pub static ATTR_KEY_CALLEE_TYPE: LazyLock<Identifier> =
     LazyLock::new(|| "builtin_callee_type".try_into().unwrap());
 
+/// Marks a [CallOpInterface] op as being in tail position.
+/// See [mark_tail_calls](crate::transforms::tail_call::mark_tail_calls).
+pub static ATTR_KEY_TAIL_CALL: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_tail_call".try_into().unwrap());
+
 /// A call-like op: Transfers control from one function to another.
 /// See MLIR's [CallOpInterface](https://mlir.llvm.org/docs/Interfaces/#callinterfaces).
 #[op_interface]
@@ -538,11 +1034,7 @@ pub trait CallOpInterface {
         let Some(callee_type_attr) = op.attributes.get::<TypeAttr>(&ATTR_KEY_CALLEE_TYPE) else {
             return verify_err!(op.loc(), CallOpInterfaceErr::CalleeTypeAttrNotFoundErr);
         };
-        if !callee_type_attr
-            .get_type(ctx)
-            .deref(ctx)
-            .is::<FunctionType>()
-        {
+        if !callee_type_attr.get_type(ctx).isa::<FunctionType>(ctx) {
             return verify_err!(op.loc(), CallOpInterfaceErr::CalleeTypeAttrIncorrectTypeErr);
         }
         Ok(())
@@ -556,6 +1048,18 @@ pub trait CallOpInterface {
     /// Get arguments passed to callee
     fn args(&self, ctx: &Context) -> Vec<Value>;
 
+    /// Replace the arguments passed to callee.
+    /// The default implementation assumes that the arguments
+    /// are the trailing operands of this operation.
+    fn set_args(&self, ctx: &mut Context, args: Vec<Value>) {
+        let num_args = self.args(ctx).len();
+        let op = self.operation();
+        let mut operands: Vec<_> = op.deref(ctx).operands().collect();
+        operands.truncate(operands.len() - num_args);
+        operands.extend(args);
+        Operation::set_operands(op, ctx, operands);
+    }
+
     /// Type of the callee
     fn callee_type(&self, ctx: &Context) -> TypePtr<FunctionType> {
         let self_op = self.operation().deref(ctx);
@@ -563,7 +1067,301 @@ pub trait CallOpInterface {
             .attributes
             .get::<TypeAttr>(&ATTR_KEY_CALLEE_TYPE)
             .unwrap();
-        TypePtr::from_ptr(ty_attr.get_type(ctx), ctx)
+        TypePtr::downcast(ctx, ty_attr.get_type(ctx))
             .expect("Incorrect callee type, not a FunctionType")
     }
+
+    /// Is this call marked (with [ATTR_KEY_TAIL_CALL]) as being in tail position?
+    fn is_tail_call(&self, ctx: &Context) -> bool {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&ATTR_KEY_TAIL_CALL)
+            .is_some()
+    }
+
+    /// Mark this call as being in tail position: Its results are
+    /// returned by the caller, with nothing else executed in between.
+    fn mark_tail_call(&self, ctx: &mut Context) {
+        self.operation()
+            .deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_TAIL_CALL, UnitAttr::new());
+    }
+}
+
+/// Key for the [OperandBundlesAttr] of an [OperandBundleInterface] op.
+pub static ATTR_KEY_OPERAND_BUNDLES: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_operand_bundles".try_into().unwrap());
+
+#[derive(Error, Debug)]
+pub enum OperandBundleInterfaceErr {
+    #[error("Operand bundles attribute must be an OperandBundlesAttr")]
+    NotOperandBundlesAttr,
+    #[error("Operand bundles have {needed} operands, but the operation has only {available}")]
+    NotEnoughOperands { needed: usize, available: usize },
+    #[error("Operand bundle tag \"{0}\" occurs more than once")]
+    DuplicateTag(String),
+}
+
+/// A tagged group of extra operands, attached to a call-like operation.
+/// Same as LLVM's [operand bundles](https://llvm.org/docs/LangRef.html#operand-bundles).
+#[derive(Clone, PartialEq, Eq)]
+pub struct OperandBundle {
+    pub tag: String,
+    pub operands: Vec<Value>,
+}
+
+impl OperandBundle {
+    /// Create a new [OperandBundle].
+    pub fn new(tag: &str, operands: Vec<Value>) -> Self {
+        OperandBundle {
+            tag: tag.to_string(),
+            operands,
+        }
+    }
+}
+
+/// An op that can have [OperandBundle]s, for example, to carry GC statepoints or
+/// deoptimization state on a call. The operands of the bundles are the trailing
+/// operands of the op, in the order of the bundles. The tags and sizes of the
+/// bundles are recorded in an [OperandBundlesAttr] with key [ATTR_KEY_OPERAND_BUNDLES].
+/// As in LLVM, a tag can occur at most once on an op.
+///
+/// Implementations must take care to exclude [bundle operands](Self::num_bundle_operands)
+/// when interpreting the other operands of the op.
+#[op_interface]
+pub trait OperandBundleInterface {
+    /// Get the tags and sizes of the bundles of this op.
+    fn operand_bundle_sizes(&self, ctx: &Context) -> Vec<(String, usize)> {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<OperandBundlesAttr>(&ATTR_KEY_OPERAND_BUNDLES)
+            .map(|bundles| bundles.0.clone())
+            .unwrap_or_default()
+    }
+
+    /// Total number of operands in the bundles of this op.
+    fn num_bundle_operands(&self, ctx: &Context) -> usize {
+        self.operand_bundle_sizes(ctx)
+            .iter()
+            .map(|(_, num_opds)| num_opds)
+            .sum()
+    }
+
+    /// Get the operand bundles of this op, in order.
+    fn operand_bundles(&self, ctx: &Context) -> Vec<OperandBundle> {
+        let op = self.operation().deref(ctx);
+        let mut opds = op
+            .operands()
+            .skip(op.num_operands() - self.num_bundle_operands(ctx));
+        self.operand_bundle_sizes(ctx)
+            .into_iter()
+            .map(|(tag, num_opds)| OperandBundle {
+                tag,
+                operands: opds.by_ref().take(num_opds).collect(),
+            })
+            .collect()
+    }
+
+    /// Get the operand bundle tagged `tag`, if there's one.
+    fn operand_bundle(&self, ctx: &Context, tag: &str) -> Option<OperandBundle> {
+        self.operand_bundles(ctx)
+            .into_iter()
+            .find(|bundle| bundle.tag == tag)
+    }
+
+    /// Get the operands of this op, excluding those of the bundles.
+    fn non_bundle_operands(&self, ctx: &Context) -> Vec<Value> {
+        let op = self.operation().deref(ctx);
+        op.operands()
+            .take(op.num_operands() - self.num_bundle_operands(ctx))
+            .collect()
+    }
+
+    /// Replace the operand bundles of this op with `bundles`.
+    fn set_operand_bundles(&self, ctx: &mut Context, bundles: Vec<OperandBundle>) {
+        let op = self.operation();
+        let mut operands = self.non_bundle_operands(ctx);
+        let mut sizes = Vec::with_capacity(bundles.len());
+        for bundle in bundles {
+            sizes.push((bundle.tag, bundle.operands.len()));
+            operands.extend(bundle.operands);
+        }
+        Operation::set_operands(op, ctx, operands);
+        let attributes = &mut op.deref_mut(ctx).attributes;
+        if sizes.is_empty() {
+            attributes.0.remove(&*ATTR_KEY_OPERAND_BUNDLES);
+        } else {
+            attributes.set(*ATTR_KEY_OPERAND_BUNDLES, OperandBundlesAttr::new(sizes));
+        }
+    }
+
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let op = op.operation().deref(ctx);
+        let Some(bundles) = op.attributes.0.get(&*ATTR_KEY_OPERAND_BUNDLES) else {
+            return Ok(());
+        };
+        let Some(bundles) = bundles.downcast_ref::<OperandBundlesAttr>() else {
+            return verify_err!(op.loc(), OperandBundleInterfaceErr::NotOperandBundlesAttr);
+        };
+        let needed = bundles.0.iter().map(|(_, num_opds)| num_opds).sum();
+        if needed > op.num_operands() {
+            return verify_err!(
+                op.loc(),
+                OperandBundleInterfaceErr::NotEnoughOperands {
+                    needed,
+                    available: op.num_operands()
+                }
+            );
+        }
+        let mut tags = FxHashSet::default();
+        if let Some((tag, _)) = bundles.0.iter().find(|(tag, _)| !tags.insert(tag)) {
+            return verify_err!(
+                op.loc(),
+                OperandBundleInterfaceErr::DuplicateTag(tag.clone())
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ForLoopInterfaceVerifyErr {
+    #[error("Loop must have a lower bound, an upper bound and a step as operands")]
+    MissingBounds,
+    #[error("Loop has {inits} initial values, but {args} loop carried block arguments")]
+    IterArgsMismatch { inits: usize, args: usize },
+    #[error("Loop has {inits} initial values, but {results} results")]
+    ResultsMismatch { inits: usize, results: usize },
+    #[error("Loop has {inits} initial values, but its body yields {yielded} values")]
+    YieldedMismatch { inits: usize, yielded: usize },
+}
+
+/// A counted loop, similar to MLIR's [scf.for](https://mlir.llvm.org/docs/Dialects/SCFDialect/#scffor-scfforop).
+///   - The operands are the lower bound, the (exclusive) upper bound and the step
+///     of the induction variable, followed by the initial values of the loop carried variables.
+///   - The single block of the body has the induction variable as its first argument,
+///     followed by the loop carried variables.
+///   - The operands of the body's terminator are the values
+///     of the loop carried variables for the next iteration.
+///   - The results are the final values of the loop carried variables.
+///
+/// See [loop_unroll](crate::transforms::loop_unroll).
+#[op_interface]
+pub trait ForLoopInterface: OneRegionInterface + SingleBlockRegionInterface {
+    /// Build (unlinked) [Operation]s computing `base + offset`, or just `offset`
+    /// if `base` is `None`, as a value of the induction variable's type.
+    /// Returns the [Operation]s, in order, and the computed value.
+    fn build_iv_value(
+        &self,
+        ctx: &mut Context,
+        base: Option<Value>,
+        offset: i64,
+    ) -> (Vec<Ptr<Operation>>, Value);
+
+    /// Lower bound of the induction variable.
+    fn lower_bound(&self, ctx: &Context) -> Value {
+        self.operation().deref(ctx).operand(0)
+    }
+
+    /// Upper bound (exclusive) of the induction variable.
+    fn upper_bound(&self, ctx: &Context) -> Value {
+        self.operation().deref(ctx).operand(1)
+    }
+
+    /// Step of the induction variable.
+    fn step(&self, ctx: &Context) -> Value {
+        self.operation().deref(ctx).operand(2)
+    }
+
+    /// Initial values of the loop carried variables.
+    fn init_args(&self, ctx: &Context) -> Vec<Value> {
+        self.operation().deref(ctx).operands().skip(3).collect()
+    }
+
+    /// The induction variable.
+    fn induction_var(&self, ctx: &Context) -> Value {
+        self.body(ctx, 0).deref(ctx).argument(0)
+    }
+
+    /// The loop carried variables, as seen in the body.
+    fn iter_args(&self, ctx: &Context) -> Vec<Value> {
+        self.body(ctx, 0).deref(ctx).arguments().skip(1).collect()
+    }
+
+    /// Terminator of the body, yielding values for the next iteration.
+    fn yield_op(&self, ctx: &Context) -> Ptr<Operation> {
+        self.body(ctx, 0)
+            .deref(ctx)
+            .tail()
+            .expect("Loop body must have a terminator")
+    }
+
+    /// The lower bound, upper bound and step, if they're all
+    /// [ConstantLikeInterface] integer constants.
+    fn constant_bounds(&self, ctx: &Context) -> Option<(i64, i64, i64)> {
+        let as_constant = |value: Value| -> Option<i64> {
+            let Value::OpResult { op: def_op, .. } = value else {
+                return None;
+            };
+            let def_op = Operation::op(def_op, ctx);
+            let value = op_cast::<dyn ConstantLikeInterface>(&*def_op)?.constant_value(ctx);
+            value.downcast_ref::<IntegerAttr>()?.as_i64(ctx).ok()
+        };
+        Some((
+            as_constant(self.lower_bound(ctx))?,
+            as_constant(self.upper_bound(ctx))?,
+            as_constant(self.step(ctx))?,
+        ))
+    }
+
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let self_op = op.operation().deref(ctx);
+        if self_op.num_operands() < 3 {
+            return verify_err!(op.loc(ctx), ForLoopInterfaceVerifyErr::MissingBounds);
+        }
+        let inits = self_op.num_operands() - 3;
+        let body = self_op.region(0).deref(ctx).head();
+        let Some(body) = body else {
+            return Ok(());
+        };
+        let args = body.deref(ctx).num_arguments();
+        if args != inits + 1 {
+            return verify_err!(
+                op.loc(ctx),
+                ForLoopInterfaceVerifyErr::IterArgsMismatch {
+                    inits,
+                    args: args.saturating_sub(1)
+                }
+            );
+        }
+        if self_op.num_results() != inits {
+            return verify_err!(
+                op.loc(ctx),
+                ForLoopInterfaceVerifyErr::ResultsMismatch {
+                    inits,
+                    results: self_op.num_results()
+                }
+            );
+        }
+        let yielded = body
+            .deref(ctx)
+            .tail()
+            .map_or(0, |term| term.deref(ctx).num_operands());
+        if yielded != inits {
+            return verify_err!(
+                op.loc(ctx),
+                ForLoopInterfaceVerifyErr::YieldedMismatch { inits, yielded }
+            );
+        }
+        Ok(())
+    }
 }
@@ -1,8 +1,9 @@
-use combine::{Parser, token};
-use pliron::derive::{def_op, derive_op_interface_impl};
+use combine::{Parser, attempt, optional, token};
+use pliron::derive::{def_op, derive_op_interface_impl, format_op};
 use thiserror::Error;
 
 use crate::{
+    attribute::AttributeDict,
     basic_block::BasicBlock,
     builtin::op_interfaces::ZeroResultInterface,
     common_traits::{Named, Verify},
@@ -10,8 +11,8 @@ use crate::{
     identifier::Identifier,
     impl_verify_succ, input_err,
     irfmt::{
-        parsers::{spaced, type_parser},
-        printers::op::{region, symb_op_header, typed_symb_op_header},
+        parsers::{spaced, symbol_ref_parser, type_parser},
+        printers::op::{region, results, symb_op_header, typed_symb_op_header},
     },
     linked_list::ContainsLinkedList,
     location::{Located, Location},
@@ -22,6 +23,7 @@ use crate::{
     region::Region,
     result::Result,
     r#type::{TypeObj, TypePtr, Typed},
+    value::Value,
     verify_err,
 };
 
@@ -29,8 +31,9 @@ use super::{
     attr_interfaces::TypedAttrInterface,
     attributes::TypeAttr,
     op_interfaces::{
-        self, IsolatedFromAboveInterface, OneRegionInterface, OneResultInterface,
-        SingleBlockRegionInterface, SymbolOpInterface, SymbolTableInterface, ZeroOpdInterface,
+        self, IsolatedFromAboveInterface, OneOpdInterface, OneRegionInterface, OneResultInterface,
+        PureInterface, SingleBlockRegionInterface, SymbolOpInterface, SymbolTableInterface,
+        ZeroOpdInterface,
     },
     types::{FunctionType, UnitType},
 };
@@ -94,14 +97,13 @@ impl Parsable for ModuleOp {
             vec![],
             0,
         );
-        let mut parser =
-            spaced(token('@').with(Identifier::parser(()))).and(spaced(Region::parser(op)));
+        let mut parser = spaced(symbol_ref_parser()).and(spaced(Region::parser(op)));
         parser
             .parse_stream(state_stream)
             .map(|(name, _region)| -> OpObj {
-                let op = Box::new(ModuleOp { op });
+                let op = ModuleOp { op };
                 op.set_symbol_name(state_stream.state.ctx, &name);
-                op
+                OpObj::new(op)
             })
             .into()
     }
@@ -136,6 +138,9 @@ impl ModuleOp {
 /// |-----|-------|-----|
 /// | [ATTR_KEY_SYM_NAME](super::op_interfaces::ATTR_KEY_SYM_NAME) | [IdentifierAttr](super::attributes::IdentifierAttr) | [SymbolOpInterface] |
 /// | [ATTR_KEY_FUNC_TYPE](func_op::ATTR_KEY_FUNC_TYPE) | [TypeAttr](super::attributes::TypeAttr) | N/A |
+///
+/// Any other attributes (such as those set by other dialects)
+/// are printed as an [AttributeDict] after the function type.
 #[def_op("builtin.func")]
 #[derive_op_interface_impl(
     OneRegionInterface,
@@ -170,9 +175,7 @@ impl FuncOp {
         {
             let opref = &mut *op.deref_mut(ctx);
             // Set function type attributes.
-            opref
-                .attributes
-                .set(func_op::ATTR_KEY_FUNC_TYPE.clone(), ty_attr);
+            opref.attributes.set(*func_op::ATTR_KEY_FUNC_TYPE, ty_attr);
         }
         let opop = FuncOp { op };
         opop.set_symbol_name(ctx, name);
@@ -219,6 +222,12 @@ impl Printable for FuncOp {
     ) -> core::fmt::Result {
         typed_symb_op_header(self).fmt(ctx, state, f)?;
         write!(f, " ")?;
+        let mut other_attrs = self.op.deref(ctx).attributes.clone();
+        other_attrs.0.remove(&*func_op::ATTR_KEY_FUNC_TYPE);
+        other_attrs.0.remove(&*op_interfaces::ATTR_KEY_SYM_NAME);
+        if !other_attrs.0.is_empty() {
+            write!(f, "{} ", other_attrs.print(ctx, state))?;
+        }
         region(self).fmt(ctx, state, f)?;
         Ok(())
     }
@@ -248,27 +257,29 @@ impl Parsable for FuncOp {
         );
 
         let mut parser = (
-            spaced(token('@').with(Identifier::parser(()))).skip(spaced(token(':'))),
+            spaced(symbol_ref_parser()).skip(spaced(token(':'))),
             spaced(type_parser()),
+            optional(attempt(spaced(AttributeDict::parser(())))),
             spaced(Region::parser(op)),
         );
 
         // Parse and build the function, providing name and type details.
         parser
             .parse_stream(state_stream)
-            .map(|(fname, fty, _region)| -> OpObj {
+            .map(|(fname, fty, other_attrs, _region)| -> OpObj {
                 let ctx = &mut state_stream.state.ctx;
                 {
                     let ty_attr = TypeAttr::new(fty);
                     let opref = &mut *op.deref_mut(ctx);
+                    if let Some(other_attrs) = other_attrs {
+                        opref.attributes = other_attrs;
+                    }
                     // Set function type attributes.
-                    opref
-                        .attributes
-                        .set(func_op::ATTR_KEY_FUNC_TYPE.clone(), ty_attr);
+                    opref.attributes.set(*func_op::ATTR_KEY_FUNC_TYPE, ty_attr);
                 }
-                let opop = Box::new(FuncOp { op });
+                let opop = FuncOp { op };
                 opop.set_symbol_name(ctx, &fname);
-                opop
+                OpObj::new(opop)
             })
             .into()
     }
@@ -282,7 +293,7 @@ impl Verify for FuncOp {
     fn verify(&self, ctx: &Context) -> Result<()> {
         let op = &*self.operation().deref(ctx);
         let ty = self.get_type(ctx);
-        if !(ty.deref(ctx).is::<FunctionType>()) {
+        if !ty.isa::<FunctionType>(ctx) {
             return verify_err!(op.loc(), FuncOpTypeErr);
         }
         Ok(())
@@ -301,14 +312,14 @@ impl Printable for ForwardRefOp {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
         write!(
             f,
             "{} = {}",
-            self.result(ctx).unique_name(ctx),
-            self.opid().disp(ctx),
+            results(self).print(ctx, state),
+            self.opid().print(ctx, state),
         )
     }
 }
@@ -354,8 +365,45 @@ impl ForwardRefOp {
     }
 }
 
+/// A cast of a value to another type, inserted by [dialect conversion](crate::conversion)
+/// where converted and unconverted values meet. It has no semantics of its own,
+/// and should be gone once the conversion is complete.
+///
+/// See MLIR's [builtin.unrealized_conversion_cast](https://mlir.llvm.org/docs/Dialects/Builtin/#builtinunrealized_conversion_cast-unrealizedconversioncastop).
+///
+/// ### Operands
+/// | operand | description |
+/// |-----|-------|
+/// | `input` | any type |
+///
+/// ### Result(s):
+/// | result | description |
+/// |-----|-------|
+/// | `res` | any type |
+#[def_op("builtin.unrealized_conversion_cast")]
+#[format_op("$0 ` to ` type($0)")]
+#[derive_op_interface_impl(OneOpdInterface, OneResultInterface, PureInterface)]
+pub struct UnrealizedConversionCastOp;
+impl_verify_succ!(UnrealizedConversionCastOp);
+
+impl UnrealizedConversionCastOp {
+    /// Create a new [UnrealizedConversionCastOp], casting `input` to `res_ty`.
+    pub fn new(ctx: &mut Context, input: Value, res_ty: Ptr<TypeObj>) -> Self {
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            vec![input],
+            vec![],
+            0,
+        );
+        UnrealizedConversionCastOp { op }
+    }
+}
+
 pub fn register(ctx: &mut Context) {
     ModuleOp::register(ctx, ModuleOp::parser_fn);
     FuncOp::register(ctx, FuncOp::parser_fn);
     ForwardRefOp::register(ctx, ForwardRefOp::parser_fn);
+    UnrealizedConversionCastOp::register(ctx, UnrealizedConversionCastOp::parser_fn);
 }
@@ -0,0 +1,352 @@
+//! [Serializable] builtin [Attribute](crate::attribute::Attribute)s and
+//! [Type](crate::r#type::Type)s.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    attribute::AttrObj,
+    builtin::{
+        attr_interfaces::TypedAttrInterface,
+        attributes::{
+            DictAttr, FloatAttr, IdentifierAttr, IntegerAttr, OperandBundlesAttr, StringAttr,
+            TypeAttr, UnitAttr, VecAttr,
+        },
+        types::{FloatType, FunctionType, IntegerType, Signedness, UnitType},
+    },
+    context::{Context, Ptr},
+    identifier::Identifier,
+    impl_serializable_via_serde,
+    serializable::{
+        Serializable, SerializableType, deserialize_repr, register_attr, register_type,
+    },
+    r#type::{TypeObj, TypePtr},
+    utils::{apfloat::APFloat, apfloat::FloatSemantics, apint::APInt},
+};
+
+impl_serializable_via_serde!(IdentifierAttr);
+impl_serializable_via_serde!(StringAttr);
+impl_serializable_via_serde!(UnitAttr);
+impl_serializable_via_serde!(OperandBundlesAttr);
+
+#[derive(Serialize, Deserialize)]
+struct IntegerTypeRepr {
+    width: u32,
+    signedness: Signedness,
+}
+
+impl SerializableType for IntegerType {
+    fn serialize_in<S: Serializer>(
+        &self,
+        _ctx: &Context,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let repr = IntegerTypeRepr {
+            width: self.width(),
+            signedness: self.signedness(),
+        };
+        repr.serialize(serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<TypePtr<Self>, D::Error> {
+        deserialize_repr(ctx, deserializer, |ctx, repr: IntegerTypeRepr| {
+            if repr.width == 0 {
+                return Err("Integer types must have a non-zero width".to_string());
+            }
+            Ok(IntegerType::get(ctx, repr.width, repr.signedness))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FloatTypeRepr {
+    semantics: FloatSemantics,
+}
+
+impl SerializableType for FloatType {
+    fn serialize_in<S: Serializer>(
+        &self,
+        _ctx: &Context,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let repr = FloatTypeRepr {
+            semantics: self.semantics(),
+        };
+        repr.serialize(serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<TypePtr<Self>, D::Error> {
+        deserialize_repr(ctx, deserializer, |ctx, repr: FloatTypeRepr| {
+            Ok(FloatType::get(ctx, repr.semantics))
+        })
+    }
+}
+
+impl SerializableType for FunctionType {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.inputs().clone(), self.results().clone()).serialize_in(ctx, serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<TypePtr<Self>, D::Error> {
+        let (inputs, results) =
+            <(Vec<Ptr<TypeObj>>, Vec<Ptr<TypeObj>>)>::deserialize_in(ctx, deserializer)?;
+        Ok(FunctionType::get(ctx, inputs, results))
+    }
+}
+
+impl SerializableType for UnitType {
+    fn serialize_in<S: Serializer>(
+        &self,
+        _ctx: &Context,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<TypePtr<Self>, D::Error> {
+        <()>::deserialize(deserializer)?;
+        Ok(UnitType::get(ctx))
+    }
+}
+
+/// The value is in decimal, and is negative only for signed integers.
+#[derive(Serialize, Deserialize)]
+struct IntegerAttrRepr {
+    ty: IntegerTypeRepr,
+    value: String,
+}
+
+impl Serializable for IntegerAttr {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let ty = TypePtr::<IntegerType>::from_ptr(TypedAttrInterface::get_type(self), ctx)
+            .expect("IntegerAttr must have an IntegerType");
+        let ty = ty.deref(ctx);
+        let signed = ty.signedness() == Signedness::Signed;
+        let repr = IntegerAttrRepr {
+            ty: IntegerTypeRepr {
+                width: ty.width(),
+                signedness: ty.signedness(),
+            },
+            value: APInt::from(self.clone()).to_string_decimal(signed),
+        };
+        repr.serialize(serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserialize_repr(ctx, deserializer, |ctx, repr: IntegerAttrRepr| {
+            let value = APInt::from_str(&repr.value, repr.ty.width as usize, 10)
+                .map_err(|err| err.to_string())?;
+            let ty = IntegerType::get(ctx, repr.ty.width, repr.ty.signedness);
+            Ok(IntegerAttr::new(ty, value))
+        })
+    }
+}
+
+/// The value is the bits of the float.
+#[derive(Serialize, Deserialize)]
+struct FloatAttrRepr {
+    ty: FloatTypeRepr,
+    bits: u64,
+}
+
+impl Serializable for FloatAttr {
+    fn serialize_in<S: Serializer>(
+        &self,
+        _ctx: &Context,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let value = APFloat::from(self.clone());
+        let repr = FloatAttrRepr {
+            ty: FloatTypeRepr {
+                semantics: value.semantics(),
+            },
+            bits: value.to_bits(),
+        };
+        repr.serialize(serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserialize_repr(ctx, deserializer, |ctx, repr: FloatAttrRepr| {
+            let semantics = repr.ty.semantics;
+            if semantics.width() < 64 && repr.bits >> semantics.width() != 0 {
+                return Err(format!("Bits {} don't fit in {semantics}", repr.bits));
+            }
+            let ty = FloatType::get(ctx, semantics);
+            Ok(FloatAttr::new(ty, APFloat::from_bits(repr.bits, semantics)))
+        })
+    }
+}
+
+/// Entries are serialized as `(key, value)` pairs, sorted by key.
+impl Serializable for DictAttr {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(Identifier, AttrObj)> = self
+            .iter_sorted()
+            .map(|(key, value)| (*key, value.clone()))
+            .collect();
+        entries.serialize_in(ctx, serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Ok(DictAttr::new(Vec::deserialize_in(ctx, deserializer)?))
+    }
+}
+
+impl Serializable for VecAttr {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_in(ctx, serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Ok(VecAttr::new(Vec::deserialize_in(ctx, deserializer)?))
+    }
+}
+
+impl Serializable for TypeAttr {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        TypedAttrInterface::get_type(self).serialize_in(ctx, serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Ok(TypeAttr::new(Ptr::<TypeObj>::deserialize_in(
+            ctx,
+            deserializer,
+        )?))
+    }
+}
+
+/// Register the builtin attributes and types for serialization.
+pub(super) fn register(ctx: &mut Context) {
+    register_type::<IntegerType>(ctx);
+    register_type::<FloatType>(ctx);
+    register_type::<FunctionType>(ctx);
+    register_type::<UnitType>(ctx);
+
+    register_attr::<IdentifierAttr>(ctx);
+    register_attr::<StringAttr>(ctx);
+    register_attr::<IntegerAttr>(ctx);
+    register_attr::<FloatAttr>(ctx);
+    register_attr::<DictAttr>(ctx);
+    register_attr::<VecAttr>(ctx);
+    register_attr::<UnitAttr>(ctx);
+    register_attr::<OperandBundlesAttr>(ctx);
+    register_attr::<TypeAttr>(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use serde::de::DeserializeSeed;
+
+    use crate::{
+        attribute::AttrObj,
+        builtin::{
+            self,
+            attributes::{
+                DictAttr, FloatAttr, IdentifierAttr, IntegerAttr, OperandBundlesAttr, StringAttr,
+                TypeAttr, UnitAttr, VecAttr,
+            },
+            types::{FloatType, FunctionType, IntegerType, Signedness, UnitType},
+        },
+        context::Context,
+        printable::Printable,
+        serializable::Serializable,
+        utils::{apfloat::FloatSemantics, apint::APInt},
+    };
+
+    fn deserialize(ctx: &mut Context, json: &str) -> serde_json::Result<AttrObj> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        AttrObj::seed(ctx).deserialize(&mut deserializer)
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let ctx = &mut Context::new();
+        builtin::register(ctx);
+
+        let i8_ty = IntegerType::get(ctx, 8, Signedness::Signed);
+        let f32_ty = FloatType::get(ctx, FloatSemantics::Single);
+        let unit_ty = UnitType::get(ctx);
+        let func_ty =
+            FunctionType::get(ctx, vec![i8_ty.into(), f32_ty.into()], vec![unit_ty.into()]);
+        let minus_five = APInt::from_i8(-5, 8.try_into().unwrap());
+        let elems: Vec<AttrObj> = vec![
+            StringAttr::new("s".into()).into(),
+            UnitAttr::new().into(),
+            TypeAttr::new(func_ty.into()).into(),
+        ];
+        let attr: AttrObj = DictAttr::new(vec![
+            (
+                "int".try_into().unwrap(),
+                IntegerAttr::new(i8_ty, minus_five).into(),
+            ),
+            (
+                "float".try_into().unwrap(),
+                FloatAttr::from_f32(ctx, f32_ty, 1.5).into(),
+            ),
+            ("vec".try_into().unwrap(), VecAttr::new(elems).into()),
+            (
+                "id".try_into().unwrap(),
+                IdentifierAttr::new("x".try_into().unwrap()).into(),
+            ),
+            (
+                "bundles".try_into().unwrap(),
+                OperandBundlesAttr::new(vec![("deopt".into(), 2)]).into(),
+            ),
+        ])
+        .into();
+
+        let json = serde_json::to_string(&attr.with_ctx(ctx)).unwrap();
+        expect![[r#"{"id":"builtin.dict","value":[["bundles",{"id":"builtin.operand_bundles","value":[["deopt",2]]}],["float",{"id":"builtin.float","value":{"ty":{"semantics":"Single"},"bits":1069547520}}],["id",{"id":"builtin.identifier","value":"x"}],["int",{"id":"builtin.integer","value":{"ty":{"width":8,"signedness":"Signed"},"value":"-5"}}],["vec",{"id":"builtin.vec","value":[{"id":"builtin.string","value":"s"},{"id":"builtin.unit","value":null},{"id":"builtin.type","value":{"id":"builtin.function","value":[[{"id":"builtin.integer","value":{"width":8,"signedness":"Signed"}},{"id":"builtin.float","value":{"semantics":"Single"}}],[{"id":"builtin.unit","value":null}]]}}]}]]}"#]]
+        .assert_eq(&json);
+
+        // Types are created, as needed, in the context deserialized into.
+        let ctx2 = &mut Context::new();
+        builtin::register(ctx2);
+        let attr2 = deserialize(ctx2, &json).unwrap();
+        assert_eq!(attr.disp(ctx).to_string(), attr2.disp(ctx2).to_string());
+        let json2 = serde_json::to_string(&attr2.with_ctx(ctx2)).unwrap();
+        assert_eq!(json, json2);
+
+        let err = deserialize(ctx2, r#"{"id":"builtin.foo","value":0}"#).unwrap_err();
+        expect!["Attribute builtin.foo can't be deserialized at line 1 column 19"]
+            .assert_eq(&err.to_string());
+        let err = deserialize(ctx2, r#"{"value":0,"id":"builtin.unit"}"#).unwrap_err();
+        expect!["missing field `id` at line 1 column 8"].assert_eq(&err.to_string());
+        let err = deserialize(
+            ctx2,
+            r#"{"id":"builtin.integer","value":{"ty":{"width":4,"signedness":"Signless"},"value":"16"}}"#,
+        )
+        .unwrap_err();
+        expect![[r#"
+            Compilation error: invalid argument.
+            APInt error: Overflow at line 1 column 88"#]]
+        .assert_eq(&err.to_string());
+    }
+}
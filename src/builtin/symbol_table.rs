@@ -0,0 +1,190 @@
+//! Symbol tables, and resolving symbols in nested scopes.
+//!
+//! An [Op](crate::op::Op) implementing [SymbolTableInterface] (such as a
+//! [ModuleOp](super::ops::ModuleOp)) defines a scope for the symbols defined
+//! ([SymbolOpInterface]) by the operations directly in its body. Symbol tables
+//! may be nested, and a symbol is resolved in the closest enclosing symbol table
+//! that defines it (see [lookup_symbol_in_scope]), so inner definitions shadow
+//! outer ones.
+//!
+//! Looking up a symbol via [SymbolTableInterface::lookup] is a linear search.
+//! A [SymbolTable] caches the symbols of a table for repeated lookups, and keeps
+//! the cache up to date as long as symbols are inserted, removed and renamed
+//! through it.
+
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    arg_err, arg_err_noloc,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{op_cast, op_impls},
+    operation::Operation,
+    result::Result,
+};
+
+use super::op_interfaces::{SymbolOpInterface, SymbolTableInterface, SymbolTableInterfaceErr};
+
+#[derive(Error, Debug)]
+pub enum SymbolTableErr {
+    #[error("Op {0} is not a symbol table")]
+    NotASymbolTable(String),
+    #[error("Op {0} does not define a symbol")]
+    NotASymbol(String),
+}
+
+/// The closest operation enclosing `op` (excluding `op` itself)
+/// that is a symbol table, if any.
+pub fn nearest_symbol_table(ctx: &Context, op: Ptr<Operation>) -> Option<Ptr<Operation>> {
+    let mut cur = op;
+    loop {
+        cur = cur
+            .deref(ctx)
+            .container()
+            .and_then(|block| block.deref(ctx).container())
+            .map(|region| region.deref(ctx).parent_op())?;
+        if op_impls::<dyn SymbolTableInterface>(&*Operation::op(cur, ctx)) {
+            return Some(cur);
+        }
+    }
+}
+
+/// Resolve `sym`, as used by `from`: look it up in the symbol tables
+/// enclosing `from`, innermost first. `from` itself isn't searched,
+/// even if it is a symbol table.
+pub fn lookup_symbol_in_scope(
+    ctx: &Context,
+    from: Ptr<Operation>,
+    sym: &Identifier,
+) -> Option<Ptr<Operation>> {
+    let mut table = nearest_symbol_table(ctx, from);
+    while let Some(table_op) = table {
+        let table_obj = Operation::op(table_op, ctx);
+        let table_intf = op_cast::<dyn SymbolTableInterface>(&*table_obj)
+            .expect("Symbol table must implement SymbolTableInterface");
+        if let Some(def) = table_intf.lookup(ctx, sym) {
+            return Some(def);
+        }
+        table = nearest_symbol_table(ctx, table_op);
+    }
+    None
+}
+
+/// The symbol defined by `op`, if it's a [SymbolOpInterface] operation.
+fn symbol_of(ctx: &Context, op: Ptr<Operation>) -> Option<Identifier> {
+    op_cast::<dyn SymbolOpInterface>(&*Operation::op(op, ctx)).map(|sym| sym.symbol_name(ctx))
+}
+
+/// A cache of the symbols defined in a [SymbolTableInterface] operation.
+/// See [module](self) documentation.
+pub struct SymbolTable {
+    table_op: Ptr<Operation>,
+    symbols: FxHashMap<Identifier, Ptr<Operation>>,
+}
+
+impl SymbolTable {
+    /// Collect the symbols defined in `table_op`.
+    /// Fails if it isn't a symbol table, or if a symbol is defined more than once.
+    pub fn new(ctx: &Context, table_op: Ptr<Operation>) -> Result<Self> {
+        let table_obj = Operation::op(table_op, ctx);
+        let Some(table_intf) = op_cast::<dyn SymbolTableInterface>(&*table_obj) else {
+            return arg_err!(
+                table_op.deref(ctx).loc(),
+                SymbolTableErr::NotASymbolTable(table_op.deref(ctx).opid().to_string())
+            );
+        };
+        let mut symbols = FxHashMap::default();
+        for op in table_intf.body(ctx, 0).deref(ctx).iter(ctx) {
+            let Some(sym) = symbol_of(ctx, op) else {
+                continue;
+            };
+            if symbols.insert(sym, op).is_some() {
+                return arg_err!(
+                    op.deref(ctx).loc(),
+                    SymbolTableInterfaceErr::SymbolRedefined(sym.to_string())
+                );
+            }
+        }
+        Ok(SymbolTable { table_op, symbols })
+    }
+
+    /// The symbol table operation.
+    pub fn table_op(&self) -> Ptr<Operation> {
+        self.table_op
+    }
+
+    /// The operation defining `sym` in this table, if any.
+    pub fn lookup(&self, sym: &Identifier) -> Option<Ptr<Operation>> {
+        self.symbols.get(sym).copied()
+    }
+
+    /// Is `sym` defined in this table?
+    pub fn contains(&self, sym: &Identifier) -> bool {
+        self.symbols.contains_key(sym)
+    }
+
+    /// The number of symbols defined in this table.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Are there no symbols defined in this table?
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Insert `op`, a [SymbolOpInterface] operation, at the end of this table.
+    /// If `op` is already linked, it is moved. Fails, without modifying the IR,
+    /// if the symbol of `op` is already defined (by a different operation).
+    pub fn insert(&mut self, ctx: &Context, op: Ptr<Operation>) -> Result<()> {
+        let Some(sym) = symbol_of(ctx, op) else {
+            return arg_err!(
+                op.deref(ctx).loc(),
+                SymbolTableErr::NotASymbol(op.deref(ctx).opid().to_string())
+            );
+        };
+        if self.lookup(&sym).is_some_and(|existing| existing != op) {
+            return arg_err!(
+                op.deref(ctx).loc(),
+                SymbolTableInterfaceErr::SymbolRedefined(sym.to_string())
+            );
+        }
+        if op.is_linked(ctx) {
+            op.unlink(ctx);
+        }
+        let table_obj = Operation::op(self.table_op, ctx);
+        let body = op_cast::<dyn SymbolTableInterface>(&*table_obj)
+            .expect("Symbol table must implement SymbolTableInterface")
+            .body(ctx, 0);
+        op.insert_at_back(body, ctx);
+        self.symbols.insert(sym, op);
+        Ok(())
+    }
+
+    /// Unlink the operation defining `sym` from this table, and return it.
+    /// The operation isn't erased, and the uses of `sym` aren't updated.
+    pub fn remove(&mut self, ctx: &Context, sym: &Identifier) -> Option<Ptr<Operation>> {
+        let op = self.symbols.remove(sym)?;
+        op.unlink(ctx);
+        Some(op)
+    }
+
+    /// Rename the symbol `from` to `to`, updating all of its uses
+    /// inside this table (see [SymbolTableInterface::rename_symbol]).
+    pub fn rename(&mut self, ctx: &mut Context, from: &Identifier, to: &Identifier) -> Result<()> {
+        if self.lookup(from).is_none() {
+            return arg_err_noloc!(SymbolTableInterfaceErr::SymbolNotFound(from.to_string()));
+        }
+        let table_obj = Operation::op(self.table_op, ctx);
+        op_cast::<dyn SymbolTableInterface>(&*table_obj)
+            .expect("Symbol table must implement SymbolTableInterface")
+            .rename_symbol(ctx, from, to)?;
+        if let Some(op) = self.symbols.remove(from) {
+            self.symbols.insert(*to, op);
+        }
+        Ok(())
+    }
+}
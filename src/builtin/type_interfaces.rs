@@ -0,0 +1,372 @@
+use std::{fmt::Display, num::NonZero, sync::LazyLock};
+
+use pliron::derive::{type_interface, type_interface_impl};
+use thiserror::Error;
+
+use crate::{
+    builtin::{attributes::StringAttr, types::IntegerType},
+    context::{Context, Ptr},
+    identifier::Identifier,
+    input_err, input_err_noloc, input_error_noloc,
+    linked_list::LinkedList,
+    location::Located,
+    operation::Operation,
+    printable::Printable,
+    result::Result,
+    r#type::{Type, TypeObj},
+    utils::apint::APInt,
+};
+
+/// Key for the [DataLayout] attribute of an operation (typically a module).
+/// The value is a [StringAttr] with the layout [specification](DataLayout::parse).
+pub static ATTR_KEY_DATA_LAYOUT: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_data_layout".try_into().unwrap());
+
+#[derive(Error, Debug)]
+pub enum DataLayoutErr {
+    #[error("Malformed data layout specification \"{0}\"")]
+    Malformed(String),
+    #[error("Alignment must be a non-zero power of two number of bytes, but is {0} bits")]
+    BadAlign(u64),
+    #[error("Data layout attribute must be a string")]
+    NotAString,
+    #[error("An integer of {width} bits is stored in {expected} bytes, but {found} were given")]
+    ByteLengthMismatch {
+        width: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Order of the bytes of a value in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first.
+    #[default]
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+/// Sizes and alignments (in bytes) of the primitive types on a target.
+/// Layouts are specified using (a subset of) the syntax of
+/// LLVM's [data layout strings](https://llvm.org/docs/LangRef.html#data-layout).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataLayout {
+    endianness: Endianness,
+    pointer_size: u64,
+    pointer_align: u64,
+    /// ABI alignments of integers, keyed (and sorted) by their width in bits.
+    int_aligns: Vec<(u32, u64)>,
+}
+
+impl Default for DataLayout {
+    /// The layout of common (little-endian) 64-bit targets:
+    /// `e-p:64:64-i1:8-i8:8-i16:16-i32:32-i64:64-i128:128`.
+    fn default() -> Self {
+        DataLayout {
+            endianness: Endianness::Little,
+            pointer_size: 8,
+            pointer_align: 8,
+            int_aligns: vec![(1, 1), (8, 1), (16, 2), (32, 4), (64, 8), (128, 16)],
+        }
+    }
+}
+
+impl DataLayout {
+    /// Parse a data layout specification, overriding the [default](DataLayout::default).
+    /// Only the endianness, pointer (in address space 0) and integer specifications are
+    /// interpreted, i.e., `e`, `E`, `p[0]:<size>:<abi>[:...]` and `i<size>:<abi>[:...]`.
+    /// Other specifications are ignored.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut layout = DataLayout::default();
+        for component in spec.split('-').filter(|component| !component.is_empty()) {
+            let malformed = || input_error_noloc!(DataLayoutErr::Malformed(component.to_string()));
+            let mut fields = component.split(':');
+            let head = fields.next().unwrap_or_default();
+            let mut next_bits = || -> Result<u64> {
+                fields
+                    .next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(malformed)
+            };
+            if head == "e" || head == "E" {
+                if fields.next().is_some() {
+                    return Err(malformed());
+                }
+                layout.endianness = if head == "e" {
+                    Endianness::Little
+                } else {
+                    Endianness::Big
+                };
+            } else if head == "p" || head == "p0" {
+                let size = next_bits()?;
+                let align = next_bits()?;
+                if size == 0 || !size.is_multiple_of(8) {
+                    return Err(malformed());
+                }
+                layout.pointer_size = size / 8;
+                layout.pointer_align = Self::align_in_bytes(align)?;
+            } else if let Some(width) = head.strip_prefix('i') {
+                let width: u32 = width.parse().map_err(|_| malformed())?;
+                let align = Self::align_in_bytes(next_bits()?)?;
+                match layout.int_aligns.binary_search_by_key(&width, |(w, _)| *w) {
+                    Ok(idx) => layout.int_aligns[idx].1 = align,
+                    Err(idx) => layout.int_aligns.insert(idx, (width, align)),
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    fn align_in_bytes(bits: u64) -> Result<u64> {
+        if !bits.is_multiple_of(8) || !(bits / 8).is_power_of_two() {
+            return input_err_noloc!(DataLayoutErr::BadAlign(bits));
+        }
+        Ok(bits / 8)
+    }
+
+    /// Get the [DataLayout] of the closest (starting from `op` itself) operation,
+    /// enclosing `op`, that has an [ATTR_KEY_DATA_LAYOUT] attribute.
+    /// If there's no such operation, the [default](DataLayout::default) layout is returned.
+    pub fn of(ctx: &Context, op: Ptr<Operation>) -> Result<Self> {
+        let mut op = Some(op);
+        while let Some(cur_op) = op {
+            let cur_op_ref = cur_op.deref(ctx);
+            if let Some(spec) = cur_op_ref.attributes.0.get(&*ATTR_KEY_DATA_LAYOUT) {
+                let Some(spec) = spec.downcast_ref::<StringAttr>() else {
+                    return input_err!(cur_op_ref.loc(), DataLayoutErr::NotAString);
+                };
+                return Self::parse(&String::from(spec.clone())).map_err(|mut err| {
+                    err.set_loc(cur_op_ref.loc());
+                    err
+                });
+            }
+            op = cur_op_ref
+                .container()
+                .and_then(|block| block.deref(ctx).container())
+                .map(|region| region.deref(ctx).parent_op());
+        }
+        Ok(DataLayout::default())
+    }
+
+    /// Set the layout of `op` (typically a module) to `self`.
+    pub fn set(&self, ctx: &Context, op: Ptr<Operation>) {
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_DATA_LAYOUT, StringAttr::new(self.to_string()));
+    }
+
+    /// Order of the bytes of values in memory.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// The bytes of `value`, in the order they're stored in memory.
+    /// As in LLVM, an integer is stored in the least number of bytes that fit it,
+    /// zero extended if its width isn't a multiple of 8 (so `i1` is one byte).
+    pub fn int_to_bytes(&self, value: &APInt) -> Vec<u8> {
+        let mut bytes = value.to_le_bytes();
+        if self.endianness == Endianness::Big {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    /// The integer of `width` bits stored in memory as `bytes`. See [int_to_bytes](Self::int_to_bytes).
+    /// Fails if there are more or fewer bytes than an integer of `width` bits is stored in.
+    pub fn int_from_bytes(&self, bytes: &[u8], width: NonZero<usize>) -> Result<APInt> {
+        let expected = width.get().div_ceil(8);
+        if bytes.len() != expected {
+            return input_err_noloc!(DataLayoutErr::ByteLengthMismatch {
+                width: width.get(),
+                expected,
+                found: bytes.len(),
+            });
+        }
+        let mut bytes = bytes.to_vec();
+        if self.endianness == Endianness::Big {
+            bytes.reverse();
+        }
+        Ok(APInt::from_le_bytes(&bytes, width))
+    }
+
+    /// Size of a pointer, in bytes.
+    pub fn pointer_size(&self) -> u64 {
+        self.pointer_size
+    }
+
+    /// ABI alignment of a pointer, in bytes.
+    pub fn pointer_align(&self) -> u64 {
+        self.pointer_align
+    }
+
+    /// ABI alignment, in bytes, of an integer of `width` bits. As in LLVM, if
+    /// the alignment of `width` isn't specified, that of the next larger specified
+    /// width is used, or that of the largest specified width if there's none larger.
+    pub fn int_align(&self, width: u32) -> u64 {
+        self.int_aligns
+            .iter()
+            .find(|(w, _)| *w >= width)
+            .or(self.int_aligns.last())
+            .map_or(1, |(_, align)| *align)
+    }
+}
+
+impl Display for DataLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let endianness = match self.endianness {
+            Endianness::Little => "e",
+            Endianness::Big => "E",
+        };
+        write!(
+            f,
+            "{}-p:{}:{}",
+            endianness,
+            self.pointer_size * 8,
+            self.pointer_align * 8
+        )?;
+        for (width, align) in &self.int_aligns {
+            write!(f, "-i{}:{}", width, align * 8)?;
+        }
+        Ok(())
+    }
+}
+
+/// Round `size` up to a multiple of `align`.
+pub fn align_to(size: u64, align: u64) -> u64 {
+    size.div_ceil(align) * align
+}
+
+#[derive(Error, Debug)]
+#[error("Type {0} has no size")]
+pub struct UnsizedTypeErr(pub String);
+
+/// [Type]s that have a size and an alignment in memory.
+#[type_interface]
+pub trait SizedTypeInterface {
+    /// Get the (allocation) size and the ABI alignment, in bytes, of this type.
+    /// As in LLVM, the size is a multiple of the alignment, and is the distance
+    /// between consecutive elements of this type in an array.
+    /// Fails if the type (for example, an aggregate containing an opaque type) has no size.
+    fn size_and_align(&self, ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)>;
+
+    fn verify(_type: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Get the size and alignment, in bytes, of `ty`. Fails if `ty` has no size,
+/// i.e., it isn't a [SizedTypeInterface] type or the interface reports so.
+pub fn size_and_align(ctx: &Context, ty: Ptr<TypeObj>, layout: &DataLayout) -> Result<(u64, u64)> {
+    let Some(sized_ty) = ty.cast::<dyn SizedTypeInterface>(ctx) else {
+        return input_err_noloc!(UnsizedTypeErr(ty.disp(ctx).to_string()));
+    };
+    sized_ty.size_and_align(ctx, layout)
+}
+
+/// Get the size, in bytes, of `ty`. See [size_and_align].
+pub fn size_of(ctx: &Context, ty: Ptr<TypeObj>, layout: &DataLayout) -> Result<u64> {
+    size_and_align(ctx, ty, layout).map(|(size, _)| size)
+}
+
+/// Get the alignment, in bytes, of `ty`. See [size_and_align].
+pub fn align_of(ctx: &Context, ty: Ptr<TypeObj>, layout: &DataLayout) -> Result<u64> {
+    size_and_align(ctx, ty, layout).map(|(_, align)| align)
+}
+
+#[type_interface_impl]
+impl SizedTypeInterface for IntegerType {
+    fn size_and_align(&self, _ctx: &Context, layout: &DataLayout) -> Result<(u64, u64)> {
+        let align = layout.int_align(self.width());
+        let store_size = u64::from(self.width()).div_ceil(8);
+        Ok((align_to(store_size, align), align))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builtin::{
+            self,
+            ops::ModuleOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::Context,
+        op::Op,
+        utils::apint::{APInt, bw},
+    };
+
+    use super::{DataLayout, Endianness, align_of, size_and_align, size_of};
+
+    #[test]
+    fn data_layout_spec() {
+        let layout =
+            DataLayout::parse("e-m:e-p270:32:32-p:32:32-i64:32-i24:32-n8:16:32-S128").unwrap();
+        assert_eq!((layout.pointer_size(), layout.pointer_align()), (4, 4));
+        assert_eq!(layout.int_align(64), 4);
+        assert_eq!(layout.int_align(24), 4);
+        assert_eq!(layout.int_align(20), 4);
+        assert_eq!(layout.int_align(256), 16);
+        assert_eq!(
+            layout.to_string(),
+            "e-p:32:32-i1:8-i8:8-i16:16-i24:32-i32:32-i64:32-i128:128"
+        );
+        assert_eq!(DataLayout::parse(&layout.to_string()).unwrap(), layout);
+
+        assert!(DataLayout::parse("i64").is_err());
+        assert!(DataLayout::parse("i64:24").is_err());
+        assert!(DataLayout::parse("p:64:sixty_four").is_err());
+        assert!(DataLayout::parse("E:64").is_err());
+    }
+
+    #[test]
+    fn int_bytes() {
+        let little = DataLayout::default();
+        let big = DataLayout::parse("E-p:32:32").unwrap();
+        assert_eq!(big.endianness(), Endianness::Big);
+        assert_eq!(
+            big.to_string(),
+            "E-p:32:32-i1:8-i8:8-i16:16-i32:32-i64:64-i128:128"
+        );
+
+        let value = APInt::from_u32(0x0102_0304, bw(32));
+        assert_eq!(little.int_to_bytes(&value), vec![4, 3, 2, 1]);
+        assert_eq!(big.int_to_bytes(&value), vec![1, 2, 3, 4]);
+        assert_eq!(big.int_from_bytes(&[1, 2, 3, 4], bw(32)).unwrap(), value);
+        assert_eq!(little.int_from_bytes(&[4, 3, 2, 1], bw(32)).unwrap(), value);
+
+        // Integers are stored in whole bytes, zero extended.
+        let value = APInt::from_i32(-1, bw(12));
+        assert_eq!(big.int_to_bytes(&value), vec![0x0f, 0xff]);
+        assert_eq!(big.int_from_bytes(&[0x0f, 0xff], bw(12)).unwrap(), value);
+        assert!(big.int_from_bytes(&[0xff], bw(12)).is_err());
+    }
+
+    #[test]
+    fn integer_sizes() {
+        let ctx = &mut Context::new();
+        builtin::register(ctx);
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless).into();
+        let i24_ty = IntegerType::get(ctx, 24, Signedness::Signed).into();
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Unsigned).into();
+        let fn_ty = FunctionType::get(ctx, vec![], vec![]).into();
+
+        let layout = DataLayout::of(ctx, module.operation()).unwrap();
+        assert_eq!(layout, DataLayout::default());
+        assert_eq!(size_and_align(ctx, i1_ty, &layout).unwrap(), (1, 1));
+        assert_eq!(size_and_align(ctx, i24_ty, &layout).unwrap(), (4, 4));
+        assert_eq!(size_and_align(ctx, i64_ty, &layout).unwrap(), (8, 8));
+        assert!(size_of(ctx, fn_ty, &layout).is_err());
+
+        DataLayout::parse("i64:32")
+            .unwrap()
+            .set(ctx, module.operation());
+        let layout = DataLayout::of(ctx, module.operation()).unwrap();
+        assert_eq!(align_of(ctx, i64_ty, &layout).unwrap(), 4);
+    }
+}
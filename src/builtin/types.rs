@@ -1,20 +1,27 @@
 use combine::{
-    Parser, choice,
+    Parser, attempt, choice,
     parser::char::{spaces, string},
 };
 use pliron::derive::def_type;
 use pliron_derive::format_type;
 
 use crate::{
+    common_traits::Verify,
     context::{Context, Ptr},
+    identifier::Identifier,
     impl_verify_succ,
-    irfmt::parsers::int_parser,
+    irfmt::{aliases::AliasErr, parsers::int_parser},
+    operation::ResultTypesErr,
     parsable::{Parsable, ParseResult, StateStream},
     printable::{self, Printable},
+    result::Result,
     r#type::{Type, TypeObj, TypePtr},
+    utils::apfloat::FloatSemantics,
+    verify_err_noloc,
 };
 
 #[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Signedness {
     Signed,
     Unsigned,
@@ -93,6 +100,69 @@ impl Printable for IntegerType {
 
 impl_verify_succ!(IntegerType);
 
+/// A binary floating point type, in one of the [FloatSemantics] formats.
+/// Printed as the name of its format, for example `f32`.
+#[def_type("builtin.float")]
+#[derive(Hash, PartialEq, Eq, Debug)]
+pub struct FloatType {
+    semantics: FloatSemantics,
+}
+
+impl FloatType {
+    /// Get or create a new float type.
+    pub fn get(ctx: &mut Context, semantics: FloatSemantics) -> TypePtr<Self> {
+        Type::register_instance(FloatType { semantics }, ctx)
+    }
+    /// Get, if it already exists, a float type.
+    pub fn existing(ctx: &Context, semantics: FloatSemantics) -> Option<TypePtr<Self>> {
+        Type::instance(FloatType { semantics }, ctx)
+    }
+
+    /// Get the format.
+    pub fn semantics(&self) -> FloatSemantics {
+        self.semantics
+    }
+
+    /// Get width.
+    pub fn width(&self) -> usize {
+        self.semantics.width()
+    }
+}
+
+impl Parsable for FloatType {
+    type Arg = ();
+    type Parsed = TypePtr<Self>;
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        _arg: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed>
+    where
+        Self: Sized,
+    {
+        let mut parser = spaces()
+            .with(choice(FloatSemantics::ALL.map(|semantics| {
+                attempt(string(semantics.name()).map(move |_| semantics))
+            })));
+        parser
+            .parse_stream(state_stream)
+            .map(|semantics| FloatType::get(state_stream.state.ctx, semantics))
+            .into()
+    }
+}
+
+impl Printable for FloatType {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "{}", self.semantics)
+    }
+}
+
+impl_verify_succ!(FloatType);
+
 /// Map from a list of inputs to a list of results
 ///
 /// See MLIR's [FunctionType](https://mlir.llvm.org/docs/Dialects/Builtin/#functiontype).
@@ -154,10 +224,70 @@ impl UnitType {
 
 impl_verify_succ!(UnitType);
 
+/// Stands for a type alias (`!name`) that is used before its definition
+/// during parsing. All placeholders are replaced once parsing completes.
+/// See [aliases](crate::irfmt::aliases).
+#[def_type("builtin.alias_placeholder")]
+#[derive(Hash, PartialEq, Eq, Debug)]
+pub struct AliasPlaceholderType {
+    name: Identifier,
+}
+
+impl AliasPlaceholderType {
+    /// Get or create a placeholder for the type alias `name`.
+    pub fn get(ctx: &mut Context, name: Identifier) -> TypePtr<Self> {
+        Type::register_instance(AliasPlaceholderType { name }, ctx)
+    }
+
+    /// Name of the alias that this stands for.
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+}
+
+impl Printable for AliasPlaceholderType {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "!{}", self.name)
+    }
+}
+
+impl Verify for AliasPlaceholderType {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        verify_err_noloc!(AliasErr::Unresolved(format!("!{}", self.name)))
+    }
+}
+
+/// Type of [Operation](crate::operation::Operation) results that are yet to be determined.
+/// See [Operation::new_with_pending_results](crate::operation::Operation::new_with_pending_results).
+#[def_type("builtin.pending_result")]
+#[format_type]
+#[derive(Hash, PartialEq, Eq, Debug)]
+pub struct PendingResultType;
+
+impl PendingResultType {
+    /// Get or create the pending result type.
+    pub fn get(ctx: &mut Context) -> TypePtr<Self> {
+        Type::register_instance(Self {}, ctx)
+    }
+}
+
+impl Verify for PendingResultType {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        verify_err_noloc!(ResultTypesErr::Pending)
+    }
+}
+
 pub fn register(ctx: &mut Context) {
     IntegerType::register_type_in_dialect(ctx, IntegerType::parser_fn);
+    FloatType::register_type_in_dialect(ctx, FloatType::parser_fn);
     FunctionType::register_type_in_dialect(ctx, FunctionType::parser_fn);
     UnitType::register_type_in_dialect(ctx, UnitType::parser_fn);
+    PendingResultType::register_type_in_dialect(ctx, PendingResultType::parser_fn);
 }
 
 #[cfg(test)]
@@ -0,0 +1,845 @@
+//! A compact binary format for the IR.
+//!
+//! [serialize] encodes an operation, and everything nested in it, into bytes that
+//! [deserialize] decodes back, into the same or another [Context]. This is faster
+//! than printing and parsing the IR, and it preserves what the text doesn't:
+//! locations, block labels and the exact use-def structure (names of values aren't
+//! needed to encode it). Use it to cache IR, or to pass it between processes.
+//!
+//! The format starts with [MAGIC] and the [VERSION] of the format. Bytecode of
+//! other versions is rejected. All integers are encoded as unsigned LEB128.
+//! Then come the tables that the operations refer to by index:
+//!   - strings (such as names, op ids and dictionary keys),
+//!   - types, each as its text, parsed once when deserializing,
+//!   - attributes, each as its text, parsed once and cloned at every use.
+//!     Integers are written as their exact bit patterns, in hexadecimal.
+//!
+//! Finally, the operations are encoded structurally. Values are numbered in the order
+//! they're defined (the arguments of all blocks of a region, and then the operations
+//! of those blocks, each defining its results before its regions), and successors
+//! are identified by their index in the region.
+//!
+//! All dialects (and ops) used must be registered in the [Context] deserializing,
+//! unless it [allows unregistered dialects](Context::allow_unregistered_dialects).
+
+use std::path::PathBuf;
+
+use combine::{Parser, eof};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc,
+    attribute::{AttrObj, AttributeDict},
+    basic_block::BasicBlock,
+    builtin::ops::ForwardRefOp,
+    context::{Context, Ptr},
+    dialect::DialectName,
+    identifier::Identifier,
+    input_err_noloc, input_error_noloc,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::{Located, Location, Source},
+    op::{Op, OpId, OpName},
+    operation::Operation,
+    parsable::{self, Parsable, ParseSourceErr, parse_diagnostics, state_stream_from_iterator},
+    printable::{Printable, State},
+    region::Region,
+    result::Result,
+    r#type::{TypeObj, Typed},
+    uniqued_any,
+    unregistered::UnregisteredOp,
+    utils::apint::Radix,
+    value::Value,
+};
+
+/// The bytes every bytecode starts with.
+pub const MAGIC: &[u8; 4] = b"PLBC";
+
+/// Version of the bytecode format written by [serialize].
+pub const VERSION: usize = 2;
+
+#[derive(Error, Debug)]
+pub enum BytecodeErr {
+    #[error("A value or block defined outside the serialized operation is used in it")]
+    ExternalReference,
+    #[error("Input is not pliron bytecode")]
+    BadMagic,
+    #[error("Bytecode version {0} is not supported, expected version {VERSION}")]
+    UnsupportedVersion(usize),
+    #[error("Unexpected end of bytecode")]
+    Truncated,
+    #[error("Unexpected bytes at the end of bytecode")]
+    TrailingBytes,
+    #[error("Invalid {0} index {1} in bytecode")]
+    InvalidIndex(&'static str, usize),
+    #[error("Integer too large in bytecode")]
+    IntegerOverflow,
+    #[error("Invalid string in bytecode: {0}")]
+    InvalidString(String),
+    #[error("Invalid location kind {0} in bytecode")]
+    InvalidLocation(usize),
+    #[error("Op {0} is not registered")]
+    UnregisteredOp(String),
+    #[error("Value {0} is used, but never defined")]
+    UndefinedValue(usize),
+}
+
+/// Kinds of [Location]s, as encoded.
+const LOC_UNKNOWN: usize = 0;
+const LOC_IN_MEMORY: usize = 1;
+const LOC_FILE: usize = 2;
+const LOC_FUSED: usize = 3;
+const LOC_NAMED: usize = 4;
+const LOC_CALL_SITE: usize = 5;
+
+fn write_uint(buf: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+struct Writer<'a> {
+    ctx: &'a Context,
+    /// State to print types and attributes with.
+    state: State,
+    strings: Vec<String>,
+    string_ids: FxHashMap<String, usize>,
+    /// Types, as indices of their text in `strings`.
+    types: Vec<usize>,
+    type_ids: FxHashMap<Ptr<TypeObj>, usize>,
+    /// Attributes, as indices of their text in `strings`.
+    attrs: Vec<usize>,
+    attr_ids: FxHashMap<usize, usize>,
+    values: FxHashMap<Value, usize>,
+    /// Index of each block in its region.
+    blocks: FxHashMap<Ptr<BasicBlock>, usize>,
+    body: Vec<u8>,
+}
+
+impl Writer<'_> {
+    fn uint(&mut self, n: usize) {
+        write_uint(&mut self.body, n);
+    }
+
+    fn string_id(&mut self, string: &str) -> usize {
+        if let Some(id) = self.string_ids.get(string) {
+            return *id;
+        }
+        let id = self.strings.len();
+        self.strings.push(string.to_string());
+        self.string_ids.insert(string.to_string(), id);
+        id
+    }
+
+    fn string(&mut self, string: &str) {
+        let id = self.string_id(string);
+        self.uint(id);
+    }
+
+    fn r#type(&mut self, ty: Ptr<TypeObj>) {
+        let id = match self.type_ids.get(&ty) {
+            Some(id) => *id,
+            None => {
+                let text = ty.print(self.ctx, &self.state).to_string();
+                let text_id = self.string_id(&text);
+                let id = self.types.len();
+                self.types.push(text_id);
+                self.type_ids.insert(ty, id);
+                id
+            }
+        };
+        self.uint(id);
+    }
+
+    fn attr(&mut self, attr: &AttrObj) {
+        let text = attr.print(self.ctx, &self.state).to_string();
+        let text_id = self.string_id(&text);
+        let next_id = self.attrs.len();
+        let id = *self.attr_ids.entry(text_id).or_insert(next_id);
+        if id == next_id {
+            self.attrs.push(text_id);
+        }
+        self.uint(id);
+    }
+
+    fn attrs(&mut self, attrs: &AttributeDict) {
+        let mut attrs: Vec<_> = attrs.0.iter().collect();
+        attrs.sort_by_key(|(key, _)| key.to_string());
+        self.uint(attrs.len());
+        for (key, attr) in attrs {
+            self.string(key);
+            self.attr(attr);
+        }
+    }
+
+    fn loc(&mut self, loc: &Location) {
+        match loc {
+            Location::Unknown => self.uint(LOC_UNKNOWN),
+            Location::SrcPos { src, pos } => {
+                match src {
+                    Source::InMemory => self.uint(LOC_IN_MEMORY),
+                    Source::File(path) => {
+                        self.uint(LOC_FILE);
+                        let path = uniqued_any::get(self.ctx, *path)
+                            .to_string_lossy()
+                            .to_string();
+                        self.string(&path);
+                    }
+                }
+                self.uint(pos.line as u32 as usize);
+                self.uint(pos.column as u32 as usize);
+            }
+            Location::Fused {
+                metadata,
+                locations,
+            } => {
+                self.uint(LOC_FUSED);
+                match metadata {
+                    Some(metadata) => {
+                        self.uint(1);
+                        self.attr(metadata);
+                    }
+                    None => self.uint(0),
+                }
+                self.uint(locations.len());
+                for loc in locations {
+                    self.loc(loc);
+                }
+            }
+            Location::Named { name, child_loc } => {
+                self.uint(LOC_NAMED);
+                self.string(name);
+                self.loc(child_loc);
+            }
+            Location::CallSite { callee, caller } => {
+                self.uint(LOC_CALL_SITE);
+                self.loc(callee);
+                self.loc(caller);
+            }
+        }
+    }
+
+    /// Number the values defined in `op`, in the order they're deserialized.
+    fn number_op(&mut self, op: Ptr<Operation>) {
+        let ctx = self.ctx;
+        for result in op.deref(ctx).results() {
+            let id = self.values.len();
+            self.values.insert(result, id);
+        }
+        for region in op.deref(ctx).regions() {
+            for (idx, block) in region.deref(ctx).iter(ctx).enumerate() {
+                self.blocks.insert(block, idx);
+                for arg in block.deref(ctx).arguments() {
+                    let id = self.values.len();
+                    self.values.insert(arg, id);
+                }
+            }
+            for block in region.deref(ctx).iter(ctx) {
+                for nested in block.deref(ctx).iter(ctx) {
+                    self.number_op(nested);
+                }
+            }
+        }
+    }
+
+    fn op(&mut self, op: Ptr<Operation>) -> Result<()> {
+        let ctx = self.ctx;
+        let op_ref = op.deref(ctx);
+        let opid = op_ref.opid();
+        self.string(&opid.dialect);
+        self.string(&opid.name);
+        self.loc(&op_ref.loc());
+        self.uint(op_ref.num_results());
+        for (idx, result) in op_ref.results().enumerate() {
+            self.r#type(result.get_type(ctx));
+            self.attrs(op_ref.result_attributes(idx));
+        }
+        self.uint(op_ref.num_operands());
+        for opd in op_ref.operands() {
+            let Some(id) = self.values.get(&opd).copied() else {
+                return arg_err_noloc!(BytecodeErr::ExternalReference);
+            };
+            self.uint(id);
+        }
+        let container = op_ref
+            .container()
+            .and_then(|block| block.deref(ctx).container());
+        self.uint(op_ref.num_successors());
+        for succ in op_ref.successors() {
+            let Some(idx) = self.blocks.get(&succ).copied() else {
+                return arg_err_noloc!(BytecodeErr::ExternalReference);
+            };
+            if container.is_none() || succ.deref(ctx).container() != container {
+                return arg_err_noloc!(BytecodeErr::ExternalReference);
+            }
+            self.uint(idx);
+        }
+        self.attrs(&op_ref.attributes);
+        self.uint(op_ref.num_regions());
+        for region in op_ref.regions() {
+            self.region(region)?;
+        }
+        Ok(())
+    }
+
+    fn region(&mut self, region: Ptr<Region>) -> Result<()> {
+        let ctx = self.ctx;
+        let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+        self.uint(blocks.len());
+        for block in &blocks {
+            let block_ref = block.deref(ctx);
+            match block_ref.label {
+                Some(label) => {
+                    let id = self.string_id(&label);
+                    self.uint(id + 1);
+                }
+                None => self.uint(0),
+            }
+            self.loc(&block_ref.loc());
+            self.attrs(&block_ref.attributes);
+            self.uint(block_ref.num_arguments());
+            for (idx, arg) in block_ref.arguments().enumerate() {
+                self.r#type(arg.get_type(ctx));
+                self.attrs(block_ref.argument_attributes(idx));
+            }
+        }
+        for block in &blocks {
+            let ops: Vec<_> = block.deref(ctx).iter(ctx).collect();
+            self.uint(ops.len());
+            for op in ops {
+                self.op(op)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialize `op`, and everything nested in it, into bytecode.
+/// See [module](self) documentation. Fails if a value (or block) used
+/// in `op` is defined outside it.
+pub fn serialize(ctx: &Context, op: Ptr<Operation>) -> Result<Vec<u8>> {
+    let state = State::default();
+    state.set_int_radix(Radix::Hex);
+    let mut writer = Writer {
+        ctx,
+        state,
+        strings: vec![],
+        string_ids: FxHashMap::default(),
+        types: vec![],
+        type_ids: FxHashMap::default(),
+        attrs: vec![],
+        attr_ids: FxHashMap::default(),
+        values: FxHashMap::default(),
+        blocks: FxHashMap::default(),
+        body: vec![],
+    };
+    writer.number_op(op);
+    writer.op(op)?;
+
+    let mut bytes = MAGIC.to_vec();
+    write_uint(&mut bytes, VERSION);
+    write_uint(&mut bytes, writer.strings.len());
+    for string in &writer.strings {
+        write_uint(&mut bytes, string.len());
+        bytes.extend_from_slice(string.as_bytes());
+    }
+    for table in [&writer.types, &writer.attrs] {
+        write_uint(&mut bytes, table.len());
+        for text_id in table {
+            write_uint(&mut bytes, *text_id);
+        }
+    }
+    bytes.extend(writer.body);
+    Ok(bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    strings: Vec<&'a str>,
+    types: Vec<Ptr<TypeObj>>,
+    attrs: Vec<AttrObj>,
+    values: Vec<Value>,
+    /// Placeholders for values used before they're defined.
+    forward_refs: FxHashMap<usize, Value>,
+}
+
+/// Get `table[idx]`, or an error about an invalid `kind` index.
+fn entry<'t, T>(table: &'t [T], kind: &'static str, idx: usize) -> Result<&'t T> {
+    match table.get(idx) {
+        Some(entry) => Ok(entry),
+        None => input_err_noloc!(BytecodeErr::InvalidIndex(kind, idx)),
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8> {
+        let Some(byte) = self.bytes.get(self.pos).copied() else {
+            return input_err_noloc!(BytecodeErr::Truncated);
+        };
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn uint(&mut self) -> Result<usize> {
+        let mut n: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as usize;
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return input_err_noloc!(BytecodeErr::IntegerOverflow);
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn raw_str(&mut self) -> Result<&'a str> {
+        let len = self.uint()?;
+        let Some(bytes) = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+        else {
+            return input_err_noloc!(BytecodeErr::Truncated);
+        };
+        self.pos += len;
+        std::str::from_utf8(bytes)
+            .map_err(|err| input_error_noloc!(BytecodeErr::InvalidString(err.to_string())))
+    }
+
+    fn string(&mut self) -> Result<&'a str> {
+        let idx = self.uint()?;
+        entry(&self.strings, "string", idx).copied()
+    }
+
+    fn identifier(&mut self) -> Result<Identifier> {
+        let string = self.string()?;
+        string
+            .try_into()
+            .map_err(|_| input_error_noloc!(BytecodeErr::InvalidString(string.to_string())))
+    }
+
+    fn r#type(&mut self) -> Result<Ptr<TypeObj>> {
+        let idx = self.uint()?;
+        entry(&self.types, "type", idx).copied()
+    }
+
+    fn attr(&mut self) -> Result<AttrObj> {
+        let idx = self.uint()?;
+        entry(&self.attrs, "attribute", idx).cloned()
+    }
+
+    fn attrs(&mut self) -> Result<AttributeDict> {
+        let mut attrs = AttributeDict::default();
+        for _ in 0..self.uint()? {
+            let key = self.identifier()?;
+            let attr = self.attr()?;
+            attrs.0.insert(key, attr);
+        }
+        Ok(attrs)
+    }
+
+    fn loc(&mut self, ctx: &mut Context) -> Result<Location> {
+        let kind = self.uint()?;
+        Ok(match kind {
+            LOC_UNKNOWN => Location::Unknown,
+            LOC_IN_MEMORY | LOC_FILE => {
+                let src = if kind == LOC_FILE {
+                    let path = self.string()?;
+                    Source::new_from_file(ctx, PathBuf::from(path))
+                } else {
+                    Source::InMemory
+                };
+                let line = self.uint()? as u32 as i32;
+                let column = self.uint()? as u32 as i32;
+                Location::SrcPos {
+                    src,
+                    pos: combine::stream::position::SourcePosition { line, column },
+                }
+            }
+            LOC_FUSED => {
+                let metadata = match self.uint()? {
+                    0 => None,
+                    _ => Some(Box::new(self.attr()?)),
+                };
+                let locations = (0..self.uint()?)
+                    .map(|_| self.loc(ctx))
+                    .collect::<Result<_>>()?;
+                Location::Fused {
+                    metadata,
+                    locations,
+                }
+            }
+            LOC_NAMED => {
+                let name = self.string()?.to_string();
+                let child_loc = Box::new(self.loc(ctx)?);
+                Location::Named { name, child_loc }
+            }
+            LOC_CALL_SITE => {
+                let callee = Box::new(self.loc(ctx)?);
+                let caller = Box::new(self.loc(ctx)?);
+                Location::CallSite { callee, caller }
+            }
+            _ => return input_err_noloc!(BytecodeErr::InvalidLocation(kind)),
+        })
+    }
+
+    fn value(&mut self, ctx: &mut Context) -> Result<Value> {
+        let id = self.uint()?;
+        if let Some(value) = self.values.get(id) {
+            return Ok(*value);
+        }
+        let fwd_ref = *self
+            .forward_refs
+            .entry(id)
+            .or_insert_with(|| ForwardRefOp::new(ctx).operation().deref(ctx).result(0));
+        Ok(fwd_ref)
+    }
+
+    /// Define the next value, `value`, replacing its forward reference, if any.
+    fn define(&mut self, ctx: &mut Context, value: Value) {
+        let id = self.values.len();
+        self.values.push(value);
+        if let Some(fwd_ref) = self.forward_refs.remove(&id) {
+            fwd_ref.replace_some_uses_with(ctx, |_, _| true, &value);
+            let Value::OpResult { op, .. } = fwd_ref else {
+                unreachable!("Forward references are results of ForwardRefOps");
+            };
+            Operation::erase(op, ctx);
+        }
+    }
+
+    fn op(&mut self, ctx: &mut Context, blocks: &[Ptr<BasicBlock>]) -> Result<Ptr<Operation>> {
+        let dialect = self.identifier()?;
+        let name = self.identifier()?;
+        let opid = OpId {
+            dialect: DialectName::new(&dialect),
+            name: OpName::new(&name),
+        };
+        if !ctx.ops.contains_key(&opid) {
+            if !ctx.allows_unregistered_dialects() || ctx.dialects.contains_key(&opid.dialect) {
+                return input_err_noloc!(BytecodeErr::UnregisteredOp(opid.to_string()));
+            }
+            UnregisteredOp::register(ctx, opid);
+        }
+        let loc = self.loc(ctx)?;
+        let (result_types, result_attrs): (Vec<_>, Vec<_>) = (0..self.uint()?)
+            .map(|_| Ok((self.r#type()?, self.attrs()?)))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        let operands = (0..self.uint()?)
+            .map(|_| self.value(ctx))
+            .collect::<Result<_>>()?;
+        let successors = (0..self.uint()?)
+            .map(|_| {
+                let idx = self.uint()?;
+                entry(blocks, "block", idx).copied()
+            })
+            .collect::<Result<_>>()?;
+        let attributes = self.attrs()?;
+        let num_regions = self.uint()?;
+
+        let op = Operation::new(ctx, opid, result_types, operands, successors, num_regions);
+        {
+            let mut op_ref = op.deref_mut(ctx);
+            op_ref.attributes = attributes;
+            op_ref.set_loc(loc);
+            for (idx, attrs) in result_attrs.into_iter().enumerate() {
+                *op_ref.result_attributes_mut(idx) = attrs;
+            }
+        }
+        let results: Vec<_> = op.deref(ctx).results().collect();
+        for result in results {
+            self.define(ctx, result);
+        }
+        for idx in 0..num_regions {
+            let region = op.deref(ctx).region(idx);
+            self.region(ctx, region)?;
+        }
+        Ok(op)
+    }
+
+    fn region(&mut self, ctx: &mut Context, region: Ptr<Region>) -> Result<()> {
+        let mut blocks = vec![];
+        for _ in 0..self.uint()? {
+            let label = match self.uint()? {
+                0 => None,
+                id => {
+                    let label = *entry(&self.strings, "string", id - 1)?;
+                    let label: Identifier = label.try_into().map_err(|_| {
+                        input_error_noloc!(BytecodeErr::InvalidString(label.to_string()))
+                    })?;
+                    Some(label)
+                }
+            };
+            let loc = self.loc(ctx)?;
+            let attributes = self.attrs()?;
+            let (arg_types, arg_attrs): (Vec<_>, Vec<_>) = (0..self.uint()?)
+                .map(|_| Ok((self.r#type()?, self.attrs()?)))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .unzip();
+            let block = BasicBlock::new(ctx, label, arg_types);
+            {
+                let mut block_ref = block.deref_mut(ctx);
+                block_ref.attributes = attributes;
+                block_ref.set_loc(loc);
+                for (idx, attrs) in arg_attrs.into_iter().enumerate() {
+                    *block_ref.argument_attributes_mut(idx) = attrs;
+                }
+            }
+            block.insert_at_back(region, ctx);
+            let args: Vec<_> = block.deref(ctx).arguments().collect();
+            for arg in args {
+                self.define(ctx, arg);
+            }
+            blocks.push(block);
+        }
+        for block in &blocks {
+            for _ in 0..self.uint()? {
+                let op = self.op(ctx, &blocks)?;
+                op.insert_at_back(*block, ctx);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `text`, all of it, as a `P`.
+fn parse_text<P: Parsable<Arg = ()>>(ctx: &mut Context, text: &str) -> Result<P::Parsed> {
+    let state_stream =
+        state_stream_from_iterator(text.chars(), parsable::State::new(ctx, Source::InMemory));
+    P::parser(())
+        .skip(eof())
+        .parse(state_stream)
+        .map(|(parsed, _)| parsed)
+        .map_err(|errors| {
+            input_error_noloc!(ParseSourceErr::Diagnostics(parse_diagnostics(errors)))
+        })
+}
+
+/// Deserialize an operation, and everything nested in it, from `bytes`
+/// (written by [serialize]). See [module](self) documentation.
+pub fn deserialize(ctx: &mut Context, bytes: &[u8]) -> Result<Ptr<Operation>> {
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        return input_err_noloc!(BytecodeErr::BadMagic);
+    };
+    let mut reader = Reader {
+        bytes: rest,
+        pos: 0,
+        strings: vec![],
+        types: vec![],
+        attrs: vec![],
+        values: vec![],
+        forward_refs: FxHashMap::default(),
+    };
+    let version = reader.uint()?;
+    if version != VERSION {
+        return input_err_noloc!(BytecodeErr::UnsupportedVersion(version));
+    }
+    for _ in 0..reader.uint()? {
+        let string = reader.raw_str()?;
+        reader.strings.push(string);
+    }
+    for _ in 0..reader.uint()? {
+        let text = reader.string()?;
+        let ty = parse_text::<Ptr<TypeObj>>(ctx, text)?;
+        reader.types.push(ty);
+    }
+    for _ in 0..reader.uint()? {
+        let text = reader.string()?;
+        let attr = parse_text::<AttrObj>(ctx, text)?;
+        reader.attrs.push(attr);
+    }
+
+    let op = reader.op(ctx, &[])?;
+    if let Some((id, _)) = reader.forward_refs.iter().next() {
+        return input_err_noloc!(BytecodeErr::UndefinedValue(*id));
+    }
+    if reader.pos != reader.bytes.len() {
+        return input_err_noloc!(BytecodeErr::TrailingBytes);
+    }
+    Ok(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use combine::stream::position::SourcePosition;
+    use expect_test::expect;
+
+    use crate::{
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            attributes::IntegerAttr,
+            op_interfaces::{OneRegionInterface, OneResultInterface},
+            ops::FuncOp,
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::Context,
+        identifier::Identifier,
+        linked_list::ContainsLinkedList,
+        location::{Located, Location, Source},
+        op::Op,
+        printable::Printable,
+        result::{Error, ErrorKind},
+        test_dialect::{
+            self,
+            ops::{BinaryOp, BrOp, ConsumeOp, ProduceOp, TerminatorOp},
+        },
+        utils::apint::{APInt, bw},
+    };
+
+    use super::{BytecodeErr, VERSION, deserialize, serialize};
+
+    fn setup_context() -> Context {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        ctx
+    }
+
+    fn fails_with(
+        res: crate::result::Result<impl Sized>,
+        expected: fn(&BytecodeErr) -> bool,
+    ) -> bool {
+        matches!(
+            res,
+            Err(Error { err, .. }) if err.downcast_ref::<BytecodeErr>().is_some_and(expected)
+        )
+    }
+
+    #[test]
+    fn test_bytecode_round_trip() {
+        let ctx = &mut setup_context();
+
+        // f(a) { br ^second; ^exit(x): consume(x, p); terminator
+        //        ^second: p = produce; b = binary(a, p); br ^exit(b) }
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless).into();
+        let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        let src = Source::new_from_file(ctx, PathBuf::from("f.pliron"));
+        let pos = SourcePosition { line: 3, column: 7 };
+        func.operation()
+            .deref_mut(ctx)
+            .set_loc(Location::SrcPos { src, pos });
+        let region = func.region(ctx);
+        let entry = func.get_entry_block(ctx);
+        let exit = BasicBlock::new(ctx, Some("exit".try_into().unwrap()), vec![i64_ty]);
+        exit.insert_at_back(region, ctx);
+        let second = BasicBlock::new(ctx, None, vec![]);
+        second.insert_at_back(region, ctx);
+
+        let a = entry.deref(ctx).argument(0);
+        BrOp::new(ctx, second, vec![])
+            .operation()
+            .insert_at_back(entry, ctx);
+        let p = ProduceOp::new(ctx, i64_ty);
+        p.operation().insert_at_back(second, ctx);
+        let named = Location::Named {
+            name: "p".into(),
+            child_loc: Box::new(Location::Unknown),
+        };
+        p.operation().deref_mut(ctx).set_loc(named.clone());
+        // Integers are written as bit patterns.
+        let si8_ty = IntegerType::get(ctx, 8, Signedness::Signed);
+        let mask = IntegerAttr::new(si8_ty, APInt::from_i8(-5, bw(8)));
+        let mask_key: Identifier = "mask".try_into().unwrap();
+        p.operation()
+            .deref_mut(ctx)
+            .attributes
+            .set(mask_key, mask.clone());
+        let b = BinaryOp::new(ctx, a, p.result(ctx));
+        b.operation().insert_at_back(second, ctx);
+        BrOp::new(ctx, exit, vec![b.result(ctx)])
+            .operation()
+            .insert_at_back(second, ctx);
+        let x = exit.deref(ctx).argument(0);
+        ConsumeOp::new(ctx, vec![x, p.result(ctx)])
+            .operation()
+            .insert_at_back(exit, ctx);
+        TerminatorOp::new(ctx, vec![])
+            .operation()
+            .insert_at_back(exit, ctx);
+        func.operation().verify(ctx).unwrap();
+        let bytes = serialize(ctx, func.operation()).unwrap();
+
+        // `p` is used (in `^exit`) before it's defined (in `^second`).
+        let ctx2 = &mut setup_context();
+        let op = deserialize(ctx2, &bytes).unwrap();
+        op.verify(ctx2).unwrap();
+        assert_eq!(
+            op.deref(ctx2).loc().disp(ctx2).to_string(),
+            func.operation().deref(ctx).loc().disp(ctx).to_string()
+        );
+        expect![[r#"
+            builtin.func @f: builtin.function <(builtin.integer i64)->()> 
+            {
+              ^entry(block_1v1_arg0:builtin.integer i64):
+                test.br ^bb2()
+              ^exit(block_2v1_arg0:builtin.integer i64):
+                test.consume block_2v1_arg0, op_6v1_res0;
+                test.terminator 
+              ^bb2():
+                op_6v1_res0 = test.produce : builtin.integer i64;
+                op_3v3_res0 = test.binary block_1v1_arg0, op_6v1_res0 : builtin.integer i64;
+                test.br ^exit(op_3v3_res0)
+            }"#]]
+        .assert_eq(&op.disp(ctx2).to_string());
+        let second = op.deref(ctx2).region(0).deref(ctx2).tail().unwrap();
+        let p = second.deref(ctx2).head().unwrap();
+        assert_eq!(p.deref(ctx2).loc(), named);
+        let mask2 = p
+            .deref(ctx2)
+            .attributes
+            .get::<IntegerAttr>(&mask_key)
+            .unwrap()
+            .clone();
+        assert_eq!(mask2.disp(ctx2).to_string(), mask.disp(ctx).to_string());
+        assert!(bytes.windows(4).any(|window| window == b"0xfb"));
+        assert_eq!(serialize(ctx2, op).unwrap(), bytes);
+
+        // Malformed bytecode.
+        let res = deserialize(ctx2, &bytes[..bytes.len() - 1]);
+        assert!(fails_with(res, |err| matches!(err, BytecodeErr::Truncated)));
+        let res = deserialize(ctx2, &bytes[1..]);
+        assert!(fails_with(res, |err| matches!(err, BytecodeErr::BadMagic)));
+        let mut newer = bytes.clone();
+        newer[4] += 1;
+        let res = deserialize(ctx2, &newer);
+        assert!(fails_with(res, |err| matches!(
+            err,
+            BytecodeErr::UnsupportedVersion(version) if *version == VERSION + 1
+        )));
+        let res = deserialize(&mut Context::new(), &bytes);
+        assert!(matches!(
+            res,
+            Err(Error {
+                kind: ErrorKind::InvalidInput,
+                ..
+            })
+        ));
+
+        // Operations using values defined outside them can't be serialized alone.
+        let res = serialize(ctx, b.operation());
+        assert!(fails_with(res, |err| matches!(
+            err,
+            BytecodeErr::ExternalReference
+        )));
+    }
+}
@@ -0,0 +1,191 @@
+//! Suggest what can be typed next in the textual format, for REPLs and editors.
+//!
+//! [complete] parses the input up to the cursor, and reports what the parser would
+//! have accepted there. There are two sources of suggestions:
+//!   - the tokens that the parser expected (see [ParseDiagnostic]), such as `}` or `si`,
+//!   - the entities that the parsers announce (using [expect_here]) they're about to parse
+//!     at the cursor: names of the [Op](crate::op::Op)s, [Type](crate::type::Type)s
+//!     and [Attribute](crate::attribute::Attribute)s registered in the [Context],
+//!     or keywords, such as the variants of enum attributes.
+//!
+//! The word being typed at the cursor (letters, digits, `_` and `.`) is taken
+//! to be a partial suggestion, and only suggestions beginning with it are returned.
+
+use combine::{Parser, Positioned, eof, stream::position::SourcePosition};
+
+use crate::{
+    context::Context,
+    identifier::Identifier,
+    irfmt::parsers::spaced,
+    location::{self, Source},
+    operation::Operation,
+    parsable::{
+        Parsable, ParseDiagnostic, ParseToken, State, StateStream, parse_diagnostics,
+        state_stream_from_iterator,
+    },
+};
+
+/// What a parser expects to parse at the cursor. See [expect_here].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    /// The name of an [Op](crate::op::Op).
+    Op,
+    /// The name of a [Type](crate::type::Type).
+    Type,
+    /// The name of an [Attribute](crate::attribute::Attribute).
+    Attribute,
+    /// One of these keywords.
+    Keywords(&'static [&'static str]),
+}
+
+/// What a [Completion] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompletionKind {
+    /// The name of a registered [Op](crate::op::Op).
+    Op,
+    /// The name of a registered [Type](crate::type::Type).
+    Type,
+    /// The name of a registered [Attribute](crate::attribute::Attribute).
+    Attribute,
+    /// A keyword, such as an enum variant.
+    Keyword,
+    /// Punctuation, or other literal text.
+    Token,
+}
+
+/// A suggestion of what can be typed at the cursor.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    /// The complete text suggested (including the part already typed).
+    pub text: String,
+}
+
+/// Parser [State] extension, present during [complete], collecting what is expected at `pos`.
+struct CompletionProbe {
+    pos: SourcePosition,
+    expected: Vec<Expected>,
+}
+
+/// Announce, from a parser, that `expected` is about to be parsed at the current position.
+/// This is recorded only if the position is the cursor of an ongoing [complete].
+pub fn expect_here(state_stream: &mut StateStream, expected: Expected) {
+    let pos = state_stream.position();
+    let probe = state_stream.state.extension_mut::<CompletionProbe>();
+    if let Some(probe) = probe.filter(|probe| probe.pos == pos) {
+        probe.expected.push(expected);
+    }
+}
+
+/// Descriptions (rather than keywords) that the parser may expect.
+const CHARACTER_CLASSES: [&str; 8] = [
+    "letter",
+    "digit",
+    "alphanumeric",
+    "whitespace",
+    "whitespaces",
+    "space",
+    "spaces",
+    "newline",
+];
+
+/// Suggest what can be typed at the (byte) offset `cursor` in `input`. The part of
+/// `input` before the cursor is parsed as a top-level operation (in `ctx`, so all the
+/// dialects that it uses must be registered). As with any failed parse, IR that
+/// is only partially parsed may be left behind in `ctx`.
+///
+/// Suggestions are sorted, first by their [kind](CompletionKind), and then by their text.
+/// No suggestions are returned if `cursor` isn't at a character boundary in `input`.
+pub fn complete(ctx: &mut Context, input: &str, cursor: usize) -> Vec<Completion> {
+    let Some(prefix) = input.get(..cursor) else {
+        return vec![];
+    };
+    let word_start = prefix
+        .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '.')
+        .len();
+    let (before, partial) = prefix.split_at(word_start);
+
+    let pos = location::end_position(before);
+    let (probe, parsed) = {
+        let mut state = State::new(ctx, Source::InMemory);
+        state.insert_extension(CompletionProbe {
+            pos,
+            expected: vec![],
+        });
+        let mut state_stream = state_stream_from_iterator(before.chars(), state);
+        let parsed = spaced(Operation::parser(()))
+            .skip(eof())
+            .parse_stream(&mut state_stream)
+            .into_result()
+            .map(|(op, _)| op)
+            .map_err(|err| {
+                parse_diagnostics(err.into_inner().error)
+                    .into_iter()
+                    .find_map(|diagnostic| diagnostic.downcast::<ParseDiagnostic>().ok())
+            });
+        let probe = state_stream
+            .state
+            .remove_extension::<CompletionProbe>()
+            .expect("Completion probe removed during parse");
+        (probe, parsed)
+    };
+    let diagnostic = match parsed {
+        Ok(op) => {
+            Operation::erase(op, ctx);
+            None
+        }
+        Err(diagnostic) => diagnostic.filter(|diagnostic| diagnostic.pos == pos),
+    };
+
+    let mut completions = vec![];
+    for expected in probe.expected {
+        match expected {
+            Expected::Op => completions.extend(ctx.dialects.values().flat_map(|dialect| {
+                dialect
+                    .op_ids()
+                    .map(|opid| (CompletionKind::Op, opid.to_string()))
+            })),
+            Expected::Type => completions.extend(ctx.dialects.values().flat_map(|dialect| {
+                dialect
+                    .type_ids()
+                    .map(|tyid| (CompletionKind::Type, tyid.to_string()))
+            })),
+            Expected::Attribute => completions.extend(ctx.dialects.values().flat_map(|dialect| {
+                dialect
+                    .attr_ids()
+                    .map(|attrid| (CompletionKind::Attribute, attrid.to_string()))
+            })),
+            Expected::Keywords(keywords) => completions.extend(
+                keywords
+                    .iter()
+                    .map(|keyword| (CompletionKind::Keyword, keyword.to_string())),
+            ),
+        }
+    }
+    for token in diagnostic
+        .map(|diagnostic| diagnostic.expected)
+        .unwrap_or_default()
+    {
+        match token {
+            // `_` begins an identifier, which is better suggested by its entity.
+            ParseToken::Literal(text) if text == "_" => (),
+            ParseToken::Literal(text) => completions.push((CompletionKind::Token, text)),
+            ParseToken::Description(text)
+                if !CHARACTER_CLASSES.contains(&text.as_str())
+                    && Identifier::try_from(text.as_str()).is_ok() =>
+            {
+                completions.push((CompletionKind::Keyword, text))
+            }
+            ParseToken::Description(_) => (),
+        }
+    }
+
+    let mut completions: Vec<_> = completions
+        .into_iter()
+        .filter(|(_, text)| text.starts_with(partial))
+        .map(|(kind, text)| Completion { kind, text })
+        .collect();
+    completions.sort();
+    completions.dedup();
+    completions
+}
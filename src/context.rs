@@ -2,9 +2,13 @@
 
 use crate::{
     basic_block::BasicBlock,
+    builtin::op_interfaces::SymbolTableInterface,
     common_traits::Verify,
-    dialect::{Dialect, DialectName},
+    dialect::{Dialect, DialectName, DialectPlugin},
     identifier::Identifier,
+    limits::Limits,
+    linked_list::{ContainsLinkedList, LinkedList},
+    listener::RewriteListener,
     op::{OpCreator, OpId},
     operation::Operation,
     printable::{self, Printable},
@@ -13,14 +17,18 @@ use crate::{
     storage_uniquer::UniqueStore,
     r#type::TypeObj,
     uniqued_any::UniquedAny,
+    value::Value,
 };
 use rustc_hash::FxHashMap;
 use slotmap::{SlotMap, new_key_type};
 use std::{
     any::TypeId,
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
+    fmt::{self, Debug},
     hash::Hash,
     marker::PhantomData,
+    ops::{Deref, DerefMut},
+    rc::Rc,
 };
 
 new_key_type! {
@@ -40,12 +48,24 @@ pub struct Context {
     pub regions: ArenaCell<Region>,
     /// Registered [Dialect]s.
     pub dialects: FxHashMap<DialectName, Dialect>,
+    /// Loaded [DialectPlugin]s, in the order of loading.
+    pub(crate) dialect_plugins: Vec<Box<dyn DialectPlugin>>,
     /// Registered [Op](crate::op::Op)s.
     pub ops: FxHashMap<OpId, OpCreator>,
     /// Storage for uniqued [TypeObj]s.
     pub(crate) type_store: UniqueStore<TypeObj>,
     /// Storage for other uniqued objects.
     pub(crate) uniqued_any_store: UniqueStore<UniquedAny>,
+    /// Notified of changes to the IR.
+    listeners: Vec<Rc<RefCell<dyn RewriteListener>>>,
+    /// [Limits] on the size of the IR, enforced during parsing and verification.
+    pub limits: Limits,
+    /// The number of [fresh names](Self::fresh_name) generated so far, per prefix.
+    name_counters: FxHashMap<String, usize>,
+    /// Whether the parser accepts entities of unregistered dialects.
+    allow_unregistered: bool,
+    /// The last [modification epoch](Self::modification_epoch) handed out.
+    modification_epoch: Cell<u64>,
 
     #[cfg(test)]
     pub(crate) linked_list_store: crate::linked_list::tests::LinkedListTestArena,
@@ -55,10 +75,94 @@ impl Context {
     pub fn new() -> Context {
         Self::default()
     }
+
+    /// Notify `listener` of changes to the IR, until it's [removed](Self::remove_listener).
+    pub fn add_listener(&mut self, listener: Rc<RefCell<dyn RewriteListener>>) {
+        self.listeners.push(listener);
+    }
+
+    /// Stop notifying `listener`.
+    pub fn remove_listener(&mut self, listener: &Rc<RefCell<dyn RewriteListener>>) {
+        self.listeners
+            .retain(|registered| !Rc::ptr_eq(registered, listener));
+    }
+
+    /// Let the parser accept (or not, the default) ops, types and attributes of
+    /// dialects not registered in this [Context].
+    /// See [unregistered](crate::unregistered).
+    pub fn allow_unregistered_dialects(&mut self, allow: bool) {
+        self.allow_unregistered = allow;
+    }
+
+    /// Does the parser accept entities of dialects not registered in this [Context]?
+    pub fn allows_unregistered_dialects(&self) -> bool {
+        self.allow_unregistered
+    }
+
+    /// Generate a name made of `prefix` and a number, such as `tmp0` for `tmp`.
+    /// Names are numbered from 0, separately for each prefix, in the order they're
+    /// generated in this [Context]. So they're deterministic (unlike names derived
+    /// from [Ptr]s), and passes don't need to keep their own counters.
+    ///
+    /// Panics if `prefix` isn't a legal [Identifier] prefix.
+    pub fn fresh_name(&mut self, prefix: &str) -> Identifier {
+        let counter = self.name_counters.entry(prefix.to_string()).or_default();
+        let name = format!("{prefix}{counter}");
+        *counter += 1;
+        name.try_into()
+            .unwrap_or_else(|_| panic!("Name prefix {prefix} isn't a legal identifier"))
+    }
+
+    /// Generate a [fresh name](Self::fresh_name), for a new symbol in `table`,
+    /// that isn't already defined in it.
+    pub fn fresh_symbol(&mut self, table: &dyn SymbolTableInterface, prefix: &str) -> Identifier {
+        loop {
+            let name = self.fresh_name(prefix);
+            if table.lookup(self, &name).is_none() {
+                return name;
+            }
+        }
+    }
+
+    /// The current modification epoch. Every [Operation], [BasicBlock] and [Region]
+    /// records the epoch it was last modified (i.e., [mutated through a PtrRefMut](PtrRefMut))
+    /// or created in, which is always later than the epochs handed out before. So
+    /// incremental consumers of the IR, such as JIT caches, can record the epoch
+    /// when they're done with the IR, to later tell what has changed since then
+    /// (see [Operation::modified_since]).
+    pub fn modification_epoch(&self) -> u64 {
+        self.modification_epoch.get()
+    }
+
+    /// Start a new [modification epoch](Self::modification_epoch), and return it.
+    pub(crate) fn next_modification_epoch(&self) -> u64 {
+        let epoch = self.modification_epoch.get() + 1;
+        self.modification_epoch.set(epoch);
+        epoch
+    }
+
+    /// Call `notify` on every listener. A listener that is itself modifying
+    /// the IR (i.e., is being notified already) isn't notified of its own changes.
+    pub(crate) fn notify_listeners(&self, notify: impl Fn(&mut dyn RewriteListener)) {
+        for listener in &self.listeners {
+            if let Ok(mut listener) = listener.try_borrow_mut() {
+                notify(&mut *listener);
+            }
+        }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        let plugins = std::mem::take(&mut self.dialect_plugins);
+        for plugin in plugins.iter().rev() {
+            plugin.deinit(self);
+        }
+    }
 }
 
 pub(crate) mod private {
-    use std::{cell::RefCell, marker::PhantomData};
+    use std::{cell::RefCell, fmt, marker::PhantomData};
 
     use super::{ArenaCell, ArenaIndex, Context, Ptr};
 
@@ -67,6 +171,11 @@ pub(crate) mod private {
     where
         Self: Sized,
     {
+        /// What kind of IR object this is, for diagnostics.
+        const KIND: &'static str;
+        /// A short form of [KIND](Self::KIND), for the [Debug](std::fmt::Debug)
+        /// output of [Ptr]s, such as `op#12`.
+        const SHORT_KIND: &'static str = Self::KIND;
         /// Get the arena that has allocated this object.
         fn arena(ctx: &Context) -> &ArenaCell<Self>;
         /// Get the arena that has allocated this object.
@@ -76,6 +185,21 @@ pub(crate) mod private {
         /// If this object contains any ArenaObj itself, it must dealloc()
         /// all of those sub-objects. This is called when self is deallocated.
         fn dealloc_sub_objects(ptr: Ptr<Self>, ctx: &mut Context);
+        /// Called when the object has been modified (and is no longer borrowed),
+        /// to notify the [RewriteListener](crate::listener::RewriteListener)s.
+        fn notify_modified(_ptr: Ptr<Self>, _ctx: &Context) {}
+        /// Called when the object has been modified, to record the
+        /// [modification epoch](Context::modification_epoch) it's modified in.
+        fn set_modified_epoch(&mut self, _epoch: u64) {}
+        /// Details of the (live) object, appended to the [Debug](std::fmt::Debug)
+        /// output of its [Ptr] formatted with a [Context]. See [DebugWithContext](super::DebugWithContext).
+        fn fmt_debug_details(
+            _ptr: Ptr<Self>,
+            _ctx: &Context,
+            _f: &mut fmt::Formatter<'_>,
+        ) -> fmt::Result {
+            Ok(())
+        }
 
         /// Allocates object on the arena, given a creator function.
         fn alloc<T: FnOnce(Ptr<Self>) -> Self>(ctx: &mut Context, f: T) -> Ptr<Self> {
@@ -103,39 +227,72 @@ pub(crate) mod private {
 use private::ArenaObj;
 
 /// Pointer to an IR Object owned by Context.
-#[derive(Debug)]
+/// The [ArenaIndex] of a [Ptr] includes a generation counter, so dereferencing
+/// a [Ptr] to a deallocated object panics, rather than silently aliasing
+/// another object that reuses the same arena slot.
+///
+/// The [Debug] output of a [Ptr] is its kind and arena index, such as `op#12`.
+/// Formatted [with a Context](DebugWithContext::dbg_with), details of the
+/// pointee are added, such as `op#12 (llvm.add)`.
 pub struct Ptr<T: ArenaObj> {
     pub(crate) idx: ArenaIndex,
     pub(crate) _dummy: PhantomData<T>,
 }
 
 impl<'a, T: ArenaObj> Ptr<T> {
+    /// Get the [RefCell] holding the pointee.
+    /// Panics if the pointee has been deallocated, since the
+    /// arena slot may since have been reused by another object.
+    #[track_caller]
+    fn cell(&self, ctx: &'a Context) -> &'a RefCell<T> {
+        T::arena(ctx)
+            .get(self.idx)
+            .unwrap_or_else(|| panic!("Stale {} handle {:?}", T::KIND, self.idx.0))
+    }
+
+    /// Is the pointee still allocated?
+    /// [Ptr]s carry a generation counter, so a [Ptr] to a deallocated object
+    /// is never live again, even if its arena slot is reused.
+    pub fn is_live(&self, ctx: &Context) -> bool {
+        T::arena(ctx).contains_key(self.idx)
+    }
+
     /// Return a [Ref] to the pointee.
     /// This borrows from a RefCell and the borrow is live
     /// as long as the returned Ref lives.
+    /// Panics if the pointee has been deallocated.
+    #[track_caller]
     pub fn deref(&self, ctx: &'a Context) -> Ref<'a, T> {
-        T::arena(ctx).get(self.idx).unwrap().borrow()
+        self.cell(ctx).borrow()
     }
 
-    /// Return a RefMut to the pointee.
+    /// Return a [PtrRefMut] to the pointee.
     /// This mutably borrows from a RefCell and the borrow is live
-    /// as long as the returned RefMut lives.
-    pub fn deref_mut(&self, ctx: &'a Context) -> RefMut<'a, T> {
-        T::arena(ctx).get(self.idx).unwrap().borrow_mut()
+    /// as long as the returned [PtrRefMut] lives. If the pointee (an operation,
+    /// block or region) is mutated through it, [RewriteListener]s are notified
+    /// when it's dropped. Panics if the pointee has been deallocated.
+    #[track_caller]
+    pub fn deref_mut(&self, ctx: &'a Context) -> PtrRefMut<'a, T> {
+        PtrRefMut::new(ctx, self.idx, self.cell(ctx).borrow_mut())
     }
 
     /// Try and return a Ref to the pointee.
     /// This borrows from a RefCell and the borrow is live
     /// as long as the returned Ref lives.
+    /// Returns [None] if the pointee has been deallocated,
+    /// or if it is already mutably borrowed.
     pub fn try_deref(&self, ctx: &'a Context) -> Option<Ref<'a, T>> {
-        T::arena(ctx).get(self.idx).unwrap().try_borrow().ok()
+        T::arena(ctx).get(self.idx)?.try_borrow().ok()
     }
 
-    /// Try and return a RefMut to the pointee.
+    /// Try and return a [PtrRefMut] to the pointee.
     /// This mutably borrows from a RefCell and the borrow is live
-    /// as long as the returned RefMut lives.
-    pub fn try_deref_mut(&self, ctx: &'a Context) -> Option<RefMut<'a, T>> {
-        T::arena(ctx).get(self.idx).unwrap().try_borrow_mut().ok()
+    /// as long as the returned [PtrRefMut] lives.
+    /// Returns [None] if the pointee has been deallocated,
+    /// or if it is already borrowed.
+    pub fn try_deref_mut(&self, ctx: &'a Context) -> Option<PtrRefMut<'a, T>> {
+        let pointee = T::arena(ctx).get(self.idx)?.try_borrow_mut().ok()?;
+        Some(PtrRefMut::new(ctx, self.idx, pointee))
     }
 
     /// Create a unique (to the arena) name based on the arena index.
@@ -145,6 +302,90 @@ impl<'a, T: ArenaObj> Ptr<T> {
     }
 }
 
+/// A mutable borrow of (a part of) an IR object, obtained by [Ptr::deref_mut].
+/// Mutating the object through it records that the object is modified:
+/// when the borrow ends, the object's [modification epoch](Context::modification_epoch)
+/// is updated, and then [RewriteListener]s are notified. Borrows that are only read
+/// through don't count as modifications. Since the object is no longer borrowed
+/// when listeners are notified, they can inspect (or even modify) it.
+pub struct PtrRefMut<'a, T: ?Sized> {
+    /// [None] only once dropped or [mapped](Self::map).
+    pointee: Option<RefMut<'a, T>>,
+    ctx: &'a Context,
+    idx: ArenaIndex,
+    /// Records the modification of the borrowed [ArenaObj] at `idx`.
+    on_modified: fn(&Context, ArenaIndex),
+    modified: bool,
+}
+
+impl<'a, T: ArenaObj> PtrRefMut<'a, T> {
+    fn new(ctx: &'a Context, idx: ArenaIndex, pointee: RefMut<'a, T>) -> Self {
+        PtrRefMut {
+            pointee: Some(pointee),
+            ctx,
+            idx,
+            on_modified: Self::on_modified,
+            modified: false,
+        }
+    }
+
+    fn on_modified(ctx: &Context, idx: ArenaIndex) {
+        let ptr = Ptr::<T> {
+            idx,
+            _dummy: PhantomData,
+        };
+        ptr.cell(ctx)
+            .borrow_mut()
+            .set_modified_epoch(ctx.next_modification_epoch());
+        T::notify_modified(ptr, ctx);
+    }
+}
+
+impl<'a, T: ?Sized> PtrRefMut<'a, T> {
+    /// Make a new [PtrRefMut] for a component of the borrowed data.
+    /// Like [RefMut::map], and mutations through the new [PtrRefMut]
+    /// are modifications of the original IR object.
+    pub fn map<U: ?Sized>(mut orig: Self, f: impl FnOnce(&mut T) -> &mut U) -> PtrRefMut<'a, U> {
+        let pointee = orig.pointee.take().expect("PtrRefMut already released");
+        // Any modification made so far is recorded by the new [PtrRefMut].
+        let modified = std::mem::take(&mut orig.modified);
+        PtrRefMut {
+            pointee: Some(RefMut::map(pointee, f)),
+            ctx: orig.ctx,
+            idx: orig.idx,
+            on_modified: orig.on_modified,
+            modified,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for PtrRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.pointee.as_deref().expect("PtrRefMut already released")
+    }
+}
+
+impl<T: ?Sized> DerefMut for PtrRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        self.pointee
+            .as_deref_mut()
+            .expect("PtrRefMut already released")
+    }
+}
+
+impl<T: ?Sized> Drop for PtrRefMut<'_, T> {
+    fn drop(&mut self) {
+        // Release the borrow before recording the modification.
+        self.pointee = None;
+        if self.modified {
+            (self.on_modified)(self.ctx, self.idx);
+        }
+    }
+}
+
 impl<T: ArenaObj> Clone for Ptr<T> {
     fn clone(&self) -> Ptr<T> {
         *self
@@ -161,6 +402,23 @@ impl<T: ArenaObj> PartialEq for Ptr<T> {
 
 impl<T: ArenaObj> Eq for Ptr<T> {}
 
+impl<T: ArenaObj> Debug for Ptr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The low 32 bits are the slot index, the high ones the generation.
+        write!(f, "{}#{}", T::SHORT_KIND, self.idx.0.as_ffi() as u32)
+    }
+}
+
+impl<T: ArenaObj> DebugWithContext for Ptr<T> {
+    fn fmt_debug(&self, ctx: &Context, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self, f)?;
+        if !self.is_live(ctx) {
+            return write!(f, " (stale)");
+        }
+        T::fmt_debug_details(*self, ctx, f)
+    }
+}
+
 impl<T: ArenaObj + 'static> Hash for Ptr<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         TypeId::of::<T>().hash(state);
@@ -184,3 +442,209 @@ impl<T: ArenaObj + Verify> Verify for Ptr<T> {
         self.deref(ctx).verify(ctx)
     }
 }
+
+/// [Debug] formatting that looks up details in a [Context].
+///
+/// Example:
+/// ```
+/// # use pliron::{context::{Context, DebugWithContext}, builtin::{self, ops::ModuleOp}, op::Op};
+/// let mut ctx = Context::new();
+/// builtin::register(&mut ctx);
+/// let module = ModuleOp::new(&mut ctx, &"m".try_into().unwrap()).operation();
+/// let debug = format!("{:?}", module.dbg_with(&ctx));
+/// assert!(debug.starts_with("op#") && debug.ends_with(" (builtin.module)"));
+/// ```
+pub trait DebugWithContext {
+    /// Format `self`, as [Debug::fmt] does, with details from `ctx`.
+    fn fmt_debug(&self, ctx: &Context, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Get a [Debug]'able object formatting `self` with `ctx`.
+    fn dbg_with<'a>(&'a self, ctx: &'a Context) -> DebugWith<'a, Self> {
+        DebugWith { obj: self, ctx }
+    }
+}
+
+/// Formats an object with a [Context]. See [DebugWithContext::dbg_with].
+pub struct DebugWith<'a, T: DebugWithContext + ?Sized> {
+    obj: &'a T,
+    ctx: &'a Context,
+}
+
+impl<T: DebugWithContext + ?Sized> Debug for DebugWith<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.obj.fmt_debug(self.ctx, f)
+    }
+}
+
+/// Read access to the IR objects of a [Context], through the [Context] itself, or through a
+/// [FrozenContext] that can be shared across threads. Analyses written against it, such as
+/// the [Cfg](crate::analysis::cfg::Cfg), the [DominatorTree](crate::analysis::dominance::DominatorTree)
+/// and the [Liveness](crate::analysis::liveness::Liveness) of a region, can be computed on either.
+pub trait IrView {
+    /// A reference to an IR object, as long as it's borrowed.
+    type Ref<'b, T: ArenaObj + 'b>: Deref<Target = T>
+    where
+        Self: 'b;
+
+    /// Get a reference to the pointee of `ptr`.
+    /// Panics if the pointee has been deallocated, or is mutably borrowed.
+    fn get<T: ArenaObj + Sync>(&self, ptr: Ptr<T>) -> Self::Ref<'_, T>;
+
+    /// The operations in `block`, in order.
+    fn block_ops(&self, block: Ptr<BasicBlock>) -> impl Iterator<Item = Ptr<Operation>> + '_ {
+        std::iter::successors(self.get(block).head(), move |op| self.get(*op).next())
+    }
+
+    /// The blocks in `region`, in order.
+    fn region_blocks(&self, region: Ptr<Region>) -> impl Iterator<Item = Ptr<BasicBlock>> + '_ {
+        std::iter::successors(self.get(region).head(), move |block| {
+            self.get(*block).next()
+        })
+    }
+
+    /// The operation whose region contains `op`, if any.
+    fn parent_op(&self, op: Ptr<Operation>) -> Option<Ptr<Operation>> {
+        let block = self.get(op).container()?;
+        let region = self.get(block).container()?;
+        Some(self.get(region).parent_op())
+    }
+
+    /// The type of `value`.
+    fn value_type(&self, value: Value) -> Ptr<TypeObj> {
+        match value {
+            Value::OpResult { op, res_idx } => self.get(op).get_type(res_idx),
+            Value::BlockArgument { block, arg_idx } => self.get(block).argument_ref(arg_idx).ty,
+        }
+    }
+}
+
+impl IrView for Context {
+    type Ref<'b, T: ArenaObj + 'b> = Ref<'b, T>;
+
+    #[track_caller]
+    fn get<T: ArenaObj + Sync>(&self, ptr: Ptr<T>) -> Ref<'_, T> {
+        ptr.deref(self)
+    }
+}
+
+/// A read-only view of the IR in a [Context], rooted at an operation (typically a module),
+/// that can be shared across threads. See [Context::freeze].
+///
+/// [Ptr::deref] tracks borrows in a [RefCell], which can't be done from many threads at once.
+/// A [FrozenContext] instead holds the [Context] exclusively borrowed, so that the IR can't
+/// be mutated (or borrowed) for as long as the view (or any copy of it) lives, and hands
+/// out plain references into the arenas. So analyses written against an [IrView] can read
+/// the IR from many threads in parallel:
+/// ```
+/// # use pliron::{builtin::{self, ops::ModuleOp}, context::{Context, IrView}, op::Op};
+/// # use pliron::analysis::cfg::Cfg;
+/// let ctx = &mut Context::new();
+/// builtin::register(ctx);
+/// let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+/// let frozen = ctx.freeze(module.operation());
+/// let num_blocks = std::thread::scope(|s| {
+///     let region = frozen.get(frozen.root()).region(0);
+///     s.spawn(move || Cfg::new(&frozen, region).blocks().len()).join().unwrap()
+/// });
+/// assert_eq!(num_blocks, 1);
+/// // The view is dropped, and the IR may be mutated again.
+/// ```
+///
+/// Only the IR objects that are [Sync] are accessible through the view: it doesn't give
+/// access to the [Context] itself (which isn't thread safe), and [Op](crate::op::Op) objects
+/// aren't created. [Operation]s, [BasicBlock]s, [Region]s and [TypeObj]s are [Sync], since
+/// [Attribute](crate::attribute::Attribute)s and [Type](crate::type::Type)s are required
+/// to be [Sync] and [Send]. So only analyses written against an [IrView] can be run on it:
+/// [Op](crate::op::Op) verifiers, for example, take a [Context], and can't.
+#[derive(Clone, Copy)]
+pub struct FrozenContext<'a> {
+    ctx: &'a Context,
+    root: Ptr<Operation>,
+    /// The [Context] is borrowed mutably, so that nothing else accesses it.
+    _exclusive: PhantomData<&'a mut Context>,
+}
+
+// SAFETY: Sharing a [FrozenContext] (or sending a copy of it) to other threads shares the
+// `&Context` in it, although a [Context] isn't [Sync]. That's sound because of what the view
+// does with it, and what nothing else can do with it while the view lives:
+//
+// - The [Context] is exclusively borrowed for `'a`, the lifetime of the view (and of every
+//   reference it hands out). So for as long as any thread can reach the IR through the view,
+//   no other code can reach the [Context]: nothing mutates the arenas or IR objects, borrows
+//   their [RefCell]s, notifies listeners, bumps the modification epoch, or drops the [Context].
+// - The view only ever reads the [Context]: it looks up arena slots in the [SlotMap]s (which
+//   doesn't mutate them), and then calls [RefCell::try_borrow_unguarded] (in [FrozenContext::get]),
+//   which only reads the borrow flag of the [RefCell] and never writes it, unlike
+//   [RefCell::borrow]. With no writer anywhere, reading the (non-atomic) flag and the
+//   object concurrently from many threads is free of data races.
+// - [RefCell::try_borrow_unguarded] requires that the [RefCell] isn't mutably borrowed while
+//   the reference it returns lives. It checks that there's no mutable borrow when it's called,
+//   and none can start later, until `'a` ends, since that needs access to the [Context]. A
+//   [RefMut] leaked (with [std::mem::forget]) before freezing leaves the flag set, and
+//   [FrozenContext::get] then panics, rather than aliasing it.
+// - Only references to objects that are [Sync] are handed out, so using them from many
+//   threads at once is sound. Other parts of the [Context] (such as the registered
+//   [Op](crate::op::Op) creators, which may hold [Rc]s) are never reached through the view.
+unsafe impl Send for FrozenContext<'_> {}
+unsafe impl Sync for FrozenContext<'_> {}
+
+impl Context {
+    /// Freeze the IR, getting a read-only view, rooted at `root`, that can be shared across
+    /// threads. The IR can be mutated again once the view is dropped. See [FrozenContext].
+    pub fn freeze(&mut self, root: Ptr<Operation>) -> FrozenContext<'_> {
+        FrozenContext {
+            ctx: self,
+            root,
+            _exclusive: PhantomData,
+        }
+    }
+}
+
+impl<'a> FrozenContext<'a> {
+    /// The operation that this view is rooted at.
+    pub fn root(&self) -> Ptr<Operation> {
+        self.root
+    }
+
+    /// Get a reference, for as long as the view lives, to the pointee of `ptr`.
+    /// Unlike [IrView::get], the reference isn't tied to this borrow of the view.
+    /// Panics if the pointee has been deallocated, or is mutably borrowed
+    /// (which is possible only if a [RefMut] to it was leaked).
+    #[track_caller]
+    pub fn get<T: ArenaObj + Sync>(&self, ptr: Ptr<T>) -> &'a T {
+        // SAFETY: The context is exclusively borrowed by this view, which never
+        // mutates it, so the pointee can't be mutably borrowed while the reference lives.
+        // See the safety argument for `Sync` above.
+        unsafe { ptr.cell(self.ctx).try_borrow_unguarded() }
+            .unwrap_or_else(|_| panic!("{} {:?} is mutably borrowed", T::KIND, ptr.idx.0))
+    }
+
+    /// All operations nested in the root (including the root itself), in pre-order.
+    pub fn walk_ops(&self) -> Vec<Ptr<Operation>> {
+        let mut ops = vec![];
+        let mut worklist = vec![self.root];
+        while let Some(op) = worklist.pop() {
+            ops.push(op);
+            let nested: Vec<_> = self
+                .get(op)
+                .regions()
+                .flat_map(|region| self.region_blocks(region))
+                .flat_map(|block| self.block_ops(block))
+                .collect();
+            worklist.extend(nested.into_iter().rev());
+        }
+        ops
+    }
+}
+
+impl<'a> IrView for FrozenContext<'a> {
+    type Ref<'b, T: ArenaObj + 'b>
+        = &'b T
+    where
+        Self: 'b;
+
+    #[track_caller]
+    fn get<T: ArenaObj + Sync>(&self, ptr: Ptr<T>) -> &T {
+        FrozenContext::get(self, ptr)
+    }
+}
@@ -0,0 +1,584 @@
+//! Dialect conversion: rewriting the operations (and types) of some dialects
+//! into those of others, for example, to lower a custom dialect to the LLVM dialect.
+//!
+//! A [ConversionTarget] specifies which operations are legal after the conversion,
+//! by dialect or by operation (possibly depending on the operation itself).
+//! Operations that aren't legal are rewritten by [ConversionPattern]s, with the help
+//! of a [TypeConverter], which converts the types of operands, results and block
+//! arguments into legal types.
+//!
+//! The driver ([apply_partial_conversion] or [apply_full_conversion]) visits the
+//! operations nested in an operation, in pre-order, and tries the patterns (in
+//! decreasing order of their [benefit](ConversionPattern::benefit)) on those that
+//! aren't legal. Before a pattern is tried on an operation, the operation's operands
+//! are converted to legal types, and passed to the pattern, which builds the
+//! replacement from them. Operations created by patterns are legalized too.
+//!
+//! Converted and unconverted values meet when an operation is replaced by one with
+//! results of different types, while some of its users are yet to be converted (or
+//! won't be converted at all, in a partial conversion). There, the [TypeConverter]
+//! materializes casts between the types, which are [UnrealizedConversionCastOp]s unless
+//! specified otherwise. Converting the operands of a user looks through such casts,
+//! and casts that end up unused are erased, so they only remain where unconverted
+//! users need them.
+//! Once (a series of) conversions is done, the remaining [UnrealizedConversionCastOp]s
+//! are [reconciled](crate::transforms::reconcile_casts).
+//!
+//! A partial conversion fails if any operation explicitly marked illegal remains,
+//! while a full conversion fails if any operation not marked legal remains.
+//! The IR may have been partially converted when a conversion fails.
+
+use std::collections::VecDeque;
+
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    builtin::ops::UnrealizedConversionCastOp,
+    context::{Context, Ptr},
+    dialect::DialectName,
+    graph::walkers::collect_ops,
+    input_err,
+    location::Located,
+    op::{Op, OpId},
+    operation::Operation,
+    printable::Printable,
+    region::Region,
+    result::Result,
+    r#type::{TypeObj, Typed},
+    value::Value,
+};
+
+/// Legality of an [Operation] for a [ConversionTarget].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Legality {
+    /// The operation can remain after the conversion.
+    Legal,
+    /// The operation must be converted.
+    Illegal,
+    /// The target doesn't say. The operation must be converted in a full conversion,
+    /// but can remain if it isn't, in a partial conversion.
+    Unknown,
+}
+
+/// Decides the legality of an [Operation]. See [ConversionTarget::add_dynamically_legal_op].
+pub type DynamicLegalityFn = dyn Fn(&Context, Ptr<Operation>) -> bool;
+
+enum OpLegality {
+    Legal,
+    Illegal,
+    Dynamic(Box<DynamicLegalityFn>),
+}
+
+/// Specifies which [Operation]s are legal after a conversion.
+/// The legality of an [OpId] takes precedence over that of its dialect.
+/// See [module](self) documentation.
+#[derive(Default)]
+pub struct ConversionTarget {
+    ops: FxHashMap<OpId, OpLegality>,
+    dialects: FxHashMap<DialectName, bool>,
+}
+
+impl ConversionTarget {
+    /// Create a target with no legal or illegal operations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark operations of kind `opid` legal.
+    pub fn add_legal_op(&mut self, opid: OpId) -> &mut Self {
+        self.ops.insert(opid, OpLegality::Legal);
+        self
+    }
+
+    /// Mark operations of kind `opid` illegal.
+    pub fn add_illegal_op(&mut self, opid: OpId) -> &mut Self {
+        self.ops.insert(opid, OpLegality::Illegal);
+        self
+    }
+
+    /// Operations of kind `opid` are legal if `is_legal` says so, and illegal otherwise.
+    pub fn add_dynamically_legal_op(
+        &mut self,
+        opid: OpId,
+        is_legal: impl Fn(&Context, Ptr<Operation>) -> bool + 'static,
+    ) -> &mut Self {
+        self.ops
+            .insert(opid, OpLegality::Dynamic(Box::new(is_legal)));
+        self
+    }
+
+    /// Mark the operations of `dialect` legal.
+    pub fn add_legal_dialect(&mut self, dialect: DialectName) -> &mut Self {
+        self.dialects.insert(dialect, true);
+        self
+    }
+
+    /// Mark the operations of `dialect` illegal.
+    pub fn add_illegal_dialect(&mut self, dialect: DialectName) -> &mut Self {
+        self.dialects.insert(dialect, false);
+        self
+    }
+
+    /// The legality of `op` for this target.
+    pub fn legality(&self, ctx: &Context, op: Ptr<Operation>) -> Legality {
+        let opid = op.deref(ctx).opid();
+        match self.ops.get(&opid) {
+            Some(OpLegality::Legal) => Legality::Legal,
+            Some(OpLegality::Illegal) => Legality::Illegal,
+            Some(OpLegality::Dynamic(is_legal)) if is_legal(ctx, op) => Legality::Legal,
+            Some(OpLegality::Dynamic(_)) => Legality::Illegal,
+            None => match self.dialects.get(&opid.dialect) {
+                Some(true) => Legality::Legal,
+                Some(false) => Legality::Illegal,
+                None => Legality::Unknown,
+            },
+        }
+    }
+}
+
+/// Converts a type, returning [None] if it can't. See [TypeConverter::add_conversion].
+pub type TypeConversionFn = dyn Fn(&mut Context, Ptr<TypeObj>) -> Option<Ptr<TypeObj>>;
+
+/// Builds an (unlinked) [Operation] whose first result is the given [Value]
+/// converted to the given type, returning [None] if it can't.
+/// See [TypeConverter::add_source_materialization].
+pub type MaterializationFn = dyn Fn(&mut Context, Value, Ptr<TypeObj>) -> Option<Ptr<Operation>>;
+
+/// Converts types into legal types during a conversion,
+/// and materializes conversions of values between them.
+/// See [module](self) documentation.
+#[derive(Default)]
+pub struct TypeConverter {
+    conversions: Vec<Box<TypeConversionFn>>,
+    source_materializations: Vec<Box<MaterializationFn>>,
+    target_materializations: Vec<Box<MaterializationFn>>,
+}
+
+/// Try `materializations`, most recently added first, falling back to an [UnrealizedConversionCastOp].
+fn materialize(
+    ctx: &mut Context,
+    materializations: &[Box<MaterializationFn>],
+    value: Value,
+    ty: Ptr<TypeObj>,
+) -> Ptr<Operation> {
+    materializations
+        .iter()
+        .rev()
+        .find_map(|materialize| materialize(ctx, value, ty))
+        .unwrap_or_else(|| UnrealizedConversionCastOp::new(ctx, value, ty).operation())
+}
+
+impl TypeConverter {
+    /// Create a converter that doesn't convert any type.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a type conversion. Conversions are tried most recently added first,
+    /// and the type returned by the first one that doesn't return [None] is used.
+    /// A conversion returning the type itself marks it as legal. Types that no
+    /// conversion applies to can't be converted.
+    pub fn add_conversion(
+        &mut self,
+        conversion: impl Fn(&mut Context, Ptr<TypeObj>) -> Option<Ptr<TypeObj>> + 'static,
+    ) -> &mut Self {
+        self.conversions.push(Box::new(conversion));
+        self
+    }
+
+    /// Add a materialization of a converted value back into its original type,
+    /// for users that aren't (yet) converted. Materializations are tried most
+    /// recently added first, falling back to an [UnrealizedConversionCastOp].
+    pub fn add_source_materialization(
+        &mut self,
+        materialization: impl Fn(&mut Context, Value, Ptr<TypeObj>) -> Option<Ptr<Operation>> + 'static,
+    ) -> &mut Self {
+        self.source_materializations.push(Box::new(materialization));
+        self
+    }
+
+    /// Add a materialization of an unconverted value into its converted type,
+    /// for the operands of operations being converted. Materializations are tried
+    /// most recently added first, falling back to an [UnrealizedConversionCastOp].
+    pub fn add_target_materialization(
+        &mut self,
+        materialization: impl Fn(&mut Context, Value, Ptr<TypeObj>) -> Option<Ptr<Operation>> + 'static,
+    ) -> &mut Self {
+        self.target_materializations.push(Box::new(materialization));
+        self
+    }
+
+    /// Convert `ty`, returning [None] if it can't be converted.
+    pub fn convert_type(&self, ctx: &mut Context, ty: Ptr<TypeObj>) -> Option<Ptr<TypeObj>> {
+        self.conversions
+            .iter()
+            .rev()
+            .find_map(|conversion| conversion(ctx, ty))
+    }
+
+    /// Convert each of `tys`, returning [None] if any of them can't be converted.
+    pub fn convert_types(
+        &self,
+        ctx: &mut Context,
+        tys: &[Ptr<TypeObj>],
+    ) -> Option<Vec<Ptr<TypeObj>>> {
+        tys.iter().map(|ty| self.convert_type(ctx, *ty)).collect()
+    }
+
+    /// Is `ty` legal, i.e., converted to itself?
+    pub fn is_legal(&self, ctx: &mut Context, ty: Ptr<TypeObj>) -> bool {
+        self.convert_type(ctx, ty) == Some(ty)
+    }
+
+    /// Build an (unlinked) [Operation] converting (the converted) `value`
+    /// back into (the original) type `ty`, as its first result.
+    pub fn materialize_source(
+        &self,
+        ctx: &mut Context,
+        value: Value,
+        ty: Ptr<TypeObj>,
+    ) -> Ptr<Operation> {
+        materialize(ctx, &self.source_materializations, value, ty)
+    }
+
+    /// Build an (unlinked) [Operation] converting (the unconverted) `value`
+    /// into (the converted) type `ty`, as its first result.
+    pub fn materialize_target(
+        &self,
+        ctx: &mut Context,
+        value: Value,
+        ty: Ptr<TypeObj>,
+    ) -> Ptr<Operation> {
+        materialize(ctx, &self.target_materializations, value, ty)
+    }
+}
+
+/// A rewrite of operations into legal ones. See [module](self) documentation.
+pub trait ConversionPattern {
+    /// A name identifying this pattern.
+    fn name(&self) -> &str;
+
+    /// Patterns with higher benefit are tried first.
+    fn benefit(&self) -> u32 {
+        1
+    }
+
+    /// The kind of operations that this pattern matches, if restricted.
+    /// Other operations aren't tried with this pattern.
+    fn root_opid(&self) -> Option<OpId> {
+        None
+    }
+
+    /// If `op` matches, rewrite it using `rewriter` and return `true`.
+    /// `operands` are the operands of `op`, converted by the
+    /// [type converter](ConversionRewriter::type_converter) where it converts their types.
+    /// Must return `false` (and not change the IR) if it doesn't match.
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut ConversionRewriter,
+        op: Ptr<Operation>,
+        operands: &[Value],
+    ) -> Result<bool>;
+}
+
+/// A set of [ConversionPattern]s.
+#[derive(Default)]
+pub struct ConversionPatternSet {
+    patterns: Vec<Box<dyn ConversionPattern>>,
+}
+
+impl ConversionPatternSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `pattern`.
+    pub fn add(&mut self, pattern: impl ConversionPattern + 'static) -> &mut Self {
+        self.patterns.push(Box::new(pattern));
+        self
+    }
+
+    /// The patterns, in decreasing order of benefit
+    /// (and in the order they were added, for equal benefits).
+    fn ordered(&self) -> Vec<&dyn ConversionPattern> {
+        let mut ordered: Vec<_> = self.patterns.iter().map(|p| &**p).collect();
+        ordered.sort_by_key(|pattern| std::cmp::Reverse(pattern.benefit()));
+        ordered
+    }
+}
+
+/// Makes the changes of a [ConversionPattern],
+/// tracking the operations to be legalized and the casts materialized.
+pub struct ConversionRewriter<'a> {
+    type_converter: &'a TypeConverter,
+    /// Operations inserted since they were last legalized.
+    inserted: Vec<Ptr<Operation>>,
+    /// Materialized casts, to be erased if they end up unused.
+    casts: Vec<Ptr<Operation>>,
+    /// Results of source materializations, mapped to the converted values they cast.
+    unconverted: FxHashMap<Value, Value>,
+}
+
+impl<'a> ConversionRewriter<'a> {
+    fn new(type_converter: &'a TypeConverter) -> Self {
+        ConversionRewriter {
+            type_converter,
+            inserted: vec![],
+            casts: vec![],
+            unconverted: FxHashMap::default(),
+        }
+    }
+
+    /// The [TypeConverter] of this conversion.
+    pub fn type_converter(&self) -> &'a TypeConverter {
+        self.type_converter
+    }
+
+    /// `op` (and the operations nested in it) were inserted into the IR.
+    /// Called by the methods inserting operations, and must be called
+    /// when a pattern inserts operations by other means, to legalize them.
+    pub fn notify_op_inserted(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        self.inserted.extend(collect_ops(ctx, op));
+    }
+
+    /// Insert (the unlinked) `op` before `mark`.
+    pub fn insert_before(&mut self, ctx: &Context, op: Ptr<Operation>, mark: Ptr<Operation>) {
+        op.insert_before(ctx, mark);
+        self.notify_op_inserted(ctx, op);
+    }
+
+    /// Insert (the unlinked) `op` after `mark`.
+    pub fn insert_after(&mut self, ctx: &Context, op: Ptr<Operation>, mark: Ptr<Operation>) {
+        op.insert_after(ctx, mark);
+        self.notify_op_inserted(ctx, op);
+    }
+
+    /// Replace the uses of `from` with `to`, casting `to` back to the type of `from`
+    /// (inserting the cast before `mark`) if they differ.
+    fn replace_value(&mut self, ctx: &mut Context, from: Value, to: Value, mark: Ptr<Operation>) {
+        let from_ty = from.get_type(ctx);
+        let to = if to.get_type(ctx) != from_ty && from.is_used(ctx) {
+            let cast = self.type_converter.materialize_source(ctx, to, from_ty);
+            cast.insert_before(ctx, mark);
+            self.casts.push(cast);
+            let cast_res = cast.deref(ctx).result(0);
+            self.unconverted.insert(cast_res, to);
+            cast_res
+        } else {
+            to
+        };
+        from.replace_some_uses_with(ctx, |_, _| true, &to);
+    }
+
+    /// Replace the results of `op` with `values`, and erase `op`.
+    /// Values of types different from the results they replace are cast back
+    /// to the types of the results, for the users that aren't converted.
+    pub fn replace_op_with_values(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        values: &[Value],
+    ) {
+        let results: Vec<_> = op.deref(ctx).results().collect();
+        assert!(
+            results.len() == values.len(),
+            "Operation replaced with a different number of values than its results"
+        );
+        for (result, value) in results.into_iter().zip(values) {
+            self.replace_value(ctx, result, *value, op);
+        }
+        self.erase_op(ctx, op);
+    }
+
+    /// Replace the results of `op` with those of `new_op` (see
+    /// [replace_op_with_values](Self::replace_op_with_values)), and erase `op`.
+    /// `new_op` is inserted before `op`, unless it's already linked.
+    pub fn replace_op(&mut self, ctx: &mut Context, op: Ptr<Operation>, new_op: Ptr<Operation>) {
+        if !new_op.is_linked(ctx) {
+            self.insert_before(ctx, new_op, op);
+        }
+        let values: Vec<_> = new_op.deref(ctx).results().collect();
+        self.replace_op_with_values(ctx, op, &values);
+    }
+
+    /// Erase `op`, whose results must not have any uses.
+    pub fn erase_op(&mut self, ctx: &mut Context, op: Ptr<Operation>) {
+        Operation::erase(op, ctx);
+    }
+
+    /// Modify `op` in place, with `modify`. The modified operation is legalized again.
+    pub fn modify_op_in_place<R>(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        modify: impl FnOnce(&mut Context) -> R,
+    ) -> R {
+        let res = modify(ctx);
+        self.inserted.push(op);
+        res
+    }
+
+    /// Convert the types of the arguments of the blocks in `region`. Arguments
+    /// whose types change are cast back to their original types, for their users
+    /// that aren't converted. Fails, without changing the IR, if some type can't
+    /// be converted.
+    pub fn convert_region_types(&mut self, ctx: &mut Context, region: Ptr<Region>) -> Result<()> {
+        let blocks = region.deref(ctx).blocks_snapshot(ctx);
+        let mut converted = vec![];
+        for block in &blocks {
+            let args: Vec<_> = block.deref(ctx).arguments().collect();
+            for arg in args {
+                let ty = arg.get_type(ctx);
+                let Some(new_ty) = self.type_converter.convert_type(ctx, ty) else {
+                    return input_err!(
+                        block.deref(ctx).loc(),
+                        ConversionErr::TypeNotConverted(ty.disp(ctx).to_string())
+                    );
+                };
+                if new_ty != ty {
+                    converted.push((*block, arg, new_ty));
+                }
+            }
+        }
+
+        for (block, arg, new_ty) in converted {
+            let Value::BlockArgument { arg_idx, .. } = arg else {
+                unreachable!("Block arguments must be Value::BlockArgument");
+            };
+            let ty = arg.get_type(ctx);
+            let uses: Vec<_> = arg.uses(ctx).collect();
+            block.deref_mut(ctx).argument_mut(arg_idx).ty = new_ty;
+            if uses.is_empty() {
+                continue;
+            }
+            let cast = self.type_converter.materialize_source(ctx, arg, ty);
+            cast.insert_at_front(block, ctx);
+            self.casts.push(cast);
+            let cast_res = cast.deref(ctx).result(0);
+            self.unconverted.insert(cast_res, arg);
+            for r#use in uses {
+                r#use.set(ctx, cast_res);
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert `value`, an operand of `op`, if the type converter converts its type,
+    /// looking through casts back from converted values.
+    fn remap_operand(&mut self, ctx: &mut Context, op: Ptr<Operation>, value: Value) -> Value {
+        let ty = value.get_type(ctx);
+        let Some(new_ty) = self.type_converter.convert_type(ctx, ty) else {
+            return value;
+        };
+        if new_ty == ty {
+            return value;
+        }
+        if let Some(converted) = self
+            .unconverted
+            .get(&value)
+            .filter(|converted| converted.get_type(ctx) == new_ty)
+        {
+            return *converted;
+        }
+        let cast = self.type_converter.materialize_target(ctx, value, new_ty);
+        cast.insert_before(ctx, op);
+        self.casts.push(cast);
+        cast.deref(ctx).result(0)
+    }
+
+    /// Erase the materialized casts that are unused, most recently materialized first.
+    fn erase_unused_casts(&mut self, ctx: &mut Context) {
+        for cast in std::mem::take(&mut self.casts).into_iter().rev() {
+            if cast.is_live(ctx) && !cast.deref(ctx).results().any(|res| res.is_used(ctx)) {
+                Operation::erase(cast, ctx);
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConversionErr {
+    #[error("Failed to legalize operation {0}")]
+    IllegalOp(String),
+    #[error("Type {0} cannot be converted")]
+    TypeNotConverted(String),
+}
+
+/// Convert the operations nested in `root`. See [module](self) documentation.
+fn apply_conversion(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    type_converter: &TypeConverter,
+    full: bool,
+) -> Result<()> {
+    let ordered = patterns.ordered();
+    let mut rewriter = ConversionRewriter::new(type_converter);
+    let mut worklist: VecDeque<_> = collect_ops(ctx, root).into_iter().skip(1).collect();
+
+    while let Some(op) = worklist.pop_front() {
+        if !op.is_live(ctx) || target.legality(ctx, op) == Legality::Legal {
+            continue;
+        }
+        let opid = op.deref(ctx).opid();
+        let operands: Vec<_> = op.deref(ctx).operands().collect();
+        let operands: Vec<_> = operands
+            .into_iter()
+            .map(|opd| rewriter.remap_operand(ctx, op, opd))
+            .collect();
+        for pattern in &ordered {
+            if pattern.root_opid().is_some_and(|root| root != opid) {
+                continue;
+            }
+            if pattern.match_and_rewrite(ctx, &mut rewriter, op, &operands)? {
+                break;
+            }
+        }
+        // Legalize the operations inserted, before those that follow.
+        for inserted in rewriter.inserted.drain(..).rev() {
+            worklist.push_front(inserted);
+        }
+    }
+    rewriter.erase_unused_casts(ctx);
+
+    for op in collect_ops(ctx, root).into_iter().skip(1) {
+        let legality = target.legality(ctx, op);
+        if legality == Legality::Illegal || (full && legality == Legality::Unknown) {
+            let opid = op.deref(ctx).opid();
+            return input_err!(
+                op.deref(ctx).loc(),
+                ConversionErr::IllegalOp(opid.disp(ctx).to_string())
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Convert the operations nested in `root`, leaving those that can't be
+/// converted as they are, unless they're [Illegal](Legality::Illegal) for `target`.
+/// See [module](self) documentation.
+pub fn apply_partial_conversion(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    type_converter: &TypeConverter,
+) -> Result<()> {
+    apply_conversion(ctx, root, target, patterns, type_converter, false)
+}
+
+/// Convert the operations nested in `root`, all of which must end up
+/// [Legal](Legality::Legal) for `target`. See [module](self) documentation.
+pub fn apply_full_conversion(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    type_converter: &TypeConverter,
+) -> Result<()> {
+    apply_conversion(ctx, root, target, patterns, type_converter, true)
+}
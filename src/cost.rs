@@ -0,0 +1,221 @@
+//! Cost models, to decide if a rewrite is profitable.
+//!
+//! Whether a rewrite (for example, strength reducing a multiplication into a shift)
+//! pays off often depends on the target. Rather than hardcoding such decisions,
+//! patterns and passes can consult a [CostModel] before rewriting:
+//! ```ignore
+//! let model = TargetCostModel::of(ctx, op)?;
+//! if model.is_profitable(ctx, &[op], &replacement_ops) { ... }
+//! ```
+//!
+//! The [TargetCostModel] estimates the cost of an operation as:
+//!   - a target specific [weight](TargetCostModel::with_weight) for its [OpId], if there's one,
+//!   - otherwise, the cost reported by its [OpCostInterface], if it implements one,
+//!   - otherwise, [DEFAULT_OP_COST].
+//!
+//! Target weights are read (see [TargetCostModel::of]) from an [ATTR_KEY_COST_WEIGHTS]
+//! attribute of an enclosing operation (typically the module), just as the
+//! [DataLayout] is. The attribute is a [StringAttr] listing comma separated
+//! `<opid>=<weight>` entries, for example, `llvm.mul=3,llvm.sdiv=20`.
+
+use std::{fmt::Display, sync::LazyLock};
+
+use pliron::derive::op_interface;
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    builtin::{attributes::StringAttr, type_interfaces::DataLayout},
+    context::{Context, Ptr},
+    dialect::DialectName,
+    identifier::Identifier,
+    input_err, input_err_noloc,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{Op, OpId, OpName, op_cast},
+    operation::Operation,
+    result::Result,
+};
+
+/// Key for the [TargetCostModel] weights attribute of an operation (typically a module).
+/// The value is a [StringAttr] with the weights (see [module](self) documentation).
+pub static ATTR_KEY_COST_WEIGHTS: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_cost_weights".try_into().unwrap());
+
+/// Cost of an operation, when nothing else is known about it.
+pub const DEFAULT_OP_COST: u64 = 1;
+
+#[derive(Error, Debug)]
+pub enum CostWeightsErr {
+    #[error("Malformed cost weight \"{0}\", expected <opid>=<weight>")]
+    Malformed(String),
+    #[error("Cost weights attribute must be a string")]
+    NotAString,
+}
+
+/// Estimates the cost of operations. See [module](self) documentation.
+pub trait CostModel {
+    /// The [DataLayout] of the target.
+    fn layout(&self) -> &DataLayout;
+
+    /// Estimated cost of `op` itself, excluding the operations nested in its regions.
+    fn op_cost(&self, ctx: &Context, op: Ptr<Operation>) -> u64;
+
+    /// Estimated cost of `ops`, including the operations nested in their regions.
+    fn cost(&self, ctx: &Context, ops: &[Ptr<Operation>]) -> u64 {
+        ops.iter()
+            .map(|op| {
+                let nested: u64 = op
+                    .deref(ctx)
+                    .regions()
+                    .flat_map(|region| region.deref(ctx).iter(ctx).collect::<Vec<_>>())
+                    .map(|block| {
+                        let block_ops: Vec<_> = block.deref(ctx).iter(ctx).collect();
+                        self.cost(ctx, &block_ops)
+                    })
+                    .sum();
+                self.op_cost(ctx, *op).saturating_add(nested)
+            })
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Is replacing `old` by `new` profitable, i.e., is `new` strictly cheaper?
+    fn is_profitable(&self, ctx: &Context, old: &[Ptr<Operation>], new: &[Ptr<Operation>]) -> bool {
+        self.cost(ctx, new) < self.cost(ctx, old)
+    }
+}
+
+/// [Op]s that can estimate their own cost.
+#[op_interface]
+pub trait OpCostInterface {
+    /// Estimated cost of this operation (excluding the operations nested in its
+    /// regions), as per `model`. The model provides, for example, the target's
+    /// [DataLayout], to estimate the cost of memory operations.
+    fn cost(&self, ctx: &Context, model: &dyn CostModel) -> u64;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// A [CostModel] with target specific weights. See [module](self) documentation.
+#[derive(Clone, Default)]
+pub struct TargetCostModel {
+    layout: DataLayout,
+    weights: FxHashMap<OpId, u64>,
+}
+
+impl TargetCostModel {
+    /// A cost model for the target described by `layout`, with no weights.
+    pub fn new(layout: DataLayout) -> Self {
+        TargetCostModel {
+            layout,
+            weights: FxHashMap::default(),
+        }
+    }
+
+    /// Set the cost of operations of type `opid` to `weight`.
+    pub fn with_weight(mut self, opid: OpId, weight: u64) -> Self {
+        self.weights.insert(opid, weight);
+        self
+    }
+
+    /// The weight of `opid`, if specified.
+    pub fn weight(&self, opid: &OpId) -> Option<u64> {
+        self.weights.get(opid).copied()
+    }
+
+    /// Parse comma separated `<opid>=<weight>` entries, adding them to `self`.
+    pub fn with_weights_spec(mut self, spec: &str) -> Result<Self> {
+        for entry in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let parsed = entry.split_once('=').and_then(|(opid, weight)| {
+                let (dialect, name) = opid.trim().split_once('.')?;
+                let is_identifier = |s| Identifier::try_from(s).is_ok();
+                if !is_identifier(dialect) || !is_identifier(name) {
+                    return None;
+                }
+                let opid = OpId {
+                    dialect: DialectName::new(dialect),
+                    name: OpName::new(name),
+                };
+                Some((opid, weight.trim().parse().ok()?))
+            });
+            let Some((opid, weight)) = parsed else {
+                return input_err_noloc!(CostWeightsErr::Malformed(entry.to_string()));
+            };
+            self.weights.insert(opid, weight);
+        }
+        Ok(self)
+    }
+
+    /// The cost model in effect at `op`: the [DataLayout] in effect at `op` (see
+    /// [DataLayout::of]), with the weights of the closest (starting from `op` itself)
+    /// operation, enclosing `op`, that has an [ATTR_KEY_COST_WEIGHTS] attribute.
+    pub fn of(ctx: &Context, op: Ptr<Operation>) -> Result<Self> {
+        let model = Self::new(DataLayout::of(ctx, op)?);
+        let mut op = Some(op);
+        while let Some(cur_op) = op {
+            let cur_op_ref = cur_op.deref(ctx);
+            if let Some(spec) = cur_op_ref.attributes.0.get(&*ATTR_KEY_COST_WEIGHTS) {
+                let Some(spec) = spec.downcast_ref::<StringAttr>() else {
+                    return input_err!(cur_op_ref.loc(), CostWeightsErr::NotAString);
+                };
+                return model
+                    .with_weights_spec(&String::from(spec.clone()))
+                    .map_err(|mut err| {
+                        err.set_loc(cur_op_ref.loc());
+                        err
+                    });
+            }
+            op = cur_op_ref
+                .container()
+                .and_then(|block| block.deref(ctx).container())
+                .map(|region| region.deref(ctx).parent_op());
+        }
+        Ok(model)
+    }
+
+    /// Set the weights of `op` (typically a module) to those of `self`.
+    /// The [DataLayout] isn't set, use [DataLayout::set] for that.
+    pub fn set(&self, ctx: &Context, op: Ptr<Operation>) {
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_COST_WEIGHTS, StringAttr::new(self.to_string()));
+    }
+}
+
+impl Display for TargetCostModel {
+    /// The weights, in the [ATTR_KEY_COST_WEIGHTS] format.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut weights: Vec<_> = self
+            .weights
+            .iter()
+            .map(|(opid, weight)| format!("{}={}", opid, weight))
+            .collect();
+        weights.sort();
+        write!(f, "{}", weights.join(","))
+    }
+}
+
+impl CostModel for TargetCostModel {
+    fn layout(&self) -> &DataLayout {
+        &self.layout
+    }
+
+    fn op_cost(&self, ctx: &Context, op: Ptr<Operation>) -> u64 {
+        let opid = op.deref(ctx).opid();
+        if let Some(weight) = self.weight(&opid) {
+            return weight;
+        }
+        let op_obj = Operation::op(op, ctx);
+        op_cast::<dyn OpCostInterface>(&*op_obj)
+            .map_or(DEFAULT_OP_COST, |op_cost| op_cost.cost(ctx, self))
+    }
+}
@@ -26,7 +26,7 @@ fn set_name_from_attr_map(
     name: Identifier,
 ) {
     let name_attr: AttrObj = IdentifierAttr::new(name).into();
-    match attributes.0.entry(ATTR_KEY_DEBUG_INFO.clone()) {
+    match attributes.0.entry(*ATTR_KEY_DEBUG_INFO) {
         hash_map::Entry::Occupied(mut occupied) => {
             let di_dict = occupied.get_mut().downcast_mut::<DictAttr>().unwrap();
             let expect_msg = "Existing attribute entry for result names incorrect";
@@ -41,11 +41,7 @@ fn set_name_from_attr_map(
             let mut names = Vec::new_init(max_idx, |_idx| UnitAttr::new().into());
             names[idx] = name_attr;
             vacant.insert(
-                DictAttr::new(vec![(
-                    DEBUG_INFO_KEY_NAME.clone(),
-                    VecAttr::new(names).into(),
-                )])
-                .into(),
+                DictAttr::new(vec![(*DEBUG_INFO_KEY_NAME, VecAttr::new(names).into())]).into(),
             );
         }
     }
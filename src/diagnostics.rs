@@ -0,0 +1,371 @@
+//! Diagnostics, rendered for humans or for tools.
+//!
+//! A [Diagnostic] is a [struct@Error] (or a warning, or a note) together with the
+//! source position it refers to. Diagnostics can be rendered in the usual
+//! human readable format, or as [JSON](DiagnosticFormat::Json), so that IDEs and
+//! CI systems can annotate the source without having to parse text.
+//!
+//! Each diagnostic is rendered as a single line JSON object:
+//! ```json
+//! {"severity":"error","message":"...","file":"input.pliron","line":3,"col":5,
+//!  "span":{"start":{"line":3,"col":5},"end":{"line":3,"col":5}},"notes":[...]}
+//! ```
+//! `file`, `line`, `col` and `span` are `null` when the position isn't known.
+//! pliron [Location]s are points, so the span of a diagnostic covers all the
+//! positions (in the same file) of a [fused](Location::Fused) location.
+//! `notes` are diagnostics too, with severity `note`.
+
+use std::{fmt::Display, str::FromStr};
+
+use combine::stream::position::SourcePosition;
+use thiserror::Error;
+
+use crate::{
+    context::Context,
+    location::{Location, Source},
+    printable::{self, Printable},
+    result::Error,
+};
+
+/// How serious a [Diagnostic] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A message about the program being compiled, at a [Location].
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub loc: Location,
+    /// Additional information, with [Severity::Note].
+    pub notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// Create a new [Diagnostic] without notes.
+    pub fn new(severity: Severity, message: impl Into<String>, loc: Location) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            loc,
+            notes: vec![],
+        }
+    }
+
+    /// Add a note, at `loc`, to this diagnostic.
+    pub fn with_note(mut self, message: impl Into<String>, loc: Location) -> Self {
+        self.notes
+            .push(Diagnostic::new(Severity::Note, message, loc));
+        self
+    }
+
+    /// Create a [Diagnostic] reporting `err`.
+    /// Errors wrapping other errors are reported at the innermost known location.
+    /// The callers of a [call site](Location::CallSite) location are added as notes.
+    pub fn from_error(err: &Error) -> Self {
+        let mut message = err.err.to_string();
+        let mut loc = err.loc.clone();
+        let mut inner = &err.err;
+        while let Some(inner_err) = inner.downcast_ref::<Error>() {
+            message = inner_err.err.to_string();
+            if inner_err.loc != Location::Unknown {
+                loc = inner_err.loc.clone();
+            }
+            inner = &inner_err.err;
+        }
+
+        let mut notes = vec![];
+        let mut site = &loc;
+        while let Location::CallSite { caller, .. } = site {
+            notes.push(Diagnostic::new(
+                Severity::Note,
+                "called from here",
+                (**caller).clone(),
+            ));
+            site = caller;
+        }
+
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            loc,
+            notes,
+        }
+    }
+
+    /// Render this diagnostic in `format`.
+    pub fn render(&self, ctx: &Context, format: DiagnosticFormat) -> String {
+        match format {
+            DiagnosticFormat::Human => self.disp(ctx).to_string(),
+            DiagnosticFormat::Json => self.to_json(ctx),
+        }
+    }
+
+    /// Render this diagnostic as a single line JSON object.
+    /// See [module](self) documentation for the format.
+    pub fn to_json(&self, ctx: &Context) -> String {
+        let position = primary_position(&self.loc);
+        let file = position
+            .and_then(|(src, _)| file_name(ctx, src))
+            .map_or("null".to_string(), |file| json_string(&file));
+        let (line, col) = position.map_or(("null".to_string(), "null".to_string()), |(_, pos)| {
+            (pos.line.to_string(), pos.column.to_string())
+        });
+        let span = position.map_or("null".to_string(), |(src, pos)| {
+            let (start, end) = span(&self.loc, src, pos);
+            format!(
+                r#"{{"start":{{"line":{},"col":{}}},"end":{{"line":{},"col":{}}}}}"#,
+                start.line, start.column, end.line, end.column
+            )
+        });
+        let notes = self
+            .notes
+            .iter()
+            .map(|note| note.to_json(ctx))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"severity":"{}","message":{},"file":{},"line":{},"col":{},"span":{},"notes":[{}]}}"#,
+            self.severity,
+            json_string(&self.message),
+            file,
+            line,
+            col,
+            span,
+            notes
+        )
+    }
+}
+
+impl Printable for Diagnostic {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.loc.print(ctx, state),
+            self.severity,
+            self.message
+        )?;
+        for note in &self.notes {
+            write!(f, "\n  {}", note.print(ctx, state))?;
+        }
+        Ok(())
+    }
+}
+
+/// Sort `diagnostics` by the file, line and column they're reported at.
+/// The sort is stable, and diagnostics without a known position come last.
+pub fn sort_by_location(ctx: &Context, diagnostics: &mut [Diagnostic]) {
+    diagnostics.sort_by_cached_key(|diagnostic| {
+        primary_position(&diagnostic.loc).map_or((true, String::new(), 0, 0), |(src, pos)| {
+            let file = file_name(ctx, src).unwrap_or_default();
+            (false, file, pos.line, pos.column)
+        })
+    });
+}
+
+/// The position that a diagnostic at `loc` is reported at.
+fn primary_position(loc: &Location) -> Option<(Source, SourcePosition)> {
+    match loc {
+        Location::SrcPos { src, pos } => Some((*src, *pos)),
+        Location::Fused { locations, .. } => locations.iter().find_map(primary_position),
+        Location::Named { child_loc, .. } => primary_position(child_loc),
+        Location::CallSite { callee, .. } => primary_position(callee),
+        Location::Unknown => None,
+    }
+}
+
+/// The first and last positions, in `src`, of `loc`,
+/// whose [primary position](primary_position) is `pos`.
+fn span(loc: &Location, src: Source, pos: SourcePosition) -> (SourcePosition, SourcePosition) {
+    fn positions(loc: &Location, src: Source, res: &mut Vec<SourcePosition>) {
+        match loc {
+            Location::SrcPos { src: pos_src, pos } if *pos_src == src => res.push(*pos),
+            Location::SrcPos { .. } | Location::Unknown => (),
+            Location::Fused { locations, .. } => {
+                for loc in locations {
+                    positions(loc, src, res);
+                }
+            }
+            Location::Named { child_loc, .. } => positions(child_loc, src, res),
+            Location::CallSite { callee, .. } => positions(callee, src, res),
+        }
+    }
+    let mut res = vec![pos];
+    positions(loc, src, &mut res);
+    let key = |pos: &&SourcePosition| (pos.line, pos.column);
+    (
+        *res.iter().min_by_key(key).unwrap(),
+        *res.iter().max_by_key(key).unwrap(),
+    )
+}
+
+/// The file name of `src`, if it's a file.
+fn file_name(ctx: &Context, src: Source) -> Option<String> {
+    match src {
+        Source::File(_) => Some(src.disp(ctx).to_string()),
+        Source::InMemory => None,
+    }
+}
+
+/// Quote and escape `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Formats that [Diagnostic]s can be rendered in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// Human readable text.
+    #[default]
+    Human,
+    /// JSON, one object per diagnostic. See [module](self) documentation.
+    Json,
+}
+
+impl DiagnosticFormat {
+    /// Render `err` in this format. The human readable format is
+    /// the same as [displaying](Printable::disp) the error.
+    pub fn render_error(&self, ctx: &Context, err: &Error) -> String {
+        match self {
+            DiagnosticFormat::Human => err.disp(ctx).to_string(),
+            DiagnosticFormat::Json => Diagnostic::from_error(err).to_json(ctx),
+        }
+    }
+}
+
+impl Display for DiagnosticFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticFormat::Human => write!(f, "human"),
+            DiagnosticFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown diagnostic format {0}, expected human or json")]
+pub struct UnknownDiagnosticFormatErr(pub String);
+
+impl FromStr for DiagnosticFormat {
+    type Err = UnknownDiagnosticFormatErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(DiagnosticFormat::Human),
+            "json" => Ok(DiagnosticFormat::Json),
+            _ => Err(UnknownDiagnosticFormatErr(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use combine::stream::position::SourcePosition;
+    use expect_test::expect;
+    use thiserror::Error;
+
+    use crate::{
+        context::Context,
+        input_error,
+        location::{Location, Source},
+    };
+
+    use super::{Diagnostic, DiagnosticFormat, Severity};
+
+    #[derive(Debug, Error)]
+    #[error("Test \"error\"\nwith two lines")]
+    pub struct TestErr;
+
+    fn src_pos(src: Source, line: i32, column: i32) -> Location {
+        Location::SrcPos {
+            src,
+            pos: SourcePosition { line, column },
+        }
+    }
+
+    #[test]
+    fn json_error() {
+        let ctx = &mut Context::new();
+        let src = Source::new_from_file(ctx, "/tmp/test.pliron".into());
+
+        let err = input_error!(src_pos(src, 1, 1), TestErr);
+        let wrapped_err = input_error!(Location::Unknown, err);
+        expect![[r#"{"severity":"error","message":"Test \"error\"\nwith two lines","file":"/tmp/test.pliron","line":1,"col":1,"span":{"start":{"line":1,"col":1},"end":{"line":1,"col":1}},"notes":[]}"#]]
+            .assert_eq(&DiagnosticFormat::Json.render_error(ctx, &wrapped_err));
+
+        let err = input_error!(Location::Unknown, TestErr);
+        expect![[r#"{"severity":"error","message":"Test \"error\"\nwith two lines","file":null,"line":null,"col":null,"span":null,"notes":[]}"#]]
+            .assert_eq(&DiagnosticFormat::Json.render_error(ctx, &err));
+    }
+
+    #[test]
+    fn json_span_and_notes() {
+        let ctx = &mut Context::new();
+        let src = Source::new_from_file(ctx, "/tmp/test.pliron".into());
+
+        let fused = Location::Fused {
+            metadata: None,
+            locations: vec![src_pos(src, 3, 5), src_pos(src, 2, 7), src_pos(src, 4, 1)],
+        };
+        let loc = Location::CallSite {
+            callee: Box::new(fused),
+            caller: Box::new(src_pos(src, 10, 3)),
+        };
+        let diag = Diagnostic::from_error(&input_error!(loc, TestErr))
+            .with_note("in memory", src_pos(Source::InMemory, 1, 1));
+        expect![[r#"{"severity":"error","message":"Test \"error\"\nwith two lines","file":"/tmp/test.pliron","line":3,"col":5,"span":{"start":{"line":2,"col":7},"end":{"line":4,"col":1}},"notes":[{"severity":"note","message":"called from here","file":"/tmp/test.pliron","line":10,"col":3,"span":{"start":{"line":10,"col":3},"end":{"line":10,"col":3}},"notes":[]},{"severity":"note","message":"in memory","file":null,"line":1,"col":1,"span":{"start":{"line":1,"col":1},"end":{"line":1,"col":1}},"notes":[]}]}"#]]
+            .assert_eq(&diag.render(ctx, DiagnosticFormat::Json));
+        expect![[r#"
+            [callsite(fused[/tmp/test.pliron: line: 3, column: 5, /tmp/test.pliron: line: 2, column: 7, /tmp/test.pliron: line: 4, column: 1] at /tmp/test.pliron: line: 10, column: 3)] error: Test "error"
+            with two lines
+              [/tmp/test.pliron: line: 10, column: 3] note: called from here
+              [<in-memory>: line: 1, column: 1] note: in memory"#]]
+        .assert_eq(&diag.render(ctx, DiagnosticFormat::Human));
+        assert_eq!(diag.notes[0].severity, Severity::Note);
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(
+            "json".parse::<DiagnosticFormat>().unwrap(),
+            DiagnosticFormat::Json
+        );
+        assert_eq!(DiagnosticFormat::Human.to_string(), "human");
+        expect!["Unknown diagnostic format xml, expected human or json"]
+            .assert_eq(&"xml".parse::<DiagnosticFormat>().unwrap_err().to_string());
+    }
+}
@@ -1,24 +1,36 @@
 //! [Dialect]s are a mechanism to group related [Op](crate::op::Op)s, [Type](crate::type::Type)s
 //! and [Attribute](crate::attribute::Attribute)s.
-use std::{fmt::Display, ops::Deref};
+//!
+//! Each is registered in its dialect under an id. Registering a different one under the
+//! same id (including a [dynamic](crate::dynamic) one) is a [DuplicateRegistrationErr],
+//! listing where both are registered, rather than one silently shadowing the other.
+use std::{any, fmt::Display, hash::Hash, ops::Deref, panic};
 
 use combine::Parser;
 use rustc_hash::FxHashMap;
+use thiserror::Error;
 
 use crate::{
-    attribute::{AttrId, AttrParserFn},
+    arg_err_noloc,
+    attribute::{AttrId, AttrParserFn, VerbatimAttrParserFn},
     context::Context,
+    dynamic::DynamicDefs,
     identifier::Identifier,
     impl_printable_for_display, input_err,
-    location::{Located, Location},
-    op::{OpId, OpObj},
-    parsable::{IntoParseResult, Parsable, ParseResult, ParserFn, StateStream},
+    location::Located,
+    op::{OP_INTERFACE_NAMES, OP_INTERFACE_VERIFIERS_MAP, OpId, OpParserFn},
+    parsable::{
+        IntoParseResult, Parsable, ParseResult, StateStream, TopLevelParserFn, TopLevelPrinterFn,
+    },
     printable::{self, Printable},
+    result::Result,
+    transforms::fold::ConstantMaterializerFn,
     r#type::{TypeId, TypeParserFn},
+    utils::{edit_distance::closest_matches, trait_cast},
 };
 
 /// Dialect name: Safe wrapper around a String.
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct DialectName(Identifier);
 
 impl DialectName {
@@ -42,6 +54,23 @@ impl Display for DialectName {
     }
 }
 
+/// A dialect name that was parsed, but isn't registered in the [Context].
+/// Registered dialect names close to it are suggested.
+#[derive(Error, Debug)]
+#[error("Unregistered dialect {name}{}", did_you_mean(suggestions))]
+pub struct UnregisteredDialectErr {
+    pub name: String,
+    pub suggestions: Vec<String>,
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean {}?", suggestions.join(" or "))
+    }
+}
+
 impl Parsable for DialectName {
     type Arg = ();
     type Parsed = DialectName;
@@ -59,10 +88,22 @@ impl Parsable for DialectName {
             let loc = loc.clone();
             combine::parser(move |state_stream: &mut StateStream<'a>| {
                 let dialect_name = DialectName::new(&dialect_name);
-                if state_stream.state.ctx.dialects.contains_key(&dialect_name) {
+                let ctx = &state_stream.state.ctx;
+                if ctx.dialects.contains_key(&dialect_name) || ctx.allows_unregistered_dialects() {
                     Ok(dialect_name).into_parse_result()
                 } else {
-                    input_err!(loc.clone(), "Unregistered dialect {}", *dialect_name)?
+                    let dialects = &state_stream.state.ctx.dialects;
+                    let suggestions = closest_matches(
+                        &dialect_name,
+                        dialects.keys().map(|name| -> &str { name }),
+                    );
+                    input_err!(
+                        loc.clone(),
+                        UnregisteredDialectErr {
+                            name: dialect_name.to_string(),
+                            suggestions: suggestions.into_iter().map(String::from).collect(),
+                        }
+                    )?
                 }
             })
         });
@@ -71,24 +112,156 @@ impl Parsable for DialectName {
 }
 
 impl Deref for DialectName {
-    type Target = String;
+    type Target = Identifier;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
+/// What registers an [Op](crate::op::Op), [Type](crate::type::Type) or
+/// [Attribute](crate::attribute::Attribute) in a [Dialect]: a Rust type, or a
+/// [dynamic](crate::dynamic) definition, and where in the source it's registered.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Registrant {
+    /// The Rust type, and its name, or `None` for dynamic definitions.
+    rust_type: Option<(any::TypeId, &'static str)>,
+    location: &'static panic::Location<'static>,
+}
+
+impl Registrant {
+    /// The Rust type `T`, registered by the caller.
+    #[track_caller]
+    pub(crate) fn rust_type<T: 'static>() -> Self {
+        Registrant {
+            rust_type: Some((any::TypeId::of::<T>(), any::type_name::<T>())),
+            location: panic::Location::caller(),
+        }
+    }
+
+    /// A dynamic definition, registered by the caller.
+    #[track_caller]
+    pub(crate) fn dynamic() -> Self {
+        Registrant {
+            rust_type: None,
+            location: panic::Location::caller(),
+        }
+    }
+
+    /// Is this the same registrant as `other`? Only Rust types can be
+    /// registered more than once, each dynamic definition being distinct.
+    fn is_same(&self, other: &Self) -> bool {
+        matches!((self.rust_type, other.rust_type), (Some((a, _)), Some((b, _))) if a == b)
+    }
+}
+
+impl Display for Registrant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.rust_type {
+            Some((_, name)) => write!(f, "`{name}`")?,
+            None => write!(f, "a dynamic definition")?,
+        }
+        write!(f, " at {}", self.location)
+    }
+}
+
+/// Two different entities registered with the same id.
+#[derive(Debug, Error)]
+#[error("{kind} {id} is registered twice: by {first}, and by {second}")]
+pub struct DuplicateRegistrationErr {
+    pub kind: &'static str,
+    pub id: String,
+    pub first: String,
+    pub second: String,
+}
+
+/// What registered each of the entities of a [Dialect], by their ids.
+#[derive(Default)]
+struct Registrants {
+    ops: FxHashMap<OpId, Registrant>,
+    types: FxHashMap<TypeId, Registrant>,
+    attributes: FxHashMap<AttrId, Registrant>,
+}
+
+/// Record that `registrant` registers `id` in `registrants`, unless a different
+/// registrant already did, which is a [DuplicateRegistrationErr].
+fn record_registrant<Id: Hash + Eq + Display>(
+    registrants: &mut FxHashMap<Id, Registrant>,
+    kind: &'static str,
+    id: Id,
+    registrant: Registrant,
+) -> Result<()> {
+    match registrants.get(&id) {
+        Some(first) if !first.is_same(&registrant) => {
+            arg_err_noloc!(DuplicateRegistrationErr {
+                kind,
+                id: id.to_string(),
+                first: first.to_string(),
+                second: registrant.to_string(),
+            })
+        }
+        Some(_) => Ok(()),
+        None => {
+            registrants.insert(id, registrant);
+            Ok(())
+        }
+    }
+}
+
+/// Check that `registrant` implements every interface implemented for
+/// [Op](crate::op::Op)s with id `op`. Interface verifiers are looked up by [OpId],
+/// so if another Rust type with the same id implements an interface, its verifier would
+/// be run on (and fail to cast) the ops of `registrant`. That's a [DuplicateRegistrationErr],
+/// even if the other type isn't registered.
+fn check_op_interfaces(op: OpId, registrant: &Registrant) -> Result<()> {
+    let Some(interfaces) = OP_INTERFACE_VERIFIERS_MAP.get(&op) else {
+        return Ok(());
+    };
+    for (interface, _) in interfaces {
+        let implemented = registrant
+            .rust_type
+            .is_some_and(|(ty, _)| trait_cast::type_casts_to(ty, *interface));
+        if !implemented {
+            return arg_err_noloc!(DuplicateRegistrationErr {
+                kind: "Op",
+                id: op.to_string(),
+                first: format!(
+                    "a Rust type implementing `{}` for it",
+                    OP_INTERFACE_NAMES[interface]
+                ),
+                second: registrant.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
 /// A collection of Types and Ops.
 /// Dialects are identified by their names.
 pub struct Dialect {
     /// Name of this dialect.
     pub name: DialectName,
     /// Ops that are part of this dialect.
-    pub(crate) ops: FxHashMap<OpId, ParserFn<Vec<(Identifier, Location)>, OpObj>>,
+    pub(crate) ops: FxHashMap<OpId, OpParserFn>,
     /// Types that are part of this dialect.
     pub(crate) types: FxHashMap<TypeId, TypeParserFn>,
     /// Attributes that are part of this dialect.
     pub(crate) attributes: FxHashMap<AttrId, AttrParserFn>,
+    /// Parser for the payload of verbatim attributes, `#dialect<payload>`.
+    pub(crate) verbatim_attr_parser: Option<VerbatimAttrParserFn>,
+    /// Definitions of the dynamic entities that are part of this dialect.
+    pub(crate) dynamic_defs: DynamicDefs,
+    /// What registered the entities of this dialect.
+    registrants: Registrants,
+    /// Creates constants of this dialect when folding its operations.
+    pub(crate) constant_materializer: Option<ConstantMaterializerFn>,
+    /// Handles top-level constructs of this dialect, `!dialect<payload>`.
+    pub(crate) top_level_parser: Option<TopLevelParserFn>,
+    /// Prints top-level constructs of this dialect.
+    pub(crate) top_level_printer: Option<TopLevelPrinterFn>,
+    /// How the attributes and types of this dialect are serialized.
+    #[cfg(feature = "serde")]
+    pub(crate) serdes: crate::serializable::DialectSerdes,
 }
 
 impl Printable for Dialect {
@@ -110,40 +283,166 @@ impl Dialect {
             ops: FxHashMap::default(),
             types: FxHashMap::default(),
             attributes: FxHashMap::default(),
+            verbatim_attr_parser: None,
+            dynamic_defs: DynamicDefs::default(),
+            registrants: Registrants::default(),
+            constant_materializer: None,
+            top_level_parser: None,
+            top_level_printer: None,
+            #[cfg(feature = "serde")]
+            serdes: Default::default(),
         }
     }
 
     /// Register this dialect if not already registered.
     pub fn register(self, ctx: &mut Context) {
-        ctx.dialects.entry(self.name.clone()).or_insert(self);
+        ctx.dialects.entry(self.name).or_insert(self);
     }
 
-    /// Add an [Op](crate::op::Op) to this dialect.
+    /// Add an [Op](crate::op::Op), registered by `registrant`, to this dialect.
+    /// Fails, leaving the dialect unchanged, if a different registrant already added `op`,
+    /// or if another Rust type implements interfaces for `op`.
     pub(crate) fn add_op(
         &mut self,
         op: OpId,
-        op_parser: ParserFn<Vec<(Identifier, Location)>, OpObj>,
-    ) {
+        op_parser: OpParserFn,
+        registrant: Registrant,
+    ) -> Result<()> {
         assert!(op.dialect == self.name);
+        check_op_interfaces(op, &registrant)?;
+        record_registrant(&mut self.registrants.ops, "Op", op, registrant)?;
         self.ops.insert(op, op_parser);
+        Ok(())
     }
 
-    /// Add a [Type](crate::type::Type) to this dialect.
-    pub(crate) fn add_type(&mut self, ty: TypeId, ty_parser: TypeParserFn) {
+    /// Add a [Type](crate::type::Type), registered by `registrant`, to this dialect.
+    /// Fails, leaving the dialect unchanged, if a different registrant already added `ty`.
+    pub(crate) fn add_type(
+        &mut self,
+        ty: TypeId,
+        ty_parser: TypeParserFn,
+        registrant: Registrant,
+    ) -> Result<()> {
         assert!(ty.dialect == self.name);
+        record_registrant(&mut self.registrants.types, "Type", ty, registrant)?;
         self.types.insert(ty, ty_parser);
+        Ok(())
     }
 
-    /// Add an [Attribute](crate::attribute::Attribute) to this dialect.
-    pub(crate) fn add_attr(&mut self, attr: AttrId, attr_parser: AttrParserFn) {
+    /// Add an [Attribute](crate::attribute::Attribute), registered by `registrant`,
+    /// to this dialect. Fails, leaving the dialect unchanged, if a different registrant
+    /// already added `attr`.
+    pub(crate) fn add_attr(
+        &mut self,
+        attr: AttrId,
+        attr_parser: AttrParserFn,
+        registrant: Registrant,
+    ) -> Result<()> {
         assert!(attr.dialect == self.name);
+        record_registrant(
+            &mut self.registrants.attributes,
+            "Attribute",
+            attr,
+            registrant,
+        )?;
         self.attributes.insert(attr, attr_parser);
+        Ok(())
+    }
+
+    /// Parse verbatim attributes of this dialect, `#dialect<payload>`, with `parser`.
+    /// See [attribute](crate::attribute) module documentation.
+    pub fn set_verbatim_attr_parser(&mut self, parser: VerbatimAttrParserFn) {
+        self.verbatim_attr_parser = Some(parser);
+    }
+
+    /// Create constants with `materializer` when folding operations of this
+    /// dialect to constant values. See [fold](crate::transforms::fold).
+    pub fn set_constant_materializer(&mut self, materializer: ConstantMaterializerFn) {
+        self.constant_materializer = Some(materializer);
+    }
+
+    /// Handle top-level constructs of this dialect, `!dialect<payload>`, written
+    /// before the module in a source, with `parser`. See [parse_source](crate::parsable::parse_source).
+    pub fn set_top_level_parser(&mut self, parser: TopLevelParserFn) {
+        self.top_level_parser = Some(parser);
+    }
+
+    /// Print top-level constructs of this dialect with `printer`.
+    /// See [print_source](crate::parsable::print_source).
+    pub fn set_top_level_printer(&mut self, printer: TopLevelPrinterFn) {
+        self.top_level_printer = Some(printer);
     }
 
     /// This Dialect's name.
     pub fn name(&self) -> &DialectName {
         &self.name
     }
+
+    /// The [Op](crate::op::Op)s registered in this dialect, in no particular order.
+    pub fn op_ids(&self) -> impl Iterator<Item = &OpId> {
+        self.ops.keys()
+    }
+
+    /// The [Type](crate::type::Type)s registered in this dialect, in no particular order.
+    pub fn type_ids(&self) -> impl Iterator<Item = &TypeId> {
+        self.types.keys()
+    }
+
+    /// The [Attribute](crate::attribute::Attribute)s registered in this dialect,
+    /// in no particular order.
+    pub fn attr_ids(&self) -> impl Iterator<Item = &AttrId> {
+        self.attributes.keys()
+    }
+}
+
+/// A dialect, along with everything it needs registered in a [Context].
+/// [Loading](DialectPlugin::load) a plugin registers the dialect's [Op](crate::op::Op)s,
+/// [Type](crate::type::Type)s and [Attribute](crate::attribute::Attribute)s, and then
+/// runs its [init](DialectPlugin::init) hook, which can register the rest of what the
+/// dialect needs (such as canonicalization patterns or constant materializers).
+/// So users need to just load the plugin, rather than remember separate registration calls.
+pub trait DialectPlugin: 'static {
+    /// Name of the dialect.
+    fn name(&self) -> DialectName;
+
+    /// Dialects to be loaded before this one.
+    fn dependencies(&self) -> Vec<Box<dyn DialectPlugin>> {
+        vec![]
+    }
+
+    /// Register the dialect, and its [Op](crate::op::Op)s,
+    /// [Type](crate::type::Type)s and [Attribute](crate::attribute::Attribute)s.
+    fn register(&self, ctx: &mut Context);
+
+    /// Run after [registration](Self::register).
+    fn init(&self, _ctx: &mut Context) {}
+
+    /// Run when the [Context] is dropped.
+    /// Plugins are deinitialized in the reverse order of their loading.
+    fn deinit(&self, _ctx: &mut Context) {}
+
+    /// Load this plugin (and its [dependencies](Self::dependencies)) into `ctx`,
+    /// unless it's already loaded. Returns `true` if it is loaded now.
+    fn load(self, ctx: &mut Context) -> bool
+    where
+        Self: Sized,
+    {
+        load_plugin(ctx, Box::new(self))
+    }
+}
+
+fn load_plugin(ctx: &mut Context, plugin: Box<dyn DialectPlugin>) -> bool {
+    let name = plugin.name();
+    if ctx.dialect_plugins.iter().any(|p| p.name() == name) {
+        return false;
+    }
+    for dep in plugin.dependencies() {
+        load_plugin(ctx, dep);
+    }
+    plugin.register(ctx);
+    plugin.init(ctx);
+    ctx.dialect_plugins.push(plugin);
+    true
 }
 
 #[cfg(test)]
@@ -159,7 +458,9 @@ mod test {
         printable::Printable,
     };
 
-    use super::DialectName;
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Dialect, DialectName, DialectPlugin};
 
     #[test]
     fn parse_dialect_name() {
@@ -188,4 +489,70 @@ mod test {
         let parsed = DialectName::parser(()).parse(state_stream).unwrap().0;
         assert_eq!(parsed.disp(&ctx).to_string(), "builtin");
     }
+
+    struct LoggingPlugin {
+        name: &'static str,
+        deps: Vec<&'static str>,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl DialectPlugin for LoggingPlugin {
+        fn name(&self) -> DialectName {
+            DialectName::new(self.name)
+        }
+
+        fn dependencies(&self) -> Vec<Box<dyn DialectPlugin>> {
+            self.deps
+                .iter()
+                .map(|dep| {
+                    Box::new(LoggingPlugin {
+                        name: dep,
+                        deps: vec![],
+                        log: self.log.clone(),
+                    }) as Box<dyn DialectPlugin>
+                })
+                .collect()
+        }
+
+        fn register(&self, ctx: &mut Context) {
+            Dialect::new(self.name()).register(ctx);
+            self.log
+                .borrow_mut()
+                .push(format!("register {}", self.name));
+        }
+
+        fn init(&self, ctx: &mut Context) {
+            assert!(ctx.dialects.contains_key(&self.name()));
+            self.log.borrow_mut().push(format!("init {}", self.name));
+        }
+
+        fn deinit(&self, _ctx: &mut Context) {
+            self.log.borrow_mut().push(format!("deinit {}", self.name));
+        }
+    }
+
+    #[test]
+    fn dialect_plugin_hooks() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let plugin = |name, deps| LoggingPlugin {
+            name,
+            deps,
+            log: log.clone(),
+        };
+
+        let mut ctx = Context::new();
+        assert!(plugin("high", vec!["low"]).load(&mut ctx));
+        assert!(!plugin("low", vec![]).load(&mut ctx));
+        assert!(!plugin("high", vec!["low"]).load(&mut ctx));
+        drop(ctx);
+
+        expect![[r#"
+            register low
+            init low
+            register high
+            init high
+            deinit high
+            deinit low"#]]
+        .assert_eq(&log.borrow().join("\n"));
+    }
 }
@@ -0,0 +1,29 @@
+//! [Attribute]s of the arith dialect.
+
+use pliron::derive::{def_attribute, format_attribute};
+
+use crate::{attribute::Attribute, context::Context, impl_verify_succ, parsable::Parsable};
+
+/// The predicate of an integer comparison ([CmpIOp](super::ops::CmpIOp)).
+/// Comparisons prefixed `S` interpret their operands as signed integers,
+/// and those prefixed `U`, as unsigned integers.
+#[def_attribute("arith.cmpi_predicate")]
+#[format_attribute]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CmpIPredicateAttr {
+    EQ,
+    NE,
+    SLT,
+    SLE,
+    SGT,
+    SGE,
+    ULT,
+    ULE,
+    UGT,
+    UGE,
+}
+impl_verify_succ!(CmpIPredicateAttr);
+
+pub fn register(ctx: &mut Context) {
+    CmpIPredicateAttr::register_attr_in_dialect(ctx, CmpIPredicateAttr::parser_fn);
+}
@@ -0,0 +1,124 @@
+//! Folding arith dialect operations.
+//!
+//! [Foldable] is implemented for the integer binary operations and [CmpIOp], with
+//! [IntegerAttr] constant operands, which fold to the constant they
+//! [evaluate](crate::utils::const_eval) to. The dialect has no poison value, so
+//! operations evaluating to poison (such as shifts by the bit width or more), and
+//! those with undefined behaviour (such as division by zero), aren't folded.
+//!
+//! Constants are [materialized](materialize_constant) as [ConstantOp]s.
+
+use std::cmp::Ordering;
+
+use pliron::derive::op_interface_impl;
+
+use crate::{
+    attribute::AttrObj,
+    builtin::{
+        attr_interfaces::TypedAttrInterface, attributes::IntegerAttr,
+        op_interfaces::OneResultInterface, types::IntegerType,
+    },
+    context::{Context, Ptr},
+    op::Op,
+    operation::Operation,
+    transforms::fold::{Foldable, OpFoldResult},
+    r#type::{TypeObj, TypePtr},
+    utils::{
+        apint::{APInt, bw},
+        const_eval::{ConstValue, IntBinOpcode, OverflowFlags, eval_int_bin_op},
+    },
+};
+
+use super::{
+    attributes::CmpIPredicateAttr,
+    ops::{
+        AddIOp, AndIOp, CmpIOp, ConstantOp, DivSIOp, DivUIOp, MulIOp, OrIOp, RemSIOp, RemUIOp,
+        ShLIOp, ShRSIOp, ShRUIOp, SubIOp, XOrIOp,
+    },
+};
+
+/// Create an operation defining the constant `value` of type `ty`.
+/// This is the constant materializer of the arith dialect.
+pub fn materialize_constant(
+    ctx: &mut Context,
+    value: AttrObj,
+    ty: Ptr<TypeObj>,
+) -> Option<Ptr<Operation>> {
+    let value = value.downcast_ref::<IntegerAttr>()?;
+    (value.get_type() == ty).then(|| ConstantOp::new(ctx, value.clone()).operation())
+}
+
+/// The values of the two `operands`, if they're [IntegerAttr] constants of the same width.
+fn int_operands(operands: &[Option<AttrObj>]) -> Option<(APInt, APInt)> {
+    let int_value = |operand: &Option<AttrObj>| {
+        let operand = operand.as_ref()?.downcast_ref::<IntegerAttr>()?;
+        Some(APInt::from(operand.clone()))
+    };
+    let [lhs, rhs] = operands else {
+        return None;
+    };
+    let (lhs, rhs) = (int_value(lhs)?, int_value(rhs)?);
+    (lhs.bw() == rhs.bw()).then_some((lhs, rhs))
+}
+
+/// Fold `op` to `result`, an [IntegerAttr] of its result type.
+fn fold_to_int<T: OneResultInterface>(ctx: &Context, op: &T, result: APInt) -> Vec<OpFoldResult> {
+    let Ok(int_ty) = TypePtr::<IntegerType>::from_ptr(op.result_type(ctx), ctx) else {
+        return vec![];
+    };
+    vec![OpFoldResult::Attribute(
+        IntegerAttr::new(int_ty, result).into(),
+    )]
+}
+
+/// Implement [Foldable] for each of the given integer binary ops,
+/// evaluating them as their [IntBinOpcode].
+macro_rules! impl_int_bin_op_fold {
+    ($($op_name:ident => $opcode:ident),*) => {
+        $(
+            #[op_interface_impl]
+            impl Foldable for $op_name {
+                fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult> {
+                    let Some((lhs, rhs)) = int_operands(operands) else {
+                        return vec![];
+                    };
+                    let folded =
+                        eval_int_bin_op(IntBinOpcode::$opcode, &lhs, &rhs, OverflowFlags::default());
+                    match folded {
+                        Some(ConstValue::Int(result)) => fold_to_int(ctx, self, result),
+                        Some(ConstValue::Poison) | None => vec![],
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_int_bin_op_fold!(
+    AddIOp => Add, SubIOp => Sub, MulIOp => Mul, DivSIOp => SDiv, DivUIOp => UDiv,
+    RemSIOp => SRem, RemUIOp => URem, AndIOp => And, OrIOp => Or, XOrIOp => Xor,
+    ShLIOp => Shl, ShRSIOp => AShr, ShRUIOp => LShr
+);
+
+#[op_interface_impl]
+impl Foldable for CmpIOp {
+    fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult> {
+        let Some((lhs, rhs)) = int_operands(operands) else {
+            return vec![];
+        };
+        use Ordering::*;
+        let result = match self.predicate(ctx) {
+            CmpIPredicateAttr::EQ => lhs == rhs,
+            CmpIPredicateAttr::NE => lhs != rhs,
+            CmpIPredicateAttr::SLT => lhs.cmp(&rhs, true) == Less,
+            CmpIPredicateAttr::SLE => lhs.cmp(&rhs, true) != Greater,
+            CmpIPredicateAttr::SGT => lhs.cmp(&rhs, true) == Greater,
+            CmpIPredicateAttr::SGE => lhs.cmp(&rhs, true) != Less,
+            CmpIPredicateAttr::ULT => lhs.cmp(&rhs, false) == Less,
+            CmpIPredicateAttr::ULE => lhs.cmp(&rhs, false) != Greater,
+            CmpIPredicateAttr::UGT => lhs.cmp(&rhs, false) == Greater,
+            CmpIPredicateAttr::UGE => lhs.cmp(&rhs, false) != Less,
+        };
+        fold_to_int(ctx, self, APInt::from_u8(result.into(), bw(1)))
+    }
+}
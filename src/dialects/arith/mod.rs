@@ -0,0 +1,267 @@
+//! A target independent dialect of integer arithmetic, similar to MLIR's
+//! [arith](https://mlir.llvm.org/docs/Dialects/ArithOps/) dialect.
+//!
+//! Its [Op](crate::op::Op)s operate on signless integers, and interpret them
+//! as signed or unsigned as specified by the operation (for example,
+//! [DivSIOp](ops::DivSIOp) and [DivUIOp](ops::DivUIOp)) or its predicate
+//! ([CmpIOp](ops::CmpIOp)). Frontends can generate this dialect, and lower
+//! it to a target dialect (such as LLVM) later. Operations on constants
+//! are [folded](fold), so [constant folding](crate::transforms::const_fold)
+//! and canonicalization evaluate them.
+
+pub mod attributes;
+pub mod fold;
+pub mod op_interfaces;
+pub mod ops;
+
+use crate::{
+    builtin::BuiltinDialect,
+    context::Context,
+    dialect::{Dialect, DialectName, DialectPlugin},
+};
+
+/// [DialectPlugin] for the arith dialect.
+pub struct ArithDialect;
+
+impl DialectPlugin for ArithDialect {
+    fn name(&self) -> DialectName {
+        DialectName::new("arith")
+    }
+
+    fn dependencies(&self) -> Vec<Box<dyn DialectPlugin>> {
+        vec![Box::new(BuiltinDialect)]
+    }
+
+    fn register(&self, ctx: &mut Context) {
+        let mut dialect = Dialect::new(self.name());
+        dialect.set_constant_materializer(fold::materialize_constant);
+        dialect.register(ctx);
+        ops::register(ctx);
+        attributes::register(ctx);
+    }
+}
+
+/// Load the arith dialect (and the builtin dialect it depends on) into context.
+pub fn register(ctx: &mut Context) {
+    ArithDialect.load(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use crate::{
+        builtin::{
+            attributes::IntegerAttr,
+            op_interfaces::{OneResultInterface, SingleBlockRegionInterface},
+            ops::{FuncOp, ModuleOp},
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::{Context, Ptr},
+        dialects::arith::{
+            self,
+            attributes::CmpIPredicateAttr,
+            op_interfaces::BinArithOp,
+            ops::{
+                AddIOp, AndIOp, CmpIOp, ConstantOp, DivSIOp, DivUIOp, MulIOp, OrIOp, RemSIOp,
+                RemUIOp, ShLIOp, ShRSIOp, ShRUIOp, SubIOp, XOrIOp, constant_op,
+            },
+        },
+        op::Op,
+        operation::Operation,
+        parse_source,
+        printable::Printable,
+        result::Result,
+        test_dialect::{self, ops::ConsumeOp},
+        transforms::const_fold::const_fold,
+        utils::apint::{APInt, bw},
+        value::Value,
+    };
+
+    fn setup_context() -> Context {
+        let mut ctx = Context::new();
+        arith::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        ctx
+    }
+
+    // Build IR using all the arith ops, and check that it verifies
+    // and round-trips through the textual format.
+    #[test]
+    fn arith_round_trip() -> Result<()> {
+        let ctx = &mut setup_context();
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless);
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+        let func_ty = FunctionType::get(ctx, vec![i32_ty.into()], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+        let entry = func.get_entry_block(ctx);
+        let arg = entry.deref(ctx).argument(0);
+        let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+            op.insert_at_back(entry, ctx);
+            op.deref(ctx).result(0)
+        };
+
+        let seven = IntegerAttr::from_u64(ctx, i32_ty, 7)?;
+        let c = ConstantOp::new(ctx, seven).operation();
+        let c = insert(ctx, c);
+        let bin_ops = [
+            AddIOp::new(ctx, arg, c).operation(),
+            SubIOp::new(ctx, arg, c).operation(),
+            MulIOp::new(ctx, arg, c).operation(),
+            DivSIOp::new(ctx, arg, c).operation(),
+            DivUIOp::new(ctx, arg, c).operation(),
+            RemSIOp::new(ctx, arg, c).operation(),
+            RemUIOp::new(ctx, arg, c).operation(),
+            AndIOp::new(ctx, arg, c).operation(),
+            OrIOp::new(ctx, arg, c).operation(),
+            XOrIOp::new(ctx, arg, c).operation(),
+            ShLIOp::new(ctx, arg, c).operation(),
+            ShRSIOp::new(ctx, arg, c).operation(),
+            ShRUIOp::new(ctx, arg, c).operation(),
+        ];
+        let mut results = vec![];
+        for op in bin_ops {
+            results.push(insert(ctx, op));
+        }
+        let cmp = CmpIOp::new(ctx, CmpIPredicateAttr::SLT, arg, c).operation();
+        results.push(insert(ctx, cmp));
+        ConsumeOp::new(ctx, results)
+            .operation()
+            .insert_at_back(entry, ctx);
+
+        module.operation().verify(ctx)?;
+        let printed = module.disp(ctx).to_string();
+        let reparse_ctx = &mut setup_context();
+        let reparsed = parse_source(reparse_ctx, printed.as_str())?;
+        reparsed.operation().verify(reparse_ctx)?;
+        expect![[r#"
+            builtin.module @m 
+            {
+              ^bb0():
+                builtin.func @f: builtin.function <(builtin.integer i32)->()> 
+                {
+                  ^entry(block_2v1_arg0_block_1v1_arg0:builtin.integer i32):
+                    op_3v1_res0_op_3v1_res0 = arith.constant <7: i32> : builtin.integer i32;
+                    op_4v1_res0_op_5v1_res0 = arith.addi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_5v1_res0_op_6v1_res0 = arith.subi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_6v1_res0_op_7v1_res0 = arith.muli block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_7v1_res0_op_8v1_res0 = arith.divsi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_8v1_res0_op_9v1_res0 = arith.divui block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_9v1_res0_op_10v1_res0 = arith.remsi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_10v1_res0_op_11v1_res0 = arith.remui block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_11v1_res0_op_12v1_res0 = arith.andi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_12v1_res0_op_13v1_res0 = arith.ori block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_13v1_res0_op_14v1_res0 = arith.xori block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_14v1_res0_op_15v1_res0 = arith.shli block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_15v1_res0_op_16v1_res0 = arith.shrsi block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_16v1_res0_op_17v1_res0 = arith.shrui block_2v1_arg0_block_1v1_arg0, op_3v1_res0_op_3v1_res0 : builtin.integer i32;
+                    op_17v1_res0_op_18v1_res0 = arith.cmpi block_2v1_arg0_block_1v1_arg0 <SLT> op_3v1_res0_op_3v1_res0 : builtin.integer i1;
+                    test.consume op_4v1_res0_op_5v1_res0, op_5v1_res0_op_6v1_res0, op_6v1_res0_op_7v1_res0, op_7v1_res0_op_8v1_res0, op_8v1_res0_op_9v1_res0, op_9v1_res0_op_10v1_res0, op_10v1_res0_op_11v1_res0, op_11v1_res0_op_12v1_res0, op_12v1_res0_op_13v1_res0, op_13v1_res0_op_14v1_res0, op_14v1_res0_op_15v1_res0, op_15v1_res0_op_16v1_res0, op_16v1_res0_op_17v1_res0, op_17v1_res0_op_18v1_res0
+                }
+            }"#]].assert_eq(&reparsed.disp(reparse_ctx).to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn arith_verify_errs() -> Result<()> {
+        let ctx = &mut setup_context();
+        let si32_ty = IntegerType::get(ctx, 32, Signedness::Signed);
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless);
+
+        // Operands must be signless integers.
+        let signed = IntegerAttr::from_i64(ctx, si32_ty, 1)?;
+        let signed = ConstantOp::new(ctx, signed);
+        let err = signed.verify(ctx).unwrap_err();
+        assert!(err.to_string().contains("signless integer"));
+        let add = AddIOp::new(ctx, signed.result(ctx), signed.result(ctx));
+        let err = add.verify_interfaces(ctx).unwrap_err();
+        assert!(err.to_string().contains("signless integer"));
+        let cmp = CmpIOp::new(
+            ctx,
+            CmpIPredicateAttr::EQ,
+            signed.result(ctx),
+            signed.result(ctx),
+        );
+        let err = cmp.verify(ctx).unwrap_err();
+        assert!(err.to_string().contains("signless integers"));
+
+        // The value of a constant must have the type of its result.
+        let one = IntegerAttr::from_i64(ctx, i32_ty, 1)?;
+        ConstantOp::new(ctx, one.clone()).verify(ctx)?;
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless);
+        let op = Operation::new(
+            ctx,
+            ConstantOp::opid_static(),
+            vec![i64_ty.into()],
+            vec![],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*constant_op::ATTR_KEY_VALUE, one);
+        let err = Operation::op(op, ctx).verify(ctx).unwrap_err();
+        assert!(err.to_string().contains("must be the result type"));
+        Ok(())
+    }
+
+    // Integer operations on constants fold, unless that's poison or undefined behaviour.
+    #[test]
+    fn arith_const_fold() -> Result<()> {
+        let ctx = &mut setup_context();
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signless);
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+        let func_ty = FunctionType::get(ctx, vec![], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+        let entry = func.get_entry_block(ctx);
+        let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+            op.insert_at_back(entry, ctx);
+            op.deref(ctx).result(0)
+        };
+        let constant = |ctx: &mut Context, value: i64| {
+            let value = IntegerAttr::from_i64(ctx, i32_ty, value).unwrap();
+            let op = ConstantOp::new(ctx, value).operation();
+            insert(ctx, op)
+        };
+        let (c7, c3, c0, c40) = (
+            constant(ctx, 7),
+            constant(ctx, 3),
+            constant(ctx, 0),
+            constant(ctx, 40),
+        );
+
+        let sub = SubIOp::new(ctx, c3, c7).operation();
+        let sub = insert(ctx, sub);
+        let mul = MulIOp::new(ctx, sub, c7).operation();
+        let mul = insert(ctx, mul);
+        let cmp = CmpIOp::new(ctx, CmpIPredicateAttr::ULT, c3, sub).operation();
+        let cmp = insert(ctx, cmp);
+        let div = DivSIOp::new(ctx, c7, c0).operation();
+        let div = insert(ctx, div);
+        let shl = ShLIOp::new(ctx, c7, c40).operation();
+        let shl = insert(ctx, shl);
+        let consume = ConsumeOp::new(ctx, vec![mul, cmp, div, shl]).operation();
+        consume.insert_at_back(entry, ctx);
+
+        assert_eq!(const_fold(ctx, module.operation()), 3);
+        module.operation().verify(ctx)?;
+        let consume = consume.deref(ctx);
+        let value = |idx: usize| {
+            let Value::OpResult { op, .. } = consume.operand(idx) else {
+                return None;
+            };
+            let constant = Operation::op(op, ctx);
+            let constant = constant.downcast_ref::<ConstantOp>()?;
+            Some(APInt::from(constant.value(ctx)))
+        };
+        // (3 - 7) * 7, and 3 < (3 - 7) when compared unsigned.
+        assert_eq!(value(0), Some(APInt::from_i64(-28, bw(32))));
+        assert_eq!(value(1), Some(APInt::from_u8(1, bw(1))));
+        assert_eq!(value(2), None);
+        assert_eq!(value(3), None);
+        Ok(())
+    }
+}
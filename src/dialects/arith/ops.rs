@@ -0,0 +1,367 @@
+//! [Op]s of the arith dialect.
+
+use pliron::derive::{def_op, derive_op_interface_impl, format_op, op_interface_impl};
+use thiserror::Error;
+
+use crate::{
+    attribute::AttrObj,
+    builtin::{
+        attr_interfaces::TypedAttrInterface,
+        attributes::IntegerAttr,
+        op_interfaces::{
+            ConstantLikeInterface, OneResultInterface, PureInterface, SameOperandsAndResultType,
+            SameOperandsType, SameResultsType, ZeroOpdInterface,
+        },
+        types::{IntegerType, Signedness},
+    },
+    common_traits::Verify,
+    context::Context,
+    identifier::Identifier,
+    impl_verify_succ,
+    op::Op,
+    operation::Operation,
+    parsable::Parsable,
+    result::Result,
+    r#type::TypePtr,
+    value::Value,
+    verify_err,
+};
+
+use super::{
+    attributes::CmpIPredicateAttr,
+    op_interfaces::{BinArithOp, IntBinArithOp},
+};
+
+macro_rules! new_int_bin_op {
+    (   $(#[$outer:meta])*
+        $op_name:ident, $op_id:literal
+    ) => {
+        #[def_op($op_id)]
+        $(#[$outer])*
+        /// ### Operands:
+        ///
+        /// | operand | description |
+        /// |-----|-------|
+        /// | `lhs` | Signless integer |
+        /// | `rhs` | Signless integer |
+        ///
+        /// ### Result(s):
+        ///
+        /// | result | description |
+        /// |-----|-------|
+        /// | `res` | Signless integer |
+        #[format_op("$0 `, ` $1 ` : ` type($0)")]
+        #[derive_op_interface_impl(
+            OneResultInterface, SameOperandsType, SameResultsType,
+            SameOperandsAndResultType, BinArithOp, IntBinArithOp, PureInterface
+        )]
+        pub struct $op_name;
+
+        impl_verify_succ!($op_name);
+    }
+}
+
+new_int_bin_op!(
+    /// Integer addition.
+    AddIOp,
+    "arith.addi"
+);
+
+new_int_bin_op!(
+    /// Integer subtraction.
+    SubIOp,
+    "arith.subi"
+);
+
+new_int_bin_op!(
+    /// Integer multiplication.
+    MulIOp,
+    "arith.muli"
+);
+
+new_int_bin_op!(
+    /// Signed integer division, rounding towards zero.
+    DivSIOp,
+    "arith.divsi"
+);
+
+new_int_bin_op!(
+    /// Unsigned integer division.
+    DivUIOp,
+    "arith.divui"
+);
+
+new_int_bin_op!(
+    /// Signed integer remainder, with the sign of the dividend.
+    RemSIOp,
+    "arith.remsi"
+);
+
+new_int_bin_op!(
+    /// Unsigned integer remainder.
+    RemUIOp,
+    "arith.remui"
+);
+
+new_int_bin_op!(
+    /// Bitwise and.
+    AndIOp,
+    "arith.andi"
+);
+
+new_int_bin_op!(
+    /// Bitwise or.
+    OrIOp,
+    "arith.ori"
+);
+
+new_int_bin_op!(
+    /// Bitwise exclusive or.
+    XOrIOp,
+    "arith.xori"
+);
+
+new_int_bin_op!(
+    /// Shift left.
+    ShLIOp,
+    "arith.shli"
+);
+
+new_int_bin_op!(
+    /// Arithmetic (sign extending) shift right.
+    ShRSIOp,
+    "arith.shrsi"
+);
+
+new_int_bin_op!(
+    /// Logical (zero extending) shift right.
+    ShRUIOp,
+    "arith.shrui"
+);
+
+#[derive(Error, Debug)]
+pub enum CmpIOpVerifyErr {
+    #[error("Result must be 1-bit integer (bool)")]
+    ResultNotBool,
+    #[error("Operands must be signless integers")]
+    IncorrectOperandsType,
+    #[error("Missing or incorrect predicate attribute")]
+    PredAttrErr,
+}
+
+/// Integer comparison.
+///
+/// ### Operands:
+///
+/// | operand | description |
+/// |-----|-------|
+/// | `lhs` | Signless integer |
+/// | `rhs` | Signless integer |
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | 1-bit signless integer |
+///
+/// ### Attributes:
+///
+/// | key | value |
+/// |-----|-------|
+/// | [ATTR_KEY_PREDICATE](cmpi_op::ATTR_KEY_PREDICATE) | [CmpIPredicateAttr] |
+#[def_op("arith.cmpi")]
+#[format_op("$0 ` <` attr($arith_cmpi_predicate, $CmpIPredicateAttr) `> ` $1 ` : ` type($0)")]
+#[derive_op_interface_impl(SameOperandsType, OneResultInterface, PureInterface)]
+pub struct CmpIOp;
+
+pub mod cmpi_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+
+    /// Attribute key for the comparison predicate.
+    pub static ATTR_KEY_PREDICATE: LazyLock<Identifier> =
+        LazyLock::new(|| "arith_cmpi_predicate".try_into().unwrap());
+}
+
+impl CmpIOp {
+    /// Create a new [CmpIOp].
+    pub fn new(ctx: &mut Context, pred: CmpIPredicateAttr, lhs: Value, rhs: Value) -> Self {
+        let bool_ty = IntegerType::get(ctx, 1, Signedness::Signless);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![bool_ty.into()],
+            vec![lhs, rhs],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*cmpi_op::ATTR_KEY_PREDICATE, pred);
+        CmpIOp { op }
+    }
+
+    /// Get the predicate.
+    pub fn predicate(&self, ctx: &Context) -> CmpIPredicateAttr {
+        *self
+            .operation()
+            .deref(ctx)
+            .attributes
+            .get::<CmpIPredicateAttr>(&cmpi_op::ATTR_KEY_PREDICATE)
+            .unwrap()
+    }
+}
+
+impl Verify for CmpIOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+
+        if self
+            .operation()
+            .deref(ctx)
+            .attributes
+            .get::<CmpIPredicateAttr>(&cmpi_op::ATTR_KEY_PREDICATE)
+            .is_none()
+        {
+            return verify_err!(loc, CmpIOpVerifyErr::PredAttrErr);
+        }
+
+        let res_ty: TypePtr<IntegerType> =
+            TypePtr::downcast_at(ctx, self.result_type(ctx), loc.clone())?;
+        let res_ty = res_ty.deref(ctx);
+        if res_ty.width() != 1 || res_ty.signedness() != Signedness::Signless {
+            return verify_err!(loc, CmpIOpVerifyErr::ResultNotBool);
+        }
+
+        let opd_ty = self.operand_type(ctx).deref(ctx);
+        if !opd_ty
+            .downcast_ref::<IntegerType>()
+            .is_some_and(|int_ty| int_ty.signedness() == Signedness::Signless)
+        {
+            return verify_err!(loc, CmpIOpVerifyErr::IncorrectOperandsType);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConstantOpVerifyErr {
+    #[error("Missing or incorrect constant value attribute")]
+    ValueAttrErr,
+    #[error("Type of the constant value must be the result type")]
+    TypeMismatch,
+    #[error("Constant value must be a signless integer")]
+    NotSignless,
+}
+
+/// Integer constant.
+///
+/// ### Attributes:
+///
+/// | key | value |
+/// |-----|-------|
+/// | [ATTR_KEY_VALUE](constant_op::ATTR_KEY_VALUE) | [IntegerAttr] |
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | Signless integer |
+#[def_op("arith.constant")]
+#[format_op("attr($arith_constant_value, $IntegerAttr) ` : ` type($0)")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface, PureInterface)]
+pub struct ConstantOp;
+
+pub mod constant_op {
+    use std::sync::LazyLock;
+
+    use super::*;
+
+    /// Attribute key for the constant value.
+    pub static ATTR_KEY_VALUE: LazyLock<Identifier> =
+        LazyLock::new(|| "arith_constant_value".try_into().unwrap());
+}
+
+impl ConstantOp {
+    /// Create a new [ConstantOp], whose result has the type of `value`.
+    pub fn new(ctx: &mut Context, value: IntegerAttr) -> Self {
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![value.get_type()],
+            vec![],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*constant_op::ATTR_KEY_VALUE, value);
+        ConstantOp { op }
+    }
+
+    /// Get the constant value that this Op defines.
+    pub fn value(&self, ctx: &Context) -> IntegerAttr {
+        self.operation()
+            .deref(ctx)
+            .attributes
+            .get::<IntegerAttr>(&constant_op::ATTR_KEY_VALUE)
+            .expect("Constant value missing or is of incorrect type")
+            .clone()
+    }
+}
+
+#[op_interface_impl]
+impl ConstantLikeInterface for ConstantOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        self.value(ctx).into()
+    }
+}
+
+impl Verify for ConstantOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        let loc = self.loc(ctx);
+        let Some(value_ty) = self
+            .operation()
+            .deref(ctx)
+            .attributes
+            .get::<IntegerAttr>(&constant_op::ATTR_KEY_VALUE)
+            .map(|value| value.get_type())
+        else {
+            return verify_err!(loc, ConstantOpVerifyErr::ValueAttrErr);
+        };
+
+        if value_ty != self.result_type(ctx) {
+            return verify_err!(loc, ConstantOpVerifyErr::TypeMismatch);
+        }
+
+        if !value_ty
+            .deref(ctx)
+            .downcast_ref::<IntegerType>()
+            .is_some_and(|int_ty| int_ty.signedness() == Signedness::Signless)
+        {
+            return verify_err!(loc, ConstantOpVerifyErr::NotSignless);
+        }
+
+        Ok(())
+    }
+}
+
+pub fn register(ctx: &mut Context) {
+    AddIOp::register(ctx, AddIOp::parser_fn);
+    SubIOp::register(ctx, SubIOp::parser_fn);
+    MulIOp::register(ctx, MulIOp::parser_fn);
+    DivSIOp::register(ctx, DivSIOp::parser_fn);
+    DivUIOp::register(ctx, DivUIOp::parser_fn);
+    RemSIOp::register(ctx, RemSIOp::parser_fn);
+    RemUIOp::register(ctx, RemUIOp::parser_fn);
+    AndIOp::register(ctx, AndIOp::parser_fn);
+    OrIOp::register(ctx, OrIOp::parser_fn);
+    XOrIOp::register(ctx, XOrIOp::parser_fn);
+    ShLIOp::register(ctx, ShLIOp::parser_fn);
+    ShRSIOp::register(ctx, ShRSIOp::parser_fn);
+    ShRUIOp::register(ctx, ShRUIOp::parser_fn);
+    CmpIOp::register(ctx, CmpIOp::parser_fn);
+    ConstantOp::register(ctx, ConstantOp::parser_fn);
+}
@@ -0,0 +1,3 @@
+//! Dialects that ship with pliron, in addition to the [builtin](crate::builtin) dialect.
+
+pub mod arith;
@@ -0,0 +1,508 @@
+//! [Op]s, [Type]s and [Attribute]s defined at runtime.
+//!
+//! Entities declared with [def_op](pliron::derive::def_op),
+//! [def_type](pliron::derive::def_type) or [def_attribute](pliron::derive::def_attribute)
+//! need a Rust type each, and hence must be known when pliron (or a tool built on it)
+//! is compiled. Dynamic entities instead are described by a definition
+//! (an id and a verifier closure) that is registered in a [Context] at runtime.
+//! This allows, for example, a DSL tool to let its users declare dialect
+//! extensions in configuration files.
+//!
+//! Once registered, dynamic entities are created, printed, parsed and verified
+//! just like any other entity:
+//!   - A [DynamicOp] wraps an [Operation] whose [OpId] has a [DynamicOpDef].
+//!   - A [DynamicType] (and similarly, a [DynamicAttr]) is an instance of a
+//!     [DynamicTypeDef] ([DynamicAttrDef]), parameterized by a list of attributes.
+//!
+//! Dynamic entities always use a generic syntax. [DynamicOp]s use the
+//! [canonical syntax](crate::op::canonical_syntax_print) of [Op]s, and
+//! [DynamicType]s and [DynamicAttr]s are printed as their ids, followed by
+//! their parameters (if any) in angle brackets: `test.vec <builtin.integer <4: si32>>`.
+//! Custom formats are not supported.
+
+use std::{
+    fmt::{self, Debug},
+    rc::Rc,
+    sync::Arc,
+};
+
+use combine::{Parser, optional};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc,
+    attribute::{AttrId, AttrObj, Attribute},
+    basic_block::BasicBlock,
+    common_traits::Verify,
+    context::{Context, Ptr},
+    dialect::{Dialect, DialectName, Registrant},
+    irfmt::{parsers::delimited_list_parser, printers::list_with_sep},
+    op::{Op, OpId, OpObj, canonical_syntax_parser, canonical_syntax_print},
+    operation::Operation,
+    parsable::{Parsable, StateStream},
+    printable::{self, ListSeparator, Printable},
+    result::Result,
+    storage_uniquer::TypeValueHash,
+    r#type::{Type, TypeId, TypeObj, TypePtr},
+    value::Value,
+};
+
+/// Errors in registering dynamic definitions.
+#[derive(Debug, Error)]
+pub enum DynamicDefErr {
+    #[error("Dialect {0} must be registered before its dynamic entity {1}")]
+    UnregisteredDialect(String, String),
+}
+
+/// Dynamic definitions of a [Dialect](crate::dialect::Dialect).
+#[derive(Default)]
+pub(crate) struct DynamicDefs {
+    ops: FxHashMap<OpId, Rc<DynamicOpDef>>,
+    types: FxHashMap<TypeId, Arc<DynamicTypeDef>>,
+    attributes: FxHashMap<AttrId, Arc<DynamicAttrDef>>,
+}
+
+/// Get `dialect`, or an error saying that `entity` can't be registered (in it).
+fn dialect_of<'a>(
+    ctx: &'a mut Context,
+    dialect: &DialectName,
+    entity: &dyn fmt::Display,
+) -> Result<&'a mut Dialect> {
+    match ctx.dialects.get_mut(dialect) {
+        Some(dialect) => Ok(dialect),
+        None => arg_err_noloc!(DynamicDefErr::UnregisteredDialect(
+            dialect.to_string(),
+            entity.to_string()
+        )),
+    }
+}
+
+/// Parse an optional list of parameters (attributes) of a dynamic type or attribute.
+fn params_parser<'a>() -> impl Parser<StateStream<'a>, Output = Vec<AttrObj>> {
+    optional(delimited_list_parser('<', '>', ',', AttrObj::parser(())))
+        .map(|params| params.unwrap_or_default())
+}
+
+/// Print the parameters (attributes) of a dynamic type or attribute.
+fn fmt_params(
+    params: &[AttrObj],
+    ctx: &Context,
+    state: &printable::State,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if params.is_empty() {
+        return Ok(());
+    }
+    write!(f, "<")?;
+    list_with_sep(params, ListSeparator::CharSpace(',')).fmt(ctx, state, f)?;
+    write!(f, ">")
+}
+
+/// Verifier of a [DynamicOp].
+pub type DynamicOpVerifier = Box<dyn Fn(&DynamicOp, &Context) -> Result<()>>;
+
+/// Runtime definition of an [Op]. See [module](self) documentation.
+pub struct DynamicOpDef {
+    opid: OpId,
+    verifier: DynamicOpVerifier,
+}
+
+impl DynamicOpDef {
+    /// Register a dynamic [Op] with id `opid`, verified by `verifier`.
+    /// The dialect of `opid` must already be registered, and not have
+    /// another [Op] registered with the same id.
+    #[track_caller]
+    pub fn register(
+        ctx: &mut Context,
+        opid: OpId,
+        verifier: impl Fn(&DynamicOp, &Context) -> Result<()> + 'static,
+    ) -> Result<Rc<DynamicOpDef>> {
+        let registrant = Registrant::dynamic();
+        let def = Rc::new(DynamicOpDef {
+            opid,
+            verifier: Box::new(verifier),
+        });
+        let dialect = dialect_of(ctx, &opid.dialect, &opid)?;
+        dialect.add_op(
+            opid,
+            Box::new(move |_, results| canonical_syntax_parser(opid, results)),
+            registrant,
+        )?;
+        dialect.dynamic_defs.ops.insert(opid, def.clone());
+
+        let creator_def = def.clone();
+        ctx.ops.insert(
+            opid,
+            Box::new(move |op| {
+                OpObj::new(DynamicOp {
+                    op,
+                    def: creator_def.clone(),
+                })
+            }),
+        );
+        Ok(def)
+    }
+
+    /// Get the definition of the dynamic [Op] `opid`, if one is registered.
+    pub fn lookup(ctx: &Context, opid: &OpId) -> Option<Rc<DynamicOpDef>> {
+        ctx.dialects
+            .get(&opid.dialect)
+            .and_then(|dialect| dialect.dynamic_defs.ops.get(opid).cloned())
+    }
+
+    /// Id of the [Op] defined.
+    pub fn opid(&self) -> OpId {
+        self.opid
+    }
+}
+
+/// An [Op] defined at runtime by a [DynamicOpDef].
+#[derive(Clone)]
+pub struct DynamicOp {
+    op: Ptr<Operation>,
+    def: Rc<DynamicOpDef>,
+}
+
+impl DynamicOp {
+    /// Create a new [Operation] defined by `def`, and wrap it.
+    pub fn new(
+        ctx: &mut Context,
+        def: &Rc<DynamicOpDef>,
+        result_types: Vec<Ptr<TypeObj>>,
+        operands: Vec<Value>,
+        successors: Vec<Ptr<BasicBlock>>,
+        num_regions: usize,
+    ) -> DynamicOp {
+        let op = Operation::new(
+            ctx,
+            def.opid,
+            result_types,
+            operands,
+            successors,
+            num_regions,
+        );
+        DynamicOp {
+            op,
+            def: def.clone(),
+        }
+    }
+
+    /// The definition of this [Op].
+    pub fn def(&self) -> &DynamicOpDef {
+        &self.def
+    }
+}
+
+impl Op for DynamicOp {
+    fn operation(&self) -> Ptr<Operation> {
+        self.op
+    }
+
+    fn wrap_operation(_op: Ptr<Operation>) -> OpObj {
+        panic!("Dynamic ops are wrapped by the creator registered with their definition")
+    }
+
+    fn opid(&self) -> OpId {
+        self.def.opid
+    }
+
+    fn opid_static() -> OpId {
+        panic!("Dynamic ops do not have a static OpId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for DynamicOp {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        (self.def.verifier)(self, ctx)
+    }
+}
+
+impl Printable for DynamicOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        canonical_syntax_print(OpObj::new(self.clone()), ctx, state, f)
+    }
+}
+
+/// Verifier of a [DynamicType].
+pub type DynamicTypeVerifier = Box<dyn Fn(&DynamicType, &Context) -> Result<()> + Send + Sync>;
+
+/// Runtime definition of a [Type]. See [module](self) documentation.
+pub struct DynamicTypeDef {
+    id: TypeId,
+    verifier: DynamicTypeVerifier,
+}
+
+impl DynamicTypeDef {
+    /// Register a dynamic [Type] with id `id`, verified by `verifier`.
+    /// The dialect of `id` must already be registered, and not have
+    /// another [Type] registered with the same id.
+    #[track_caller]
+    pub fn register(
+        ctx: &mut Context,
+        id: TypeId,
+        verifier: impl Fn(&DynamicType, &Context) -> Result<()> + Send + Sync + 'static,
+    ) -> Result<Arc<DynamicTypeDef>> {
+        let registrant = Registrant::dynamic();
+        let def = Arc::new(DynamicTypeDef {
+            id,
+            verifier: Box::new(verifier),
+        });
+        let parser_def = def.clone();
+        let dialect = dialect_of(ctx, &id.dialect, &id)?;
+        dialect.add_type(
+            id,
+            Box::new(move |_| {
+                let def = parser_def.clone();
+                combine::parser(move |parsable_state: &mut StateStream<'_>| {
+                    params_parser()
+                        .parse_stream(parsable_state)
+                        .map(|params| {
+                            DynamicType::get(parsable_state.state.ctx, &def, params).to_ptr()
+                        })
+                        .into_result()
+                })
+                .boxed()
+            }),
+            registrant,
+        )?;
+        dialect.dynamic_defs.types.insert(id, def.clone());
+        Ok(def)
+    }
+
+    /// Get the definition of the dynamic [Type] `id`, if one is registered.
+    pub fn lookup(ctx: &Context, id: &TypeId) -> Option<Arc<DynamicTypeDef>> {
+        ctx.dialects
+            .get(&id.dialect)
+            .and_then(|dialect| dialect.dynamic_defs.types.get(id).cloned())
+    }
+
+    /// Id of the [Type] defined.
+    pub fn id(&self) -> TypeId {
+        self.id
+    }
+}
+
+/// A [Type] defined at runtime by a [DynamicTypeDef].
+pub struct DynamicType {
+    def: Arc<DynamicTypeDef>,
+    params: Vec<AttrObj>,
+}
+
+impl DynamicType {
+    /// Get or create a [DynamicType] defined by `def`, with parameters `params`.
+    pub fn get(
+        ctx: &mut Context,
+        def: &Arc<DynamicTypeDef>,
+        params: Vec<AttrObj>,
+    ) -> TypePtr<DynamicType> {
+        Type::register_instance(
+            DynamicType {
+                def: def.clone(),
+                params,
+            },
+            ctx,
+        )
+    }
+
+    /// The definition of this [Type].
+    pub fn def(&self) -> &DynamicTypeDef {
+        &self.def
+    }
+
+    /// Parameters of this [Type].
+    pub fn params(&self) -> &[AttrObj] {
+        &self.params
+    }
+}
+
+impl Debug for DynamicType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicType")
+            .field("id", &self.def.id.to_string())
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl Type for DynamicType {
+    fn hash_type(&self) -> TypeValueHash {
+        // Attributes aren't hashable, but equal attributes have equal ids.
+        let param_ids: Vec<_> = self.params.iter().map(|param| param.attr_id()).collect();
+        TypeValueHash::new(&(self.def.id, param_ids))
+    }
+
+    fn eq_type(&self, other: &dyn Type) -> bool {
+        other
+            .downcast_ref::<DynamicType>()
+            .is_some_and(|other| self.def.id == other.def.id && self.params == other.params)
+    }
+
+    fn get_type_id(&self) -> TypeId {
+        self.def.id
+    }
+
+    fn get_type_id_static() -> TypeId {
+        panic!("Dynamic types do not have a static TypeId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for DynamicType {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        (self.def.verifier)(self, ctx)
+    }
+}
+
+impl Printable for DynamicType {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        fmt_params(&self.params, ctx, state, f)
+    }
+}
+
+/// Verifier of a [DynamicAttr].
+pub type DynamicAttrVerifier = Box<dyn Fn(&DynamicAttr, &Context) -> Result<()> + Send + Sync>;
+
+/// Runtime definition of an [Attribute]. See [module](self) documentation.
+pub struct DynamicAttrDef {
+    id: AttrId,
+    verifier: DynamicAttrVerifier,
+}
+
+impl DynamicAttrDef {
+    /// Register a dynamic [Attribute] with id `id`, verified by `verifier`.
+    /// The dialect of `id` must already be registered, and not have
+    /// another [Attribute] registered with the same id.
+    #[track_caller]
+    pub fn register(
+        ctx: &mut Context,
+        id: AttrId,
+        verifier: impl Fn(&DynamicAttr, &Context) -> Result<()> + Send + Sync + 'static,
+    ) -> Result<Arc<DynamicAttrDef>> {
+        let registrant = Registrant::dynamic();
+        let def = Arc::new(DynamicAttrDef {
+            id,
+            verifier: Box::new(verifier),
+        });
+        let parser_def = def.clone();
+        let dialect = dialect_of(ctx, &id.dialect, &id)?;
+        dialect.add_attr(
+            id,
+            Box::new(move |_| {
+                let def = parser_def.clone();
+                combine::parser(move |parsable_state: &mut StateStream<'_>| {
+                    params_parser()
+                        .parse_stream(parsable_state)
+                        .map(|params| -> AttrObj { DynamicAttr::new(&def, params).into() })
+                        .into_result()
+                })
+                .boxed()
+            }),
+            registrant,
+        )?;
+        dialect.dynamic_defs.attributes.insert(id, def.clone());
+        Ok(def)
+    }
+
+    /// Get the definition of the dynamic [Attribute] `id`, if one is registered.
+    pub fn lookup(ctx: &Context, id: &AttrId) -> Option<Arc<DynamicAttrDef>> {
+        ctx.dialects
+            .get(&id.dialect)
+            .and_then(|dialect| dialect.dynamic_defs.attributes.get(id).cloned())
+    }
+
+    /// Id of the [Attribute] defined.
+    pub fn id(&self) -> AttrId {
+        self.id
+    }
+}
+
+/// An [Attribute] defined at runtime by a [DynamicAttrDef].
+#[derive(Clone)]
+pub struct DynamicAttr {
+    def: Arc<DynamicAttrDef>,
+    params: Vec<AttrObj>,
+}
+
+impl DynamicAttr {
+    /// Create a [DynamicAttr] defined by `def`, with parameters `params`.
+    pub fn new(def: &Arc<DynamicAttrDef>, params: Vec<AttrObj>) -> DynamicAttr {
+        DynamicAttr {
+            def: def.clone(),
+            params,
+        }
+    }
+
+    /// The definition of this [Attribute].
+    pub fn def(&self) -> &DynamicAttrDef {
+        &self.def
+    }
+
+    /// Parameters of this [Attribute].
+    pub fn params(&self) -> &[AttrObj] {
+        &self.params
+    }
+}
+
+impl Debug for DynamicAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicAttr")
+            .field("id", &self.def.id.to_string())
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl Attribute for DynamicAttr {
+    fn eq_attr(&self, other: &dyn Attribute) -> bool {
+        other
+            .downcast_ref::<DynamicAttr>()
+            .is_some_and(|other| self.def.id == other.def.id && self.params == other.params)
+    }
+
+    fn attr_id(&self) -> AttrId {
+        self.def.id
+    }
+
+    fn attr_id_static() -> AttrId {
+        panic!("Dynamic attributes do not have a static AttrId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for DynamicAttr {
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        (self.def.verifier)(self, ctx)
+    }
+}
+
+impl Printable for DynamicAttr {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        fmt_params(&self.params, ctx, state, f)
+    }
+}
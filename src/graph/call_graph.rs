@@ -0,0 +1,113 @@
+//! Call graph of the functions in a symbol table.
+//!
+//! Calls are discovered via [SymbolUserOpInterface]: a symbol use by an
+//! [Op](crate::op::Op) implementing [CallOpInterface], that directly calls the symbol, is a
+//! call edge. Any other use of a symbol (for example, taking its address)
+//! is recorded as a non-call use, meaning that the symbol may be called
+//! from places that aren't known.
+//!
+//! Uses in nested symbol tables are included, except in those that
+//! (re)define the symbol used, since they refer to that definition.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    basic_block::BasicBlock,
+    builtin::op_interfaces::{
+        CallOpCallable, CallOpInterface, SymbolTableInterface, SymbolUserOpInterface,
+    },
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+    identifier::Identifier,
+    linked_list::LinkedList,
+    op::op_cast,
+    operation::Operation,
+};
+
+/// Is `sym` defined in a symbol table that (transitively) contains `op`,
+/// and is nested in `table_op`?
+fn shadowed(ctx: &Context, op: Ptr<Operation>, table_op: Ptr<Operation>, sym: &Identifier) -> bool {
+    let parent_op = |op: Ptr<Operation>| {
+        let block = op.deref(ctx).container()?;
+        let region = block.deref(ctx).container()?;
+        Some(region.deref(ctx).parent_op())
+    };
+    let mut cur = parent_op(op);
+    while let Some(ancestor) = cur.filter(|ancestor| *ancestor != table_op) {
+        if op_cast::<dyn SymbolTableInterface>(&*Operation::op(ancestor, ctx))
+            .is_some_and(|table| table.lookup(ctx, sym).is_some())
+        {
+            return true;
+        }
+        cur = parent_op(ancestor);
+    }
+    false
+}
+
+/// Direct call sites and non-call uses of the symbols in a symbol table.
+#[derive(Default)]
+pub struct CallGraph {
+    /// Direct calls to each symbol.
+    call_sites: FxHashMap<Identifier, Vec<Ptr<Operation>>>,
+    /// Uses, other than direct calls, of each symbol.
+    non_call_uses: FxHashMap<Identifier, Vec<Ptr<Operation>>>,
+}
+
+impl CallGraph {
+    /// Compute the call graph from the uses of symbols nested inside `table`.
+    pub fn new(ctx: &mut Context, table: &dyn SymbolTableInterface) -> CallGraph {
+        let mut cg = CallGraph::default();
+        let table_op = table.operation();
+        let body = table.body(ctx, 0);
+        let _ = BasicBlock::walk(
+            body,
+            ctx,
+            &WALKCONFIG_PREORDER_FORWARD,
+            &mut |ctx: &mut Context, node| {
+                let IRNode::Operation(op) = node else {
+                    return walk_advance::<()>();
+                };
+                let op_obj = Operation::op(op, ctx);
+                if let Some(user) = op_cast::<dyn SymbolUserOpInterface>(&*op_obj) {
+                    let direct_callee = op_cast::<dyn CallOpInterface>(&*op_obj).and_then(|call| {
+                        match call.callee(ctx) {
+                            CallOpCallable::Direct(callee) => Some(callee),
+                            CallOpCallable::Indirect(_) => None,
+                        }
+                    });
+                    for sym in user.used_symbols(ctx) {
+                        if shadowed(ctx, op, table_op, &sym) {
+                            continue;
+                        }
+                        if direct_callee.as_ref() == Some(&sym) {
+                            cg.call_sites.entry(sym).or_default().push(op);
+                        } else {
+                            cg.non_call_uses.entry(sym).or_default().push(op);
+                        }
+                    }
+                }
+                walk_advance()
+            },
+        );
+        cg
+    }
+
+    /// Direct calls to `sym`, in the order they appear in the IR.
+    pub fn call_sites(&self, sym: &Identifier) -> &[Ptr<Operation>] {
+        self.call_sites
+            .get(sym)
+            .map_or(&[], |calls| calls.as_slice())
+    }
+
+    /// Uses of `sym` other than direct calls, in the order they appear in the IR.
+    pub fn non_call_uses(&self, sym: &Identifier) -> &[Ptr<Operation>] {
+        self.non_call_uses
+            .get(sym)
+            .map_or(&[], |uses| uses.as_slice())
+    }
+
+    /// Does `sym` have uses other than direct calls?
+    pub fn has_non_call_uses(&self, sym: &Identifier) -> bool {
+        self.non_call_uses.contains_key(sym)
+    }
+}
@@ -1,4 +1,6 @@
 //! IR and control-flow-graph utilities
 
+pub mod call_graph;
+pub mod op_index;
 pub mod traversals;
 pub mod walkers;
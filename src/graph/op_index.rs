@@ -0,0 +1,95 @@
+//! An index of the operations nested in an [Operation], grouped by their [OpId].
+//!
+//! Passes targeting specific kinds of [Op]s, or computing statistics, can query
+//! an [OpIndex] any number of times, instead of walking the IR for every query.
+//!
+//! The index is built on demand, and isn't updated automatically as the IR changes.
+//! Operations erased since the index was built are skipped in queries,
+//! but new operations are included only if [added](OpIndex::insert) explicitly.
+//! [Rebuild](OpIndex::new) the index after more extensive changes.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    basic_block::BasicBlock,
+    context::{Context, Ptr},
+    linked_list::ContainsLinkedList,
+    op::{Op, OpId},
+    operation::Operation,
+};
+
+/// Operations nested in (and including) a root [Operation], grouped by [OpId].
+/// See [module](self) documentation.
+#[derive(Default)]
+pub struct OpIndex {
+    ops: FxHashMap<OpId, Vec<Ptr<Operation>>>,
+}
+
+impl OpIndex {
+    /// Index `root` and all operations nested in it.
+    pub fn new(ctx: &Context, root: Ptr<Operation>) -> OpIndex {
+        fn collect(ctx: &Context, block: Ptr<BasicBlock>, index: &mut OpIndex) {
+            for op in block.deref(ctx).iter(ctx) {
+                index.insert(ctx, op);
+                for region in op.deref(ctx).regions() {
+                    for block in region.deref(ctx).iter(ctx) {
+                        collect(ctx, block, index);
+                    }
+                }
+            }
+        }
+
+        let mut index = OpIndex::default();
+        index.insert(ctx, root);
+        for region in root.deref(ctx).regions() {
+            for block in region.deref(ctx).iter(ctx) {
+                collect(ctx, block, &mut index);
+            }
+        }
+        index
+    }
+
+    /// Add `op` (but not the operations nested in it) to the index.
+    pub fn insert(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        let opid = op.deref(ctx).opid();
+        self.ops.entry(opid).or_default().push(op);
+    }
+
+    /// Operations with id `opid`, in the order they were indexed.
+    pub fn ops_with_id<'a>(
+        &'a self,
+        ctx: &'a Context,
+        opid: OpId,
+    ) -> impl Iterator<Item = Ptr<Operation>> + 'a {
+        self.ops
+            .get(&opid)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|op| op.is_live(ctx))
+    }
+
+    /// [Op]s of type `T`, in the order they were indexed.
+    pub fn ops_of<'a, T: Op + Clone>(&'a self, ctx: &'a Context) -> impl Iterator<Item = T> + 'a {
+        self.ops_with_id(ctx, T::opid_static()).map(|op| {
+            Operation::op(op, ctx)
+                .downcast_ref::<T>()
+                .expect("Indexed operation has a different OpId")
+                .clone()
+        })
+    }
+
+    /// Number of operations with id `opid`.
+    pub fn count(&self, ctx: &Context, opid: OpId) -> usize {
+        self.ops_with_id(ctx, opid).count()
+    }
+
+    /// The [OpId]s of the indexed operations, along with the number of operations
+    /// having that id. The order is unspecified.
+    pub fn counts<'a>(&'a self, ctx: &'a Context) -> impl Iterator<Item = (OpId, usize)> + 'a {
+        self.ops
+            .keys()
+            .map(|opid| (*opid, self.count(ctx, *opid)))
+            .filter(|(_, count)| *count != 0)
+    }
+}
@@ -13,6 +13,8 @@
 //! Two types of walkers are provided. The ones in [interruptible] can be interrupted
 //! during the walk, or have the walk over unvisited children skipped. The ones directly
 //! in this module cannot be interrupted and always complete the full walk.
+//! Walks can also be started with [Operation::walk], [Region::walk] and [BasicBlock::walk],
+//! which call back to a [Visitor] (such as a closure), instead of a function with a state.
 //!
 //! Care must be taken if modifications are made to the graph during the walk.
 //! Safety:
@@ -30,6 +32,7 @@
 use crate::{
     basic_block::BasicBlock,
     context::{Context, Ptr},
+    linked_list::ContainsLinkedList,
     operation::Operation,
     region::Region,
 };
@@ -237,6 +240,110 @@ pub mod interruptible {
     }
 }
 
+/// Visits the nodes of a walk started by [Operation::walk], [Region::walk] or
+/// [BasicBlock::walk]. Each kind of node has its own method, which by default
+/// just advances the walk, so implementors override only the ones they need.
+/// The [WalkResult](interruptible::WalkResult) of a method skips the children
+/// of the node (in a [PreOrder](Order::PreOrder) walk) or interrupts the walk,
+/// just like a callback of the [interruptible] walkers.
+///
+/// Closures taking an [IRNode] are visitors too:
+/// ```
+/// use pliron::{
+///     builtin::ops::ModuleOp,
+///     context::Context,
+///     graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+///     op::Op,
+///     operation::Operation,
+/// };
+/// let ctx = &mut Context::new();
+/// let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+/// let mut num_ops = 0;
+/// let _ = Operation::walk(
+///     module.operation(),
+///     ctx,
+///     &WALKCONFIG_PREORDER_FORWARD,
+///     &mut |_ctx: &mut Context, node| {
+///         if let IRNode::Operation(_) = node {
+///             num_ops += 1;
+///         }
+///         walk_advance::<()>()
+///     },
+/// );
+/// assert_eq!(num_ops, 1);
+/// ```
+pub trait Visitor {
+    /// What an interrupted walk results in.
+    type Break;
+
+    /// Visit an [Operation].
+    fn visit_op(
+        &mut self,
+        _ctx: &mut Context,
+        _op: Ptr<Operation>,
+    ) -> interruptible::WalkResult<Self::Break> {
+        interruptible::walk_advance()
+    }
+
+    /// Visit a [BasicBlock].
+    fn visit_block(
+        &mut self,
+        _ctx: &mut Context,
+        _block: Ptr<BasicBlock>,
+    ) -> interruptible::WalkResult<Self::Break> {
+        interruptible::walk_advance()
+    }
+
+    /// Visit a [Region].
+    fn visit_region(
+        &mut self,
+        _ctx: &mut Context,
+        _region: Ptr<Region>,
+    ) -> interruptible::WalkResult<Self::Break> {
+        interruptible::walk_advance()
+    }
+}
+
+impl<B, F> Visitor for F
+where
+    F: FnMut(&mut Context, IRNode) -> interruptible::WalkResult<B>,
+{
+    type Break = B;
+
+    fn visit_op(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> interruptible::WalkResult<B> {
+        self(ctx, IRNode::Operation(op))
+    }
+
+    fn visit_block(
+        &mut self,
+        ctx: &mut Context,
+        block: Ptr<BasicBlock>,
+    ) -> interruptible::WalkResult<B> {
+        self(ctx, IRNode::BasicBlock(block))
+    }
+
+    fn visit_region(
+        &mut self,
+        ctx: &mut Context,
+        region: Ptr<Region>,
+    ) -> interruptible::WalkResult<B> {
+        self(ctx, IRNode::Region(region))
+    }
+}
+
+/// Dispatch `node` to the method of `visitor` for its kind.
+pub(crate) fn visit<V: Visitor>(
+    ctx: &mut Context,
+    visitor: &mut V,
+    node: IRNode,
+) -> interruptible::WalkResult<V::Break> {
+    match node {
+        IRNode::Operation(op) => visitor.visit_op(ctx, op),
+        IRNode::BasicBlock(block) => visitor.visit_block(ctx, block),
+        IRNode::Region(region) => visitor.visit_region(ctx, region),
+    }
+}
+
 pub type WalkerCallback<State> = fn(&mut Context, &mut State, IRNode);
 
 /// Visit an [Operation] and walk its children [Region]s.
@@ -302,6 +409,38 @@ pub fn walk_block<State>(
     });
 }
 
+/// Collect `root` and all operations nested (at any depth) in it, in the order
+/// a [walk_op] with [WALKCONFIG_PREORDER_FORWARD] visits them. Unlike the walkers,
+/// this only needs a shared [Context], and modifications made after the collection
+/// don't affect which operations are (to be) processed.
+pub fn collect_ops(ctx: &Context, root: Ptr<Operation>) -> Vec<Ptr<Operation>> {
+    let mut ops = vec![root];
+    for region in root.deref(ctx).regions() {
+        collect_region_ops_into(ctx, region, &mut ops);
+    }
+    ops
+}
+
+/// Collect the operations nested (at any depth) in `root`, in the order
+/// a [walk_region] with [WALKCONFIG_PREORDER_FORWARD] visits them.
+/// See [collect_ops].
+pub fn collect_region_ops(ctx: &Context, root: Ptr<Region>) -> Vec<Ptr<Operation>> {
+    let mut ops = vec![];
+    collect_region_ops_into(ctx, root, &mut ops);
+    ops
+}
+
+fn collect_region_ops_into(ctx: &Context, region: Ptr<Region>, ops: &mut Vec<Ptr<Operation>>) {
+    for block in region.deref(ctx).iter(ctx) {
+        for op in block.deref(ctx).iter(ctx) {
+            ops.push(op);
+            for nested in op.deref(ctx).regions() {
+                collect_region_ops_into(ctx, nested, ops);
+            }
+        }
+    }
+}
+
 /// A preset config with [Order::PreOrder] and [Direction::Forward]
 /// for all IR node kinds.
 pub const WALKCONFIG_PREORDER_FORWARD: WalkConfig = WalkConfig {
@@ -1,12 +1,28 @@
 //! [Identifier]s are strings used to name entities in programming languages.
+//!
+//! [Identifier]s are interned in a process-wide table. So they are [Copy]-able
+//! handles, and comparing them for equality is just a pointer comparison.
+//! Interned strings are never freed: they are leaked, and live until the process exits.
+//! So creating [Identifier]s from an unbounded set of strings grows memory use unboundedly.
+//! ```
+//! use pliron::identifier::Identifier;
+//! let foo: Identifier = "foo".try_into().unwrap();
+//! let foo_copy = foo;
+//! assert_eq!(foo, foo_copy);
+//! assert_eq!(foo, "foo".try_into().unwrap());
+//! assert_eq!(foo.as_str(), "foo");
+//! ```
 
 use std::{
+    cmp::Ordering,
     fmt::Display,
+    hash::{Hash, Hasher},
     ops::{Add, Deref},
+    sync::{LazyLock, RwLock},
 };
 
 use combine::{Parser, token};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use thiserror::Error;
 
 use crate::{
@@ -17,10 +33,57 @@ use crate::{
     verify_err_noloc,
 };
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug, PartialOrd, Ord)]
+/// Interned strings. These are leaked, and live until the process exits.
+static INTERNER: LazyLock<RwLock<FxHashSet<&'static str>>> = LazyLock::new(Default::default);
+
+/// Get the unique interned copy of `s`, allocating (and leaking) one only if there's none yet.
+fn intern(s: &str) -> &'static str {
+    if let Some(interned) = INTERNER.read().unwrap().get(s) {
+        return interned;
+    }
+    let mut interner = INTERNER.write().unwrap();
+    // Another thread may have interned `s` since we released the read lock.
+    if let Some(interned) = interner.get(s) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(s.to_string().into_boxed_str());
+    interner.insert(interned);
+    interned
+}
+
+#[derive(Clone, Copy, Debug)]
 /// An [Identifier] must satisfy the regex `[a-zA-Z_][a-zA-Z0-9_]*`.
 /// Also see [module description](module@crate::identifier).
-pub struct Identifier(String);
+pub struct Identifier(&'static str);
+
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        // Interning guarantees that equal strings have the same address.
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Identifier {}
+
+impl Hash for Identifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash the contents, and not the address, so that
+        // iteration orders of hash maps are stable across runs.
+        self.0.hash(state)
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(other.0)
+    }
+}
 
 impl Identifier {
     /// Attempt to construct a new [Identifier] from a [String].
@@ -34,18 +97,28 @@ impl Identifier {
     /// TryInto::<Identifier>::try_into(".a12ab").expect_err("Malformed identifier not caught");
     /// ```
     pub fn try_new(value: String) -> Result<Self> {
+        Self::try_from_str(&value)
+    }
+
+    /// Attempt to construct a new [Identifier] from a `&str`.
+    /// Allocates only if `value` hasn't been interned yet.
+    fn try_from_str(value: &str) -> Result<Self> {
         let mut chars_iter = value.chars();
-        match chars_iter.next() {
+        let is_wellformed = match chars_iter.next() {
             Some(first_char) if (first_char.is_ascii_alphabetic() || first_char == '_') => {
-                if !chars_iter.all(|c| c.is_ascii_alphanumeric() || c == '_') {
-                    return verify_err_noloc!(MalformedIdentifierErr(value.clone()));
-                }
-            }
-            _ => {
-                return verify_err_noloc!(MalformedIdentifierErr(value.clone()));
+                chars_iter.all(|c| c.is_ascii_alphanumeric() || c == '_')
             }
+            _ => false,
+        };
+        if !is_wellformed {
+            return verify_err_noloc!(MalformedIdentifierErr(value.to_string()));
         }
-        Ok(Identifier(value))
+        Ok(Identifier(intern(value)))
+    }
+
+    /// Get the underlying string.
+    pub fn as_str(&self) -> &'static str {
+        self.0
     }
 }
 
@@ -53,7 +126,7 @@ impl Add for Identifier {
     type Output = Identifier;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Identifier(self.0 + &rhs.0)
+        Identifier(intern(&(self.0.to_string() + rhs.0)))
     }
 }
 
@@ -65,6 +138,26 @@ impl Display for Identifier {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Identifier {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Identifier {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Identifier::try_new(name).map_err(serde::de::Error::custom)
+    }
+}
+
 impl TryFrom<String> for Identifier {
     type Error = result::Error;
 
@@ -77,7 +170,7 @@ impl TryFrom<&str> for Identifier {
     type Error = result::Error;
 
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        Self::try_new(value.to_string())
+        Self::try_from_str(value)
     }
 }
 
@@ -91,21 +184,21 @@ impl TryFrom<StringAttr> for Identifier {
 
 impl From<Identifier> for String {
     fn from(value: Identifier) -> Self {
-        value.0
+        value.0.to_string()
     }
 }
 
 impl Deref for Identifier {
-    type Target = String;
+    type Target = str;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        self.0
     }
 }
 
 /// A fast way to get just the "_" character as a string.
 pub fn underscore() -> Identifier {
-    Identifier("_".to_string())
+    Identifier(intern("_"))
 }
 
 #[derive(Debug, Error)]
@@ -141,27 +234,27 @@ impl Parsable for Identifier {
 /// use pliron::identifier::{Legaliser, Identifier};
 /// let mut legaliser = Legaliser::default();
 /// let id1 = legaliser.legalise("hello_");
-/// assert_eq!(*id1, "hello_");
+/// assert_eq!(id1.as_str(), "hello_");
 /// assert_eq!(legaliser.source_name(&id1).unwrap(), "hello_");
 /// let id2 = legaliser.legalise("hello.");
-/// assert_eq!(*id2, "hello__0");
+/// assert_eq!(id2.as_str(), "hello__0");
 /// assert_eq!(legaliser.source_name(&id2).unwrap(), "hello.");
 /// let id3 = legaliser.legalise("hello__0");
-/// assert_eq!(*id3, "hello__0_1");
+/// assert_eq!(id3.as_str(), "hello__0_1");
 /// assert_eq!(legaliser.source_name(&id3).unwrap(), "hello__0");
 /// let id4 = legaliser.legalise("");
-/// assert_eq!(*id4, "_");
+/// assert_eq!(id4.as_str(), "_");
 /// assert_eq!(legaliser.source_name(&id4).unwrap(), "");
 /// let id5 = legaliser.legalise("_");
-/// assert_eq!(*id5, "__2");
+/// assert_eq!(id5.as_str(), "__2");
 /// assert_eq!(legaliser.source_name(&id5).unwrap(), "_");
 ///
 /// let mut another_legaliser = Legaliser::default();
 /// let id6 = another_legaliser.legalise("_");
-/// assert_eq!(*id6, "_");
+/// assert_eq!(id6.as_str(), "_");
 /// assert_eq!(another_legaliser.source_name(&id6).unwrap(), "_");
 /// let id7 = another_legaliser.legalise("");
-/// assert_eq!(*id7, "__0");
+/// assert_eq!(id7.as_str(), "__0");
 /// assert_eq!(another_legaliser.source_name(&id7).unwrap(), "");
 ///
 /// ```
@@ -204,7 +297,7 @@ impl Legaliser {
     pub fn legalise(&mut self, name: &str) -> Identifier {
         // If we've already mapped this before, just return that.
         if let Some(id) = self.str_to_id.get(name) {
-            return id.clone();
+            return *id;
         }
 
         let legal_name = Self::replace_illegal_chars(name);
@@ -215,9 +308,8 @@ impl Legaliser {
             self.counter += 1;
         }
 
-        let legal_name_id = Identifier(legal_name_unique.clone());
-        self.str_to_id
-            .insert(name.to_string(), legal_name_id.clone());
+        let legal_name_id = Identifier(intern(&legal_name_unique));
+        self.str_to_id.insert(name.to_string(), legal_name_id);
         self.rev_str_to_id
             .insert(legal_name_unique.clone(), name.to_string());
 
@@ -226,6 +318,52 @@ impl Legaliser {
 
     /// Get the source name from which this [Identifier] was mapped to.
     pub fn source_name(&self, id: &Identifier) -> Option<String> {
-        self.rev_str_to_id.get(&id.0).cloned()
+        self.rev_str_to_id.get(id.0).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{BuildHasher, RandomState};
+
+    use super::Identifier;
+
+    #[test]
+    fn test_interning() {
+        let from_str: Identifier = "interned_id".try_into().unwrap();
+        let from_string: Identifier = String::from("interned_id").try_into().unwrap();
+        assert_eq!(from_str, from_string);
+        // Both share the same interned string.
+        assert!(std::ptr::eq(from_str.as_str(), from_string.as_str()));
+
+        let other: Identifier = "other_id".try_into().unwrap();
+        assert_ne!(from_str, other);
+        assert!(!std::ptr::eq(from_str.as_str(), other.as_str()));
+    }
+
+    #[test]
+    fn test_ord_and_hash_by_contents() {
+        // Create "b_id" first, so that its interned string is (likely) allocated first.
+        let b: Identifier = "b_id".try_into().unwrap();
+        let a: Identifier = "a_id".try_into().unwrap();
+        assert!(a < b);
+        assert_eq!(
+            ["b_id", "a_id", "c_id"]
+                .map(|id| Identifier::try_from(id).unwrap())
+                .iter()
+                .max(),
+            Some(&"c_id".try_into().unwrap())
+        );
+
+        let hasher = RandomState::new();
+        assert_eq!(hasher.hash_one(a), hasher.hash_one("a_id"));
+        assert_eq!(hasher.hash_one(b), hasher.hash_one("b_id"));
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(Identifier::try_from("").is_err());
+        assert!(Identifier::try_from("1a").is_err());
+        assert!(Identifier::try_from(String::from("a.b")).is_err());
     }
 }
@@ -0,0 +1,71 @@
+//! Mapping [Value]s and [BasicBlock]s to their copies, when cloning IR.
+//!
+//! [Operation::clone], [Region::clone_into] and [BasicBlock::clone_into] copy IR,
+//! using an [IRMapping] to remap the operands and successors of the copies.
+//! Values and blocks that aren't mapped are used as they are, so for example,
+//! mapping the arguments of a function's entry block to the arguments of a call
+//! before cloning its body, gets the body inlined.
+//! Values (and blocks) defined in the cloned IR are mapped to their copies
+//! as they are created.
+//!
+//! [Operation::clone]: crate::operation::Operation::clone
+//! [Region::clone_into]: crate::region::Region::clone_into
+//! [BasicBlock::clone_into]: crate::basic_block::BasicBlock::clone_into
+
+use rustc_hash::FxHashMap;
+
+use crate::{basic_block::BasicBlock, context::Ptr, value::Value};
+
+/// Mapping from [Value]s and [BasicBlock]s to their replacements.
+/// See [module](self) documentation.
+#[derive(Default, Clone)]
+pub struct IRMapping {
+    values: FxHashMap<Value, Value>,
+    blocks: FxHashMap<Ptr<BasicBlock>, Ptr<BasicBlock>>,
+}
+
+impl IRMapping {
+    /// Create an empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `from` to `to`.
+    pub fn map_value(&mut self, from: Value, to: Value) {
+        self.values.insert(from, to);
+    }
+
+    /// Map each of `from` to the corresponding value in `to`.
+    pub fn map_values(
+        &mut self,
+        from: impl IntoIterator<Item = Value>,
+        to: impl IntoIterator<Item = Value>,
+    ) {
+        self.values.extend(from.into_iter().zip(to));
+    }
+
+    /// Map `from` to `to`.
+    pub fn map_block(&mut self, from: Ptr<BasicBlock>, to: Ptr<BasicBlock>) {
+        self.blocks.insert(from, to);
+    }
+
+    /// What `value` is mapped to, if it is.
+    pub fn lookup_value(&self, value: Value) -> Option<Value> {
+        self.values.get(&value).copied()
+    }
+
+    /// What `block` is mapped to, if it is.
+    pub fn lookup_block(&self, block: Ptr<BasicBlock>) -> Option<Ptr<BasicBlock>> {
+        self.blocks.get(&block).copied()
+    }
+
+    /// What `value` is mapped to, or `value` itself if it isn't mapped.
+    pub fn value(&self, value: Value) -> Value {
+        self.lookup_value(value).unwrap_or(value)
+    }
+
+    /// What `block` is mapped to, or `block` itself if it isn't mapped.
+    pub fn block(&self, block: Ptr<BasicBlock>) -> Ptr<BasicBlock> {
+        self.lookup_block(block).unwrap_or(block)
+    }
+}
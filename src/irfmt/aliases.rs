@@ -0,0 +1,315 @@
+//! Type and attribute aliases in the textual IR.
+//!
+//! An alias gives a short name to a type (`!name = <type>`) or
+//! an attribute (`#name = <attribute>`). Alias definitions are
+//! statements, separated by `;`, and can appear wherever an [Operation]
+//! can, as well as before and after the top-level operation.
+//! ```text
+//! !i64 = builtin.integer si64;
+//! builtin.module @m {
+//!   ^entry():
+//!     ...
+//! }
+//! ```
+//! A use of an alias (`!name` or `#name`) may precede its definition.
+//! Such uses parse to an [AliasPlaceholderType] / [AliasPlaceholderAttr],
+//! which are replaced with the alias definition when parsing of the
+//! top-level operation completes. An alias that isn't defined by then
+//! results in an error, located at its first use, listing all unresolved aliases.
+
+use combine::{Parser, attempt, many, parser::char::spaces, token};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    attribute::{AttrObj, Attribute},
+    builtin::{attributes::AliasPlaceholderAttr, types::AliasPlaceholderType},
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, walk_op},
+    identifier::Identifier,
+    input_err, input_error,
+    location::{Located, Location, Source},
+    operation::Operation,
+    parsable::{
+        IntoParseResult, Parsable, ParseResult, State, StateStream, state_stream_from_iterator,
+    },
+    printable::Printable,
+    result::Result,
+    r#type::{Type, TypeObj},
+};
+
+use super::parsers::{attr_parser, spaced, type_parser};
+
+#[derive(Error, Debug)]
+pub enum AliasErr {
+    #[error("Alias {0} defined more than once")]
+    MultipleDefinitions(String),
+    #[error("Unresolved aliases: {0}")]
+    Unresolved(String),
+    #[error("Aliases used in {0} have cyclic definitions")]
+    Cyclic(String),
+}
+
+/// Aliases seen so far in a parse.
+/// Attached as an [extension](State::extension) to the parser [State].
+#[derive(Default, Clone)]
+pub struct Aliases {
+    types: FxHashMap<Identifier, Ptr<TypeObj>>,
+    attrs: FxHashMap<Identifier, AttrObj>,
+    /// Type aliases used before being defined, with the location of their first use.
+    type_placeholders: Vec<(Identifier, Location)>,
+    /// Attribute aliases used before being defined, with the location of their first use.
+    attr_placeholders: Vec<(Identifier, Location)>,
+    /// Set when an alias use is replaced by its definition.
+    substituted: bool,
+}
+
+impl Aliases {
+    /// Get the type that `name` is an alias of.
+    pub fn type_alias(&self, name: &Identifier) -> Option<Ptr<TypeObj>> {
+        self.types.get(name).copied()
+    }
+
+    /// Get the attribute that `name` is an alias of.
+    pub fn attr_alias(&self, name: &Identifier) -> Option<&AttrObj> {
+        self.attrs.get(name)
+    }
+}
+
+enum AliasDef {
+    Type(Identifier, Ptr<TypeObj>),
+    Attr(Identifier, AttrObj),
+}
+
+fn alias_def_parse<'a>(state_stream: &mut StateStream<'a>, _arg: ()) -> ParseResult<'a, ()> {
+    let loc = state_stream.loc();
+    let type_def = token('!')
+        .with(Identifier::parser(()))
+        .skip(spaced(token('=')))
+        .and(type_parser())
+        .map(|(name, ty)| AliasDef::Type(name, ty));
+    let attr_def = token('#')
+        .with(Identifier::parser(()))
+        .skip(spaced(token('=')))
+        .and(attr_parser())
+        .map(|(name, attr)| AliasDef::Attr(name, attr));
+    let def = type_def
+        .or(attr_def)
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+
+    let aliases = state_stream.state.extension_or_default::<Aliases>();
+    let redefined = match def {
+        AliasDef::Type(name, ty) => aliases.types.insert(name, ty).map(|_| format!("!{name}")),
+        AliasDef::Attr(name, attr) => aliases.attrs.insert(name, attr).map(|_| format!("#{name}")),
+    };
+    if let Some(redefined) = redefined {
+        input_err!(loc, AliasErr::MultipleDefinitions(redefined))?
+    }
+    Ok(()).into_parse_result()
+}
+
+/// Parse an alias definition, i.e., `!name = <type>` or `#name = <attribute>`.
+pub fn alias_def_parser<'a>()
+-> Box<dyn Parser<StateStream<'a>, Output = (), PartialState = ()> + 'a> {
+    combine::parser(|state_stream: &mut StateStream<'a>| alias_def_parse(state_stream, ())).boxed()
+}
+
+/// Parse a use of a type alias, `!name`. If the alias isn't defined yet,
+/// an [AliasPlaceholderType] is returned instead.
+pub(crate) fn type_alias_use<'a>(
+    state_stream: &mut StateStream<'a>,
+) -> ParseResult<'a, Ptr<TypeObj>> {
+    let loc = state_stream.loc();
+    let name = token('!')
+        .with(Identifier::parser(()))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+
+    let state = &mut state_stream.state;
+    let aliases = state.extension_or_default::<Aliases>();
+    if let Some(ty) = aliases.type_alias(&name) {
+        aliases.substituted = true;
+        return Ok(ty).into_parse_result();
+    }
+    if !aliases.type_placeholders.iter().any(|(n, _)| *n == name) {
+        aliases.type_placeholders.push((name, loc));
+    }
+    Ok(AliasPlaceholderType::get(state.ctx, name).into()).into_parse_result()
+}
+
+/// Parse a use of an attribute alias, `#name`. If the alias isn't defined yet,
+/// an [AliasPlaceholderAttr] is returned instead.
+pub(crate) fn attr_alias_use<'a>(state_stream: &mut StateStream<'a>) -> ParseResult<'a, AttrObj> {
+    let loc = state_stream.loc();
+    let name = token('#')
+        .with(Identifier::parser(()))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+
+    let aliases = state_stream.state.extension_or_default::<Aliases>();
+    if let Some(attr) = aliases.attr_alias(&name).cloned() {
+        aliases.substituted = true;
+        return Ok(attr).into_parse_result();
+    }
+    if !aliases.attr_placeholders.iter().any(|(n, _)| *n == name) {
+        aliases.attr_placeholders.push((name, loc));
+    }
+    Ok(AttrObj::from(AliasPlaceholderAttr::new(name))).into_parse_result()
+}
+
+/// Parse a top-level operation, along with alias definitions preceding and following it,
+/// and then resolve all alias placeholders in it.
+pub(crate) fn top_level_op_parse<'a>(
+    state_stream: &mut StateStream<'a>,
+    op_parser: impl Parser<StateStream<'a>, Output = Ptr<Operation>>,
+) -> ParseResult<'a, Ptr<Operation>> {
+    let defs_before = many::<(), _, _>(alias_def_parser().skip(spaced(token(';'))));
+    let defs_after = many::<(), _, _>(attempt(spaced(token(';')).with(alias_def_parser())));
+    let op = spaces()
+        .with(defs_before)
+        .with(op_parser)
+        .skip(defs_after)
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+    resolve_placeholders(&mut state_stream.state, op)?;
+    Ok(op).into_parse_result()
+}
+
+/// Replace alias placeholders in `root` (and everything nested in it)
+/// with the definitions of those aliases.
+fn resolve_placeholders(state: &mut State, root: Ptr<Operation>) -> Result<()> {
+    let Some(aliases) = state.extension_mut::<Aliases>() else {
+        return Ok(());
+    };
+    let type_placeholders = std::mem::take(&mut aliases.type_placeholders);
+    let attr_placeholders = std::mem::take(&mut aliases.attr_placeholders);
+    let Some((_, first_use)) = type_placeholders.first().or(attr_placeholders.first()) else {
+        return Ok(());
+    };
+    let first_use = first_use.clone();
+
+    let unresolved_types = type_placeholders
+        .iter()
+        .filter(|(name, _)| !aliases.types.contains_key(name))
+        .map(|(name, loc)| (format!("!{name}"), loc));
+    let unresolved_attrs = attr_placeholders
+        .iter()
+        .filter(|(name, _)| !aliases.attrs.contains_key(name))
+        .map(|(name, loc)| (format!("#{name}"), loc));
+    let unresolved: Vec<_> = unresolved_types.chain(unresolved_attrs).collect();
+    if let Some((_, loc)) = unresolved.first() {
+        let names: Vec<_> = unresolved.iter().map(|(name, _)| name.clone()).collect();
+        input_err!((*loc).clone(), AliasErr::Unresolved(names.join(", ")))?
+    }
+
+    let aliases = aliases.clone();
+    let ctx = &mut *state.ctx;
+    let mut nodes = vec![];
+    walk_op(
+        ctx,
+        &mut nodes,
+        &WALKCONFIG_PREORDER_FORWARD,
+        root,
+        |_ctx, nodes, node| nodes.push(node),
+    );
+
+    for node in nodes {
+        match node {
+            IRNode::Operation(op) => {
+                let num_results = op.deref(ctx).num_results();
+                for res_idx in 0..num_results {
+                    let ty = op.deref(ctx).results[res_idx].get_type();
+                    if let Some(ty) = resolve::<Ptr<TypeObj>>(ctx, &aliases, &ty, &first_use)? {
+                        op.deref_mut(ctx).results[res_idx].set_type(ty);
+                    }
+                }
+                let attrs: Vec<_> = op.deref(ctx).attributes.0.clone().into_iter().collect();
+                for (key, attr) in attrs {
+                    if let Some(attr) = resolve::<AttrObj>(ctx, &aliases, &attr, &first_use)? {
+                        op.deref_mut(ctx).attributes.0.insert(key, attr);
+                    }
+                }
+            }
+            IRNode::BasicBlock(block) => {
+                let num_args = block.deref(ctx).num_arguments();
+                for arg_idx in 0..num_args {
+                    let ty = block.deref(ctx).args[arg_idx].ty;
+                    if let Some(ty) = resolve::<Ptr<TypeObj>>(ctx, &aliases, &ty, &first_use)? {
+                        block.deref_mut(ctx).args[arg_idx].ty = ty;
+                    }
+                }
+                let attrs: Vec<_> = block.deref(ctx).attributes.0.clone().into_iter().collect();
+                for (key, attr) in attrs {
+                    if let Some(attr) = resolve::<AttrObj>(ctx, &aliases, &attr, &first_use)? {
+                        block.deref_mut(ctx).attributes.0.insert(key, attr);
+                    }
+                }
+            }
+            IRNode::Region(_) => (),
+        }
+    }
+    Ok(())
+}
+
+/// Types and attributes are immutable, so placeholders nested in them
+/// cannot be patched in place. Instead, `entity` is re-parsed from its
+/// printed form, now that all the aliases are known. This is repeated
+/// until no more placeholders remain, since alias definitions may
+/// themselves contain placeholders. Returns [None] if `entity` has none.
+fn resolve<T: Parsable<Arg = ()>>(
+    ctx: &mut Context,
+    aliases: &Aliases,
+    entity: &dyn Printable,
+    loc: &Location,
+) -> Result<Option<T::Parsed>>
+where
+    T::Parsed: Printable,
+{
+    let markers = [
+        AliasPlaceholderType::get_type_id_static().to_string(),
+        AliasPlaceholderAttr::attr_id_static().to_string(),
+    ];
+    let mut printed = entity.disp(ctx).to_string();
+    let mut resolved = None;
+    // Each round substitutes at least one level of alias definitions.
+    for _ in 0..=(aliases.types.len() + aliases.attrs.len()) {
+        // A false positive here (say, a string attribute containing a marker)
+        // only costs a re-parse, which won't substitute anything.
+        if !markers.iter().any(|marker| printed.contains(marker)) {
+            return Ok(resolved);
+        }
+        let (parsed, substituted) = {
+            let mut state = State::new(ctx, Source::InMemory);
+            state.insert_extension(aliases.clone());
+            let mut state_stream = state_stream_from_iterator(printed.chars(), state);
+            let parsed = T::parser(())
+                .parse_stream(&mut state_stream)
+                .into_result()
+                .map_err(|err| {
+                    input_error!(
+                        loc.clone(),
+                        "Error resolving aliases in {}: {}",
+                        printed,
+                        err.into_inner().error
+                    )
+                })?
+                .0;
+            let substituted = state_stream
+                .state
+                .extension::<Aliases>()
+                .is_some_and(|aliases| aliases.substituted);
+            (parsed, substituted)
+        };
+        if !substituted {
+            return Ok(resolved);
+        }
+        printed = parsed.disp(ctx).to_string();
+        resolved = Some(parsed);
+    }
+    input_err!(loc.clone(), AliasErr::Cyclic(printed))
+}
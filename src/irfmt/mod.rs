@@ -1,4 +1,5 @@
 //! IR printing and parsing utilities
 
+pub mod aliases;
 pub mod parsers;
 pub mod printers;
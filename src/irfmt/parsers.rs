@@ -3,21 +3,28 @@
 use std::str::FromStr;
 
 use crate::{
-    attribute::AttrObj,
+    attribute::{AttrObj, AttributeDict},
     basic_block::BasicBlock,
-    context::Ptr,
+    builtin::{
+        attributes::StringAttr,
+        op_interfaces::{CallOpCallable, OperandBundle, OperandBundleInterface},
+        types::FunctionType,
+    },
+    context::{Context, Ptr},
     debug_info::set_operation_result_name,
     identifier::Identifier,
+    input_err,
     location::{Located, Location},
+    op::{OpObj, op_cast},
     operation::Operation,
-    parsable::{Parsable, ParseResult, StateStream},
+    parsable::{IntoParseResult, Parsable, ParseResult, StateStream},
     result::Result,
-    r#type::TypeObj,
+    r#type::{TypeObj, TypePtr},
     value::Value,
 };
 use combine::{
-    Parser, Stream, between, many, many1,
-    parser::char::{digit, spaces},
+    Parser, Stream, any, attempt, between, choice, many, many1, none_of, one_of, optional,
+    parser::char::{digit, spaces, string},
     sep_by, token,
 };
 
@@ -87,6 +94,51 @@ pub fn attr_parser<'a>()
     AttrObj::parser(())
 }
 
+/// Parse a `"..."` string literal, returning its text as is, escape sequences included.
+fn string_literal_text<Input: Stream<Token = char>>() -> impl Parser<Input, Output = String> {
+    let escaped = token('\\').and(any()).map(|(esc, c)| format!("{esc}{c}"));
+    let unescaped = none_of("\\\"".chars()).map(String::from);
+    between(
+        token('"'),
+        token('"'),
+        many::<Vec<String>, _, _>(escaped.or(unescaped)),
+    )
+    .map(|pieces| format!("\"{}\"", pieces.concat()))
+}
+
+fn verbatim_payload_parse<'a>(
+    state_stream: &mut StateStream<'a>,
+    _arg: (),
+) -> ParseResult<'a, String> {
+    let nested = one_of("<([{".chars()).then(|open| {
+        let close = match open {
+            '<' => '>',
+            '(' => ')',
+            '[' => ']',
+            _ => '}',
+        };
+        verbatim_payload_parser()
+            .skip(token(close))
+            .map(move |inner| format!("{open}{inner}{close}"))
+    });
+    // An arrow, as in function types, doesn't close an angle bracket.
+    let arrow = attempt(string("->")).map(String::from);
+    let plain = none_of("<>()[]{}\"".chars()).map(String::from);
+    many::<Vec<String>, _, _>(choice((arrow, plain, string_literal_text(), nested)))
+        .map(|pieces| pieces.concat())
+        .parse_stream(state_stream)
+        .into()
+}
+
+/// Parse text, as is, in which `<>`, `()`, `[]` and `{}` are balanced. Brackets inside
+/// `"..."` string literals aren't considered. Parsing stops before an unmatched closing
+/// bracket (other than in `->`). This is the payload of a [verbatim attribute](crate::attribute::VerbatimAttrParserFn).
+pub fn verbatim_payload_parser<'a>()
+-> Box<dyn Parser<StateStream<'a>, Output = String, PartialState = ()> + 'a> {
+    combine::parser(|state_stream: &mut StateStream<'a>| verbatim_payload_parse(state_stream, ()))
+        .boxed()
+}
+
 /// Parse a delimitted list of objects.
 pub fn delimited_list_parser<Input: Stream<Token = char>, Output>(
     open: char,
@@ -165,6 +217,103 @@ pub fn block_opd_parser<'a>()
         .boxed()
 }
 
+/// Parse a reference to a symbol, like `@foo`, into its name.
+pub fn symbol_ref_parser<'a>()
+-> Box<dyn Parser<StateStream<'a>, Output = Identifier, PartialState = ()> + 'a> {
+    combine::parser(|parsable_state: &mut StateStream<'a>| {
+        token('@')
+            .with(Identifier::parser(()))
+            .parse_stream(parsable_state)
+            .into_result()
+    })
+    .boxed()
+}
+
+/// Parse the signature of a function type, like `(i32, i32) -> i64`,
+/// as printed by [function_signature](crate::irfmt::printers::function_signature).
+pub fn function_signature_parse<'a>(
+    state_stream: &mut StateStream<'a>,
+    _arg: (),
+) -> ParseResult<'a, TypePtr<FunctionType>> {
+    let results = choice((
+        delimited_list_parser('(', ')', ',', type_parser()),
+        type_parser().map(|result| vec![result]),
+    ));
+    delimited_list_parser('(', ')', ',', type_parser())
+        .skip(spaced(string("->")))
+        .and(results)
+        .parse_stream(state_stream)
+        .map(|(inputs, results)| FunctionType::get(state_stream.state.ctx, inputs, results))
+        .into()
+}
+
+/// A parser for the signature of a function type, like `(i32, i32) -> i64`,
+/// as printed by [function_signature](crate::irfmt::printers::function_signature).
+pub fn function_signature_parser<'a>()
+-> Box<dyn Parser<StateStream<'a>, Output = TypePtr<FunctionType>, PartialState = ()> + 'a> {
+    combine::parser(move |parsable_state: &mut StateStream<'a>| {
+        function_signature_parse(parsable_state, ())
+    })
+    .boxed()
+}
+
+/// A parser for the callee and arguments of a call-like Op, like `@callee(a, b)` or
+/// `fptr(a, b)`, as printed by [call_callee_args](crate::irfmt::printers::op::call_callee_args).
+pub fn call_callee_args_parser<'a>()
+-> Box<dyn Parser<StateStream<'a>, Output = (CallOpCallable, Vec<Value>), PartialState = ()> + 'a> {
+    combine::parser(|parsable_state: &mut StateStream<'a>| {
+        let callee = choice((
+            symbol_ref_parser().map(CallOpCallable::Direct),
+            ssa_opd_parser().map(CallOpCallable::Indirect),
+        ));
+        callee
+            .and(delimited_list_parser('(', ')', ',', ssa_opd_parser()))
+            .parse_stream(parsable_state)
+            .into_result()
+    })
+    .boxed()
+}
+
+/// Parse a call-like Op printed by [call_like](crate::irfmt::printers::op::call_like).
+/// The Op is built by `build` from its callee, callee type and arguments. Its operand
+/// bundles, if any, and other attributes are then set, and `results` are registered
+/// as the SSA definitions of its results.
+pub fn call_like_parse<'a>(
+    state_stream: &mut StateStream<'a>,
+    results: Vec<(Identifier, Location)>,
+    build: impl FnOnce(&mut Context, CallOpCallable, TypePtr<FunctionType>, Vec<Value>) -> Result<OpObj>,
+) -> ParseResult<'a, OpObj> {
+    let loc = state_stream.loc();
+    let bundle = StringAttr::parser(())
+        .and(delimited_list_parser('(', ')', ',', ssa_opd_parser()))
+        .map(|(tag, opds)| OperandBundle::new(&String::from(tag), opds));
+    let ((((callee, args), bundles), other_attrs), callee_ty) = spaced(call_callee_args_parser())
+        .and(optional(attempt(spaced(delimited_list_parser(
+            '[', ']', ',', bundle,
+        )))))
+        .and(optional(attempt(spaced(AttributeDict::parser(())))))
+        .and(spaced(token(':')).with(function_signature_parser()))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+
+    let ctx = &mut state_stream.state.ctx;
+    let op = build(ctx, callee, callee_ty, args)?;
+    let bundles = bundles.unwrap_or_default();
+    if !bundles.is_empty() {
+        let Some(bundle_op) = op_cast::<dyn OperandBundleInterface>(&*op) else {
+            return input_err!(loc, "{} does not have operand bundles", op.opid())?;
+        };
+        bundle_op.set_operand_bundles(ctx, bundles);
+    }
+    if let Some(other_attrs) = other_attrs {
+        let attributes = &mut op.operation().deref_mut(ctx).attributes;
+        attributes.0.extend(other_attrs.0);
+    }
+    process_parsed_ssa_defs(state_stream, &results, op.operation())?;
+    Ok(op).into_parse_result()
+}
+
 /// After an [Operation] is fully parsed, for each result,
 /// set its name and register it as an SSA definition.
 pub fn process_parsed_ssa_defs(
@@ -182,7 +331,7 @@ pub fn process_parsed_ssa_defs(
     for (idx, name_loc) in results.iter().enumerate() {
         let res = op.deref(ctx).result(idx);
         name_tracker.ssa_def(ctx, name_loc, res)?;
-        set_operation_result_name(ctx, op, idx, name_loc.0.clone());
+        set_operation_result_name(ctx, op, idx, name_loc.0);
     }
     Ok(())
 }
@@ -243,4 +392,24 @@ mod test {
         let parsed = type_parser().parse(state_stream).unwrap().0;
         assert_eq!(parsed.disp(&ctx).to_string(), "builtin.integer si32");
     }
+
+    #[test]
+    fn test_parse_function_signature() {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+
+        for signature in [
+            "() -> ()",
+            "(builtin.integer si32) -> builtin.integer si64",
+            "(builtin.integer si32, builtin.integer si32) -> (builtin.integer si32, builtin.integer si64)",
+        ] {
+            let state_stream = state_stream_from_iterator(
+                signature.chars(),
+                parsable::State::new(&mut ctx, location::Source::InMemory),
+            );
+            let parsed = function_signature_parser().parse(state_stream).unwrap().0;
+            let printed = crate::irfmt::printers::function_signature(parsed);
+            assert_eq!(printed.disp(&ctx).to_string(), signature);
+        }
+    }
 }
@@ -8,8 +8,12 @@ use std::fmt;
 pub mod op;
 
 use crate::{
-    context::Context,
-    printable::{ListSeparator, Printable, State, fmt_iter},
+    basic_block::BasicBlock,
+    builtin::types::FunctionType,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    printable::{Highlight, ListSeparator, Printable, State, fmt_iter},
+    r#type::TypePtr,
 };
 
 /// Wrap a function to implement the Printable trait
@@ -75,6 +79,17 @@ pub fn enclosed<P: Printable>(left: &'static str, right: &'static str, p: P) ->
     )
 }
 
+/// Print a reference to `block`, i.e., its label, like `^entry`.
+pub fn block_label(block: Ptr<BasicBlock>) -> impl Printable {
+    PrinterFn(
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            state.highlighted(Highlight::BlockLabel, f, |f| {
+                write!(f, "^{}", block.deref(ctx).printed_label(ctx))
+            })
+        },
+    )
+}
+
 /// Print a function type with inputs and results like `<(i32, i32) -> (i64)>`
 pub fn functional_type<'a>(
     inputs: impl Printable + 'a,
@@ -91,3 +106,30 @@ pub fn functional_type<'a>(
         },
     )
 }
+
+/// Print a reference to the symbol `name`, like `@foo`.
+pub fn symbol_ref(name: Identifier) -> impl Printable {
+    PrinterFn(
+        move |_ctx: &Context, _state: &State, f: &mut fmt::Formatter<'_>| write!(f, "@{}", name),
+    )
+}
+
+/// Print the signature of a function type like `(i32, i32) -> i64`.
+/// The results are parenthesized, unless there's exactly one.
+pub fn function_signature(ty: TypePtr<FunctionType>) -> impl Printable {
+    PrinterFn(
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            let ty = ty.deref(ctx);
+            let sep = ListSeparator::CharSpace(',');
+            write!(
+                f,
+                "({}) -> ",
+                list_with_sep(ty.inputs(), sep).print(ctx, state)
+            )?;
+            match ty.results().as_slice() {
+                [result] => result.fmt(ctx, state, f),
+                results => write!(f, "({})", list_with_sep(results, sep).print(ctx, state)),
+            }
+        },
+    )
+}
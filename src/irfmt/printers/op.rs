@@ -3,22 +3,54 @@
 use std::fmt;
 
 use crate::{
-    builtin::op_interfaces::{OneRegionInterface, SymbolOpInterface},
+    builtin::{
+        ATTR_KEY_DEBUG_INFO,
+        op_interfaces::{
+            ATTR_KEY_CALLEE_TYPE, ATTR_KEY_OPERAND_BUNDLES, CallOpCallable, CallOpInterface,
+            OneRegionInterface, OperandBundleInterface, SymbolOpInterface,
+        },
+    },
     context::Context,
-    op::Op,
-    printable::{Printable, State},
+    identifier::Identifier,
+    op::{Op, op_cast},
+    printable::{ListSeparator, Printable, State},
     r#type::Typed,
 };
 
-use super::PrinterFn;
+use super::{PrinterFn, function_signature, iter_with_sep, quoted, symbol_ref};
+
+/// Print the results of the Op, as `res0, res1 [attrs]`, each followed by its
+/// [attributes](crate::operation::Operation::result_attributes), if it has any.
+/// Prints nothing if the Op has no results. The results and their attributes
+/// are parsed before the Op's own syntax, when followed by ` = `.
+pub fn results<T: Op + ?Sized>(op: &T) -> impl Printable + '_ {
+    PrinterFn(
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            let op = op.operation().deref(ctx);
+            for idx in 0..op.num_results() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+                op.result(idx).fmt(ctx, state, f)?;
+                let attrs = op.result_attributes(idx);
+                if !attrs.0.is_empty() {
+                    write!(f, " {}", attrs.print(ctx, state))?;
+                }
+            }
+            Ok(())
+        },
+    )
+}
 
 /// Print the operation name and associated symbol of the Op. The Op must implement [SymbolOpInterface].
 /// The common pattern is `<opid> @<symbol_name>`. For example a function call would be printed as
 /// `call @my_func`.
 pub fn symb_op_header<T: Op + SymbolOpInterface>(op: &T) -> impl Printable + '_ {
     PrinterFn(
-        move |ctx: &Context, _state: &State, f: &mut fmt::Formatter<'_>| {
-            write!(f, "{} @{}", op.opid(), op.symbol_name(ctx))
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            op.opid().fmt(ctx, state, f)?;
+            write!(f, " ")?;
+            symbol_ref(op.symbol_name(ctx)).fmt(ctx, state, f)
         },
     )
 }
@@ -45,3 +77,75 @@ pub fn region<T: Op + OneRegionInterface>(op: &T) -> impl Printable + '_ {
         },
     )
 }
+
+/// Print the callee and arguments of a call-like Op, like `@callee(a, b)`, or like
+/// `fptr(a, b)` if the callee is the value `fptr`. Its operand bundles aren't printed.
+/// Parsed by [call_callee_args_parser](crate::irfmt::parsers::call_callee_args_parser).
+pub fn call_callee_args<T: Op + CallOpInterface>(op: &T) -> impl Printable + '_ {
+    PrinterFn(
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            match op.callee(ctx) {
+                CallOpCallable::Direct(callee) => symbol_ref(callee).fmt(ctx, state, f)?,
+                CallOpCallable::Indirect(callee) => callee.fmt(ctx, state, f)?,
+            }
+            let args = op.args(ctx);
+            let args = iter_with_sep(args.iter(), ListSeparator::CharSpace(','));
+            write!(f, "({})", args.print(ctx, state))
+        },
+    )
+}
+
+/// Print a call-like Op as `@callee(a, b) ["tag"(c)] [attrs] : (i32, i32) -> i64`, i.e.,
+///   - its [callee and arguments](call_callee_args),
+///   - its [operand bundles](OperandBundleInterface), if it has any,
+///   - its other attributes, if it has any, other than the
+///     [callee type](ATTR_KEY_CALLEE_TYPE), the bundle sizes, the result names
+///     and `implied_attrs` (such as the attribute holding the callee symbol),
+///   - the [signature](function_signature) of its [callee type](CallOpInterface::callee_type).
+///
+/// This is the syntax of calls in all dialects, and is parsed by
+/// [call_like_parse](crate::irfmt::parsers::call_like_parse).
+pub fn call_like<'a, T: Op + CallOpInterface>(
+    op: &'a T,
+    implied_attrs: &'a [Identifier],
+) -> impl Printable + 'a {
+    PrinterFn(
+        move |ctx: &Context, state: &State, f: &mut fmt::Formatter<'_>| {
+            call_callee_args(op).fmt(ctx, state, f)?;
+
+            let bundles = op_cast::<dyn OperandBundleInterface>(op)
+                .map(|op| op.operand_bundles(ctx))
+                .unwrap_or_default();
+            if !bundles.is_empty() {
+                write!(f, " [")?;
+                for (idx, bundle) in bundles.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let opds = iter_with_sep(bundle.operands.iter(), ListSeparator::CharSpace(','));
+                    let tag = quoted(&bundle.tag);
+                    write!(f, "{}({})", tag.print(ctx, state), opds.print(ctx, state))?;
+                }
+                write!(f, "]")?;
+            }
+
+            let mut other_attrs = op.operation().deref(ctx).attributes.clone();
+            for key in [
+                &*ATTR_KEY_CALLEE_TYPE,
+                &*ATTR_KEY_OPERAND_BUNDLES,
+                &*ATTR_KEY_DEBUG_INFO,
+            ]
+            .into_iter()
+            .chain(implied_attrs)
+            {
+                other_attrs.0.remove(key);
+            }
+            if !other_attrs.0.is_empty() {
+                write!(f, " {}", other_attrs.print(ctx, state))?;
+            }
+
+            write!(f, " : ")?;
+            function_signature(op.callee_type(ctx)).fmt(ctx, state, f)
+        },
+    )
+}
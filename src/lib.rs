@@ -9,26 +9,51 @@ extern crate self as pliron;
 // Export pliron_derive as pliron::derive.
 pub use pliron_derive as derive;
 
+pub mod analysis;
 pub mod attribute;
 pub mod basic_block;
+pub mod builder;
 pub mod builtin;
+pub mod bytecode;
 pub mod common_traits;
+pub mod completion;
 pub mod context;
+pub mod conversion;
+pub mod cost;
 pub mod debug_info;
+pub mod diagnostics;
 pub mod dialect;
+pub mod dialects;
+pub mod dynamic;
 pub mod graph;
 pub mod identifier;
+pub mod ir_mapping;
 pub mod irfmt;
+pub mod limits;
 pub mod linked_list;
+pub mod listener;
 pub mod location;
 pub mod op;
 pub mod operation;
 pub mod parsable;
+pub mod pass;
+pub mod pattern_match;
 pub mod printable;
 pub mod region;
 pub mod result;
+#[cfg(feature = "serde")]
+pub mod serializable;
+pub mod session;
 pub mod storage_uniquer;
+pub mod target;
+#[cfg(any(test, feature = "test-dialect"))]
+pub mod test_dialect;
+pub mod testing;
+pub mod transforms;
 pub mod r#type;
 pub mod uniqued_any;
+pub mod unregistered;
 pub mod utils;
 pub mod value;
+
+pub use parsable::parse_source;
@@ -0,0 +1,160 @@
+//! Limits on the size of the IR, for tools that process untrusted inputs.
+//!
+//! Parsing and verification are recursive, so deeply nested IR can overflow
+//! the stack, and huge inputs can exhaust memory. The [Limits] of a [Context]
+//! are enforced while [parsing](crate::parsable) and while [verifying](crate::common_traits::Verify)
+//! an [Operation], failing with a [LimitErr] diagnostic instead:
+//!   - [max_nesting_depth](Limits::max_nesting_depth) bounds how deeply regions,
+//!     and (when parsing) attributes and types, are nested,
+//!   - [max_op_count](Limits::max_op_count) bounds the number of operations
+//!     parsed from a single input, or verified together,
+//!   - [max_attribute_size](Limits::max_attribute_size) bounds the size,
+//!     in bytes of its printed form, of every attribute.
+//!
+//! ```
+//! use pliron::{context::Context, limits::Limits, parsable::parse_source};
+//! let mut ctx = Context::new();
+//! pliron::builtin::register(&mut ctx);
+//! ctx.limits = Limits {
+//!     max_op_count: Some(1),
+//!     ..Limits::default()
+//! };
+//! let err = parse_source(&mut ctx, "builtin.module @m { ^entry(): builtin.module @n {} }");
+//! assert!(err.err().unwrap().to_string().contains("More than 1 operations"));
+//! ```
+
+use std::fmt::{self, Write};
+
+use thiserror::Error;
+
+use crate::{
+    attribute::AttrObj,
+    context::{Context, Ptr},
+    linked_list::ContainsLinkedList,
+    location::Located,
+    operation::Operation,
+    printable::Printable,
+    result::Result,
+    verify_err,
+};
+
+/// Limits on the size of the IR. See [module](self) documentation.
+/// A limit of `None` is no limit at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of regions, attributes and types.
+    pub max_nesting_depth: Option<usize>,
+    /// The maximum number of operations.
+    pub max_op_count: Option<usize>,
+    /// The maximum size of an attribute, in bytes of its printed form.
+    pub max_attribute_size: Option<usize>,
+}
+
+impl Default for Limits {
+    /// Nesting depth is limited to 32, enough for any reasonable input while
+    /// staying within the 2 MiB stack of a spawned thread, even in debug builds.
+    /// Other sizes are unlimited.
+    fn default() -> Self {
+        Limits {
+            max_nesting_depth: Some(32),
+            max_op_count: None,
+            max_attribute_size: None,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_nesting_depth: None,
+            max_op_count: None,
+            max_attribute_size: None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LimitErr {
+    #[error("Nesting depth exceeds the limit of {0}")]
+    NestingDepth(usize),
+    #[error("More than {0} operations")]
+    OpCount(usize),
+    #[error("Attribute is larger than the limit of {0} bytes")]
+    AttributeSize(usize),
+}
+
+/// Is `value` over the limit `limit`, if any?
+pub(crate) fn exceeds(value: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| value > limit)
+}
+
+/// A [Write]r that only counts bytes, failing once there are more than `limit`.
+struct BoundedCounter {
+    len: usize,
+    limit: usize,
+}
+
+impl Write for BoundedCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.len += s.len();
+        if self.len > self.limit {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Is the printed form of `attr` larger than [Limits::max_attribute_size]?
+/// Printing stops as soon as the limit is exceeded.
+pub(crate) fn attr_exceeds_size(ctx: &Context, attr: &AttrObj) -> bool {
+    let Some(limit) = ctx.limits.max_attribute_size else {
+        return false;
+    };
+    let mut counter = BoundedCounter { len: 0, limit };
+    write!(counter, "{}", attr.disp(ctx)).is_err()
+}
+
+/// Check that `op`, along with the operations nested in it, is within the
+/// [Limits] of `ctx`. The IR is walked iteratively, so that this can be done
+/// before (recursively) [verifying](crate::common_traits::Verify) it.
+pub fn check_limits(ctx: &Context, op: Ptr<Operation>) -> Result<()> {
+    let limits = ctx.limits;
+    let mut worklist = vec![(op, 0)];
+    let mut num_ops = 0;
+    while let Some((op, depth)) = worklist.pop() {
+        let op_ref = op.deref(ctx);
+        num_ops += 1;
+        if exceeds(num_ops, limits.max_op_count) {
+            return verify_err!(
+                op_ref.loc(),
+                LimitErr::OpCount(limits.max_op_count.unwrap())
+            );
+        }
+        for attr in op_ref.attributes.0.values() {
+            if attr_exceeds_size(ctx, attr) {
+                return verify_err!(
+                    op_ref.loc(),
+                    LimitErr::AttributeSize(limits.max_attribute_size.unwrap())
+                );
+            }
+        }
+        if op_ref.num_regions() == 0 {
+            continue;
+        }
+        if exceeds(depth + 1, limits.max_nesting_depth) {
+            return verify_err!(
+                op_ref.loc(),
+                LimitErr::NestingDepth(limits.max_nesting_depth.unwrap())
+            );
+        }
+        for region in op_ref.regions() {
+            for block in region.deref(ctx).iter(ctx) {
+                for nested in block.deref(ctx).iter(ctx) {
+                    worklist.push((nested, depth + 1));
+                }
+            }
+        }
+    }
+    Ok(())
+}
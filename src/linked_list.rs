@@ -1,4 +1,23 @@
 //! Provide linked-list operations for `Ptr<T: LinkedList>`.
+//!
+//! # Iterator invalidation
+//! [Iter] follows the links of the list as it goes, with the [Context] borrowed
+//! immutably throughout. Since linking and unlinking only need `&Context`,
+//! the list can still be changed during an iteration, subject to these rules:
+//!   - The item last returned by the iterator may be unlinked, or moved elsewhere.
+//!   - Items may be inserted anywhere, but those inserted after the item last
+//!     returned (or before it, when iterating backwards) may or may not be visited.
+//!   - Items not yet returned must not be unlinked or moved.
+//!
+//! Violating the last rule is caught by debug assertions. Items can't be erased
+//! (deallocated) while the [Context] is borrowed, so passes that erase items,
+//! or that otherwise change the list freely, should iterate over a snapshot of it
+//! instead, such as [BasicBlock::ops_snapshot] or [Region::blocks_snapshot].
+//! The [Ptr]s in a snapshot stay valid as long as their items are alive
+//! (see [Ptr::is_live]).
+//!
+//! [BasicBlock::ops_snapshot]: crate::basic_block::BasicBlock::ops_snapshot
+//! [Region::blocks_snapshot]: crate::region::Region::blocks_snapshot
 use crate::context::{Context, Ptr};
 
 /// The setter methods on [LinkedList] and [ContainsLinkedList]
@@ -37,19 +56,29 @@ pub trait ContainsLinkedList<T: LinkedList>: private::ContainsLinkedList<T> {
     /// Simply get the tail of the list.
     fn tail(&self) -> Option<Ptr<T>>;
     /// Get an iterator over the items. Context is borrowed throughout.
+    /// See [iterator invalidation](self#iterator-invalidation) rules.
     fn iter<'a>(&self, ctx: &'a Context) -> Iter<'a, T> {
+        let head = self.head();
         Iter {
-            next: self.head(),
+            next: head,
             next_back: self.tail(),
+            container: if cfg!(debug_assertions) {
+                head.and_then(|head| head.deref(ctx).container())
+            } else {
+                None
+            },
             ctx,
         }
     }
 }
 
-/// An iterator over the elements of a [LinkedList]
+/// An iterator over the elements of a [LinkedList].
+/// See [iterator invalidation](self#iterator-invalidation) rules.
 pub struct Iter<'a, T: LinkedList> {
     next: Option<Ptr<T>>,
     next_back: Option<Ptr<T>>,
+    /// The list being iterated, tracked only with debug assertions enabled.
+    container: Option<Ptr<T::ContainerType>>,
     ctx: &'a Context,
 }
 
@@ -58,16 +87,28 @@ impl<T: LinkedList> Clone for Iter<'_, T> {
         Self {
             next: self.next,
             next_back: self.next_back,
+            container: self.container,
             ctx: self.ctx,
         }
     }
 }
 
+impl<T: LinkedList> Iter<'_, T> {
+    /// Check that `curr`, about to be returned, is still in the list being iterated.
+    fn debug_check_linked(&self, curr: Ptr<T>) {
+        debug_assert!(
+            curr.deref(self.ctx).container() == self.container,
+            "Linked list item unlinked or moved before the iterator reached it"
+        );
+    }
+}
+
 impl<T: LinkedList> Iterator for Iter<'_, T> {
     type Item = Ptr<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.inspect(|curr| {
+            self.debug_check_linked(*curr);
             if *curr
                 == self
                     .next_back
@@ -89,6 +130,7 @@ impl<T: LinkedList> Iterator for Iter<'_, T> {
 impl<T: LinkedList> DoubleEndedIterator for Iter<'_, T> {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.next_back.inspect(|curr| {
+            self.debug_check_linked(*curr);
             if *curr == self.next.expect("Some(next_back) => Some(next) violated") {
                 self.next_back = None;
                 self.next = None;
@@ -310,6 +352,8 @@ pub(crate) mod tests {
         self_ptr: Ptr<LLNode>,
     }
     impl ArenaObj for LLNode {
+        const KIND: &'static str = "linked list node";
+
         fn arena(ctx: &Context) -> &ArenaCell<Self> {
             &ctx.linked_list_store.nodes
         }
@@ -386,6 +430,8 @@ pub(crate) mod tests {
     }
 
     impl ArenaObj for LLRoot {
+        const KIND: &'static str = "linked list root";
+
         fn arena(ctx: &Context) -> &ArenaCell<Self> {
             &ctx.linked_list_store.containers
         }
@@ -561,4 +607,47 @@ pub(crate) mod tests {
         // n1 itself is unlinked, so this is a panic.
         n2.insert_before(ctx, n1);
     }
+
+    #[test]
+    fn unlink_while_iterating() {
+        let ctx = &mut Context::default();
+
+        let root = LLRoot::empty(ctx);
+        for data in 0..5 {
+            LLNode::new(ctx, data).insert_at_back(root, ctx);
+        }
+        // Unlinking (and relinking elsewhere) the item just returned is allowed.
+        let other = LLRoot::empty(ctx);
+        let iter = root.deref(ctx).iter(ctx);
+        for node in iter {
+            if node.deref(ctx).data % 2 == 0 {
+                node.unlink(ctx);
+                node.insert_at_back(other, ctx);
+            }
+        }
+        validate_list(ctx, root, vec![1, 3]);
+        validate_list(ctx, other, vec![0, 2, 4]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "unlinked or moved before the iterator reached it")]
+    fn unlink_next_while_iterating_panic() {
+        let ctx = &mut Context::default();
+
+        let root = LLRoot::empty(ctx);
+        for data in 0..3 {
+            LLNode::new(ctx, data).insert_at_back(root, ctx);
+        }
+        let other = LLRoot::empty(ctx);
+        let iter = root.deref(ctx).iter(ctx);
+        for node in iter {
+            // Moving the item yet to be returned is caught.
+            let next = node.deref(ctx).next();
+            if let Some(next) = next {
+                next.unlink(ctx);
+                next.insert_at_back(other, ctx);
+            }
+        }
+    }
 }
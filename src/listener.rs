@@ -0,0 +1,205 @@
+//! Listeners notified of changes to the IR.
+//!
+//! A [RewriteListener] [added](Context::add_listener) to the [Context] is notified
+//! whenever an [Operation], [BasicBlock] or [Region] has been modified, i.e., mutated
+//! through a [mutable dereference](Ptr::deref_mut), once that dereference ends.
+//! Since every change to the IR goes through a mutable dereference, no change goes
+//! unnoticed, although notifications may be spurious (for example, for a field
+//! overwritten with the same value). Mutable dereferences that are only read
+//! through aren't notified. Linking an operation into (or unlinking it from)
+//! a block notifies the operation, its neighbours and possibly the block.
+//! Creating IR notifies the new objects too.
+//!
+//! Listeners are given the [Context], and may inspect, or even modify, the IR.
+//! A listener isn't notified of the modifications it makes itself. Other objects
+//! may still be borrowed by the code making the change, so listeners should access
+//! the IR with [try_deref](Ptr::try_deref) and [try_deref_mut](Ptr::try_deref_mut).
+//!
+//! [ChangeTracker] is a listener recording what changed, to then
+//! [verify just the changes](ChangeTracker::verify_changed).
+//!
+//! Consumers that only need to know whether some IR changed since a point in time,
+//! without listening all along, can compare [modification epochs](Context::modification_epoch).
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    basic_block::BasicBlock,
+    common_traits::Verify,
+    context::{Context, Ptr},
+    linked_list::LinkedList,
+    operation::Operation,
+    region::Region,
+    result::Result,
+    value::Value,
+};
+
+/// Notified of changes to the IR. See [module](self) documentation.
+pub trait RewriteListener {
+    /// `op` has been modified.
+    fn notify_op_modified(&mut self, _ctx: &Context, _op: Ptr<Operation>) {}
+
+    /// `block` has been modified.
+    fn notify_block_modified(&mut self, _ctx: &Context, _block: Ptr<BasicBlock>) {}
+
+    /// `region` has been modified.
+    fn notify_region_modified(&mut self, _ctx: &Context, _region: Ptr<Region>) {}
+}
+
+/// A [RewriteListener] recording the operations, blocks and regions modified.
+#[derive(Default)]
+pub struct ChangeTracker {
+    ops: Vec<Ptr<Operation>>,
+    blocks: Vec<Ptr<BasicBlock>>,
+    regions: Vec<Ptr<Region>>,
+    seen_ops: FxHashSet<Ptr<Operation>>,
+    seen_blocks: FxHashSet<Ptr<BasicBlock>>,
+    seen_regions: FxHashSet<Ptr<Region>>,
+}
+
+impl RewriteListener for ChangeTracker {
+    fn notify_op_modified(&mut self, _ctx: &Context, op: Ptr<Operation>) {
+        if self.seen_ops.insert(op) {
+            self.ops.push(op);
+        }
+    }
+
+    fn notify_block_modified(&mut self, _ctx: &Context, block: Ptr<BasicBlock>) {
+        if self.seen_blocks.insert(block) {
+            self.blocks.push(block);
+        }
+    }
+
+    fn notify_region_modified(&mut self, _ctx: &Context, region: Ptr<Region>) {
+        if self.seen_regions.insert(region) {
+            self.regions.push(region);
+        }
+    }
+}
+
+/// The operation whose region contains `op`, if any.
+fn parent_op(ctx: &Context, op: Ptr<Operation>) -> Option<Ptr<Operation>> {
+    let block = op.deref(ctx).container()?;
+    let region = block.deref(ctx).container()?;
+    Some(region.deref(ctx).parent_op())
+}
+
+/// `op`, and the operations enclosing it, up to (and including) `root`.
+/// [None] if `op` isn't nested in `root`.
+fn ancestors_upto(
+    ctx: &Context,
+    op: Ptr<Operation>,
+    root: Ptr<Operation>,
+) -> Option<Vec<Ptr<Operation>>> {
+    let mut ancestors = vec![op];
+    let mut cur = op;
+    while cur != root {
+        cur = parent_op(ctx, cur)?;
+        ancestors.push(cur);
+    }
+    Some(ancestors)
+}
+
+impl ChangeTracker {
+    /// Has nothing been modified?
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.blocks.is_empty() && self.regions.is_empty()
+    }
+
+    /// Forget all the modifications recorded so far.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The (still existing) operations that were modified, in the order of their
+    /// first modification. Blocks and regions modified aren't included.
+    pub fn modified_ops(&self, ctx: &Context) -> Vec<Ptr<Operation>> {
+        self.ops
+            .iter()
+            .copied()
+            .filter(|op| op.is_live(ctx))
+            .collect()
+    }
+
+    /// Verify what has been modified in `root` (or in the operations nested in it):
+    ///   - operations modified are verified fully, along with the operations nested in them,
+    ///   - operations enclosing modified operations, blocks or regions (up to `root`), and
+    ///     operations using the results of modified operations or the arguments of modified
+    ///     blocks, are verified without the operations nested in them
+    ///     (see [Operation::verify_shallow]).
+    ///
+    /// Modifications of operations, blocks and regions that have since been erased, or
+    /// that aren't nested in `root`, are ignored. Returns the number of operations verified
+    /// (counting an operation that's verified fully as one).
+    pub fn verify_changed(&self, ctx: &Context, root: Ptr<Operation>) -> Result<usize> {
+        let (mut full, mut full_seen) = (vec![], FxHashSet::default());
+        let mut shallow = vec![];
+        // Users of `values` may observe changes to them (for example, to their types).
+        let add_users = |shallow: &mut Vec<_>, values: Vec<Value>| {
+            for value in values {
+                let users = value.uses(ctx).map(|r#use| r#use.op);
+                shallow.extend(users.filter(|user| ancestors_upto(ctx, *user, root).is_some()));
+            }
+        };
+
+        for op in self.modified_ops(ctx) {
+            let Some(ancestors) = ancestors_upto(ctx, op, root) else {
+                continue;
+            };
+            if full_seen.insert(op) {
+                full.push(op);
+            }
+            add_users(&mut shallow, op.deref(ctx).results().collect());
+            shallow.extend(ancestors.into_iter().skip(1));
+        }
+        let modified_blocks = self.blocks.iter().filter(|block| block.is_live(ctx));
+        for block in modified_blocks {
+            let Some(region) = block.deref(ctx).container() else {
+                continue;
+            };
+            let parent = region.deref(ctx).parent_op();
+            let Some(ancestors) = ancestors_upto(ctx, parent, root) else {
+                continue;
+            };
+            add_users(&mut shallow, block.deref(ctx).arguments().collect());
+            shallow.extend(ancestors);
+        }
+        let modified_regions = self.regions.iter().filter(|region| region.is_live(ctx));
+        for region in modified_regions {
+            let parent = region.deref(ctx).parent_op();
+            if let Some(ancestors) = ancestors_upto(ctx, parent, root) {
+                shallow.extend(ancestors);
+            }
+        }
+
+        // Operations nested in (or same as) one that's verified fully needn't be verified again.
+        let in_full = |op: Ptr<Operation>| {
+            let mut cur = Some(op);
+            while let Some(op) = cur {
+                if full_seen.contains(&op) {
+                    return true;
+                }
+                cur = parent_op(ctx, op);
+            }
+            false
+        };
+        let full: Vec<_> = full
+            .iter()
+            .copied()
+            .filter(|op| parent_op(ctx, *op).is_none_or(|parent| !in_full(parent)))
+            .collect();
+        let mut shallow_seen = FxHashSet::default();
+        let shallow: Vec<_> = shallow
+            .into_iter()
+            .filter(|op| !in_full(*op) && shallow_seen.insert(*op))
+            .collect();
+
+        for op in &full {
+            op.deref(ctx).verify(ctx)?;
+        }
+        for op in &shallow {
+            op.deref(ctx).verify_shallow(ctx)?;
+        }
+        Ok(full.len() + shallow.len())
+    }
+}
@@ -1,6 +1,6 @@
 //! Source location for different IR entities
 
-use std::{fmt::Debug, path::PathBuf};
+use std::{collections::BTreeMap, fmt::Debug, path::PathBuf};
 
 use combine::stream::position::SourcePosition;
 use rustc_hash::FxHashSet;
@@ -46,6 +46,61 @@ impl Printable for Source {
     }
 }
 
+/// The Unicode byte order mark, skipped at the beginning of sources.
+pub const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Read `\r\n` line endings in `chars` as `\n`.
+pub fn normalize_newlines(chars: impl Iterator<Item = char>) -> impl Iterator<Item = char> {
+    let mut chars = chars.peekable();
+    std::iter::from_fn(move || match chars.next()? {
+        '\r' => Some(chars.next_if_eq(&'\n').unwrap_or('\r')),
+        c => Some(c),
+    })
+}
+
+/// The [SourcePosition] right after the end of `text`,
+/// as computed when parsing `text` (see [byte_offset]).
+pub fn end_position(text: &str) -> SourcePosition {
+    let chars = text.strip_prefix(BYTE_ORDER_MARK).unwrap_or(text).chars();
+    normalize_newlines(chars).fold(SourcePosition::default(), |mut pos, c| {
+        if c == '\n' {
+            pos.line += 1;
+            pos.column = 1;
+        } else {
+            pos.column += 1;
+        }
+        pos
+    })
+}
+
+/// Get the offset, in bytes, in `text`, of the position `pos` of a [Location::SrcPos] parsed
+/// from `text`. Parsed positions count characters (not bytes), ignoring a leading
+/// [BYTE_ORDER_MARK] and counting `\r\n` as a single newline. Returns [None] if
+/// `pos` is beyond the end of `text`.
+pub fn byte_offset(text: &str, pos: SourcePosition) -> Option<usize> {
+    let bom_len = if text.starts_with(BYTE_ORDER_MARK) {
+        BYTE_ORDER_MARK.len_utf8()
+    } else {
+        0
+    };
+    let mut cur = SourcePosition::default();
+    let mut chars = text[bom_len..].char_indices().peekable();
+    loop {
+        let Some((idx, c)) = chars.next() else {
+            return (cur == pos).then_some(text.len());
+        };
+        if cur == pos {
+            return Some(bom_len + idx);
+        }
+        if c == '\n' || (c == '\r' && chars.next_if(|(_, c)| *c == '\n').is_some()) {
+            cur.line += 1;
+            cur.column = 1;
+        } else {
+            cur.column += 1;
+        }
+    }
+}
+
 /// Represents a (combination of) program source locations.
 /// This captures more or less the functionality of MLIR's
 /// [BuiltinLocationAttributes](https://mlir.llvm.org/docs/Dialects/Builtin/#location-attributes).
@@ -58,7 +113,8 @@ pub enum Location {
     /// A collection of other source locations.
     /// This is same as MLIR's [FusedLoc](https://mlir.llvm.org/docs/Dialects/Builtin/#fusedloc).
     Fused {
-        metadata: Option<AttrObj>,
+        /// Boxed, as [AttrObj]s are larger than the other variants.
+        metadata: Option<Box<AttrObj>>,
         locations: Vec<Location>,
     },
     /// Location with a name.
@@ -118,6 +174,22 @@ impl Location {
         sources(self, &mut res);
         res.into_iter().collect()
     }
+
+    /// Is `other` this location, or one of the locations it's made of?
+    pub fn contains(&self, other: &Location) -> bool {
+        self == other
+            || match self {
+                Location::Fused {
+                    metadata: _,
+                    locations,
+                } => locations.iter().any(|loc| loc.contains(other)),
+                Location::Named { name: _, child_loc } => child_loc.contains(other),
+                Location::CallSite { callee, caller } => {
+                    callee.contains(other) || caller.contains(other)
+                }
+                Location::SrcPos { .. } | Location::Unknown => false,
+            }
+    }
 }
 
 impl Printable for Location {
@@ -161,3 +233,70 @@ pub trait Located {
     fn loc(&self) -> Location;
     fn set_loc(&mut self, loc: Location);
 }
+
+/// Maps the lines of a generated artifact (such as LLVM IR emitted for a module)
+/// back to the [Location]s of the IR entities they were generated from.
+/// Lines are numbered from 1, as in [SourcePosition].
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    lines: BTreeMap<usize, Location>,
+}
+
+impl SourceMap {
+    /// Create an empty source map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `line` was generated from `loc`.
+    /// [Unknown](Location::Unknown) locations aren't recorded.
+    pub fn add(&mut self, line: usize, loc: Location) {
+        if loc != Location::Unknown {
+            self.lines.insert(line, loc);
+        }
+    }
+
+    /// Get the location that `line` was generated from.
+    pub fn lookup(&self, line: usize) -> Option<&Location> {
+        self.lines.get(&line)
+    }
+
+    /// Get the lines generated from `loc`, or from locations made of it.
+    pub fn lines_generated_from<'a>(
+        &'a self,
+        loc: &'a Location,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.lines
+            .iter()
+            .filter_map(move |(line, line_loc)| line_loc.contains(loc).then_some(*line))
+    }
+
+    /// Iterate over the lines mapped, in increasing order, along with their locations.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Location)> {
+        self.lines.iter().map(|(line, loc)| (*line, loc))
+    }
+
+    /// Number of lines mapped.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Are no lines mapped?
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+impl Printable for SourceMap {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        _state: &printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for (line, loc) in self.iter() {
+            writeln!(f, "{}: {}", line, loc.disp(ctx))?;
+        }
+        Ok(())
+    }
+}
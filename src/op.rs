@@ -23,7 +23,9 @@
 //! the interface verifier is automatically called during verification
 //! and that a `&dyn Op` object can be [cast](op_cast) into an interface object,
 //! (or that it can be checked if the interface is [implemented](op_impls))
-//! with ease.
+//! with ease. The interfaces implemented by an [Op] can also be queried
+//! from its [OpId], without an instance (see [OpId::implements]
+//! and [OpId::implemented_interfaces]).
 //!
 //! [OpObj]s can be downcasted to their concrete types using
 //! [downcast_rs](https://docs.rs/downcast-rs/1.2.0/downcast_rs/index.html#example-without-generics).
@@ -46,9 +48,10 @@ use thiserror::Error;
 use crate::{
     attribute::AttributeDict,
     builtin::types::FunctionType,
-    common_traits::{Named, Verify},
+    common_traits::Verify,
+    completion,
     context::{Context, Ptr},
-    dialect::DialectName,
+    dialect::{DialectName, Registrant},
     identifier::Identifier,
     impl_printable_for_display, input_err,
     irfmt::{
@@ -56,30 +59,31 @@ use crate::{
             block_opd_parser, delimited_list_parser, location, process_parsed_ssa_defs, spaced,
             ssa_opd_parser, zero_or_more_parser,
         },
-        printers::{functional_type, iter_with_sep},
+        printers::{self, block_label, functional_type, iter_with_sep},
     },
     location::{Located, Location},
     operation::Operation,
     parsable::{IntoParseResult, Parsable, ParseResult, ParserFn, StateStream},
-    printable::{self, Printable},
+    printable::{self, Highlight, Printable},
     region::Region,
     result::Result,
     r#type::Typed,
+    utils::small_box::{Coerce, SmallBox},
 };
 
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 /// An Op's name (not including it's dialect).
-pub struct OpName(String);
+pub struct OpName(Identifier);
 
 impl OpName {
     /// Create a new OpName.
     pub fn new(name: &str) -> OpName {
-        OpName(name.to_string())
+        OpName(name.try_into().expect("Invalid Identifier for OpName"))
     }
 }
 
 impl Deref for OpName {
-    type Target = String;
+    type Target = Identifier;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -100,7 +104,7 @@ impl Parsable for OpName {
         Self: Sized,
     {
         Identifier::parser(())
-            .map(|name| OpName::new(&name))
+            .map(OpName)
             .parse_stream(state_stream)
             .into()
     }
@@ -113,13 +117,22 @@ impl Display for OpName {
 }
 
 /// A combination of an [Op]'s name and its dialect.
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct OpId {
     pub dialect: DialectName,
     pub name: OpName,
 }
 
-impl_printable_for_display!(OpId);
+impl Printable for OpId {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        state.highlighted(Highlight::OpId, f, |f| write!(f, "{}", self))
+    }
+}
 
 impl Parsable for OpId {
     type Arg = ();
@@ -133,6 +146,7 @@ impl Parsable for OpId {
     where
         Self: Sized,
     {
+        completion::expect_here(state_stream, completion::Expected::Op);
         let mut parser = DialectName::parser(())
             .skip(parser::char::char('.'))
             .and(OpName::parser(()))
@@ -147,7 +161,40 @@ impl Display for OpId {
     }
 }
 
-pub(crate) type OpCreator = fn(Ptr<Operation>) -> OpObj;
+impl OpId {
+    /// Do [Op]s of this [OpId] implement interface `T`?
+    /// Unlike [op_impls], this doesn't need an instance of the [Op].
+    pub fn implements<T: ?Sized + Op>(&self) -> bool {
+        let interface = std::any::TypeId::of::<T>();
+        OP_INTERFACE_VERIFIERS_MAP
+            .get(self)
+            .is_some_and(|interfaces| interfaces.iter().any(|(intr, _)| *intr == interface))
+    }
+
+    /// Names of the interfaces implemented by [Op]s of this [OpId],
+    /// with super-interfaces listed before the interfaces that depend on them.
+    pub fn implemented_interfaces(&self) -> Vec<&'static str> {
+        OP_INTERFACE_VERIFIERS_MAP
+            .get(self)
+            .map(|interfaces| {
+                interfaces
+                    .iter()
+                    .map(|(intr, _)| OP_INTERFACE_NAMES[intr])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) type OpCreator = Box<dyn Fn(Ptr<Operation>) -> OpObj>;
+
+/// A storable closure for parsing an [Op], given its (already parsed) results.
+pub(crate) type OpParserFn = Box<
+    dyn for<'a> Fn(
+        &'a (),
+        Vec<(Identifier, Location)>,
+    ) -> Box<dyn Parser<StateStream<'a>, Output = OpObj, PartialState = ()> + 'a>,
+>;
 
 /// A wrapper around [Operation] for Op(code) specific work.
 /// All per-instance data must be in the underyling Operation,
@@ -172,29 +219,34 @@ pub trait Op: Downcast + Verify + Printable {
     fn verify_interfaces(&self, ctx: &Context) -> Result<()>;
 
     /// Register Op in Context and add it to its dialect.
+    /// Panics if a different Op is already registered with the same [OpId],
+    /// or implements interfaces for it.
+    #[track_caller]
     fn register(ctx: &mut Context, op_parser: ParserFn<Vec<(Identifier, Location)>, OpObj>)
     where
         Self: Sized,
     {
         let opid = Self::opid_static();
-        let dialect = opid.dialect.clone();
-        match ctx.ops.entry(opid) {
-            std::collections::hash_map::Entry::Occupied(_) => (),
-            std::collections::hash_map::Entry::Vacant(v) => {
-                v.insert(Self::wrap_operation);
-                let dialect = ctx
-                    .dialects
-                    .get_mut(&dialect)
-                    .unwrap_or_else(|| panic!("Unregistered dialect {}", dialect));
-                dialect.add_op(Self::opid_static(), op_parser);
-            }
+        let dialect = ctx
+            .dialects
+            .get_mut(&opid.dialect)
+            .unwrap_or_else(|| panic!("Unregistered dialect {}", opid.dialect));
+        let registrant = Registrant::rust_type::<Self>();
+        if let Err(err) = dialect.add_op(opid, Box::new(op_parser), registrant) {
+            panic!("{}", err);
         }
+        ctx.ops.insert(opid, Box::new(Self::wrap_operation));
     }
 
     /// Get Op's location
     fn loc(&self, ctx: &Context) -> Location {
         self.operation().deref(ctx).loc()
     }
+
+    /// Names of the interfaces implemented by this Op. See [OpId::implemented_interfaces].
+    fn implemented_interfaces(&self) -> Vec<&'static str> {
+        self.opid().implemented_interfaces()
+    }
 }
 impl_downcast!(Op);
 
@@ -207,7 +259,14 @@ pub(crate) fn from_operation(ctx: &Context, op: Ptr<Operation>) -> OpObj {
 }
 
 /// [Op] objects are boxed and stored in the IR.
-pub type OpObj = Box<dyn Op>;
+/// They're just a [`Ptr<Operation>`](Operation), so they're stored in place, see [SmallBox].
+pub type OpObj = SmallBox<dyn Op>;
+
+unsafe impl<T: Op> Coerce<T> for dyn Op {
+    fn coerce(ptr: *mut T) -> *mut Self {
+        ptr
+    }
+}
 
 /// Cast reference to an [Op] object to an interface reference.
 pub fn op_cast<T: ?Sized + Op>(op: &dyn Op) -> Option<&T> {
@@ -226,9 +285,22 @@ pub type OpInterfaceVerifier = fn(&dyn Op, &Context) -> Result<()>;
 #[distributed_slice]
 pub static OP_INTERFACE_VERIFIERS: [LazyLock<(OpId, (std::any::TypeId, OpInterfaceVerifier))>];
 
-/// All interfaces mapped to their super-interfaces
+/// All interfaces (along with their names) mapped to their super-interfaces
 #[distributed_slice]
-pub static OP_INTERFACE_DEPS: [LazyLock<(std::any::TypeId, Vec<std::any::TypeId>)>];
+pub static OP_INTERFACE_DEPS: [LazyLock<(
+    std::any::TypeId,
+    &'static str,
+    Vec<std::any::TypeId>,
+)>];
+
+/// Names of all interfaces, keyed by their [TypeId](std::any::TypeId).
+pub(crate) static OP_INTERFACE_NAMES: LazyLock<FxHashMap<std::any::TypeId, &'static str>> =
+    LazyLock::new(|| {
+        OP_INTERFACE_DEPS
+            .iter()
+            .map(|lazy| (lazy.0, lazy.1))
+            .collect()
+    });
 
 /// A map from every [Op] to its ordered (as per interface deps) list of interface verifiers.
 /// An interface's super-interfaces are to be verified before it itself is.
@@ -239,7 +311,7 @@ pub static OP_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect OP_INTERFACE_VERIFIERS into an [OpId] indexed map.
     let mut op_intr_verifiers = FxHashMap::default();
     for lazy in OP_INTERFACE_VERIFIERS {
-        let (op_id, (type_id, verifier)) = (**lazy).clone();
+        let (op_id, (type_id, verifier)) = **lazy;
         op_intr_verifiers
             .entry(op_id)
             .and_modify(|verifiers: &mut Vec<(TypeId, OpInterfaceVerifier)>| {
@@ -251,7 +323,7 @@ pub static OP_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect interface deps into a map.
     let interface_deps: FxHashMap<_, _> = OP_INTERFACE_DEPS
         .iter()
-        .map(|lazy| (**lazy).clone())
+        .map(|lazy| (lazy.0, lazy.2.clone()))
         .collect();
 
     // Assign an integer to each interface, such that if y depends on x
@@ -284,7 +356,7 @@ pub static OP_INTERFACE_VERIFIERS_MAP: LazyLock<
 
     // Assign dep_sort_idx to every interface.
     for lazy in OP_INTERFACE_DEPS.iter() {
-        let (intr, _deps) = &**lazy;
+        let (intr, _name, _deps) = &**lazy;
         assign_idx_to_intr(&interface_deps, &mut dep_sort_idx, &mut sort_idx, intr);
     }
 
@@ -299,6 +371,10 @@ pub static OP_INTERFACE_VERIFIERS_MAP: LazyLock<
 /// Printer for an [Op] in canonical syntax.
 /// `res_1, res_2, ... res_n =
 ///      op_id (opd_1, opd_2, ... opd_n) [succ_1, succ_2, ... succ_n] [attr-dict]: function-type (regions)*`
+///
+/// When [State::print_generic](printable::State::print_generic) is set, `op_id` is quoted
+/// (`"op_id"`), marking the [Operation] as being in the generic syntax. Such [Operation]s
+/// are parsed with [canonical_syntax_parser] irrespective of their [Op]'s own parser.
 pub fn canonical_syntax_print(
     op: OpObj,
     ctx: &Context,
@@ -306,13 +382,10 @@ pub fn canonical_syntax_print(
     f: &mut fmt::Formatter<'_>,
 ) -> fmt::Result {
     let sep = printable::ListSeparator::CharSpace(',');
+    let results = printers::op::results(&*op);
     let op = op.operation().deref(ctx);
     let operands = iter_with_sep(op.operands(), sep);
-    let successors = iter_with_sep(
-        op.successors()
-            .map(|succ| "^".to_string() + &succ.unique_name(ctx)),
-        sep,
-    );
+    let successors = iter_with_sep(op.successors().map(block_label), sep);
     let op_type = functional_type(
         iter_with_sep(op.operands().map(|opd| opd.get_type(ctx)), sep),
         iter_with_sep(op.results().map(|res| res.get_type(ctx)), sep),
@@ -320,18 +393,21 @@ pub fn canonical_syntax_print(
     let regions = iter_with_sep(op.regions.iter(), printable::ListSeparator::Newline);
 
     if op.num_results() != 0 {
-        let results = iter_with_sep(op.results(), sep);
-        write!(f, "{} = ", results.disp(ctx))?;
+        write!(f, "{} = ", results.print(ctx, state))?;
     }
 
+    if state.print_generic() {
+        write!(f, "\"{}\"", op.opid().print(ctx, state))?;
+    } else {
+        write!(f, "{}", op.opid().print(ctx, state))?;
+    }
     write!(
         f,
-        "{} ({}) [{}] {}: {}",
-        op.opid().disp(ctx),
-        operands.disp(ctx),
-        successors.disp(ctx),
-        op.attributes.disp(ctx),
-        op_type.disp(ctx),
+        " ({}) [{}] {}: {}",
+        operands.print(ctx, state),
+        successors.print(ctx, state),
+        op.attributes.print(ctx, state),
+        op_type.print(ctx, state),
     )?;
 
     if !op.regions.is_empty() {
@@ -363,7 +439,6 @@ pub fn canonical_syntax_parse<'a>(
         .and((location(), FunctionType::parser(())))
         .then(
             move |(((operands, successors), attr_dict), (fty_loc, fty))| {
-                let opid = opid.clone();
                 let results = results.clone();
                 let fty_loc = fty_loc.clone();
                 combine::parser(move |parsable_state: &mut StateStream<'a>| {
@@ -391,7 +466,7 @@ pub fn canonical_syntax_parse<'a>(
                     }
                     let opr = Operation::new(
                         ctx,
-                        opid.clone(),
+                        opid,
                         results_types,
                         operands.clone(),
                         successors.clone(),
@@ -419,7 +494,7 @@ pub fn canonical_syntax_parser<'a>(
     results: Vec<(Identifier, Location)>,
 ) -> Box<dyn Parser<StateStream<'a>, Output = OpObj, PartialState = ()> + 'a> {
     combine::parser(move |parsable_state: &mut StateStream<'a>| {
-        canonical_syntax_parse(opid.clone(), parsable_state, results.clone())
+        canonical_syntax_parse(opid, parsable_state, results.clone())
     })
     .boxed()
 }
@@ -444,7 +519,7 @@ macro_rules! impl_canonical_syntax {
                 state: &$crate::printable::State,
                 f: &mut std::fmt::Formatter<'_>,
             ) -> std::fmt::Result {
-                $crate::op::canonical_syntax_print(Box::new(*self), ctx, state, f)
+                $crate::op::canonical_syntax_print($crate::op::OpObj::new(*self), ctx, state, f)
             }
         }
 
@@ -486,7 +561,7 @@ mod tests {
         // Collect interface deps into a map.
         let interface_deps: FxHashMap<_, _> = OP_INTERFACE_DEPS
             .iter()
-            .map(|lazy| (**lazy).clone())
+            .map(|lazy| (lazy.0, lazy.2.clone()))
             .collect();
 
         for (op, intrs) in OP_INTERFACE_VERIFIERS_MAP.iter() {
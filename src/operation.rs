@@ -2,30 +2,50 @@
 //! The general idea is similar to MLIR's
 //! [Operation](https://mlir.llvm.org/docs/LangRef/#operations)
 
-use std::marker::PhantomData;
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use combine::{Parser, attempt, parser::char::spaces, token};
+use combine::{Parser, attempt, between, parser::char::spaces, token};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use thiserror::Error;
 
 use crate::{
-    attribute::AttributeDict,
+    analysis::dominance::DominanceInfo,
+    arg_err,
+    attribute::{AttrObj, AttributeDict},
     basic_block::BasicBlock,
+    builtin::{
+        ATTR_KEY_DEBUG_INFO,
+        op_interfaces::{IsolatedFromAboveInterface, RegionKindInterface},
+        types::PendingResultType,
+    },
     common_traits::{Named, Verify},
     context::{ArenaCell, Context, Ptr, private::ArenaObj},
     debug_info,
+    diagnostics::{self, Diagnostic},
+    graph::walkers::{self, Visitor, WalkConfig, interruptible::WalkResult},
     identifier::Identifier,
     input_err,
-    irfmt::parsers::{location, spaced},
-    linked_list::{LinkedList, private},
+    ir_mapping::IRMapping,
+    irfmt::{
+        aliases,
+        parsers::{location, spaced},
+    },
+    limits,
+    linked_list::{ContainsLinkedList, LinkedList, private},
     location::{Located, Location},
-    op::{self, OpId, OpObj},
+    op::{self, OpId, OpObj, op_cast, op_impls},
     parsable::{self, Parsable, ParseResult, StateStream},
-    printable::{self, Printable},
+    printable::{self, Highlight, Printable},
     region::Region,
     result::Result,
     r#type::{TypeObj, Typed},
+    unregistered::UnregisteredOp,
     utils::vec_exns::VecExtns,
-    value::{DefNode, DefTrait, DefUseParticipant, Use, UseNode, Value},
+    value::{DefNode, DefTrait, DefUseParticipant, OpOperand, Use, UseNode, Value},
     verify_err,
 };
 
@@ -39,6 +59,8 @@ pub(crate) struct OpResult {
     res_idx: usize,
     /// [Type](crate::type::Type) of this operation result.
     ty: Ptr<TypeObj>,
+    /// Attributes of this result (such as the alignment of a returned pointer).
+    attributes: AttributeDict,
 }
 
 impl OpResult {
@@ -46,6 +68,11 @@ impl OpResult {
     pub fn get_type(&self) -> Ptr<TypeObj> {
         self.ty
     }
+
+    /// Set the [Type](crate::type::Type) of this operation result.
+    pub(crate) fn set_type(&mut self, ty: Ptr<TypeObj>) {
+        self.ty = ty;
+    }
 }
 
 impl Typed for OpResult {
@@ -58,10 +85,12 @@ impl Printable for OpResult {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
-        write!(f, "{}", self.unique_name(ctx))
+        state.highlighted(Highlight::Value, f, |f| {
+            write!(f, "{}", Value::from(self).printed_name(ctx, state))
+        })
     }
 }
 
@@ -124,6 +153,8 @@ pub struct Operation {
     pub(crate) regions: Vec<Ptr<Region>>,
     /// Source location of this operation.
     loc: Location,
+    /// The [modification epoch](Context::modification_epoch) this was last modified in.
+    modified_epoch: u64,
 }
 
 impl PartialEq for Operation {
@@ -167,6 +198,7 @@ impl Operation {
         successors: Vec<Ptr<BasicBlock>>,
         num_regions: usize,
     ) -> Ptr<Operation> {
+        let modified_epoch = ctx.next_modification_epoch();
         let f = |self_ptr: Ptr<Operation>| Operation {
             opid,
             self_ptr,
@@ -177,6 +209,7 @@ impl Operation {
             attributes: AttributeDict::default(),
             regions: vec![],
             loc: Location::Unknown,
+            modified_epoch,
         };
 
         // Create the new Operation.
@@ -190,6 +223,7 @@ impl Operation {
                 def_op: newop,
                 ty,
                 res_idx,
+                attributes: AttributeDict::default(),
             })
             .collect();
         newop.deref_mut(ctx).results = results;
@@ -211,6 +245,73 @@ impl Operation {
         newop
     }
 
+    /// Create a new, unlinked operation, with `num_results` results whose types
+    /// aren't known yet. Until they're set with [finalize_result_types](Self::finalize_result_types),
+    /// the results have [PendingResultType] and the operation fails to verify.
+    pub fn new_with_pending_results(
+        ctx: &mut Context,
+        opid: OpId,
+        num_results: usize,
+        operands: Vec<Value>,
+        successors: Vec<Ptr<BasicBlock>>,
+        num_regions: usize,
+    ) -> Ptr<Operation> {
+        let pending_ty = PendingResultType::get(ctx).into();
+        Self::new(
+            ctx,
+            opid,
+            vec![pending_ty; num_results],
+            operands,
+            successors,
+            num_regions,
+        )
+    }
+
+    /// Are the types of any of this operation's results yet to be finalized?
+    pub fn has_pending_result_types(&self, ctx: &Context) -> bool {
+        self.results
+            .iter()
+            .any(|res| res.get_type().isa::<PendingResultType>(ctx))
+    }
+
+    /// Set the types of the results of an operation created with
+    /// [new_with_pending_results](Self::new_with_pending_results).
+    /// Fails if the result types are already finalized, or if the number of
+    /// types provided doesn't match the number of results.
+    pub fn finalize_result_types(
+        ptr: Ptr<Operation>,
+        ctx: &mut Context,
+        result_types: Vec<Ptr<TypeObj>>,
+    ) -> Result<()> {
+        let op = ptr.deref(ctx);
+        let loc = op.loc();
+        if !op.has_pending_result_types(ctx) {
+            return arg_err!(loc, ResultTypesErr::AlreadyFinalized);
+        }
+        if op.num_results() != result_types.len() {
+            return arg_err!(
+                loc,
+                ResultTypesErr::NumMismatch {
+                    expected: op.num_results(),
+                    provided: result_types.len()
+                }
+            );
+        }
+        if result_types
+            .iter()
+            .any(|ty| ty.isa::<PendingResultType>(ctx))
+        {
+            return arg_err!(loc, ResultTypesErr::Pending);
+        }
+        drop(op);
+
+        let op = &mut *ptr.deref_mut(ctx);
+        for (res, ty) in op.results.iter_mut().zip(result_types) {
+            res.set_type(ty);
+        }
+        Ok(())
+    }
+
     /// Number of results this operation has.
     pub fn num_results(&self) -> usize {
         self.results.len()
@@ -229,6 +330,16 @@ impl Operation {
         self.results.iter().map(Into::into)
     }
 
+    /// The attributes of the idx'th result.
+    pub fn result_attributes(&self, idx: usize) -> &AttributeDict {
+        &self.result_ref(idx).attributes
+    }
+
+    /// The attributes of the idx'th result, to modify them.
+    pub fn result_attributes_mut(&mut self, idx: usize) -> &mut AttributeDict {
+        &mut self.result_mut(idx).attributes
+    }
+
     /// Does any result of this operation have a use?
     pub fn has_use(&self) -> bool {
         self.results.iter().any(|res| res.def.is_used())
@@ -267,6 +378,11 @@ impl Operation {
         self.operand_ref(opd_idx).into()
     }
 
+    /// Get the operands of this [Operation] as [OpOperand]s.
+    pub fn op_operands(&self) -> impl Iterator<Item = OpOperand> + '_ {
+        self.operands.iter().map(Use::from)
+    }
+
     /// Get an iterator over the results of this operation.
     pub fn operands(&self) -> impl Iterator<Item = Value> + Clone + '_ {
         self.operands.iter().map(Operand::def)
@@ -281,6 +397,21 @@ impl Operation {
         cur_def.replace_use_with(ctx, cur_use, &other);
     }
 
+    /// Replace all operands of `this` with `operands`.
+    /// The number of operands may differ from the current number of operands.
+    pub fn set_operands(this: Ptr<Operation>, ctx: &Context, operands: Vec<Value>) {
+        let old_operands = std::mem::take(&mut this.deref_mut(ctx).operands);
+        for opd in &old_operands {
+            opd.drop_use(ctx);
+        }
+        let operands = operands
+            .iter()
+            .enumerate()
+            .map(|(opd_idx, def)| Operand::new(ctx, *def, this, opd_idx))
+            .collect();
+        this.deref_mut(ctx).operands = operands;
+    }
+
     /// Get number of successors
     pub fn num_successors(&self) -> usize {
         self.successors.len()
@@ -321,6 +452,68 @@ impl Operation {
         self.successors.iter().map(|opd| opd.def())
     }
 
+    /// Create an (unlinked) copy of this operation, along with its regions (see
+    /// [Region::clone_into]), remapping operands and successors according to `mapping`.
+    /// The results of the operation are mapped to their copies in `mapping`.
+    /// The copy is a different definition, so the names of its results aren't copied.
+    /// See [ir_mapping](crate::ir_mapping).
+    pub fn clone(ptr: Ptr<Self>, ctx: &mut Context, mapping: &mut IRMapping) -> Ptr<Operation> {
+        let (opid, result_types, result_attrs, operands, successors, regions, mut attributes, loc) = {
+            let op_ref = ptr.deref(ctx);
+            (
+                op_ref.opid(),
+                op_ref.results().map(|res| res.get_type(ctx)).collect(),
+                op_ref
+                    .results
+                    .iter()
+                    .map(|res| res.attributes.clone())
+                    .collect::<Vec<_>>(),
+                op_ref.operands().map(|opd| mapping.value(opd)).collect(),
+                op_ref
+                    .successors()
+                    .map(|succ| mapping.block(succ))
+                    .collect(),
+                op_ref.regions().collect::<Vec<_>>(),
+                op_ref.attributes.clone(),
+                op_ref.loc(),
+            )
+        };
+        attributes.0.remove(&*ATTR_KEY_DEBUG_INFO);
+        let new_op = Operation::new(ctx, opid, result_types, operands, successors, regions.len());
+        {
+            let mut new_op_ref = new_op.deref_mut(ctx);
+            new_op_ref.attributes = attributes;
+            new_op_ref.set_loc(loc);
+            for (res, res_attrs) in new_op_ref.results.iter_mut().zip(result_attrs) {
+                res.attributes = res_attrs;
+            }
+        }
+        let results: Vec<_> = ptr.deref(ctx).results().collect();
+        let new_results: Vec<_> = new_op.deref(ctx).results().collect();
+        mapping.map_values(results, new_results);
+
+        for (region_idx, region) in regions.into_iter().enumerate() {
+            let new_region = new_op.deref(ctx).region(region_idx);
+            Region::clone_into(region, ctx, new_region, mapping);
+        }
+        new_op
+    }
+
+    /// Is this operation in `region`, directly or nested in other operations?
+    pub fn is_in_region(&self, ctx: &Context, region: Ptr<Region>) -> bool {
+        let mut block = self.container();
+        while let Some(cur_block) = block {
+            let Some(cur_region) = cur_block.deref(ctx).container() else {
+                return false;
+            };
+            if cur_region == region {
+                return true;
+            }
+            block = cur_region.deref(ctx).parent_op().deref(ctx).container();
+        }
+        false
+    }
+
     /// Create an OpObj corresponding to self.
     pub fn op(ptr: Ptr<Self>, ctx: &Context) -> OpObj {
         op::from_operation(ctx, ptr)
@@ -361,7 +554,34 @@ impl Operation {
 
     /// Get the OpId of the Op of this Operation.
     pub fn opid(&self) -> OpId {
-        self.opid.clone()
+        self.opid
+    }
+
+    /// The [modification epoch](Context::modification_epoch) this operation was last
+    /// modified in. Changes to the operations nested in it aren't accounted for,
+    /// see [modified_since](Self::modified_since) for that.
+    pub fn modified_epoch(&self) -> u64 {
+        self.modified_epoch
+    }
+
+    /// Has this operation, or any operation, block or region nested in it, been modified
+    /// (or created) after `epoch`? Operations erased from, or moved out of, a block
+    /// modify the block or their neighbours, so they're accounted for too.
+    /// See [Context::modification_epoch].
+    pub fn modified_since(ptr: Ptr<Self>, ctx: &Context, epoch: u64) -> bool {
+        let op = ptr.deref(ctx);
+        op.modified_epoch() > epoch
+            || op.regions().any(|region| {
+                let region = region.deref(ctx);
+                region.modified_epoch() > epoch
+                    || region.iter(ctx).any(|block| {
+                        let block = block.deref(ctx);
+                        block.modified_epoch() > epoch
+                            || block
+                                .iter(ctx)
+                                .any(|op| Operation::modified_since(op, ctx, epoch))
+                    })
+            })
     }
 
     /// Drop all uses that this operation holds.
@@ -397,6 +617,17 @@ impl Operation {
         ArenaObj::dealloc(ptr, ctx);
     }
 
+    /// Walk this operation and everything nested in it, calling back to `visitor`
+    /// in the order specified by `config`. See [Visitor].
+    pub fn walk<V: Visitor>(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        config: &WalkConfig,
+        visitor: &mut V,
+    ) -> WalkResult<V::Break> {
+        walkers::interruptible::walk_op(ctx, visitor, config, ptr, walkers::visit)
+    }
+
     /// Get a reference to the idx'th result.
     pub(crate) fn result_ref(&self, idx: usize) -> &OpResult {
         self.results
@@ -441,6 +672,9 @@ impl Operation {
 }
 
 impl ArenaObj for Operation {
+    const KIND: &'static str = "operation";
+    const SHORT_KIND: &'static str = "op";
+
     fn arena(ctx: &Context) -> &ArenaCell<Self> {
         &ctx.operations
     }
@@ -456,6 +690,22 @@ impl ArenaObj for Operation {
     fn self_ptr(&self, _ctx: &Context) -> Ptr<Self> {
         self.self_ptr
     }
+    fn set_modified_epoch(&mut self, epoch: u64) {
+        self.modified_epoch = epoch;
+    }
+    fn notify_modified(ptr: Ptr<Self>, ctx: &Context) {
+        ctx.notify_listeners(|listener| listener.notify_op_modified(ctx, ptr));
+    }
+    fn fmt_debug_details(
+        ptr: Ptr<Self>,
+        ctx: &Context,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match ptr.try_deref(ctx) {
+            Some(op) => write!(f, " ({})", op.opid),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Container for a [Use] in an [Operation].
@@ -505,14 +755,14 @@ impl<T: DefUseParticipant> From<&Operand<T>> for Use<T> {
     }
 }
 
-impl<T: DefUseParticipant + Named> Printable for Operand<T> {
+impl Printable for Operand<Value> {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        write!(f, "{}", self.r#use.def().unique_name(ctx))
+        write!(f, "{}", self.r#use.def().printed_name(ctx, state))
     }
 }
 
@@ -526,6 +776,23 @@ impl<T: DefUseParticipant + Typed> Typed for Operand<T> {
 #[error("operand is not a use of its def")]
 pub struct DefUseVerifyErr;
 
+#[derive(Error, Debug)]
+#[error("Operand {opd_idx} ({value}) is used before it is defined, or outside its scope")]
+pub struct OperandDominanceErr {
+    pub opd_idx: usize,
+    pub value: String,
+}
+
+#[derive(Error, Debug)]
+pub enum ResultTypesErr {
+    #[error("Operation result types are yet to be finalized")]
+    Pending,
+    #[error("Operation result types are already finalized")]
+    AlreadyFinalized,
+    #[error("Operation has {expected} results, but {provided} result types were provided")]
+    NumMismatch { expected: usize, provided: usize },
+}
+
 impl<T: DefUseParticipant + DefTrait> Verify for Operand<T> {
     fn verify(&self, ctx: &Context) -> Result<()> {
         if !self.r#use.def().defnode_ref(ctx).has_use_of(&self.into()) {
@@ -537,26 +804,230 @@ impl<T: DefUseParticipant + DefTrait> Verify for Operand<T> {
     }
 }
 
-impl Verify for Operation {
-    fn verify(&self, ctx: &Context) -> Result<()> {
+impl Operation {
+    /// Verify this operation, but not the operations nested in its regions.
+    pub fn verify_shallow(&self, ctx: &Context) -> Result<()> {
+        self.verify_impl(ctx, false, &mut DominanceInfo::new())
+    }
+
+    fn verify_impl(&self, ctx: &Context, nested: bool, dom_info: &mut DominanceInfo) -> Result<()> {
+        self.verify_attributes_and_operands(ctx)?;
+        self.verify_operand_dominance(ctx, dom_info)?;
+        self.verify_successors(ctx)?;
+        if nested {
+            for region in &self.regions {
+                for block in region.deref(ctx).iter(ctx) {
+                    for op in block.deref(ctx).iter(ctx) {
+                        op.deref(ctx).verify_impl(ctx, true, dom_info)?;
+                    }
+                }
+            }
+        }
+        self.verify_op(ctx)
+    }
+
+    /// The checks of [verify_impl](Self::verify_impl) before operand dominance.
+    fn verify_attributes_and_operands(&self, ctx: &Context) -> Result<()> {
+        if self.has_pending_result_types(ctx) {
+            return verify_err!(self.loc(), ResultTypesErr::Pending);
+        }
         for attr in self.attributes.0.values() {
             attr.verify(ctx)?;
-            attr.verify_interfaces(ctx)?;
         }
         for opd in &self.operands {
             opd.verify(ctx)?;
         }
+        Ok(())
+    }
+
+    fn verify_successors(&self, ctx: &Context) -> Result<()> {
         for opd in &self.successors {
             opd.verify(ctx)?;
         }
-        for region in &self.regions {
-            region.verify(ctx)?;
-        }
+        Ok(())
+    }
+
+    /// Run the verifiers of the [Op] and its interfaces.
+    fn verify_op(&self, ctx: &Context) -> Result<()> {
         Self::op(self.self_ptr, ctx).verify_interfaces(ctx)?;
         Self::op(self.self_ptr, ctx).verify(ctx)
     }
 }
 
+impl Operation {
+    /// Check that the operands of this operation dominate it (see [DominanceInfo]).
+    /// Operations not in a block yet aren't checked.
+    fn verify_operand_dominance(&self, ctx: &Context, dom_info: &mut DominanceInfo) -> Result<()> {
+        if self.container().is_none() {
+            return Ok(());
+        }
+        for (opd_idx, opd) in self.operands().enumerate() {
+            if !dom_info.properly_dominates_value(ctx, opd, self.self_ptr) {
+                return self.operand_dominance_err(ctx, opd_idx);
+            }
+        }
+        Ok(())
+    }
+
+    /// The error for operand `opd_idx` not dominating this operation.
+    fn operand_dominance_err(&self, ctx: &Context, opd_idx: usize) -> Result<()> {
+        verify_err!(
+            self.loc(),
+            OperandDominanceErr {
+                opd_idx,
+                value: self.operand(opd_idx).unique_name(ctx).to_string(),
+            }
+        )
+    }
+}
+
+impl Verify for Operation {
+    /// Verify this operation and the operations nested in it,
+    /// first [checking](limits::check_limits) that they're within the [Limits](limits::Limits).
+    fn verify(&self, ctx: &Context) -> Result<()> {
+        limits::check_limits(ctx, self.self_ptr)?;
+        self.verify_impl(ctx, true, &mut DominanceInfo::new())
+    }
+}
+
+impl Operation {
+    /// Like [Verify::verify], but rather than stopping at the first invalid operation,
+    /// report the first error of every invalid operation, [sorted by location](diagnostics::sort_by_location).
+    /// Nothing is reported but the error if the IR isn't within the [Limits](limits::Limits).
+    /// Operations are verified in turn, on the calling thread.
+    /// See [verify_all_parallel](Self::verify_all_parallel).
+    pub fn verify_all(&self, ctx: &Context) -> Vec<Diagnostic> {
+        if let Err(err) = limits::check_limits(ctx, self.self_ptr) {
+            return vec![Diagnostic::from_error(&err)];
+        }
+        let mut diagnostics = vec![];
+        let mut dom_info = DominanceInfo::new();
+        let mut worklist = vec![self.self_ptr];
+        while let Some(op) = worklist.pop() {
+            let op_ref = op.deref(ctx);
+            if let Err(err) = op_ref.verify_impl(ctx, false, &mut dom_info) {
+                diagnostics.push(Diagnostic::from_error(&err));
+            }
+            for region in op_ref.regions().collect::<Vec<_>>().into_iter().rev() {
+                let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+                for block in blocks.into_iter().rev() {
+                    let ops: Vec<_> = block.deref(ctx).iter(ctx).collect();
+                    worklist.extend(ops.into_iter().rev());
+                }
+            }
+        }
+        diagnostics::sort_by_location(ctx, &mut diagnostics);
+        diagnostics
+    }
+    /// Like [verify_all](Self::verify_all), reporting the same diagnostics in the same order,
+    /// but checking that operands dominate their uses on up to `num_threads` threads.
+    ///
+    /// Checking dominance computes dominator trees, so it's split up by the subtrees
+    /// [isolated from above](IsolatedFromAboveInterface), which are checked in parallel
+    /// over a [FrozenContext](crate::context::FrozenContext). The verifiers of [Op](op::Op)s and
+    /// [Attribute](crate::attribute::Attribute)s take a [Context], which isn't thread safe,
+    /// so they're run before, on the calling thread. Then the errors of each operation are
+    /// merged, in the order of the operations, and [sorted by location](diagnostics::sort_by_location),
+    /// so that the diagnostics don't depend on how the threads are scheduled.
+    pub fn verify_all_parallel(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        num_threads: usize,
+    ) -> Vec<Diagnostic> {
+        if let Err(err) = limits::check_limits(ctx, ptr) {
+            return vec![Diagnostic::from_error(&err)];
+        }
+
+        // On the calling thread, run the verifiers, and collect the regions exempt from
+        // dominance, and the operations of each isolated subtree to check dominance for.
+        let mut ops = vec![];
+        let mut results = vec![];
+        let mut graph_regions = FxHashSet::default();
+        let mut subtrees = vec![vec![]];
+        let mut worklist = vec![(ptr, 0)];
+        while let Some((op, subtree)) = worklist.pop() {
+            let op_ref = op.deref(ctx);
+            let result = op_ref.verify_attributes_and_operands(ctx).map(|()| {
+                if op_ref.container().is_some() {
+                    subtrees[subtree].push(ops.len());
+                }
+                op_ref
+                    .verify_successors(ctx)
+                    .and_then(|()| op_ref.verify_op(ctx))
+            });
+            ops.push(op);
+            results.push(result);
+
+            let op_obj = Self::op(op, ctx);
+            if let Some(region_kind) = op_cast::<dyn RegionKindInterface>(&*op_obj) {
+                graph_regions.extend(
+                    op_ref
+                        .regions()
+                        .enumerate()
+                        .filter(|(idx, _)| !region_kind.has_ssa_dominance(*idx))
+                        .map(|(_, region)| region),
+                );
+            }
+            let nested_subtree =
+                if op != ptr && op_impls::<dyn IsolatedFromAboveInterface>(&*op_obj) {
+                    subtrees.push(vec![]);
+                    subtrees.len() - 1
+                } else {
+                    subtree
+                };
+            for region in op_ref.regions().collect::<Vec<_>>().into_iter().rev() {
+                let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+                for block in blocks.into_iter().rev() {
+                    let nested: Vec<_> = block.deref(ctx).iter(ctx).collect();
+                    worklist.extend(nested.into_iter().rev().map(|op| (op, nested_subtree)));
+                }
+            }
+        }
+
+        // Check dominance for the subtrees in parallel, each thread taking the next subtree.
+        let frozen = ctx.freeze(ptr);
+        let next_subtree = AtomicUsize::new(0);
+        let check_subtrees = || {
+            let mut non_dominating = vec![];
+            while let Some(subtree) = subtrees.get(next_subtree.fetch_add(1, Ordering::Relaxed)) {
+                let mut dom_info = DominanceInfo::new();
+                for &op_idx in subtree {
+                    let op = ops[op_idx];
+                    let opd_idx = frozen.get(op).operands().position(|opd| {
+                        !dom_info.properly_dominates_value_with(&frozen, opd, op, |region| {
+                            !graph_regions.contains(&region)
+                        })
+                    });
+                    non_dominating.extend(opd_idx.map(|opd_idx| (op_idx, opd_idx)));
+                }
+            }
+            non_dominating
+        };
+        let non_dominating: FxHashMap<_, _> = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..num_threads.clamp(1, subtrees.len()))
+                .map(|_| s.spawn(check_subtrees))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        let mut diagnostics = vec![];
+        for (op_idx, (op, result)) in ops.into_iter().zip(results).enumerate() {
+            let result = result.and_then(|result| match non_dominating.get(&op_idx) {
+                Some(opd_idx) => op.deref(ctx).operand_dominance_err(ctx, *opd_idx),
+                None => result,
+            });
+            if let Err(err) = result {
+                diagnostics.push(Diagnostic::from_error(&err));
+            }
+        }
+        diagnostics::sort_by_location(ctx, &mut diagnostics);
+        diagnostics
+    }
+}
+
 impl Printable for Operation {
     fn fmt(
         &self,
@@ -564,7 +1035,14 @@ impl Printable for Operation {
         state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        Self::op(self.self_ptr, ctx).fmt(ctx, state, f)
+        let op = Self::op(self.self_ptr, ctx);
+        state.naming_values(|| {
+            if state.print_generic() {
+                op::canonical_syntax_print(op, ctx, state, f)
+            } else {
+                op.fmt(ctx, state, f)
+            }
+        })
     }
 }
 
@@ -586,10 +1064,52 @@ impl Parsable for Operation {
     // - res_1, res_2, ..., res_n = opid
     // - opid
     // and hand it over to the Op specific parser.
+    // If opid is quoted (i.e., "opid"), the Op is in the generic syntax
+    // and is handed over to the canonical syntax parser instead.
     fn parse<'a>(
         state_stream: &mut parsable::StateStream<'a>,
         _arg: Self::Arg,
     ) -> ParseResult<'a, Self::Parsed> {
+        if state_stream.state.name_tracker.is_top_level() {
+            // Alias definitions may surround the top-level operation,
+            // and aliases used in it are resolved once it is parsed.
+            return aliases::top_level_op_parse(
+                state_stream,
+                combine::parser(Operation::parse_operation),
+            );
+        }
+        Operation::parse_operation(state_stream)
+    }
+}
+
+impl Operation {
+    /// Set the attributes of the results of `op`, as parsed along with their names.
+    fn set_parsed_result_attrs(
+        op: Ptr<Operation>,
+        ctx: &mut Context,
+        loc: &Location,
+        result_attrs: Vec<Option<AttributeDict>>,
+    ) -> Result<()> {
+        if result_attrs.len() > op.deref(ctx).num_results() {
+            input_err!(
+                loc.clone(),
+                "Operation has {} results, but {} were named",
+                op.deref(ctx).num_results(),
+                result_attrs.len()
+            )?
+        }
+        let mut op_ref = op.deref_mut(ctx);
+        for (idx, attrs) in result_attrs.into_iter().enumerate() {
+            if let Some(attrs) = attrs {
+                *op_ref.result_attributes_mut(idx) = attrs;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_operation<'a>(
+        state_stream: &mut parsable::StateStream<'a>,
+    ) -> ParseResult<'a, Ptr<Operation>> {
         let loc = state_stream.loc();
         let _src = loc
             .source()
@@ -598,35 +1118,64 @@ impl Parsable for Operation {
         let results_opid = combine::optional(attempt(
             spaces()
                 .with(combine::sep_by::<Vec<_>, _, _, _>(
-                    (location(), Identifier::parser(())).skip(spaces()),
+                    (
+                        location(),
+                        Identifier::parser(()).skip(spaces()),
+                        combine::optional(AttributeDict::parser(()).skip(spaces())),
+                    ),
                     token(',').skip(spaces()),
                 ))
                 .skip(spaced(token('='))),
         ))
-        .and(spaced(OpId::parser(())));
+        .and(spaced(
+            between(token('"'), token('"'), OpId::parser(()))
+                .map(|opid| (opid, true))
+                .or(OpId::parser(()).map(|opid| (opid, false))),
+        ));
 
         results_opid
-            .then(|(results_opt, opid)| {
+            .then(|(results_opt, (opid, is_generic))| {
                 let loc = loc.clone();
-                let results: Vec<_> = results_opt
+                let (results, result_attrs): (Vec<_>, Vec<_>) = results_opt
                     .unwrap_or(vec![])
                     .into_iter()
-                    .map(|(res_loc, id)| (id, (res_loc)))
-                    .collect();
+                    .map(|(res_loc, id, attrs)| ((id, res_loc), attrs))
+                    .unzip();
                 combine::parser(move |parsable_state: &mut StateStream<'a>| {
-                    let state = &parsable_state.state;
-                    let dialect = state
-                        .ctx
-                        .dialects
-                        .get(&opid.dialect)
-                        .expect("Dialect name parsed but dialect isn't registered");
-                    let Some(opid_parser) = dialect.ops.get(&opid) else {
-                        input_err!(loc.clone(), "Unregistered Op {}", opid.disp(state.ctx))?
+                    parsable_state.state.num_ops += 1;
+                    let limit = parsable_state.state.ctx.limits.max_op_count;
+                    if limits::exceeds(parsable_state.state.num_ops, limit) {
+                        input_err!(loc.clone(), limits::LimitErr::OpCount(limit.unwrap()))?
+                    }
+                    let state = &mut parsable_state.state;
+                    let (op, commit) = match state.ctx.dialects.get(&opid.dialect) {
+                        // Dialect names parse only if registered, or if that's allowed.
+                        None => UnregisteredOp::parser(state.ctx, opid, results.clone())
+                            .parse_stream(parsable_state)
+                            .map(|op| op.operation())
+                            .into_result()?,
+                        Some(dialect) => {
+                            let Some(opid_parser) = dialect.ops.get(&opid) else {
+                                input_err!(loc.clone(), "Unregistered Op {}", opid.disp(state.ctx))?
+                            };
+                            let mut opid_parser = if is_generic {
+                                op::canonical_syntax_parser(opid, results.clone())
+                            } else {
+                                opid_parser(&(), results.clone())
+                            };
+                            opid_parser
+                                .parse_stream(parsable_state)
+                                .map(|op| op.operation())
+                                .into_result()?
+                        }
                     };
-                    opid_parser(&(), results.clone())
-                        .parse_stream(parsable_state)
-                        .map(|op| op.operation())
-                        .into()
+                    Operation::set_parsed_result_attrs(
+                        op,
+                        parsable_state.state.ctx,
+                        &loc,
+                        result_attrs.clone(),
+                    )?;
+                    Ok((op, commit))
                 })
             })
             .parse_stream(state_stream)
@@ -637,3 +1186,92 @@ impl Parsable for Operation {
             .into()
     }
 }
+
+/// Structural hashing and equality of [Operation]s, for example to find common
+/// subexpressions. Two [Operation]s are equivalent if they have the same [OpId],
+/// operands, result types and result attributes, successors and attributes (except `ignored_attrs`
+/// and [ATTR_KEY_DEBUG_INFO]), and have no regions.
+/// Equivalent [Operation]s have the same [hash](Self::hash).
+///
+/// [OpEquivalence](crate::builtin::op_interfaces::OpEquivalence) customizes this per [Op](crate::op::Op).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OperationEquivalence<'a> {
+    /// Is the order of operands irrelevant?
+    pub commutative: bool,
+    /// Attributes that don't affect equivalence.
+    pub ignored_attrs: &'a [Identifier],
+}
+
+impl OperationEquivalence<'_> {
+    /// Attributes of `op` that are relevant for equivalence, sorted by their keys.
+    fn attributes<'b>(
+        &self,
+        op: &'b Operation,
+    ) -> impl Iterator<Item = (&'b Identifier, &'b AttrObj)> {
+        op.attributes
+            .iter_sorted()
+            .filter(|(key, _)| **key != *ATTR_KEY_DEBUG_INFO && !self.ignored_attrs.contains(key))
+    }
+
+    /// Hash `op` into `state`.
+    pub fn hash(&self, ctx: &Context, op: Ptr<Operation>, mut state: &mut dyn Hasher) {
+        let op = &*op.deref(ctx);
+        op.opid().hash(&mut state);
+        if self.commutative {
+            // Combine the operand hashes in an order independent way.
+            let mut opd_hashes: Vec<_> = op
+                .operands()
+                .map(|opd| {
+                    let mut opd_state = FxHasher::default();
+                    opd.hash(&mut opd_state);
+                    opd_state.finish()
+                })
+                .collect();
+            opd_hashes.sort_unstable();
+            opd_hashes.hash(&mut state);
+        } else {
+            op.operands().for_each(|opd| opd.hash(&mut state));
+        }
+        (0..op.num_results()).for_each(|idx| op.get_type(idx).hash(&mut state));
+        op.successors().for_each(|succ| succ.hash(&mut state));
+        op.num_regions().hash(&mut state);
+        for (key, val) in self.attributes(op) {
+            key.hash(&mut state);
+            val.attr_id().hash(&mut state);
+        }
+    }
+
+    /// Are `op1` and `op2` equivalent?
+    pub fn is_equivalent(&self, ctx: &Context, op1: Ptr<Operation>, op2: Ptr<Operation>) -> bool {
+        if op1 == op2 {
+            return true;
+        }
+        let (op1, op2) = (&*op1.deref(ctx), &*op2.deref(ctx));
+        if op1.opid() != op2.opid()
+            || op1.num_regions() != 0
+            || op2.num_regions() != 0
+            || op1.num_results() != op2.num_results()
+            || (0..op1.num_results()).any(|idx| {
+                op1.get_type(idx) != op2.get_type(idx)
+                    || op1.result_attributes(idx) != op2.result_attributes(idx)
+            })
+            || !op1.successors().eq(op2.successors())
+            || !self.attributes(op1).eq(self.attributes(op2))
+        {
+            return false;
+        }
+        if !self.commutative {
+            return op1.operands().eq(op2.operands());
+        }
+        // Check that the operands of `op2` are a permutation of those of `op1`.
+        let mut opds2: Vec<_> = op2.operands().map(Some).collect();
+        op1.num_operands() == opds2.len()
+            && op1.operands().all(|opd1| {
+                opds2
+                    .iter_mut()
+                    .find(|opd2| **opd2 == Some(opd1))
+                    .map(|opd2| opd2.take())
+                    .is_some()
+            })
+    }
+}
@@ -1,32 +1,46 @@
 //! IR objects that can be parsed from their text representation.
 
-use std::collections::hash_map::Entry;
+use std::{
+    any::{Any, TypeId},
+    collections::hash_map::Entry,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use crate::{
+    attribute::resource_alias,
     basic_block::BasicBlock,
     builtin::{
         op_interfaces::{IsolatedFromAboveInterface, OneResultInterface},
-        ops::ForwardRefOp,
+        ops::{ForwardRefOp, ModuleOp},
     },
+    common_traits::RcSharable,
     context::{Context, Ptr},
+    dialect::DialectName,
     identifier::Identifier,
-    input_err,
-    irfmt::parsers::int_parser,
+    input_err, input_error, input_error_noloc,
+    irfmt::parsers::{int_parser, verbatim_payload_parser},
+    limits::{self, LimitErr},
     location::{self, Located, Location},
-    op::op_impls,
+    op::{Op, op_impls},
     operation::Operation,
+    printable::{self, Printable},
     result::{self, Result},
     value::Value,
 };
 use combine::{
     Parser, Positioned, StreamOnce,
     easy::{self, Errors, ParseError},
+    eof,
     error::{StdParseResult2, Tracked},
+    many,
+    parser::char::spaces,
     stream::{
         self, IteratorStream, buffered,
         position::{self, SourcePosition},
         state::Stream,
     },
+    token,
 };
 use rustc_hash::FxHashMap;
 use thiserror::Error;
@@ -35,10 +49,31 @@ use utf8_chars::BufReadCharsExt;
 /// State during parsing of any [Parsable] object.
 /// Every parser implemented using [Parsable] will be passed
 /// a mutable reference (wrapped with [StateStream]) to this state.
+///
+/// Parsers (for example, of a dialect) can attach their own data to the state,
+/// to be shared among them during a parse, as extensions. At most one extension
+/// of each (Rust) type can be attached.
+/// ```
+/// use pliron::{context::Context, location::Source, parsable::State};
+/// #[derive(Default)]
+/// struct NumTypesSeen(usize);
+/// let mut ctx = Context::new();
+/// let mut state = State::new(&mut ctx, Source::InMemory);
+/// state.extension_or_default::<NumTypesSeen>().0 += 1;
+/// state.extension_or_default::<NumTypesSeen>().0 += 1;
+/// assert_eq!(state.extension::<NumTypesSeen>().unwrap().0, 2);
+/// assert_eq!(state.remove_extension::<NumTypesSeen>().unwrap().0, 2);
+/// assert!(state.extension::<NumTypesSeen>().is_none());
+/// ```
 pub struct State<'a> {
     pub ctx: &'a mut Context,
     pub(crate) name_tracker: NameTracker,
     pub src: location::Source,
+    extensions: FxHashMap<TypeId, Box<dyn Any>>,
+    /// How deeply nested the parser currently is. See [parse_nested].
+    nesting_depth: usize,
+    /// The number of operations parsed so far.
+    pub(crate) num_ops: usize,
 }
 
 impl<'a> State<'a> {
@@ -48,8 +83,66 @@ impl<'a> State<'a> {
             ctx,
             name_tracker: NameTracker::default(),
             src,
+            extensions: FxHashMap::default(),
+            nesting_depth: 0,
+            num_ops: 0,
         }
     }
+
+    /// Get a reference to the extension of type `T`, if attached.
+    pub fn extension<T: Any>(&self) -> Option<&T> {
+        self.extensions
+            .get(&TypeId::of::<T>())
+            .map(|ext| ext.downcast_ref::<T>().unwrap())
+    }
+
+    /// Get a mutable reference to the extension of type `T`, if attached.
+    pub fn extension_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.extensions
+            .get_mut(&TypeId::of::<T>())
+            .map(|ext| ext.downcast_mut::<T>().unwrap())
+    }
+
+    /// Get a mutable reference to the extension of type `T`,
+    /// attaching a default one if it isn't already attached.
+    pub fn extension_or_default<T: Any + Default>(&mut self) -> &mut T {
+        self.extensions
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut::<T>()
+            .unwrap()
+    }
+
+    /// Attach `ext` as the extension of type `T`,
+    /// returning the previously attached one, if any.
+    pub fn insert_extension<T: Any>(&mut self, ext: T) -> Option<T> {
+        self.extensions
+            .insert(TypeId::of::<T>(), Box::new(ext))
+            .map(|prev| *prev.downcast::<T>().unwrap())
+    }
+
+    /// Detach and return the extension of type `T`, if attached.
+    pub fn remove_extension<T: Any>(&mut self) -> Option<T> {
+        self.extensions
+            .remove(&TypeId::of::<T>())
+            .map(|ext| *ext.downcast::<T>().unwrap())
+    }
+}
+
+/// Parse using `parse`, one level of nesting deeper, failing instead if that's
+/// deeper than [Limits::max_nesting_depth](crate::limits::Limits::max_nesting_depth).
+pub fn parse_nested<'a, T>(
+    state_stream: &mut StateStream<'a>,
+    parse: impl FnOnce(&mut StateStream<'a>) -> ParseResult<'a, T>,
+) -> ParseResult<'a, T> {
+    let limit = state_stream.state.ctx.limits.max_nesting_depth;
+    if limits::exceeds(state_stream.state.nesting_depth + 1, limit) {
+        input_err!(state_stream.loc(), LimitErr::NestingDepth(limit.unwrap()))?
+    }
+    state_stream.state.nesting_depth += 1;
+    let result = parse(state_stream);
+    state_stream.state.nesting_depth -= 1;
+    result
 }
 
 /// A wrapper around any [char] [Iterator] object.
@@ -90,7 +183,7 @@ impl Located for StateStream<'_> {
 
 pub type ParseResult<'a, T> = StdParseResult2<T, <StateStream<'a> as StreamOnce>::Error>;
 
-/// Any object that can be parsed from its [Printable](crate::printable::Printable) text.
+/// Any object that can be parsed from its [Printable] text.
 ///
 /// Implement [parse](Parsable::parse) and call [parser](Parsable::parser)
 /// to get a parser combinator that can be combined with any other parser
@@ -164,14 +257,19 @@ pub trait Parsable {
 }
 
 /// Build a [StateStream] from an iterator, for use with [Parsable].
+/// A leading byte order mark is skipped, and `\r\n` line endings are read as `\n`,
+/// so that parsed [SourcePosition]s are the same regardless of the platform's line
+/// endings. Use [location::byte_offset] to map them back to offsets in the input.
 pub fn state_stream_from_iterator<'a, T: Iterator<Item = char> + 'a>(
     input: T,
     state: State<'a>,
 ) -> StateStream<'a> {
+    let mut input = input.peekable();
+    input.next_if_eq(&location::BYTE_ORDER_MARK);
     StateStream {
         stream: buffered::Stream::new(
             easy::Stream::from(position::Stream::with_positioner(
-                IteratorStream::new(CharIterator(Box::new(input))),
+                IteratorStream::new(CharIterator(Box::new(location::normalize_newlines(input)))),
                 SourcePosition::default(),
             )),
             100,
@@ -194,6 +292,408 @@ pub fn state_stream_from_file<'a>(
     )
 }
 
+/// Input to [parse_source]: either the program text itself, or the file containing it.
+#[derive(Clone, Copy)]
+pub enum SourceInput<'a> {
+    Text(&'a str),
+    File(&'a Path),
+}
+
+impl<'a> From<&'a str> for SourceInput<'a> {
+    fn from(text: &'a str) -> Self {
+        SourceInput::Text(text)
+    }
+}
+
+impl<'a> From<&'a Path> for SourceInput<'a> {
+    fn from(path: &'a Path) -> Self {
+        SourceInput::File(path)
+    }
+}
+
+impl<'a> From<&'a PathBuf> for SourceInput<'a> {
+    fn from(path: &'a PathBuf) -> Self {
+        SourceInput::File(path)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParseSourceErr {
+    #[error("Unable to read {path}: {err}")]
+    Read { path: PathBuf, err: std::io::Error },
+    #[error("Source is not valid UTF-8 (at byte offset {offset})")]
+    NotUtf8 { offset: usize },
+    #[error("Source is UTF-16 encoded, but only UTF-8 is supported")]
+    Utf16,
+    #[error("{}", join_diagnostics(.0))]
+    Diagnostics(Vec<Box<dyn std::error::Error + Send + Sync>>),
+    #[error(
+        "Expected a {} at the top level, but found {0}",
+        ModuleOp::opid_static()
+    )]
+    NotAModule(String),
+}
+
+fn join_diagnostics(diagnostics: &[Box<dyn std::error::Error + Send + Sync>]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A token (or a description of one) in a [ParseDiagnostic].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseToken {
+    /// Literal text, such as `:` or `builtin.module`.
+    Literal(String),
+    /// A description, such as "whitespaces" or "end of input".
+    Description(String),
+}
+
+impl ParseToken {
+    /// The literal text, or the description, of this token.
+    pub fn text(&self) -> &str {
+        match self {
+            ParseToken::Literal(text) | ParseToken::Description(text) => text,
+        }
+    }
+}
+
+impl From<easy::Info<char, char>> for ParseToken {
+    fn from(info: easy::Info<char, char>) -> Self {
+        match info {
+            easy::Info::Token(token) => ParseToken::Literal(token.to_string()),
+            easy::Info::Range(range) => ParseToken::Literal(range.to_string()),
+            easy::Info::Owned(description) => ParseToken::Description(description),
+            easy::Info::Static(description) => ParseToken::Description(description.to_string()),
+        }
+    }
+}
+
+impl Display for ParseToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseToken::Literal(text) => write!(f, "`{}`", text),
+            ParseToken::Description(description) => write!(f, "{}", description),
+        }
+    }
+}
+
+/// A syntax error: what the parser found at a position, and what it expected there
+/// instead. Tools can use the expected alternatives to offer hints or completions.
+/// Retrieve it from an [Error](result::Error) returned by [parse_source] using [Self::of].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub struct ParseDiagnostic {
+    /// Where the parser failed.
+    pub pos: SourcePosition,
+    /// What was found at `pos`, if known.
+    pub unexpected: Option<ParseToken>,
+    /// The alternatives that the parser would have accepted at `pos`.
+    pub expected: Vec<ParseToken>,
+}
+
+impl ParseDiagnostic {
+    /// Get the syntax error diagnostic, if any, in the [ParseSourceErr::Diagnostics] of `err`.
+    pub fn of(err: &result::Error) -> Option<&ParseDiagnostic> {
+        let Some(ParseSourceErr::Diagnostics(diagnostics)) = err.err.downcast_ref() else {
+            return None;
+        };
+        diagnostics
+            .iter()
+            .find_map(|diagnostic| diagnostic.downcast_ref::<ParseDiagnostic>())
+    }
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(unexpected) = &self.unexpected {
+            write!(f, "Unexpected {}", unexpected)?;
+            if !self.expected.is_empty() {
+                writeln!(f)?;
+            }
+        }
+        if let Some((last, rest)) = self.expected.split_last() {
+            write!(f, "Expected ")?;
+            if !rest.is_empty() {
+                let rest: Vec<_> = rest.iter().map(ToString::to_string).collect();
+                write!(f, "{} or ", rest.join(", "))?;
+            }
+            write!(f, "{}", last)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collect the errors reported by the parser into a list of diagnostics.
+/// Errors raised by [Parsable] implementations are retained as they are
+/// (so that they may be downcast), and the unexpected and expected tokens
+/// are collected into a single [ParseDiagnostic], at the end of the list.
+pub(crate) fn parse_diagnostics(
+    errors: ParseError<StateStream<'_>>,
+) -> Vec<Box<dyn std::error::Error + Send + Sync>> {
+    let mut diagnostics: Vec<Box<dyn std::error::Error + Send + Sync>> = vec![];
+    let mut syntax = ParseDiagnostic {
+        pos: errors.position,
+        unexpected: None,
+        expected: vec![],
+    };
+    for error in errors.errors {
+        match error {
+            easy::Error::Other(err) => diagnostics.push(err),
+            easy::Error::Expected(info) => syntax.expected.push(info.into()),
+            easy::Error::Unexpected(info) if syntax.unexpected.is_none() => {
+                syntax.unexpected = Some(info.into())
+            }
+            easy::Error::Unexpected(info) => diagnostics.push(Box::new(result::StringError(
+                format!("Unexpected {}", ParseToken::from(info)),
+            ))),
+            easy::Error::Message(info) => {
+                diagnostics.push(Box::new(result::StringError(info.to_string())))
+            }
+        }
+    }
+    if syntax.unexpected.is_some() || !syntax.expected.is_empty() {
+        diagnostics.push(Box::new(syntax));
+    }
+    diagnostics
+}
+
+/// Parse a [ModuleOp] from `input`, which can be the program text (a `&str`) or
+/// the path to a file containing it (a [Path]). The text must contain only the module,
+/// preceded by any top-level constructs, `!dialect<payload>`, of dialects that handle
+/// them (see [TopLevelParserFn]).
+///
+/// All dialects that the program uses must already be registered in `ctx`. For
+/// an unregistered dialect, registered dialects with similar names are suggested (see
+/// [UnregisteredDialectErr](crate::dialect::UnregisteredDialectErr)). On a syntax error,
+/// the diagnostics reported by the parser are returned in [ParseSourceErr::Diagnostics],
+/// with what was expected available in a [ParseDiagnostic] (see [ParseDiagnostic::of]).
+///
+/// The text must be UTF-8, optionally beginning with a byte order mark, and may use
+/// `\r\n` line endings (see [state_stream_from_iterator]). With the `mmap` feature,
+/// files larger than [MMAP_THRESHOLD] bytes are memory mapped instead of being read in.
+/// ```
+/// use pliron::{builtin, context::Context, parse_source};
+/// let ctx = &mut Context::new();
+/// builtin::register(ctx);
+/// let module = parse_source(ctx, "builtin.module @m { ^entry(): }").unwrap();
+/// ```
+pub fn parse_source<'a>(ctx: &mut Context, input: impl Into<SourceInput<'a>>) -> Result<ModuleOp> {
+    let (bytes, src) = match input.into() {
+        SourceInput::Text(text) => (SourceBytes::Text(text), location::Source::InMemory),
+        SourceInput::File(path) => {
+            let bytes = SourceBytes::read(path).map_err(|err| {
+                input_error_noloc!(ParseSourceErr::Read {
+                    path: path.to_path_buf(),
+                    err,
+                })
+            })?;
+            let src = location::Source::new_from_file(ctx, path.to_path_buf());
+            (bytes, src)
+        }
+    };
+    let text = decode_source(&bytes, src)?;
+
+    let state_stream = state_stream_from_iterator(text.chars(), State::new(ctx, src));
+    let constructs =
+        many::<Vec<_>, _, _>(combine::parser(top_level_construct_parse).skip(spaces()));
+    let parsed = spaces()
+        .with(constructs)
+        .and(Operation::parser(()).skip(spaces()))
+        .skip(eof())
+        .parse(state_stream)
+        .map(|(parsed, _)| parsed);
+    let (constructs, op) = match parsed {
+        Ok(parsed) => parsed,
+        Err(errors) => {
+            let loc = Location::SrcPos {
+                src,
+                pos: errors.position,
+            };
+            return input_err!(loc, ParseSourceErr::Diagnostics(parse_diagnostics(errors)));
+        }
+    };
+
+    let Some(module) = Operation::op(op, ctx).downcast_ref::<ModuleOp>().copied() else {
+        let (opid, loc) = {
+            let op_ref = op.deref(ctx);
+            (op_ref.opid(), op_ref.loc())
+        };
+        Operation::erase(op, ctx);
+        return input_err!(loc, ParseSourceErr::NotAModule(opid.to_string()));
+    };
+    for construct in constructs {
+        (construct.parser)(ctx, module, &construct.payload).map_err(|mut err| {
+            if matches!(err.loc(), Location::Unknown) {
+                err.set_loc(construct.payload_loc);
+            }
+            err
+        })?;
+    }
+    Ok(module)
+}
+
+/// Handles a top-level construct of a dialect, `!dialect<payload>`, written before
+/// the module in a source. It's called, once the module is parsed, with the module
+/// and the payload (as is), and can record it, for example, as an attribute of the
+/// module, or in the [Context]. See [Dialect::set_top_level_parser].
+///
+/// [Dialect::set_top_level_parser]: crate::dialect::Dialect::set_top_level_parser
+pub type TopLevelParserFn = fn(ctx: &mut Context, module: ModuleOp, payload: &str) -> Result<()>;
+
+/// Gets the payloads of the top-level constructs of a dialect for `module`, to be
+/// printed back by [print_source]. See [Dialect::set_top_level_printer].
+///
+/// [Dialect::set_top_level_printer]: crate::dialect::Dialect::set_top_level_printer
+pub type TopLevelPrinterFn = fn(ctx: &Context, module: ModuleOp) -> Vec<String>;
+
+#[derive(Error, Debug)]
+#[error("Dialect {0} doesn't support top-level constructs")]
+pub struct TopLevelUnsupportedErr(pub String);
+
+/// A parsed top-level construct, to be handled once the module is parsed.
+struct TopLevelConstruct {
+    parser: TopLevelParserFn,
+    payload: String,
+    payload_loc: Location,
+}
+
+/// Parse a top-level construct, `!dialect<payload>`, of a dialect with a [TopLevelParserFn].
+fn top_level_construct_parse<'a>(
+    state_stream: &mut StateStream<'a>,
+) -> ParseResult<'a, TopLevelConstruct> {
+    let loc = state_stream.loc();
+    let dialect_name = token('!')
+        .with(DialectName::parser(()))
+        .skip(token('<'))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+    let payload_loc = state_stream.loc();
+    let payload = verbatim_payload_parser()
+        .skip(token('>'))
+        .parse_stream(state_stream)
+        .into_result()?
+        .0;
+    let parser = state_stream
+        .state
+        .ctx
+        .dialects
+        .get(&dialect_name)
+        .and_then(|dialect| dialect.top_level_parser);
+    let Some(parser) = parser else {
+        input_err!(loc, TopLevelUnsupportedErr(dialect_name.to_string()))?
+    };
+    Ok(TopLevelConstruct {
+        parser,
+        payload,
+        payload_loc,
+    })
+    .into_parse_result()
+}
+
+/// Print `module` such that [parse_source] reads it back: the top-level constructs
+/// of each dialect with a [TopLevelPrinterFn] (in the order of the dialects' names),
+/// a line each, followed by the module.
+pub fn print_source(ctx: &Context, module: ModuleOp) -> String {
+    print_source_with(ctx, module, &printable::State::default())
+}
+
+/// [print_source], printing the module with `state`. Attributes that `state`
+/// [elides](printable::State::set_attr_elision_limit) are printed exactly after
+/// the module, as resources that the elided attributes refer to, so that the
+/// module printed is still read back by [parse_source]. See [attribute](crate::attribute)
+/// module documentation.
+pub fn print_source_with(ctx: &Context, module: ModuleOp, state: &printable::State) -> String {
+    let mut printers: Vec<_> = ctx
+        .dialects
+        .values()
+        .filter_map(|dialect| Some((dialect.name(), dialect.top_level_printer?)))
+        .collect();
+    printers.sort_by_key(|(name, _)| name.to_string());
+    let mut source = String::new();
+    for (name, printer) in printers {
+        for payload in printer(ctx, module) {
+            source.push_str(&format!("!{name}<{payload}>\n"));
+        }
+    }
+    state.collect_resources();
+    source.push_str(&module.print(ctx, state).to_string());
+    let resources = state.take_resources();
+    let exact = state.replicate();
+    exact.set_attr_elision_limit(None);
+    for (idx, resource) in resources.iter().enumerate() {
+        source.push_str(&format!(
+            ";\n#{} = {}",
+            resource_alias(idx),
+            resource.print(ctx, &exact)
+        ));
+    }
+    source
+}
+
+/// With the `mmap` feature, [parse_source] memory maps files at least this large (in bytes).
+pub const MMAP_THRESHOLD: u64 = 16 << 20;
+
+/// The contents of a source being parsed.
+enum SourceBytes<'a> {
+    Text(&'a str),
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl SourceBytes<'_> {
+    fn read(path: &Path) -> std::io::Result<Self> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = std::fs::File::open(path)?;
+            if file.metadata()?.len() >= MMAP_THRESHOLD {
+                // SAFETY: The mapping is only read, and only while parsing. As documented
+                // on the `mmap` feature, the file must not be modified during that time.
+                return unsafe { memmap2::Mmap::map(&file) }.map(SourceBytes::Mapped);
+            }
+        }
+        std::fs::read(path).map(SourceBytes::Read)
+    }
+}
+
+impl std::ops::Deref for SourceBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            SourceBytes::Text(text) => text.as_bytes(),
+            SourceBytes::Read(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            SourceBytes::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+/// Decode `bytes`, read from `src`, as UTF-8 text.
+/// Invalid UTF-8 is reported at the position where it begins.
+fn decode_source(bytes: &[u8], src: location::Source) -> Result<&str> {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        let loc = Location::SrcPos {
+            src,
+            pos: SourcePosition::default(),
+        };
+        return input_err!(loc, ParseSourceErr::Utf16);
+    }
+    std::str::from_utf8(bytes).map_err(|err| {
+        let offset = err.valid_up_to();
+        // Everything before the error is valid, so this can't fail.
+        let valid = std::str::from_utf8(&bytes[..offset]).unwrap_or_default();
+        let loc = Location::SrcPos {
+            src,
+            pos: location::end_position(valid),
+        };
+        input_error!(loc, ParseSourceErr::NotUtf8 { offset })
+    })
+}
+
 /// A storable parser function. This allows storing a function pointer
 /// to a parser in a table, allowing for invoking it indirectly.
 // (if we can get rid of the dummy parameter, we wouldn't need [Parsable::parser_fn]).
@@ -273,6 +773,11 @@ pub enum ParserNameTrackerError {
 }
 
 impl NameTracker {
+    /// Are we outside of any region, i.e., parsing a top-level operation?
+    pub(crate) fn is_top_level(&self) -> bool {
+        self.block_label_scope.is_empty()
+    }
+
     /// An SSA use is seen. Get its [definition value][Value]
     /// or return a [forward reference][ForwardRefOp] that will
     /// be updated when the actual definition is seen.
@@ -281,7 +786,7 @@ impl NameTracker {
             .ssa_name_scope
             .last_mut()
             .expect("NameTracker doesn't have an active scope.");
-        match scope.entry(id.clone()) {
+        match scope.entry(*id) {
             Entry::Occupied(occ) => *occ.get(),
             Entry::Vacant(vac) => {
                 // Insert a forward reference.
@@ -305,7 +810,7 @@ impl NameTracker {
             .last_mut()
             .expect("NameTracker doesn't have an active scope.");
 
-        match scope.entry(id.0.clone()) {
+        match scope.entry(id.0) {
             Entry::Occupied(mut occ) => match occ.get_mut() {
                 Value::OpResult { op, res_idx: _ } => {
                     let fref_opt = Operation::op(*op, ctx)
@@ -320,7 +825,7 @@ impl NameTracker {
                         // There's another def and it isn't a forward ref.
                         input_err!(
                             id.1.clone(),
-                            ParserNameTrackerError::MultipleDefinitions(id.0.clone())
+                            ParserNameTrackerError::MultipleDefinitions(id.0)
                         )?
                     }
                 }
@@ -328,7 +833,7 @@ impl NameTracker {
                     // There's another def and it isn't a forward ref.
                     input_err!(
                         id.1.clone(),
-                        ParserNameTrackerError::MultipleDefinitions(id.0.clone())
+                        ParserNameTrackerError::MultipleDefinitions(id.0)
                     )?
                 }
             },
@@ -347,11 +852,11 @@ impl NameTracker {
             .block_label_scope
             .last_mut()
             .expect("NameTracker doesn't have an active scope.");
-        match scope.entry(id.clone()) {
+        match scope.entry(*id) {
             Entry::Occupied(occ) => occ.get().label(),
             Entry::Vacant(vac) => {
                 // Insert a forward reference.
-                let block_forward = BasicBlock::new(ctx, Some(id.clone()), vec![]);
+                let block_forward = BasicBlock::new(ctx, Some(*id), vec![]);
                 vac.insert(LabelRef::ForwardRef(block_forward));
                 block_forward
             }
@@ -370,7 +875,7 @@ impl NameTracker {
             .block_label_scope
             .last_mut()
             .expect("NameTracker doesn't have an active scope.");
-        match scope.entry(id.0.clone()) {
+        match scope.entry(id.0) {
             Entry::Occupied(mut occ) => match occ.get_mut() {
                 LabelRef::ForwardRef(fref) => {
                     fref.retarget_some_preds_to(ctx, |_, _| true, block);
@@ -379,7 +884,7 @@ impl NameTracker {
                 }
                 LabelRef::Defined(_) => input_err!(
                     id.1.clone(),
-                    ParserNameTrackerError::MultipleDefinitions(id.0.clone())
+                    ParserNameTrackerError::MultipleDefinitions(id.0)
                 )?,
             },
             Entry::Vacant(vac) => {
@@ -424,7 +929,7 @@ impl NameTracker {
             for (id, op) in ssa_scope {
                 if matches!(op, Value::OpResult { op, .. } if Operation::op(op, ctx).is::<ForwardRefOp>())
                 {
-                    input_err!(loc.clone(), UnresolvedReference(id.clone()))?
+                    input_err!(loc.clone(), UnresolvedReference(id))?
                 }
             }
         }
@@ -437,7 +942,7 @@ impl NameTracker {
         // Check if there are any unresolved forward label references.
         for (id, op) in label_scope {
             if matches!(op, LabelRef::ForwardRef(_)) {
-                input_err!(loc.clone(), UnresolvedReference(id.clone()))?
+                input_err!(loc.clone(), UnresolvedReference(id))?
             }
         }
 
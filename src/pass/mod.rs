@@ -0,0 +1,428 @@
+//! Passes, and a [PassManager] to schedule and run them.
+//!
+//! A [PassManager] is anchored on a kind of [Op] (or on any [Operation]).
+//! When run on an anchor operation, it runs its passes (in the order they were added)
+//! on that operation, and its nested pass managers on the operations immediately
+//! nested in the anchor's regions. This mirrors MLIR's
+//! [pass manager](https://mlir.llvm.org/docs/PassManagement/#oppassmanager).
+//!
+//! A [PassManager] may also have a [filter](PassManager::with_filter), and then it
+//! skips the anchors that the filter rejects. This allows, for example, optimizing
+//! (or debugging) just a single function of a large module.
+//! See [symbol_name_matches], [has_attribute] and [is_op_kind] for commonly used filters.
+//!
+//! [PassInstrumentation]s added to a [PassManager] are notified before and after
+//! each pass run by it (and by its nested pass managers). See [record] for an
+//! instrumentation recording the IR after every pass.
+//!
+//! Passes may report [PassStatistics], such as how many operations they changed,
+//! or how long parts of them took. These are accumulated over all the runs of
+//! a pass, and gathered for a whole pipeline by [PassManager::statistics].
+//!
+//! A [PassManager] can verify the IR after each pass, catching a broken pass right
+//! when it runs (see [VerifyMode]). Verifying a large module after every pass is
+//! expensive though, so [VerifyMode::Changed] verifies just what the pass modified,
+//! as recorded by a [ChangeTracker].
+//!
+//! Passes share the [Analysis](crate::analysis::manager::Analysis) results
+//! cached in an [AnalysisManager], which lives for a single [run](PassManager::run).
+
+pub mod record;
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Display, rc::Rc, time::Duration};
+
+use regex::Regex;
+
+use crate::{
+    analysis::manager::AnalysisManager,
+    builtin::op_interfaces::SymbolOpInterface,
+    common_traits::Verify,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    linked_list::ContainsLinkedList,
+    listener::{ChangeTracker, RewriteListener},
+    op::{Op, OpId, op_cast},
+    operation::Operation,
+    result::{Error, Result},
+};
+
+/// A transformation or analysis of the IR, run on an [Operation].
+pub trait Pass {
+    /// A name identifying this pass.
+    fn name(&self) -> &str;
+
+    /// Run this pass on `op`.
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()>;
+
+    /// Run this pass on `op`, with the [AnalysisManager] of the [PassManager] running it.
+    /// Analyses that this pass doesn't [preserve](AnalysisManager::preserve) are
+    /// invalidated after it runs. Defaults to [run](Self::run), preserving nothing.
+    fn run_with_analyses(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        _analyses: &mut AnalysisManager,
+    ) -> Result<()> {
+        self.run(ctx, op)
+    }
+
+    /// Statistics of this pass, accumulated over all its runs so far.
+    fn statistics(&self) -> PassStatistics {
+        PassStatistics::default()
+    }
+}
+
+/// Named counters and timers reported by a [Pass]. See [module](self) documentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PassStatistics {
+    counters: BTreeMap<String, u64>,
+    timers: BTreeMap<String, Duration>,
+}
+
+impl PassStatistics {
+    /// Add `count` to the counter `name`.
+    pub fn add(&mut self, name: &str, count: u64) {
+        *self.counters.entry(name.to_string()).or_default() += count;
+    }
+
+    /// Add `time` to the timer `name`.
+    pub fn add_time(&mut self, name: &str, time: Duration) {
+        *self.timers.entry(name.to_string()).or_default() += time;
+    }
+
+    /// The counter `name`, zero if it was never added to.
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters.get(name).copied().unwrap_or_default()
+    }
+
+    /// The timer `name`, zero if it was never added to.
+    pub fn timer(&self, name: &str) -> Duration {
+        self.timers.get(name).copied().unwrap_or_default()
+    }
+
+    /// All counters, sorted by name.
+    pub fn counters(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counters
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+    }
+
+    /// All timers, sorted by name.
+    pub fn timers(&self) -> impl Iterator<Item = (&str, Duration)> {
+        self.timers
+            .iter()
+            .map(|(name, time)| (name.as_str(), *time))
+    }
+
+    /// Are there no counters or timers?
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.timers.is_empty()
+    }
+
+    /// Add the counters and timers of `other` to these.
+    pub fn merge(&mut self, other: &PassStatistics) {
+        for (name, count) in other.counters() {
+            self.add(name, count);
+        }
+        for (name, time) in other.timers() {
+            self.add_time(name, time);
+        }
+    }
+}
+
+impl Display for PassStatistics {
+    /// One `<name>: <value>` line per counter, and then per timer.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, count) in self.counters() {
+            writeln!(f, "{}: {}", name, count)?;
+        }
+        for (name, time) in self.timers() {
+            writeln!(f, "{}: {:?}", name, time)?;
+        }
+        Ok(())
+    }
+}
+
+/// Predicate deciding if a [PassManager] must process an anchor [Operation].
+pub type PassFilter = Box<dyn Fn(&Context, Ptr<Operation>) -> bool>;
+
+/// Hooks called around each [Pass] run by a [PassManager].
+/// An error returned by a hook stops the pipeline.
+pub trait PassInstrumentation {
+    /// Called before `pass` is run on `op`.
+    fn before_pass(&mut self, _ctx: &Context, _pass: &dyn Pass, _op: Ptr<Operation>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after `pass` successfully ran on `op`.
+    fn after_pass(&mut self, _ctx: &Context, _pass: &dyn Pass, _op: Ptr<Operation>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after `pass` failed, with `err`, on `op`.
+    /// The pipeline stops with `err` after this.
+    fn after_pass_failed(
+        &mut self,
+        _ctx: &Context,
+        _pass: &dyn Pass,
+        _op: Ptr<Operation>,
+        _err: &Error,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// What a [PassManager] verifies after each [Pass] it runs.
+/// A verification failure is reported as a failure of the pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// Don't verify.
+    #[default]
+    None,
+    /// Verify the operation that the pass ran on, and everything nested in it.
+    Full,
+    /// Verify only what the pass modified (see [ChangeTracker::verify_changed]).
+    /// Invalid IR that the pass didn't touch goes unnoticed.
+    Changed,
+}
+
+enum PassEntry {
+    Pass(Box<dyn Pass>),
+    Nested(PassManager),
+}
+
+/// Runs a pipeline of [Pass]es on operations it is anchored on.
+/// See the [module](self) documentation.
+#[derive(Default)]
+pub struct PassManager {
+    /// Operations that this pass manager runs on. [None] for any operation.
+    anchor: Option<OpId>,
+    /// Anchors not accepted by this filter are skipped.
+    filter: Option<PassFilter>,
+    /// The pipeline.
+    entries: Vec<PassEntry>,
+    /// Notified around the passes of this (and nested) pass managers.
+    instrumentations: Vec<Box<dyn PassInstrumentation>>,
+    /// [None] to inherit the mode of the enclosing pass manager.
+    verify_mode: Option<VerifyMode>,
+}
+
+impl PassManager {
+    /// Create a [PassManager] that runs on any [Operation].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a [PassManager] that runs on [Op]s of type `T`.
+    pub fn new_anchored<T: Op>() -> Self {
+        PassManager {
+            anchor: Some(T::opid_static()),
+            ..Self::default()
+        }
+    }
+
+    /// Skip the anchor operations that `filter` returns `false` for.
+    pub fn with_filter(
+        mut self,
+        filter: impl Fn(&Context, Ptr<Operation>) -> bool + 'static,
+    ) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Verify the IR, as per `mode`, after each pass run by this pass manager,
+    /// or by pass managers nested in it that don't set their own mode.
+    /// Defaults to [VerifyMode::None].
+    pub fn with_verify_mode(mut self, mode: VerifyMode) -> Self {
+        self.verify_mode = Some(mode);
+        self
+    }
+
+    /// The kind of operations that this pass manager runs on, if restricted.
+    pub fn anchor(&self) -> Option<&OpId> {
+        self.anchor.as_ref()
+    }
+
+    /// Append `pass` to the pipeline.
+    pub fn add_pass(&mut self, pass: impl Pass + 'static) -> &mut Self {
+        self.entries.push(PassEntry::Pass(Box::new(pass)));
+        self
+    }
+
+    /// Append `pm` to the pipeline, to be run on operations
+    /// immediately nested in the regions of the anchor.
+    pub fn add_nested(&mut self, pm: PassManager) -> &mut PassManager {
+        self.entries.push(PassEntry::Nested(pm));
+        let Some(PassEntry::Nested(pm)) = self.entries.last_mut() else {
+            unreachable!()
+        };
+        pm
+    }
+
+    /// Append a new [PassManager], anchored on [Op]s of type `T`,
+    /// to the pipeline and return it. See [add_nested](Self::add_nested).
+    pub fn nest<T: Op>(&mut self) -> &mut PassManager {
+        self.add_nested(Self::new_anchored::<T>())
+    }
+
+    /// Add `instrumentation`, to be notified around every pass run
+    /// by this pass manager, or by pass managers nested in it.
+    pub fn add_instrumentation(
+        &mut self,
+        instrumentation: impl PassInstrumentation + 'static,
+    ) -> &mut Self {
+        self.instrumentations.push(Box::new(instrumentation));
+        self
+    }
+
+    /// The (non-empty) [statistics](Pass::statistics) of the passes in this pipeline,
+    /// including those of nested pass managers, in pipeline order, along with the
+    /// [names](Pass::name) of the passes.
+    pub fn statistics(&self) -> Vec<(String, PassStatistics)> {
+        let mut statistics = vec![];
+        for entry in &self.entries {
+            match entry {
+                PassEntry::Pass(pass) => {
+                    let pass_statistics = pass.statistics();
+                    if !pass_statistics.is_empty() {
+                        statistics.push((pass.name().to_string(), pass_statistics));
+                    }
+                }
+                PassEntry::Nested(pm) => statistics.extend(pm.statistics()),
+            }
+        }
+        statistics
+    }
+
+    /// Does this pass manager process `op`?
+    pub fn accepts(&self, ctx: &Context, op: Ptr<Operation>) -> bool {
+        self.anchor
+            .as_ref()
+            .is_none_or(|anchor| *anchor == op.deref(ctx).opid())
+            && self.filter.as_ref().is_none_or(|filter| filter(ctx, op))
+    }
+
+    /// Run the pipeline on `op`, if it is [accepted](Self::accepts).
+    /// Stops at the first pass that fails.
+    pub fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        let mut analyses = AnalysisManager::new();
+        self.run_instrumented(ctx, op, &mut vec![], VerifyMode::None, &mut analyses)
+    }
+
+    /// Run the pipeline on `op`, notifying `instrumentations` (of the
+    /// enclosing pass managers) in addition to this pass manager's own.
+    /// `verify_mode` is that of the enclosing pass manager.
+    fn run_instrumented(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        instrumentations: &mut Vec<Box<dyn PassInstrumentation>>,
+        verify_mode: VerifyMode,
+        analyses: &mut AnalysisManager,
+    ) -> Result<()> {
+        if !self.accepts(ctx, op) {
+            return Ok(());
+        }
+        let verify_mode = self.verify_mode.unwrap_or(verify_mode);
+        let num_outer = instrumentations.len();
+        instrumentations.append(&mut self.instrumentations);
+        let res = self.run_entries(ctx, op, instrumentations, verify_mode, analyses);
+        self.instrumentations = instrumentations.split_off(num_outer);
+        res
+    }
+
+    /// Run `pass` on `op`, and then verify as per `verify_mode`.
+    fn run_pass_verified(
+        ctx: &mut Context,
+        pass: &mut dyn Pass,
+        op: Ptr<Operation>,
+        verify_mode: VerifyMode,
+        analyses: &mut AnalysisManager,
+    ) -> Result<()> {
+        match verify_mode {
+            VerifyMode::None => pass.run_with_analyses(ctx, op, analyses),
+            VerifyMode::Full => {
+                pass.run_with_analyses(ctx, op, analyses)?;
+                op.deref(ctx).verify(ctx)
+            }
+            VerifyMode::Changed => {
+                let tracker = Rc::new(RefCell::new(ChangeTracker::default()));
+                let listener: Rc<RefCell<dyn RewriteListener>> = tracker.clone();
+                ctx.add_listener(listener.clone());
+                let res = pass.run_with_analyses(ctx, op, analyses);
+                ctx.remove_listener(&listener);
+                res?;
+                tracker.borrow().verify_changed(ctx, op).map(|_| ())
+            }
+        }
+    }
+
+    fn run_entries(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        instrumentations: &mut Vec<Box<dyn PassInstrumentation>>,
+        verify_mode: VerifyMode,
+        analyses: &mut AnalysisManager,
+    ) -> Result<()> {
+        for entry in &mut self.entries {
+            match entry {
+                PassEntry::Pass(pass) => {
+                    for instrumentation in instrumentations.iter_mut() {
+                        instrumentation.before_pass(ctx, &**pass, op)?;
+                    }
+                    let res = Self::run_pass_verified(ctx, &mut **pass, op, verify_mode, analyses);
+                    analyses.invalidate_unpreserved();
+                    if let Err(err) = res {
+                        for instrumentation in instrumentations.iter_mut().rev() {
+                            instrumentation.after_pass_failed(ctx, &**pass, op, &err)?;
+                        }
+                        return Err(err);
+                    }
+                    for instrumentation in instrumentations.iter_mut().rev() {
+                        instrumentation.after_pass(ctx, &**pass, op)?;
+                    }
+                }
+                PassEntry::Nested(pm) => {
+                    // Passes may modify the IR, so collect the nested operations upfront.
+                    let nested_ops: Vec<_> = op
+                        .deref(ctx)
+                        .regions()
+                        .flat_map(|region| region.deref(ctx).iter(ctx).collect::<Vec<_>>())
+                        .flat_map(|block| block.deref(ctx).iter(ctx).collect::<Vec<_>>())
+                        .collect();
+                    for nested_op in nested_ops {
+                        pm.run_instrumented(
+                            ctx,
+                            nested_op,
+                            instrumentations,
+                            verify_mode,
+                            analyses,
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [PassManager] filter accepting [SymbolOpInterface] operations
+/// whose symbol name matches `regex`.
+pub fn symbol_name_matches(regex: Regex) -> impl Fn(&Context, Ptr<Operation>) -> bool {
+    move |ctx, op| {
+        let op = Operation::op(op, ctx);
+        op_cast::<dyn SymbolOpInterface>(&*op)
+            .is_some_and(|sym_op| regex.is_match(&sym_op.symbol_name(ctx)))
+    }
+}
+
+/// A [PassManager] filter accepting operations that have an attribute for `key`.
+pub fn has_attribute(key: Identifier) -> impl Fn(&Context, Ptr<Operation>) -> bool {
+    move |ctx, op| op.deref(ctx).attributes.0.contains_key(&key)
+}
+
+/// A [PassManager] filter accepting operations of kind `op_id`.
+pub fn is_op_kind(op_id: OpId) -> impl Fn(&Context, Ptr<Operation>) -> bool {
+    move |ctx, op| op.deref(ctx).opid() == op_id
+}
@@ -0,0 +1,269 @@
+//! Record the IR after every pass, to debug a pipeline.
+//!
+//! An [IrRecorder] is a [PassInstrumentation] that writes the IR, in the textual
+//! format, to a directory: once before the first pass (as `000-input.pliron`), and
+//! then after every pass (as `<n>-<pass name>.pliron`, where `n` is a sequence number).
+//! The whole top-level operation (containing the operation that the pass ran on) is recorded.
+//! The IR is recorded even after a pass fails, since that's often what needs debugging.
+//!
+//! The recordings are listed, in order, in a [manifest](MANIFEST_FILE),
+//! with one line per recording, containing tab separated:
+//!   - the sequence number,
+//!   - the name of the pass after which the IR was recorded (empty for the input),
+//!   - `ok` or `failed`, the status of that pass,
+//!   - the file name, in the directory, of the recorded IR.
+//!
+//! A [Recording] loads the manifest back, to resume a pipeline from any recorded point
+//! (see [Recording::parse]), or to [bisect](Recording::bisect) which pass broke the IR.
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc, arg_error_noloc,
+    builtin::ops::ModuleOp,
+    context::{Context, Ptr},
+    linked_list::LinkedList,
+    op::Op,
+    operation::Operation,
+    parsable::parse_source,
+    printable::Printable,
+    result::{Error, Result},
+};
+
+use super::{Pass, PassInstrumentation};
+
+/// Name of the manifest file in a recording directory.
+pub const MANIFEST_FILE: &str = "manifest.tsv";
+
+#[derive(Error, Debug)]
+pub enum IrRecordErr {
+    #[error("Error writing IR recording {path}: {err}")]
+    Write { path: PathBuf, err: std::io::Error },
+    #[error("Error reading IR recording manifest {path}: {err}")]
+    ReadManifest { path: PathBuf, err: std::io::Error },
+    #[error("Malformed line {line} in IR recording manifest {path}")]
+    MalformedManifest { path: PathBuf, line: usize },
+    #[error("No IR recorded with sequence number {0}")]
+    UnknownSeq(usize),
+}
+
+/// An entry in the manifest of a recording directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedIr {
+    /// Position of this recording in the sequence of recordings.
+    pub seq: usize,
+    /// The pass after which the IR was recorded. [None] for the input IR.
+    pub pass: Option<String>,
+    /// Did the pass fail?
+    pub failed: bool,
+    /// Path to the recorded IR.
+    pub path: PathBuf,
+}
+
+impl RecordedIr {
+    /// The manifest line describing this recording.
+    fn manifest_line(&self) -> String {
+        let file_name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        format!(
+            "{}\t{}\t{}\t{}\n",
+            self.seq,
+            self.pass.as_deref().unwrap_or_default(),
+            if self.failed { "failed" } else { "ok" },
+            file_name
+        )
+    }
+}
+
+/// A [PassInstrumentation] recording the IR after every pass.
+/// See [module](self) documentation.
+pub struct IrRecorder {
+    dir: PathBuf,
+    recorded: Vec<RecordedIr>,
+}
+
+impl IrRecorder {
+    /// Record IR into the directory `dir`, creating it if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|err| {
+            arg_error_noloc!(IrRecordErr::Write {
+                path: dir.clone(),
+                err
+            })
+        })?;
+        Ok(IrRecorder {
+            dir,
+            recorded: vec![],
+        })
+    }
+
+    /// The IR recorded so far.
+    pub fn recorded(&self) -> &[RecordedIr] {
+        &self.recorded
+    }
+
+    /// Record the top-level operation containing `op`, after `pass`.
+    fn record(
+        &mut self,
+        ctx: &Context,
+        op: Ptr<Operation>,
+        pass: Option<&str>,
+        failed: bool,
+    ) -> Result<()> {
+        let seq = self.recorded.len();
+        let name: String = pass
+            .unwrap_or("input")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let recorded = RecordedIr {
+            seq,
+            pass: pass.map(str::to_string),
+            failed,
+            path: self.dir.join(format!("{:03}-{}.pliron", seq, name)),
+        };
+        write_file(&recorded.path, &root_op(ctx, op).disp(ctx).to_string())?;
+        self.recorded.push(recorded);
+
+        let manifest: String = self
+            .recorded
+            .iter()
+            .map(RecordedIr::manifest_line)
+            .collect();
+        write_file(&self.dir.join(MANIFEST_FILE), &manifest)
+    }
+}
+
+impl PassInstrumentation for IrRecorder {
+    fn before_pass(&mut self, ctx: &Context, _pass: &dyn Pass, op: Ptr<Operation>) -> Result<()> {
+        if self.recorded.is_empty() {
+            self.record(ctx, op, None, false)?;
+        }
+        Ok(())
+    }
+
+    fn after_pass(&mut self, ctx: &Context, pass: &dyn Pass, op: Ptr<Operation>) -> Result<()> {
+        self.record(ctx, op, Some(pass.name()), false)
+    }
+
+    fn after_pass_failed(
+        &mut self,
+        ctx: &Context,
+        pass: &dyn Pass,
+        op: Ptr<Operation>,
+        _err: &Error,
+    ) -> Result<()> {
+        self.record(ctx, op, Some(pass.name()), true)
+    }
+}
+
+/// Write `contents` to the file at `path`.
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).map_err(|err| {
+        arg_error_noloc!(IrRecordErr::Write {
+            path: path.to_path_buf(),
+            err
+        })
+    })
+}
+
+/// Get the outermost [Operation] containing `op` (or `op` itself).
+fn root_op(ctx: &Context, mut op: Ptr<Operation>) -> Ptr<Operation> {
+    while let Some(block) = op.deref(ctx).container() {
+        let Some(region) = block.deref(ctx).container() else {
+            break;
+        };
+        op = region.deref(ctx).parent_op();
+    }
+    op
+}
+
+/// IR recorded by an [IrRecorder], loaded from its directory.
+pub struct Recording {
+    recorded: Vec<RecordedIr>,
+}
+
+impl Recording {
+    /// Load the manifest of the recording directory `dir`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let path = dir.join(MANIFEST_FILE);
+        let manifest = std::fs::read_to_string(&path).map_err(|err| {
+            arg_error_noloc!(IrRecordErr::ReadManifest {
+                path: path.clone(),
+                err
+            })
+        })?;
+        let recorded = manifest
+            .lines()
+            .enumerate()
+            .map(|(line_idx, line)| {
+                let malformed = || {
+                    arg_error_noloc!(IrRecordErr::MalformedManifest {
+                        path: path.clone(),
+                        line: line_idx + 1
+                    })
+                };
+                let fields: Vec<_> = line.split('\t').collect();
+                let [seq, pass, status, file_name] = fields[..] else {
+                    return Err(malformed());
+                };
+                Ok(RecordedIr {
+                    seq: seq.parse().map_err(|_| malformed())?,
+                    pass: (!pass.is_empty()).then(|| pass.to_string()),
+                    failed: match status {
+                        "ok" => false,
+                        "failed" => true,
+                        _ => return Err(malformed()),
+                    },
+                    path: dir.join(file_name),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Recording { recorded })
+    }
+
+    /// The recordings, in order.
+    pub fn recorded(&self) -> &[RecordedIr] {
+        &self.recorded
+    }
+
+    /// Parse the IR recorded with sequence number `seq`.
+    /// The recorded top-level operation must be a [ModuleOp].
+    pub fn parse(&self, ctx: &mut Context, seq: usize) -> Result<ModuleOp> {
+        let Some(recorded) = self.recorded.iter().find(|recorded| recorded.seq == seq) else {
+            return arg_err_noloc!(IrRecordErr::UnknownSeq(seq));
+        };
+        parse_source(ctx, &recorded.path)
+    }
+
+    /// Find the first recording whose IR isn't `good`. Assuming that the IR, once
+    /// bad, remains bad, this is the recording after the pass that broke the IR.
+    /// IR that doesn't parse is bad. Each parsed module is erased after checking it.
+    pub fn bisect(
+        &self,
+        ctx: &mut Context,
+        mut good: impl FnMut(&mut Context, ModuleOp) -> bool,
+    ) -> Option<&RecordedIr> {
+        let mut is_good = |ctx: &mut Context, recorded: &RecordedIr| {
+            parse_source(ctx, &recorded.path).is_ok_and(|module| {
+                let res = good(ctx, module);
+                Operation::erase(module.operation(), ctx);
+                res
+            })
+        };
+        // Invariant: all recordings before `lo` are good, and those from `hi` are bad.
+        let (mut lo, mut hi) = (0, self.recorded.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if is_good(ctx, &self.recorded[mid]) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        self.recorded.get(lo)
+    }
+}
@@ -0,0 +1,455 @@
+//! Rewriting the IR with patterns.
+//!
+//! A [RewritePattern] matches an [Operation], and rewrites it (and possibly the IR
+//! around it): replacing it, erasing it, creating new operations or modifying
+//! existing ones. All changes must be made through the [PatternRewriter] passed
+//! to the pattern, which notifies the driver of them, so that the operations
+//! affected are visited again.
+//!
+//! [apply_patterns_greedily] applies a [RewritePatternSet] to the operations in
+//! a region, until none of the patterns apply anymore (or a limit is reached, see
+//! [GreedyRewriteConfig]). The patterns applicable to an operation are tried in
+//! decreasing order of their [benefit](RewritePattern::benefit), and the first
+//! one that succeeds rewrites it. [GreedyRewritePass] is a [Pass] doing this.
+//!
+//! To debug pattern sets whose patterns fight each other, or take too long,
+//! patterns can be [disabled](RewritePatternSet::set_enabled), or have their
+//! [benefit overridden](RewritePatternSet::set_benefit), by name at runtime.
+//! A [RewritePatternSet] records, for each pattern, how many times it was tried
+//! and applied, and the time it took (see [PatternStatistics]).
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    context::{Context, Ptr},
+    graph::walkers::{collect_ops, collect_region_ops},
+    linked_list::LinkedList,
+    op::OpId,
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    region::Region,
+    result::Result,
+    value::Value,
+};
+
+/// A rewrite of operations matching some pattern. See [module](self) documentation.
+pub trait RewritePattern {
+    /// A name identifying this pattern.
+    fn name(&self) -> &str;
+
+    /// Patterns with higher benefit are tried first.
+    fn benefit(&self) -> u32 {
+        1
+    }
+
+    /// The kind of operations that this pattern matches, if restricted.
+    /// Other operations aren't tried with this pattern.
+    fn root_opid(&self) -> Option<OpId> {
+        None
+    }
+
+    /// If `op` matches, rewrite it using `rewriter` and return `true`.
+    /// Must return `false` (and not change the IR) if it doesn't match.
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool>;
+}
+
+/// Makes the changes of a [RewritePattern],
+/// tracking the operations to be visited (again) by the driver.
+#[derive(Default)]
+pub struct PatternRewriter {
+    worklist: VecDeque<Ptr<Operation>>,
+    in_worklist: FxHashSet<Ptr<Operation>>,
+}
+
+impl PatternRewriter {
+    /// Add `op` to the worklist, unless it's already there.
+    fn push(&mut self, op: Ptr<Operation>) {
+        if self.in_worklist.insert(op) {
+            self.worklist.push_back(op);
+        }
+    }
+
+    /// Take the next operation off the worklist.
+    fn pop(&mut self) -> Option<Ptr<Operation>> {
+        while let Some(op) = self.worklist.pop_front() {
+            if self.in_worklist.remove(&op) {
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    /// Add the operations using `value` to the worklist.
+    fn push_users(&mut self, ctx: &Context, value: &Value) {
+        for r#use in value.uses(ctx) {
+            self.push(r#use.op);
+        }
+    }
+
+    /// `op` (and the operations nested in it) were inserted into the IR.
+    /// Called by the methods inserting operations, and must be called
+    /// when a pattern inserts operations by other means.
+    pub fn notify_op_inserted(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        for op in collect_ops(ctx, op) {
+            self.push(op);
+        }
+    }
+
+    /// `op` was modified in place. Called by [modify_op_in_place](Self::modify_op_in_place),
+    /// and must be called when a pattern modifies an operation by other means.
+    pub fn notify_op_modified(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        self.push(op);
+        for result in op.deref(ctx).results() {
+            self.push_users(ctx, &result);
+        }
+    }
+
+    /// Insert (the unlinked) `op` before `mark`.
+    pub fn insert_before(&mut self, ctx: &Context, op: Ptr<Operation>, mark: Ptr<Operation>) {
+        op.insert_before(ctx, mark);
+        self.notify_op_inserted(ctx, op);
+    }
+
+    /// Insert (the unlinked) `op` after `mark`.
+    pub fn insert_after(&mut self, ctx: &Context, op: Ptr<Operation>, mark: Ptr<Operation>) {
+        op.insert_after(ctx, mark);
+        self.notify_op_inserted(ctx, op);
+    }
+
+    /// Modify `op` in place, with `modify`.
+    pub fn modify_op_in_place<R>(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        modify: impl FnOnce(&mut Context) -> R,
+    ) -> R {
+        let res = modify(ctx);
+        self.notify_op_modified(ctx, op);
+        res
+    }
+
+    /// Replace all uses of `from` with `to`.
+    pub fn replace_all_uses_with(&mut self, ctx: &Context, from: &Value, to: &Value) {
+        self.push_users(ctx, from);
+        from.replace_some_uses_with(ctx, |_, _| true, to);
+    }
+
+    /// Replace the results of `op` with `values`, and erase `op`.
+    pub fn replace_op_with_values(
+        &mut self,
+        ctx: &mut Context,
+        op: Ptr<Operation>,
+        values: &[Value],
+    ) {
+        let results: Vec<_> = op.deref(ctx).results().collect();
+        assert!(
+            results.len() == values.len(),
+            "Operation replaced with a different number of values than its results"
+        );
+        for (result, value) in results.iter().zip(values) {
+            self.replace_all_uses_with(ctx, result, value);
+        }
+        self.erase_op(ctx, op);
+    }
+
+    /// Replace the results of `op` with those of `new_op`, and erase `op`.
+    /// `new_op` is inserted before `op`, unless it's already linked.
+    pub fn replace_op(&mut self, ctx: &mut Context, op: Ptr<Operation>, new_op: Ptr<Operation>) {
+        if !new_op.is_linked(ctx) {
+            self.insert_before(ctx, new_op, op);
+        }
+        let values: Vec<_> = new_op.deref(ctx).results().collect();
+        self.replace_op_with_values(ctx, op, &values);
+    }
+
+    /// Erase `op`, whose results must not have any uses.
+    /// The operations defining its operands are visited again,
+    /// since they may have become dead.
+    pub fn erase_op(&mut self, ctx: &mut Context, op: Ptr<Operation>) {
+        for nested_op in collect_ops(ctx, op) {
+            self.in_worklist.remove(&nested_op);
+        }
+        let operands: Vec<_> = op.deref(ctx).operands().collect();
+        Operation::erase(op, ctx);
+        for opd in operands {
+            if let Value::OpResult { op: def_op, .. } = opd
+                && def_op.is_live(ctx)
+            {
+                self.push(def_op);
+            }
+        }
+    }
+}
+
+/// How many times a [RewritePattern] was tried and applied, and the time it took.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatternStatistics {
+    /// Number of operations that the pattern was tried on.
+    pub attempted: u64,
+    /// Number of operations that the pattern rewrote.
+    pub applied: u64,
+    /// Time spent matching and rewriting.
+    pub time: Duration,
+}
+
+struct PatternEntry {
+    pattern: Box<dyn RewritePattern>,
+    enabled: bool,
+    /// Overrides the pattern's own benefit.
+    benefit: Option<u32>,
+    statistics: PatternStatistics,
+}
+
+impl PatternEntry {
+    fn benefit(&self) -> u32 {
+        self.benefit.unwrap_or_else(|| self.pattern.benefit())
+    }
+}
+
+/// A set of [RewritePattern]s, along with their [PatternStatistics].
+/// See [module](self) documentation.
+#[derive(Default)]
+pub struct RewritePatternSet {
+    entries: Vec<PatternEntry>,
+}
+
+impl RewritePatternSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (an enabled) `pattern`.
+    pub fn add(&mut self, pattern: impl RewritePattern + 'static) -> &mut Self {
+        self.entries.push(PatternEntry {
+            pattern: Box::new(pattern),
+            enabled: true,
+            benefit: None,
+            statistics: PatternStatistics::default(),
+        });
+        self
+    }
+
+    /// The names of the patterns in this set, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.pattern.name())
+    }
+
+    /// Enable (or disable, if not `enabled`) the patterns named `name`.
+    /// Returns `false` if there are none.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.update_named(name, |entry| entry.enabled = enabled)
+    }
+
+    /// Is there an enabled pattern named `name`?
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.enabled && entry.pattern.name() == name)
+    }
+
+    /// Override the benefit of the patterns named `name`, changing the order they're
+    /// tried in. Returns `false` if there are none.
+    pub fn set_benefit(&mut self, name: &str, benefit: u32) -> bool {
+        self.update_named(name, |entry| entry.benefit = Some(benefit))
+    }
+
+    /// The statistics of the (first) pattern named `name`.
+    pub fn pattern_statistics(&self, name: &str) -> Option<&PatternStatistics> {
+        self.entries
+            .iter()
+            .find(|entry| entry.pattern.name() == name)
+            .map(|entry| &entry.statistics)
+    }
+
+    /// The statistics of all the patterns that were tried, as [PassStatistics]:
+    /// counters `<name>.attempted` and `<name>.applied`, and timer `<name>`.
+    pub fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        for entry in &self.entries {
+            let (name, pattern_statistics) = (entry.pattern.name(), &entry.statistics);
+            if pattern_statistics.attempted == 0 {
+                continue;
+            }
+            statistics.add(&format!("{name}.attempted"), pattern_statistics.attempted);
+            statistics.add(&format!("{name}.applied"), pattern_statistics.applied);
+            statistics.add_time(name, pattern_statistics.time);
+        }
+        statistics
+    }
+
+    fn update_named(&mut self, name: &str, update: impl Fn(&mut PatternEntry)) -> bool {
+        let mut found = false;
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.pattern.name() == name)
+        {
+            update(entry);
+            found = true;
+        }
+        found
+    }
+
+    /// Indices of the enabled patterns, in decreasing order of benefit
+    /// (and in the order they were added, for equal benefits).
+    fn ordered(&self) -> Vec<usize> {
+        let mut ordered: Vec<_> = (0..self.entries.len())
+            .filter(|idx| self.entries[*idx].enabled)
+            .collect();
+        ordered.sort_by_key(|idx| std::cmp::Reverse(self.entries[*idx].benefit()));
+        ordered
+    }
+}
+
+/// Limits for [apply_patterns_greedily].
+#[derive(Clone, Copy, Debug)]
+pub struct GreedyRewriteConfig {
+    /// Maximum number of sweeps over the region. Each sweep visits all operations,
+    /// and those that are changed while doing so. Sweeps are repeated until one
+    /// doesn't rewrite anything.
+    pub max_iterations: usize,
+    /// Maximum number of rewrites in total. [None] for no limit.
+    pub max_rewrites: Option<usize>,
+}
+
+impl Default for GreedyRewriteConfig {
+    fn default() -> Self {
+        GreedyRewriteConfig {
+            max_iterations: 10,
+            max_rewrites: None,
+        }
+    }
+}
+
+/// Is `op` nested (at any depth) in `region`?
+fn is_in_region(ctx: &Context, op: Ptr<Operation>, region: Ptr<Region>) -> bool {
+    let mut op = op;
+    loop {
+        let Some(op_region) = op
+            .deref(ctx)
+            .container()
+            .and_then(|block| block.deref(ctx).container())
+        else {
+            return false;
+        };
+        if op_region == region {
+            return true;
+        }
+        op = op_region.deref(ctx).parent_op();
+    }
+}
+
+/// Apply `patterns` to the operations nested (at any depth) in `region`, until none
+/// apply anymore, or the limits in `config` are reached. Returns `true` if none apply
+/// anymore (i.e., a fixed point was reached). See [module](self) documentation.
+pub fn apply_patterns_greedily(
+    ctx: &mut Context,
+    region: Ptr<Region>,
+    patterns: &mut RewritePatternSet,
+    config: &GreedyRewriteConfig,
+) -> Result<bool> {
+    let ordered = patterns.ordered();
+    let mut num_rewrites = 0;
+    for _ in 0..config.max_iterations {
+        let mut rewriter = PatternRewriter::default();
+        for op in collect_region_ops(ctx, region) {
+            rewriter.push(op);
+        }
+
+        let mut changed = false;
+        while let Some(op) = rewriter.pop() {
+            if !op.is_live(ctx) || !is_in_region(ctx, op, region) {
+                continue;
+            }
+            let opid = op.deref(ctx).opid();
+            for idx in &ordered {
+                let entry = &mut patterns.entries[*idx];
+                if entry.pattern.root_opid().is_some_and(|root| root != opid) {
+                    continue;
+                }
+                let start = Instant::now();
+                let applied = entry.pattern.match_and_rewrite(ctx, &mut rewriter, op);
+                entry.statistics.time += start.elapsed();
+                entry.statistics.attempted += 1;
+                if !applied? {
+                    continue;
+                }
+                entry.statistics.applied += 1;
+                changed = true;
+                num_rewrites += 1;
+                if config.max_rewrites.is_some_and(|max| num_rewrites >= max) {
+                    return Ok(false);
+                }
+                // The operation may have been modified, and then other patterns may apply.
+                if op.is_live(ctx) {
+                    rewriter.push(op);
+                }
+                break;
+            }
+        }
+        if !changed {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// A [Pass] applying a [RewritePatternSet] (see [apply_patterns_greedily])
+/// to the regions of the operation it runs on. Its [statistics](Pass::statistics)
+/// are those of its [patterns](RewritePatternSet::statistics).
+pub struct GreedyRewritePass {
+    name: String,
+    patterns: RewritePatternSet,
+    config: GreedyRewriteConfig,
+}
+
+impl GreedyRewritePass {
+    /// Create a [GreedyRewritePass] named `name`, applying `patterns`.
+    pub fn new(name: &str, patterns: RewritePatternSet) -> Self {
+        GreedyRewritePass {
+            name: name.to_string(),
+            patterns,
+            config: GreedyRewriteConfig::default(),
+        }
+    }
+
+    /// Use `config` instead of the default [GreedyRewriteConfig].
+    pub fn with_config(mut self, config: GreedyRewriteConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The patterns applied by this pass, for example, to disable some of them.
+    pub fn patterns_mut(&mut self) -> &mut RewritePatternSet {
+        &mut self.patterns
+    }
+}
+
+impl Pass for GreedyRewritePass {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        let regions: Vec<_> = op.deref(ctx).regions().collect();
+        for region in regions {
+            apply_patterns_greedily(ctx, region, &mut self.patterns, &self.config)?;
+        }
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        self.patterns.statistics()
+    }
+}
@@ -1,12 +1,128 @@
 //! IR objects that are to be printed must implement [Printable].
+//!
+//! An [Operation](crate::operation::Operation) is printed by its [Op](crate::op::Op)'s printer
+//! (or in the generic syntax), which prints its [Region](crate::region::Region)s, each printing
+//! its [BasicBlock](crate::basic_block::BasicBlock)s, indented, and so on. The printing of the
+//! whole tree is configured by the [State] passed down: indentation, generic syntax,
+//! naming of values, integer radix, attribute elision and styling.
 
 use std::{
     cell::RefCell,
     fmt::{self, Display},
+    io::IsTerminal,
     rc::Rc,
 };
 
-use crate::{common_traits::RcSharable, context::Context};
+use crate::{
+    attribute::AttrObj,
+    common_traits::RcSharable,
+    context::Context,
+    identifier::Identifier,
+    utils::apint::Radix,
+    value::{Value, ValueNames},
+};
+
+/// Syntactic categories of printed IR, that a [Theme] may style differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Highlight {
+    /// Names of [Op](crate::op::Op)s.
+    OpId,
+    /// [Type](crate::type::Type)s.
+    Type,
+    /// [Attribute](crate::attribute::Attribute)s.
+    Attribute,
+    /// SSA [Value]s.
+    Value,
+    /// [BasicBlock](crate::basic_block::BasicBlock) labels.
+    BlockLabel,
+}
+
+/// Styles printed IR, by surrounding every [highlighted](State::highlighted) item
+/// with a prefix and a suffix. Themes can be defined outside of pliron,
+/// and are selected by [State::set_theme].
+pub trait Theme {
+    /// Printed before an item of category `highlight`.
+    fn prefix(&self, highlight: Highlight) -> &str;
+    /// Printed after an item of category `highlight`.
+    fn suffix(&self, highlight: Highlight) -> &str;
+}
+
+/// A [Theme] that colors the IR using ANSI escape codes, for terminals.
+#[derive(Clone, Debug)]
+pub struct AnsiTheme {
+    op_id: String,
+    r#type: String,
+    attribute: String,
+    value: String,
+    block_label: String,
+}
+
+impl AnsiTheme {
+    /// Code to reset the style to the terminal's default.
+    const RESET: &'static str = "\x1b[0m";
+
+    /// Set the style of `highlight` to the ANSI
+    /// [SGR](https://en.wikipedia.org/wiki/ANSI_escape_code#SGR) parameters `sgr`.
+    /// For example, `"1;34"` is bold blue.
+    pub fn with_style(mut self, highlight: Highlight, sgr: &str) -> Self {
+        let code = format!("\x1b[{}m", sgr);
+        match highlight {
+            Highlight::OpId => self.op_id = code,
+            Highlight::Type => self.r#type = code,
+            Highlight::Attribute => self.attribute = code,
+            Highlight::Value => self.value = code,
+            Highlight::BlockLabel => self.block_label = code,
+        }
+        self
+    }
+
+    /// The default [AnsiTheme], if colored output is wanted, as detected from the environment:
+    /// Colors are disabled when `NO_COLOR` is set (and not empty), forced when `CLICOLOR_FORCE`
+    /// is set (and neither empty nor `0`), and otherwise used only if stdout is a terminal.
+    pub fn detect() -> Option<AnsiTheme> {
+        let env_set = |var: &str| std::env::var_os(var).is_some_and(|val| !val.is_empty());
+        if env_set("NO_COLOR") {
+            return None;
+        }
+        let forced =
+            std::env::var_os("CLICOLOR_FORCE").is_some_and(|val| !val.is_empty() && val != "0");
+        (forced || std::io::stdout().is_terminal()).then(AnsiTheme::default)
+    }
+}
+
+impl Default for AnsiTheme {
+    fn default() -> Self {
+        let theme = AnsiTheme {
+            op_id: String::new(),
+            r#type: String::new(),
+            attribute: String::new(),
+            value: String::new(),
+            block_label: String::new(),
+        };
+        theme
+            .with_style(Highlight::OpId, "1;34")
+            .with_style(Highlight::Type, "32")
+            .with_style(Highlight::Attribute, "33")
+            .with_style(Highlight::Value, "35")
+            .with_style(Highlight::BlockLabel, "36")
+    }
+}
+
+impl Theme for AnsiTheme {
+    fn prefix(&self, highlight: Highlight) -> &str {
+        match highlight {
+            Highlight::OpId => &self.op_id,
+            Highlight::Type => &self.r#type,
+            Highlight::Attribute => &self.attribute,
+            Highlight::Value => &self.value,
+            Highlight::BlockLabel => &self.block_label,
+        }
+    }
+
+    fn suffix(&self, _highlight: Highlight) -> &str {
+        Self::RESET
+    }
+}
 
 #[derive(Clone)]
 struct StateInner {
@@ -14,6 +130,22 @@ struct StateInner {
     indent_width: u16,
     // Current indentation
     cur_indent: u16,
+    // Print all operations in the generic (canonical) syntax.
+    print_generic: bool,
+    // Radix to print integer attributes in.
+    int_radix: Radix,
+    // Theme to style the printed IR with.
+    theme: Option<Rc<dyn Theme>>,
+    // Highlighted items being printed, innermost last.
+    highlights: Vec<Highlight>,
+    // Attributes with more elements than this are elided.
+    attr_elision_limit: Option<usize>,
+    // The attributes elided so far, if they're being collected as resources.
+    resources: Option<Vec<AttrObj>>,
+    // Name SSA values in the order they're printed in.
+    numbered_values: bool,
+    // The SSA values named so far, while printing with numbered values.
+    value_names: Option<ValueNames>,
 }
 
 impl Default for StateInner {
@@ -21,6 +153,14 @@ impl Default for StateInner {
         Self {
             indent_width: 2,
             cur_indent: 0,
+            print_generic: false,
+            int_radix: Radix::Decimal,
+            theme: None,
+            highlights: vec![],
+            attr_elision_limit: None,
+            resources: None,
+            numbered_values: false,
+            value_names: None,
         }
     }
 }
@@ -55,6 +195,170 @@ impl State {
         let mut inner = self.0.as_ref().borrow_mut();
         inner.cur_indent -= inner.indent_width;
     }
+
+    /// Are [Operation](crate::operation::Operation)s printed in the generic syntax?
+    pub fn print_generic(&self) -> bool {
+        self.0.as_ref().borrow().print_generic
+    }
+
+    /// Print every [Operation](crate::operation::Operation), including those nested
+    /// in regions, in the generic syntax (see [canonical_syntax_print](crate::op::canonical_syntax_print)),
+    /// ignoring any custom [Printable] implementation of the [Op](crate::op::Op)s.
+    pub fn set_print_generic(&self, print_generic: bool) {
+        self.0.as_ref().borrow_mut().print_generic = print_generic;
+    }
+
+    /// The [Radix] that integer attributes are printed in.
+    pub fn int_radix(&self) -> Radix {
+        self.0.as_ref().borrow().int_radix
+    }
+
+    /// Print integer attributes in `radix` ([Radix::Decimal] by default).
+    /// In other radices, their exact bit pattern is printed
+    /// (see [to_string_bits](crate::utils::apint::APInt::to_string_bits)),
+    /// which is more readable for masks and other bit patterns.
+    pub fn set_int_radix(&self, radix: Radix) {
+        self.0.as_ref().borrow_mut().int_radix = radix;
+    }
+
+    /// The [Theme] that printed IR is styled with, if any.
+    pub fn theme(&self) -> Option<Rc<dyn Theme>> {
+        self.0.as_ref().borrow().theme.clone()
+    }
+
+    /// Style printed IR with `theme`. No styling is done when it is [None] (the default).
+    pub fn set_theme(&self, theme: Option<Rc<dyn Theme>>) {
+        self.0.as_ref().borrow_mut().theme = theme;
+    }
+
+    /// Style printed IR with an [AnsiTheme], if the environment
+    /// wants colored output (see [AnsiTheme::detect]).
+    pub fn set_theme_from_env(&self) {
+        self.set_theme(AnsiTheme::detect().map(|theme| Rc::new(theme) as Rc<dyn Theme>));
+    }
+
+    /// The number of elements above which attributes are elided.
+    pub fn attr_elision_limit(&self) -> Option<usize> {
+        self.0.as_ref().borrow().attr_elision_limit
+    }
+
+    /// Elide [ElidableAttrInterface] attributes with more than `limit` elements,
+    /// printing `[...N elements...]` instead, to keep dumps of IR with huge constants
+    /// readable. Attributes are printed exactly when `limit` is [None] (the default).
+    /// Elided attributes can't be parsed back, unless printed as resources by
+    /// [print_source_with](crate::parsable::print_source_with).
+    ///
+    /// [ElidableAttrInterface]: crate::builtin::attr_interfaces::ElidableAttrInterface
+    pub fn set_attr_elision_limit(&self, limit: Option<usize>) {
+        self.0.as_ref().borrow_mut().attr_elision_limit = limit;
+    }
+
+    /// Start collecting elided attributes as resources, discarding any collected so far.
+    pub(crate) fn collect_resources(&self) {
+        self.0.as_ref().borrow_mut().resources = Some(vec![]);
+    }
+
+    /// The index of the resource holding `attr`, which is being elided,
+    /// if resources are being collected.
+    pub(crate) fn resource_index(&self, attr: &AttrObj) -> Option<usize> {
+        let mut inner = self.0.as_ref().borrow_mut();
+        let resources = inner.resources.as_mut()?;
+        let idx = resources
+            .iter()
+            .position(|resource| resource == attr)
+            .unwrap_or_else(|| {
+                resources.push(attr.clone());
+                resources.len() - 1
+            });
+        Some(idx)
+    }
+
+    /// Stop collecting resources, returning those collected.
+    pub(crate) fn take_resources(&self) -> Vec<AttrObj> {
+        self.0
+            .as_ref()
+            .borrow_mut()
+            .resources
+            .take()
+            .unwrap_or_default()
+    }
+
+    /// Are SSA [Value]s printed with names numbered in the order they're printed in?
+    pub fn numbered_values(&self) -> bool {
+        self.0.as_ref().borrow().numbered_values
+    }
+
+    /// Print SSA [Value]s with names assigned in the order they're printed in,
+    /// rather than with their [unique names](crate::common_traits::Named::unique_name),
+    /// which are made unique with IDs that depend on how the IR was built.
+    /// Values with a given name keep it (suffixed with `_<N>` if it's already taken),
+    /// and the others are named `v0`, `v1`, ..., so that the same IR always prints the same.
+    /// Names are assigned afresh for every outermost [Operation], [Region] or [BasicBlock]
+    /// printed, and so, are consistent within it.
+    ///
+    /// [Operation]: crate::operation::Operation
+    /// [Region]: crate::region::Region
+    /// [BasicBlock]: crate::basic_block::BasicBlock
+    pub fn set_numbered_values(&self, numbered_values: bool) {
+        self.0.as_ref().borrow_mut().numbered_values = numbered_values;
+    }
+
+    /// Print with `print`, within a scope in which the names assigned to [Value]s
+    /// (when [numbering values](Self::set_numbered_values)) are consistent.
+    /// Nested scopes are part of the outermost one.
+    pub(crate) fn naming_values(&self, print: impl FnOnce() -> fmt::Result) -> fmt::Result {
+        let is_outermost = {
+            let mut inner = self.0.as_ref().borrow_mut();
+            let is_outermost = inner.numbered_values && inner.value_names.is_none();
+            if is_outermost {
+                inner.value_names = Some(ValueNames::default());
+            }
+            is_outermost
+        };
+        let res = print();
+        if is_outermost {
+            self.0.as_ref().borrow_mut().value_names = None;
+        }
+        res
+    }
+
+    /// The name assigned to `value`, if values are being named (see [Self::naming_values]).
+    pub(crate) fn value_name(&self, ctx: &Context, value: Value) -> Option<Identifier> {
+        self.0
+            .as_ref()
+            .borrow_mut()
+            .value_names
+            .as_mut()
+            .map(|names| names.name(ctx, value))
+    }
+
+    /// Print an item of category `highlight`, using `print`, styled as per the [Theme].
+    /// Items can be nested (for example, a type inside an attribute), in which case
+    /// the style of the outer item is restored after the inner one is printed.
+    pub fn highlighted(
+        &self,
+        highlight: Highlight,
+        f: &mut fmt::Formatter<'_>,
+        print: impl FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        let Some(theme) = self.theme() else {
+            return print(f);
+        };
+        write!(f, "{}", theme.prefix(highlight))?;
+        self.0.as_ref().borrow_mut().highlights.push(highlight);
+        let res = print(f);
+        let outer = {
+            let mut inner = self.0.as_ref().borrow_mut();
+            inner.highlights.pop();
+            inner.highlights.last().copied()
+        };
+        res?;
+        write!(f, "{}", theme.suffix(highlight))?;
+        if let Some(outer) = outer {
+            write!(f, "{}", theme.prefix(outer))?;
+        }
+        Ok(())
+    }
 }
 
 impl RcSharable for State {
@@ -5,7 +5,12 @@ use crate::{
     basic_block::BasicBlock,
     common_traits::Verify,
     context::{Context, Ptr, private::ArenaObj},
+    graph::{
+        traversals::region::topological_order,
+        walkers::{self, Visitor, WalkConfig, interruptible::WalkResult},
+    },
     indented_block,
+    ir_mapping::IRMapping,
     linked_list::{ContainsLinkedList, private},
     location::Located,
     operation::Operation,
@@ -30,15 +35,19 @@ pub struct Region {
     pub(crate) self_ptr: Ptr<Region>,
     pub(crate) parent_op: Ptr<Operation>,
     blocks: BlocksInRegion,
+    /// The [modification epoch](Context::modification_epoch) this was last modified in.
+    modified_epoch: u64,
 }
 
 impl Region {
     /// Create a new Region.
     pub(crate) fn new(ctx: &mut Context, parent_op: Ptr<Operation>) -> Ptr<Region> {
+        let modified_epoch = ctx.next_modification_epoch();
         let f = |self_ptr: Ptr<Region>| Region {
             self_ptr,
             blocks: BlocksInRegion::default(),
             parent_op,
+            modified_epoch,
         };
         Self::alloc(ctx, f)
     }
@@ -67,13 +76,57 @@ impl Region {
         self.parent_op
     }
 
+    /// The [modification epoch](Context::modification_epoch) this region was last modified in.
+    pub fn modified_epoch(&self) -> u64 {
+        self.modified_epoch
+    }
+
+    /// The [BasicBlock]s in this region, in order. Unlike [iter](ContainsLinkedList::iter),
+    /// the snapshot stays unchanged as the region is changed, so it can be used to
+    /// erase or move blocks while going through them.
+    /// See [iterator invalidation](crate::linked_list#iterator-invalidation).
+    pub fn blocks_snapshot(&self, ctx: &Context) -> Vec<Ptr<BasicBlock>> {
+        self.iter(ctx).collect()
+    }
+
+    /// Copy the [BasicBlock]s of this region (see [BasicBlock::clone_into]) to the
+    /// back of `dest`. See [ir_mapping](crate::ir_mapping).
+    /// All blocks are created before their [Operation]s are copied, and blocks are
+    /// visited in topological order, so that definitions are copied before their uses.
+    pub fn clone_into(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        dest: Ptr<Region>,
+        mapping: &mut IRMapping,
+    ) {
+        let blocks = ptr.deref(ctx).blocks_snapshot(ctx);
+        for block in blocks {
+            BasicBlock::clone_empty(block, ctx, dest, mapping);
+        }
+        for block in topological_order(ctx, ptr) {
+            let new_block = mapping.block(block);
+            BasicBlock::clone_ops(block, ctx, new_block, mapping);
+        }
+    }
+
     /// Drop all uses that this region holds.
     pub fn drop_all_uses(ptr: Ptr<Self>, ctx: &Context) {
-        let blocks: Vec<_> = ptr.deref(ctx).iter(ctx).collect();
+        let blocks = ptr.deref(ctx).blocks_snapshot(ctx);
         for block in blocks {
             BasicBlock::drop_all_uses(block, ctx);
         }
     }
+
+    /// Walk this region and everything nested in it, calling back to `visitor`
+    /// in the order specified by `config`. See [Visitor].
+    pub fn walk<V: Visitor>(
+        ptr: Ptr<Self>,
+        ctx: &mut Context,
+        config: &WalkConfig,
+        visitor: &mut V,
+    ) -> WalkResult<V::Break> {
+        walkers::interruptible::walk_region(ctx, visitor, config, ptr, walkers::visit)
+    }
 }
 
 impl private::ContainsLinkedList<BasicBlock> for Region {
@@ -95,6 +148,8 @@ impl ContainsLinkedList<BasicBlock> for Region {
 }
 
 impl ArenaObj for Region {
+    const KIND: &'static str = "region";
+
     fn arena(ctx: &Context) -> &crate::context::ArenaCell<Self> {
         &ctx.regions
     }
@@ -107,12 +162,31 @@ impl ArenaObj for Region {
         self.self_ptr
     }
 
+    fn set_modified_epoch(&mut self, epoch: u64) {
+        self.modified_epoch = epoch;
+    }
+
     fn dealloc_sub_objects(ptr: Ptr<Self>, ctx: &mut Context) {
-        let blocks: Vec<_> = ptr.deref_mut(ctx).iter(ctx).collect();
+        let blocks = ptr.deref(ctx).blocks_snapshot(ctx);
         for block in blocks {
             ArenaObj::dealloc(block, ctx);
         }
     }
+
+    fn notify_modified(ptr: Ptr<Self>, ctx: &Context) {
+        ctx.notify_listeners(|listener| listener.notify_region_modified(ctx, ptr));
+    }
+
+    fn fmt_debug_details(
+        ptr: Ptr<Self>,
+        ctx: &Context,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match ptr.try_deref(ctx) {
+            Some(region) => write!(f, " (in {:?})", region.parent_op),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Verify for Region {
@@ -128,17 +202,19 @@ impl Printable for Region {
         state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        fmt_indented_newline(state, f)?;
-        write!(f, "{{")?;
-
-        indented_block!(state, {
+        state.naming_values(|| {
             fmt_indented_newline(state, f)?;
-            fmt_iter(self.iter(ctx), ctx, state, ListSeparator::Newline, f)?;
-        });
+            write!(f, "{{")?;
 
-        fmt_indented_newline(state, f)?;
-        write!(f, "}}")?;
-        Ok(())
+            indented_block!(state, {
+                fmt_indented_newline(state, f)?;
+                fmt_iter(self.iter(ctx), ctx, state, ListSeparator::Newline, f)?;
+            });
+
+            fmt_indented_newline(state, f)?;
+            write!(f, "}}")?;
+            Ok(())
+        })
     }
 }
 
@@ -153,6 +229,17 @@ impl Parsable for Region {
         state_stream: &mut parsable::StateStream<'a>,
         parent_op: Self::Arg,
     ) -> ParseResult<'a, Self::Parsed> {
+        parsable::parse_nested(state_stream, |state_stream| {
+            Region::parse_region(state_stream, parent_op)
+        })
+    }
+}
+
+impl Region {
+    fn parse_region<'a>(
+        state_stream: &mut parsable::StateStream<'a>,
+        parent_op: Ptr<Operation>,
+    ) -> ParseResult<'a, Ptr<Region>> {
         let loc = state_stream.loc();
         state_stream
             .state
@@ -0,0 +1,511 @@
+//! Serialize [Attribute]s and [Type]s with [serde].
+//!
+//! Attributes and types refer to entities (such as uniqued types) that live in
+//! a [Context], so serializing them needs the [Context] too. [Serializable] is
+//! the [Context] aware counterpart of serde's [Serialize] and [Deserialize], and
+//! [with_ctx](Serializable::with_ctx) and [seed](Serializable::seed) adapt it
+//! to serde, so that it works with any serde data format:
+//! ```
+//! # use pliron::{
+//! #     builtin::{self, attributes::StringAttr},
+//! #     attribute::AttrObj, context::Context, serializable::Serializable,
+//! # };
+//! # use serde::de::DeserializeSeed;
+//! let ctx = &mut Context::new();
+//! builtin::register(ctx);
+//! let attr: AttrObj = StringAttr::new("hello".into()).into();
+//! let json = serde_json::to_string(&attr.with_ctx(ctx)).unwrap();
+//! assert_eq!(json, r#"{"id":"builtin.string","value":"hello"}"#);
+//! let mut deserializer = serde_json::Deserializer::from_str(&json);
+//! let attr2 = AttrObj::seed(ctx).deserialize(&mut deserializer).unwrap();
+//! assert!(attr == attr2);
+//! ```
+//!
+//! [AttrObj]s and [`Ptr<TypeObj>`]s are serialized as their id, followed by their
+//! contents. Each [Dialect](crate::dialect::Dialect) keeps a registry of how its
+//! attributes and types are serialized, which is filled in with
+//! [register_attr] and [register_type]. The builtin dialect registers all of its
+//! attributes and types, except for the placeholders used internally by the parser.
+//! Attributes that don't refer to the [Context] can just derive serde's traits
+//! and use [impl_serializable_via_serde](crate::impl_serializable_via_serde).
+//!
+//! This module is available with the `serde` feature.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct, SerializeTuple},
+};
+
+pub use serde;
+
+use crate::{
+    attribute::{AttrId, AttrName, AttrObj, Attribute},
+    context::{Context, Ptr},
+    dialect::DialectName,
+    identifier::Identifier,
+    r#type::{Type, TypeId, TypeName, TypeObj, TypePtr},
+};
+
+/// Serialize and deserialize `Self`, which may refer to entities in a [Context].
+/// See [module](self) documentation.
+pub trait Serializable: Sized {
+    /// Serialize `self` with `serializer`.
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error>;
+
+    /// Deserialize a `Self` from `deserializer`, creating the entities it refers to in `ctx`.
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error>;
+
+    /// Get a [Serialize]able `self`.
+    fn with_ctx<'a>(&'a self, ctx: &'a Context) -> WithCtx<'a, Self> {
+        WithCtx { ctx, value: self }
+    }
+
+    /// Get a [DeserializeSeed] for `Self`.
+    fn seed(ctx: &mut Context) -> Seed<'_, Self> {
+        Seed {
+            ctx,
+            _value: PhantomData,
+        }
+    }
+}
+
+/// A [Type] that can be serialized. [`TypePtr<T>`] is then [Serializable].
+pub trait SerializableType: Type + Sized {
+    /// Serialize `self` with `serializer`.
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error>;
+
+    /// Deserialize a [Type] from `deserializer`, and get or create it in `ctx`.
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<TypePtr<Self>, D::Error>;
+}
+
+/// A [Serializable] value, along with its [Context], to [Serialize] it.
+pub struct WithCtx<'a, T: Serializable> {
+    ctx: &'a Context,
+    value: &'a T,
+}
+
+impl<T: Serializable> Serialize for WithCtx<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize_in(self.ctx, serializer)
+    }
+}
+
+/// A [DeserializeSeed] to deserialize a [Serializable] value in a [Context].
+pub struct Seed<'a, T: Serializable> {
+    ctx: &'a mut Context,
+    _value: PhantomData<T>,
+}
+
+impl<'de, T: Serializable> DeserializeSeed<'de> for Seed<'_, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        T::deserialize_in(self.ctx, deserializer)
+    }
+}
+
+/// Implement [Serializable] for a type that implements serde's [Serialize]
+/// and [Deserialize], and so doesn't need a [Context] to be serialized.
+/// Usage:
+/// ```
+/// # use pliron::{impl_serializable_via_serde, context::Context, serializable::Serializable};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct A(u32);
+/// impl_serializable_via_serde!(A);
+/// let ctx = Context::new();
+/// let json = serde_json::to_string(&A(42).with_ctx(&ctx)).unwrap();
+/// assert_eq!(json, "42");
+/// ```
+#[macro_export]
+macro_rules! impl_serializable_via_serde {
+    ($type_name:ty) => {
+        impl $crate::serializable::Serializable for $type_name {
+            fn serialize_in<S: $crate::serializable::serde::Serializer>(
+                &self,
+                _ctx: &$crate::context::Context,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::serializable::serde::Serialize::serialize(self, serializer)
+            }
+
+            fn deserialize_in<'de, D: $crate::serializable::serde::Deserializer<'de>>(
+                _ctx: &mut $crate::context::Context,
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::serializable::serde::Deserialize::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+impl_serializable_via_serde!(Identifier);
+
+impl<T: SerializableType> Serializable for TypePtr<T> {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        self.deref(ctx).serialize_in(ctx, serializer)
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        T::deserialize_in(ctx, deserializer)
+    }
+}
+
+impl<T: Serializable> Serializable for Vec<T> {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self {
+            seq.serialize_element(&value.with_ctx(ctx))?;
+        }
+        seq.end()
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        struct VecVisitor<'a, T>(&'a mut Context, PhantomData<T>);
+
+        impl<'de, T: Serializable> Visitor<'de> for VecVisitor<'_, T> {
+            type Value = Vec<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a sequence")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(value) = seq.next_element_seed(T::seed(self.0))? {
+                    values.push(value);
+                }
+                Ok(values)
+            }
+        }
+
+        deserializer.deserialize_seq(VecVisitor(ctx, PhantomData))
+    }
+}
+
+impl<T1: Serializable, T2: Serializable> Serializable for (T1, T2) {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.0.with_ctx(ctx))?;
+        tuple.serialize_element(&self.1.with_ctx(ctx))?;
+        tuple.end()
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        struct PairVisitor<'a, T1, T2>(&'a mut Context, PhantomData<(T1, T2)>);
+
+        impl<'de, T1: Serializable, T2: Serializable> Visitor<'de> for PairVisitor<'_, T1, T2> {
+            type Value = (T1, T2);
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a pair")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(T1, T2), A::Error> {
+                let first = seq
+                    .next_element_seed(T1::seed(self.0))?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let second = seq
+                    .next_element_seed(T2::seed(self.0))?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok((first, second))
+            }
+        }
+
+        deserializer.deserialize_tuple(2, PairVisitor(ctx, PhantomData))
+    }
+}
+
+type SerializeFn<T> = for<'a> fn(&'a Context, &'a T) -> Box<dyn erased_serde::Serialize + 'a>;
+type DeserializeFn<T> = for<'de> fn(
+    &mut Context,
+    &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<T, erased_serde::Error>;
+
+/// How to serialize an [Attribute] or a [Type] that's only known dynamically.
+struct SerdeFns<E, T> {
+    serialize: SerializeFn<E>,
+    deserialize: DeserializeFn<T>,
+}
+
+// Not derived, since that would require `E: Clone` and `T: Clone`.
+impl<E, T> Clone for SerdeFns<E, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, T> Copy for SerdeFns<E, T> {}
+
+/// The registry of how the attributes and types of a
+/// [Dialect](crate::dialect::Dialect) are serialized.
+#[derive(Default)]
+pub(crate) struct DialectSerdes {
+    attrs: rustc_hash::FxHashMap<AttrId, SerdeFns<AttrObj, AttrObj>>,
+    types: rustc_hash::FxHashMap<TypeId, SerdeFns<Ptr<TypeObj>, Ptr<TypeObj>>>,
+}
+
+fn dialect_serdes_mut<'a>(ctx: &'a mut Context, dialect: &DialectName) -> &'a mut DialectSerdes {
+    &mut ctx
+        .dialects
+        .get_mut(dialect)
+        .unwrap_or_else(|| panic!("Unregistered dialect {dialect}"))
+        .serdes
+}
+
+/// Serialize [AttrObj]s that are `A`s as [Serializable] `A`s.
+pub fn register_attr<A: Attribute + Serializable>(ctx: &mut Context) {
+    fn serialize<'a, A: Attribute + Serializable>(
+        ctx: &'a Context,
+        attr: &'a AttrObj,
+    ) -> Box<dyn erased_serde::Serialize + 'a> {
+        let attr = attr
+            .downcast_ref::<A>()
+            .expect("Registered for another AttrId");
+        Box::new(attr.with_ctx(ctx))
+    }
+    fn deserialize<A: Attribute + Serializable>(
+        ctx: &mut Context,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<AttrObj, erased_serde::Error> {
+        A::deserialize_in(ctx, deserializer).map(AttrObj::from)
+    }
+
+    let attr_id = A::attr_id_static();
+    let fns = SerdeFns {
+        serialize: serialize::<A>,
+        deserialize: deserialize::<A>,
+    };
+    dialect_serdes_mut(ctx, &attr_id.dialect)
+        .attrs
+        .insert(attr_id, fns);
+}
+
+/// Serialize [`Ptr<TypeObj>`]s that are `T`s as [SerializableType] `T`s.
+pub fn register_type<T: SerializableType>(ctx: &mut Context) {
+    /// A [`Ptr<TypeObj>`] that's known to be a `T`.
+    struct Typed<'a, T>(&'a Context, Ptr<TypeObj>, PhantomData<T>);
+
+    impl<T: SerializableType> Serialize for Typed<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let ty = self.1.deref(self.0);
+            let ty = ty
+                .downcast_ref::<T>()
+                .expect("Registered for another TypeId");
+            ty.serialize_in(self.0, serializer)
+        }
+    }
+
+    fn serialize<'a, T: SerializableType>(
+        ctx: &'a Context,
+        ty: &'a Ptr<TypeObj>,
+    ) -> Box<dyn erased_serde::Serialize + 'a> {
+        Box::new(Typed::<T>(ctx, *ty, PhantomData))
+    }
+    fn deserialize<T: SerializableType>(
+        ctx: &mut Context,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Ptr<TypeObj>, erased_serde::Error> {
+        T::deserialize_in(ctx, deserializer).map(|ty| ty.to_ptr())
+    }
+
+    let type_id = T::get_type_id_static();
+    let fns = SerdeFns {
+        serialize: serialize::<T>,
+        deserialize: deserialize::<T>,
+    };
+    dialect_serdes_mut(ctx, &type_id.dialect)
+        .types
+        .insert(type_id, fns);
+}
+
+/// Split `id`, `dialect.name`, into its dialect and name, if they're [Identifier]s.
+fn split_id(id: &str) -> Option<(DialectName, &str)> {
+    let (dialect, name) = id.split_once('.')?;
+    (Identifier::try_from(dialect).is_ok() && Identifier::try_from(name).is_ok())
+        .then(|| (DialectName::new(dialect), name))
+}
+
+fn lookup_attr(ctx: &Context, id: &str) -> Option<SerdeFns<AttrObj, AttrObj>> {
+    let (dialect, name) = split_id(id)?;
+    let attr_id = AttrId {
+        dialect,
+        name: AttrName::new(name),
+    };
+    ctx.dialects
+        .get(&dialect)?
+        .serdes
+        .attrs
+        .get(&attr_id)
+        .copied()
+}
+
+fn lookup_type(ctx: &Context, id: &str) -> Option<SerdeFns<Ptr<TypeObj>, Ptr<TypeObj>>> {
+    let (dialect, name) = split_id(id)?;
+    let type_id = TypeId {
+        dialect,
+        name: TypeName::new(name),
+    };
+    ctx.dialects
+        .get(&dialect)?
+        .serdes
+        .types
+        .get(&type_id)
+        .copied()
+}
+
+/// Serialize a dynamically known entity, `what`, `id` as `{ id, value }`.
+fn serialize_dyn<S: Serializer>(
+    serializer: S,
+    what: &'static str,
+    id: String,
+    value: Option<&dyn erased_serde::Serialize>,
+) -> Result<S::Ok, S::Error> {
+    let Some(value) = value else {
+        return Err(serde::ser::Error::custom(format!(
+            "{what} {id} can't be serialized"
+        )));
+    };
+    let mut state = serializer.serialize_struct(what, 2)?;
+    state.serialize_field("id", &id)?;
+    state.serialize_field("value", value)?;
+    state.end()
+}
+
+/// Deserialize the `value` of a dynamically known entity with `deserialize`.
+struct ErasedSeed<'a, T> {
+    ctx: &'a mut Context,
+    deserialize: DeserializeFn<T>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for ErasedSeed<'_, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.deserialize)(self.ctx, &mut deserializer).map_err(de::Error::custom)
+    }
+}
+
+/// Deserialize a dynamically known entity, `what`, serialized as `{ id, value }`.
+struct DynVisitor<'a, T> {
+    ctx: &'a mut Context,
+    what: &'static str,
+    lookup: fn(&Context, &str) -> Option<DeserializeFn<T>>,
+}
+
+impl<T> DynVisitor<'_, T> {
+    fn lookup<E: de::Error>(&self, id: &str) -> Result<DeserializeFn<T>, E> {
+        (self.lookup)(self.ctx, id)
+            .ok_or_else(|| E::custom(format!("{} {id} can't be deserialized", self.what)))
+    }
+}
+
+impl<'de, T> Visitor<'de> for DynVisitor<'_, T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an id followed by a value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let id: String = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let deserialize = self.lookup(&id)?;
+        let seed = ErasedSeed {
+            ctx: self.ctx,
+            deserialize,
+        };
+        seq.next_element_seed(seed)?
+            .ok_or_else(|| de::Error::invalid_length(1, &"an id followed by a value"))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        // The id must come first, to know how to deserialize the value.
+        if map.next_key::<String>()?.as_deref() != Some("id") {
+            return Err(de::Error::missing_field("id"));
+        }
+        let id: String = map.next_value()?;
+        let deserialize = self.lookup(&id)?;
+        if map.next_key::<String>()?.as_deref() != Some("value") {
+            return Err(de::Error::missing_field("value"));
+        }
+        let seed = ErasedSeed {
+            ctx: self.ctx,
+            deserialize,
+        };
+        map.next_value_seed(seed)
+    }
+}
+
+impl Serializable for AttrObj {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let id = self.attr_id();
+        let fns = lookup_attr(ctx, &id.to_string());
+        let value = fns.map(|fns| (fns.serialize)(ctx, self));
+        serialize_dyn(serializer, "Attribute", id.to_string(), value.as_deref())
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let visitor = DynVisitor {
+            ctx,
+            what: "Attribute",
+            lookup: |ctx, id| lookup_attr(ctx, id).map(|fns| fns.deserialize),
+        };
+        deserializer.deserialize_struct("Attribute", &["id", "value"], visitor)
+    }
+}
+
+impl Serializable for Ptr<TypeObj> {
+    fn serialize_in<S: Serializer>(&self, ctx: &Context, serializer: S) -> Result<S::Ok, S::Error> {
+        let id = self.deref(ctx).get_type_id();
+        let fns = lookup_type(ctx, &id.to_string());
+        let value = fns.map(|fns| (fns.serialize)(ctx, self));
+        serialize_dyn(serializer, "Type", id.to_string(), value.as_deref())
+    }
+
+    fn deserialize_in<'de, D: Deserializer<'de>>(
+        ctx: &mut Context,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let visitor = DynVisitor {
+            ctx,
+            what: "Type",
+            lookup: |ctx, id| lookup_type(ctx, id).map(|fns| fns.deserialize),
+        };
+        deserializer.deserialize_struct("Type", &["id", "value"], visitor)
+    }
+}
+
+/// Deserialize a `Repr` of `T` and convert it to a `T` in `ctx`.
+/// Useful to implement [Serializable] and [SerializableType] through a
+/// representation that doesn't refer to a [Context].
+pub fn deserialize_repr<'de, Repr: Deserialize<'de>, T, D: Deserializer<'de>>(
+    ctx: &mut Context,
+    deserializer: D,
+    convert: impl FnOnce(&mut Context, Repr) -> Result<T, String>,
+) -> Result<T, D::Error> {
+    let repr = Repr::deserialize(deserializer)?;
+    convert(ctx, repr).map_err(de::Error::custom)
+}
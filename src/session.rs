@@ -0,0 +1,319 @@
+//! Compile many translation units, each in its own [Context], and link them.
+//!
+//! Types and attributes are uniqued in (and [Ptr]s point into) a [Context], so IR
+//! can't simply be moved from one context to another. [import_op] copies an
+//! operation into another context, re-interning its types and attributes there
+//! (by going through the textual format). [import_type] and [import_attr] do the
+//! same for individual types and attributes.
+//!
+//! A [Session] holds a set of [CompilationUnit]s, each a [ModuleOp] in its own context,
+//! so that the units can be processed independently (for example, by running a
+//! [PassManager] on each), and finally [linked](Session::link) into a single module.
+
+use combine::{Parser, eof};
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc, arg_error_noloc,
+    attribute::AttrObj,
+    builtin::{
+        op_interfaces::{
+            ATTR_KEY_SYM_NAME, SingleBlockRegionInterface, SymbolOpInterface, SymbolTableInterface,
+        },
+        ops::ModuleOp,
+    },
+    context::{Context, Ptr},
+    identifier::Identifier,
+    irfmt::parsers::spaced,
+    linked_list::ContainsLinkedList,
+    location::{Located, Location, Source},
+    op::{Op, op_cast},
+    operation::Operation,
+    parsable::{
+        Parsable, ParseSourceErr, SourceInput, State, parse_diagnostics, parse_source,
+        state_stream_from_iterator,
+    },
+    pass::PassManager,
+    printable::Printable,
+    result::Result,
+    r#type::TypeObj,
+    uniqued_any,
+};
+
+#[derive(Error, Debug)]
+pub enum SessionErr {
+    #[error("Unable to import {text} into another context:\n{reason}")]
+    Import { text: String, reason: String },
+    #[error("Linked units have different values for module attribute {key}: {first} and {second}")]
+    ConflictingModuleAttr {
+        key: String,
+        first: String,
+        second: String,
+    },
+}
+
+/// Parse `text`, printed from another context, as a `T` in `into_ctx`.
+fn reparse<T: Parsable<Arg = ()>>(into_ctx: &mut Context, text: String) -> Result<T::Parsed> {
+    let state_stream =
+        state_stream_from_iterator(text.chars(), State::new(into_ctx, Source::InMemory));
+    let parsed = spaced(T::parser(()))
+        .skip(eof())
+        .parse(state_stream)
+        .map(|(parsed, _)| parsed);
+    parsed.map_err(|errors| {
+        let reason = ParseSourceErr::Diagnostics(parse_diagnostics(errors)).to_string();
+        arg_error_noloc!(SessionErr::Import { text, reason })
+    })
+}
+
+/// Get, in `into_ctx`, the type `ty` of `from_ctx`.
+pub fn import_type(
+    into_ctx: &mut Context,
+    from_ctx: &Context,
+    ty: Ptr<TypeObj>,
+) -> Result<Ptr<TypeObj>> {
+    reparse::<Ptr<TypeObj>>(into_ctx, ty.disp(from_ctx).to_string())
+}
+
+/// Get, in `into_ctx`, a copy of the attribute `attr` of `from_ctx`.
+pub fn import_attr(into_ctx: &mut Context, from_ctx: &Context, attr: &AttrObj) -> Result<AttrObj> {
+    reparse::<AttrObj>(into_ctx, attr.disp(from_ctx).to_string())
+}
+
+/// Get, in `into_ctx`, the location `loc` of `from_ctx`.
+/// Source files are registered afresh in `into_ctx`.
+pub fn import_location(
+    into_ctx: &mut Context,
+    from_ctx: &Context,
+    loc: &Location,
+) -> Result<Location> {
+    Ok(match loc {
+        Location::SrcPos { src, pos } => {
+            let src = match src {
+                Source::File(path_key) => {
+                    let path = uniqued_any::get(from_ctx, *path_key).clone();
+                    Source::new_from_file(into_ctx, path)
+                }
+                Source::InMemory => Source::InMemory,
+            };
+            Location::SrcPos { src, pos: *pos }
+        }
+        Location::Fused {
+            metadata,
+            locations,
+        } => Location::Fused {
+            metadata: metadata
+                .as_ref()
+                .map(|metadata| import_attr(into_ctx, from_ctx, metadata).map(Box::new))
+                .transpose()?,
+            locations: locations
+                .iter()
+                .map(|loc| import_location(into_ctx, from_ctx, loc))
+                .collect::<Result<_>>()?,
+        },
+        Location::Named { name, child_loc } => Location::Named {
+            name: name.clone(),
+            child_loc: Box::new(import_location(into_ctx, from_ctx, child_loc)?),
+        },
+        Location::CallSite { callee, caller } => Location::CallSite {
+            callee: Box::new(import_location(into_ctx, from_ctx, callee)?),
+            caller: Box::new(import_location(into_ctx, from_ctx, caller)?),
+        },
+        Location::Unknown => Location::Unknown,
+    })
+}
+
+/// Copy `op` (of `from_ctx`), along with the operations nested in it, into `into_ctx`.
+/// The types and attributes of the copy are interned in `into_ctx`, and its
+/// locations refer to the sources that the original's did.
+/// The copy isn't linked to any block.
+///
+/// `op` must not use values defined outside it (for example, it may be a
+/// module, or a function isolated from above): they don't exist in `into_ctx`.
+pub fn import_op(
+    into_ctx: &mut Context,
+    from_ctx: &Context,
+    op: Ptr<Operation>,
+) -> Result<Ptr<Operation>> {
+    let copy = reparse::<Operation>(into_ctx, op.disp(from_ctx).to_string())?;
+    import_op_details(into_ctx, from_ctx, op, copy)?;
+    Ok(copy)
+}
+
+/// Copy the locations, and the attributes that the textual format of `op`
+/// doesn't include, from `op` (in `from_ctx`) to its parsed `copy` (in `into_ctx`).
+fn import_op_details(
+    into_ctx: &mut Context,
+    from_ctx: &Context,
+    op: Ptr<Operation>,
+    copy: Ptr<Operation>,
+) -> Result<()> {
+    let op_ref = op.deref(from_ctx);
+    let loc = import_location(into_ctx, from_ctx, &op_ref.loc())?;
+    copy.deref_mut(into_ctx).set_loc(loc);
+    for (key, attr) in op_ref.attributes.0.iter() {
+        if !copy.deref(into_ctx).attributes.0.contains_key(key) {
+            let attr = import_attr(into_ctx, from_ctx, attr)?;
+            copy.deref_mut(into_ctx).attributes.0.insert(*key, attr);
+        }
+    }
+
+    let copy_regions: Vec<_> = copy.deref(into_ctx).regions().collect();
+    for (region, copy_region) in op_ref.regions().zip(copy_regions) {
+        let copy_blocks: Vec<_> = copy_region.deref(into_ctx).iter(into_ctx).collect();
+        for (block, copy_block) in region.deref(from_ctx).iter(from_ctx).zip(copy_blocks) {
+            let loc = import_location(into_ctx, from_ctx, &block.deref(from_ctx).loc())?;
+            copy_block.deref_mut(into_ctx).set_loc(loc);
+            let copy_ops: Vec<_> = copy_block.deref(into_ctx).iter(into_ctx).collect();
+            for (op, copy_op) in block.deref(from_ctx).iter(from_ctx).zip(copy_ops) {
+                import_op_details(into_ctx, from_ctx, op, copy_op)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A translation unit, being compiled in its own [Context].
+pub struct CompilationUnit {
+    /// Name of the unit, typically that of the source file it was parsed from.
+    pub name: String,
+    pub ctx: Context,
+    pub module: ModuleOp,
+}
+
+/// A set of [CompilationUnit]s, to be linked together. See [module](self) documentation.
+pub struct Session {
+    new_context: Box<dyn Fn() -> Context>,
+    units: Vec<CompilationUnit>,
+}
+
+impl Session {
+    /// Create a session whose contexts are all created by `new_context`
+    /// (which, for example, registers the dialects that the units use).
+    pub fn new(new_context: impl Fn() -> Context + 'static) -> Self {
+        Session {
+            new_context: Box::new(new_context),
+            units: vec![],
+        }
+    }
+
+    /// Create a new [Context], set up for this session.
+    pub fn new_context(&self) -> Context {
+        (self.new_context)()
+    }
+
+    /// Add a unit, with `module` in `ctx`, to the session.
+    pub fn add_unit(&mut self, name: &str, ctx: Context, module: ModuleOp) -> &mut CompilationUnit {
+        self.units.push(CompilationUnit {
+            name: name.to_string(),
+            ctx,
+            module,
+        });
+        self.units.last_mut().unwrap()
+    }
+
+    /// Parse (see [parse_source]) a unit, in a new context, and add it to the session.
+    pub fn parse_unit<'a>(
+        &mut self,
+        name: &str,
+        input: impl Into<SourceInput<'a>>,
+    ) -> Result<&mut CompilationUnit> {
+        let mut ctx = self.new_context();
+        let module = parse_source(&mut ctx, input)?;
+        Ok(self.add_unit(name, ctx, module))
+    }
+
+    /// The units in this session, in the order they were added.
+    pub fn units(&self) -> &[CompilationUnit] {
+        &self.units
+    }
+
+    /// The units in this session, in the order they were added.
+    pub fn units_mut(&mut self) -> &mut [CompilationUnit] {
+        &mut self.units
+    }
+
+    /// Run `pm` on the module of every unit. Stops at the first failure.
+    pub fn run_passes(&mut self, pm: &mut PassManager) -> Result<()> {
+        for unit in &mut self.units {
+            pm.run(&mut unit.ctx, unit.module.operation())?;
+        }
+        Ok(())
+    }
+
+    /// Link all the units into a new module, `name`, in a new context.
+    ///
+    /// The top-level operations of the units are moved, in order, into the linked module,
+    /// where references between the units resolve by symbol name. Declarations aren't merged
+    /// with definitions: a symbol defined by a unit that was already defined by an earlier
+    /// unit is renamed apart (along with its uses in the unit) to a fresh name.
+    /// The module attributes of the units are merged, and must agree where they overlap.
+    pub fn link(&self, name: &Identifier) -> Result<(Context, ModuleOp)> {
+        let mut ctx = self.new_context();
+        let linked = ModuleOp::new(&mut ctx, name);
+        for (unit_idx, unit) in self.units.iter().enumerate() {
+            let imported = import_op(&mut ctx, &unit.ctx, unit.module.operation())?;
+            let imported = *Operation::op(imported, &ctx)
+                .downcast_ref::<ModuleOp>()
+                .expect("Imported module must be a ModuleOp");
+
+            let symbols: Vec<_> = imported
+                .body(&ctx, 0)
+                .deref(&ctx)
+                .iter(&ctx)
+                .filter_map(|op| {
+                    op_cast::<dyn SymbolOpInterface>(&*Operation::op(op, &ctx))
+                        .map(|sym_op| sym_op.symbol_name(&ctx))
+                })
+                .collect();
+            for symbol in symbols {
+                if linked.lookup(&ctx, &symbol).is_none() {
+                    continue;
+                }
+                let prefix = format!("{}_{}_", symbol, unit_idx);
+                let fresh = loop {
+                    let fresh = ctx.fresh_name(&prefix);
+                    if linked.lookup(&ctx, &fresh).is_none()
+                        && imported.lookup(&ctx, &fresh).is_none()
+                    {
+                        break fresh;
+                    }
+                };
+                imported.rename_symbol(&mut ctx, &symbol, &fresh)?;
+            }
+
+            let ops: Vec<_> = imported.body(&ctx, 0).deref(&ctx).iter(&ctx).collect();
+            let linked_body = linked.body(&ctx, 0);
+            for op in ops {
+                op.unlink(&ctx);
+                op.insert_at_back(linked_body, &ctx);
+            }
+
+            let attributes: Vec<_> = imported
+                .operation()
+                .deref(&ctx)
+                .attributes
+                .0
+                .iter()
+                .filter(|(key, _)| **key != *ATTR_KEY_SYM_NAME)
+                .map(|(key, attr)| (*key, attr.clone()))
+                .collect();
+            for (key, attr) in attributes {
+                let linked_op = linked.operation();
+                if let Some(existing) = linked_op.deref(&ctx).attributes.0.get(&key) {
+                    if existing != &attr {
+                        return arg_err_noloc!(SessionErr::ConflictingModuleAttr {
+                            key: key.to_string(),
+                            first: existing.disp(&ctx).to_string(),
+                            second: attr.disp(&ctx).to_string(),
+                        });
+                    }
+                    continue;
+                }
+                linked_op.deref_mut(&ctx).attributes.0.insert(key, attr);
+            }
+            Operation::erase(imported.operation(), &mut ctx);
+        }
+        Ok((ctx, linked))
+    }
+}
@@ -0,0 +1,194 @@
+//! Target features, to make lowering decisions data driven.
+//!
+//! Whether a target supports some operation (for example, 128-bit integer arithmetic,
+//! or a vector extension) is described by a set of named features, enabled or disabled,
+//! attached to an operation (typically a module or a function) as an
+//! [ATTR_KEY_TARGET_FEATURES] attribute. The attribute is a [StringAttr] listing
+//! comma separated features, each prefixed with `+` (enabled) or `-` (disabled), as in
+//! LLVM's `target-features`. For example, `+i128,-avx512f`.
+//!
+//! The features in effect at an operation are those specified on it and on all the
+//! operations enclosing it, with the innermost specification of a feature taking
+//! precedence (see [TargetFeatures::of]). So a function may enable (or disable)
+//! features in addition to those of its module. Feature names are a convention
+//! between the dialects, passes and drivers that use them.
+//!
+//! [Op]s that need features declare them by implementing [RequiresFeaturesInterface].
+//! Passes and conversions can then check whether an operation is [legal](is_legal)
+//! on the target, before generating it or to decide whether it must be lowered further:
+//! ```ignore
+//! if !TargetFeatures::of(ctx, op)?.is_enabled("i128") {
+//!     // Split the 128-bit multiplication into 64-bit ones.
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fmt::Display, sync::LazyLock};
+
+use pliron::derive::op_interface;
+use thiserror::Error;
+
+use crate::{
+    builtin::attributes::StringAttr,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    input_err, input_err_noloc,
+    linked_list::LinkedList,
+    location::Located,
+    op::{Op, op_cast},
+    operation::Operation,
+    result::Result,
+};
+
+/// Key for the [TargetFeatures] attribute of an operation (typically a module or function).
+/// The value is a [StringAttr] with the features (see [module](self) documentation).
+pub static ATTR_KEY_TARGET_FEATURES: LazyLock<Identifier> =
+    LazyLock::new(|| "builtin_target_features".try_into().unwrap());
+
+#[derive(Error, Debug)]
+pub enum TargetFeaturesErr {
+    #[error("Malformed target feature \"{0}\", expected +<feature> or -<feature>")]
+    Malformed(String),
+    #[error("Target features attribute must be a string")]
+    NotAString,
+}
+
+/// Features, enabled or disabled, of a target. See [module](self) documentation.
+/// Features that aren't specified are considered disabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TargetFeatures {
+    features: BTreeMap<String, bool>,
+}
+
+impl TargetFeatures {
+    /// Parse comma separated `+<feature>` or `-<feature>` entries.
+    /// A feature specified more than once takes its last specification.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut features = TargetFeatures::default();
+        for entry in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let parsed = entry
+                .strip_prefix('+')
+                .map(|name| (true, name))
+                .or_else(|| entry.strip_prefix('-').map(|name| (false, name)))
+                .filter(|(_, name)| !name.is_empty() && !name.contains(char::is_whitespace));
+            let Some((enabled, name)) = parsed else {
+                return input_err_noloc!(TargetFeaturesErr::Malformed(entry.to_string()));
+            };
+            features.features.insert(name.to_string(), enabled);
+        }
+        Ok(features)
+    }
+
+    /// Enable (or disable, if `enabled` is false) `feature`.
+    pub fn with_feature(mut self, feature: &str, enabled: bool) -> Self {
+        self.features.insert(feature.to_string(), enabled);
+        self
+    }
+
+    /// Is `feature` enabled?
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.features.get(feature).copied().unwrap_or(false)
+    }
+
+    /// Is `feature` specified (either enabled or disabled)?
+    pub fn is_specified(&self, feature: &str) -> bool {
+        self.features.contains_key(feature)
+    }
+
+    /// The enabled features, in sorted order.
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.features
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(feature, _)| feature.as_str())
+    }
+
+    /// The features in effect at `op`: those of the [ATTR_KEY_TARGET_FEATURES]
+    /// attributes of `op` and of the operations enclosing it, with the innermost
+    /// specification of a feature taking precedence.
+    pub fn of(ctx: &Context, op: Ptr<Operation>) -> Result<Self> {
+        let mut features = TargetFeatures::default();
+        let mut op = Some(op);
+        while let Some(cur_op) = op {
+            let cur_op_ref = cur_op.deref(ctx);
+            if let Some(spec) = cur_op_ref.attributes.0.get(&*ATTR_KEY_TARGET_FEATURES) {
+                let Some(spec) = spec.downcast_ref::<StringAttr>() else {
+                    return input_err!(cur_op_ref.loc(), TargetFeaturesErr::NotAString);
+                };
+                let specified = Self::parse(&String::from(spec.clone())).map_err(|mut err| {
+                    err.set_loc(cur_op_ref.loc());
+                    err
+                })?;
+                for (feature, enabled) in specified.features {
+                    features.features.entry(feature).or_insert(enabled);
+                }
+            }
+            op = cur_op_ref
+                .container()
+                .and_then(|block| block.deref(ctx).container())
+                .map(|region| region.deref(ctx).parent_op());
+        }
+        Ok(features)
+    }
+
+    /// Set the features of `op` (typically a module or function) to `self`.
+    pub fn set(&self, ctx: &Context, op: Ptr<Operation>) {
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_TARGET_FEATURES, StringAttr::new(self.to_string()));
+    }
+}
+
+impl Display for TargetFeatures {
+    /// The features, in the [ATTR_KEY_TARGET_FEATURES] format.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let features: Vec<_> = self
+            .features
+            .iter()
+            .map(|(feature, enabled)| format!("{}{}", if *enabled { '+' } else { '-' }, feature))
+            .collect();
+        write!(f, "{}", features.join(","))
+    }
+}
+
+/// [Op]s that are legal only on targets with certain features.
+#[op_interface]
+pub trait RequiresFeaturesInterface {
+    /// The features that the target must have enabled for this operation to be legal.
+    /// They may depend on the operation, for example, on the width of its operands.
+    fn required_features(&self, ctx: &Context) -> Vec<String>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// The features that `op` requires (see [RequiresFeaturesInterface]),
+/// but that aren't enabled in `features`.
+pub fn missing_features(
+    ctx: &Context,
+    op: Ptr<Operation>,
+    features: &TargetFeatures,
+) -> Vec<String> {
+    let op_obj = Operation::op(op, ctx);
+    op_cast::<dyn RequiresFeaturesInterface>(&*op_obj).map_or(vec![], |requires| {
+        requires
+            .required_features(ctx)
+            .into_iter()
+            .filter(|feature| !features.is_enabled(feature))
+            .collect()
+    })
+}
+
+/// Is `op` legal with the target features in effect at it (see [TargetFeatures::of])?
+/// Operations that don't implement [RequiresFeaturesInterface] are always legal.
+pub fn is_legal(ctx: &Context, op: Ptr<Operation>) -> Result<bool> {
+    let features = TargetFeatures::of(ctx, op)?;
+    Ok(missing_features(ctx, op, &features).is_empty())
+}
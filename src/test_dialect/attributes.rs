@@ -0,0 +1,127 @@
+//! [Attribute]s of the test dialect.
+
+use combine::{Parser, between, token};
+use pliron::derive::{attr_interface_impl, def_attribute, format_attribute};
+use thiserror::Error;
+
+use crate::{
+    attribute::{AttrObj, Attribute},
+    builtin::attr_interfaces::VerbatimAttrInterface,
+    context::{Context, Ptr},
+    impl_verify_succ, input_err_noloc,
+    irfmt::parsers::verbatim_payload_parser,
+    parsable::{Parsable, ParseResult, StateStream},
+    printable::{self, Printable},
+    result::Result,
+    r#type::TypeObj,
+};
+
+/// An enumeration.
+#[def_attribute("test.enum")]
+#[format_attribute]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EnumAttr {
+    First,
+    Second,
+    Third,
+}
+impl_verify_succ!(EnumAttr);
+
+/// A list of integers, along with a type.
+#[def_attribute("test.list")]
+#[format_attribute("`[` vec($elems, CharSpace(`,`)) `]` `: ` $ty")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ListAttr {
+    elems: Vec<u64>,
+    ty: Ptr<TypeObj>,
+}
+impl_verify_succ!(ListAttr);
+
+impl ListAttr {
+    /// Create a new [ListAttr].
+    pub fn new(elems: Vec<u64>, ty: Ptr<TypeObj>) -> Self {
+        ListAttr { elems, ty }
+    }
+
+    /// The integers in the list.
+    pub fn elems(&self) -> &[u64] {
+        &self.elems
+    }
+
+    /// The type of the list.
+    pub fn ty(&self) -> Ptr<TypeObj> {
+        self.ty
+    }
+}
+
+/// A regular expression, printed verbatim as `#test<regex:pattern>`.
+/// The brackets in the pattern must be balanced.
+#[def_attribute("test.regex")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RegexAttr(String);
+impl_verify_succ!(RegexAttr);
+
+impl RegexAttr {
+    /// Create a new [RegexAttr].
+    pub fn new(pattern: &str) -> Self {
+        RegexAttr(pattern.to_string())
+    }
+
+    /// The pattern of the regular expression.
+    pub fn pattern(&self) -> &str {
+        &self.0
+    }
+}
+
+#[attr_interface_impl]
+impl VerbatimAttrInterface for RegexAttr {
+    fn verbatim_payload(&self, _ctx: &Context) -> String {
+        format!("regex:{}", self.0)
+    }
+}
+
+impl Printable for RegexAttr {
+    /// The non-verbatim form, `<pattern>`.
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "<{}>", self.0)
+    }
+}
+
+impl Parsable for RegexAttr {
+    type Arg = ();
+    type Parsed = Self;
+
+    /// Parse the non-verbatim form, `<pattern>`.
+    fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        _arg: Self::Arg,
+    ) -> ParseResult<'a, Self::Parsed> {
+        between(token('<'), token('>'), verbatim_payload_parser())
+            .map(RegexAttr)
+            .parse_stream(state_stream)
+            .into()
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown verbatim attribute {0}")]
+pub struct UnknownVerbatimAttrErr(pub String);
+
+/// Parse the payload of a verbatim attribute of the test dialect, `kind:contents`.
+pub fn parse_verbatim(_ctx: &mut Context, payload: &str) -> Result<AttrObj> {
+    match payload.split_once(':') {
+        Some(("regex", pattern)) => Ok(RegexAttr::new(pattern).into()),
+        _ => input_err_noloc!(UnknownVerbatimAttrErr(payload.to_string())),
+    }
+}
+
+pub fn register(ctx: &mut Context) {
+    EnumAttr::register_attr_in_dialect(ctx, EnumAttr::parser_fn);
+    ListAttr::register_attr_in_dialect(ctx, ListAttr::parser_fn);
+    RegexAttr::register_attr_in_dialect(ctx, RegexAttr::parser_fn);
+}
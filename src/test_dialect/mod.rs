@@ -0,0 +1,285 @@
+//! A dialect for testing the framework, similar to MLIR's
+//! [test dialect](https://github.com/llvm/llvm-project/tree/main/mlir/test/lib/Dialect/Test).
+//!
+//! Its [Op](crate::op::Op)s come in a variety of shapes (operands, results, regions and
+//! successors), and its [Type](crate::type::Type)s and [Attribute](crate::attribute::Attribute)s
+//! use the format derives, so that features of the framework (printing and parsing,
+//! interfaces, rewrites and conversions) can be exercised without depending on a real dialect.
+//!
+//! The dialect is available in the crate's own tests, and elsewhere
+//! (for example, to test other dialects or tools) with the `test-dialect` feature.
+
+pub mod attributes;
+pub mod ops;
+pub mod types;
+
+use crate::{
+    context::Context,
+    dialect::{Dialect, DialectName, DialectPlugin},
+};
+
+/// [DialectPlugin] for the test dialect.
+pub struct TestDialect;
+
+impl DialectPlugin for TestDialect {
+    fn name(&self) -> DialectName {
+        DialectName::new("test")
+    }
+
+    fn register(&self, ctx: &mut Context) {
+        let mut dialect = Dialect::new(self.name());
+        dialect.set_verbatim_attr_parser(attributes::parse_verbatim);
+        dialect.register(ctx);
+        ops::register(ctx);
+        types::register(ctx);
+        attributes::register(ctx);
+    }
+}
+
+/// Load the test dialect into context.
+pub fn register(ctx: &mut Context) {
+    TestDialect.load(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        attribute::AttrObj,
+        basic_block::BasicBlock,
+        builtin::{
+            self,
+            attributes::{
+                DictAttr, FloatAttr, IdentifierAttr, IntegerAttr, StringAttr, TypeAttr, UnitAttr,
+                VecAttr,
+            },
+            op_interfaces::{BranchOpInterface, OneResultInterface, SingleBlockRegionInterface},
+            ops::{FuncOp, ModuleOp},
+            types::{FloatType, FunctionType, IntegerType, Signedness},
+        },
+        common_traits::Verify,
+        context::Context,
+        identifier::Identifier,
+        irfmt::parsers::attr_parser,
+        linked_list::ContainsLinkedList,
+        location,
+        op::{Op, op_cast},
+        operation::Operation,
+        parsable::{self, state_stream_from_iterator},
+        parse_source,
+        printable::Printable,
+        result::Result,
+        test_dialect::{
+            self,
+            attributes::{EnumAttr, ListAttr, RegexAttr},
+            ops::{
+                AttrsOp, BinaryOp, BrOp, CondBrOp, ConsumeOp, IsolatedOp, NoopOp, ProduceOp,
+                RegionsOp, SingleBlockRegionOp, TaggedOp, TerminatorOp,
+            },
+            types::{ParamType, SimpleType},
+        },
+        utils::{apfloat::FloatSemantics, apint::APInt},
+    };
+    use awint::bw;
+    use combine::Parser;
+    use expect_test::expect;
+
+    fn setup_context() -> Context {
+        let mut ctx = Context::new();
+        builtin::register(&mut ctx);
+        test_dialect::register(&mut ctx);
+        ctx
+    }
+
+    // Build IR using the test dialect's ops, types and attributes,
+    // and check that it verifies and round-trips through the textual format.
+    #[test]
+    fn test_dialect_round_trip() -> Result<()> {
+        let ctx = &mut setup_context();
+        let i1_ty = IntegerType::get(ctx, 1, Signedness::Signless);
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless);
+        let f64_ty = FloatType::get(ctx, FloatSemantics::Double);
+        let simple_ty = SimpleType::get(ctx);
+        let param_ty = ParamType::get(ctx, 8, simple_ty.into());
+
+        let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+        let func_ty = FunctionType::get(ctx, vec![i64_ty.into()], vec![]);
+        let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+        let entry = func.get_entry_block(ctx);
+        let arg = entry.deref(ctx).argument(0);
+
+        let produce = ProduceOp::new(ctx, param_ty.into());
+        produce.operation().insert_at_back(entry, ctx);
+        let cond = ProduceOp::new(ctx, i1_ty.into());
+        cond.operation().insert_at_back(entry, ctx);
+        let binary = BinaryOp::new(ctx, arg, arg);
+        binary.operation().insert_at_back(entry, ctx);
+        let tagged = TaggedOp::new(ctx, EnumAttr::Second, binary.result(ctx));
+        tagged.operation().insert_at_back(entry, ctx);
+        let consume = ConsumeOp::new(ctx, vec![produce.result(ctx), tagged.result(ctx)]);
+        consume.operation().insert_at_back(entry, ctx);
+
+        let key = |name: &str| -> Identifier { name.try_into().unwrap() };
+        let attrs: Vec<(Identifier, AttrObj)> = vec![
+            (key("ident"), IdentifierAttr::new(key("foo")).into()),
+            (key("string"), StringAttr::new("bar".to_string()).into()),
+            (
+                key("integer"),
+                IntegerAttr::new(i64_ty, APInt::from_u64(42, bw(64))).into(),
+            ),
+            (key("float"), FloatAttr::from_f64(ctx, f64_ty, -2.5).into()),
+            (key("unit"), UnitAttr::new().into()),
+            (key("regex"), RegexAttr::new("[<a-z>]+(x|\"}\")?").into()),
+            (
+                key("dict"),
+                DictAttr::new(vec![
+                    (key("nested"), DictAttr::new(vec![]).into()),
+                    (key("enum"), EnumAttr::First.into()),
+                ])
+                .into(),
+            ),
+            (key("type"), TypeAttr::new(param_ty.into()).into()),
+            (
+                key("vec"),
+                VecAttr::new(vec![UnitAttr::new().into(), EnumAttr::Third.into()]).into(),
+            ),
+            (
+                key("list"),
+                ListAttr::new(vec![1, 2, 3], i64_ty.into()).into(),
+            ),
+        ];
+        let attrs_op = AttrsOp::new(ctx, attrs);
+        attrs_op.operation().insert_at_back(entry, ctx);
+
+        let single_block = SingleBlockRegionOp::new(ctx);
+        single_block.operation().insert_at_back(entry, ctx);
+        let noop = NoopOp::new(ctx);
+        noop.operation()
+            .insert_at_back(single_block.body(ctx, 0), ctx);
+        // A region with two blocks.
+        let regions = RegionsOp::new(ctx, 1);
+        regions.operation().insert_at_back(entry, ctx);
+        let region = regions.operation().deref(ctx).region(0);
+        for _ in 0..2 {
+            let block = BasicBlock::new(ctx, None, vec![]);
+            block.insert_at_back(region, ctx);
+            NoopOp::new(ctx).operation().insert_at_back(block, ctx);
+        }
+        let isolated = IsolatedOp::new(ctx, vec![i64_ty.into()]);
+        isolated.operation().insert_at_back(entry, ctx);
+        let isolated_entry = isolated
+            .operation()
+            .deref(ctx)
+            .region(0)
+            .deref(ctx)
+            .head()
+            .unwrap();
+        let isolated_arg = isolated_entry.deref(ctx).argument(0);
+        let term = TerminatorOp::new(ctx, vec![isolated_arg]);
+        term.operation().insert_at_back(isolated_entry, ctx);
+
+        // Control flow: entry -> (then | exit), then -> exit.
+        let region = func.operation().deref(ctx).region(0);
+        let then_block = BasicBlock::new(ctx, Some(key("then")), vec![]);
+        then_block.insert_at_back(region, ctx);
+        let exit_block = BasicBlock::new(ctx, Some(key("exit")), vec![i64_ty.into()]);
+        exit_block.insert_at_back(region, ctx);
+        let cond_br = CondBrOp::new(ctx, cond.result(ctx), then_block, exit_block);
+        cond_br.operation().insert_at_back(entry, ctx);
+        let br = BrOp::new(ctx, exit_block, vec![arg]);
+        br.operation().insert_at_back(then_block, ctx);
+        let exit_arg = exit_block.deref(ctx).argument(0);
+        let ret = TerminatorOp::new(ctx, vec![exit_arg]);
+        ret.operation().insert_at_back(exit_block, ctx);
+
+        module.verify(ctx)?;
+        let br_op = Operation::op(br.operation(), ctx);
+        let branch = op_cast::<dyn BranchOpInterface>(&*br_op).unwrap();
+        assert!(branch.successor_operands(ctx, 0) == vec![arg]);
+
+        let printed = module.disp(ctx).to_string();
+        let reparse_ctx = &mut setup_context();
+        let reparsed = parse_source(reparse_ctx, printed.as_str())?;
+        reparsed.verify(reparse_ctx)?;
+        expect![[r#"
+            builtin.module @m 
+            {
+              ^bb0():
+                builtin.func @f: builtin.function <(builtin.integer i64)->()> 
+                {
+                  ^entry(block_2v1_arg0_block_7v1_arg0:builtin.integer i64):
+                    op_3v1_res0_op_3v1_res0 = test.produce : test.param <8, test.simple >;
+                    op_4v1_res0_op_4v1_res0 = test.produce : builtin.integer i1;
+                    op_5v1_res0_op_6v1_res0 = test.binary block_2v1_arg0_block_7v1_arg0, block_2v1_arg0_block_7v1_arg0 : builtin.integer i64;
+                    op_6v1_res0_op_7v1_res0 = test.tagged <Second> op_5v1_res0_op_6v1_res0 : builtin.integer i64;
+                    test.consume op_3v1_res0_op_3v1_res0, op_6v1_res0_op_7v1_res0;
+                    test.attrs () [] [(dict: builtin.dict {enum = test.enum First, nested = builtin.dict {}}), (float: builtin.float <-2.5: f64>), (ident: builtin.identifier (foo)), (integer: builtin.integer <42: i64>), (list: test.list [1, 2, 3]: builtin.integer i64), (regex: #test<regex:[<a-z>]+(x|"}")?>), (string: builtin.string "bar"), (type: builtin.type test.param <8, test.simple >), (unit: builtin.unit ), (vec: builtin.vec [builtin.unit , test.enum Third])]: <() -> ()>;
+                    test.single_block_region () [] []: <() -> ()>
+                    {
+                      ^bb0():
+                        test.noop () [] []: <() -> ()>
+                    };
+                    test.regions () [] []: <() -> ()>
+                    {
+                      ^bb0():
+                        test.noop () [] []: <() -> ()>
+                      ^bb1():
+                        test.noop () [] []: <() -> ()>
+                    };
+                    test.isolated () [] []: <() -> ()>
+                    {
+                      ^bb0(block_6v1_arg0_block_4v1_arg0:builtin.integer i64):
+                        test.terminator block_6v1_arg0_block_4v1_arg0
+                    };
+                    test.cond_br (op_4v1_res0_op_4v1_res0) [^then, ^exit] []: <(builtin.integer i1) -> ()>
+                  ^then():
+                    test.br ^exit(block_2v1_arg0_block_7v1_arg0)
+                  ^exit(block_8v1_arg0_block_5v3_arg0:builtin.integer i64):
+                    test.terminator block_8v1_arg0_block_5v3_arg0
+                }
+            }"#]]
+        .assert_eq(&reparsed.disp(reparse_ctx).to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verbatim_attributes() {
+        let ctx = &mut setup_context();
+        let mut parse = |input: &str| {
+            let state_stream = state_stream_from_iterator(
+                input.chars(),
+                parsable::State::new(ctx, location::Source::InMemory),
+            );
+            attr_parser()
+                .parse(state_stream)
+                .map(|(attr, _)| attr)
+                .map_err(|err| err.to_string())
+        };
+
+        let attr = parse("#test<regex:(a|<b>)*\"]\">").unwrap();
+        let regex = attr.downcast_ref::<RegexAttr>().unwrap();
+        assert_eq!(regex.pattern(), "(a|<b>)*\"]\"");
+        let attr = parse("test.regex <[0-9]+>").unwrap();
+        assert_eq!(
+            attr.downcast_ref::<RegexAttr>().unwrap().pattern(),
+            "[0-9]+"
+        );
+
+        expect![[r#"
+            Parse error at line: 1, column: 7
+            Unknown verbatim attribute glob:*.rs
+        "#]]
+        .assert_eq(&parse("#test<glob:*.rs>").unwrap_err());
+        expect![[r#"
+            Parse error at line: 1, column: 1
+            Dialect builtin doesn't support verbatim attributes
+        "#]]
+        .assert_eq(&parse("#builtin<regex:a>").unwrap_err());
+        expect![[r#"
+            Parse error at line: 1, column: 15
+            Unexpected `]`
+            Expected `)`
+        "#]]
+        .assert_eq(&parse("#test<regex:(a]>").unwrap_err());
+    }
+}
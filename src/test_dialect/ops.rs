@@ -0,0 +1,353 @@
+//! [Op]s of the test dialect.
+
+use pliron::derive::{def_op, derive_op_interface_impl, format_op, op_interface_impl};
+
+use crate::{
+    attribute::AttrObj,
+    basic_block::BasicBlock,
+    builtin::{
+        attributes::IntegerAttr,
+        op_interfaces::{
+            BranchOpInterface, ConditionalBranchInterface, IsTerminatorInterface,
+            IsolatedFromAboveInterface, OneOpdInterface, OneRegionInterface, OneResultInterface,
+            SameOperandsAndResultType, SameOperandsType, SameResultsType,
+            SingleBlockRegionInterface, ZeroOpdInterface, ZeroResultInterface,
+        },
+    },
+    context::{Context, Ptr},
+    identifier::Identifier,
+    impl_canonical_syntax, impl_verify_succ,
+    op::Op,
+    operation::Operation,
+    parsable::Parsable,
+    r#type::{TypeObj, Typed},
+    utils::apint::APInt,
+    value::Value,
+};
+
+use super::attributes::EnumAttr;
+
+/// An operation without operands or results.
+#[def_op("test.noop")]
+#[derive_op_interface_impl(ZeroOpdInterface, ZeroResultInterface)]
+pub struct NoopOp;
+impl_canonical_syntax!(NoopOp);
+impl_verify_succ!(NoopOp);
+
+impl NoopOp {
+    /// Create a new [NoopOp].
+    pub fn new(ctx: &mut Context) -> Self {
+        NoopOp {
+            op: Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0),
+        }
+    }
+}
+
+/// Produces a value of any type.
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | any type |
+#[def_op("test.produce")]
+#[format_op("`: ` type($0)")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface)]
+pub struct ProduceOp;
+impl_verify_succ!(ProduceOp);
+
+impl ProduceOp {
+    /// Create a new [ProduceOp], producing a value of type `ty`.
+    pub fn new(ctx: &mut Context, ty: Ptr<TypeObj>) -> Self {
+        ProduceOp {
+            op: Operation::new(ctx, Self::opid_static(), vec![ty], vec![], vec![], 0),
+        }
+    }
+}
+
+/// Consumes any number of values, of any types.
+#[def_op("test.consume")]
+#[format_op("operands(CharSpace(`,`))")]
+#[derive_op_interface_impl(ZeroResultInterface)]
+pub struct ConsumeOp;
+impl_verify_succ!(ConsumeOp);
+
+impl ConsumeOp {
+    /// Create a new [ConsumeOp], consuming `values`.
+    pub fn new(ctx: &mut Context, values: Vec<Value>) -> Self {
+        ConsumeOp {
+            op: Operation::new(ctx, Self::opid_static(), vec![], values, vec![], 0),
+        }
+    }
+}
+
+/// A binary operation, whose operands and result have the same type.
+///
+/// ### Operands:
+///
+/// | operand | description |
+/// |-----|-------|
+/// | `lhs` | any type |
+/// | `rhs` | type of `lhs` |
+///
+/// ### Result(s):
+///
+/// | result | description |
+/// |-----|-------|
+/// | `res` | type of `lhs` |
+#[def_op("test.binary")]
+#[format_op("$0 `, ` $1 ` : ` type($0)")]
+#[derive_op_interface_impl(
+    OneResultInterface,
+    SameOperandsType,
+    SameResultsType,
+    SameOperandsAndResultType
+)]
+pub struct BinaryOp;
+impl_verify_succ!(BinaryOp);
+
+impl BinaryOp {
+    /// Create a new [BinaryOp].
+    pub fn new(ctx: &mut Context, lhs: Value, rhs: Value) -> Self {
+        let ty = lhs.get_type(ctx);
+        BinaryOp {
+            op: Operation::new(
+                ctx,
+                Self::opid_static(),
+                vec![ty],
+                vec![lhs, rhs],
+                vec![],
+                0,
+            ),
+        }
+    }
+}
+
+/// A unary operation tagged with an [EnumAttr].
+///
+/// ### Attributes:
+///
+/// | key | value |
+/// |-----|-------|
+/// | `test_tag` | [EnumAttr] |
+#[def_op("test.tagged")]
+#[format_op("`<` attr($test_tag, `super::attributes::EnumAttr`) `> ` $0 ` : ` type($0)")]
+#[derive_op_interface_impl(OneOpdInterface, OneResultInterface)]
+pub struct TaggedOp;
+impl_verify_succ!(TaggedOp);
+
+impl TaggedOp {
+    /// Create a new [TaggedOp].
+    pub fn new(ctx: &mut Context, tag: EnumAttr, operand: Value) -> Self {
+        let ty = operand.get_type(ctx);
+        let op = Operation::new(ctx, Self::opid_static(), vec![ty], vec![operand], vec![], 0);
+        let key: Identifier = "test_tag".try_into().unwrap();
+        op.deref_mut(ctx).attributes.set(key, tag);
+        TaggedOp { op }
+    }
+}
+
+/// An operation with attributes (of any kinds), printed in the canonical syntax.
+#[def_op("test.attrs")]
+#[derive_op_interface_impl(ZeroOpdInterface, ZeroResultInterface)]
+pub struct AttrsOp;
+impl_canonical_syntax!(AttrsOp);
+impl_verify_succ!(AttrsOp);
+
+impl AttrsOp {
+    /// Create a new [AttrsOp], with `attributes`.
+    pub fn new(ctx: &mut Context, attributes: Vec<(Identifier, AttrObj)>) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        op.deref_mut(ctx).attributes.0.extend(attributes);
+        AttrsOp { op }
+    }
+}
+
+/// An operation with a single region, containing a single block, which
+/// can contain any operations and doesn't need a terminator.
+#[def_op("test.single_block_region")]
+#[derive_op_interface_impl(
+    OneRegionInterface,
+    SingleBlockRegionInterface,
+    ZeroOpdInterface,
+    ZeroResultInterface
+)]
+pub struct SingleBlockRegionOp;
+impl_canonical_syntax!(SingleBlockRegionOp);
+impl_verify_succ!(SingleBlockRegionOp);
+
+impl SingleBlockRegionOp {
+    /// Create a new [SingleBlockRegionOp], with an empty block.
+    pub fn new(ctx: &mut Context) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 1);
+        let region = op.deref(ctx).region(0);
+        let block = BasicBlock::new(ctx, None, vec![]);
+        block.insert_at_front(region, ctx);
+        SingleBlockRegionOp { op }
+    }
+}
+
+/// An operation with a single region, which is isolated from above.
+/// The region has an entry block, with arguments of the given types.
+#[def_op("test.isolated")]
+#[derive_op_interface_impl(
+    OneRegionInterface,
+    IsolatedFromAboveInterface,
+    ZeroOpdInterface,
+    ZeroResultInterface
+)]
+pub struct IsolatedOp;
+impl_canonical_syntax!(IsolatedOp);
+impl_verify_succ!(IsolatedOp);
+
+impl IsolatedOp {
+    /// Create a new [IsolatedOp], with an entry block with arguments of types `arg_types`.
+    pub fn new(ctx: &mut Context, arg_types: Vec<Ptr<TypeObj>>) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 1);
+        let region = op.deref(ctx).region(0);
+        let block = BasicBlock::new(ctx, None, arg_types);
+        block.insert_at_front(region, ctx);
+        IsolatedOp { op }
+    }
+}
+
+/// An operation with any number of regions, with any number of blocks each.
+#[def_op("test.regions")]
+#[derive_op_interface_impl(ZeroOpdInterface, ZeroResultInterface)]
+pub struct RegionsOp;
+impl_canonical_syntax!(RegionsOp);
+impl_verify_succ!(RegionsOp);
+
+impl RegionsOp {
+    /// Create a new [RegionsOp], with `num_regions` empty regions.
+    pub fn new(ctx: &mut Context, num_regions: usize) -> Self {
+        RegionsOp {
+            op: Operation::new(
+                ctx,
+                Self::opid_static(),
+                vec![],
+                vec![],
+                vec![],
+                num_regions,
+            ),
+        }
+    }
+}
+
+/// Unconditional branch, passing its operands to the successor.
+///
+/// ### Successors:
+///
+/// | Successor | description |
+/// |-----|-------|
+/// | `dest` | Any successor |
+#[def_op("test.br")]
+#[format_op("succ($0) `(` operands(CharSpace(`,`)) `)`")]
+#[derive_op_interface_impl(IsTerminatorInterface, ZeroResultInterface)]
+pub struct BrOp;
+impl_verify_succ!(BrOp);
+
+#[op_interface_impl]
+impl BranchOpInterface for BrOp {
+    fn successor_operands(&self, ctx: &Context, succ_idx: usize) -> Vec<Value> {
+        assert!(succ_idx == 0, "BrOp has exactly one successor");
+        self.operation().deref(ctx).operands().collect()
+    }
+}
+
+impl BrOp {
+    /// Create a new [BrOp].
+    pub fn new(ctx: &mut Context, dest: Ptr<BasicBlock>, dest_opds: Vec<Value>) -> Self {
+        BrOp {
+            op: Operation::new(ctx, Self::opid_static(), vec![], dest_opds, vec![dest], 0),
+        }
+    }
+}
+
+/// Conditional branch, on its only operand, to one of two successors,
+/// neither of which is passed any operands.
+///
+/// ### Successors:
+///
+/// | Successor | description |
+/// |-----|-------|
+/// | `true_dest` | Any successor |
+/// | `false_dest` | Any successor |
+#[def_op("test.cond_br")]
+#[derive_op_interface_impl(IsTerminatorInterface, ZeroResultInterface, OneOpdInterface)]
+pub struct CondBrOp;
+impl_canonical_syntax!(CondBrOp);
+impl_verify_succ!(CondBrOp);
+
+#[op_interface_impl]
+impl BranchOpInterface for CondBrOp {
+    fn successor_operands(&self, _ctx: &Context, succ_idx: usize) -> Vec<Value> {
+        assert!(succ_idx < 2, "CondBrOp has exactly two successors");
+        vec![]
+    }
+}
+
+#[op_interface_impl]
+impl ConditionalBranchInterface for CondBrOp {
+    fn taken_successor(&self, _ctx: &Context, operands: &[Option<AttrObj>]) -> Option<usize> {
+        let cond = operands[0].as_ref()?.downcast_ref::<IntegerAttr>()?;
+        Some(usize::from(APInt::from(cond.clone()).is_zero()))
+    }
+
+    fn build_branch_to(&self, ctx: &mut Context, succ_idx: usize) -> Ptr<Operation> {
+        let dest = self.operation().deref(ctx).successor(succ_idx);
+        BrOp::new(ctx, dest, vec![]).operation()
+    }
+}
+
+impl CondBrOp {
+    /// Create a new [CondBrOp].
+    pub fn new(
+        ctx: &mut Context,
+        condition: Value,
+        true_dest: Ptr<BasicBlock>,
+        false_dest: Ptr<BasicBlock>,
+    ) -> Self {
+        CondBrOp {
+            op: Operation::new(
+                ctx,
+                Self::opid_static(),
+                vec![],
+                vec![condition],
+                vec![true_dest, false_dest],
+                0,
+            ),
+        }
+    }
+}
+
+/// A terminator, without successors, with any number of operands.
+#[def_op("test.terminator")]
+#[format_op("operands(CharSpace(`,`))")]
+#[derive_op_interface_impl(IsTerminatorInterface, ZeroResultInterface)]
+pub struct TerminatorOp;
+impl_verify_succ!(TerminatorOp);
+
+impl TerminatorOp {
+    /// Create a new [TerminatorOp].
+    pub fn new(ctx: &mut Context, operands: Vec<Value>) -> Self {
+        TerminatorOp {
+            op: Operation::new(ctx, Self::opid_static(), vec![], operands, vec![], 0),
+        }
+    }
+}
+
+pub fn register(ctx: &mut Context) {
+    NoopOp::register(ctx, NoopOp::parser_fn);
+    ProduceOp::register(ctx, ProduceOp::parser_fn);
+    ConsumeOp::register(ctx, ConsumeOp::parser_fn);
+    BinaryOp::register(ctx, BinaryOp::parser_fn);
+    TaggedOp::register(ctx, TaggedOp::parser_fn);
+    AttrsOp::register(ctx, AttrsOp::parser_fn);
+    SingleBlockRegionOp::register(ctx, SingleBlockRegionOp::parser_fn);
+    IsolatedOp::register(ctx, IsolatedOp::parser_fn);
+    RegionsOp::register(ctx, RegionsOp::parser_fn);
+    BrOp::register(ctx, BrOp::parser_fn);
+    CondBrOp::register(ctx, CondBrOp::parser_fn);
+    TerminatorOp::register(ctx, TerminatorOp::parser_fn);
+}
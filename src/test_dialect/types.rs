@@ -0,0 +1,56 @@
+//! [Type]s of the test dialect.
+
+use pliron::derive::{def_type, format_type};
+
+use crate::{
+    context::{Context, Ptr},
+    impl_verify_succ,
+    parsable::Parsable,
+    r#type::{Type, TypeObj, TypePtr},
+};
+
+/// A type without parameters.
+#[def_type("test.simple")]
+#[derive(Hash, PartialEq, Eq, Debug)]
+#[format_type]
+pub struct SimpleType;
+impl_verify_succ!(SimpleType);
+
+impl SimpleType {
+    /// Get or create the simple type.
+    pub fn get(ctx: &mut Context) -> TypePtr<Self> {
+        Type::register_instance(SimpleType, ctx)
+    }
+}
+
+/// A type with an integer and a type parameter.
+#[def_type("test.param")]
+#[derive(Hash, PartialEq, Eq, Debug)]
+#[format_type("`<` $width `, ` $elem `>`")]
+pub struct ParamType {
+    width: u32,
+    elem: Ptr<TypeObj>,
+}
+impl_verify_succ!(ParamType);
+
+impl ParamType {
+    /// Get or create a parametric type.
+    pub fn get(ctx: &mut Context, width: u32, elem: Ptr<TypeObj>) -> TypePtr<Self> {
+        Type::register_instance(ParamType { width, elem }, ctx)
+    }
+
+    /// The integer parameter.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The type parameter.
+    pub fn elem(&self) -> Ptr<TypeObj> {
+        self.elem
+    }
+}
+
+pub fn register(ctx: &mut Context) {
+    SimpleType::register_type_in_dialect(ctx, SimpleType::parser_fn);
+    ParamType::register_type_in_dialect(ctx, ParamType::parser_fn);
+}
@@ -0,0 +1,290 @@
+//! Utilities to test dialects and transformations built on pliron.
+//!
+//! [round_trip] checks that IR parses, verifies and prints back such that
+//! it parses again to the same IR, and [check] matches (printed) text against
+//! `CHECK` directives, similar to LLVM's [FileCheck](https://llvm.org/docs/CommandGuide/FileCheck.html):
+//! ```
+//! use pliron::{builtin, context::Context, testing::{check, round_trip}};
+//! let ctx = &mut Context::new();
+//! builtin::register(ctx);
+//! let printed = round_trip(ctx, "builtin.module @m { ^entry(): }").unwrap();
+//! check(
+//!     &printed,
+//!     r"
+//!     CHECK: builtin.module @m
+//!     CHECK-NEXT: {
+//!     CHECK-NEXT: ^{{[a-z]+}}():
+//!     CHECK-NOT: builtin.func
+//!     ",
+//! )
+//! .unwrap();
+//! ```
+//!
+//! The directives, one per line, and possibly preceded by other text (such as
+//! a comment marker), are:
+//!   - `CHECK: pattern`: matches the first occurrence of `pattern` after the previous match.
+//!   - `CHECK-NEXT: pattern`: matches `pattern` in the line right after the previous match.
+//!   - `CHECK-SAME: pattern`: matches `pattern` in the same line, after the previous match.
+//!   - `CHECK-NOT: pattern`: `pattern` must not occur between the previous and the next
+//!     matches (or the end of the text).
+//!
+//! Patterns are matched literally, except for `{{regex}}` parts, which are
+//! [regular expressions](regex). Lines without directives are ignored.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc,
+    common_traits::Verify,
+    context::Context,
+    op::Op,
+    parsable::{parse_source, print_source},
+    result::Result,
+};
+
+#[derive(Error, Debug)]
+#[error(
+    "Printed IR differs from its reparse at line {line}:\n  printed: {printed}\nreprinted: {reprinted}"
+)]
+pub struct RoundTripErr {
+    pub line: usize,
+    pub printed: String,
+    pub reprinted: String,
+}
+
+/// Parse and verify `input`, and print it (with [print_source]).
+/// Then parse and verify the printed text, and check that it prints the same.
+/// Returns the printed text.
+pub fn round_trip(ctx: &mut Context, input: &str) -> Result<String> {
+    let module = parse_source(ctx, input)?;
+    module.operation().verify(ctx)?;
+    let printed = print_source(ctx, module);
+
+    let reparsed = parse_source(ctx, printed.as_str())?;
+    reparsed.operation().verify(ctx)?;
+    let reprinted = print_source(ctx, reparsed);
+
+    if let Some((line, (printed, reprinted))) = printed
+        .lines()
+        .chain(std::iter::repeat(""))
+        .zip(reprinted.lines().chain(std::iter::repeat("")))
+        .take(printed.lines().count().max(reprinted.lines().count()))
+        .enumerate()
+        .find(|(_, (printed, reprinted))| printed != reprinted)
+    {
+        return arg_err_noloc!(RoundTripErr {
+            line: line + 1,
+            printed: printed.to_string(),
+            reprinted: reprinted.to_string(),
+        });
+    }
+    Ok(printed)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CheckKind {
+    Check,
+    Next,
+    Same,
+    Not,
+}
+
+impl CheckKind {
+    fn directive(&self) -> &'static str {
+        match self {
+            CheckKind::Check => "CHECK",
+            CheckKind::Next => "CHECK-NEXT",
+            CheckKind::Same => "CHECK-SAME",
+            CheckKind::Not => "CHECK-NOT",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CheckErr {
+    #[error("Invalid check at line {line}: {reason}")]
+    InvalidCheck { line: usize, reason: String },
+    #[error("{directive} at line {line} didn't match: {pattern}")]
+    NoMatch {
+        directive: &'static str,
+        line: usize,
+        pattern: String,
+    },
+    #[error("CHECK-NOT at line {line} matched at line {output_line}: {pattern}")]
+    UnexpectedMatch {
+        line: usize,
+        output_line: usize,
+        pattern: String,
+    },
+}
+
+/// A parsed `CHECK` directive.
+struct Directive {
+    kind: CheckKind,
+    /// Line of the directive in the checks, starting from 1.
+    line: usize,
+    pattern: String,
+    regex: Regex,
+}
+
+/// Compile a pattern: literal text, except for `{{regex}}` parts.
+fn compile_pattern(pattern: &str, line: usize) -> Result<Regex> {
+    let invalid = |reason: String| arg_err_noloc!(CheckErr::InvalidCheck { line, reason });
+    let mut regex = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find("{{") {
+        regex.push_str(&regex::escape(&rest[..start]));
+        let Some(end) = rest[start..].find("}}") else {
+            return invalid("Unterminated {{".to_string());
+        };
+        regex.push_str(&format!("(?:{})", &rest[start + 2..start + end]));
+        rest = &rest[start + end + 2..];
+    }
+    regex.push_str(&regex::escape(rest));
+    Regex::new(&regex).or_else(|err| invalid(err.to_string()))
+}
+
+fn parse_directives(checks: &str) -> Result<Vec<Directive>> {
+    static DIRECTIVE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"CHECK(-NEXT|-SAME|-NOT)?:(.*)").unwrap());
+
+    let mut directives = vec![];
+    for (idx, text) in checks.lines().enumerate() {
+        let line = idx + 1;
+        let Some(captures) = DIRECTIVE.captures(text) else {
+            continue;
+        };
+        let kind = match captures.get(1).map(|kind| kind.as_str()) {
+            None => CheckKind::Check,
+            Some("-NEXT") => CheckKind::Next,
+            Some("-SAME") => CheckKind::Same,
+            _ => CheckKind::Not,
+        };
+        let pattern = captures[2].trim().to_string();
+        if pattern.is_empty() {
+            return arg_err_noloc!(CheckErr::InvalidCheck {
+                line,
+                reason: "Empty pattern".to_string(),
+            });
+        }
+        let follows_match = directives
+            .iter()
+            .any(|directive: &Directive| directive.kind != CheckKind::Not);
+        if matches!(kind, CheckKind::Next | CheckKind::Same) && !follows_match {
+            return arg_err_noloc!(CheckErr::InvalidCheck {
+                line,
+                reason: format!("{} must follow a match", kind.directive()),
+            });
+        }
+        let regex = compile_pattern(&pattern, line)?;
+        directives.push(Directive {
+            kind,
+            line,
+            pattern,
+            regex,
+        });
+    }
+    Ok(directives)
+}
+
+/// A position in the checked text: a line index, and a byte offset in it.
+type Pos = (usize, usize);
+
+/// Check that none of `nots` match in `lines` between `from` and `to`.
+fn check_nots(lines: &[&str], nots: &[&Directive], from: Pos, to: Pos) -> Result<()> {
+    for (line, text) in lines.iter().enumerate().take(to.0 + 1).skip(from.0) {
+        let start = if line == from.0 { from.1 } else { 0 };
+        let end = if line == to.0 { to.1 } else { text.len() };
+        let text = &text[start..end];
+        if let Some(not) = nots.iter().find(|not| not.regex.is_match(text)) {
+            return arg_err_noloc!(CheckErr::UnexpectedMatch {
+                line: not.line,
+                output_line: line + 1,
+                pattern: not.pattern.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Match `output` against the `CHECK` directives in `checks`.
+/// See [module](self) documentation.
+pub fn check(output: &str, checks: &str) -> Result<()> {
+    let lines: Vec<&str> = output.lines().collect();
+    let find_in = |directive: &Directive, (line, col): Pos| -> Option<(Pos, Pos)> {
+        let found = directive.regex.find(lines.get(line)?.get(col..)?)?;
+        Some(((line, col + found.start()), (line, col + found.end())))
+    };
+
+    // Where the next match may start.
+    let directives = parse_directives(checks)?;
+    let mut pos: Pos = (0, 0);
+    let mut nots = vec![];
+    for directive in &directives {
+        let found = match directive.kind {
+            CheckKind::Not => {
+                nots.push(directive);
+                continue;
+            }
+            CheckKind::Check => (pos.0..lines.len()).find_map(|line| {
+                let col = if line == pos.0 { pos.1 } else { 0 };
+                find_in(directive, (line, col))
+            }),
+            CheckKind::Same => find_in(directive, pos),
+            CheckKind::Next => find_in(directive, (pos.0 + 1, 0)),
+        };
+        let Some((start, end)) = found else {
+            return arg_err_noloc!(CheckErr::NoMatch {
+                directive: directive.kind.directive(),
+                line: directive.line,
+                pattern: directive.pattern.clone(),
+            });
+        };
+        check_nots(&lines, &nots, pos, start)?;
+        nots.clear();
+        pos = end;
+    }
+    let end = lines
+        .last()
+        .map_or((0, 0), |last| (lines.len() - 1, last.len()));
+    check_nots(&lines, &nots, pos, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::check;
+
+    #[test]
+    fn test_check() {
+        let output = "builtin.module @m\n{\n  ^entry():\n    test.op %0, %1\n}";
+        check(
+            output,
+            r"
+            // CHECK: module @m
+            // CHECK-NOT: test.op
+            // CHECK: ^{{[a-z]+}}():
+            // CHECK-NEXT: test.op
+            // CHECK-SAME: %1
+            // CHECK-NOT: %0
+            ",
+        )
+        .unwrap();
+
+        let err = |checks: &str| check(output, checks).unwrap_err().err.to_string();
+        expect!["CHECK-NOT at line 2 matched at line 4: %0"]
+            .assert_eq(&err("CHECK: test.op\nCHECK-NOT: %0"));
+        expect!["CHECK-NEXT at line 2 didn't match: test.op"]
+            .assert_eq(&err("CHECK: {\nCHECK-NEXT: test.op"));
+        expect!["CHECK-NOT at line 2 matched at line 3: entry"]
+            .assert_eq(&err("CHECK: module\nCHECK-NOT: entry\nCHECK: }"));
+        expect!["CHECK at line 1 didn't match: test.op %1"].assert_eq(&err("CHECK: test.op %1"));
+        expect!["Invalid check at line 1: CHECK-SAME must follow a match"]
+            .assert_eq(&err("CHECK-SAME: module"));
+        expect!["Invalid check at line 1: Unterminated {{"].assert_eq(&err("CHECK: {{[a-z]"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Generic folds based on the algebraic properties of operations.
+//!
+//! [Op]s declare their algebraic properties by implementing [AlgebraicPropertiesInterface],
+//! and [fold_algebraic] (or the [AlgebraicFoldPass]) then simplifies them, without any
+//! op specific folding code. Writing `f` for the operation, the simplifications are:
+//!   - idempotent: `f(x, x) = x` for binary operations, and `f(f(x)) = f(x)` for unary ones,
+//!   - involution: `f(f(x)) = x` (unary operations only),
+//!   - identity element `e`: `f(x, e) = x`,
+//!   - absorbing element `a`: `f(x, a) = a`.
+//!
+//! Identity and absorbing elements are matched on the right operand, and also on the left
+//! one if the operation is [commutative](OpEquivalence::is_commutative). An operand is an
+//! element if it is defined by a [ConstantLikeInterface] operation whose
+//! [value](ConstantLikeInterface::constant_value) equals the element.
+//!
+//! Nested operations of `f(f(x))` match only if they have the same [OpId](crate::op::OpId)
+//! and attributes. Operations whose results become unused aren't erased, that's left to
+//! dead code elimination.
+
+use pliron::derive::op_interface;
+use thiserror::Error;
+
+use crate::{
+    attribute::AttrObj,
+    builtin::op_interfaces::{ConstantLikeInterface, OpEquivalence},
+    context::{Context, Ptr},
+    graph::walkers::collect_ops,
+    location::Located,
+    op::{Op, op_cast},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    result::Result,
+    r#type::Typed,
+    value::Value,
+    verify_err,
+};
+
+#[derive(Error, Debug)]
+#[error("Op {0} with algebraic properties must have one result, and one or two operands")]
+pub struct AlgebraicPropertiesVerifyErr(pub String);
+
+/// Algebraic properties of a unary or binary [Op] with a single result.
+/// See [module](self) documentation for the folds they enable.
+#[op_interface]
+pub trait AlgebraicPropertiesInterface {
+    /// Is `f(x, x) = x` (binary operations) or `f(f(x)) = f(x)` (unary operations)?
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
+    /// Is `f(f(x)) = x`? Only applies to unary operations.
+    fn is_involution(&self) -> bool {
+        false
+    }
+
+    /// The constant `e` such that `f(x, e) = x`, if there's one.
+    /// It may depend on the operation, for example, on its result type.
+    fn identity_element(&self, _ctx: &Context) -> Option<AttrObj> {
+        None
+    }
+
+    /// The constant `a` such that `f(x, a) = a`, if there's one.
+    /// It may depend on the operation, for example, on its result type.
+    fn absorbing_element(&self, _ctx: &Context) -> Option<AttrObj> {
+        None
+    }
+
+    fn verify(op: &dyn Op, ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let op = op.operation().deref(ctx);
+        if op.num_results() != 1 || !(1..=2).contains(&op.num_operands()) {
+            return verify_err!(
+                op.loc(),
+                AlgebraicPropertiesVerifyErr(op.opid().to_string())
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The constant value of `value`, if it's defined by a [ConstantLikeInterface] operation.
+fn constant_value(ctx: &Context, value: &Value) -> Option<AttrObj> {
+    let Value::OpResult { op, .. } = value else {
+        return None;
+    };
+    op_cast::<dyn ConstantLikeInterface>(&*Operation::op(*op, ctx))
+        .map(|constant| constant.constant_value(ctx))
+}
+
+/// The operation defining `value`, if it has the same [OpId](crate::op::OpId)
+/// and attributes as `op`.
+fn same_op_def(ctx: &Context, op: Ptr<Operation>, value: &Value) -> Option<Ptr<Operation>> {
+    let Value::OpResult { op: def_op, .. } = value else {
+        return None;
+    };
+    let (op_ref, def_op_ref) = (op.deref(ctx), def_op.deref(ctx));
+    (op_ref.opid() == def_op_ref.opid() && op_ref.attributes == def_op_ref.attributes)
+        .then_some(*def_op)
+}
+
+/// The value that the result of `op` can be replaced with, based on
+/// its [AlgebraicPropertiesInterface]. See [module](self) documentation.
+pub fn fold_algebraic_op(ctx: &Context, op: Ptr<Operation>) -> Option<Value> {
+    let op_obj = Operation::op(op, ctx);
+    let props = op_cast::<dyn AlgebraicPropertiesInterface>(&*op_obj)?;
+    let op_ref = op.deref(ctx);
+    if op_ref.num_results() != 1 {
+        return None;
+    }
+    let result = op_ref.result(0);
+    let operands: Vec<_> = op_ref.operands().collect();
+
+    let folded = match operands[..] {
+        [opd] => {
+            let inner = same_op_def(ctx, op, &opd);
+            if props.is_involution() {
+                inner.map(|inner| inner.deref(ctx).operand(0))
+            } else if props.is_idempotent() {
+                inner.map(|_| opd)
+            } else {
+                None
+            }
+        }
+        [lhs, rhs] => {
+            let commutative = op_cast::<dyn OpEquivalence>(&*op_obj)
+                .is_some_and(|equivalence| equivalence.is_commutative());
+            // Operands (with the other operand), that an element is matched against.
+            let mut candidates = vec![(rhs, lhs)];
+            if commutative {
+                candidates.push((lhs, rhs));
+            }
+            let matching = |element: Option<AttrObj>| {
+                let element = element?;
+                candidates.iter().copied().find(|(candidate, _)| {
+                    constant_value(ctx, candidate).as_ref() == Some(&element)
+                })
+            };
+            if props.is_idempotent() && lhs == rhs {
+                Some(lhs)
+            } else if let Some((_, other)) = matching(props.identity_element(ctx)) {
+                Some(other)
+            } else {
+                matching(props.absorbing_element(ctx)).map(|(absorbing, _)| absorbing)
+            }
+        }
+        _ => None,
+    };
+    folded.filter(|value| *value != result && value.get_type(ctx) == result.get_type(ctx))
+}
+
+/// Fold (see [fold_algebraic_op]) the operations nested (at any depth) in `root`,
+/// replacing the uses of their results. Operations whose results are unused are skipped.
+/// Returns the number of operations folded.
+pub fn fold_algebraic(ctx: &mut Context, root: Ptr<Operation>) -> usize {
+    let mut num_folded = 0;
+    for op in collect_ops(ctx, root).into_iter().skip(1) {
+        let is_used = op.deref(ctx).results().any(|result| result.is_used(ctx));
+        let Some(folded) = fold_algebraic_op(ctx, op).filter(|_| is_used) else {
+            continue;
+        };
+        let result = op.deref(ctx).result(0);
+        result.replace_some_uses_with(ctx, |_, _| true, &folded);
+        num_folded += 1;
+    }
+    num_folded
+}
+
+/// A [Pass] running [fold_algebraic].
+/// Its [statistics](Pass::statistics) count the operations `folded`.
+#[derive(Default)]
+pub struct AlgebraicFoldPass {
+    num_folded: u64,
+}
+
+impl Pass for AlgebraicFoldPass {
+    fn name(&self) -> &str {
+        "algebraic-fold"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_folded += fold_algebraic(ctx, op) as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("folded", self.num_folded);
+        statistics
+    }
+}
@@ -0,0 +1,227 @@
+//! Canonicalization: rewriting operations into a canonical form.
+//!
+//! [Op]s implement [CanonicalizeInterface] to supply their own canonicalizations,
+//! such as folding operations on constants. Along with those, the
+//! [canonicalization patterns](canonicalize_patterns) include generic ones,
+//! applying to operations of all dialects:
+//!   - [folding](super::fold) [Foldable] operations,
+//!   - [algebraic folds](fold_algebraic_op), for operations with
+//!     [algebraic properties](AlgebraicPropertiesInterface),
+//!   - moving constant operands to the right of
+//!     [commutative](OpEquivalence::is_commutative) binary operations,
+//!   - erasing unused [constants](ConstantLikeInterface).
+//!
+//! The [CanonicalizePass] applies them (see [apply_patterns_greedily](crate::pattern_match::apply_patterns_greedily))
+//! until none apply anymore. Its patterns may be [disabled](RewritePatternSet::set_enabled)
+//! by name, and more patterns [added](RewritePatternSet::add).
+
+use pliron::derive::op_interface;
+
+use crate::{
+    builtin::op_interfaces::{ConstantLikeInterface, OpEquivalence},
+    context::{Context, Ptr},
+    op::{Op, op_cast, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    pattern_match::{GreedyRewritePass, PatternRewriter, RewritePattern, RewritePatternSet},
+    result::Result,
+    transforms::{
+        algebraic::{AlgebraicPropertiesInterface, fold_algebraic_op},
+        fold::{Foldable, fold_op},
+    },
+    value::Value,
+};
+
+/// An [Op] with its own canonicalizations.
+/// See [module](self) documentation.
+#[op_interface]
+pub trait CanonicalizeInterface {
+    /// Rewrite this operation into its canonical form, if it isn't already,
+    /// making all changes through `rewriter`. Returns whether the IR was changed.
+    fn canonicalize(&self, ctx: &mut Context, rewriter: &mut PatternRewriter) -> Result<bool>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Is `value` defined by a [ConstantLikeInterface] operation?
+pub fn is_constant(ctx: &Context, value: &Value) -> bool {
+    let Value::OpResult { op, .. } = value else {
+        return false;
+    };
+    op_impls::<dyn ConstantLikeInterface>(&*Operation::op(*op, ctx))
+}
+
+/// Folds [Foldable] operations, with [fold_op].
+struct FoldPattern;
+
+impl RewritePattern for FoldPattern {
+    fn name(&self) -> &str {
+        "fold"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        if !op_impls::<dyn Foldable>(&*Operation::op(op, ctx)) {
+            return Ok(false);
+        }
+        Ok(fold_op(ctx, rewriter, op))
+    }
+}
+
+/// Canonicalizes operations implementing [CanonicalizeInterface].
+struct OpCanonicalizePattern;
+
+impl RewritePattern for OpCanonicalizePattern {
+    fn name(&self) -> &str {
+        "op-canonicalize"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        let op_obj = Operation::op(op, ctx);
+        match op_cast::<dyn CanonicalizeInterface>(&*op_obj) {
+            Some(canonicalize) => canonicalize.canonicalize(ctx, rewriter),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Replaces operations with the result of [fold_algebraic_op].
+struct AlgebraicFoldPattern;
+
+impl RewritePattern for AlgebraicFoldPattern {
+    fn name(&self) -> &str {
+        "algebraic-fold"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        if !op_impls::<dyn AlgebraicPropertiesInterface>(&*Operation::op(op, ctx)) {
+            return Ok(false);
+        }
+        let Some(folded) = fold_algebraic_op(ctx, op) else {
+            return Ok(false);
+        };
+        rewriter.replace_op_with_values(ctx, op, &[folded]);
+        Ok(true)
+    }
+}
+
+/// Swaps the operands of commutative binary operations whose left operand
+/// is a constant and right operand isn't.
+struct ConstantToRhsPattern;
+
+impl RewritePattern for ConstantToRhsPattern {
+    fn name(&self) -> &str {
+        "constant-to-rhs"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        let commutative = op_cast::<dyn OpEquivalence>(&*Operation::op(op, ctx))
+            .is_some_and(|equivalence| equivalence.is_commutative());
+        let operands: Vec<_> = op.deref(ctx).operands().collect();
+        let [lhs, rhs] = operands[..] else {
+            return Ok(false);
+        };
+        if !commutative || !is_constant(ctx, &lhs) || is_constant(ctx, &rhs) {
+            return Ok(false);
+        }
+        rewriter.modify_op_in_place(ctx, op, |ctx| {
+            Operation::set_operands(op, ctx, vec![rhs, lhs]);
+        });
+        Ok(true)
+    }
+}
+
+/// Erases [ConstantLikeInterface] operations whose results are unused.
+struct EraseDeadConstantPattern;
+
+impl RewritePattern for EraseDeadConstantPattern {
+    fn name(&self) -> &str {
+        "erase-dead-constant"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        if !op_impls::<dyn ConstantLikeInterface>(&*Operation::op(op, ctx))
+            || op.deref(ctx).has_use()
+        {
+            return Ok(false);
+        }
+        rewriter.erase_op(ctx, op);
+        Ok(true)
+    }
+}
+
+/// The canonicalization patterns. See [module](self) documentation.
+pub fn canonicalize_patterns() -> RewritePatternSet {
+    let mut patterns = RewritePatternSet::new();
+    patterns
+        .add(FoldPattern)
+        .add(OpCanonicalizePattern)
+        .add(AlgebraicFoldPattern)
+        .add(ConstantToRhsPattern)
+        .add(EraseDeadConstantPattern);
+    patterns
+}
+
+/// A [Pass] applying the [canonicalization patterns](canonicalize_patterns)
+/// to the regions of the operation it runs on. Its [statistics](Pass::statistics)
+/// are those of its [patterns](RewritePatternSet::statistics).
+pub struct CanonicalizePass(GreedyRewritePass);
+
+impl Default for CanonicalizePass {
+    fn default() -> Self {
+        CanonicalizePass(GreedyRewritePass::new(
+            "canonicalize",
+            canonicalize_patterns(),
+        ))
+    }
+}
+
+impl CanonicalizePass {
+    /// The patterns applied by this pass, to disable some, or add more.
+    pub fn patterns_mut(&mut self) -> &mut RewritePatternSet {
+        self.0.patterns_mut()
+    }
+}
+
+impl Pass for CanonicalizePass {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.0.run(ctx, op)
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        self.0.statistics()
+    }
+}
@@ -0,0 +1,122 @@
+//! Constant folding: replacing operations on constants with constants.
+//!
+//! [const_fold] [folds](super::fold) the [Foldable] operations all of whose operands
+//! are constants, and erases the constants left unused. Unlike
+//! [canonicalization](super::canonicalize), nothing else is simplified, so that
+//! (for example) `x * 1` is left as it is. The evaluation itself is up to the
+//! [Foldable] implementation of each dialect.
+
+use crate::{
+    builtin::op_interfaces::ConstantLikeInterface,
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+    op::op_impls,
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    pattern_match::PatternRewriter,
+    result::Result,
+    transforms::{
+        canonicalize::is_constant,
+        fold::{Foldable, fold_op},
+    },
+    value::Value,
+};
+
+/// Collect the [Foldable] operations nested in `root`, in pre-order.
+fn collect_foldable(ctx: &mut Context, root: Ptr<Operation>) -> Vec<Ptr<Operation>> {
+    let mut foldable = vec![];
+    let _ = Operation::walk(
+        root,
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut |ctx: &mut Context, node| {
+            if let IRNode::Operation(op) = node
+                && op != root
+                && op_impls::<dyn Foldable>(&*Operation::op(op, ctx))
+            {
+                foldable.push(op);
+            }
+            walk_advance::<()>()
+        },
+    );
+    foldable
+}
+
+/// Are all operands of `op` (of which there are some) constants?
+fn has_constant_operands(ctx: &Context, op: Ptr<Operation>) -> bool {
+    let op_ref = op.deref(ctx);
+    op_ref.num_operands() > 0 && op_ref.operands().all(|opd| is_constant(ctx, &opd))
+}
+
+/// Erase the operation defining `value`, if it's an unused constant.
+fn erase_if_dead_constant(ctx: &mut Context, value: Value) {
+    let Value::OpResult { op, .. } = value else {
+        return;
+    };
+    if op.is_live(ctx)
+        && op_impls::<dyn ConstantLikeInterface>(&*Operation::op(op, ctx))
+        && !value.is_used(ctx)
+    {
+        Operation::erase(op, ctx);
+    }
+}
+
+/// Fold the operations on constants nested in `root`, until there are none left
+/// that can be folded. Returns the number of operations folded.
+/// See [module](self) documentation.
+pub fn const_fold(ctx: &mut Context, root: Ptr<Operation>) -> usize {
+    let rewriter = &mut PatternRewriter::default();
+    let mut num_folded = 0;
+    loop {
+        let mut changed = false;
+        // Operations are mostly visited after the operations defining their operands.
+        // Those that aren't, such as across back edges, are folded in the next sweep.
+        for op in collect_foldable(ctx, root) {
+            if !op.is_live(ctx) || !has_constant_operands(ctx, op) {
+                continue;
+            }
+            let operands: Vec<_> = op.deref(ctx).operands().collect();
+            if !fold_op(ctx, rewriter, op) {
+                continue;
+            }
+            for operand in operands {
+                erase_if_dead_constant(ctx, operand);
+            }
+            num_folded += 1;
+            changed = true;
+        }
+        if !changed {
+            return num_folded;
+        }
+    }
+}
+
+/// A [Pass] running [const_fold].
+/// Its [statistics](Pass::statistics) count the operations `folded`.
+#[derive(Default)]
+pub struct ConstFoldPass {
+    num_folded: u64,
+}
+
+impl ConstFoldPass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Pass for ConstFoldPass {
+    fn name(&self) -> &str {
+        "const-fold"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_folded += const_fold(ctx, op) as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("folded", self.num_folded);
+        statistics
+    }
+}
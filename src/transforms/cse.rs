@@ -0,0 +1,212 @@
+//! Common subexpression elimination.
+//!
+//! [cse] replaces an operation with an earlier [equivalent](ops_equivalent) one
+//! that dominates it, erasing it. Only operations that are [pure](is_pure), and that
+//! have results but no regions, are replaced.
+//!
+//! Blocks are visited in the order of the [dominator tree](DominatorTree) of their
+//! region, so that the operations in a block are replaced by those in the blocks
+//! dominating it. Operations in nested regions are replaced by those before the
+//! operation containing them, unless it's [isolated from above](IsolatedFromAboveInterface).
+//! Regions with multiple blocks but no [SSA dominance](RegionKindInterface::has_ssa_dominance)
+//! (graph regions) are skipped.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    analysis::dominance::DominatorTree,
+    basic_block::BasicBlock,
+    builtin::op_interfaces::{
+        IsTerminatorInterface, IsolatedFromAboveInterface, RegionKindInterface, is_pure,
+        op_equivalence_hash, ops_equivalent,
+    },
+    context::{Context, Ptr},
+    linked_list::{ContainsLinkedList, LinkedList},
+    op::{op_cast, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    region::Region,
+    result::Result,
+};
+
+/// Operations available to replace equivalent ones, bucketed by their
+/// [equivalence hash](op_equivalence_hash). Scopes are entered and exited
+/// as the dominator tree is traversed.
+#[derive(Default)]
+struct KnownOps {
+    buckets: FxHashMap<u64, Vec<Ptr<Operation>>>,
+    /// Hashes of the operations made known, in order, to forget them when leaving scopes.
+    log: Vec<u64>,
+}
+
+impl KnownOps {
+    /// A known operation equivalent to `op`, which has `hash`.
+    fn find(&self, ctx: &Context, op: Ptr<Operation>, hash: u64) -> Option<Ptr<Operation>> {
+        self.buckets
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|known| ops_equivalent(ctx, *known, op))
+    }
+
+    fn insert(&mut self, op: Ptr<Operation>, hash: u64) {
+        self.buckets.entry(hash).or_default().push(op);
+        self.log.push(hash);
+    }
+
+    /// Enter a new scope, returning what's needed to [exit](Self::exit_scope) it.
+    fn enter_scope(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Forget the operations made known since `scope` was entered.
+    fn exit_scope(&mut self, scope: usize) {
+        for hash in self.log.drain(scope..).rev() {
+            self.buckets
+                .get_mut(&hash)
+                .and_then(|bucket| bucket.pop())
+                .expect("Known operation must be in its bucket");
+        }
+    }
+}
+
+/// Can `op` be replaced by an equivalent operation?
+fn is_candidate(ctx: &Context, op: Ptr<Operation>) -> bool {
+    let op_ref = op.deref(ctx);
+    op_ref.num_results() != 0
+        && op_ref.num_regions() == 0
+        && !op_impls::<dyn IsTerminatorInterface>(&*Operation::op(op, ctx))
+        && is_pure(ctx, op)
+}
+
+/// Does `region` have [SSA dominance](RegionKindInterface::has_ssa_dominance)?
+fn has_ssa_dominance(ctx: &Context, region: Ptr<Region>) -> bool {
+    let parent = region.deref(ctx).parent_op();
+    let parent_op = Operation::op(parent, ctx);
+    let Some(region_kind) = op_cast::<dyn RegionKindInterface>(&*parent_op) else {
+        return true;
+    };
+    let idx = parent
+        .deref(ctx)
+        .regions()
+        .position(|parent_region| parent_region == region)
+        .expect("Region must be in its parent operation");
+    region_kind.has_ssa_dominance(idx)
+}
+
+/// Replace the operations in `block`, and the regions nested in it,
+/// with equivalent `known` ones. Returns the number of operations erased.
+fn simplify_block(ctx: &mut Context, known: &mut KnownOps, block: Ptr<BasicBlock>) -> usize {
+    let mut num_erased = 0;
+    let ops: Vec<_> = block.deref(ctx).iter(ctx).collect();
+    for op in ops {
+        let regions: Vec<_> = op.deref(ctx).regions().collect();
+        if !regions.is_empty() {
+            if op_impls::<dyn IsolatedFromAboveInterface>(&*Operation::op(op, ctx)) {
+                let mut nested_known = KnownOps::default();
+                for region in regions {
+                    num_erased += simplify_region(ctx, &mut nested_known, region);
+                }
+            } else {
+                for region in regions {
+                    num_erased += simplify_region(ctx, known, region);
+                }
+            }
+        }
+
+        if !is_candidate(ctx, op) {
+            continue;
+        }
+        let hash = op_equivalence_hash(ctx, op);
+        let Some(existing) = known.find(ctx, op, hash) else {
+            known.insert(op, hash);
+            continue;
+        };
+        let results: Vec<_> = op.deref(ctx).results().collect();
+        let existing_results: Vec<_> = existing.deref(ctx).results().collect();
+        for (result, existing_result) in results.iter().zip(&existing_results) {
+            result.replace_some_uses_with(ctx, |_, _| true, existing_result);
+        }
+        Operation::erase(op, ctx);
+        num_erased += 1;
+    }
+    num_erased
+}
+
+/// Replace the operations in `region` with equivalent ones that dominate them.
+/// Returns the number of operations erased.
+fn simplify_region(ctx: &mut Context, known: &mut KnownOps, region: Ptr<Region>) -> usize {
+    let Some(entry) = region.deref(ctx).head() else {
+        return 0;
+    };
+    if entry.deref(ctx).next().is_none() {
+        let scope = known.enter_scope();
+        let num_erased = simplify_block(ctx, known, entry);
+        known.exit_scope(scope);
+        return num_erased;
+    }
+    if !has_ssa_dominance(ctx, region) {
+        return 0;
+    }
+
+    enum Visit {
+        Enter(Ptr<BasicBlock>),
+        Exit(usize),
+    }
+    let dom_tree = DominatorTree::new(ctx, region);
+    let mut num_erased = 0;
+    let mut worklist = vec![Visit::Enter(entry)];
+    while let Some(visit) = worklist.pop() {
+        match visit {
+            Visit::Enter(block) => {
+                let scope = known.enter_scope();
+                num_erased += simplify_block(ctx, known, block);
+                worklist.push(Visit::Exit(scope));
+                worklist.extend(dom_tree.children(block).into_iter().rev().map(Visit::Enter));
+            }
+            Visit::Exit(scope) => known.exit_scope(scope),
+        }
+    }
+    num_erased
+}
+
+/// Eliminate the common subexpressions in the regions of `root`.
+/// See [module](self) documentation. Returns the number of operations erased.
+pub fn cse(ctx: &mut Context, root: Ptr<Operation>) -> usize {
+    let mut known = KnownOps::default();
+    let regions: Vec<_> = root.deref(ctx).regions().collect();
+    regions
+        .into_iter()
+        .map(|region| simplify_region(ctx, &mut known, region))
+        .sum()
+}
+
+/// A [Pass] running [cse].
+/// Its [statistics](Pass::statistics) count the erased `ops`.
+#[derive(Default)]
+pub struct CsePass {
+    num_erased: usize,
+}
+
+impl CsePass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Pass for CsePass {
+    fn name(&self) -> &str {
+        "cse"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_erased += cse(ctx, op);
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("ops", self.num_erased as u64);
+        statistics
+    }
+}
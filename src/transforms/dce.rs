@@ -0,0 +1,197 @@
+//! Dead code elimination.
+//!
+//! [dce] erases
+//!   - blocks not reachable, through the successors of terminators, from the entry
+//!     block of their region (other than in [graph](RegionKind::Graph) regions,
+//!     which have no control flow),
+//!   - [trivially dead](is_trivially_dead) operations: those whose results are
+//!     unused, and which have no side effects other than reading memory, or
+//!     allocating the memory their results point to. Erasing an operation may leave
+//!     the operations defining its operands dead, and those are erased too.
+//!
+//! Operations declare their side effects with [MemoryEffectOpInterface], or that they
+//! have none by implementing [PureInterface]. [Constants][ConstantLikeInterface] are
+//! considered free of side effects too. See [memory_effects].
+//!
+//! [MemoryEffectOpInterface]: crate::builtin::op_interfaces::MemoryEffectOpInterface
+//! [PureInterface]: crate::builtin::op_interfaces::PureInterface
+//! [ConstantLikeInterface]: crate::builtin::op_interfaces::ConstantLikeInterface
+//! [memory_effects]: crate::builtin::op_interfaces::memory_effects
+
+use std::ops::AddAssign;
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    analysis::cfg::Cfg,
+    basic_block::BasicBlock,
+    builtin::op_interfaces::{
+        IsTerminatorInterface, MemoryEffect, MemoryEffectKind, MemoryEffectOn, RegionKind,
+        RegionKindInterface, has_only_effects,
+    },
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+    op::{op_cast, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    region::Region,
+    result::Result,
+    value::Value,
+};
+
+/// What [dce] erased.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DceStats {
+    /// Number of (trivially dead) operations erased.
+    pub num_ops: usize,
+    /// Number of (unreachable) blocks erased.
+    pub num_blocks: usize,
+}
+
+impl AddAssign for DceStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.num_ops += rhs.num_ops;
+        self.num_blocks += rhs.num_blocks;
+    }
+}
+
+/// Can `op` be erased if its results are unused? It can if its effects, and those
+/// of the operations nested in it, are only reads and allocations of their own results.
+fn is_erasable_if_unused(ctx: &Context, op: Ptr<Operation>) -> bool {
+    has_only_effects(ctx, op, &|_, effect: &MemoryEffect| {
+        matches!(
+            (effect.kind, effect.on),
+            (MemoryEffectKind::Read, _) | (MemoryEffectKind::Allocate, MemoryEffectOn::Result(_))
+        )
+    })
+}
+
+/// Can `op` be erased, because its results are unused and it has no side effects
+/// (other than reads, and allocations of its results)?
+/// Terminators never can.
+pub fn is_trivially_dead(ctx: &Context, op: Ptr<Operation>) -> bool {
+    let op_ref = op.deref(ctx);
+    !op_ref.results().any(|result| result.is_used(ctx))
+        && !op_impls::<dyn IsTerminatorInterface>(&*Operation::op(op, ctx))
+        && is_erasable_if_unused(ctx, op)
+}
+
+/// Is `region` a [graph](RegionKind::Graph) region?
+fn is_graph_region(ctx: &Context, region: Ptr<Region>) -> bool {
+    let parent = region.deref(ctx).parent_op();
+    let parent_op = Operation::op(parent, ctx);
+    let Some(region_kind) = op_cast::<dyn RegionKindInterface>(&*parent_op) else {
+        return false;
+    };
+    let idx = parent
+        .deref(ctx)
+        .regions()
+        .position(|parent_region| parent_region == region)
+        .expect("Region must be in its parent operation");
+    matches!(region_kind.region_kind(idx), RegionKind::Graph)
+}
+
+/// The operations nested in `root` (excluding it), and the regions, in pre-order.
+fn collect_ops_and_regions(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+) -> (Vec<Ptr<Operation>>, Vec<Ptr<Region>>) {
+    let mut ops = vec![];
+    let mut regions = vec![];
+    let _ = Operation::walk(
+        root,
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut |_ctx: &mut Context, node| {
+            match node {
+                IRNode::Operation(op) if op != root => ops.push(op),
+                IRNode::Region(region) => regions.push(region),
+                _ => (),
+            }
+            walk_advance::<()>()
+        },
+    );
+    (ops, regions)
+}
+
+/// Erase the blocks of `region` not reachable from its entry block
+/// (see [Cfg::unreachable_blocks]), returning how many were erased.
+/// Values defined in the blocks erased must not be used outside them.
+pub fn erase_unreachable_blocks(ctx: &mut Context, region: Ptr<Region>) -> usize {
+    // Drop all uses first, since the blocks erased may use each other.
+    let dead = Cfg::new(ctx, region).unreachable_blocks();
+    for block in &dead {
+        BasicBlock::drop_all_uses(*block, ctx);
+    }
+    for block in &dead {
+        BasicBlock::erase(*block, ctx);
+    }
+    dead.len()
+}
+
+/// Erase the unreachable blocks and trivially dead operations nested in `root`.
+/// See [module](self) documentation. Values defined in blocks that are erased
+/// must not be used outside them (which holds when definitions dominate their uses).
+pub fn dce(ctx: &mut Context, root: Ptr<Operation>) -> DceStats {
+    let mut stats = DceStats::default();
+    let (ops, regions) = collect_ops_and_regions(ctx, root);
+
+    // Inner regions first, as they may be in blocks erased from outer ones.
+    for region in regions.into_iter().rev() {
+        if !region.is_live(ctx) || is_graph_region(ctx, region) {
+            continue;
+        }
+        stats.num_blocks += erase_unreachable_blocks(ctx, region);
+    }
+
+    // Uses are mostly visited before their definitions, so that chains of
+    // dead operations are erased in one sweep, and nested operations before
+    // the operations containing them.
+    let nested: FxHashSet<_> = ops.iter().copied().collect();
+    let mut worklist = ops;
+    while let Some(op) = worklist.pop() {
+        if !op.is_live(ctx) || !is_trivially_dead(ctx, op) {
+            continue;
+        }
+        let operands: Vec<_> = op.deref(ctx).operands().collect();
+        Operation::erase(op, ctx);
+        stats.num_ops += 1;
+        // The definitions of the operands (in `root`) may have become dead.
+        worklist.extend(operands.into_iter().filter_map(|operand| match operand {
+            Value::OpResult { op, .. } if nested.contains(&op) => Some(op),
+            _ => None,
+        }));
+    }
+    stats
+}
+
+/// A [Pass] running [dce].
+/// Its [statistics](Pass::statistics) count the erased `ops` and `blocks`.
+#[derive(Default)]
+pub struct DcePass {
+    stats: DceStats,
+}
+
+impl DcePass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Pass for DcePass {
+    fn name(&self) -> &str {
+        "dce"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.stats += dce(ctx, op);
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("ops", self.stats.num_ops as u64);
+        statistics.add("blocks", self.stats.num_blocks as u64);
+        statistics
+    }
+}
@@ -0,0 +1,122 @@
+//! Folding: evaluating operations at compile time.
+//!
+//! [Op]s implement [Foldable] to compute their results from the constant values
+//! of their operands, without modifying the IR. A result may fold to a constant
+//! ([OpFoldResult::Attribute]) or to an existing value ([OpFoldResult::Value]).
+//!
+//! [fold_op] uses that to replace an operation's results. Constants are created
+//! by the [materializer](ConstantMaterializerFn) of the dialect of the operation
+//! folded (see [set_constant_materializer]). Folding is one of the
+//! [canonicalization patterns](super::canonicalize::canonicalize_patterns), so
+//! passes needing constant evaluation can reuse the folds of every dialect.
+
+//!
+//! [set_constant_materializer]: crate::dialect::Dialect::set_constant_materializer
+
+use pliron::derive::op_interface;
+
+use crate::{
+    attribute::AttrObj,
+    builtin::op_interfaces::ConstantLikeInterface,
+    context::{Context, Ptr},
+    op::{Op, op_cast},
+    operation::Operation,
+    pattern_match::PatternRewriter,
+    result::Result,
+    r#type::{TypeObj, Typed},
+    value::Value,
+};
+
+/// What a result of an operation folds to. See [Foldable].
+#[derive(Clone)]
+pub enum OpFoldResult {
+    /// A constant value.
+    Attribute(AttrObj),
+    /// An existing value (other than a result of the operation folded).
+    Value(Value),
+}
+
+/// An [Op] that can be evaluated at compile time.
+/// See [module](self) documentation.
+#[op_interface]
+pub trait Foldable {
+    /// Fold this operation, given the constant values of its operands
+    /// (`None` for operands that aren't constants). Returns what each
+    /// of its results folds to, or nothing if it can't be folded.
+    fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Create an (unlinked) operation defining a constant `value` of type `ty`,
+/// or `None` if it can't be represented.
+/// See [Dialect::set_constant_materializer](crate::dialect::Dialect::set_constant_materializer).
+pub type ConstantMaterializerFn =
+    fn(ctx: &mut Context, value: AttrObj, ty: Ptr<TypeObj>) -> Option<Ptr<Operation>>;
+
+/// The constant value of `value`, if it's defined by a [ConstantLikeInterface] operation.
+pub fn constant_value(ctx: &Context, value: &Value) -> Option<AttrObj> {
+    let Value::OpResult { op, .. } = value else {
+        return None;
+    };
+    op_cast::<dyn ConstantLikeInterface>(&*Operation::op(*op, ctx))
+        .map(|constant| constant.constant_value(ctx))
+}
+
+/// What the results of `op` fold to, if it's [Foldable] and can be folded.
+/// The IR isn't modified.
+pub fn try_fold(ctx: &Context, op: Ptr<Operation>) -> Option<Vec<OpFoldResult>> {
+    let op_obj = Operation::op(op, ctx);
+    let foldable = op_cast::<dyn Foldable>(&*op_obj)?;
+    let op_ref = op.deref(ctx);
+    let operands: Vec<_> = op_ref
+        .operands()
+        .map(|opd| constant_value(ctx, &opd))
+        .collect();
+    let folded = foldable.fold(ctx, &operands);
+    (!folded.is_empty() && folded.len() == op_ref.num_results()).then_some(folded)
+}
+
+/// Fold (see [try_fold]) `op`, replacing its results, and erasing it.
+/// Constants are materialized right before `op`. Returns whether `op` was folded:
+/// it isn't if a constant can't be materialized, and the IR is then unchanged.
+pub fn fold_op(ctx: &mut Context, rewriter: &mut PatternRewriter, op: Ptr<Operation>) -> bool {
+    let Some(folded) = try_fold(ctx, op) else {
+        return false;
+    };
+    let dialect = op.deref(ctx).opid().dialect;
+    let materializer = ctx
+        .dialects
+        .get(&dialect)
+        .and_then(|dialect| dialect.constant_materializer);
+    let results: Vec<_> = op.deref(ctx).results().collect();
+    let mut constants = vec![];
+    let mut values = vec![];
+    for (result, folded) in results.iter().zip(folded) {
+        match folded {
+            OpFoldResult::Value(value) => values.push(value),
+            OpFoldResult::Attribute(attr) => {
+                let ty = result.get_type(ctx);
+                let constant = materializer.and_then(|materialize| materialize(ctx, attr, ty));
+                let Some(constant) = constant else {
+                    for constant in constants {
+                        Operation::erase(constant, ctx);
+                    }
+                    return false;
+                };
+                constants.push(constant);
+                values.push(constant.deref(ctx).result(0));
+            }
+        }
+    }
+    for constant in constants {
+        rewriter.insert_before(ctx, constant, op);
+    }
+    rewriter.replace_op_with_values(ctx, op, &values);
+    true
+}
@@ -0,0 +1,214 @@
+//! Instrument operations with calls to runtime functions.
+//!
+//! [instrument] inserts, around each operation accepted by a filter, calls to
+//! functions of a runtime library (such as counters or tracers), as described
+//! by [InstrumentHook]s. This is useful for profiling and tracing the code generated
+//! by compilers built on pliron. The filters used to select [PassManager]
+//! anchors, such as [has_attribute](crate::pass::has_attribute) and
+//! [is_op_kind](crate::pass::is_op_kind), can be used here too.
+//!
+//! The runtime functions are declared (as a [FuncOp] with an empty body) in the
+//! closest symbol table enclosing each instrumented operation, unless already there.
+//! Since pliron doesn't define a call op, calls are built using the [BuildCallFn]
+//! provided by the user.
+//!
+//! [PassManager]: crate::pass::PassManager
+
+use thiserror::Error;
+
+use crate::{
+    arg_err,
+    builtin::{
+        op_interfaces::{IsTerminatorInterface, SymbolTableInterface},
+        ops::FuncOp,
+        types::FunctionType,
+    },
+    context::{Context, Ptr},
+    graph::walkers::collect_ops,
+    identifier::Identifier,
+    linked_list::LinkedList,
+    location::Located,
+    op::{Op, op_cast, op_impls},
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    result::Result,
+    transforms::outline::BuildCallFn,
+    r#type::TypePtr,
+    value::Value,
+};
+
+#[derive(Error, Debug)]
+pub enum InstrumentErr {
+    #[error("Instrumented operations must be nested in a symbol table")]
+    NoSymbolTable,
+    #[error("Symbol {0} is already defined, but isn't a function of the hook's type")]
+    SymbolMismatch(String),
+}
+
+/// Select the operations to [instrument].
+pub type InstrumentFilter = dyn Fn(&Context, Ptr<Operation>) -> bool;
+
+/// Compute the arguments of a hook's call for an instrumented operation.
+/// The values must be available at the call, so for example, a hook called
+/// [before](HookPosition::Before) the operation can't be passed its results.
+pub type HookArgsFn = dyn Fn(&mut Context, Ptr<Operation>) -> Vec<Value>;
+
+/// Where an [InstrumentHook] is called, relative to the instrumented operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookPosition {
+    Before,
+    /// Terminators aren't instrumented after, since nothing can follow them.
+    After,
+}
+
+/// A call to runtime function `callee`, of type `func_ty`, inserted around
+/// each instrumented operation.
+pub struct InstrumentHook {
+    callee: Identifier,
+    func_ty: TypePtr<FunctionType>,
+    position: HookPosition,
+    args: Option<Box<HookArgsFn>>,
+}
+
+impl InstrumentHook {
+    /// A hook calling `callee` at `position`, without arguments.
+    pub fn new(callee: Identifier, func_ty: TypePtr<FunctionType>, position: HookPosition) -> Self {
+        InstrumentHook {
+            callee,
+            func_ty,
+            position,
+            args: None,
+        }
+    }
+
+    /// Pass the values computed by `args` to the hook's calls.
+    pub fn with_args(
+        mut self,
+        args: impl Fn(&mut Context, Ptr<Operation>) -> Vec<Value> + 'static,
+    ) -> Self {
+        self.args = Some(Box::new(args));
+        self
+    }
+}
+
+/// Get the declaration of `hook`'s callee in the closest symbol table enclosing `op`,
+/// declaring it (right before the operation of the table that contains `op`) if needed.
+fn declare_callee(ctx: &mut Context, op: Ptr<Operation>, hook: &InstrumentHook) -> Result<FuncOp> {
+    let loc = op.deref(ctx).loc();
+    let mut anchor = op;
+    let table = loop {
+        let parent = anchor
+            .deref(ctx)
+            .container()
+            .and_then(|block| block.deref(ctx).container())
+            .map(|region| region.deref(ctx).parent_op());
+        let Some(parent) = parent else {
+            return arg_err!(loc, InstrumentErr::NoSymbolTable);
+        };
+        if op_impls::<dyn SymbolTableInterface>(&*Operation::op(parent, ctx)) {
+            break parent;
+        }
+        anchor = parent;
+    };
+    let table_op = Operation::op(table, ctx);
+    let table_intf = op_cast::<dyn SymbolTableInterface>(&*table_op)
+        .expect("Symbol table must implement SymbolTableInterface");
+
+    let Some(existing) = table_intf.lookup(ctx, &hook.callee) else {
+        let func = FuncOp::new(ctx, &hook.callee, hook.func_ty);
+        func.operation().insert_before(ctx, anchor);
+        return Ok(func);
+    };
+    match Operation::op(existing, ctx).downcast_ref::<FuncOp>() {
+        Some(func) if func.get_type(ctx) == hook.func_ty.into() => Ok(*func),
+        _ => arg_err!(loc, InstrumentErr::SymbolMismatch(hook.callee.to_string())),
+    }
+}
+
+/// Insert the calls of `hooks` around each operation nested (at any depth) in `root`
+/// that `filter` accepts. See [module](self) documentation.
+/// Hooks at the same position are called in the order they're listed.
+/// Returns the number of calls inserted.
+pub fn instrument(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+    filter: &InstrumentFilter,
+    hooks: &[InstrumentHook],
+    build_call: &BuildCallFn,
+) -> Result<usize> {
+    // Collect the operations first, to not instrument the calls inserted.
+    let ops: Vec<_> = collect_ops(ctx, root)
+        .into_iter()
+        .skip(1)
+        .filter(|&op| filter(ctx, op))
+        .collect();
+
+    let mut num_calls = 0;
+    for op in ops {
+        let is_terminator = op_impls::<dyn IsTerminatorInterface>(&*Operation::op(op, ctx));
+        let loc = op.deref(ctx).loc();
+        let mut last = op;
+        for hook in hooks {
+            if hook.position == HookPosition::After && is_terminator {
+                continue;
+            }
+            let callee = declare_callee(ctx, op, hook)?;
+            let args = hook.args.as_ref().map_or(vec![], |args| args(ctx, op));
+            let call = build_call(ctx, callee, args);
+            call.deref_mut(ctx).set_loc(loc.clone());
+            match hook.position {
+                HookPosition::Before => call.insert_before(ctx, op),
+                HookPosition::After => {
+                    call.insert_after(ctx, last);
+                    last = call;
+                }
+            }
+            num_calls += 1;
+        }
+    }
+    Ok(num_calls)
+}
+
+/// A [Pass] running [instrument].
+/// Its [statistics](Pass::statistics) count the `calls` inserted.
+pub struct InstrumentPass {
+    filter: Box<InstrumentFilter>,
+    hooks: Vec<InstrumentHook>,
+    build_call: Box<BuildCallFn>,
+    num_calls: u64,
+}
+
+impl InstrumentPass {
+    /// Instrument the operations that `filter` accepts with `hooks`,
+    /// building calls with `build_call`.
+    pub fn new(
+        filter: impl Fn(&Context, Ptr<Operation>) -> bool + 'static,
+        hooks: Vec<InstrumentHook>,
+        build_call: impl Fn(&mut Context, FuncOp, Vec<Value>) -> Ptr<Operation> + 'static,
+    ) -> Self {
+        InstrumentPass {
+            filter: Box::new(filter),
+            hooks,
+            build_call: Box::new(build_call),
+            num_calls: 0,
+        }
+    }
+}
+
+impl Pass for InstrumentPass {
+    fn name(&self) -> &str {
+        "instrument"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        let num_calls = instrument(ctx, op, &*self.filter, &self.hooks, &*self.build_call)?;
+        self.num_calls += num_calls as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("calls", self.num_calls);
+        statistics
+    }
+}
@@ -0,0 +1,541 @@
+//! Apply a scripted sequence of named transforms.
+//!
+//! A [TransformScript] lists transforms, by name, one per line (or separated by `;`).
+//! Each step may take parameters and restrict the operations it applies to:
+//! ```text
+//! # Comments run till the end of the line.
+//! canonicalize
+//! unroll(factor = 2) on test.for
+//! strip-attributes(key = my_attr) with my_attr; lower-to-cfg
+//! ```
+//! - `name(key = value, ...)`: the transform to apply and its [TransformParams].
+//!   Values are either words or double-quoted strings.
+//! - `on dialect.op`: apply only to operations of this kind.
+//! - `with attr_key`: apply only to operations having an attribute for `attr_key`.
+//!
+//! Without a `on` or `with` clause, a step applies to the root operation only.
+//! Otherwise it applies to every matching operation nested in (or being) the root.
+//!
+//! Transforms are looked up, at runtime, in a [TransformRegistry]. This lets
+//! the order of transformations be experimented with, without writing a new [Pass].
+
+use std::{iter::Peekable, str::Chars, str::FromStr};
+
+use combine::stream::position::SourcePosition;
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc, arg_error_noloc,
+    builtin::op_interfaces::ForLoopInterface,
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, walk_op},
+    identifier::Identifier,
+    input_err, input_error,
+    location::{Location, Source},
+    op::op_impls,
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    result::Result,
+    transforms::{
+        algebraic::fold_algebraic,
+        canonicalize::CanonicalizePass,
+        loop_unroll::{unroll_by_factor, unroll_full},
+        strip::{strip_attributes, strip_debug_info, strip_locations},
+        structured_cfg::lower_to_cfg,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum TransformScriptErr {
+    #[error("Expected {expected}, but found {found}")]
+    Expected { expected: String, found: String },
+    #[error("Unknown clause \"{0}\", expected \"on\" or \"with\"")]
+    UnknownClause(String),
+    #[error("Invalid attribute key \"{0}\"")]
+    InvalidAttrKey(String),
+    #[error("Unknown transform \"{0}\"")]
+    UnknownTransform(String),
+    #[error("Missing parameter \"{0}\"")]
+    MissingParam(String),
+    #[error("Invalid value \"{value}\" for parameter \"{param}\"")]
+    InvalidParam { param: String, value: String },
+    #[error("Transform \"{transform}\" cannot be applied to {op}")]
+    UnsupportedOp { transform: String, op: String },
+}
+
+/// Parameters of a [TransformStep], in the order they're specified.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransformParams(Vec<(String, String)>);
+
+impl TransformParams {
+    /// Get the (unparsed) value of parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the value of parameter `name`, parsed as a `T`.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.get(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    arg_error_noloc!(TransformScriptErr::InvalidParam {
+                        param: name.to_string(),
+                        value: value.to_string(),
+                    })
+                })
+            })
+            .transpose()
+    }
+
+    /// Get the value of parameter `name`, which must be specified, parsed as a `T`.
+    pub fn required<T: FromStr>(&self, name: &str) -> Result<T> {
+        match self.parse(name)? {
+            Some(value) => Ok(value),
+            None => arg_err_noloc!(TransformScriptErr::MissingParam(name.to_string())),
+        }
+    }
+
+    /// Iterate over the parameters, as (name, value) pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// A transform that can be named in a [TransformScript].
+pub type TransformFn = dyn Fn(&mut Context, Ptr<Operation>, &TransformParams) -> Result<()>;
+
+/// Transforms, by name, available to a [TransformScript].
+#[derive(Default)]
+pub struct TransformRegistry {
+    transforms: FxHashMap<String, Box<TransformFn>>,
+}
+
+impl TransformRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the transforms of this crate:
+    ///   - `canonicalize`: the [CanonicalizePass].
+    ///   - `algebraic-fold`: [fold_algebraic].
+    ///   - `strip-locations`: [strip_locations].
+    ///   - `strip-debug-info`: [strip_debug_info].
+    ///   - `strip-attributes(key = k)`: [strip_attributes] with key `k`.
+    ///   - `lower-to-cfg`: [lower_to_cfg].
+    ///   - `unroll(factor = f)`: [unroll_by_factor] `f`, or [unroll_full] without `factor`.
+    ///     Applies only to [ForLoopInterface] operations.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register("canonicalize", |ctx, op, _| {
+                CanonicalizePass::default().run(ctx, op)
+            })
+            .register("algebraic-fold", |ctx, op, _| {
+                fold_algebraic(ctx, op);
+                Ok(())
+            })
+            .register("strip-locations", |ctx, op, _| {
+                strip_locations(ctx, op);
+                Ok(())
+            })
+            .register("strip-debug-info", |ctx, op, _| {
+                strip_debug_info(ctx, op);
+                Ok(())
+            })
+            .register("strip-attributes", |ctx, op, params| {
+                let key: String = params.required("key")?;
+                let key = Identifier::try_from(key.as_str()).map_err(|_| {
+                    arg_error_noloc!(TransformScriptErr::InvalidParam {
+                        param: "key".to_string(),
+                        value: key.clone(),
+                    })
+                })?;
+                strip_attributes(ctx, op, &[key]);
+                Ok(())
+            })
+            .register("lower-to-cfg", |ctx, op, _| {
+                lower_to_cfg(ctx, op)?;
+                Ok(())
+            })
+            .register("unroll", |ctx, op, params| {
+                if !op_impls::<dyn ForLoopInterface>(&*Operation::op(op, ctx)) {
+                    return arg_err_noloc!(TransformScriptErr::UnsupportedOp {
+                        transform: "unroll".to_string(),
+                        op: op.deref(ctx).opid().to_string(),
+                    });
+                }
+                match params.parse("factor")? {
+                    Some(factor) => unroll_by_factor(ctx, op, factor),
+                    None => unroll_full(ctx, op),
+                }
+            });
+        registry
+    }
+
+    /// Register (or replace) the transform `name`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        transform: impl Fn(&mut Context, Ptr<Operation>, &TransformParams) -> Result<()> + 'static,
+    ) -> &mut Self {
+        self.transforms
+            .insert(name.to_string(), Box::new(transform));
+        self
+    }
+
+    /// Get the transform `name`.
+    pub fn get(&self, name: &str) -> Option<&TransformFn> {
+        self.transforms.get(name).map(|transform| &**transform)
+    }
+}
+
+/// A step of a [TransformScript]. See [module](self) documentation.
+#[derive(Clone, Debug)]
+pub struct TransformStep {
+    /// Name of the transform, in a [TransformRegistry].
+    pub name: String,
+    /// Parameters passed to the transform.
+    pub params: TransformParams,
+    /// Apply only to operations of this kind (`dialect.op`).
+    pub op_name: Option<String>,
+    /// Apply only to operations having an attribute for this key.
+    pub attr_key: Option<Identifier>,
+    /// Location of this step in the script.
+    pub loc: Location,
+}
+
+impl TransformStep {
+    /// Does this step apply to `op`, nested in (or being) `root`?
+    fn matches(&self, ctx: &Context, root: Ptr<Operation>, op: Ptr<Operation>) -> bool {
+        if self.op_name.is_none() && self.attr_key.is_none() {
+            return op == root;
+        }
+        let op_ref = op.deref(ctx);
+        self.op_name
+            .as_ref()
+            .is_none_or(|op_name| op_ref.opid().to_string() == *op_name)
+            && self
+                .attr_key
+                .as_ref()
+                .is_none_or(|key| op_ref.attributes.0.contains_key(key))
+    }
+}
+
+/// A sequence of [TransformStep]s. See [module](self) documentation.
+#[derive(Clone, Debug, Default)]
+pub struct TransformScript {
+    steps: Vec<TransformStep>,
+}
+
+impl TransformScript {
+    /// Parse `text` into a script. Locations in the script refer to `src`.
+    pub fn parse(src: Source, text: &str) -> Result<Self> {
+        let mut cursor = Cursor::new(src, text);
+        let mut steps = vec![];
+        loop {
+            cursor.skip_blanks();
+            match cursor.peek() {
+                None => break,
+                Some('\n' | ';') => {
+                    cursor.bump();
+                }
+                Some(_) => steps.push(cursor.step()?),
+            }
+        }
+        Ok(TransformScript { steps })
+    }
+
+    /// The steps of this script.
+    pub fn steps(&self) -> &[TransformStep] {
+        &self.steps
+    }
+
+    /// Apply the steps of this script, in order, to `root` and operations nested in it.
+    /// Returns the number of operations transformed (counting once per step).
+    ///
+    /// Each step is applied to the operations that match it before the step begins,
+    /// skipping those erased by the step's application to an earlier one.
+    pub fn apply(
+        &self,
+        ctx: &mut Context,
+        registry: &TransformRegistry,
+        root: Ptr<Operation>,
+    ) -> Result<usize> {
+        let transforms = self
+            .steps
+            .iter()
+            .map(|step| match registry.get(&step.name) {
+                Some(transform) => Ok(transform),
+                None => input_err!(
+                    step.loc.clone(),
+                    TransformScriptErr::UnknownTransform(step.name.clone())
+                ),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut num_applied = 0;
+        for (step, transform) in self.steps.iter().zip(transforms) {
+            let mut ops = vec![];
+            walk_op(
+                ctx,
+                &mut ops,
+                &WALKCONFIG_PREORDER_FORWARD,
+                root,
+                |_, ops, node| {
+                    if let IRNode::Operation(op) = node {
+                        ops.push(op);
+                    }
+                },
+            );
+            let targets: Vec<_> = ops
+                .into_iter()
+                .filter(|op| step.matches(ctx, root, *op))
+                .collect();
+            for op in targets {
+                if !op.is_live(ctx) {
+                    continue;
+                }
+                transform(ctx, op, &step.params).map_err(|mut err| {
+                    if err.loc == Location::Unknown {
+                        err.loc = step.loc.clone();
+                    }
+                    err
+                })?;
+                num_applied += 1;
+            }
+        }
+        Ok(num_applied)
+    }
+}
+
+/// Characters of a [TransformScript] being parsed, along with their positions.
+struct Cursor<'a> {
+    src: Source,
+    chars: Peekable<Chars<'a>>,
+    pos: SourcePosition,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: Source, text: &'a str) -> Self {
+        Cursor {
+            src,
+            chars: text.chars().peekable(),
+            pos: SourcePosition { line: 1, column: 1 },
+        }
+    }
+
+    fn loc(&self) -> Location {
+        Location::SrcPos {
+            src: self.src,
+            pos: self.pos,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else {
+            self.pos.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Skip whitespace and comments, but not newlines, which end a step.
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                '\n' => break,
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn expected<T>(&mut self, expected: &str) -> Result<T> {
+        let found = match self.peek() {
+            Some('\n') | None => "end of step".to_string(),
+            Some(c) => format!("'{c}'"),
+        };
+        input_err!(
+            self.loc(),
+            TransformScriptErr::Expected {
+                expected: expected.to_string(),
+                found,
+            }
+        )
+    }
+
+    /// A non-empty sequence of alphanumerics, `_`, `-` or `.`.
+    fn word(&mut self, expected: &str) -> Result<String> {
+        let mut word = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            word.push(c);
+            self.bump();
+        }
+        if word.is_empty() {
+            return self.expected(expected);
+        }
+        Ok(word)
+    }
+
+    /// A [word](Self::word) or a double-quoted string (without escapes).
+    fn value(&mut self) -> Result<String> {
+        if self.peek() != Some('"') {
+            return self.word("parameter value");
+        }
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.bump();
+                    return Ok(value);
+                }
+                Some('\n') | None => return self.expected("'\"'"),
+                Some(c) => {
+                    value.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn punct(&mut self, punct: char) -> Result<()> {
+        self.skip_blanks();
+        if self.peek() != Some(punct) {
+            return self.expected(&format!("'{punct}'"));
+        }
+        self.bump();
+        Ok(())
+    }
+
+    fn params(&mut self) -> Result<TransformParams> {
+        let mut params = vec![];
+        self.punct('(')?;
+        self.skip_blanks();
+        if self.peek() == Some(')') {
+            self.bump();
+            return Ok(TransformParams(params));
+        }
+        loop {
+            self.skip_blanks();
+            let key = self.word("parameter name")?;
+            self.punct('=')?;
+            self.skip_blanks();
+            params.push((key, self.value()?));
+            self.skip_blanks();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(')') => {
+                    self.bump();
+                    return Ok(TransformParams(params));
+                }
+                _ => return self.expected("',' or ')'"),
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<TransformStep> {
+        let loc = self.loc();
+        let name = self.word("transform name")?;
+        self.skip_blanks();
+        let params = if self.peek() == Some('(') {
+            self.params()?
+        } else {
+            TransformParams::default()
+        };
+
+        let (mut op_name, mut attr_key) = (None, None);
+        loop {
+            self.skip_blanks();
+            if matches!(self.peek(), None | Some('\n' | ';')) {
+                break;
+            }
+            let clause_loc = self.loc();
+            let clause = self.word("\"on\" or \"with\"")?;
+            self.skip_blanks();
+            match clause.as_str() {
+                "on" => op_name = Some(self.word("operation name")?),
+                "with" => {
+                    let key_loc = self.loc();
+                    let key = self.word("attribute key")?;
+                    attr_key = Some(Identifier::try_from(key.as_str()).map_err(|_| {
+                        input_error!(key_loc, TransformScriptErr::InvalidAttrKey(key.clone()))
+                    })?);
+                }
+                _ => {
+                    return input_err!(clause_loc, TransformScriptErr::UnknownClause(clause));
+                }
+            }
+        }
+
+        Ok(TransformStep {
+            name,
+            params,
+            op_name,
+            attr_key,
+            loc,
+        })
+    }
+}
+
+/// A [Pass] applying a [TransformScript], with transforms from a [TransformRegistry].
+/// Its [statistics](Pass::statistics) count the operations `transformed`.
+pub struct TransformInterpreterPass {
+    script: TransformScript,
+    registry: TransformRegistry,
+    num_transformed: u64,
+}
+
+impl TransformInterpreterPass {
+    /// A pass applying `script`, with transforms from `registry`.
+    pub fn new(script: TransformScript, registry: TransformRegistry) -> Self {
+        TransformInterpreterPass {
+            script,
+            registry,
+            num_transformed: 0,
+        }
+    }
+}
+
+impl Pass for TransformInterpreterPass {
+    fn name(&self) -> &str {
+        "transform-interpreter"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_transformed += self.script.apply(ctx, &self.registry, op)? as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("transformed", self.num_transformed);
+        statistics
+    }
+}
@@ -0,0 +1,475 @@
+//! Interprocedural sparse conditional constant propagation.
+//!
+//! A light-weight version of LLVM's IPSCCP, over the functions defined in a symbol
+//! table. Values are optimistically assumed to be constants, and blocks to not be
+//! executed, until shown otherwise:
+//!   - a block argument is a constant when it's passed the same constant
+//!     along every executable edge into the block.
+//!   - a block is executable when it's the entry block of a function that may be
+//!     called, or a successor, that may be branched to, of an executable block.
+//!     A [ConditionalBranchInterface] terminator whose operands are known
+//!     only branches to the successor it [takes](ConditionalBranchInterface::taken_successor).
+//!   - a [private](SymbolOpInterface::is_private) function whose callers are all known
+//!     (see [CallGraph]) may only be called from its executable call sites.
+//!     An argument of it is a constant when the same constant is passed at all of those,
+//!     and a result of calls to it is a constant when it's the same at every executable return.
+//!     Other functions may be called from outside the table, with any arguments.
+//!
+//! Once this is solved, the values found to be constants are replaced by those constants,
+//! conditional branches with a known successor are replaced by unconditional branches
+//! (see [ConditionalBranchInterface::build_branch_to]), and blocks no longer reachable
+//! from the entry block of their function are erased.
+//!
+//! Constants are [Op](crate::op::Op)s that implement [ConstantLikeInterface], and are
+//! materialized by cloning them. Returns are terminators without successors.
+//! Blocks in regions nested in an operation are executed whenever the block containing
+//! the operation is, and the values they define (other than call results) aren't solved for.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    attribute::AttrObj,
+    basic_block::BasicBlock,
+    builtin::{
+        ATTR_KEY_DEBUG_INFO,
+        op_interfaces::{
+            BranchOpInterface, CallOpInterface, ConditionalBranchInterface, ConstantLikeInterface,
+            IsTerminatorInterface, OneRegionInterface, SymbolOpInterface, SymbolTableInterface,
+            UnreachableInterface,
+        },
+        ops::FuncOp,
+    },
+    context::{Context, Ptr},
+    graph::call_graph::CallGraph,
+    identifier::Identifier,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{op_cast, op_impls},
+    operation::Operation,
+    region::Region,
+    transforms::dce::erase_unreachable_blocks,
+    value::Value,
+};
+
+/// Lattice of (compile time) values that a [Value] may take.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ConstLattice {
+    /// No value seen yet.
+    Undefined,
+    /// A single constant, defined by this [ConstantLikeInterface] [Operation].
+    Constant(Ptr<Operation>),
+    /// Not a constant, or not the same constant everywhere.
+    Overdefined,
+}
+
+impl ConstLattice {
+    /// Meet `self` with `other`.
+    fn meet(self, ctx: &Context, other: ConstLattice) -> ConstLattice {
+        match (self, other) {
+            (ConstLattice::Undefined, other) | (other, ConstLattice::Undefined) => other,
+            (ConstLattice::Constant(cur_op), ConstLattice::Constant(other_op)) => {
+                let (cur_op_obj, other_op_obj) =
+                    (Operation::op(cur_op, ctx), Operation::op(other_op, ctx));
+                let cur_const = op_cast::<dyn ConstantLikeInterface>(&*cur_op_obj)
+                    .expect("Lattice constant must be ConstantLikeInterface");
+                let other_const = op_cast::<dyn ConstantLikeInterface>(&*other_op_obj)
+                    .expect("Lattice constant must be ConstantLikeInterface");
+                if cur_const.constant_value(ctx) == other_const.constant_value(ctx)
+                    && cur_const.result_type(ctx) == other_const.result_type(ctx)
+                {
+                    self
+                } else {
+                    ConstLattice::Overdefined
+                }
+            }
+            _ => ConstLattice::Overdefined,
+        }
+    }
+
+    /// The constant value, if `self` is one.
+    fn constant_value(self, ctx: &Context) -> Option<AttrObj> {
+        let ConstLattice::Constant(const_op) = self else {
+            return None;
+        };
+        let const_op_obj = Operation::op(const_op, ctx);
+        op_cast::<dyn ConstantLikeInterface>(&*const_op_obj)
+            .map(|const_op| const_op.constant_value(ctx))
+    }
+}
+
+/// Create a copy of the constant defining [Operation] `const_op`.
+fn materialize_constant(ctx: &mut Context, const_op: Ptr<Operation>) -> Ptr<Operation> {
+    let (opid, result_ty, mut attributes) = {
+        let const_op = const_op.deref(ctx);
+        (
+            const_op.opid(),
+            const_op.get_type(0),
+            const_op.attributes.clone(),
+        )
+    };
+    // The copy is a different definition, it shouldn't share the name.
+    attributes.0.remove(&*ATTR_KEY_DEBUG_INFO);
+    let new_op = Operation::new(ctx, opid, vec![result_ty], vec![], vec![], 0);
+    new_op.deref_mut(ctx).attributes = attributes;
+    new_op
+}
+
+/// Is `term` a return-like terminator?
+fn is_return(ctx: &Context, term: Ptr<Operation>) -> bool {
+    let term_obj = Operation::op(term, ctx);
+    term.deref(ctx).num_successors() == 0
+        && op_impls::<dyn IsTerminatorInterface>(&*term_obj)
+        && !op_impls::<dyn UnreachableInterface>(&*term_obj)
+}
+
+/// The block, in one of `bodies`, containing `op` (possibly in a nested region).
+fn body_block(
+    ctx: &Context,
+    bodies: &FxHashSet<Ptr<Region>>,
+    mut op: Ptr<Operation>,
+) -> Option<Ptr<BasicBlock>> {
+    loop {
+        let block = op.deref(ctx).container()?;
+        let region = block.deref(ctx).container()?;
+        if bodies.contains(&region) {
+            return Some(block);
+        }
+        op = region.deref(ctx).parent_op();
+    }
+}
+
+/// The state of the propagation.
+struct Solver {
+    /// Functions defined in the symbol table.
+    funcs: Vec<FuncOp>,
+    /// Private functions whose callers are all known, with their call sites.
+    tracked: FxHashMap<Identifier, Vec<Ptr<Operation>>>,
+    /// For each call site of a tracked function, the
+    /// block in the body of a function containing it, if any.
+    call_blocks: FxHashMap<Ptr<Operation>, Option<Ptr<BasicBlock>>>,
+    /// The values solved for. Other values are constants when defined
+    /// by a [ConstantLikeInterface] [Operation], and overdefined otherwise.
+    values: FxHashMap<Value, ConstLattice>,
+    /// Blocks, in function bodies, that may be executed.
+    executable: FxHashSet<Ptr<BasicBlock>>,
+    /// Did the last round change anything?
+    changed: bool,
+}
+
+impl Solver {
+    fn new(ctx: &mut Context, table: &dyn SymbolTableInterface) -> Solver {
+        let cg = CallGraph::new(ctx, table);
+        let funcs: Vec<FuncOp> = table
+            .body(ctx, 0)
+            .deref(ctx)
+            .iter(ctx)
+            .filter_map(|op| Operation::op(op, ctx).downcast_ref::<FuncOp>().copied())
+            .filter(|func| func.region(ctx).deref(ctx).head().is_some())
+            .collect();
+        let bodies: FxHashSet<_> = funcs.iter().map(|func| func.region(ctx)).collect();
+
+        let mut solver = Solver {
+            funcs: funcs.clone(),
+            tracked: FxHashMap::default(),
+            call_blocks: FxHashMap::default(),
+            values: FxHashMap::default(),
+            executable: FxHashSet::default(),
+            changed: false,
+        };
+        for func in funcs {
+            let region = func.region(ctx);
+            let entry = func.get_entry_block(ctx);
+            for block in region.deref(ctx).iter(ctx).filter(|block| *block != entry) {
+                solver.track_args(ctx, block);
+            }
+
+            let sym = func.symbol_name(ctx);
+            if !func.is_private(ctx) || cg.has_non_call_uses(&sym) {
+                continue;
+            }
+            solver.track_args(ctx, entry);
+            let calls = cg.call_sites(&sym).to_vec();
+            for call in &calls {
+                solver
+                    .call_blocks
+                    .insert(*call, body_block(ctx, &bodies, *call));
+                for res in call.deref(ctx).results() {
+                    solver.values.insert(res, ConstLattice::Undefined);
+                }
+            }
+            solver.tracked.insert(sym, calls);
+        }
+        solver
+    }
+
+    /// Solve for the arguments of `block`.
+    fn track_args(&mut self, ctx: &Context, block: Ptr<BasicBlock>) {
+        for arg in block.deref(ctx).arguments() {
+            self.values.insert(arg, ConstLattice::Undefined);
+        }
+    }
+
+    /// The current lattice value of `val`.
+    fn lattice(&self, ctx: &Context, val: Value) -> ConstLattice {
+        if let Some(lattice) = self.values.get(&val) {
+            return *lattice;
+        }
+        match val {
+            Value::OpResult { op, .. }
+                if op_impls::<dyn ConstantLikeInterface>(&*Operation::op(op, ctx)) =>
+            {
+                ConstLattice::Constant(op)
+            }
+            _ => ConstLattice::Overdefined,
+        }
+    }
+
+    /// Lower the lattice value of `val`, if it's solved for, by meeting it with `lattice`.
+    fn lower(&mut self, ctx: &Context, val: Value, lattice: ConstLattice) {
+        let Some(cur) = self.values.get_mut(&val) else {
+            return;
+        };
+        let new = cur.meet(ctx, lattice);
+        if new != *cur {
+            *cur = new;
+            self.changed = true;
+        }
+    }
+
+    /// Mark `block` as executable.
+    fn mark_executable(&mut self, block: Ptr<BasicBlock>) {
+        self.changed |= self.executable.insert(block);
+    }
+
+    /// Is the call site `call` in a block that may be executed?
+    fn is_executable_call(&self, call: Ptr<Operation>) -> bool {
+        self.call_blocks[&call].is_none_or(|block| self.executable.contains(&block))
+    }
+
+    /// The constant values of the operands of `op`, or `None` if one is still undefined.
+    fn operand_constants(&self, ctx: &Context, op: Ptr<Operation>) -> Option<Vec<Option<AttrObj>>> {
+        op.deref(ctx)
+            .operands()
+            .map(|opd| match self.lattice(ctx, opd) {
+                ConstLattice::Undefined => None,
+                lattice => Some(lattice.constant_value(ctx)),
+            })
+            .collect()
+    }
+
+    /// The successors that the terminator `term` may branch to.
+    fn taken_successors(&self, ctx: &Context, term: Ptr<Operation>) -> Vec<usize> {
+        let num_succs = term.deref(ctx).num_successors();
+        let term_obj = Operation::op(term, ctx);
+        let Some(cond_br) = op_cast::<dyn ConditionalBranchInterface>(&*term_obj) else {
+            return (0..num_succs).collect();
+        };
+        match self.operand_constants(ctx, term) {
+            // Wait for all operands to be known.
+            None => vec![],
+            Some(opds) => match cond_br.taken_successor(ctx, &opds) {
+                Some(succ_idx) => vec![succ_idx],
+                None => (0..num_succs).collect(),
+            },
+        }
+    }
+
+    /// Propagate along the edges out of the executable `block`.
+    fn visit_terminator(&mut self, ctx: &Context, block: Ptr<BasicBlock>) {
+        let Some(term) = block.deref(ctx).tail() else {
+            return;
+        };
+        for succ_idx in self.taken_successors(ctx, term) {
+            let succ = term.deref(ctx).successor(succ_idx);
+            self.mark_executable(succ);
+            let term_obj = Operation::op(term, ctx);
+            let forwarded = op_cast::<dyn BranchOpInterface>(&*term_obj)
+                .map(|branch| branch.successor_operands(ctx, succ_idx));
+            let args: Vec<_> = succ.deref(ctx).arguments().collect();
+            for (arg_idx, arg) in args.into_iter().enumerate() {
+                let lattice = forwarded
+                    .as_ref()
+                    .and_then(|forwarded| forwarded.get(arg_idx))
+                    .map_or(ConstLattice::Overdefined, |opd| self.lattice(ctx, *opd));
+                self.lower(ctx, arg, lattice);
+            }
+        }
+    }
+
+    /// The lattice value of the `res_idx`th result of calls to `func`.
+    fn call_result(&self, ctx: &Context, func: FuncOp, res_idx: usize) -> ConstLattice {
+        func.region(ctx)
+            .deref(ctx)
+            .iter(ctx)
+            .filter(|block| self.executable.contains(block))
+            .filter_map(|block| block.deref(ctx).tail())
+            .filter(|term| is_return(ctx, *term))
+            .fold(ConstLattice::Undefined, |lattice, ret| {
+                let opd = ret.deref(ctx).operands().nth(res_idx);
+                let ret_lattice =
+                    opd.map_or(ConstLattice::Overdefined, |opd| self.lattice(ctx, opd));
+                lattice.meet(ctx, ret_lattice)
+            })
+    }
+
+    /// Propagate into the entry block of `func`, and along the edges out of its executable blocks.
+    fn visit_func(&mut self, ctx: &Context, func: FuncOp) {
+        let entry = func.get_entry_block(ctx);
+        let sym = func.symbol_name(ctx);
+        match self.tracked.get(&sym).cloned() {
+            Some(calls) => {
+                let calls: Vec<_> = calls
+                    .into_iter()
+                    .filter(|call| self.is_executable_call(*call))
+                    .collect();
+                if !calls.is_empty() {
+                    self.mark_executable(entry);
+                }
+                let args: Vec<_> = entry.deref(ctx).arguments().collect();
+                for call in calls {
+                    let call_op = Operation::op(call, ctx);
+                    let call_args = op_cast::<dyn CallOpInterface>(&*call_op)
+                        .expect("Call site must be a CallOpInterface Op")
+                        .args(ctx);
+                    for (arg_idx, arg) in args.iter().enumerate() {
+                        let lattice = call_args
+                            .get(arg_idx)
+                            .map_or(ConstLattice::Overdefined, |opd| self.lattice(ctx, *opd));
+                        self.lower(ctx, *arg, lattice);
+                    }
+                }
+            }
+            None => self.mark_executable(entry),
+        }
+
+        let blocks: Vec<_> = func
+            .region(ctx)
+            .deref(ctx)
+            .iter(ctx)
+            .filter(|block| self.executable.contains(block))
+            .collect();
+        for block in blocks {
+            self.visit_terminator(ctx, block);
+        }
+    }
+
+    /// Propagate until nothing changes.
+    fn solve(&mut self, ctx: &Context) {
+        loop {
+            self.changed = false;
+            for func in self.funcs.clone() {
+                self.visit_func(ctx, func);
+            }
+            for func in self.funcs.clone() {
+                let Some(calls) = self.tracked.get(&func.symbol_name(ctx)).cloned() else {
+                    continue;
+                };
+                for call in calls {
+                    let results: Vec<_> = call.deref(ctx).results().collect();
+                    for (res_idx, res) in results.into_iter().enumerate() {
+                        let lattice = self.call_result(ctx, func, res_idx);
+                        self.lower(ctx, res, lattice);
+                    }
+                }
+            }
+            if !self.changed {
+                return;
+            }
+        }
+    }
+}
+
+/// Replace the uses of `val`, if it's used and found to be a constant,
+/// with that constant, materialized by `insert`. Returns whether it was replaced.
+fn replace_with_constant(
+    ctx: &mut Context,
+    solver: &Solver,
+    val: Value,
+    insert: impl FnOnce(&mut Context, Ptr<Operation>),
+) -> bool {
+    let ConstLattice::Constant(const_op) = solver.lattice(ctx, val) else {
+        return false;
+    };
+    if !solver.values.contains_key(&val) || !val.is_used(ctx) {
+        return false;
+    }
+    let new_const = materialize_constant(ctx, const_op);
+    insert(ctx, new_const);
+    let new_val = new_const.deref(ctx).result(0);
+    val.replace_some_uses_with(ctx, |_, _| true, &new_val);
+    true
+}
+
+/// Rewrite the body of `func` with the solution. Returns whether it was changed.
+fn rewrite_func(ctx: &mut Context, solver: &Solver, func: FuncOp) -> bool {
+    let region = func.region(ctx);
+    if !solver.executable.contains(&func.get_entry_block(ctx)) {
+        return false;
+    }
+    let mut changed = false;
+
+    let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+    for block in blocks {
+        if !solver.executable.contains(&block) {
+            continue;
+        }
+        let Some(term) = block.deref(ctx).tail() else {
+            continue;
+        };
+        let Some(opds) = solver.operand_constants(ctx, term) else {
+            continue;
+        };
+        let term_obj = Operation::op(term, ctx);
+        let Some(cond_br) = op_cast::<dyn ConditionalBranchInterface>(&*term_obj) else {
+            continue;
+        };
+        let Some(succ_idx) = cond_br.taken_successor(ctx, &opds) else {
+            continue;
+        };
+        let br = cond_br.build_branch_to(ctx, succ_idx);
+        let loc = term.deref(ctx).loc();
+        br.deref_mut(ctx).set_loc(loc);
+        br.insert_before(ctx, term);
+        Operation::erase(term, ctx);
+        changed = true;
+    }
+
+    changed |= erase_unreachable_blocks(ctx, region) != 0;
+
+    let blocks: Vec<_> = region.deref(ctx).iter(ctx).collect();
+    for block in blocks {
+        let args: Vec<_> = block.deref(ctx).arguments().collect();
+        for arg in args {
+            changed |= replace_with_constant(ctx, solver, arg, |ctx, new_const| {
+                new_const.insert_at_front(block, ctx)
+            });
+        }
+    }
+    changed
+}
+
+/// Propagate constants across the functions defined in `table`.
+/// Returns whether the IR was changed.
+pub fn ipsccp(ctx: &mut Context, table: &dyn SymbolTableInterface) -> bool {
+    let mut solver = Solver::new(ctx, table);
+    solver.solve(ctx);
+
+    let mut changed = false;
+    for func in &solver.funcs {
+        let Some(calls) = solver.tracked.get(&func.symbol_name(ctx)) else {
+            continue;
+        };
+        for call in calls {
+            let results: Vec<_> = call.deref(ctx).results().collect();
+            for res in results {
+                changed |= replace_with_constant(ctx, &solver, res, |ctx, new_const| {
+                    new_const.insert_before(ctx, *call)
+                });
+            }
+        }
+    }
+    for func in &solver.funcs {
+        changed |= rewrite_func(ctx, &solver, *func);
+    }
+    changed
+}
@@ -0,0 +1,207 @@
+//! Loop unrolling.
+//!
+//! [ForLoopInterface] loops whose bounds and step are integer constants
+//! (see [constant_bounds](ForLoopInterface::constant_bounds)) can be
+//!   - [fully unrolled](unroll_full): the loop is replaced by copies of its
+//!     body, one per iteration, with the induction variable being a constant in each.
+//!   - [unrolled by a factor](unroll_by_factor) `f`: the body of the loop is replaced
+//!     by `f` copies of it, and the step is multiplied by `f`. The remaining iterations
+//!     (when the trip count isn't a multiple of `f`) are fully unrolled into an epilogue
+//!     after the loop.
+//!
+//! Values (and blocks) in the copies are remapped from the originals (see [IRMapping]):
+//! the loop carried variables of each copy are the values yielded by the previous one.
+
+use thiserror::Error;
+
+use crate::{
+    arg_err,
+    builtin::op_interfaces::ForLoopInterface,
+    context::{Context, Ptr},
+    ir_mapping::IRMapping,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::op_cast,
+    operation::Operation,
+    result::Result,
+    value::Value,
+};
+
+#[derive(Error, Debug)]
+pub enum LoopUnrollErr {
+    #[error("Loop bounds and step must be integer constants to unroll it")]
+    NonConstantBounds,
+    #[error("Loop step must be positive, but is {0}")]
+    NonPositiveStep(i64),
+    #[error("Unroll factor must be positive")]
+    ZeroFactor,
+}
+
+/// Insert, before `mark`, a copy of `iteration` of the body of `loop_op`,
+/// with the induction variable being `iv` and the loop carried variables being `iter_vals`.
+/// Returns the values yielded by the copy for the next iteration.
+fn clone_iteration(
+    ctx: &mut Context,
+    loop_op: &dyn ForLoopInterface,
+    iteration: &Iteration,
+    iv: Value,
+    iter_vals: Vec<Value>,
+    mark: Ptr<Operation>,
+) -> Vec<Value> {
+    let mut map = IRMapping::new();
+    map.map_value(loop_op.induction_var(ctx), iv);
+    map.map_values(loop_op.iter_args(ctx), iter_vals);
+    for op in &iteration.ops {
+        Operation::clone(*op, ctx, &mut map).insert_before(ctx, mark);
+    }
+    iteration
+        .yielded
+        .iter()
+        .map(|value| map.value(*value))
+        .collect()
+}
+
+/// Insert, before `mark`, the [Operation]s computing `base + offset` (see
+/// [build_iv_value](ForLoopInterface::build_iv_value)), returning the computed value.
+fn insert_iv_value(
+    ctx: &mut Context,
+    loop_op: &dyn ForLoopInterface,
+    base: Option<Value>,
+    offset: i64,
+    mark: Ptr<Operation>,
+) -> Value {
+    let (ops, value) = loop_op.build_iv_value(ctx, base, offset);
+    for op in ops {
+        op.insert_before(ctx, mark);
+    }
+    value
+}
+
+/// Get the constant bounds of `loop_op` and its trip count.
+fn trip_count(ctx: &Context, loop_op: &dyn ForLoopInterface) -> Result<(i64, i64, u64)> {
+    let loc = loop_op.operation().deref(ctx).loc();
+    let Some((lb, ub, step)) = loop_op.constant_bounds(ctx) else {
+        return arg_err!(loc, LoopUnrollErr::NonConstantBounds);
+    };
+    if step <= 0 {
+        return arg_err!(loc, LoopUnrollErr::NonPositiveStep(step));
+    }
+    let (lb_wide, ub_wide, step_wide) = (lb as i128, ub as i128, step as i128);
+    let trip_count = if ub_wide <= lb_wide {
+        0
+    } else {
+        (ub_wide - lb_wide + step_wide - 1) / step_wide
+    };
+    Ok((lb, step, trip_count as u64))
+}
+
+/// An iteration of a loop body, to be copied.
+struct Iteration {
+    /// The [Operation]s in the body, except for the terminator.
+    ops: Vec<Ptr<Operation>>,
+    /// The values yielded for the next iteration.
+    yielded: Vec<Value>,
+}
+
+impl Iteration {
+    /// The current body of `loop_op`.
+    fn new(ctx: &Context, loop_op: &dyn ForLoopInterface) -> Iteration {
+        let yield_op = loop_op.yield_op(ctx);
+        let body = loop_op.body(ctx, 0);
+        Iteration {
+            ops: body
+                .deref(ctx)
+                .iter(ctx)
+                .filter(|op| *op != yield_op)
+                .collect(),
+            yielded: yield_op.deref(ctx).operands().collect(),
+        }
+    }
+}
+
+/// Replace the [ForLoopInterface] loop `loop_op` by copies of its body,
+/// one for each iteration. See [module](self) documentation.
+pub fn unroll_full(ctx: &mut Context, loop_op: Ptr<Operation>) -> Result<()> {
+    let loop_obj = Operation::op(loop_op, ctx);
+    let for_loop = op_cast::<dyn ForLoopInterface>(&*loop_obj)
+        .expect("Expected loop to implement ForLoopInterface");
+    let (lb, step, trip_count) = trip_count(ctx, for_loop)?;
+    let iteration = Iteration::new(ctx, for_loop);
+
+    let mut iter_vals = for_loop.init_args(ctx);
+    for iteration_idx in 0..trip_count {
+        let iv_offset = lb + (iteration_idx as i64) * step;
+        let iv = insert_iv_value(ctx, for_loop, None, iv_offset, loop_op);
+        iter_vals = clone_iteration(ctx, for_loop, &iteration, iv, iter_vals, loop_op);
+    }
+
+    let results: Vec<_> = loop_op.deref(ctx).results().collect();
+    for (result, final_val) in results.into_iter().zip(iter_vals) {
+        result.replace_some_uses_with(ctx, |_, _| true, &final_val);
+    }
+    Operation::erase(loop_op, ctx);
+    Ok(())
+}
+
+/// Unroll the [ForLoopInterface] loop `loop_op` by `factor`.
+/// See [module](self) documentation.
+pub fn unroll_by_factor(ctx: &mut Context, loop_op: Ptr<Operation>, factor: usize) -> Result<()> {
+    let loop_obj = Operation::op(loop_op, ctx);
+    let for_loop = op_cast::<dyn ForLoopInterface>(&*loop_obj)
+        .expect("Expected loop to implement ForLoopInterface");
+    if factor == 0 {
+        return arg_err!(loop_op.deref(ctx).loc(), LoopUnrollErr::ZeroFactor);
+    }
+    let (lb, step, trip_count) = trip_count(ctx, for_loop)?;
+    let factor = factor as u64;
+    if factor == 1 {
+        return Ok(());
+    }
+    if trip_count <= factor {
+        return unroll_full(ctx, loop_op);
+    }
+    let iteration = Iteration::new(ctx, for_loop);
+    let main_iterations = trip_count - trip_count % factor;
+
+    // Adjust the bounds of the loop, to run only the main iterations.
+    let new_ub = lb + (main_iterations as i64) * step;
+    let new_step = step * (factor as i64);
+    let new_ub = insert_iv_value(ctx, for_loop, None, new_ub, loop_op);
+    let new_step = insert_iv_value(ctx, for_loop, None, new_step, loop_op);
+    Operation::replace_operand(loop_op, ctx, 1, new_ub);
+    Operation::replace_operand(loop_op, ctx, 2, new_step);
+
+    // Append more copies of the body, each continuing from the previous one.
+    let yield_op = for_loop.yield_op(ctx);
+    let iv = for_loop.induction_var(ctx);
+    let mut iter_vals = iteration.yielded.clone();
+    for copy_idx in 1..factor {
+        let iv_offset = (copy_idx as i64) * step;
+        let copy_iv = insert_iv_value(ctx, for_loop, Some(iv), iv_offset, yield_op);
+        iter_vals = clone_iteration(ctx, for_loop, &iteration, copy_iv, iter_vals, yield_op);
+    }
+    Operation::set_operands(yield_op, ctx, iter_vals);
+
+    // The remaining iterations are unrolled into an epilogue.
+    if main_iterations == trip_count {
+        return Ok(());
+    }
+    let results: Vec<_> = loop_op.deref(ctx).results().collect();
+    let result_uses: Vec<_> = results.iter().map(|result| result.uses(ctx)).collect();
+    let mark = loop_op
+        .deref(ctx)
+        .next()
+        .expect("Loop must be followed by a terminator");
+    let mut iter_vals = results.clone();
+    for iteration_idx in main_iterations..trip_count {
+        let iv_offset = lb + (iteration_idx as i64) * step;
+        let iv = insert_iv_value(ctx, for_loop, None, iv_offset, mark);
+        iter_vals = clone_iteration(ctx, for_loop, &iteration, iv, iter_vals, mark);
+    }
+    for ((result, uses), final_val) in results.iter().zip(result_uses).zip(iter_vals) {
+        for r#use in uses {
+            result.replace_use_with(ctx, r#use, &final_val);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+//! Transformations on the IR.
+
+pub mod algebraic;
+pub mod canonicalize;
+pub mod const_fold;
+pub mod cse;
+pub mod dce;
+pub mod fold;
+pub mod instrument;
+pub mod interpreter;
+pub mod ipsccp;
+pub mod loop_unroll;
+pub mod outline;
+pub mod promote_constants;
+pub mod reconcile_casts;
+pub mod signature;
+pub mod strip;
+pub mod structured_cfg;
+pub mod tail_call;
@@ -0,0 +1,187 @@
+//! Outline a sequence of operations into a new function.
+//!
+//! [outline_ops] moves consecutive operations of a block into the body of a new
+//! function, and replaces them with a call to it. The function's arguments are the
+//! values that the operations (or the operations nested in them) use, but that are
+//! defined outside them. Its results are the results of the operations that are
+//! used outside them. This is useful, for example, to extract kernels or to share
+//! repeated code.
+//!
+//! Since pliron doesn't define call and return ops, they're built using
+//! the [BuildCallFn] and [BuildReturnFn] provided by the user.
+
+use rustc_hash::FxHashSet;
+use thiserror::Error;
+
+use crate::{
+    arg_err, arg_err_noloc,
+    basic_block::BasicBlock,
+    builtin::{
+        op_interfaces::{IsTerminatorInterface, SymbolTableInterface},
+        ops::FuncOp,
+        types::FunctionType,
+    },
+    context::{Context, Ptr},
+    identifier::Identifier,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{Op, op_cast, op_impls},
+    operation::Operation,
+    result::Result,
+    r#type::Typed,
+    value::Value,
+};
+
+#[derive(Error, Debug)]
+pub enum OutlineErr {
+    #[error("No operations to outline")]
+    Empty,
+    #[error("Operations to outline must be consecutive operations in a block")]
+    NotConsecutive,
+    #[error("Terminators can't be outlined")]
+    Terminator,
+    #[error("Operations to outline must be nested in a symbol table")]
+    NoSymbolTable,
+    #[error("Symbol {0} is already defined")]
+    SymbolExists(String),
+}
+
+/// Build an (unlinked) call to `callee`, passing `args`.
+/// The call must have the results of `callee`.
+pub type BuildCallFn = dyn Fn(&mut Context, FuncOp, Vec<Value>) -> Ptr<Operation>;
+
+/// Build an (unlinked) return of `values` from a function.
+pub type BuildReturnFn = dyn Fn(&mut Context, Vec<Value>) -> Ptr<Operation>;
+
+/// Collect `op` and all the operations and blocks nested in it.
+fn collect_nested(
+    ctx: &Context,
+    op: Ptr<Operation>,
+    ops: &mut Vec<Ptr<Operation>>,
+    blocks: &mut FxHashSet<Ptr<BasicBlock>>,
+) {
+    ops.push(op);
+    for region in op.deref(ctx).regions() {
+        for block in region.deref(ctx).iter(ctx) {
+            blocks.insert(block);
+            for nested in block.deref(ctx).iter(ctx) {
+                collect_nested(ctx, nested, ops, blocks);
+            }
+        }
+    }
+}
+
+/// Outline `ops`, consecutive (non-terminator) operations of a block, into a new
+/// function `name`, replacing them with a call to it. See [module](self) documentation.
+///
+/// The function is defined in the closest symbol table enclosing `ops`, right
+/// after the operation (typically a function) of the table that contains them.
+/// Returns the new function and the call.
+pub fn outline_ops(
+    ctx: &mut Context,
+    ops: &[Ptr<Operation>],
+    name: &Identifier,
+    build_call: &BuildCallFn,
+    build_return: &BuildReturnFn,
+) -> Result<(FuncOp, Ptr<Operation>)> {
+    let Some(first) = ops.first().copied() else {
+        return arg_err_noloc!(OutlineErr::Empty);
+    };
+    let loc = first.deref(ctx).loc();
+    if first.deref(ctx).container().is_none()
+        || ops
+            .windows(2)
+            .any(|pair| pair[0].deref(ctx).next() != Some(pair[1]))
+    {
+        return arg_err!(loc, OutlineErr::NotConsecutive);
+    }
+    if let Some(term) = ops
+        .iter()
+        .find(|op| op_impls::<dyn IsTerminatorInterface>(&*Operation::op(**op, ctx)))
+    {
+        return arg_err!(term.deref(ctx).loc(), OutlineErr::Terminator);
+    }
+
+    // Find the enclosing symbol table, and its operation that contains `ops`.
+    let mut anchor = first;
+    let table = loop {
+        let parent = anchor
+            .deref(ctx)
+            .container()
+            .and_then(|block| block.deref(ctx).container())
+            .map(|region| region.deref(ctx).parent_op());
+        let Some(parent) = parent else {
+            return arg_err!(loc, OutlineErr::NoSymbolTable);
+        };
+        if op_impls::<dyn SymbolTableInterface>(&*Operation::op(parent, ctx)) {
+            break parent;
+        }
+        anchor = parent;
+    };
+    let table_op = Operation::op(table, ctx);
+    let table_intf = op_cast::<dyn SymbolTableInterface>(&*table_op)
+        .expect("Symbol table must implement SymbolTableInterface");
+    if table_intf.lookup(ctx, name).is_some() {
+        return arg_err!(loc, OutlineErr::SymbolExists(name.to_string()));
+    }
+
+    let mut inner_ops = vec![];
+    let mut inner_blocks = FxHashSet::default();
+    for op in ops {
+        collect_nested(ctx, *op, &mut inner_ops, &mut inner_blocks);
+    }
+    let inner_ops_set: FxHashSet<_> = inner_ops.iter().copied().collect();
+    let is_inner = |value: &Value| match value {
+        Value::OpResult { op, .. } => inner_ops_set.contains(op),
+        Value::BlockArgument { block, .. } => inner_blocks.contains(block),
+    };
+
+    // Values defined outside, and used inside, in the order of their first use.
+    let mut inputs = vec![];
+    for op in &inner_ops {
+        for opd in op.deref(ctx).operands() {
+            if !is_inner(&opd) && !inputs.contains(&opd) {
+                inputs.push(opd);
+            }
+        }
+    }
+    // Results used outside, in order.
+    let outputs: Vec<_> = ops
+        .iter()
+        .flat_map(|op| op.deref(ctx).results().collect::<Vec<_>>())
+        .filter(|res| {
+            res.uses(ctx)
+                .any(|r#use| !inner_ops_set.contains(&r#use.op))
+        })
+        .collect();
+
+    let input_types = inputs.iter().map(|input| input.get_type(ctx)).collect();
+    let output_types = outputs.iter().map(|output| output.get_type(ctx)).collect();
+    let func_ty = FunctionType::get(ctx, input_types, output_types);
+    let func = FuncOp::new(ctx, name, func_ty);
+    func.operation().insert_after(ctx, anchor);
+
+    let call = build_call(ctx, func, inputs.clone());
+    call.insert_before(ctx, first);
+    for (idx, output) in outputs.iter().enumerate() {
+        let call_res = call.deref(ctx).result(idx);
+        output.replace_some_uses_with(
+            ctx,
+            |_, r#use| !inner_ops_set.contains(&r#use.op),
+            &call_res,
+        );
+    }
+
+    let entry = func.get_entry_block(ctx);
+    for op in ops {
+        op.unlink(ctx);
+        op.insert_at_back(entry, ctx);
+    }
+    for (idx, input) in inputs.iter().enumerate() {
+        let arg = entry.deref(ctx).argument(idx);
+        input.replace_some_uses_with(ctx, |_, r#use| inner_ops_set.contains(&r#use.op), &arg);
+    }
+    build_return(ctx, outputs).insert_at_back(entry, ctx);
+
+    Ok((func, call))
+}
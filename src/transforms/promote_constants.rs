@@ -0,0 +1,88 @@
+//! Promote large constants into module level globals.
+//!
+//! Every [HoistableConstantInterface] [Op](crate::op::Op) nested (at any depth) in the
+//! operations of a symbol table, whose value is at least a given size,
+//! is replaced by a load of a new global, defined at the beginning of the
+//! symbol table. Equal constants (same value and type) share a global.
+//! Constants defined directly in the symbol table are left alone.
+//!
+//! This reduces the stack usage of generated code, since large constant
+//! aggregates needn't be materialized on the stack of every function using them.
+
+use crate::{
+    attribute::AttrObj,
+    basic_block::BasicBlock,
+    builtin::op_interfaces::{HoistableConstantInterface, SymbolTableInterface},
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+    identifier::Identifier,
+    linked_list::LinkedList,
+    op::op_cast,
+    operation::Operation,
+    r#type::TypeObj,
+};
+
+/// Promote [HoistableConstantInterface] constants of at least `min_size` bytes, used in
+/// the operations of `table`, into globals defined in `table`.
+/// See [module](self) documentation. Returns the number of constants promoted.
+pub fn promote_constants(
+    ctx: &mut Context,
+    table: &dyn SymbolTableInterface,
+    min_size: usize,
+) -> usize {
+    let table_block = table.body(ctx, 0);
+    let mut constants = vec![];
+    let _ = BasicBlock::walk(
+        table_block,
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut |ctx: &mut Context, node| {
+            if let IRNode::Operation(op) = node {
+                let is_large_constant = op.deref(ctx).container() != Some(table_block)
+                    && op_cast::<dyn HoistableConstantInterface>(&*Operation::op(op, ctx))
+                        .is_some_and(|constant| constant.constant_size(ctx) >= min_size);
+                if is_large_constant {
+                    constants.push(op);
+                }
+            }
+            walk_advance::<()>()
+        },
+    );
+
+    // Globals created so far, identified by the value and type they hold.
+    let mut globals: Vec<(AttrObj, Ptr<TypeObj>, Identifier)> = vec![];
+    let mut last_global: Option<Ptr<Operation>> = None;
+    for const_op in &constants {
+        let const_op_obj = Operation::op(*const_op, ctx);
+        let constant = op_cast::<dyn HoistableConstantInterface>(&*const_op_obj)
+            .expect("Collected constants must be hoistable");
+        let value = constant.constant_value(ctx);
+        let ty = constant.result_type(ctx);
+
+        let existing = globals
+            .iter()
+            .find(|(g_value, g_ty, _)| *g_ty == ty && g_value == &value)
+            .map(|(_, _, name)| *name);
+        let name = existing.unwrap_or_else(|| {
+            let name = ctx.fresh_symbol(table, "promoted_const_");
+            let global = constant.build_global(ctx, &name);
+            match last_global {
+                Some(last_global) => global.insert_after(ctx, last_global),
+                None => global.insert_at_front(table_block, ctx),
+            }
+            last_global = Some(global);
+            globals.push((value, ty, name));
+            name
+        });
+
+        let (load_ops, loaded) = constant.build_global_load(ctx, &name);
+        for load_op in load_ops {
+            load_op.insert_before(ctx, *const_op);
+        }
+        constant
+            .result(ctx)
+            .replace_some_uses_with(ctx, |_, _| true, &loaded);
+        Operation::erase(*const_op, ctx);
+    }
+    constants.len()
+}
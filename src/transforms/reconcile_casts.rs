@@ -0,0 +1,154 @@
+//! Reconcile the [UnrealizedConversionCastOp]s left behind by [conversions](crate::conversion).
+//!
+//! A partial conversion leaves casts where converted and unconverted values meet.
+//! Once the unconverted users are converted too (possibly by later conversions),
+//! the casts form chains, such as `A -> B -> A`, which just cast a value back to
+//! its own type. [reconcile_unrealized_casts] replaces the uses of each such chain
+//! with the value it starts from, and erases the casts left unused.
+//!
+//! Casts that are still used after that have no meaning, and can't be lowered
+//! any further, so the first of them (in pre-order) is reported as an error.
+
+use thiserror::Error;
+
+use crate::{
+    builtin::{
+        op_interfaces::{OneOpdInterface, OneResultInterface},
+        ops::UnrealizedConversionCastOp,
+    },
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, interruptible::walk_advance},
+    input_err,
+    location::Located,
+    op::Op,
+    operation::Operation,
+    pass::{Pass, PassStatistics},
+    printable::Printable,
+    result::Result,
+    r#type::Typed,
+    value::Value,
+};
+
+#[derive(Error, Debug)]
+pub enum ReconcileCastsErr {
+    #[error("Cast from {from} to {to} remains after conversion ({others} other casts remain)")]
+    UnresolvedCast {
+        from: String,
+        to: String,
+        others: usize,
+    },
+}
+
+/// Get the [UnrealizedConversionCastOp] defining `value`, if any.
+fn defining_cast(ctx: &Context, value: Value) -> Option<UnrealizedConversionCastOp> {
+    let Value::OpResult { op, .. } = value else {
+        return None;
+    };
+    Operation::op(op, ctx)
+        .downcast_ref::<UnrealizedConversionCastOp>()
+        .copied()
+}
+
+/// Collect the [UnrealizedConversionCastOp]s nested in `root`, in pre-order.
+fn collect_casts(ctx: &mut Context, root: Ptr<Operation>) -> Vec<UnrealizedConversionCastOp> {
+    let mut casts = vec![];
+    let _ = Operation::walk(
+        root,
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut |ctx: &mut Context, node| {
+            if let IRNode::Operation(op) = node
+                && let Some(cast) =
+                    Operation::op(op, ctx).downcast_ref::<UnrealizedConversionCastOp>()
+            {
+                casts.push(*cast);
+            }
+            walk_advance::<()>()
+        },
+    );
+    casts
+}
+
+/// Replace the uses of chains of [UnrealizedConversionCastOp]s nested in `root`,
+/// that cast a value back to its own type, with that value, and erase the unused casts.
+/// See [module](self) documentation. Returns the number of casts erased.
+pub fn reconcile_unrealized_casts(ctx: &mut Context, root: Ptr<Operation>) -> Result<usize> {
+    let casts = collect_casts(ctx, root);
+    for cast in &casts {
+        let res = cast.result(ctx);
+        let res_ty = res.get_type(ctx);
+        // Walk up the chain of casts, looking for a value of the cast's type.
+        let mut input = cast.operand(ctx);
+        loop {
+            if input.get_type(ctx) == res_ty {
+                res.replace_some_uses_with(ctx, |_, _| true, &input);
+                break;
+            }
+            let Some(input_cast) = defining_cast(ctx, input) else {
+                break;
+            };
+            input = input_cast.operand(ctx);
+        }
+    }
+
+    // Users come after their definitions, except across back edges,
+    // so erase in reverse, until there's nothing more to erase.
+    let mut num_erased = 0;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for cast in casts.iter().rev() {
+            let op = cast.operation();
+            if op.is_live(ctx) && !cast.result(ctx).is_used(ctx) {
+                Operation::erase(op, ctx);
+                num_erased += 1;
+                changed = true;
+            }
+        }
+    }
+
+    let mut remaining = casts.iter().filter(|cast| cast.operation().is_live(ctx));
+    if let Some(cast) = remaining.next() {
+        let from = cast.operand_type(ctx);
+        let to = cast.result_type(ctx);
+        return input_err!(
+            cast.operation().deref(ctx).loc(),
+            ReconcileCastsErr::UnresolvedCast {
+                from: from.disp(ctx).to_string(),
+                to: to.disp(ctx).to_string(),
+                others: remaining.count(),
+            }
+        );
+    }
+    Ok(num_erased)
+}
+
+/// A [Pass] running [reconcile_unrealized_casts].
+/// Its [statistics](Pass::statistics) count the `casts` erased.
+#[derive(Default)]
+pub struct ReconcileCastsPass {
+    num_erased: u64,
+}
+
+impl ReconcileCastsPass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Pass for ReconcileCastsPass {
+    fn name(&self) -> &str {
+        "reconcile-unrealized-casts"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        self.num_erased += reconcile_unrealized_casts(ctx, op)? as u64;
+        Ok(())
+    }
+
+    fn statistics(&self) -> PassStatistics {
+        let mut statistics = PassStatistics::default();
+        statistics.add("casts", self.num_erased);
+        statistics
+    }
+}
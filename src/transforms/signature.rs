@@ -0,0 +1,244 @@
+//! Rewrite the signature of a function, updating all its call sites.
+//!
+//! Function arguments can be dropped, reordered or appended by
+//! specifying, for each argument of the new signature, whether it
+//! is an [existing](NewArg::Existing) argument or a [new](NewArg::New) one.
+//!
+//! Call sites are found in the [CallGraph] of the function's parent symbol table,
+//! which includes calls from symbol tables nested in it. Every use of the function
+//! must be a direct call, via [CallOpInterface], so that the signature change is safe.
+
+use thiserror::Error;
+
+use crate::{
+    arg_err,
+    basic_block::BasicBlock,
+    builtin::{
+        attributes::TypeAttr,
+        op_interfaces::{
+            ATTR_KEY_CALLEE_TYPE, CallOpInterface, OneRegionInterface, SymbolOpInterface,
+            SymbolTableInterface,
+        },
+        ops::{FuncOp, func_op},
+        types::FunctionType,
+    },
+    context::{Context, Ptr},
+    debug_info::{block_arg_name, set_block_arg_name},
+    graph::call_graph::CallGraph,
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{Op, op_cast},
+    operation::Operation,
+    result::Result,
+    r#type::{TypeObj, TypePtr},
+    value::Value,
+};
+
+/// Source of an argument in a rewritten function signature.
+#[derive(Clone, Copy)]
+pub enum NewArg {
+    /// The argument at this index in the current signature.
+    Existing(usize),
+    /// A new argument of the given type.
+    New(Ptr<TypeObj>),
+}
+
+#[derive(Error, Debug)]
+pub enum SignatureRewriteErr {
+    #[error("Argument index {0} out of bounds, function has {1} arguments")]
+    ArgOutOfBounds(usize, usize),
+    #[error("Argument {0} is dropped, but has uses in the function body")]
+    DroppedArgInUse(usize),
+    #[error("Function {0} is used by an operation that isn't a direct call to it")]
+    NonCallUse(String),
+    #[error("Call to function {0} passes {1} arguments, but the function takes {2} arguments")]
+    CallArgsMismatch(String, usize, usize),
+}
+
+/// Get the [Operation] that (immediately) contains `op`, if any.
+fn parent_op(ctx: &Context, op: Ptr<Operation>) -> Option<Ptr<Operation>> {
+    let block = op.deref(ctx).container()?;
+    let region = block.deref(ctx).container()?;
+    Some(region.deref(ctx).parent_op())
+}
+
+/// Get all calls to `func` in its parent symbol table (if any),
+/// including those nested in other symbol tables.
+/// Fails if `func` has a use that isn't a direct call.
+fn call_sites(ctx: &mut Context, func: FuncOp) -> Result<Vec<Ptr<Operation>>> {
+    let Some(table) = parent_op(ctx, func.operation()) else {
+        return Ok(vec![]);
+    };
+    let table = Operation::op(table, ctx);
+    let Some(table) = op_cast::<dyn SymbolTableInterface>(&*table) else {
+        return Ok(vec![]);
+    };
+
+    let sym = func.symbol_name(ctx);
+    let cg = CallGraph::new(ctx, table);
+    if let Some(user) = cg.non_call_uses(&sym).first() {
+        return arg_err!(
+            user.deref(ctx).loc(),
+            SignatureRewriteErr::NonCallUse(sym.to_string())
+        );
+    }
+    Ok(cg.call_sites(&sym).to_vec())
+}
+
+/// Rewrite the arguments of `func` to be `new_args`, updating all call sites.
+///
+/// Existing arguments not mentioned in `new_args` are dropped,
+/// and must not have any uses. At each call site, the arguments passed
+/// are rearranged accordingly. The value passed to a [new](NewArg::New)
+/// argument is obtained by calling `new_arg_value` with the call operation
+/// and the index of the argument in the new signature. It may insert new
+/// operations (for example, constants) before the call.
+///
+/// All checks are performed before the IR is modified, so on failure,
+/// the IR is left unchanged.
+pub fn rewrite_func_args(
+    ctx: &mut Context,
+    func: FuncOp,
+    new_args: &[NewArg],
+    mut new_arg_value: impl FnMut(&mut Context, Ptr<Operation>, usize) -> Value,
+) -> Result<()> {
+    let func_ty = TypePtr::<FunctionType>::downcast(ctx, func.get_type(ctx))?;
+    let (old_inputs, results) = {
+        let func_ty = func_ty.deref(ctx);
+        (func_ty.inputs().clone(), func_ty.results().clone())
+    };
+    let entry = func.get_entry_block(ctx);
+    let loc = func.loc(ctx);
+
+    // Validate the new signature.
+    let mut kept = vec![false; old_inputs.len()];
+    for new_arg in new_args {
+        if let NewArg::Existing(idx) = *new_arg {
+            if idx >= old_inputs.len() {
+                return arg_err!(
+                    loc,
+                    SignatureRewriteErr::ArgOutOfBounds(idx, old_inputs.len())
+                );
+            }
+            kept[idx] = true;
+        }
+    }
+    for (idx, _) in kept.iter().enumerate().filter(|(_, kept)| !**kept) {
+        if entry.deref(ctx).argument(idx).is_used(ctx) {
+            return arg_err!(loc, SignatureRewriteErr::DroppedArgInUse(idx));
+        }
+    }
+
+    // Validate the call sites.
+    let calls = call_sites(ctx, func)?;
+    for call in &calls {
+        let call_op = Operation::op(*call, ctx);
+        let num_args = op_cast::<dyn CallOpInterface>(&*call_op)
+            .expect("Call site must be a CallOpInterface Op")
+            .args(ctx)
+            .len();
+        if num_args != old_inputs.len() {
+            return arg_err!(
+                call.deref(ctx).loc(),
+                SignatureRewriteErr::CallArgsMismatch(
+                    func.symbol_name(ctx).to_string(),
+                    num_args,
+                    old_inputs.len()
+                )
+            );
+        }
+    }
+
+    // Update the function type.
+    let new_inputs: Vec<_> = new_args
+        .iter()
+        .map(|new_arg| match *new_arg {
+            NewArg::Existing(idx) => old_inputs[idx],
+            NewArg::New(ty) => ty,
+        })
+        .collect();
+    let new_func_ty = FunctionType::get(ctx, new_inputs.clone(), results);
+    func.operation().deref_mut(ctx).attributes.set(
+        *func_op::ATTR_KEY_FUNC_TYPE,
+        TypeAttr::new(new_func_ty.into()),
+    );
+
+    // Replace the entry block with one that has the new arguments.
+    let label = entry.deref(ctx).label;
+    let new_entry = BasicBlock::new(ctx, label, new_inputs);
+    for (new_idx, new_arg) in new_args.iter().enumerate() {
+        if let NewArg::Existing(old_idx) = *new_arg {
+            let (old_val, new_val) = (
+                entry.deref(ctx).argument(old_idx),
+                new_entry.deref(ctx).argument(new_idx),
+            );
+            old_val.replace_some_uses_with(ctx, |_, _| true, &new_val);
+            if let Some(name) = block_arg_name(ctx, entry, old_idx) {
+                set_block_arg_name(ctx, new_entry, new_idx, name);
+            }
+        }
+    }
+    let ops = entry.deref(ctx).ops_snapshot(ctx);
+    for op in ops {
+        op.unlink(ctx);
+        op.insert_at_back(new_entry, ctx);
+    }
+    new_entry.insert_before(ctx, entry);
+    new_entry.deref_mut(ctx).set_loc(entry.deref(ctx).loc());
+    BasicBlock::erase(entry, ctx);
+    debug_assert!(func.region(ctx).deref(ctx).head() == Some(new_entry));
+
+    // Update the call sites.
+    for call in calls {
+        let call_op = Operation::op(call, ctx);
+        let call_op = op_cast::<dyn CallOpInterface>(&*call_op)
+            .expect("Call site must be a CallOpInterface Op");
+        let old_call_args = call_op.args(ctx);
+        let mut new_call_args = Vec::with_capacity(new_args.len());
+        for (new_idx, new_arg) in new_args.iter().enumerate() {
+            new_call_args.push(match *new_arg {
+                NewArg::Existing(old_idx) => old_call_args[old_idx],
+                NewArg::New(_) => new_arg_value(ctx, call, new_idx),
+            });
+        }
+        call_op.set_args(ctx, new_call_args);
+        call.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_CALLEE_TYPE, TypeAttr::new(new_func_ty.into()));
+    }
+
+    Ok(())
+}
+
+/// Drop all arguments of `func` that have no uses in its body,
+/// updating all call sites. Returns the indices (in the original signature)
+/// of the dropped arguments.
+pub fn drop_unused_args(ctx: &mut Context, func: FuncOp) -> Result<Vec<usize>> {
+    let entry = func.get_entry_block(ctx);
+    let (used, unused): (Vec<_>, Vec<_>) = (0..entry.deref(ctx).num_arguments())
+        .partition(|idx| entry.deref(ctx).argument(*idx).is_used(ctx));
+    if unused.is_empty() {
+        return Ok(unused);
+    }
+    let new_args: Vec<_> = used.into_iter().map(NewArg::Existing).collect();
+    rewrite_func_args(ctx, func, &new_args, |_, _, _| {
+        unreachable!("No new arguments are being added")
+    })?;
+    Ok(unused)
+}
+
+/// Append arguments of types `arg_types` to the arguments of `func`,
+/// updating all call sites. See [rewrite_func_args] for `new_arg_value`.
+pub fn append_args(
+    ctx: &mut Context,
+    func: FuncOp,
+    arg_types: &[Ptr<TypeObj>],
+    new_arg_value: impl FnMut(&mut Context, Ptr<Operation>, usize) -> Value,
+) -> Result<()> {
+    let num_args = func.get_entry_block(ctx).deref(ctx).num_arguments();
+    let new_args: Vec<_> = (0..num_args)
+        .map(NewArg::Existing)
+        .chain(arg_types.iter().map(|ty| NewArg::New(*ty)))
+        .collect();
+    rewrite_func_args(ctx, func, &new_args, new_arg_value)
+}
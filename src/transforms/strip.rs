@@ -0,0 +1,115 @@
+//! Strip metadata from the IR.
+//!
+//! - [strip_locations] resets the [Location]s of operations and blocks to [Location::Unknown].
+//! - [strip_debug_info] removes debug info, such as the names of values.
+//! - [strip_attributes] removes other (discardable) attributes, given their keys.
+//!
+//! This is useful to produce minimized test cases, and
+//! the returned [StripStats] help in measuring the overhead of metadata.
+
+use std::ops::AddAssign;
+
+use crate::{
+    attribute::AttributeDict,
+    builtin::ATTR_KEY_DEBUG_INFO,
+    context::{Context, Ptr},
+    graph::walkers::{IRNode, WALKCONFIG_PREORDER_FORWARD, walk_op},
+    identifier::Identifier,
+    location::{Located, Location},
+    operation::Operation,
+    printable::Printable,
+};
+
+/// Statistics of what was stripped.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StripStats {
+    /// Number of locations or attributes removed.
+    pub num_removed: usize,
+    /// Total size, in bytes, of the printed form of the removed entities.
+    pub bytes_removed: usize,
+}
+
+impl StripStats {
+    fn record(&mut self, ctx: &Context, removed: &dyn Printable) {
+        self.num_removed += 1;
+        self.bytes_removed += removed.disp(ctx).to_string().len();
+    }
+}
+
+impl AddAssign for StripStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.num_removed += rhs.num_removed;
+        self.bytes_removed += rhs.bytes_removed;
+    }
+}
+
+/// Set the [Location] of `root` and every operation and block nested in it
+/// to [Location::Unknown].
+pub fn strip_locations(ctx: &mut Context, root: Ptr<Operation>) -> StripStats {
+    fn strip(ctx: &Context, entity: &mut dyn Located, stats: &mut StripStats) {
+        let loc = entity.loc();
+        if loc != Location::Unknown {
+            stats.record(ctx, &loc);
+            entity.set_loc(Location::Unknown);
+        }
+    }
+
+    let mut stats = StripStats::default();
+    walk_op(
+        ctx,
+        &mut stats,
+        &WALKCONFIG_PREORDER_FORWARD,
+        root,
+        |ctx, stats, node| match node {
+            IRNode::Operation(op) => strip(ctx, &mut *op.deref_mut(ctx), stats),
+            IRNode::BasicBlock(block) => strip(ctx, &mut *block.deref_mut(ctx), stats),
+            IRNode::Region(_) => (),
+        },
+    );
+    stats
+}
+
+/// Remove attributes with any of the keys in `keys` from `root`
+/// and every operation and block nested in it.
+/// The attributes must be discardable, i.e., the IR must remain valid without them.
+pub fn strip_attributes(
+    ctx: &mut Context,
+    root: Ptr<Operation>,
+    keys: &[Identifier],
+) -> StripStats {
+    struct State<'a> {
+        keys: &'a [Identifier],
+        stats: StripStats,
+    }
+
+    fn strip(ctx: &Context, attributes: &mut AttributeDict, state: &mut State) {
+        for key in state.keys {
+            if let Some(attr) = attributes.0.remove(key) {
+                state.stats.record(ctx, &attr);
+            }
+        }
+    }
+
+    let mut state = State {
+        keys,
+        stats: StripStats::default(),
+    };
+    walk_op(
+        ctx,
+        &mut state,
+        &WALKCONFIG_PREORDER_FORWARD,
+        root,
+        |ctx, state, node| match node {
+            IRNode::Operation(op) => strip(ctx, &mut op.deref_mut(ctx).attributes, state),
+            IRNode::BasicBlock(block) => strip(ctx, &mut block.deref_mut(ctx).attributes, state),
+            IRNode::Region(_) => (),
+        },
+    );
+    state.stats
+}
+
+/// Remove debug info (such as names of values) from `root`
+/// and every operation and block nested in it.
+pub fn strip_debug_info(ctx: &mut Context, root: Ptr<Operation>) -> StripStats {
+    strip_attributes(ctx, root, std::slice::from_ref(&ATTR_KEY_DEBUG_INFO))
+}
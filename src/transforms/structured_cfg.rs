@@ -0,0 +1,362 @@
+//! Conversion between structured control flow and control flow graphs.
+//!
+//! Structured control flow [Op]s (such as `if` and `for` ops, with regions for
+//! their bodies) are lowered into [BasicBlock]s and branches by [lower_to_cfg],
+//! using the [LowerToCfgInterface] implementation of each [Op]. The helpers
+//! [split_block] and [inline_region_before] do most of the work of such implementations.
+//!
+//! In the other direction, [lift_structured] analyzes the control flow graph of a
+//! region, reconstructing the `if-then-else` and `while` structures it is made of.
+//! Targets that require structured control flow (such as WebAssembly) can emit code
+//! by walking the resulting [Structured] tree. Only reducible control flow graphs,
+//! built out of such structures, can be lifted:
+//!   - Every loop must be entered and exited only through its header,
+//!     which must branch to the loop body and to the loop exit.
+//!   - Every other block must have at most two successors,
+//!     and the two paths from a block with two successors must join
+//!     (unless one of them returns) before control reaches any other block.
+
+use pliron::derive::op_interface;
+use rustc_hash::{FxHashMap, FxHashSet};
+use thiserror::Error;
+
+use crate::{
+    arg_err,
+    basic_block::BasicBlock,
+    common_traits::Named,
+    context::{Context, Ptr},
+    linked_list::{ContainsLinkedList, LinkedList},
+    location::Located,
+    op::{Op, op_cast},
+    operation::Operation,
+    region::Region,
+    result::Result,
+};
+
+/// A structured control flow [Op] that can be lowered into [BasicBlock]s and branches.
+#[op_interface]
+pub trait LowerToCfgInterface {
+    /// Replace this [Op] with [BasicBlock]s (and branches between them),
+    /// in the region containing this [Op].
+    fn lower_to_cfg(&self, ctx: &mut Context) -> Result<()>;
+
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Move `op`, and all [Operation]s following it in its block, into a new
+/// [BasicBlock] (with no arguments), inserted after the block containing `op`.
+pub fn split_block(ctx: &mut Context, op: Ptr<Operation>) -> Ptr<BasicBlock> {
+    let block = op
+        .deref(ctx)
+        .container()
+        .expect("Operation to split the block at must be in a block");
+    let new_block = BasicBlock::new(ctx, None, vec![]);
+    new_block.insert_after(ctx, block);
+    let mut next = Some(op);
+    while let Some(op) = next {
+        next = op.deref(ctx).next();
+        op.unlink(ctx);
+        op.insert_at_back(new_block, ctx);
+    }
+    new_block
+}
+
+/// Move all the [BasicBlock]s of `region`, in order, before `dest` (in the region containing it).
+/// Returns the moved blocks.
+pub fn inline_region_before(
+    ctx: &mut Context,
+    region: Ptr<Region>,
+    dest: Ptr<BasicBlock>,
+) -> Vec<Ptr<BasicBlock>> {
+    let blocks = region.deref(ctx).blocks_snapshot(ctx);
+    for block in &blocks {
+        block.unlink(ctx);
+        block.insert_before(ctx, dest);
+    }
+    blocks
+}
+
+/// Collect, in post-order, the [LowerToCfgInterface] [Operation]s nested in `op`.
+fn collect_structured(ctx: &Context, op: Ptr<Operation>, ops: &mut Vec<Ptr<Operation>>) {
+    for region in op.deref(ctx).regions() {
+        for block in region.deref(ctx).iter(ctx) {
+            for inner_op in block.deref(ctx).iter(ctx) {
+                collect_structured(ctx, inner_op, ops);
+                if op_cast::<dyn LowerToCfgInterface>(&*Operation::op(inner_op, ctx)).is_some() {
+                    ops.push(inner_op);
+                }
+            }
+        }
+    }
+}
+
+/// Lower all [LowerToCfgInterface] [Op]s nested in `root`, innermost first.
+/// Returns the number of [Op]s lowered.
+pub fn lower_to_cfg(ctx: &mut Context, root: Ptr<Operation>) -> Result<usize> {
+    let mut ops = vec![];
+    collect_structured(ctx, root, &mut ops);
+    for op in &ops {
+        let op_obj = Operation::op(*op, ctx);
+        op_cast::<dyn LowerToCfgInterface>(&*op_obj)
+            .expect("Collected op must implement LowerToCfgInterface")
+            .lower_to_cfg(ctx)?;
+    }
+    Ok(ops.len())
+}
+
+/// Structured control flow, lifted from a control flow graph by [lift_structured].
+#[derive(Clone, PartialEq, Eq)]
+pub enum Structured {
+    /// A block, after which control flows to whatever follows in the enclosing sequence.
+    /// If nothing follows, the block's terminator returns or, in a loop body,
+    /// branches back to the loop header.
+    Block(Ptr<BasicBlock>),
+    /// `cond` is executed, after which its terminator branches to either `then` or `else_`.
+    If {
+        cond: Ptr<BasicBlock>,
+        then: Vec<Structured>,
+        else_: Vec<Structured>,
+    },
+    /// `header` is executed, after which its terminator either exits
+    /// the loop, or branches to `body`, which is followed by `header` again.
+    While {
+        header: Ptr<BasicBlock>,
+        body: Vec<Structured>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum StructuredCfgErr {
+    #[error("Control flow graph is irreducible: Loop with header {0} has multiple entries")]
+    Irreducible(String),
+    #[error("Loop with header {0} is exited from a block other than its header")]
+    LoopExit(String),
+    #[error("Loop header {0} must branch to one block in the loop and one block outside it")]
+    LoopHeader(String),
+    #[error("Block {0} has more than two successors")]
+    TooManySuccessors(String),
+    #[error("Control flow through block {0} doesn't have an if-then-else or while structure")]
+    Unstructured(String),
+}
+
+/// Loops and back edges of a control flow graph.
+struct CfgLoops {
+    /// Edges to blocks that are being visited, in a depth-first search from the entry.
+    back_edges: FxHashSet<(Ptr<BasicBlock>, Ptr<BasicBlock>)>,
+    /// Blocks in each (natural) loop, keyed by the loop header.
+    loops: FxHashMap<Ptr<BasicBlock>, FxHashSet<Ptr<BasicBlock>>>,
+}
+
+impl CfgLoops {
+    fn new(ctx: &Context, entry: Ptr<BasicBlock>) -> Result<CfgLoops> {
+        fn find_back_edges(
+            ctx: &Context,
+            block: Ptr<BasicBlock>,
+            visited: &mut FxHashSet<Ptr<BasicBlock>>,
+            on_stack: &mut FxHashSet<Ptr<BasicBlock>>,
+            back_edges: &mut FxHashSet<(Ptr<BasicBlock>, Ptr<BasicBlock>)>,
+        ) {
+            visited.insert(block);
+            on_stack.insert(block);
+            for succ in block.deref(ctx).succs(ctx) {
+                if on_stack.contains(&succ) {
+                    back_edges.insert((block, succ));
+                } else if !visited.contains(&succ) {
+                    find_back_edges(ctx, succ, visited, on_stack, back_edges);
+                }
+            }
+            on_stack.remove(&block);
+        }
+
+        let mut back_edges = FxHashSet::default();
+        let mut visited = FxHashSet::default();
+        find_back_edges(
+            ctx,
+            entry,
+            &mut visited,
+            &mut FxHashSet::default(),
+            &mut back_edges,
+        );
+
+        // A natural loop has the blocks from which the source of a back edge
+        // can be reached, without going through the header.
+        let mut loops = FxHashMap::<_, FxHashSet<_>>::default();
+        for (latch, header) in &back_edges {
+            let loop_blocks = loops.entry(*header).or_default();
+            loop_blocks.insert(*header);
+            let mut worklist = vec![*latch];
+            while let Some(block) = worklist.pop() {
+                if loop_blocks.insert(block) {
+                    let preds = block.preds(ctx).into_iter();
+                    worklist.extend(preds.filter(|pred| visited.contains(pred)));
+                }
+            }
+        }
+
+        for (header, loop_blocks) in &loops {
+            let header_name = || header.deref(ctx).unique_name(ctx).to_string();
+            // If the header doesn't dominate the loop, the loop
+            // can be entered (from the entry) without going through it.
+            if *header != entry && loop_blocks.contains(&entry) {
+                return arg_err!(
+                    header.deref(ctx).loc(),
+                    StructuredCfgErr::Irreducible(header_name())
+                );
+            }
+            for block in loop_blocks {
+                let exits = block
+                    .deref(ctx)
+                    .succs(ctx)
+                    .into_iter()
+                    .any(|succ| !loop_blocks.contains(&succ));
+                if block != header && exits {
+                    return arg_err!(
+                        block.deref(ctx).loc(),
+                        StructuredCfgErr::LoopExit(header_name())
+                    );
+                }
+            }
+        }
+
+        Ok(CfgLoops { back_edges, loops })
+    }
+
+    /// Blocks reachable from `block` (including itself), without following back edges.
+    fn forward_reach(&self, ctx: &Context, block: Ptr<BasicBlock>) -> FxHashSet<Ptr<BasicBlock>> {
+        let mut reach = FxHashSet::default();
+        let mut worklist = vec![block];
+        while let Some(block) = worklist.pop() {
+            if !reach.insert(block) {
+                continue;
+            }
+            for succ in block.deref(ctx).succs(ctx) {
+                if !self.back_edges.contains(&(block, succ)) {
+                    worklist.push(succ);
+                }
+            }
+        }
+        reach
+    }
+
+    /// The first block at which control flowing from `cond`
+    /// through `then` and `else_` joins, if it does.
+    fn join(
+        &self,
+        ctx: &Context,
+        cond: Ptr<BasicBlock>,
+        then: Ptr<BasicBlock>,
+        else_: Ptr<BasicBlock>,
+    ) -> Result<Option<Ptr<BasicBlock>>> {
+        let then_reach = self.forward_reach(ctx, then);
+        let else_reach = self.forward_reach(ctx, else_);
+        let common: Vec<_> = then_reach.intersection(&else_reach).copied().collect();
+        if common.is_empty() {
+            return Ok(None);
+        }
+        // The join can reach all other blocks that both paths reach.
+        let join = common.iter().copied().find(|candidate| {
+            let candidate_reach = self.forward_reach(ctx, *candidate);
+            common.iter().all(|block| candidate_reach.contains(block))
+        });
+        match join {
+            Some(join) => Ok(Some(join)),
+            None => arg_err!(
+                cond.deref(ctx).loc(),
+                StructuredCfgErr::Unstructured(cond.deref(ctx).unique_name(ctx).to_string())
+            ),
+        }
+    }
+
+    /// Lift the sequence of structures starting at `entry`, until `stop` is reached.
+    fn lift_seq(
+        &self,
+        ctx: &Context,
+        entry: Ptr<BasicBlock>,
+        stop: Option<Ptr<BasicBlock>>,
+        lifted: &mut FxHashSet<Ptr<BasicBlock>>,
+    ) -> Result<Vec<Structured>> {
+        let mut seq = vec![];
+        let mut cur = Some(entry);
+        while let Some(block) = cur {
+            if Some(block) == stop {
+                break;
+            }
+            let block_name = || block.deref(ctx).unique_name(ctx).to_string();
+            if !lifted.insert(block) {
+                return arg_err!(
+                    block.deref(ctx).loc(),
+                    StructuredCfgErr::Unstructured(block_name())
+                );
+            }
+            let succs = block.deref(ctx).succs(ctx);
+
+            if let Some(loop_blocks) = self.loops.get(&block) {
+                let (inside, outside): (Vec<_>, Vec<_>) =
+                    succs.iter().partition(|succ| loop_blocks.contains(*succ));
+                let ([body_entry], [exit]) = (&inside[..], &outside[..]) else {
+                    return arg_err!(
+                        block.deref(ctx).loc(),
+                        StructuredCfgErr::LoopHeader(block_name())
+                    );
+                };
+                let body = if *body_entry == block {
+                    vec![]
+                } else {
+                    self.lift_seq(ctx, *body_entry, Some(block), lifted)?
+                };
+                seq.push(Structured::While {
+                    header: block,
+                    body,
+                });
+                cur = Some(*exit);
+                continue;
+            }
+
+            match succs[..] {
+                [] => {
+                    seq.push(Structured::Block(block));
+                    cur = None;
+                }
+                [succ] => {
+                    seq.push(Structured::Block(block));
+                    cur = Some(succ);
+                }
+                [then_entry, else_entry] => {
+                    let join = self.join(ctx, block, then_entry, else_entry)?;
+                    let branch_stop = join.or(stop);
+                    let then = self.lift_seq(ctx, then_entry, branch_stop, lifted)?;
+                    let else_ = self.lift_seq(ctx, else_entry, branch_stop, lifted)?;
+                    seq.push(Structured::If {
+                        cond: block,
+                        then,
+                        else_,
+                    });
+                    cur = join;
+                }
+                _ => {
+                    return arg_err!(
+                        block.deref(ctx).loc(),
+                        StructuredCfgErr::TooManySuccessors(block_name())
+                    );
+                }
+            }
+        }
+        Ok(seq)
+    }
+}
+
+/// Reconstruct structured control flow from the control flow graph of `region`.
+/// Blocks unreachable from the entry block are ignored.
+/// See [module](self) documentation.
+pub fn lift_structured(ctx: &Context, region: Ptr<Region>) -> Result<Vec<Structured>> {
+    let Some(entry) = region.deref(ctx).head() else {
+        return Ok(vec![]);
+    };
+    let loops = CfgLoops::new(ctx, entry)?;
+    loops.lift_seq(ctx, entry, None, &mut FxHashSet::default())
+}
@@ -0,0 +1,169 @@
+//! Tail calls.
+//!
+//! A [CallOpInterface] op is in tail position when the caller, immediately
+//! after the call, returns the call's results (all of them, in order) and
+//! nothing else. The return may also be reached through unconditional branches
+//! ([BranchOpInterface] terminators with a single successor) that forward
+//! the results to blocks which contain only a return of their arguments.
+//! Returns are terminators without successors.
+//!
+//! [mark_tail_calls] marks such calls with
+//! [ATTR_KEY_TAIL_CALL](crate::builtin::op_interfaces::ATTR_KEY_TAIL_CALL),
+//! allowing code generators to reuse the caller's stack frame and return
+//! the callee's results directly to the caller's caller.
+//!
+//! [eliminate_tail_recursion] turns calls to the function itself, in tail
+//! position, into branches back to the beginning of the function's body,
+//! i.e., the recursion is turned into a loop.
+
+use crate::{
+    basic_block::BasicBlock,
+    builtin::{
+        op_interfaces::{
+            BranchOpInterface, CallOpCallable, CallOpInterface, IsTerminatorInterface,
+            OneRegionInterface, SymbolOpInterface, UnreachableInterface,
+        },
+        ops::FuncOp,
+    },
+    context::{Context, Ptr},
+    linked_list::{ContainsLinkedList, LinkedList},
+    op::{op_cast, op_impls},
+    operation::Operation,
+    r#type::Typed,
+    value::Value,
+};
+
+/// Does executing the terminator `term` lead to returning exactly `values`,
+/// without executing any other [Operation]? `visited` holds the blocks whose
+/// terminators were already followed, to not loop forever in infinite loops.
+fn returns_values(
+    ctx: &Context,
+    term: Ptr<Operation>,
+    values: &[Value],
+    visited: &mut Vec<Ptr<BasicBlock>>,
+) -> bool {
+    let term_obj = Operation::op(term, ctx);
+    if !op_impls::<dyn IsTerminatorInterface>(&*term_obj)
+        || op_impls::<dyn UnreachableInterface>(&*term_obj)
+    {
+        return false;
+    }
+    let term_ref = term.deref(ctx);
+    if term_ref.num_successors() == 0 {
+        return term_ref.operands().eq(values.iter().copied());
+    }
+
+    // An unconditional branch, forwarding just `values`.
+    if term_ref.num_successors() != 1 || term_ref.num_operands() != values.len() {
+        return false;
+    }
+    let forwards_values = op_cast::<dyn BranchOpInterface>(&*term_obj)
+        .is_some_and(|branch| branch.successor_operands(ctx, 0) == values);
+    if !forwards_values {
+        return false;
+    }
+
+    // The successor must do nothing other than passing on its arguments.
+    let succ = term_ref.successor(0);
+    if visited.contains(&succ) {
+        return false;
+    }
+    visited.push(succ);
+    let succ_ref = succ.deref(ctx);
+    let Some(succ_term) = succ_ref.tail() else {
+        return false;
+    };
+    if succ_ref.head() != Some(succ_term) {
+        return false;
+    }
+    let succ_args: Vec<_> = succ_ref.arguments().collect();
+    returns_values(ctx, succ_term, &succ_args, visited)
+}
+
+/// Get all [CallOpInterface] ops in `func` that are in tail position.
+/// See [module](self) documentation.
+pub fn tail_calls(ctx: &Context, func: FuncOp) -> Vec<Ptr<Operation>> {
+    func.op_iter(ctx)
+        .filter(|op| {
+            op_impls::<dyn CallOpInterface>(&*Operation::op(*op, ctx))
+                && op.deref(ctx).next().is_some_and(|term| {
+                    let results: Vec<_> = op.deref(ctx).results().collect();
+                    returns_values(ctx, term, &results, &mut vec![])
+                })
+        })
+        .collect()
+}
+
+/// Mark all calls in tail position in `func` as [tail calls](CallOpInterface::mark_tail_call).
+/// Returns the number of calls marked.
+pub fn mark_tail_calls(ctx: &mut Context, func: FuncOp) -> usize {
+    let calls = tail_calls(ctx, func);
+    for call in &calls {
+        let call_op = Operation::op(*call, ctx);
+        op_cast::<dyn CallOpInterface>(&*call_op)
+            .expect("Tail call must be a CallOpInterface op")
+            .mark_tail_call(ctx);
+    }
+    calls.len()
+}
+
+/// Build an (unlinked) unconditional branch to a block, forwarding values to it.
+pub type BuildBranchFn = dyn Fn(&mut Context, Ptr<BasicBlock>, Vec<Value>) -> Ptr<Operation>;
+
+/// Turn tail calls in `func`, that directly call `func` itself, into loops.
+///
+/// The body of `func` gets a new entry block, which branches to the old
+/// entry block (the loop header), passing on the function's arguments.
+/// Every self-recursive tail call (along with the return following it) is
+/// replaced by a branch to the loop header, passing the call's arguments.
+/// Since pliron doesn't define a branch op, branches are built using
+/// `build_branch`. Returns the number of calls eliminated.
+pub fn eliminate_tail_recursion(
+    ctx: &mut Context,
+    func: FuncOp,
+    build_branch: &BuildBranchFn,
+) -> usize {
+    let func_name = func.symbol_name(ctx);
+    let is_self_call = |ctx: &Context, call: Ptr<Operation>| {
+        let call_op = Operation::op(call, ctx);
+        let callee = op_cast::<dyn CallOpInterface>(&*call_op)
+            .expect("Tail call must be a CallOpInterface op")
+            .callee(ctx);
+        matches!(callee, CallOpCallable::Direct(callee) if callee == func_name)
+    };
+    let self_calls: Vec<_> = tail_calls(ctx, func)
+        .into_iter()
+        .filter(|call| is_self_call(ctx, *call))
+        .collect();
+    if self_calls.is_empty() {
+        return 0;
+    }
+
+    let header = func.get_entry_block(ctx);
+    let arg_types = header
+        .deref(ctx)
+        .arguments()
+        .map(|arg| arg.get_type(ctx))
+        .collect();
+    let entry_label = header.deref(ctx).label;
+    let entry = BasicBlock::new(ctx, entry_label, arg_types);
+    header.deref_mut(ctx).label = Some("tail_loop".try_into().unwrap());
+    entry.insert_at_front(func.region(ctx), ctx);
+    let entry_args = entry.deref(ctx).arguments().collect();
+    build_branch(ctx, header, entry_args).insert_at_back(entry, ctx);
+
+    for call in &self_calls {
+        let args = op_cast::<dyn CallOpInterface>(&*Operation::op(*call, ctx))
+            .expect("Tail call must be a CallOpInterface op")
+            .args(ctx);
+        let term = call
+            .deref(ctx)
+            .next()
+            .expect("Tail call must be followed by a terminator");
+        build_branch(ctx, header, args).insert_after(ctx, term);
+        // The terminator is the only user of the call's results.
+        Operation::erase(term, ctx);
+        Operation::erase(*call, ctx);
+    }
+    self_calls.len()
+}
@@ -31,19 +31,22 @@
 //! [TypeObj]s can be downcasted to their concrete types using
 //! [downcast_rs](https://docs.rs/downcast-rs/1.2.0/downcast_rs/index.html#example-without-generics).
 
+use crate::builtin::types::AliasPlaceholderType;
 use crate::common_traits::Verify;
+use crate::completion;
 use crate::context::{ArenaCell, Context, Ptr, private::ArenaObj};
-use crate::dialect::DialectName;
+use crate::dialect::{DialectName, Registrant};
 use crate::identifier::Identifier;
-use crate::irfmt::parsers::spaced;
-use crate::location::Located;
-use crate::parsable::{Parsable, ParseResult, ParserFn, StateStream};
-use crate::printable::{self, Printable};
+use crate::irfmt::{aliases::type_alias_use, parsers::spaced};
+use crate::location::{Located, Location};
+use crate::parsable::{self, Parsable, ParseResult, ParserFn, StateStream};
+use crate::printable::{self, Highlight, Printable};
 use crate::result::Result;
 use crate::storage_uniquer::TypeValueHash;
+use crate::unregistered::OpaqueType;
 use crate::{arg_err_noloc, impl_printable_for_display, input_err};
 
-use combine::{Parser, parser};
+use combine::{Parser, parser, parser::char::spaces};
 use downcast_rs::{Downcast, impl_downcast};
 use linkme::distributed_slice;
 use rustc_hash::FxHashMap;
@@ -160,6 +163,8 @@ pub trait Type: Printable + Verify + Downcast + Sync + Send + Debug {
     fn verify_interfaces(&self, ctx: &Context) -> Result<()>;
 
     /// Register this Type's [TypeId] in the dialect it belongs to.
+    /// Panics if a different Type is already registered with the same [TypeId].
+    #[track_caller]
     fn register_type_in_dialect(ctx: &mut Context, parser: ParserFn<(), TypePtr<Self>>)
     where
         Self: Sized,
@@ -190,7 +195,10 @@ pub trait Type: Printable + Verify + Downcast + Sync + Send + Debug {
             .dialects
             .get_mut(&typeid.dialect)
             .unwrap_or_else(|| panic!("Unregistered dialect {}", &typeid.dialect));
-        dialect.add_type(typeid, Box::new(ptr_parser));
+        let registrant = Registrant::rust_type::<Self>();
+        if let Err(err) = dialect.add_type(typeid, Box::new(ptr_parser), registrant) {
+            panic!("{}", err);
+        }
     }
 }
 impl_downcast!(Type);
@@ -216,6 +224,24 @@ impl Typed for Ptr<TypeObj> {
     }
 }
 
+impl Ptr<TypeObj> {
+    /// Is this type a `T`?
+    pub fn isa<T: Type>(&self, ctx: &Context) -> bool {
+        self.deref(ctx).is::<T>()
+    }
+
+    /// Cast this type to the interface `T`, if it implements it.
+    /// The returned [Ref] borrows the type, as [deref](Ptr::deref) does.
+    pub fn cast<'a, T: ?Sized + Type>(&self, ctx: &'a Context) -> Option<Ref<'a, T>> {
+        Ref::filter_map(self.deref(ctx), |ty| type_cast::<T>(&**ty)).ok()
+    }
+
+    /// Does this type implement the interface `T`?
+    pub fn impls<T: ?Sized + Type>(&self, ctx: &Context) -> bool {
+        type_impls::<T>(&**self.deref(ctx))
+    }
+}
+
 impl Typed for dyn Type {
     fn get_type(&self, ctx: &Context) -> Ptr<TypeObj> {
         self.self_ptr(ctx)
@@ -240,7 +266,7 @@ impl<T: Typed + ?Sized> Typed for Box<T> {
     }
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 /// A Type's name (not including it's dialect).
 pub struct TypeName(Identifier);
 
@@ -279,14 +305,14 @@ impl Parsable for TypeName {
         Self: Sized,
     {
         Identifier::parser(())
-            .map(|name| TypeName::new(&name))
+            .map(TypeName)
             .parse_stream(state_stream)
             .into()
     }
 }
 
 /// A combination of a Type's name and its dialect.
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct TypeId {
     pub dialect: DialectName,
     pub name: TypeName,
@@ -304,6 +330,7 @@ impl Parsable for TypeId {
     where
         Self: Sized,
     {
+        completion::expect_here(state_stream, completion::Expected::Type);
         let mut parser = DialectName::parser(())
             .skip(parser::char::char('.'))
             .and(TypeName::parser(()))
@@ -339,6 +366,8 @@ impl Hash for TypeObj {
 }
 
 impl ArenaObj for TypeObj {
+    const KIND: &'static str = "type";
+
     fn arena(ctx: &Context) -> &ArenaCell<Self> {
         &ctx.type_store.unique_store
     }
@@ -354,6 +383,14 @@ impl ArenaObj for TypeObj {
     fn dealloc_sub_objects(_ptr: Ptr<Self>, _ctx: &mut Context) {
         panic!("Cannot dealloc arena sub-objects of types")
     }
+
+    fn fmt_debug_details(
+        ptr: Ptr<Self>,
+        ctx: &Context,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, " ({})", ptr.disp(ctx))
+    }
 }
 
 impl Printable for TypeObj {
@@ -363,8 +400,10 @@ impl Printable for TypeObj {
         state: &printable::State,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
-        write!(f, "{} ", self.get_type_id())?;
-        Printable::fmt(self.deref(), ctx, state, f)
+        state.highlighted(Highlight::Type, f, |f| {
+            write!(f, "{} ", self.get_type_id())?;
+            Printable::fmt(self.deref(), ctx, state, f)
+        })
     }
 }
 
@@ -379,16 +418,18 @@ impl Parsable for Ptr<TypeObj> {
         let loc = state_stream.loc();
         let type_id_parser = spaced(TypeId::parser(()));
 
-        let mut type_parser = type_id_parser.then(move |type_id: TypeId| {
+        let type_id_parser = type_id_parser.then(move |type_id: TypeId| {
             // This clone is to satify the borrow checker.
             let loc = loc.clone();
             combine::parser(move |parsable_state: &mut StateStream<'a>| {
+                if type_id == AliasPlaceholderType::get_type_id_static() {
+                    return type_alias_use(parsable_state);
+                }
                 let state = &parsable_state.state;
-                let dialect = state
-                    .ctx
-                    .dialects
-                    .get(&type_id.dialect)
-                    .expect("Dialect name parsed but dialect isn't registered");
+                let Some(dialect) = state.ctx.dialects.get(&type_id.dialect) else {
+                    // Dialect names parse only if registered, or if that's allowed.
+                    return OpaqueType::parse(parsable_state, type_id);
+                };
                 let Some(type_parser) = dialect.types.get(&type_id) else {
                     input_err!(loc.clone(), "Unregistered type {}", type_id.disp(state.ctx))?
                 };
@@ -396,12 +437,20 @@ impl Parsable for Ptr<TypeObj> {
             })
         });
 
-        type_parser.parse_stream(state_stream).into_result()
+        let mut type_parser = spaces()
+            .with(combine::parser(type_alias_use).or(type_id_parser))
+            .skip(spaces());
+        parsable::parse_nested(state_stream, |state_stream| {
+            type_parser.parse_stream(state_stream).into_result()
+        })
     }
 }
 
 impl Verify for TypeObj {
+    /// Verify the interfaces implemented by the [Type] (see
+    /// [verify_interfaces](Type::verify_interfaces)), and then the [Type] itself.
     fn verify(&self, ctx: &Context) -> Result<()> {
+        self.as_ref().verify_interfaces(ctx)?;
         self.as_ref().verify(ctx)
     }
 }
@@ -411,7 +460,7 @@ impl Verify for TypeObj {
 pub struct TypePtr<T: Type>(Ptr<TypeObj>, PhantomData<T>);
 
 #[derive(Error, Debug)]
-#[error("TypePtr mismatch: Constructing {expected} but provided {provided}")]
+#[error("Expected type {expected}, but got {provided}")]
 pub struct TypePtrErr {
     pub expected: String,
     pub provided: String,
@@ -428,9 +477,16 @@ impl<T: Type> TypePtr<T> {
         })
     }
 
-    /// Create a new [TypePtr] from [`Ptr<TypeObj>`](TypeObj)
+    /// Create a new [TypePtr] from [`Ptr<TypeObj>`](TypeObj).
+    /// Same as [downcast](Self::downcast).
     pub fn from_ptr(ptr: Ptr<TypeObj>, ctx: &Context) -> Result<TypePtr<T>> {
-        if ptr.deref(ctx).is::<T>() {
+        Self::downcast(ctx, ptr)
+    }
+
+    /// Downcast `ptr` to a [TypePtr] of `T`, or fail with a [TypePtrErr]
+    /// (an invalid argument error, without a location).
+    pub fn downcast(ctx: &Context, ptr: Ptr<TypeObj>) -> Result<TypePtr<T>> {
+        if ptr.isa::<T>(ctx) {
             Ok(TypePtr(ptr, PhantomData::<T>))
         } else {
             arg_err_noloc!(TypePtrErr {
@@ -440,6 +496,15 @@ impl<T: Type> TypePtr<T> {
         }
     }
 
+    /// Same as [downcast](Self::downcast), but the error is located at `loc`
+    /// (typically, that of the operation whose operand or result `ptr` is the type of).
+    pub fn downcast_at(ctx: &Context, ptr: Ptr<TypeObj>, loc: Location) -> Result<TypePtr<T>> {
+        Self::downcast(ctx, ptr).map_err(|mut err| {
+            err.set_loc(loc);
+            err
+        })
+    }
+
     /// Erase the static rust type.
     pub fn to_ptr(&self) -> Ptr<TypeObj> {
         self.0
@@ -540,9 +605,13 @@ pub static TYPE_INTERFACE_VERIFIERS: [LazyLock<(
     (std::any::TypeId, TypeInterfaceVerifier),
 )>];
 
-/// All interfaces mapped to their super-interfaces
+/// All interfaces (along with their names) mapped to their super-interfaces
 #[distributed_slice]
-pub static TYPE_INTERFACE_DEPS: [LazyLock<(std::any::TypeId, Vec<std::any::TypeId>)>];
+pub static TYPE_INTERFACE_DEPS: [LazyLock<(
+    std::any::TypeId,
+    &'static str,
+    Vec<std::any::TypeId>,
+)>];
 
 /// A map from every [Type] to its ordered (as per interface deps) list of interface verifiers.
 /// An interface's super-interfaces are to be verified before it itself is.
@@ -553,7 +622,7 @@ pub static TYPE_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect TYPE_INTERFACE_VERIFIERS into a [TypeId] indexed map.
     let mut type_intr_verifiers = FxHashMap::default();
     for lazy in TYPE_INTERFACE_VERIFIERS {
-        let (ty_id, (type_id, verifier)) = (**lazy).clone();
+        let (ty_id, (type_id, verifier)) = **lazy;
         type_intr_verifiers
             .entry(ty_id)
             .and_modify(|verifiers: &mut Vec<(TypeId, TypeInterfaceVerifier)>| {
@@ -565,7 +634,7 @@ pub static TYPE_INTERFACE_VERIFIERS_MAP: LazyLock<
     // Collect interface deps into a map.
     let interface_deps: FxHashMap<_, _> = TYPE_INTERFACE_DEPS
         .iter()
-        .map(|lazy| (**lazy).clone())
+        .map(|lazy| (lazy.0, lazy.2.clone()))
         .collect();
 
     // Assign an integer to each interface, such that if y depends on x
@@ -598,7 +667,7 @@ pub static TYPE_INTERFACE_VERIFIERS_MAP: LazyLock<
 
     // Assign dep_sort_idx to every interface.
     for lazy in TYPE_INTERFACE_DEPS.iter() {
-        let (intr, _deps) = &**lazy;
+        let (intr, _name, _deps) = &**lazy;
         assign_idx_to_intr(&interface_deps, &mut dep_sort_idx, &mut sort_idx, intr);
     }
 
@@ -613,13 +682,54 @@ pub static TYPE_INTERFACE_VERIFIERS_MAP: LazyLock<
 #[cfg(test)]
 mod tests {
 
+    use expect_test::expect;
     use pliron::result::Result;
     use rustc_hash::{FxHashMap, FxHashSet};
     use std::any::TypeId;
 
-    use crate::verify_err_noloc;
+    use crate::{
+        builtin::{
+            self,
+            type_interfaces::{DataLayout, SizedTypeInterface},
+            types::{FunctionType, IntegerType, Signedness},
+        },
+        context::Context,
+        location::{Location, Source},
+        verify_err_noloc,
+    };
+
+    use super::{TYPE_INTERFACE_DEPS, TYPE_INTERFACE_VERIFIERS_MAP, TypePtr};
 
-    use super::{TYPE_INTERFACE_DEPS, TYPE_INTERFACE_VERIFIERS_MAP};
+    #[test]
+    fn typed_casts() {
+        let ctx = &mut Context::new();
+        builtin::register(ctx);
+        let i32_ty = IntegerType::get(ctx, 32, Signedness::Signed).to_ptr();
+        let fn_ty = FunctionType::get(ctx, vec![i32_ty], vec![]).to_ptr();
+
+        assert!(i32_ty.isa::<IntegerType>(ctx) && !fn_ty.isa::<IntegerType>(ctx));
+        assert!(i32_ty.impls::<dyn SizedTypeInterface>(ctx));
+        assert!(fn_ty.cast::<dyn SizedTypeInterface>(ctx).is_none());
+        let sized = i32_ty.cast::<dyn SizedTypeInterface>(ctx).unwrap();
+        let (size, _) = sized.size_and_align(ctx, &DataLayout::default()).unwrap();
+        assert_eq!(size, 4);
+        drop(sized);
+
+        let int_ty = TypePtr::<IntegerType>::downcast(ctx, i32_ty).unwrap();
+        assert_eq!(int_ty.deref(ctx).width(), 32);
+        let err = TypePtr::<IntegerType>::downcast(ctx, fn_ty).unwrap_err();
+        assert!(matches!(err.loc, Location::Unknown));
+        let loc = Location::SrcPos {
+            src: Source::InMemory,
+            pos: Default::default(),
+        };
+        let err = TypePtr::<IntegerType>::downcast_at(ctx, fn_ty, loc.clone()).unwrap_err();
+        assert!(err.loc == loc);
+        expect![
+            "Expected type builtin.integer, but got builtin.function <(builtin.integer si32)->()>"
+        ]
+        .assert_eq(&err.err.to_string());
+    }
 
     #[test]
     /// For every interface that a [Type] implements, ensure that the interface verifiers
@@ -629,7 +739,7 @@ mod tests {
         // Collect interface deps into a map.
         let interface_deps: FxHashMap<_, _> = TYPE_INTERFACE_DEPS
             .iter()
-            .map(|lazy| (**lazy).clone())
+            .map(|lazy| (lazy.0, lazy.2.clone()))
             .collect();
 
         for (ty, intrs) in TYPE_INTERFACE_VERIFIERS_MAP.iter() {
@@ -0,0 +1,270 @@
+//! [Op]s, [Type]s and [Attribute]s of dialects not registered in the [Context].
+//!
+//! Tools that only inspect or transport IR (such as printing, filtering or
+//! bundling it) shouldn't need every dialect the IR uses to be linked in.
+//! When a [Context] [allows unregistered dialects](Context::allow_unregistered_dialects),
+//! the parser accepts entities of dialects that aren't registered in it, and keeps them
+//! as is:
+//!   - An [UnregisteredOp] wraps an [Operation] parsed in the
+//!     [canonical syntax](crate::op::canonical_syntax_print) of [Op]s.
+//!   - An [OpaqueType] (and similarly, an [OpaqueAttr]) is its id, optionally followed
+//!     by a payload in angle brackets, `foo.vec <4 x i32>`, kept as unparsed text.
+//!     The payload is the text in which brackets (and `"..."` strings) are balanced,
+//!     as in [verbatim attributes](crate::attribute::VerbatimAttrParserFn).
+//!
+//! Since nothing is known about their semantics, these entities always verify,
+//! and they are printed back just as they were parsed. Entities of dialects that
+//! are registered must still be registered themselves.
+
+use std::fmt;
+
+use combine::{Parser, optional, token};
+
+use crate::{
+    attribute::{AttrId, AttrObj, Attribute},
+    common_traits::Verify,
+    context::{Context, Ptr},
+    identifier::Identifier,
+    irfmt::parsers::verbatim_payload_parser,
+    location::Location,
+    op::{Op, OpId, OpObj, canonical_syntax_parser, canonical_syntax_print},
+    operation::Operation,
+    parsable::{IntoParseResult, ParseResult, StateStream},
+    printable::{self, Printable},
+    result::Result,
+    storage_uniquer::TypeValueHash,
+    r#type::{Type, TypeId, TypeObj, TypePtr},
+};
+
+/// Parse the optional `<payload>` of an [OpaqueType] or an [OpaqueAttr].
+fn payload_parse<'a>(state_stream: &mut StateStream<'a>) -> ParseResult<'a, Option<String>> {
+    optional(token('<').with(verbatim_payload_parser()).skip(token('>')))
+        .parse_stream(state_stream)
+        .into()
+}
+
+/// Print the optional `<payload>` of an [OpaqueType] or an [OpaqueAttr].
+fn fmt_payload(payload: &Option<String>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match payload {
+        Some(payload) => write!(f, "<{payload}>"),
+        None => Ok(()),
+    }
+}
+
+/// An [Op] of an unregistered dialect. See [module](self) documentation.
+#[derive(Clone, Copy)]
+pub struct UnregisteredOp {
+    op: Ptr<Operation>,
+    opid: OpId,
+}
+
+impl UnregisteredOp {
+    /// Let [Operation]s `opid` be wrapped as [UnregisteredOp]s.
+    pub(crate) fn register(ctx: &mut Context, opid: OpId) {
+        ctx.ops
+            .entry(opid)
+            .or_insert_with(|| Box::new(move |op| OpObj::new(UnregisteredOp { op, opid })));
+    }
+
+    /// Parse an [UnregisteredOp] `opid` in canonical syntax, defining `results`.
+    pub(crate) fn parser<'a>(
+        ctx: &mut Context,
+        opid: OpId,
+        results: Vec<(Identifier, Location)>,
+    ) -> Box<dyn Parser<StateStream<'a>, Output = OpObj, PartialState = ()> + 'a> {
+        Self::register(ctx, opid);
+        canonical_syntax_parser(opid, results)
+    }
+}
+
+impl Op for UnregisteredOp {
+    fn operation(&self) -> Ptr<Operation> {
+        self.op
+    }
+
+    fn wrap_operation(_op: Ptr<Operation>) -> OpObj {
+        panic!("Unregistered ops are wrapped by the creator registered when parsing them")
+    }
+
+    fn opid(&self) -> OpId {
+        self.opid
+    }
+
+    fn opid_static() -> OpId {
+        panic!("Unregistered ops do not have a static OpId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for UnregisteredOp {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Printable for UnregisteredOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        canonical_syntax_print(OpObj::new(*self), ctx, state, f)
+    }
+}
+
+/// A [Type] of an unregistered dialect. See [module](self) documentation.
+#[derive(PartialEq, Eq, Hash)]
+pub struct OpaqueType {
+    id: TypeId,
+    payload: Option<String>,
+}
+
+impl OpaqueType {
+    /// Get or create an [OpaqueType] `id`, with `payload` (excluding the angle brackets).
+    pub fn get(ctx: &mut Context, id: TypeId, payload: Option<String>) -> TypePtr<OpaqueType> {
+        Type::register_instance(OpaqueType { id, payload }, ctx)
+    }
+
+    /// The unparsed payload of this [Type], excluding the angle brackets.
+    pub fn payload(&self) -> Option<&str> {
+        self.payload.as_deref()
+    }
+
+    /// Parse the payload of an [OpaqueType] `id`.
+    pub(crate) fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        id: TypeId,
+    ) -> ParseResult<'a, Ptr<TypeObj>> {
+        let payload = payload_parse(state_stream)?.0;
+        let ty = OpaqueType::get(state_stream.state.ctx, id, payload);
+        Ok(ty.to_ptr()).into_parse_result()
+    }
+}
+
+impl fmt::Debug for OpaqueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpaqueType")
+            .field("id", &self.id.to_string())
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+impl Type for OpaqueType {
+    fn hash_type(&self) -> TypeValueHash {
+        TypeValueHash::new(self)
+    }
+
+    fn eq_type(&self, other: &dyn Type) -> bool {
+        other
+            .downcast_ref::<OpaqueType>()
+            .is_some_and(|other| self == other)
+    }
+
+    fn get_type_id(&self) -> TypeId {
+        self.id
+    }
+
+    fn get_type_id_static() -> TypeId {
+        panic!("Opaque types do not have a static TypeId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for OpaqueType {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Printable for OpaqueType {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        fmt_payload(&self.payload, f)
+    }
+}
+
+/// An [Attribute] of an unregistered dialect. See [module](self) documentation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OpaqueAttr {
+    id: AttrId,
+    payload: Option<String>,
+}
+
+impl OpaqueAttr {
+    /// Create an [OpaqueAttr] `id`, with `payload` (excluding the angle brackets).
+    pub fn new(id: AttrId, payload: Option<String>) -> OpaqueAttr {
+        OpaqueAttr { id, payload }
+    }
+
+    /// The unparsed payload of this [Attribute], excluding the angle brackets.
+    pub fn payload(&self) -> Option<&str> {
+        self.payload.as_deref()
+    }
+
+    /// Parse the payload of an [OpaqueAttr] `id`.
+    pub(crate) fn parse<'a>(
+        state_stream: &mut StateStream<'a>,
+        id: AttrId,
+    ) -> ParseResult<'a, AttrObj> {
+        let payload = payload_parse(state_stream)?.0;
+        Ok(AttrObj::from(OpaqueAttr::new(id, payload))).into_parse_result()
+    }
+}
+
+impl fmt::Debug for OpaqueAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpaqueAttr")
+            .field("id", &self.id.to_string())
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+impl Attribute for OpaqueAttr {
+    fn eq_attr(&self, other: &dyn Attribute) -> bool {
+        other
+            .downcast_ref::<OpaqueAttr>()
+            .is_some_and(|other| self == other)
+    }
+
+    fn attr_id(&self) -> AttrId {
+        self.id
+    }
+
+    fn attr_id_static() -> AttrId {
+        panic!("Opaque attributes do not have a static AttrId")
+    }
+
+    fn verify_interfaces(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Verify for OpaqueAttr {
+    fn verify(&self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Printable for OpaqueAttr {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        fmt_payload(&self.payload, f)
+    }
+}
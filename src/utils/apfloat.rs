@@ -0,0 +1,616 @@
+//! Arbitrary precision floating point implementation, for the IEEE-754 binary formats.
+//! This is similar in functionality to LLVM's APFloat class.
+//!
+//! Conversions (from native floats, from other formats, and from text)
+//! round to the nearest representable value, ties to even.
+
+use std::{cmp::Ordering, fmt::Display, ops::Neg};
+
+use thiserror::Error;
+
+use crate::{
+    arg_err_noloc,
+    result::Result,
+    utils::apint::{APInt, bw},
+};
+
+/// The binary floating point formats that an [APFloat] can be in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FloatSemantics {
+    /// IEEE-754 binary16.
+    Half,
+    /// The bfloat16 format: binary32 with its fraction truncated to 7 bits.
+    BFloat,
+    /// IEEE-754 binary32.
+    Single,
+    /// IEEE-754 binary64.
+    Double,
+}
+
+impl FloatSemantics {
+    /// All the supported formats.
+    pub const ALL: [FloatSemantics; 4] = [
+        FloatSemantics::Half,
+        FloatSemantics::BFloat,
+        FloatSemantics::Single,
+        FloatSemantics::Double,
+    ];
+
+    /// Total number of bits.
+    pub fn width(&self) -> usize {
+        match self {
+            FloatSemantics::Half | FloatSemantics::BFloat => 16,
+            FloatSemantics::Single => 32,
+            FloatSemantics::Double => 64,
+        }
+    }
+
+    /// Number of exponent bits.
+    pub fn exponent_bits(&self) -> usize {
+        match self {
+            FloatSemantics::Half => 5,
+            FloatSemantics::BFloat | FloatSemantics::Single => 8,
+            FloatSemantics::Double => 11,
+        }
+    }
+
+    /// Number of (explicitly stored) fraction bits.
+    pub fn fraction_bits(&self) -> usize {
+        match self {
+            FloatSemantics::Half => 10,
+            FloatSemantics::BFloat => 7,
+            FloatSemantics::Single => 23,
+            FloatSemantics::Double => 52,
+        }
+    }
+
+    /// Short name of the format: `f16`, `bf16`, `f32` or `f64`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FloatSemantics::Half => "f16",
+            FloatSemantics::BFloat => "bf16",
+            FloatSemantics::Single => "f32",
+            FloatSemantics::Double => "f64",
+        }
+    }
+
+    /// Exponent bias.
+    fn bias(&self) -> i64 {
+        (1 << (self.exponent_bits() - 1)) - 1
+    }
+
+    /// All ones, the exponent field of infinities and NaNs.
+    fn max_exponent_field(&self) -> u64 {
+        (1 << self.exponent_bits()) - 1
+    }
+}
+
+impl Display for FloatSemantics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum APFloatErr {
+    #[error("Malformed floating point literal \"{0}\"")]
+    Malformed(String),
+    #[error("Bit pattern {bits} is wider than {semantics}")]
+    BitPatternTooWide {
+        bits: String,
+        semantics: FloatSemantics,
+    },
+}
+
+/// A floating point number, in one of the [FloatSemantics] formats.
+/// Equality is bitwise, so `-0.0 != 0.0`, and a NaN is equal to itself.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct APFloat {
+    semantics: FloatSemantics,
+    bits: APInt,
+}
+
+/// `2^exp` as an [f64]. `exp` must be in the range of normal [f64] exponents.
+fn exp2_f64(exp: i64) -> f64 {
+    f64::from_bits(((exp + 1023) as u64) << 52)
+}
+
+/// Parse a decimal literal (without its sign) into its significant digits and
+/// exponent, such that the value is `0.<digits> x 10^exp`. No digits means zero.
+fn parse_decimal(literal: &str) -> Option<(String, i64)> {
+    let (mantissa, exp) = match literal.find(['e', 'E']) {
+        Some(idx) => (&literal[..idx], &literal[idx + 1..]),
+        None => (literal, "0"),
+    };
+    let exp_digits = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+    if exp_digits.is_empty() || !exp_digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // Saturate large exponents, they under/overflow anyway.
+    let exp: i64 = exp.parse().unwrap_or(if exp.starts_with('-') {
+        -(1 << 32)
+    } else {
+        1 << 32
+    });
+
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_part.is_empty() && frac_part.is_empty()
+        || !int_part
+            .chars()
+            .chain(frac_part.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let digits = format!("{}{}", int_part, frac_part);
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0').to_string();
+    if digits.is_empty() {
+        return Some((digits, 0));
+    }
+    Some((digits, exp + int_part.len() as i64 - leading_zeros as i64))
+}
+
+impl APFloat {
+    /// Build an [APFloat] from its bit pattern. Bits beyond the width of
+    /// `semantics` are ignored.
+    pub fn from_bits(bits: u64, semantics: FloatSemantics) -> APFloat {
+        APFloat {
+            semantics,
+            bits: APInt::from_u64(bits, bw(semantics.width())),
+        }
+    }
+
+    /// The bit pattern of this value.
+    pub fn to_bits(&self) -> u64 {
+        self.bits.to_u64()
+    }
+
+    /// The format of this value.
+    pub fn semantics(&self) -> FloatSemantics {
+        self.semantics
+    }
+
+    /// Build an [APFloat] from its sign, (biased) exponent field and fraction field.
+    fn from_fields(
+        semantics: FloatSemantics,
+        negative: bool,
+        exponent: u64,
+        fraction: u64,
+    ) -> Self {
+        let sign = u64::from(negative) << (semantics.width() - 1);
+        let bits = sign | exponent << semantics.fraction_bits() | fraction;
+        Self::from_bits(bits, semantics)
+    }
+
+    /// The sign, (biased) exponent field and fraction field of this value.
+    fn fields(&self) -> (bool, u64, u64) {
+        let bits = self.to_bits();
+        let fraction_bits = self.semantics.fraction_bits();
+        (
+            bits >> (self.semantics.width() - 1) == 1,
+            (bits >> fraction_bits) & self.semantics.max_exponent_field(),
+            bits & ((1 << fraction_bits) - 1),
+        )
+    }
+
+    /// Positive or negative zero.
+    pub fn zero(semantics: FloatSemantics, negative: bool) -> Self {
+        Self::from_fields(semantics, negative, 0, 0)
+    }
+
+    /// Positive or negative infinity.
+    pub fn inf(semantics: FloatSemantics, negative: bool) -> Self {
+        Self::from_fields(semantics, negative, semantics.max_exponent_field(), 0)
+    }
+
+    /// The canonical quiet NaN.
+    pub fn nan(semantics: FloatSemantics) -> Self {
+        let quiet_bit = 1 << (semantics.fraction_bits() - 1);
+        Self::from_fields(semantics, false, semantics.max_exponent_field(), quiet_bit)
+    }
+
+    /// Is this a NaN?
+    pub fn is_nan(&self) -> bool {
+        let (_, exponent, fraction) = self.fields();
+        exponent == self.semantics.max_exponent_field() && fraction != 0
+    }
+
+    /// Is this positive or negative infinity?
+    pub fn is_infinite(&self) -> bool {
+        let (_, exponent, fraction) = self.fields();
+        exponent == self.semantics.max_exponent_field() && fraction == 0
+    }
+
+    /// Is this neither infinite nor NaN?
+    pub fn is_finite(&self) -> bool {
+        let (_, exponent, _) = self.fields();
+        exponent != self.semantics.max_exponent_field()
+    }
+
+    /// Is this positive or negative zero?
+    pub fn is_zero(&self) -> bool {
+        let (_, exponent, fraction) = self.fields();
+        exponent == 0 && fraction == 0
+    }
+
+    /// Is the sign bit set? This is true for `-0.0`, and may be true for NaNs.
+    pub fn is_negative(&self) -> bool {
+        self.fields().0
+    }
+
+    /// Round `mantissa * 2^exp` (negated if `negative`) to the nearest value in
+    /// `semantics`, ties to even. The exact value may differ infinitesimally from
+    /// `mantissa * 2^exp`: `rest` tells (only when needed, to break a tie) how the
+    /// exact magnitude compares to that of `mantissa * 2^exp`.
+    fn round(
+        semantics: FloatSemantics,
+        negative: bool,
+        mantissa: u128,
+        exp: i64,
+        rest: impl FnOnce() -> Ordering,
+    ) -> Self {
+        if mantissa == 0 {
+            return Self::zero(semantics, negative);
+        }
+        let fraction_bits = semantics.fraction_bits() as i64;
+        let min_exp = 1 - semantics.bias();
+        let msb_exp = exp + (128 - mantissa.leading_zeros() as i64) - 1;
+        // Exponent of the least significant bit that we keep.
+        let mut lsb_exp = (msb_exp - fraction_bits).max(min_exp - fraction_bits);
+        let shift = lsb_exp - exp;
+        let (mut kept, discarded) = match shift {
+            ..=0 => (mantissa << -shift, Ordering::Less),
+            1..=127 => (
+                mantissa >> shift,
+                (mantissa & ((1 << shift) - 1)).cmp(&(1 << (shift - 1))),
+            ),
+            128 => (0, mantissa.cmp(&(1 << 127))),
+            _ => (0, Ordering::Less),
+        };
+        let round_up = match discarded {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => match rest() {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                Ordering::Equal => kept & 1 == 1,
+            },
+        };
+        if round_up {
+            kept += 1;
+            if kept == 1 << (fraction_bits + 1) {
+                kept >>= 1;
+                lsb_exp += 1;
+            }
+        }
+
+        if kept < 1 << fraction_bits {
+            // Subnormal (or zero).
+            return Self::from_fields(semantics, negative, 0, kept as u64);
+        }
+        let exponent = lsb_exp + fraction_bits + semantics.bias();
+        if exponent >= semantics.max_exponent_field() as i64 {
+            return Self::inf(semantics, negative);
+        }
+        let fraction = kept as u64 & ((1 << fraction_bits) - 1);
+        Self::from_fields(semantics, negative, exponent as u64, fraction)
+    }
+
+    /// Convert `value` to `semantics`, rounding to the nearest value.
+    pub fn from_f64(value: f64, semantics: FloatSemantics) -> Self {
+        Self::from_f64_near(value, semantics, || Ordering::Equal)
+    }
+
+    /// Convert a value near `value` to `semantics`, rounding to the nearest value.
+    /// See [Self::round] for `rest`, which is needed only when `value` is a tie.
+    fn from_f64_near(
+        value: f64,
+        semantics: FloatSemantics,
+        rest: impl FnOnce() -> Ordering,
+    ) -> Self {
+        let bits = value.to_bits();
+        let negative = value.is_sign_negative();
+        if value.is_nan() {
+            // Keep the most significant bits of the payload, and keep it quiet.
+            let fraction_bits = semantics.fraction_bits();
+            let payload = (bits & ((1 << 52) - 1)) >> (52 - fraction_bits);
+            let fraction = payload | 1 << (fraction_bits - 1);
+            return Self::from_fields(
+                semantics,
+                negative,
+                semantics.max_exponent_field(),
+                fraction,
+            );
+        }
+        if value.is_infinite() {
+            return Self::inf(semantics, negative);
+        }
+        let exponent = (bits >> 52) & 0x7ff;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exp) = if exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | 1 << 52, exponent as i64 - 1075)
+        };
+        Self::round(semantics, negative, mantissa.into(), exp, rest)
+    }
+
+    /// Convert `value` to `semantics`, rounding to the nearest value.
+    pub fn from_f32(value: f32, semantics: FloatSemantics) -> Self {
+        Self::from_f64(value.into(), semantics)
+    }
+
+    /// Convert to an [f64]. This is exact for all [FloatSemantics].
+    pub fn to_f64(&self) -> f64 {
+        if self.semantics == FloatSemantics::Double {
+            return f64::from_bits(self.to_bits());
+        }
+        let (negative, exponent, fraction) = self.fields();
+        let fraction_bits = self.semantics.fraction_bits() as i64;
+        let magnitude = if exponent == self.semantics.max_exponent_field() {
+            if fraction == 0 {
+                f64::INFINITY
+            } else {
+                f64::from_bits(0x7ff << 52 | fraction << (52 - fraction_bits))
+            }
+        } else if exponent == 0 {
+            fraction as f64 * exp2_f64(1 - self.semantics.bias() - fraction_bits)
+        } else {
+            (fraction | 1 << fraction_bits) as f64
+                * exp2_f64(exponent as i64 - self.semantics.bias() - fraction_bits)
+        };
+        if negative { -magnitude } else { magnitude }
+    }
+
+    /// Convert to an [f32], rounding to the nearest value.
+    pub fn to_f32(&self) -> f32 {
+        if self.semantics == FloatSemantics::Single {
+            return f32::from_bits(self.to_bits() as u32);
+        }
+        self.to_f64() as f32
+    }
+
+    /// Convert to `semantics`, rounding to the nearest value.
+    pub fn convert(&self, semantics: FloatSemantics) -> Self {
+        Self::from_f64(self.to_f64(), semantics)
+    }
+
+    /// Parse `value` into `semantics`, rounding to the nearest value. The accepted syntax is:
+    ///   - A decimal literal, such as `1`, `-2.5` or `1.5e-3`.
+    ///   - A hexadecimal float literal (as in C), such as `0x1.8p3` (which is `12.0`).
+    ///   - The bit pattern, in hexadecimal, such as `0x7FC00000` (a NaN in `f32`).
+    ///   - `inf`, `-inf` or `nan`.
+    pub fn from_str(value: &str, semantics: FloatSemantics) -> Result<Self> {
+        let malformed = || arg_err_noloc!(APFloatErr::Malformed(value.to_string()));
+        let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+        let negative = value.starts_with('-');
+
+        if unsigned == "inf" {
+            return Ok(Self::inf(semantics, negative));
+        }
+        if unsigned == "nan" {
+            let nan = Self::nan(semantics);
+            return Ok(if negative { -nan } else { nan });
+        }
+        let Some(hex) = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"))
+        else {
+            let Some((digits, exp)) = parse_decimal(unsigned) else {
+                return malformed();
+            };
+            let Ok(approx) = value.parse::<f64>() else {
+                return malformed();
+            };
+            // Rounding the (correctly rounded) f64 to a narrower format may be off if
+            // the f64 is a tie in that format. Break such ties using the literal itself.
+            let rest = || {
+                let exact = format!("{:.1100e}", approx.abs());
+                let (exact_digits, exact_exp) =
+                    parse_decimal(&exact).expect("Formatted f64 must be a decimal literal");
+                exp.cmp(&exact_exp).then(digits.cmp(&exact_digits))
+            };
+            return Ok(Self::from_f64_near(approx, semantics, rest));
+        };
+
+        let Some((mantissa, exp)) = hex.split_once(['p', 'P']) else {
+            // A bit pattern.
+            if unsigned.len() != value.len()
+                || hex.is_empty()
+                || !hex.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return malformed();
+            }
+            let bits = u64::from_str_radix(hex, 16).ok();
+            let Some(bits) = bits.filter(|bits| bits >> (semantics.width() - 1) >> 1 == 0) else {
+                return arg_err_noloc!(APFloatErr::BitPatternTooWide {
+                    bits: value.to_string(),
+                    semantics
+                });
+            };
+            return Ok(Self::from_bits(bits, semantics));
+        };
+
+        let exp_digits = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if exp_digits.is_empty()
+            || !exp_digits.chars().all(|c| c.is_ascii_digit())
+            || int_part.is_empty() && frac_part.is_empty()
+            || !int_part
+                .chars()
+                .chain(frac_part.chars())
+                .all(|c| c.is_ascii_hexdigit())
+        {
+            return malformed();
+        }
+        // Saturate large exponents, they under/overflow anyway.
+        let mut exp2: i64 = exp.parse().unwrap_or(if exp.starts_with('-') {
+            -(1 << 32)
+        } else {
+            1 << 32
+        });
+        let mut mantissa: u128 = 0;
+        let mut sticky = false;
+        let digit_value = |c: char| u128::from(c.to_digit(16).expect("Checked hex digit"));
+        for c in int_part.chars() {
+            if mantissa >> 120 == 0 {
+                mantissa = mantissa << 4 | digit_value(c);
+            } else {
+                exp2 += 4;
+                sticky |= c != '0';
+            }
+        }
+        for c in frac_part.chars() {
+            if mantissa >> 120 == 0 {
+                mantissa = mantissa << 4 | digit_value(c);
+                exp2 -= 4;
+            } else {
+                sticky |= c != '0';
+            }
+        }
+        Ok(Self::round(semantics, negative, mantissa, exp2, || {
+            if sticky {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        }))
+    }
+}
+
+impl Neg for APFloat {
+    type Output = APFloat;
+
+    /// Flip the sign bit.
+    fn neg(self) -> Self::Output {
+        let sign = 1 << (self.semantics.width() - 1);
+        Self::from_bits(self.to_bits() ^ sign, self.semantics)
+    }
+}
+
+impl Display for APFloat {
+    /// Prints the shortest decimal that parses (see [APFloat::from_str]) back to
+    /// this value, `inf` or `-inf` for infinities, and the bit pattern for NaNs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_nan() {
+            return write!(
+                f,
+                "0x{:0width$X}",
+                self.to_bits(),
+                width = self.semantics.width() / 4
+            );
+        }
+        if self.is_infinite() {
+            return write!(f, "{}inf", if self.is_negative() { "-" } else { "" });
+        }
+        match self.semantics {
+            // f16 and bf16 values are exact in f32, and their shortest f32
+            // representations are close enough to round back to them.
+            FloatSemantics::Half | FloatSemantics::BFloat | FloatSemantics::Single => {
+                write!(f, "{:?}", self.to_f32())
+            }
+            FloatSemantics::Double => write!(f, "{:?}", self.to_f64()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f64_round_trip() {
+        for value in [0.0, -0.0, 1.0, -2.5, 0.1, 1e300, 5e-324, f64::MAX, f64::MIN] {
+            let apfloat = APFloat::from_f64(value, FloatSemantics::Double);
+            assert_eq!(apfloat.to_f64().to_bits(), value.to_bits());
+            assert_eq!(
+                APFloat::from_str(&apfloat.to_string(), FloatSemantics::Double).unwrap(),
+                apfloat
+            );
+        }
+    }
+
+    #[test]
+    fn test_narrowing() {
+        // Same as the native conversion, which rounds to nearest, ties to even.
+        for value in [
+            0.1,
+            1.0 / 3.0,
+            3.4e38,
+            3.5e38,
+            1e-45,
+            7e-46,
+            -1e-40,
+            16777217.0,
+        ] {
+            let apfloat = APFloat::from_f64(value, FloatSemantics::Single);
+            assert_eq!(apfloat.to_f32().to_bits(), (value as f32).to_bits());
+        }
+
+        // f16: max is 65504, ulp at 2048 is 2.
+        let half = |value: f64| APFloat::from_f64(value, FloatSemantics::Half).to_f64();
+        assert_eq!(half(65504.0), 65504.0);
+        assert_eq!(half(65519.0), 65504.0);
+        assert_eq!(half(65520.0), f64::INFINITY);
+        assert_eq!(half(2049.0), 2048.0);
+        assert_eq!(half(2051.0), 2052.0);
+        assert_eq!(half(2.0f64.powi(-24)), 2.0f64.powi(-24));
+        assert_eq!(half(2.0f64.powi(-25)), 0.0);
+        assert_eq!(half(1.5 * 2.0f64.powi(-25)), 2.0f64.powi(-24));
+        // bf16 has the range of f32, with 8 bits of precision.
+        let bfloat = |value: f64| APFloat::from_f64(value, FloatSemantics::BFloat).to_f64();
+        assert_eq!(bfloat(257.0), 256.0);
+        assert_eq!(bfloat(259.0), 260.0);
+        for value in [1e38f32, 0.1, -3.7e-39, 65793.0] {
+            // Round the f32 to the upper 16 bits, ties to even.
+            let bits = value.to_bits();
+            let rounded = (bits + 0x7fff + ((bits >> 16) & 1)) & 0xffff0000;
+            assert_eq!(bfloat(value.into()), f64::from(f32::from_bits(rounded)));
+        }
+    }
+
+    #[test]
+    fn test_parse_and_print() {
+        let parse = |value: &str, semantics| APFloat::from_str(value, semantics).unwrap();
+        let f32_val = parse("0.1", FloatSemantics::Single);
+        assert_eq!(f32_val.to_f32(), 0.1f32);
+        assert_eq!(f32_val.to_string(), "0.1");
+        assert_eq!(parse("0x1.8p3", FloatSemantics::Double).to_f64(), 12.0);
+        assert_eq!(parse("-0x.8p-1", FloatSemantics::Half).to_f64(), -0.25);
+        assert_eq!(parse("0x3C00", FloatSemantics::Half).to_f64(), 1.0);
+        assert_eq!(parse("1e5", FloatSemantics::Half).to_string(), "inf");
+        assert_eq!(parse("-inf", FloatSemantics::BFloat).to_string(), "-inf");
+        assert_eq!(
+            parse("nan", FloatSemantics::Single).to_string(),
+            "0x7FC00000"
+        );
+        assert_eq!(parse("-0.0", FloatSemantics::Half).to_string(), "-0.0");
+
+        // 2049 is a tie in f16, only its digits beyond f64 precision break it.
+        assert_eq!(
+            parse("2049.00000000000000000001", FloatSemantics::Half).to_f64(),
+            2050.0
+        );
+        assert_eq!(
+            parse("2048.99999999999999999999", FloatSemantics::Half).to_f64(),
+            2048.0
+        );
+        assert_eq!(parse("2049", FloatSemantics::Half).to_f64(), 2048.0);
+
+        // Printed values parse back to themselves.
+        for semantics in FloatSemantics::ALL {
+            for value in ["0.1", "-1.5e-7", "3.14159", "6e4", "1e-40"] {
+                let apfloat = parse(value, semantics);
+                assert_eq!(parse(&apfloat.to_string(), semantics), apfloat);
+            }
+        }
+
+        for value in [
+            "", "1.2.3", "0x", "0x1.8", "0x1p", "e5", "1e", "0x1FFFF", "-0x3C00",
+        ] {
+            assert!(APFloat::from_str(value, FloatSemantics::Half).is_err());
+        }
+    }
+}
@@ -2,8 +2,8 @@
 //! This is similar in functionality to LLVM's APInt class.
 
 use crate::{arg_error_noloc, result::Result};
-use awint::{Awi, SerdeError};
-use std::num::NonZero;
+use awint::{Awi, Bits, SerdeError};
+use std::{cmp::Ordering, num::NonZero};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct APInt {
@@ -18,12 +18,97 @@ impl From<SerdeError> for crate::result::Error {
 
 pub use awint::bw;
 
+/// Radix to print (or parse) an [APInt] in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Radix {
+    Binary,
+    Octal,
+    #[default]
+    Decimal,
+    Hex,
+}
+
+impl Radix {
+    /// The numeric value of this radix.
+    pub fn value(&self) -> u8 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hex => 16,
+        }
+    }
+
+    /// The prefix of numbers in this radix: `0b`, `0o`, none, or `0x`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Decimal => "",
+            Radix::Hex => "0x",
+        }
+    }
+
+    /// Split a number into its radix (as given by its [prefix](Self::prefix)) and digits.
+    pub fn split_prefix(value: &str) -> (Radix, &str) {
+        [Radix::Binary, Radix::Octal, Radix::Hex]
+            .into_iter()
+            .find_map(|radix| {
+                value
+                    .strip_prefix(radix.prefix())
+                    .map(|digits| (radix, digits))
+            })
+            .unwrap_or((Radix::Decimal, value))
+    }
+
+    /// Number of bits a digit represents, if it represents a whole number of bits.
+    fn bits_per_digit(&self) -> Option<usize> {
+        match self {
+            Radix::Binary => Some(1),
+            Radix::Octal => Some(3),
+            Radix::Decimal => None,
+            Radix::Hex => Some(4),
+        }
+    }
+}
+
 impl APInt {
     /// Get the bitwidth of the APInt.
     pub fn bw(&self) -> usize {
         self.value.bw()
     }
 
+    /// The bytes of this APInt, least significant first. The last byte
+    /// is zero extended if the bit width isn't a multiple of 8.
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; self.bw().div_ceil(8)];
+        self.value.to_u8_slice(&mut bytes);
+        bytes
+    }
+
+    /// Create an APInt of bit width `width` from its bytes, least significant first.
+    /// Bits of `bytes` beyond `width` are ignored, and missing ones are zero.
+    pub fn from_le_bytes(bytes: &[u8], width: NonZero<usize>) -> APInt {
+        let mut value = Awi::zero(width);
+        value.u8_slice_(bytes);
+        APInt { value }
+    }
+
+    /// The bytes of this APInt, most significant first. The first byte
+    /// is zero extended if the bit width isn't a multiple of 8.
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_le_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Create an APInt of bit width `width` from its bytes, most significant first.
+    /// Bits of `bytes` beyond `width` are ignored, and missing ones are zero.
+    pub fn from_be_bytes(bytes: &[u8], width: NonZero<usize>) -> APInt {
+        let bytes: Vec<u8> = bytes.iter().rev().copied().collect();
+        Self::from_le_bytes(&bytes, width)
+    }
+
     /// Get zero valued APInt.
     pub fn zero(width: NonZero<usize>) -> APInt {
         APInt {
@@ -100,6 +185,42 @@ impl APInt {
         }
     }
 
+    /// Parse a string, with an optional sign followed by the [prefix](Radix::prefix)
+    /// of its radix, into an APInt. So this parses what [to_string_prefixed](Self::to_string_prefixed)
+    /// and [to_string_bits](Self::to_string_bits) print.
+    pub fn from_str_prefixed(value: &str, width: usize) -> Result<APInt> {
+        let (sign, unsigned) = match value.strip_prefix(['-', '+']) {
+            Some(unsigned) => (&value[..1], unsigned),
+            None => ("", value),
+        };
+        let (radix, digits) = Radix::split_prefix(unsigned);
+        Self::from_str(&format!("{sign}{digits}"), width, radix.value())
+    }
+
+    /// Convert APInt to string in `radix`, with the radix's [prefix](Radix::prefix),
+    /// interpreting it as a signed or unsigned integer. A negative value is printed
+    /// with a `-` before the prefix, as in `-0x1f`.
+    pub fn to_string_prefixed(&self, radix: Radix, signed: bool) -> String {
+        let digits = self.to_string(radix.value(), signed);
+        match digits.strip_prefix('-') {
+            Some(digits) => format!("-{}{digits}", radix.prefix()),
+            None => format!("{}{digits}", radix.prefix()),
+        }
+    }
+
+    /// Convert APInt to the exact bit pattern (two's complement, for negative values)
+    /// in `radix`, with the radix's [prefix](Radix::prefix). Except in [Radix::Decimal],
+    /// which prints the unsigned value, leading zeros are kept so that all the bits
+    /// of the bit width are represented. For example, -1 in 12 bits is `0xfff`,
+    /// and 5 in 8 bits is `0b00000101`.
+    pub fn to_string_bits(&self, radix: Radix) -> String {
+        let digits = self.to_string(radix.value(), false);
+        let num_digits = radix
+            .bits_per_digit()
+            .map_or(0, |bits| self.bw().div_ceil(bits));
+        format!("{}{digits:0>num_digits$}", radix.prefix())
+    }
+
     /// Convert APInt to a decimal string
     pub fn to_string_decimal(&self, signed: bool) -> String {
         self.to_string(10, signed)
@@ -284,6 +405,180 @@ impl APInt {
     pub fn to_i128(&self) -> i128 {
         self.value.to_i128()
     }
+
+    /// Is this value negative, when interpreted as a signed integer?
+    pub fn is_negative(&self) -> bool {
+        self.value.msb()
+    }
+
+    /// Convert APInt, interpreted as signed if `signed`, to u64.
+    /// Returns [None] if the value doesn't fit in (or, being negative, isn't) a u64.
+    pub fn try_to_u64(&self, signed: bool) -> Option<u64> {
+        if signed && self.is_negative() {
+            return None;
+        }
+        let value = self.to_u64();
+        (APInt::from_u64(value, bw(self.bw())) == *self).then_some(value)
+    }
+
+    /// Convert APInt, interpreted as signed if `signed`, to i64.
+    /// Returns [None] if the value doesn't fit in an i64.
+    pub fn try_to_i64(&self, signed: bool) -> Option<i64> {
+        if !signed {
+            return self
+                .try_to_u64(false)
+                .and_then(|value| i64::try_from(value).ok());
+        }
+        let value = self.to_i64();
+        (APInt::from_i64(value, bw(self.bw())) == *self).then_some(value)
+    }
+
+    /// Add `rhs`, wrapping around at the bit width. Also returns whether the addition,
+    /// interpreting the operands as signed if `signed`, overflowed.
+    /// Panics if the bit widths of the operands differ.
+    pub fn overflowing_add(&self, rhs: &APInt, signed: bool) -> (APInt, bool) {
+        let mut value = Awi::zero(self.value.nzbw());
+        let (unsigned_overflow, signed_overflow) = value
+            .cin_sum_(false, &self.value, &rhs.value)
+            .expect("APInt bit widths must match");
+        let overflow = if signed {
+            signed_overflow
+        } else {
+            unsigned_overflow
+        };
+        (APInt { value }, overflow)
+    }
+
+    /// Subtract `rhs`, wrapping around at the bit width. Also returns whether the
+    /// subtraction, interpreting the operands as signed if `signed`, overflowed.
+    /// Panics if the bit widths of the operands differ.
+    pub fn overflowing_sub(&self, rhs: &APInt, signed: bool) -> (APInt, bool) {
+        // self - rhs = self + !rhs + 1, which carries out unless it borrows.
+        let mut not_rhs = rhs.value.clone();
+        not_rhs.not_();
+        let mut value = Awi::zero(self.value.nzbw());
+        let (carry, signed_overflow) = value
+            .cin_sum_(true, &self.value, &not_rhs)
+            .expect("APInt bit widths must match");
+        let overflow = if signed { signed_overflow } else { !carry };
+        (APInt { value }, overflow)
+    }
+
+    /// Multiply by `rhs`, wrapping around at the bit width. Also returns whether the
+    /// multiplication, interpreting the operands as signed if `signed`, overflowed.
+    /// Panics if the bit widths of the operands differ.
+    pub fn overflowing_mul(&self, rhs: &APInt, signed: bool) -> (APInt, bool) {
+        assert_eq!(self.bw(), rhs.bw(), "APInt bit widths must match");
+        // The product of the extended operands is exact at double the width.
+        let wide = bw(2 * self.bw());
+        let extend = |value: &Awi| {
+            let mut extended = Awi::zero(wide);
+            if signed {
+                extended.sign_resize_(value);
+            } else {
+                extended.zero_resize_(value);
+            }
+            extended
+        };
+        let mut product = Awi::zero(wide);
+        product
+            .mul_add_(&extend(&self.value), &extend(&rhs.value))
+            .expect("Bit widths of extended operands must match");
+        let mut value = Awi::zero(self.value.nzbw());
+        let overflow = if signed {
+            value.sign_resize_(&product)
+        } else {
+            value.zero_resize_(&product)
+        };
+        (APInt { value }, overflow)
+    }
+
+    /// Divide by `rhs`, interpreting the operands as signed if `signed`, returning
+    /// the quotient (rounded towards zero) and the remainder (with the sign of `self`).
+    /// Returns [None] if `rhs` is zero. The signed division of the minimum value by
+    /// `-1` wraps around to the minimum value. Panics if the bit widths differ.
+    pub fn div_rem(&self, rhs: &APInt, signed: bool) -> Option<(APInt, APInt)> {
+        assert_eq!(self.bw(), rhs.bw(), "APInt bit widths must match");
+        let mut quo = Awi::zero(self.value.nzbw());
+        let mut rem = Awi::zero(self.value.nzbw());
+        if signed {
+            let mut duo = self.value.clone();
+            let mut div = rhs.value.clone();
+            Bits::idivide(&mut quo, &mut rem, &mut duo, &mut div)?;
+        } else {
+            Bits::udivide(&mut quo, &mut rem, &self.value, &rhs.value)?;
+        }
+        Some((APInt { value: quo }, APInt { value: rem }))
+    }
+
+    /// Combine with `rhs`, bit by bit, using `op`.
+    fn bitwise(&self, rhs: &APInt, op: fn(&mut Awi, &Awi) -> Option<()>) -> APInt {
+        let mut value = self.value.clone();
+        op(&mut value, &rhs.value).expect("APInt bit widths must match");
+        APInt { value }
+    }
+
+    /// Bitwise and. Panics if the bit widths differ.
+    pub fn and(&self, rhs: &APInt) -> APInt {
+        self.bitwise(rhs, |lhs, rhs| lhs.and_(rhs))
+    }
+
+    /// Bitwise or. Panics if the bit widths differ.
+    pub fn or(&self, rhs: &APInt) -> APInt {
+        self.bitwise(rhs, |lhs, rhs| lhs.or_(rhs))
+    }
+
+    /// Bitwise exclusive or. Panics if the bit widths differ.
+    pub fn xor(&self, rhs: &APInt) -> APInt {
+        self.bitwise(rhs, |lhs, rhs| lhs.xor_(rhs))
+    }
+
+    /// Shift left by `amount` bits, or [None] if `amount` isn't less than the bit width.
+    pub fn checked_shl(&self, amount: usize) -> Option<APInt> {
+        let mut value = self.value.clone();
+        value.shl_(amount)?;
+        Some(APInt { value })
+    }
+
+    /// Shift right by `amount` bits, filling in with zeros (logical shift), or
+    /// copies of the sign bit if `signed` (arithmetic shift).
+    /// Returns [None] if `amount` isn't less than the bit width.
+    pub fn checked_shr(&self, amount: usize, signed: bool) -> Option<APInt> {
+        let mut value = self.value.clone();
+        if signed {
+            value.ashr_(amount)?;
+        } else {
+            value.lshr_(amount)?;
+        }
+        Some(APInt { value })
+    }
+
+    /// Compare with `rhs`, interpreting both as signed if `signed`.
+    /// Panics if the bit widths differ.
+    pub fn cmp(&self, rhs: &APInt, signed: bool) -> Ordering {
+        let less = if signed {
+            self.value.ilt(&rhs.value)
+        } else {
+            self.value.ult(&rhs.value)
+        };
+        match less.expect("APInt bit widths must match") {
+            true => Ordering::Less,
+            false if self == rhs => Ordering::Equal,
+            false => Ordering::Greater,
+        }
+    }
+
+    /// Resize to bit width `width`, truncating the most significant bits, or
+    /// extending with zeros, or copies of the sign bit if `signed`.
+    pub fn resize(&self, width: NonZero<usize>, signed: bool) -> APInt {
+        let mut value = Awi::zero(width);
+        if signed {
+            value.sign_resize_(&self.value);
+        } else {
+            value.zero_resize_(&self.value);
+        }
+        APInt { value }
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -358,6 +653,45 @@ mod tests {
         .assert_eq(&result.unwrap_err().to_string());
     }
 
+    #[test]
+    fn test_radix_strings() {
+        let minus_five = APInt::from_i16(-5, bw(12));
+        assert_eq!(minus_five.to_string_prefixed(Radix::Hex, true), "-0x5");
+        assert_eq!(minus_five.to_string_prefixed(Radix::Hex, false), "0xffb");
+        assert_eq!(minus_five.to_string_prefixed(Radix::Decimal, true), "-5");
+        assert_eq!(minus_five.to_string_bits(Radix::Hex), "0xffb");
+        assert_eq!(minus_five.to_string_bits(Radix::Octal), "0o7773");
+        assert_eq!(minus_five.to_string_bits(Radix::Decimal), "4091");
+
+        let five = APInt::from_u8(5, bw(8));
+        assert_eq!(five.to_string_prefixed(Radix::Binary, false), "0b101");
+        assert_eq!(five.to_string_bits(Radix::Binary), "0b00000101");
+        assert_eq!(five.to_string_bits(Radix::Hex), "0x05");
+        assert_eq!(five.to_string_bits(Radix::Octal), "0o005");
+
+        for text in ["-0x5", "0xffb", "0o7773", "4091", "-5", "+0b101"] {
+            let apint = APInt::from_str_prefixed(text, 12).unwrap();
+            let expected = if text.ends_with("101") { 5 } else { -5 };
+            assert_eq!(apint.to_i16(), expected, "{text}");
+        }
+        expect![[r#"
+            Compilation error: invalid argument.
+            APInt error: Overflow"#]]
+        .assert_eq(
+            &APInt::from_str_prefixed("0x1000", 12)
+                .unwrap_err()
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn test_be_bytes() {
+        let apint = APInt::from_u16(0x1234, bw(12));
+        assert_eq!(apint.to_be_bytes(), vec![0x02, 0x34]);
+        assert_eq!(apint.to_le_bytes(), vec![0x34, 0x02]);
+        assert_eq!(APInt::from_be_bytes(&[0x12, 0x34], bw(12)), apint);
+    }
+
     #[test]
     fn test_from_u8() {
         let width = bw(4);
@@ -447,4 +781,101 @@ mod tests {
             assert_eq!(apint.to_i128(), i);
         }
     }
+
+    #[test]
+    fn test_try_to_native() {
+        // 200 in 8 bits is -56 when signed.
+        let apint = APInt::from_u64(200, bw(8));
+        assert_eq!(apint.try_to_u64(false), Some(200));
+        assert_eq!(apint.try_to_u64(true), None);
+        assert_eq!(apint.try_to_i64(false), Some(200));
+        assert_eq!(apint.try_to_i64(true), Some(-56));
+
+        let wide = APInt::from_u128(u64::MAX as u128 + 1, bw(128));
+        assert_eq!(wide.try_to_u64(false), None);
+        assert_eq!(wide.try_to_i64(true), None);
+        let wide_neg = APInt::from_i128(i64::MIN as i128, bw(128));
+        assert_eq!(wide_neg.try_to_i64(true), Some(i64::MIN));
+        assert_eq!(wide_neg.try_to_u64(false), None);
+        let wide_max = APInt::from_u64(u64::MAX, bw(128));
+        assert_eq!(wide_max.try_to_u64(true), Some(u64::MAX));
+        assert_eq!(wide_max.try_to_i64(false), None);
+    }
+
+    #[test]
+    fn test_overflowing_arith() {
+        let width = bw(8);
+        let apint = |value: i64| APInt::from_i64(value, width);
+        let check = |(result, overflow): (APInt, bool), expected: i64, expected_overflow| {
+            assert_eq!(result, apint(expected));
+            assert_eq!(overflow, expected_overflow);
+        };
+
+        check(apint(100).overflowing_add(&apint(27), true), 127, false);
+        check(apint(100).overflowing_add(&apint(28), true), -128, true);
+        check(apint(100).overflowing_add(&apint(28), false), 128, false);
+        check(apint(-1).overflowing_add(&apint(1), false), 0, true);
+        check(apint(-1).overflowing_add(&apint(1), true), 0, false);
+
+        check(apint(1).overflowing_sub(&apint(2), false), -1, true);
+        check(apint(1).overflowing_sub(&apint(2), true), -1, false);
+        check(apint(0).overflowing_sub(&apint(-128), true), -128, true);
+        check(apint(5).overflowing_sub(&apint(5), false), 0, false);
+
+        check(apint(16).overflowing_mul(&apint(8), true), -128, true);
+        check(apint(16).overflowing_mul(&apint(8), false), 128, false);
+        check(apint(-16).overflowing_mul(&apint(8), true), -128, false);
+        check(apint(16).overflowing_mul(&apint(16), false), 0, true);
+        check(apint(-1).overflowing_mul(&apint(-1), true), 1, false);
+        check(apint(-128).overflowing_mul(&apint(-1), true), -128, true);
+    }
+
+    #[test]
+    fn test_div_bitwise_shift() {
+        let width = bw(8);
+        let apint = |value: i64| APInt::from_i64(value, width);
+
+        // 200 / 7 = 28, 200 % 7 = 4, but -56 / 7 = -8, -56 % 7 = 0.
+        assert_eq!(
+            apint(200).div_rem(&apint(7), false),
+            Some((apint(28), apint(4)))
+        );
+        assert_eq!(
+            apint(-56).div_rem(&apint(7), true),
+            Some((apint(-8), apint(0)))
+        );
+        assert_eq!(
+            apint(-7).div_rem(&apint(2), true),
+            Some((apint(-3), apint(-1)))
+        );
+        assert_eq!(
+            apint(-128).div_rem(&apint(-1), true),
+            Some((apint(-128), apint(0)))
+        );
+        assert_eq!(apint(1).div_rem(&apint(0), false), None);
+
+        assert_eq!(apint(0b1100).and(&apint(0b1010)), apint(0b1000));
+        assert_eq!(apint(0b1100).or(&apint(0b1010)), apint(0b1110));
+        assert_eq!(apint(0b1100).xor(&apint(0b1010)), apint(0b0110));
+
+        assert_eq!(apint(3).checked_shl(6), Some(apint(-64)));
+        assert_eq!(apint(3).checked_shl(8), None);
+        assert_eq!(apint(-64).checked_shr(4, false), Some(apint(12)));
+        assert_eq!(apint(-64).checked_shr(4, true), Some(apint(-4)));
+        assert_eq!(apint(-64).checked_shr(8, true), None);
+
+        assert_eq!(apint(-1).cmp(&apint(1), true), Ordering::Less);
+        assert_eq!(apint(-1).cmp(&apint(1), false), Ordering::Greater);
+        assert_eq!(apint(5).cmp(&apint(5), true), Ordering::Equal);
+
+        assert_eq!(apint(-2).resize(bw(16), true), APInt::from_i64(-2, bw(16)));
+        assert_eq!(
+            apint(-2).resize(bw(16), false),
+            APInt::from_u64(254, bw(16))
+        );
+        assert_eq!(
+            APInt::from_u64(0x1ff, bw(16)).resize(width, false),
+            apint(-1)
+        );
+    }
 }
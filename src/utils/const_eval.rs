@@ -0,0 +1,175 @@
+//! Evaluating integer operations on constants.
+//!
+//! [eval_int_bin_op] computes the result of an integer binary operation from the
+//! values of its operands. Dialects map their operations to an [IntBinOpcode] to
+//! [fold](crate::transforms::fold) them, so they agree on the semantics, which
+//! follow LLVM's [LangRef](https://llvm.org/docs/LangRef.html):
+//!   - arithmetic wraps around on overflow, but is poison if it overflows
+//!     despite its `nsw` or `nuw` [flag](OverflowFlags),
+//!   - shifting by the bit width or more is poison, and so is a flagged `shl`
+//!     shifting out set bits (`nuw`) or bits differing from the sign bit (`nsw`),
+//!   - division and remainder by zero, and signed division and remainder
+//!     of the minimum value by `-1`, are undefined behaviour, and aren't evaluated.
+
+use super::apint::{APInt, bw};
+
+/// The value an operation evaluates to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstValue {
+    Int(APInt),
+    Poison,
+}
+
+/// The integer binary operations that can be evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntBinOpcode {
+    Add,
+    Sub,
+    Mul,
+    Shl,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    And,
+    Or,
+    Xor,
+    LShr,
+    AShr,
+}
+
+/// Whether signed (`nsw`) or unsigned (`nuw`) overflow of an operation is poison.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OverflowFlags {
+    pub nsw: bool,
+    pub nuw: bool,
+}
+
+/// Evaluate the integer binary operation `opcode` on `lhs` and `rhs`,
+/// with the overflow `flags` (which are ignored for operations not taking them).
+/// Returns [None] if that's undefined behaviour. See [module](self) documentation.
+pub fn eval_int_bin_op(
+    opcode: IntBinOpcode,
+    lhs: &APInt,
+    rhs: &APInt,
+    flags: OverflowFlags,
+) -> Option<ConstValue> {
+    // The result, with whether it overflowed signed and unsigned.
+    let flagged = |(result, signed_overflow), (_, unsigned_overflow): (APInt, bool)| {
+        if (flags.nsw && signed_overflow) || (flags.nuw && unsigned_overflow) {
+            ConstValue::Poison
+        } else {
+            ConstValue::Int(result)
+        }
+    };
+    // The shift amount, if it's less than the bit width.
+    let amount = || {
+        rhs.try_to_u64(false)
+            .and_then(|amount| usize::try_from(amount).ok())
+            .filter(|amount| *amount < lhs.bw())
+    };
+    // Signed division of the minimum value by -1 overflows.
+    let signed_div_overflows =
+        || *lhs == APInt::imin(bw(lhs.bw())) && *rhs == APInt::umax(bw(rhs.bw()));
+    let value = match opcode {
+        IntBinOpcode::Add => flagged(
+            lhs.overflowing_add(rhs, true),
+            lhs.overflowing_add(rhs, false),
+        ),
+        IntBinOpcode::Sub => flagged(
+            lhs.overflowing_sub(rhs, true),
+            lhs.overflowing_sub(rhs, false),
+        ),
+        IntBinOpcode::Mul => flagged(
+            lhs.overflowing_mul(rhs, true),
+            lhs.overflowing_mul(rhs, false),
+        ),
+        IntBinOpcode::Shl => {
+            let Some(amount) = amount() else {
+                return Some(ConstValue::Poison);
+            };
+            let result = lhs.checked_shl(amount)?;
+            // Shifting back must give the original value, for no bits to be lost.
+            let signed_overflow = result.checked_shr(amount, true)? != *lhs;
+            let unsigned_overflow = result.checked_shr(amount, false)? != *lhs;
+            flagged(
+                (result.clone(), signed_overflow),
+                (result, unsigned_overflow),
+            )
+        }
+        IntBinOpcode::LShr | IntBinOpcode::AShr => match amount() {
+            Some(amount) => ConstValue::Int(lhs.checked_shr(amount, opcode == IntBinOpcode::AShr)?),
+            None => ConstValue::Poison,
+        },
+        IntBinOpcode::UDiv => ConstValue::Int(lhs.div_rem(rhs, false)?.0),
+        IntBinOpcode::URem => ConstValue::Int(lhs.div_rem(rhs, false)?.1),
+        IntBinOpcode::SDiv if !signed_div_overflows() => ConstValue::Int(lhs.div_rem(rhs, true)?.0),
+        IntBinOpcode::SRem if !signed_div_overflows() => ConstValue::Int(lhs.div_rem(rhs, true)?.1),
+        IntBinOpcode::SDiv | IntBinOpcode::SRem => return None,
+        IntBinOpcode::And => ConstValue::Int(lhs.and(rhs)),
+        IntBinOpcode::Or => ConstValue::Int(lhs.or(rhs)),
+        IntBinOpcode::Xor => ConstValue::Int(lhs.xor(rhs)),
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstValue, IntBinOpcode, OverflowFlags, eval_int_bin_op};
+    use crate::utils::apint::{APInt, bw};
+
+    #[test]
+    fn test_eval_int_bin_op() {
+        let apint = |value: i64| APInt::from_i64(value, bw(8));
+        let eval =
+            |opcode, lhs, rhs, flags| eval_int_bin_op(opcode, &apint(lhs), &apint(rhs), flags);
+        let int = |value| Some(ConstValue::Int(apint(value)));
+        let poison = || Some(ConstValue::Poison);
+        use IntBinOpcode::*;
+        let no_flag = OverflowFlags::default();
+        let nsw = OverflowFlags {
+            nsw: true,
+            nuw: false,
+        };
+        let nuw = OverflowFlags {
+            nsw: false,
+            nuw: true,
+        };
+        let both = OverflowFlags {
+            nsw: true,
+            nuw: true,
+        };
+
+        // 100 + 28 overflows signed, but not unsigned.
+        assert_eq!(eval(Add, 100, 28, no_flag), int(-128));
+        assert_eq!(eval(Add, 100, 28, nsw), poison());
+        assert_eq!(eval(Add, 100, 28, nuw), int(-128));
+        assert_eq!(eval(Add, 100, 28, both), poison());
+        assert_eq!(eval(Sub, 0, 1, nuw), poison());
+        assert_eq!(eval(Mul, -16, 8, nsw), int(-128));
+
+        // 3 << 6 = 192 loses no bits, but changes the sign.
+        assert_eq!(eval(Shl, 3, 6, nuw), int(-64));
+        assert_eq!(eval(Shl, 3, 6, nsw), poison());
+        assert_eq!(eval(Shl, -1, 7, nsw), int(-128));
+        assert_eq!(eval(Shl, -1, 7, nuw), poison());
+        assert_eq!(eval(Shl, 1, 8, no_flag), poison());
+        assert_eq!(eval(LShr, -64, 4, no_flag), int(12));
+        assert_eq!(eval(AShr, -64, 4, no_flag), int(-4));
+        assert_eq!(eval(AShr, -64, -1, no_flag), poison());
+
+        assert_eq!(eval(UDiv, -56, 7, no_flag), int(28));
+        assert_eq!(eval(SDiv, -56, 7, no_flag), int(-8));
+        assert_eq!(eval(URem, -7, 2, no_flag), int(1));
+        assert_eq!(eval(SRem, -7, 2, no_flag), int(-1));
+        assert_eq!(eval(UDiv, 1, 0, no_flag), None);
+        assert_eq!(eval(SRem, 1, 0, no_flag), None);
+        assert_eq!(eval(SDiv, -128, -1, no_flag), None);
+        assert_eq!(eval(SRem, -128, -1, no_flag), None);
+        assert_eq!(eval(UDiv, -128, -1, no_flag), int(0));
+
+        assert_eq!(eval(And, 0b1100, 0b1010, no_flag), int(0b1000));
+        assert_eq!(eval(Or, 0b1100, 0b1010, no_flag), int(0b1110));
+        assert_eq!(eval(Xor, 0b1100, 0b1010, no_flag), int(0b0110));
+    }
+}
@@ -0,0 +1,71 @@
+//! Edit distance between strings, for suggesting names close to a misspelt one.
+
+/// The [Levenshtein distance](https://en.wikipedia.org/wiki/Levenshtein_distance)
+/// between `a` and `b`, i.e., the minimum number of (character) insertions,
+/// deletions and substitutions needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // Distances from a prefix of `a` to every prefix of `b`.
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = Vec::with_capacity(b.len() + 1);
+        row.push(i + 1);
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = prev_row[j] + usize::from(a_char != *b_char);
+            let deletion = prev_row[j + 1] + 1;
+            let insertion = row[j] + 1;
+            row.push(substitution.min(deletion).min(insertion));
+        }
+        prev_row = row;
+    }
+    prev_row[b.len()]
+}
+
+/// Maximum number of suggestions returned by [closest_matches].
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Get those `candidates` that are close enough to `name` (within an edit distance
+/// of a third of its length, but at least 1), closest first. At most
+/// [MAX_SUGGESTIONS] are returned.
+pub fn closest_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut matches: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest_matches, edit_distance};
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("llvm", ""), 4);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("builtin", "biultin"), 2);
+    }
+
+    #[test]
+    fn suggestions() {
+        let candidates = ["builtin", "llvm", "test", "built"];
+        assert_eq!(closest_matches("bultin", candidates), vec!["builtin"]);
+        assert_eq!(
+            closest_matches("builti", candidates),
+            vec!["built", "builtin"]
+        );
+        assert!(closest_matches("arith", candidates).is_empty());
+    }
+}
@@ -1,5 +1,9 @@
 //! Independent support tools / utilities
 
+pub mod apfloat;
 pub mod apint;
+pub mod const_eval;
+pub mod edit_distance;
+pub mod small_box;
 pub mod trait_cast;
 pub mod vec_exns;
@@ -0,0 +1,279 @@
+//! A [Box] for trait objects that stores small values inline, without allocating.
+//!
+//! [AttrObj](crate::attribute::AttrObj)s and [OpObj](crate::op::OpObj)s are created
+//! far more often than they're large: most attributes are a few words, and an [Op](crate::op::Op)
+//! is just a [`Ptr<Operation>`](crate::operation::Operation), wrapped every time it's
+//! [looked at](crate::operation::Operation::op). A [SmallBox] keeps such values in place,
+//! and only allocates for values larger than [INLINE_WORDS] words.
+
+use std::{
+    any::Any,
+    fmt::{self, Debug},
+    marker::PhantomData,
+    mem::{ManuallyDrop, MaybeUninit, align_of, size_of},
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+use downcast_rs::Downcast;
+use dyn_clone::DynClone;
+
+/// Values of at most this many words, aligned to at most a word, are stored inline.
+pub const INLINE_WORDS: usize = 4;
+
+type Storage = MaybeUninit<[usize; INLINE_WORDS]>;
+
+/// Implemented by a trait object type, `dyn Trait`, for every type `U` implementing `Trait`,
+/// so that a [`SmallBox<dyn Trait>`](SmallBox) can be created from a `U`.
+///
+/// # Safety
+/// [coerce](Self::coerce) must return its argument, unsized to `Self`.
+pub unsafe trait Coerce<U> {
+    fn coerce(ptr: *mut U) -> *mut Self;
+
+    /// How to clone a `U`, for `Self` that's [DynClone], so that its boxes can be [cloned](Clone).
+    const CLONE: Option<fn(&U) -> U> = None;
+}
+
+/// Functions to get at, drop and clone the `U` a [SmallBox] was created from.
+struct VTable<T: ?Sized + 'static> {
+    /// The value, given a pointer to the storage.
+    get: unsafe fn(*mut u8) -> *mut T,
+    /// Drop the value, given a pointer to the storage.
+    drop: unsafe fn(*mut u8),
+    /// Clone the value, given a pointer to the storage.
+    clone: unsafe fn(*const u8) -> SmallBox<T>,
+}
+
+/// The [VTable] for `U`.
+struct VTableOf<T: ?Sized + 'static, U>(PhantomData<T>, PhantomData<U>);
+
+impl<T: ?Sized + Coerce<U> + 'static, U> VTableOf<T, U> {
+    const VTABLE: &'static VTable<T> = &VTable {
+        get: get::<T, U>,
+        drop: drop::<U>,
+        clone: clone::<T, U>,
+    };
+}
+
+/// Is a `U` stored inline?
+const fn is_inline<U>() -> bool {
+    size_of::<U>() <= size_of::<Storage>() && align_of::<U>() <= align_of::<Storage>()
+}
+
+/// The `U` in `storage`.
+unsafe fn value<U>(storage: *mut u8) -> *mut U {
+    if is_inline::<U>() {
+        storage.cast()
+    } else {
+        unsafe { *storage.cast::<*mut U>() }
+    }
+}
+
+unsafe fn get<T: ?Sized + Coerce<U>, U>(storage: *mut u8) -> *mut T {
+    T::coerce(unsafe { value::<U>(storage) })
+}
+
+unsafe fn drop<U>(storage: *mut u8) {
+    if is_inline::<U>() {
+        unsafe { ptr::drop_in_place(storage.cast::<U>()) }
+    } else {
+        std::mem::drop(unsafe { Box::from_raw(value::<U>(storage)) })
+    }
+}
+
+unsafe fn clone<T: ?Sized + Coerce<U> + 'static, U>(storage: *const u8) -> SmallBox<T> {
+    let clone = T::CLONE.expect("Coerce::CLONE must be specified for DynClone trait objects");
+    SmallBox::new(clone(unsafe { &*value::<U>(storage.cast_mut()) }))
+}
+
+/// An owned `T` (a trait object type), stored inline if it's at most [INLINE_WORDS] words,
+/// and on the heap otherwise.
+pub struct SmallBox<T: ?Sized + 'static> {
+    /// The value if it's stored inline, and a pointer to it otherwise.
+    storage: Storage,
+    vtable: &'static VTable<T>,
+    _owns: PhantomData<T>,
+}
+
+impl<T: ?Sized + 'static> SmallBox<T> {
+    /// Box `value`.
+    pub fn new<U>(value: U) -> Self
+    where
+        T: Coerce<U>,
+    {
+        let mut storage = Storage::uninit();
+        if is_inline::<U>() {
+            unsafe { storage.as_mut_ptr().cast::<U>().write(value) }
+        } else {
+            let boxed = Box::into_raw(Box::new(value));
+            unsafe { storage.as_mut_ptr().cast::<*mut U>().write(boxed) }
+        }
+        SmallBox {
+            storage,
+            vtable: VTableOf::<T, U>::VTABLE,
+            _owns: PhantomData,
+        }
+    }
+
+    /// Is the value stored inline, rather than on the heap?
+    pub fn is_inline(&self) -> bool {
+        let storage = self.storage.as_ptr();
+        ptr::addr_eq(&**self, storage)
+    }
+}
+
+impl<T: ?Sized + Downcast> SmallBox<T> {
+    /// Move the value out, if it's a `U`.
+    pub fn downcast<U: Any>(self) -> Result<U, Self> {
+        if !(*self).as_any().is::<U>() {
+            return Err(self);
+        }
+        let mut this = ManuallyDrop::new(self);
+        unsafe {
+            let value = value::<U>(this.storage.as_mut_ptr().cast());
+            if is_inline::<U>() {
+                Ok(value.read())
+            } else {
+                Ok(*Box::from_raw(value))
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + 'static> Deref for SmallBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(self.vtable.get)(self.storage.as_ptr().cast_mut().cast()) }
+    }
+}
+
+impl<T: ?Sized + 'static> DerefMut for SmallBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *(self.vtable.get)(self.storage.as_mut_ptr().cast()) }
+    }
+}
+
+impl<T: ?Sized + 'static> AsRef<T> for SmallBox<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized + 'static> AsMut<T> for SmallBox<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized + 'static> Drop for SmallBox<T> {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.drop)(self.storage.as_mut_ptr().cast()) }
+    }
+}
+
+impl<T: ?Sized + DynClone + 'static> Clone for SmallBox<T> {
+    fn clone(&self) -> Self {
+        unsafe { (self.vtable.clone)(self.storage.as_ptr().cast()) }
+    }
+}
+
+impl<T: ?Sized + Debug + 'static> Debug for SmallBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use downcast_rs::Downcast;
+    use dyn_clone::DynClone;
+
+    use super::{Coerce, INLINE_WORDS, SmallBox};
+
+    trait Counted: DynClone + Downcast {
+        fn count(&self) -> usize;
+    }
+
+    unsafe impl<U: Counted + 'static> Coerce<U> for dyn Counted {
+        fn coerce(ptr: *mut U) -> *mut Self {
+            ptr
+        }
+
+        const CLONE: Option<fn(&U) -> U> = Some(dyn_clone::clone);
+    }
+
+    /// Counts how many of its clones are alive, padded to `N` words.
+    struct Alive<const N: usize>(Rc<Cell<usize>>, [usize; N]);
+
+    impl<const N: usize> Alive<N> {
+        fn new(alive: &Rc<Cell<usize>>) -> Self {
+            alive.set(alive.get() + 1);
+            Alive(alive.clone(), [N; N])
+        }
+    }
+
+    impl<const N: usize> Clone for Alive<N> {
+        fn clone(&self) -> Self {
+            Alive::new(&self.0)
+        }
+    }
+
+    impl<const N: usize> Counted for Alive<N> {
+        fn count(&self) -> usize {
+            self.1.iter().sum()
+        }
+    }
+
+    impl<const N: usize> Drop for Alive<N> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() - 1);
+        }
+    }
+
+    #[test]
+    fn inline_and_heap() {
+        let alive = Rc::new(Cell::new(0));
+        // The [Rc] takes up a word.
+        let small: SmallBox<dyn Counted> =
+            SmallBox::new(Alive::<{ INLINE_WORDS - 1 }>::new(&alive));
+        let large: SmallBox<dyn Counted> = SmallBox::new(Alive::<INLINE_WORDS>::new(&alive));
+        assert!(small.is_inline() && !large.is_inline());
+
+        // Values stay reachable as the boxes move.
+        let boxes = vec![small, large];
+        let clones = boxes.clone();
+        assert_eq!(alive.get(), 4);
+        for (boxed, clone) in boxes.iter().zip(&clones) {
+            assert_eq!(boxed.count(), clone.count());
+            assert_eq!(boxed.is_inline(), clone.is_inline());
+        }
+        assert_eq!(boxes[0].count(), (INLINE_WORDS - 1) * (INLINE_WORDS - 1));
+        assert_eq!(boxes[1].count(), INLINE_WORDS * INLINE_WORDS);
+
+        std::mem::drop(boxes);
+        assert_eq!(alive.get(), 2);
+        std::mem::drop(clones);
+        assert_eq!(alive.get(), 0);
+    }
+
+    #[test]
+    fn downcast() {
+        let alive = Rc::new(Cell::new(0));
+        let small: SmallBox<dyn Counted> = SmallBox::new(Alive::<1>::new(&alive));
+        let large: SmallBox<dyn Counted> = SmallBox::new(Alive::<INLINE_WORDS>::new(&alive));
+        let small = small.downcast::<Alive<2>>().err().unwrap();
+        let small = small.downcast::<Alive<1>>().ok().unwrap();
+        let large = large.downcast::<Alive<INLINE_WORDS>>().ok().unwrap();
+        assert_eq!(alive.get(), 2);
+        assert_eq!(
+            small.count() + large.count(),
+            1 + INLINE_WORDS * INLINE_WORDS
+        );
+        std::mem::drop((small, large));
+        assert_eq!(alive.get(), 0);
+    }
+}
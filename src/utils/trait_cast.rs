@@ -55,6 +55,12 @@ pub fn any_to_trait<T: ?Sized + 'static>(r: &dyn Any) -> Option<&T> {
         })
 }
 
+/// Has [type_to_trait](crate::type_to_trait) been specified for the type `ty` and
+/// the trait (object type) `to_trait`?
+pub(crate) fn type_casts_to(ty: TypeId, to_trait: TypeId) -> bool {
+    TRAIT_CASTERS_MAP.contains_key(&(ty, to_trait))
+}
+
 pub trait ClonableAny: Any + DynClone + Downcast {}
 dyn_clone::clone_trait_object!(ClonableAny);
 impl<T: Any + DynClone + Downcast> ClonableAny for T {}
@@ -5,12 +5,26 @@
 //!   - [`Ptr<BasicBlock>`] describes a block definition.
 //!     Its uses are in the predecessor branch operations.
 //!   - [Use] describes the use of a definition.
-//!     This may describe either a [Value] use (as operand in an [Operation])
-//!     or a [BasicBlock] use (as successor of an [Operation]).
-
-use rustc_hash::FxHashSet;
+//!     This may describe either a [Value] use (an [OpOperand], as operand in an [Operation])
+//!     or a [BasicBlock] use (a [BlockOperand], as successor of an [Operation]).
+//!
+//! A [Use] is a handle that can be rewired on its own, for example, to replace
+//! only some of the uses of a value:
+//! ```
+//! # use pliron::{context::{Context, Ptr}, region::Region, value::Value};
+//! fn replace_uses_outside(ctx: &Context, value: Value, region: Ptr<Region>, other: Value) {
+//!     for r#use in value.uses(ctx) {
+//!         if !r#use.op.deref(ctx).is_in_region(ctx, region) {
+//!             r#use.set(ctx, other);
+//!         }
+//!     }
+//! }
+//! ```
+
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
-    cell::{Ref, RefMut},
+    cell::Ref,
+    fmt::{self, Debug},
     hash::Hash,
     marker::PhantomData,
 };
@@ -18,11 +32,12 @@ use std::{
 use crate::{
     basic_block::BasicBlock,
     common_traits::Named,
-    context::{Context, Ptr},
+    context::{Context, DebugWithContext, Ptr, PtrRefMut},
     identifier::Identifier,
     linked_list::{ContainsLinkedList, LinkedList},
     operation::Operation,
-    printable::Printable,
+    printable::{Highlight, Printable, State},
+    region::Region,
     r#type::{TypeObj, Typed},
 };
 
@@ -102,7 +117,7 @@ impl<T: DefUseParticipant> DefNode<T> {
 /// Interface for [UseNode] wrappers.
 pub(crate) trait UseTrait: DefUseParticipant {
     /// Get a mutable reference to the [UseNode] described by this  use.
-    fn usenode_mut<'a>(r#use: &Use<Self>, ctx: &'a Context) -> RefMut<'a, UseNode<Self>>;
+    fn usenode_mut<'a>(r#use: &Use<Self>, ctx: &'a Context) -> PtrRefMut<'a, UseNode<Self>>;
 }
 
 /// Interface for [DefNode] wrappers.
@@ -110,11 +125,13 @@ pub(crate) trait DefTrait: DefUseParticipant {
     /// Get a reference to the underlying [DefNode].
     fn defnode_ref<'a>(&self, ctx: &'a Context) -> Ref<'a, DefNode<Self>>;
     /// Get a mutable reference to the underlying [DefNode].
-    fn defnode_mut<'a>(&self, ctx: &'a Context) -> RefMut<'a, DefNode<Self>>;
+    fn defnode_mut<'a>(&self, ctx: &'a Context) -> PtrRefMut<'a, DefNode<Self>>;
 }
 
 /// Describes a value definition.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// Formatted [with a Context](DebugWithContext::dbg_with),
+/// its definition site is shown, such as `op#12 (llvm.add) result 0`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Value {
     OpResult {
         op: Ptr<Operation>,
@@ -132,9 +149,10 @@ impl Value {
         self.defnode_ref(ctx).num_uses()
     }
 
-    /// Get all uses of this value.
-    pub fn uses(&self, ctx: &Context) -> Vec<Use<Value>> {
-        self.defnode_ref(ctx).uses().collect()
+    /// Get all uses of this value. The uses are collected first,
+    /// so they may be [rewired](Use::set) while iterating.
+    pub fn uses(&self, ctx: &Context) -> impl Iterator<Item = OpOperand> + use<> {
+        self.defnode_ref(ctx).uses().collect::<Vec<_>>().into_iter()
     }
 
     /// Does this definition have any [Use]?
@@ -166,6 +184,28 @@ impl Value {
     pub fn replace_use_with(&self, ctx: &Context, r#use: Use<Value>, other: &Value) {
         DefNode::replace_use_with(ctx, self, &r#use, other);
     }
+
+    /// Replace the uses of this value inside `region` (at any depth) with `other`.
+    pub fn replace_uses_in_region_with(&self, ctx: &Context, region: Ptr<Region>, other: &Value) {
+        self.replace_some_uses_with(
+            ctx,
+            |ctx, r#use| r#use.op.deref(ctx).is_in_region(ctx, region),
+            other,
+        );
+    }
+}
+
+impl DebugWithContext for Value {
+    fn fmt_debug(&self, ctx: &Context, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::OpResult { op, res_idx } => {
+                write!(f, "{:?} result {}", op.dbg_with(ctx), res_idx)
+            }
+            Value::BlockArgument { block, arg_idx } => {
+                write!(f, "{:?} arg {}", block.dbg_with(ctx), arg_idx)
+            }
+        }
+    }
 }
 
 impl Typed for Value {
@@ -203,10 +243,59 @@ impl Printable for Value {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &crate::printable::State,
+        state: &crate::printable::State,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
-        write!(f, "{}", self.unique_name(ctx))
+        state.highlighted(Highlight::Value, f, |f| {
+            write!(f, "{}", self.printed_name(ctx, state))
+        })
+    }
+}
+
+impl Value {
+    /// The name that this value is printed with. That's the name assigned to it
+    /// when [numbering values](State::set_numbered_values), and otherwise,
+    /// its [unique name](Named::unique_name).
+    pub fn printed_name(&self, ctx: &Context, state: &State) -> Identifier {
+        state
+            .value_name(ctx, *self)
+            .unwrap_or_else(|| self.unique_name(ctx))
+    }
+}
+
+/// Names of [Value]s, assigned in the order that the values are first printed in.
+#[derive(Clone, Default)]
+pub(crate) struct ValueNames {
+    names: FxHashMap<Value, Identifier>,
+    taken: FxHashSet<Identifier>,
+    next_num: usize,
+}
+
+impl ValueNames {
+    /// The name of `value`, assigning it one if it has none yet. A value is named
+    /// by its [given name](Named::given_name), suffixed with `_<N>` if that's taken,
+    /// and a value without a given name is named `v<N>`.
+    pub(crate) fn name(&mut self, ctx: &Context, value: Value) -> Identifier {
+        if let Some(name) = self.names.get(&value) {
+            return *name;
+        }
+        let name = match value.given_name(ctx) {
+            Some(given) if !self.taken.contains(&given) => given,
+            Some(given) => (1..)
+                .map(|n| Identifier::try_from(format!("{}_{}", given, n)).unwrap())
+                .find(|name| !self.taken.contains(name))
+                .unwrap(),
+            None => loop {
+                let name = Identifier::try_from(format!("v{}", self.next_num)).unwrap();
+                self.next_num += 1;
+                if !self.taken.contains(&name) {
+                    break name;
+                }
+            },
+        };
+        self.taken.insert(name);
+        self.names.insert(value, name);
+        name
     }
 }
 
@@ -224,24 +313,24 @@ impl DefTrait for Value {
         }
     }
 
-    fn defnode_mut<'a>(&self, ctx: &'a Context) -> RefMut<'a, DefNode<Self>> {
+    fn defnode_mut<'a>(&self, ctx: &'a Context) -> PtrRefMut<'a, DefNode<Self>> {
         match self {
             Self::OpResult { op, res_idx } => {
                 let op = op.deref_mut(ctx);
-                RefMut::map(op, |opref| &mut opref.result_mut(*res_idx).def)
+                PtrRefMut::map(op, |opref| &mut opref.result_mut(*res_idx).def)
             }
             Self::BlockArgument { block, arg_idx } => {
                 let block = block.deref_mut(ctx);
-                RefMut::map(block, |blockref| &mut blockref.argument_mut(*arg_idx).def)
+                PtrRefMut::map(block, |blockref| &mut blockref.argument_mut(*arg_idx).def)
             }
         }
     }
 }
 
 impl UseTrait for Value {
-    fn usenode_mut<'a>(r#use: &Use<Self>, ctx: &'a Context) -> RefMut<'a, UseNode<Value>> {
+    fn usenode_mut<'a>(r#use: &Use<Self>, ctx: &'a Context) -> PtrRefMut<'a, UseNode<Value>> {
         let op = r#use.op.deref_mut(ctx);
-        RefMut::map(op, |opref| &mut opref.operand_mut(r#use.opd_idx).r#use)
+        PtrRefMut::map(op, |opref| &mut opref.operand_mut(r#use.opd_idx).r#use)
     }
 }
 
@@ -337,9 +426,9 @@ impl DefTrait for Ptr<BasicBlock> {
         Ref::map(block, |blockref| &blockref.preds)
     }
 
-    fn defnode_mut<'a>(&self, ctx: &'a Context) -> RefMut<'a, DefNode<Self>> {
+    fn defnode_mut<'a>(&self, ctx: &'a Context) -> PtrRefMut<'a, DefNode<Self>> {
         let block = self.deref_mut(ctx);
-        RefMut::map(block, |blockref| &mut blockref.preds)
+        PtrRefMut::map(block, |blockref| &mut blockref.preds)
     }
 }
 
@@ -347,9 +436,9 @@ impl UseTrait for Ptr<BasicBlock> {
     fn usenode_mut<'a>(
         r#use: &Use<Ptr<BasicBlock>>,
         ctx: &'a Context,
-    ) -> RefMut<'a, UseNode<Ptr<BasicBlock>>> {
+    ) -> PtrRefMut<'a, UseNode<Ptr<BasicBlock>>> {
         let op = r#use.op.deref_mut(ctx);
-        RefMut::map(op, |opref| &mut opref.successor_mut(r#use.opd_idx).r#use)
+        PtrRefMut::map(op, |opref| &mut opref.successor_mut(r#use.opd_idx).r#use)
     }
 }
 
@@ -366,7 +455,15 @@ impl<T: DefUseParticipant> UseNode<T> {
     }
 }
 
+/// A [Use] of a [Value], as an operand of an [Operation].
+pub type OpOperand = Use<Value>;
+
+/// A [Use] of a [BasicBlock], as a successor of an [Operation].
+pub type BlockOperand = Use<Ptr<BasicBlock>>;
+
 /// Describes a [Value] or [BasicBlock] use.
+/// Formatted [with a Context](DebugWithContext::dbg_with), the definition used
+/// is shown too, such as `op#13 (llvm.ret) operand 0 = op#12 (llvm.add) result 0`.
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub struct Use<T: DefUseParticipant> {
     /// Uses of a def can only be in an operation.
@@ -375,3 +472,70 @@ pub struct Use<T: DefUseParticipant> {
     pub opd_idx: usize,
     pub(crate) _dummy: PhantomData<T>,
 }
+
+impl<T: DefUseParticipant> Debug for Use<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Use")
+            .field("op", &self.op)
+            .field("opd_idx", &self.opd_idx)
+            .finish()
+    }
+}
+
+impl Use<Value> {
+    /// The [Value] used by this operand.
+    pub fn get(&self, ctx: &Context) -> Value {
+        self.op.deref(ctx).operand(self.opd_idx)
+    }
+
+    /// Make this operand use `value` instead. Other uses of the
+    /// current value are unchanged.
+    pub fn set(&self, ctx: &Context, value: Value) {
+        Operation::replace_operand(self.op, ctx, self.opd_idx, value);
+    }
+}
+
+impl Use<Ptr<BasicBlock>> {
+    /// The [BasicBlock] used by this successor.
+    pub fn get(&self, ctx: &Context) -> Ptr<BasicBlock> {
+        self.op.deref(ctx).successor(self.opd_idx)
+    }
+
+    /// Make this successor `block` instead. Other predecessors of the
+    /// current block are unchanged.
+    pub fn set(&self, ctx: &Context, block: Ptr<BasicBlock>) {
+        Operation::replace_successor(self.op, ctx, self.opd_idx, block);
+    }
+}
+
+impl DebugWithContext for Use<Value> {
+    fn fmt_debug(&self, ctx: &Context, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} operand {}", self.op.dbg_with(ctx), self.opd_idx)?;
+        let def = self
+            .op
+            .is_live(ctx)
+            .then(|| self.op.try_deref(ctx))
+            .flatten()
+            .and_then(|op| op.operands().nth(self.opd_idx));
+        match def {
+            Some(def) => write!(f, " = {:?}", def.dbg_with(ctx)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DebugWithContext for Use<Ptr<BasicBlock>> {
+    fn fmt_debug(&self, ctx: &Context, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} successor {}", self.op.dbg_with(ctx), self.opd_idx)?;
+        let def = self
+            .op
+            .is_live(ctx)
+            .then(|| self.op.try_deref(ctx))
+            .flatten()
+            .and_then(|op| op.successors().nth(self.opd_idx));
+        match def {
+            Some(def) => write!(f, " = {:?}", def.dbg_with(ctx)),
+            None => Ok(()),
+        }
+    }
+}
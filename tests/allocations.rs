@@ -0,0 +1,141 @@
+//! Count heap allocations made when constructing and printing attributes and ops.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use pliron::{
+    attribute::{AttrObj, Attribute, AttributeDict},
+    builtin::{
+        self,
+        attributes::{IntegerAttr, StringAttr, TypeAttr, UnitAttr},
+        ops::ModuleOp,
+        types::{IntegerType, Signedness},
+    },
+    context::Context,
+    identifier::Identifier,
+    op::Op,
+    operation::Operation,
+    printable::Printable,
+    utils::apint::APInt,
+};
+
+/// A [GlobalAlloc] that counts the number of allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static NUM_ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        NUM_ALLOCS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of allocations made by the current thread while running `f`.
+fn count_allocs<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = NUM_ALLOCS.with(|n| n.get());
+    let res = f();
+    (res, NUM_ALLOCS.with(|n| n.get()) - before)
+}
+
+const NUM_ATTRS: usize = 1_000_000;
+
+#[test]
+fn construct_attributes() {
+    let ctx = &mut Context::new();
+    builtin::register(ctx);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+
+    // Boxing each attribute on the heap costs an allocation per attribute.
+    let mut boxed: Vec<Box<dyn Attribute>> = Vec::with_capacity(NUM_ATTRS);
+    let ((), boxed_allocs) = count_allocs(|| {
+        for _ in 0..NUM_ATTRS {
+            boxed.push(Box::new(TypeAttr::new(i64_ty.into())));
+        }
+    });
+    assert_eq!(boxed_allocs, NUM_ATTRS);
+
+    // Small attributes are stored in their [AttrObj], without allocating.
+    let mut attrs: Vec<AttrObj> = Vec::with_capacity(3 * NUM_ATTRS);
+    let ((), type_allocs) = count_allocs(|| {
+        for _ in 0..NUM_ATTRS {
+            attrs.push(TypeAttr::new(i64_ty.into()).into());
+        }
+    });
+    assert_eq!(type_allocs, 0);
+    let ((), unit_allocs) = count_allocs(|| {
+        for _ in 0..NUM_ATTRS {
+            attrs.push(UnitAttr::new().into());
+        }
+    });
+    assert_eq!(unit_allocs, 0);
+    let ((), string_allocs) = count_allocs(|| {
+        for _ in 0..NUM_ATTRS {
+            attrs.push(StringAttr::new(String::new()).into());
+        }
+    });
+    assert_eq!(string_allocs, 0);
+
+    let int_attr: AttrObj =
+        IntegerAttr::new(i64_ty, APInt::from_i64(5, 64.try_into().unwrap())).into();
+    assert!(attrs.iter().chain([&int_attr]).all(|attr| attr.is_inline()));
+}
+
+// Wrapping an [Operation] in its [Op] doesn't allocate.
+#[test]
+fn wrap_operations() {
+    let ctx = &mut Context::new();
+    builtin::register(ctx);
+    let module = ModuleOp::new(ctx, &"m".try_into().unwrap());
+    let module_op = module.operation();
+
+    let (wrapped, op_allocs) = count_allocs(|| {
+        (0..NUM_ATTRS / 1000)
+            .filter(|_| {
+                let op = Operation::op(module_op, ctx);
+                op.downcast_ref::<ModuleOp>()
+                    .is_some_and(|module| module.operation() == module_op)
+            })
+            .count()
+    });
+    assert_eq!(wrapped, NUM_ATTRS / 1000);
+    assert_eq!(op_allocs, 0);
+}
+
+#[test]
+fn print_attribute_dict() {
+    let ctx = &mut Context::new();
+    builtin::register(ctx);
+
+    const NUM_ENTRIES: usize = 1000;
+    let mut dict = AttributeDict::default();
+    for i in 0..NUM_ENTRIES {
+        let key: Identifier = format!("key{i}").try_into().unwrap();
+        dict.set(key, UnitAttr::new());
+    }
+
+    // Don't count one-time initializations (such as of interface casts) made by printing.
+    let _ = dict.disp(ctx).to_string();
+    let mut printed = String::with_capacity(64 * NUM_ENTRIES);
+    let ((), print_allocs) = count_allocs(|| {
+        use std::fmt::Write;
+        write!(printed, "{}", dict.disp(ctx)).unwrap();
+    });
+    // Printing an [AttrId](pliron::attribute::AttrId) allocates its two name strings.
+    // Other than that, entries must be printed without cloning them.
+    assert!(
+        print_allocs <= 2 * NUM_ENTRIES + 8,
+        "printing {NUM_ENTRIES} entries made {print_allocs} allocations"
+    );
+}
@@ -1,7 +1,7 @@
 use std::sync::LazyLock;
 
 use awint::bw;
-use pliron::derive::{def_op, derive_op_interface_impl};
+use pliron::derive::{def_op, derive_op_interface_impl, op_interface_impl};
 use pliron::utils::apint::APInt;
 use pliron::{
     attribute::AttrObj,
@@ -9,7 +9,7 @@ use pliron::{
         self,
         attributes::IntegerAttr,
         op_interfaces::{
-            IsTerminatorInterface, OneResultInterface, OneResultVerifyErr,
+            ConstantLikeInterface, IsTerminatorInterface, OneResultInterface, OneResultVerifyErr,
             SingleBlockRegionInterface, ZeroOpdInterface,
         },
         ops::{FuncOp, ModuleOp},
@@ -21,7 +21,10 @@ use pliron::{
     dialect::{Dialect, DialectName},
     identifier::Identifier,
     impl_verify_succ, input_err,
-    irfmt::parsers::{attr_parser, process_parsed_ssa_defs},
+    irfmt::{
+        parsers::{attr_parser, process_parsed_ssa_defs},
+        printers::op::results,
+    },
     location::{Located, Location},
     op::{Op, OpObj},
     operation::Operation,
@@ -67,7 +70,7 @@ impl ConstantOp {
         op.deref_mut(ctx)
             .attributes
             .0
-            .insert(Self::ATTR_KEY_VALUE.clone(), Box::new(int_attr));
+            .insert(*Self::ATTR_KEY_VALUE, int_attr.into());
         ConstantOp { op }
     }
 
@@ -76,19 +79,26 @@ impl ConstantOp {
         op.attributes.0.get(&Self::ATTR_KEY_VALUE).unwrap().clone()
     }
 }
+#[op_interface_impl]
+impl ConstantLikeInterface for ConstantOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        self.get_value(ctx)
+    }
+}
+
 impl Printable for ConstantOp {
     fn fmt(
         &self,
         ctx: &Context,
-        _state: &printable::State,
+        state: &printable::State,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
         write!(
             f,
             "{} = {} {}",
-            self.result(ctx).disp(ctx),
-            self.opid().disp(ctx),
-            self.get_value(ctx).disp(ctx)
+            results(self).print(ctx, state),
+            self.opid().print(ctx, state),
+            self.get_value(ctx).print(ctx, state)
         )
     }
 }
@@ -118,11 +128,11 @@ impl Parsable for ConstantOp {
                 attr.disp(state_stream.state.ctx)
             )?,
         };
-        let int_val: u64 = Into::<APInt>::into(*int_attr).to_u64();
-        let op = Box::new(Self::new(state_stream.state.ctx, int_val));
+        let int_val: u64 = Into::<APInt>::into(int_attr).to_u64();
+        let op = Self::new(state_stream.state.ctx, int_val);
         process_parsed_ssa_defs(state_stream, &results, op.operation())?;
 
-        Ok(op as OpObj).into_parse_result()
+        Ok(OpObj::new(op)).into_parse_result()
     }
 }
 
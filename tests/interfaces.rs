@@ -5,21 +5,31 @@ use std::sync::{LazyLock, Mutex};
 use common::ReturnOp;
 use expect_test::expect;
 use pliron::derive::{
-    attr_interface, attr_interface_impl, def_attribute, def_op, def_type, derive_op_interface_impl,
-    op_interface, op_interface_impl, type_interface, type_interface_impl,
+    attr_interface, attr_interface_impl, def_attribute, def_op, def_type,
+    derive_attr_interface_impl, derive_op_interface_impl, derive_type_interface_impl, op_interface,
+    op_interface_impl, type_interface, type_interface_impl,
 };
 use pliron::verify_err;
 use pliron::{
-    attribute::Attribute,
+    attribute::{AttrObj, Attribute, attr_cast},
+    builtin::type_interfaces::DataLayout,
     builtin::{
         attr_interfaces::TypedAttrInterface,
-        attributes::{IntegerAttr, StringAttr},
-        op_interfaces::{OneResultInterface, OneResultVerifyErr},
-        ops::ModuleOp,
+        attributes::{IdentifierAttr, IntegerAttr, OperandBundlesAttr, StringAttr},
+        op_interfaces::{
+            ATTR_KEY_OPERAND_BUNDLES, OneResultInterface, OneResultVerifyErr, OpEquivalence,
+            OperandBundle, OperandBundleInterface, SingleBlockRegionInterface, SymbolOpInterface,
+            SymbolTableInterface, SymbolUserOpInterface, op_equivalence_hash, ops_equivalent,
+            structural_equivalent,
+        },
+        ops::{FuncOp, ModuleOp},
+        symbol_table::{SymbolTable, lookup_symbol_in_scope, nearest_symbol_table},
         types::{IntegerType, UnitType},
     },
     common_traits::Verify,
     context::{Context, Ptr},
+    cost::{CostModel, OpCostInterface, TargetCostModel},
+    debug_info::set_operation_result_name,
     identifier::Identifier,
     impl_canonical_syntax, impl_verify_succ,
     location::Location,
@@ -28,8 +38,10 @@ use pliron::{
     parsable::{Parsable, ParseResult, StateStream},
     printable::{self, Printable},
     result::{Error, ErrorKind, Result},
-    r#type::{Type, TypeObj},
+    target::{RequiresFeaturesInterface, TargetFeatures, is_legal, missing_features},
+    r#type::{Type, TypeObj, TypePtr, Typed, type_cast},
     utils::trait_cast::any_to_trait,
+    value::Value,
 };
 use pliron_derive::format_attribute;
 use thiserror::Error;
@@ -336,6 +348,290 @@ fn test_type_intr_verify_order() -> Result<()> {
     Ok(())
 }
 
+// Diamond shaped interface hierarchies:
+//      Top
+//     /   \
+//  Left   Right
+//     \   /
+//     Bottom
+
+static TEST_OP_DIAMOND_OUTPUT: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new("".into()));
+
+#[op_interface]
+trait DiamondOpTop {
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_OP_DIAMOND_OUTPUT.lock().unwrap() += "DiamondOpTop verified\n";
+        Ok(())
+    }
+}
+
+#[op_interface]
+trait DiamondOpLeft: DiamondOpTop {
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_OP_DIAMOND_OUTPUT.lock().unwrap() += "DiamondOpLeft verified\n";
+        Ok(())
+    }
+}
+
+#[op_interface]
+trait DiamondOpRight: DiamondOpTop {
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_OP_DIAMOND_OUTPUT.lock().unwrap() += "DiamondOpRight verified\n";
+        Ok(())
+    }
+}
+
+#[op_interface]
+trait DiamondOpBottom: DiamondOpLeft + DiamondOpRight {
+    fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_OP_DIAMOND_OUTPUT.lock().unwrap() += "DiamondOpBottom verified\n";
+        Ok(())
+    }
+}
+
+#[def_op("test.diamond_intr_op")]
+#[derive_op_interface_impl(DiamondOpBottom, DiamondOpRight, DiamondOpLeft, DiamondOpTop)]
+struct DiamondIntrOp {}
+impl_canonical_syntax!(DiamondIntrOp);
+impl_verify_succ!(DiamondIntrOp);
+
+#[test]
+fn test_op_intr_diamond() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    DiamondIntrOp::register(ctx, DiamondIntrOp::parser_fn);
+
+    let op = Operation::new(ctx, DiamondIntrOp::opid_static(), vec![], vec![], vec![], 0);
+    op.deref(ctx).verify(ctx)?;
+
+    let output = TEST_OP_DIAMOND_OUTPUT.lock().unwrap().clone();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "DiamondOpTop verified");
+    assert_eq!(lines[3], "DiamondOpBottom verified");
+
+    // Cast to every interface in the hierarchy, starting from the bottom.
+    let op_obj = Operation::op(op, ctx);
+    let bottom = op_cast::<dyn DiamondOpBottom>(&*op_obj).unwrap();
+    assert!(op_cast::<dyn DiamondOpLeft>(bottom).is_some());
+    assert!(op_cast::<dyn DiamondOpRight>(bottom).is_some());
+    assert!(op_cast::<dyn DiamondOpTop>(bottom).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_op_implemented_interfaces() {
+    let ctx = &mut setup_context_dialects();
+    DiamondIntrOp::register(ctx, DiamondIntrOp::parser_fn);
+
+    // Queries on the OpId don't need an instance.
+    let opid = DiamondIntrOp::opid_static();
+    assert!(opid.implements::<dyn DiamondOpLeft>());
+    assert!(!opid.implements::<dyn OneResultInterface>());
+    assert!(ReturnOp::opid_static().implements::<dyn TestOpInterfaceX>());
+    assert!(!ReturnOp::opid_static().implements::<dyn DiamondOpTop>());
+
+    // Super-interfaces are listed first.
+    let interfaces = opid.implemented_interfaces();
+    assert_eq!(interfaces.len(), 4);
+    assert_eq!(interfaces[0], "DiamondOpTop");
+    assert_eq!(interfaces[3], "DiamondOpBottom");
+    assert!(interfaces.contains(&"DiamondOpLeft") && interfaces.contains(&"DiamondOpRight"));
+
+    let op = Operation::new(ctx, opid, vec![], vec![], vec![], 0);
+    assert_eq!(Operation::op(op, ctx).implemented_interfaces(), interfaces);
+    assert!(
+        ModuleOp::opid_static()
+            .implemented_interfaces()
+            .contains(&"SymbolTableInterface")
+    );
+}
+
+static TEST_ATTR_DIAMOND_OUTPUT: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new("".into()));
+
+#[attr_interface]
+trait DiamondAttrTop {
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_ATTR_DIAMOND_OUTPUT.lock().unwrap() += "DiamondAttrTop verified\n";
+        Ok(())
+    }
+}
+
+#[attr_interface]
+trait DiamondAttrLeft: DiamondAttrTop {
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_ATTR_DIAMOND_OUTPUT.lock().unwrap() += "DiamondAttrLeft verified\n";
+        Ok(())
+    }
+}
+
+#[attr_interface]
+trait DiamondAttrRight: DiamondAttrTop {
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_ATTR_DIAMOND_OUTPUT.lock().unwrap() += "DiamondAttrRight verified\n";
+        Ok(())
+    }
+}
+
+#[attr_interface]
+trait DiamondAttrBottom: DiamondAttrLeft + DiamondAttrRight {
+    fn verify(_attr: &dyn Attribute, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_ATTR_DIAMOND_OUTPUT.lock().unwrap() += "DiamondAttrBottom verified\n";
+        Ok(())
+    }
+}
+
+#[def_attribute("test.diamond_intr_attr")]
+#[derive_attr_interface_impl(DiamondAttrBottom, DiamondAttrRight, DiamondAttrLeft, DiamondAttrTop)]
+#[derive(PartialEq, Clone, Debug)]
+struct DiamondIntrAttr {}
+impl_verify_succ!(DiamondIntrAttr);
+
+impl Printable for DiamondIntrAttr {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "DiamondIntrAttr")
+    }
+}
+
+#[test]
+fn test_attr_intr_diamond() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+
+    // Verifying an attribute object verifies its interfaces too.
+    let attr: AttrObj = DiamondIntrAttr {}.into();
+    attr.verify(ctx)?;
+
+    let output = TEST_ATTR_DIAMOND_OUTPUT.lock().unwrap().clone();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "DiamondAttrTop verified");
+    assert_eq!(lines[3], "DiamondAttrBottom verified");
+
+    // Cast to every interface in the hierarchy, starting from the bottom.
+    let bottom = attr_cast::<dyn DiamondAttrBottom>(&*attr).unwrap();
+    assert!(attr_cast::<dyn DiamondAttrLeft>(bottom).is_some());
+    assert!(attr_cast::<dyn DiamondAttrRight>(bottom).is_some());
+    assert!(attr_cast::<dyn DiamondAttrTop>(bottom).is_some());
+
+    Ok(())
+}
+
+static TEST_TYPE_DIAMOND_OUTPUT: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new("".into()));
+
+#[type_interface]
+trait DiamondTypeTop {
+    fn verify(_ty: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_TYPE_DIAMOND_OUTPUT.lock().unwrap() += "DiamondTypeTop verified\n";
+        Ok(())
+    }
+}
+
+#[type_interface]
+trait DiamondTypeLeft: DiamondTypeTop {
+    fn verify(_ty: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_TYPE_DIAMOND_OUTPUT.lock().unwrap() += "DiamondTypeLeft verified\n";
+        Ok(())
+    }
+}
+
+#[type_interface]
+trait DiamondTypeRight: DiamondTypeTop {
+    fn verify(_ty: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_TYPE_DIAMOND_OUTPUT.lock().unwrap() += "DiamondTypeRight verified\n";
+        Ok(())
+    }
+}
+
+#[type_interface]
+trait DiamondTypeBottom: DiamondTypeLeft + DiamondTypeRight {
+    fn verify(_ty: &dyn Type, _ctx: &Context) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *TEST_TYPE_DIAMOND_OUTPUT.lock().unwrap() += "DiamondTypeBottom verified\n";
+        Ok(())
+    }
+}
+
+#[def_type("test.diamond_intr_type")]
+#[derive_type_interface_impl(DiamondTypeBottom, DiamondTypeRight, DiamondTypeLeft, DiamondTypeTop)]
+#[derive(PartialEq, Clone, Debug, Hash)]
+struct DiamondIntrType {}
+impl_verify_succ!(DiamondIntrType);
+
+impl Printable for DiamondIntrType {
+    fn fmt(
+        &self,
+        _ctx: &Context,
+        _state: &printable::State,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "DiamondIntrType")
+    }
+}
+
+#[test]
+fn test_type_intr_diamond() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+
+    // Verifying a type object verifies its interfaces too.
+    let ty = Type::register_instance(DiamondIntrType {}, ctx);
+    ty.verify(ctx)?;
+
+    let output = TEST_TYPE_DIAMOND_OUTPUT.lock().unwrap().clone();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "DiamondTypeTop verified");
+    assert_eq!(lines[3], "DiamondTypeBottom verified");
+
+    // Cast to every interface in the hierarchy, starting from the bottom.
+    let ty_ref = ty.to_ptr().deref(ctx);
+    let bottom = type_cast::<dyn DiamondTypeBottom>(&**ty_ref).unwrap();
+    assert!(type_cast::<dyn DiamondTypeLeft>(bottom).is_some());
+    assert!(type_cast::<dyn DiamondTypeRight>(bottom).is_some());
+    assert!(type_cast::<dyn DiamondTypeTop>(bottom).is_some());
+
+    Ok(())
+}
+
 #[op_interface]
 trait TestNoInbuiltVerifyInterface {
     fn verify(_op: &dyn Op, _ctx: &Context) -> Result<()>
@@ -414,3 +710,445 @@ fn test_no_inbuilt_verify2() -> Result<()> {
 
     Ok(())
 }
+
+static ATTR_KEY_SYM_REF: LazyLock<Identifier> =
+    LazyLock::new(|| "test_sym_ref".try_into().unwrap());
+
+#[def_op("test.sym_ref")]
+struct SymRefOp {}
+impl_canonical_syntax!(SymRefOp);
+impl_verify_succ!(SymRefOp);
+impl SymRefOp {
+    fn new(ctx: &mut Context, sym: Identifier) -> SymRefOp {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_SYM_REF, IdentifierAttr::new(sym));
+        SymRefOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl SymbolUserOpInterface for SymRefOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        let op = self.op.deref(ctx);
+        let sym = op
+            .attributes
+            .get::<IdentifierAttr>(&ATTR_KEY_SYM_REF)
+            .unwrap();
+        vec![sym.clone().into()]
+    }
+
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if self.used_symbols(ctx).contains(from) {
+            self.op
+                .deref_mut(ctx)
+                .attributes
+                .set(*ATTR_KEY_SYM_REF, IdentifierAttr::new(*to));
+        }
+    }
+}
+
+#[test]
+fn test_symbol_rename() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    SymRefOp::register(ctx, SymRefOp::parser_fn);
+
+    let (module_op, func_op, _, ret_op) = const_ret_in_mod(ctx)?;
+    let foo: Identifier = "foo".try_into().unwrap();
+    let baz: Identifier = "baz".try_into().unwrap();
+
+    let sym_ref = SymRefOp::new(ctx, foo);
+    sym_ref.operation().insert_before(ctx, ret_op.operation());
+    assert!(module_op.symbol_uses(ctx, &foo) == vec![sym_ref.operation()]);
+    assert!(!module_op.symbol_is_used(ctx, &baz));
+
+    module_op.rename_symbol(ctx, &foo, &baz)?;
+    assert_eq!(func_op.symbol_name(ctx), baz);
+    assert_eq!(sym_ref.used_symbols(ctx), vec![baz]);
+    assert!(module_op.lookup(ctx, &foo).is_none());
+    assert!(module_op.symbol_uses(ctx, &baz) == vec![sym_ref.operation()]);
+
+    // Renaming a symbol that doesn't exist must fail.
+    assert!(matches!(
+        module_op.rename_symbol(ctx, &foo, &baz),
+        Err(Error {
+            kind: ErrorKind::InvalidArgument,
+            ..
+        })
+    ));
+
+    // Renaming a symbol to an already defined one must fail.
+    let func2_ty = func_op.get_type(ctx);
+    let func2 = FuncOp::new(ctx, &foo, TypePtr::from_ptr(func2_ty, ctx)?);
+    func2.operation().insert_after(ctx, func_op.operation());
+    assert!(matches!(
+        module_op.rename_symbol(ctx, &baz, &foo),
+        Err(Error {
+            kind: ErrorKind::InvalidArgument,
+            ..
+        })
+    ));
+    assert_eq!(sym_ref.used_symbols(ctx), vec![baz]);
+
+    module_op.operation().verify(ctx)
+}
+
+#[test]
+fn test_nested_symbol_tables() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    SymRefOp::register(ctx, SymRefOp::parser_fn);
+
+    let (module_op, func_op, _, ret_op) = const_ret_in_mod(ctx)?;
+    let foo: Identifier = "foo".try_into().unwrap();
+    let baz: Identifier = "baz".try_into().unwrap();
+    let inner_name: Identifier = "inner".try_into().unwrap();
+    let func_ty = TypePtr::from_ptr(func_op.get_type(ctx), ctx)?;
+
+    // An inner module, defining its own `foo`, which shadows the outer one.
+    let inner = ModuleOp::new(ctx, &inner_name);
+    module_op.append_operation(ctx, inner.operation(), 0);
+    let inner_foo = FuncOp::new(ctx, &foo, func_ty);
+    inner.append_operation(ctx, inner_foo.operation(), 0);
+    let inner_ref = SymRefOp::new(ctx, foo);
+    inner.append_operation(ctx, inner_ref.operation(), 0);
+    let outer_ref = SymRefOp::new(ctx, foo);
+    outer_ref.operation().insert_before(ctx, ret_op.operation());
+    module_op.operation().verify(ctx)?;
+
+    assert!(nearest_symbol_table(ctx, inner_ref.operation()) == Some(inner.operation()));
+    assert!(nearest_symbol_table(ctx, module_op.operation()).is_none());
+    assert!(
+        lookup_symbol_in_scope(ctx, inner_ref.operation(), &foo) == Some(inner_foo.operation())
+    );
+    assert!(lookup_symbol_in_scope(ctx, outer_ref.operation(), &foo) == Some(func_op.operation()));
+    assert!(
+        lookup_symbol_in_scope(ctx, inner_ref.operation(), &inner_name) == Some(inner.operation())
+    );
+    assert!(lookup_symbol_in_scope(ctx, inner_ref.operation(), &baz).is_none());
+    assert!(module_op.symbol_uses(ctx, &foo) == vec![outer_ref.operation()]);
+
+    let mut table = SymbolTable::new(ctx, module_op.operation())?;
+    assert_eq!(table.len(), 2);
+    assert!(table.lookup(&foo) == Some(func_op.operation()));
+    // Renaming the outer `foo` leaves the uses of the inner one alone.
+    table.rename(ctx, &foo, &baz)?;
+    assert!(table.lookup(&baz) == Some(func_op.operation()) && !table.contains(&foo));
+    assert_eq!(outer_ref.used_symbols(ctx), vec![baz]);
+    assert_eq!(inner_ref.used_symbols(ctx), vec![foo]);
+    assert!(
+        lookup_symbol_in_scope(ctx, inner_ref.operation(), &foo) == Some(inner_foo.operation())
+    );
+
+    // Inserting a symbol that's already defined must fail.
+    let dup = FuncOp::new(ctx, &baz, func_ty);
+    assert!(matches!(
+        table.insert(ctx, dup.operation()),
+        Err(Error {
+            kind: ErrorKind::InvalidArgument,
+            ..
+        })
+    ));
+    assert!(!dup.operation().is_linked(ctx));
+    let qux: Identifier = "qux".try_into().unwrap();
+    dup.set_symbol_name(ctx, &qux);
+    table.insert(ctx, dup.operation())?;
+    assert!(module_op.lookup(ctx, &qux) == Some(dup.operation()));
+    module_op.operation().verify(ctx)?;
+    assert!(table.remove(ctx, &qux) == Some(dup.operation()));
+    assert!(!dup.operation().is_linked(ctx) && module_op.lookup(ctx, &qux).is_none());
+
+    // A function isn't a symbol table.
+    assert!(SymbolTable::new(ctx, func_op.operation()).is_err());
+
+    // Symbols defined more than once are rejected.
+    let inner_dup = FuncOp::new(ctx, &foo, func_ty);
+    inner.append_operation(ctx, inner_dup.operation(), 0);
+    assert!(SymbolTable::new(ctx, inner.operation()).is_err());
+    assert!(module_op.operation().verify(ctx).is_err());
+    Ok(())
+}
+
+static ATTR_KEY_META: LazyLock<Identifier> = LazyLock::new(|| "test_meta".try_into().unwrap());
+
+#[def_op("test.commutative_add")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct CommutativeAddOp {}
+impl_canonical_syntax!(CommutativeAddOp);
+impl_verify_succ!(CommutativeAddOp);
+
+impl CommutativeAddOp {
+    fn new(ctx: &mut Context, lhs: Value, rhs: Value, meta: &str) -> Self {
+        let res_ty = lhs.get_type(ctx);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![res_ty],
+            vec![lhs, rhs],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_META, StringAttr::new(meta.to_string()));
+        CommutativeAddOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl OpEquivalence for CommutativeAddOp {
+    fn is_commutative(&self) -> bool {
+        true
+    }
+
+    fn ignored_attributes(&self) -> Vec<Identifier> {
+        vec![*ATTR_KEY_META]
+    }
+}
+
+#[test]
+fn test_op_equivalence() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CommutativeAddOp::register(ctx, CommutativeAddOp::parser_fn);
+
+    // Structural equivalence, ignoring debug info.
+    let (c1, c1_dup, c2) = (
+        common::ConstantOp::new(ctx, 1),
+        common::ConstantOp::new(ctx, 1),
+        common::ConstantOp::new(ctx, 2),
+    );
+    set_operation_result_name(ctx, c1_dup.operation(), 0, "one".try_into().unwrap());
+    let (c1, c1_dup, c2) = (c1.operation(), c1_dup.operation(), c2.operation());
+    assert!(ops_equivalent(ctx, c1, c1_dup));
+    assert_eq!(
+        op_equivalence_hash(ctx, c1),
+        op_equivalence_hash(ctx, c1_dup)
+    );
+    assert!(!ops_equivalent(ctx, c1, c2));
+
+    // Equivalence customized by the Op.
+    let (v1, v2) = (c1.deref(ctx).result(0), c2.deref(ctx).result(0));
+    let add = CommutativeAddOp::new(ctx, v1, v2, "a").operation();
+    let add_swapped = CommutativeAddOp::new(ctx, v2, v1, "b").operation();
+    let add_same_opds = CommutativeAddOp::new(ctx, v1, v1, "a").operation();
+    assert!(ops_equivalent(ctx, add, add_swapped));
+    assert_eq!(
+        op_equivalence_hash(ctx, add),
+        op_equivalence_hash(ctx, add_swapped)
+    );
+    assert!(!ops_equivalent(ctx, add, add_same_opds));
+    assert!(!ops_equivalent(ctx, add_same_opds, add));
+    assert!(!ops_equivalent(ctx, add, c1));
+
+    // Without the interface, the same operands in a different order aren't equivalent.
+    assert!(!structural_equivalent(ctx, add, add_swapped, false, &[]));
+    Ok(())
+}
+
+#[def_op("test.costly")]
+struct CostlyOp {}
+impl_canonical_syntax!(CostlyOp);
+impl_verify_succ!(CostlyOp);
+
+impl CostlyOp {
+    fn new(ctx: &mut Context) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        CostlyOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl OpCostInterface for CostlyOp {
+    fn cost(&self, _ctx: &Context, model: &dyn CostModel) -> u64 {
+        2 * model.layout().pointer_size()
+    }
+}
+
+#[test]
+fn test_cost_model() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CostlyOp::register(ctx, CostlyOp::parser_fn);
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let (module, const_op) = (module_op.operation(), const_op.operation());
+    let costly = CostlyOp::new(ctx).operation();
+    costly.insert_before(ctx, ret_op.operation());
+
+    // Ops without weights or the interface have the default cost.
+    let model = TargetCostModel::of(ctx, costly)?;
+    assert_eq!(model.op_cost(ctx, const_op), 1);
+    assert_eq!(model.op_cost(ctx, costly), 16);
+    // func, constant, costly and return.
+    assert_eq!(model.cost(ctx, &[func_op.operation()]), 19);
+    assert!(model.is_profitable(ctx, &[costly], &[const_op]));
+    assert!(!model.is_profitable(ctx, &[const_op], &[ret_op.operation()]));
+
+    // The interface can consult the target's data layout.
+    DataLayout::parse("p:32:32")?.set(ctx, module);
+    assert_eq!(TargetCostModel::of(ctx, costly)?.op_cost(ctx, costly), 8);
+
+    // Target weights override the interface.
+    TargetCostModel::default()
+        .with_weights_spec("test.constant=5, test.costly=2")?
+        .set(ctx, module);
+    let model = TargetCostModel::of(ctx, costly)?;
+    assert_eq!(model.op_cost(ctx, const_op), 5);
+    assert_eq!(model.op_cost(ctx, costly), 2);
+    assert_eq!(model.to_string(), "test.constant=5,test.costly=2");
+    assert!(model.is_profitable(ctx, &[const_op], &[costly]));
+
+    assert!(
+        TargetCostModel::default()
+            .with_weights_spec("test.constant")
+            .is_err()
+    );
+    assert!(
+        TargetCostModel::default()
+            .with_weights_spec("constant=1")
+            .is_err()
+    );
+    Ok(())
+}
+
+#[def_op("test.wide_mul")]
+struct WideMulOp {}
+impl_canonical_syntax!(WideMulOp);
+impl_verify_succ!(WideMulOp);
+
+impl WideMulOp {
+    fn new(ctx: &mut Context) -> Self {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![], vec![], 0);
+        WideMulOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl RequiresFeaturesInterface for WideMulOp {
+    fn required_features(&self, _ctx: &Context) -> Vec<String> {
+        vec!["i128".to_string()]
+    }
+}
+
+#[test]
+fn test_target_features() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    WideMulOp::register(ctx, WideMulOp::parser_fn);
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let (module, func) = (module_op.operation(), func_op.operation());
+    let wide_mul = WideMulOp::new(ctx).operation();
+    wide_mul.insert_before(ctx, ret_op.operation());
+
+    // Without features, only ops that don't require any are legal.
+    assert!(is_legal(ctx, const_op.operation())?);
+    assert!(!is_legal(ctx, wide_mul)?);
+    let features = TargetFeatures::of(ctx, wide_mul)?;
+    assert_eq!(missing_features(ctx, wide_mul, &features), ["i128"]);
+
+    // Features of the function add to, and override, those of the module.
+    TargetFeatures::parse("+i128, -avx")?.set(ctx, module);
+    assert!(is_legal(ctx, wide_mul)?);
+    TargetFeatures::default()
+        .with_feature("i128", false)
+        .with_feature("sse", true)
+        .set(ctx, func);
+    let features = TargetFeatures::of(ctx, wide_mul)?;
+    assert!(!is_legal(ctx, wide_mul)?);
+    assert_eq!(features.to_string(), "-avx,-i128,+sse");
+    assert_eq!(features.enabled().collect::<Vec<_>>(), ["sse"]);
+    assert!(features.is_specified("avx") && !features.is_enabled("avx"));
+    assert!(TargetFeatures::of(ctx, module)?.is_enabled("i128"));
+
+    assert!(TargetFeatures::parse("i128").is_err());
+    assert!(TargetFeatures::parse("+").is_err());
+    Ok(())
+}
+
+#[def_op("test.bundled")]
+struct BundledOp {}
+impl_canonical_syntax!(BundledOp);
+impl_verify_succ!(BundledOp);
+
+#[op_interface_impl]
+impl OperandBundleInterface for BundledOp {}
+
+#[test]
+fn test_operand_bundles() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    BundledOp::register(ctx, BundledOp::parser_fn);
+    let (module_op, _, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let c0 = const_op.result(ctx);
+    let c1 = common::ConstantOp::new(ctx, 1);
+    c1.operation().insert_after(ctx, const_op.operation());
+    let c1 = c1.result(ctx);
+
+    let op = Operation::new(ctx, BundledOp::opid_static(), vec![], vec![c0], vec![], 0);
+    op.insert_before(ctx, ret_op.operation());
+    let bundled = BundledOp { op };
+    assert!(bundled.operand_bundles(ctx).is_empty());
+
+    bundled.set_operand_bundles(
+        ctx,
+        vec![
+            OperandBundle::new("deopt", vec![c1, c0]),
+            OperandBundle::new("gc-live", vec![]),
+            OperandBundle::new("funclet", vec![c1]),
+        ],
+    );
+    assert!(bundled.non_bundle_operands(ctx) == vec![c0]);
+    assert_eq!(bundled.num_bundle_operands(ctx), 3);
+    assert!(bundled.operand_bundle(ctx, "deopt").unwrap().operands == vec![c1, c0]);
+    assert!(bundled.operand_bundle(ctx, "funclet").unwrap().operands == vec![c1]);
+    assert!(
+        bundled
+            .operand_bundle(ctx, "gc-live")
+            .unwrap()
+            .operands
+            .is_empty()
+    );
+    assert!(bundled.operand_bundle(ctx, "gc-transition").is_none());
+    module_op.operation().verify(ctx)?;
+
+    // The bundles survive a round trip through the textual format.
+    let printed = module_op.operation().disp(ctx).to_string();
+    let bundles_attr = r#"["deopt": 2, "gc-live": 0, "funclet": 1]"#;
+    assert!(printed.contains(bundles_attr));
+    let reparsed = pliron::parse_source(ctx, printed.as_str())?;
+    reparsed.operation().verify(ctx)?;
+    let reprinted = reparsed.operation().disp(ctx).to_string();
+    assert!(reprinted.contains(bundles_attr));
+
+    // Dropping the bundles leaves the other operands alone.
+    bundled.set_operand_bundles(ctx, vec![OperandBundle::new("deopt", vec![c0])]);
+    assert!(bundled.non_bundle_operands(ctx) == vec![c0]);
+    bundled.set_operand_bundles(ctx, vec![]);
+    assert!(op.deref(ctx).operands().collect::<Vec<_>>() == vec![c0]);
+    assert!(
+        !op.deref(ctx)
+            .attributes
+            .0
+            .contains_key(&*ATTR_KEY_OPERAND_BUNDLES)
+    );
+
+    // Bundles must have distinct tags, and enough operands.
+    let set_sizes = |ctx: &mut Context, sizes: Vec<(&str, usize)>| {
+        let sizes = sizes
+            .into_iter()
+            .map(|(tag, n)| (tag.to_string(), n))
+            .collect();
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_OPERAND_BUNDLES, OperandBundlesAttr::new(sizes));
+    };
+    set_sizes(ctx, vec![("deopt", 0), ("deopt", 1)]);
+    let err = op.verify(ctx).unwrap_err();
+    expect![[r#"
+        Compilation error: verification failed.
+        Operand bundle tag "deopt" occurs more than once"#]]
+    .assert_eq(&err.to_string());
+    set_sizes(ctx, vec![("deopt", 2)]);
+    let err = op.verify(ctx).unwrap_err();
+    expect![[r#"
+        Compilation error: verification failed.
+        Operand bundles have 2 operands, but the operation has only 1"#]]
+    .assert_eq(&err.to_string());
+    Ok(())
+}
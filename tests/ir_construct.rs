@@ -1,32 +1,62 @@
 use common::{ConstantOp, ReturnOp};
 use expect_test::{Expect, expect};
-use pliron::derive::def_op;
+use pliron::derive::{def_attribute, def_op, derive_op_interface_impl, format_attribute};
 use pliron::{
+    arg_err_noloc,
+    attribute::{AttrId, AttrName, Attribute, ElidedAttrErr},
     basic_block::BasicBlock,
+    builder::{BuilderListener, InsertionPoint, OpBuilder},
     builtin::{
-        op_interfaces::OneResultInterface,
-        types::{IntegerType, Signedness},
+        attributes::{StringAttr, UnitAttr, VecAttr},
+        op_interfaces::{
+            IsTerminatorInterface, OneRegionInterface, OneResultInterface,
+            SingleBlockRegionInterface, SymbolOpInterface, SymbolTableInterface,
+        },
+        ops::{FuncOp, ModuleOp},
+        type_interfaces::DataLayout,
+        types::{FunctionType, IntegerType, Signedness},
     },
-    common_traits::Verify,
-    context::Context,
+    bytecode,
+    common_traits::{Named, Verify},
+    completion::{CompletionKind, complete},
+    context::{Context, DebugWithContext, IrView, Ptr},
     debug_info::set_operation_result_name,
+    dialect::{Dialect, DialectName, UnregisteredDialectErr},
+    dynamic::{DynamicAttrDef, DynamicOpDef, DynamicType, DynamicTypeDef},
+    graph::op_index::OpIndex,
     graph::walkers::{
-        self, IRNode, WALKCONFIG_POSTORDER_FORWARD, WALKCONFIG_POSTORDER_REVERSE,
+        self, IRNode, Visitor, WALKCONFIG_POSTORDER_FORWARD, WALKCONFIG_POSTORDER_REVERSE,
         WALKCONFIG_PREORDER_FORWARD,
-        interruptible::{self, walk_advance, walk_break},
+        interruptible::{self, WalkResult, walk_advance, walk_break, walk_skip},
     },
+    identifier::Identifier,
     impl_canonical_syntax, impl_verify_succ,
+    ir_mapping::IRMapping,
     irfmt::parsers::spaced,
-    location,
-    op::Op,
-    operation::Operation,
-    parsable::{self, Parsable, state_stream_from_iterator},
-    printable::Printable,
-    result::Result,
+    limits::{LimitErr, Limits},
+    linked_list::{ContainsLinkedList, LinkedList},
+    listener::{ChangeTracker, RewriteListener},
+    location::{self, Located, Location},
+    op::{Op, OpId, OpName},
+    operation::{OperandDominanceErr, Operation, ResultTypesErr},
+    parsable::{
+        self, Parsable, ParseDiagnostic, ParseSourceErr, ParseToken, TopLevelUnsupportedErr,
+        state_stream_from_iterator,
+    },
+    parse_source,
+    printable::{self, AnsiTheme, Highlight, Printable},
+    region::Region,
+    result::{Error, ErrorKind, Result},
+    session::{self, Session},
+    testing,
+    r#type::{Type, TypeId, TypeName, Typed},
+    unregistered::{OpaqueAttr, OpaqueType, UnregisteredOp},
+    verify_err_noloc,
 };
 
 use crate::common::{const_ret_in_mod, setup_context_dialects};
-use combine::parser::Parser;
+use combine::{parser::Parser, stream::position::SourcePosition};
+use std::{cell::RefCell, rc::Rc};
 
 mod common;
 
@@ -40,6 +70,48 @@ fn construct_and_erase() -> Result<()> {
     Ok(())
 }
 
+// Read the IR from many threads through a frozen view, and mutate it afterwards.
+#[test]
+fn frozen_parallel_reads() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, func, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let i64_ty = const_op.result(ctx).get_type(ctx);
+
+    let frozen = ctx.freeze(module.operation());
+    let ops = frozen.walk_ops();
+    assert!(
+        ops == vec![
+            module.operation(),
+            func.operation(),
+            const_op.operation(),
+            ret_op.operation()
+        ]
+    );
+    let results: Vec<_> = std::thread::scope(|s| {
+        let handles: Vec<_> = ops
+            .iter()
+            .map(|op| {
+                s.spawn(move || {
+                    let op_ref = frozen.get(*op);
+                    let opd_types: Vec<_> = op_ref
+                        .operands()
+                        .map(|opd| frozen.value_type(opd))
+                        .collect();
+                    (frozen.parent_op(*op), opd_types)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(results[0] == (None, vec![]));
+    assert!(results[2] == (Some(func.operation()), vec![]));
+    assert!(results[3] == (Some(func.operation()), vec![i64_ty]));
+
+    // With the view dropped, the IR can be mutated again.
+    Operation::erase(module.operation(), ctx);
+    Ok(())
+}
+
 // Ensure that erasing an op with uses panics.
 #[test]
 #[should_panic(expected = "Operation with use(s) being erased")]
@@ -53,6 +125,110 @@ fn removed_used_op() {
     Operation::erase(const_op.operation(), ctx);
 }
 
+// Ensure that using a handle to an erased op panics,
+// even when its arena slot is reused by a new op.
+#[test]
+#[should_panic(expected = "Stale operation handle")]
+fn stale_op_handle() {
+    let ctx = &mut setup_context_dialects();
+
+    let const_op = ConstantOp::new(ctx, 0).operation();
+    Operation::erase(const_op, ctx);
+    assert!(!const_op.is_live(ctx));
+
+    let new_op = ConstantOp::new(ctx, 1).operation();
+    assert!(new_op.is_live(ctx) && new_op != const_op);
+    let _ = const_op.deref(ctx);
+}
+
+// Trying to dereference a handle to an erased op doesn't panic, but fails.
+#[test]
+fn stale_op_handle_try_deref() {
+    let ctx = &mut setup_context_dialects();
+
+    let const_op = ConstantOp::new(ctx, 0).operation();
+    assert!(const_op.try_deref(ctx).is_some());
+    Operation::erase(const_op, ctx);
+
+    let new_op = ConstantOp::new(ctx, 1).operation();
+    assert!(const_op.try_deref(ctx).is_none());
+    assert!(const_op.try_deref_mut(ctx).is_none());
+    assert!(new_op.try_deref_mut(ctx).is_some());
+}
+
+// Debug output of handles, with and without a context.
+#[test]
+fn debug_handles() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, func, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let (const_op, ret_op) = (const_op.operation(), ret_op.operation());
+
+    let const_dbg = format!("{:?}", const_op);
+    let index = const_dbg.strip_prefix("op#").unwrap();
+    assert!(index.parse::<u32>().is_ok());
+    assert_eq!(
+        format!("{:?}", const_op.dbg_with(ctx)),
+        format!("{const_dbg} (test.constant)")
+    );
+
+    let c0 = const_op.deref(ctx).result(0);
+    assert_eq!(
+        format!("{:?}", c0.dbg_with(ctx)),
+        format!("{const_dbg} (test.constant) result 0")
+    );
+    let r#use = c0.uses(ctx).next().unwrap();
+    assert_eq!(
+        format!("{:?}", r#use.dbg_with(ctx)),
+        format!("{ret_op:?} (test.return) operand 0 = {const_dbg} (test.constant) result 0")
+    );
+
+    let entry = func.get_entry_block(ctx);
+    assert_eq!(
+        format!("{:?}", entry.dbg_with(ctx)),
+        format!("{entry:?} (^{})", entry.unique_name(ctx))
+    );
+    let region = module.operation().deref(ctx).region(0);
+    assert_eq!(
+        format!("{:?}", region.dbg_with(ctx)),
+        format!("{region:?} (in {:?})", module.operation())
+    );
+
+    // Stale handles are marked as such.
+    Operation::erase(ret_op, ctx);
+    Operation::erase(const_op, ctx);
+    assert_eq!(
+        format!("{:?}", const_op.dbg_with(ctx)),
+        format!("{const_dbg} (stale)")
+    );
+    Ok(())
+}
+
+#[test]
+fn fresh_names() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, _, const_op, _) = const_ret_in_mod(ctx)?;
+
+    assert_eq!(ctx.fresh_name("tmp").to_string(), "tmp0");
+    assert_eq!(ctx.fresh_name("tmp").to_string(), "tmp1");
+    assert_eq!(ctx.fresh_name("outlined_").to_string(), "outlined_0");
+    let tmp = ctx.fresh_name("tmp");
+    set_operation_result_name(ctx, const_op.operation(), 0, tmp);
+    let c0 = const_op.operation().deref(ctx).result(0);
+    assert!(c0.unique_name(ctx).starts_with("tmp2_"));
+
+    // Symbols already defined in the table are skipped.
+    let func_ty = FunctionType::get(ctx, vec![], vec![]);
+    for name in ["f0", "f2"] {
+        let func = FuncOp::new(ctx, &name.try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+    }
+    let names: Vec<_> = (0..2)
+        .map(|_| ctx.fresh_symbol(&module, "f").to_string())
+        .collect();
+    assert_eq!(names, ["f1", "f3"]);
+    Ok(())
+}
+
 // Testing replacing all uses of c0 with c1.
 #[test]
 fn replace_c0_with_c1() -> Result<()> {
@@ -95,10 +271,10 @@ fn replace_c0_with_c1_operand() -> Result<()> {
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
                 c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
                 c1_op_5v1_res0 = test.constant builtin.integer <1: si64>;
                 test.return c0_op_3v1_res0
@@ -113,10 +289,10 @@ fn replace_c0_with_c1_operand() -> Result<()> {
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
                 c1_op_5v1_res0 = test.constant builtin.integer <1: si64>;
                 test.return c1_op_5v1_res0
             }
@@ -133,6 +309,24 @@ struct DualDefOp {}
 impl_verify_succ!(DualDefOp);
 impl_canonical_syntax!(DualDefOp);
 
+/// A different Op with the same id as [DualDefOp].
+#[def_op("test.dual_def")]
+struct ShadowingDualDefOp {}
+impl_verify_succ!(ShadowingDualDefOp);
+impl_canonical_syntax!(ShadowingDualDefOp);
+
+#[def_op("test.interface_def")]
+#[derive_op_interface_impl(IsTerminatorInterface)]
+struct InterfaceDefOp {}
+impl_verify_succ!(InterfaceDefOp);
+impl_canonical_syntax!(InterfaceDefOp);
+
+/// A different Op with the same id as [InterfaceDefOp], not implementing its interfaces.
+#[def_op("test.interface_def")]
+struct ShadowingInterfaceDefOp {}
+impl_verify_succ!(ShadowingInterfaceDefOp);
+impl_canonical_syntax!(ShadowingInterfaceDefOp);
+
 /// If an Op has multiple results, or a block multiple args,
 /// replacing all uses of one with the other should work.
 /// (since our RefCell is at the Op or block level, we shouldn't
@@ -166,10 +360,10 @@ fn test_replace_within_same_def_site() {
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
                 c0_op_4v1_res0 = test.constant builtin.integer <0: si64>;
                 op_1v1_res0, op_1v1_res1 = test.dual_def () [] []: <() -> (builtin.integer si64, builtin.integer si64)>;
                 test.return op_1v1_res1
@@ -191,20 +385,311 @@ fn test_replace_within_same_def_site() {
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
                 c0_op_4v1_res0 = test.constant builtin.integer <0: si64>;
                 op_1v1_res0, op_1v1_res1 = test.dual_def () [] []: <() -> (builtin.integer si64, builtin.integer si64)>;
                 test.return op_1v1_res1
-              ^block_3v1(block_3v1_arg0:builtin.integer si64,block_3v1_arg1:builtin.integer si64):
+              ^bb1(block_3v1_arg0:builtin.integer si64,block_3v1_arg1:builtin.integer si64):
                 test.return block_3v1_arg1
             }
         }"#]]
     .assert_eq(&printed);
 }
 
+// Clone a function, and a block, remapping values and blocks.
+#[test]
+fn clone_with_mapping() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let c0 = const_op.result(ctx);
+
+    let mut mapping = IRMapping::new();
+    let copy = Operation::clone(func_op.operation(), ctx, &mut mapping);
+    let copy = Operation::op(copy, ctx)
+        .downcast_ref::<FuncOp>()
+        .copied()
+        .unwrap();
+    copy.set_symbol_name(ctx, &"foo_copy".try_into().unwrap());
+    module_op.append_operation(ctx, copy.operation(), 0);
+    let c0_copy = mapping.lookup_value(c0).unwrap();
+    let entry_copy = mapping.lookup_block(func_op.get_entry_block(ctx)).unwrap();
+    assert!(entry_copy == copy.get_entry_block(ctx));
+    assert_eq!((c0.num_uses(ctx), c0_copy.num_uses(ctx)), (1, 1));
+    let ret_copy = entry_copy.deref(ctx).tail().unwrap();
+    assert!(ret_copy.deref(ctx).operand(0) == c0_copy);
+    module_op.operation().verify(ctx)?;
+
+    // A copy of the entry block, returning a value from outside it.
+    let const1_op = ConstantOp::new(ctx, 1);
+    const1_op.operation().insert_before(ctx, ret_op.operation());
+    let mut mapping = IRMapping::new();
+    mapping.map_value(c0, const1_op.result(ctx));
+    let entry = func_op.get_entry_block(ctx);
+    let block_copy = BasicBlock::clone_into(entry, ctx, func_op.region(ctx), &mut mapping);
+    let ops: Vec<_> = block_copy.deref(ctx).iter(ctx).collect();
+    assert_eq!(ops.len(), 3);
+    // The constant defined in the block is copied, and the copy is used.
+    assert!(ops[2].deref(ctx).operand(0) == ops[0].deref(ctx).result(0));
+    Ok(())
+}
+
+// Replace only some uses of c0, through its uses in a region, and individually.
+#[test]
+fn replace_uses_partially() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx)?;
+    let c0 = const_op.result(ctx);
+    let const1_op = ConstantOp::new(ctx, 1);
+    const1_op
+        .operation()
+        .insert_after(ctx, const_op.operation());
+    let c1 = const1_op.result(ctx);
+
+    // Another function, returning c0 (from outside it) twice.
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+    let func_ty = FunctionType::get(ctx, vec![], vec![i64_ty.into()]);
+    let other_func = FuncOp::new(ctx, &"other".try_into().unwrap(), func_ty);
+    module_op.append_operation(ctx, other_func.operation(), 0);
+    let other_entry = other_func.get_entry_block(ctx);
+    let other_rets: Vec<_> = (0..2)
+        .map(|_| {
+            let ret = ReturnOp::new(ctx, c0).operation();
+            ret.insert_at_back(other_entry, ctx);
+            ret
+        })
+        .collect();
+    assert!(
+        other_rets[0]
+            .deref(ctx)
+            .is_in_region(ctx, module_op.region(ctx))
+    );
+    assert!(
+        !other_rets[0]
+            .deref(ctx)
+            .is_in_region(ctx, func_op.region(ctx))
+    );
+
+    // Only the use in the first function.
+    c0.replace_uses_in_region_with(ctx, func_op.region(ctx), &c1);
+    assert!(ret_op.operation().deref(ctx).operand(0) == c1);
+    let mut users: Vec<_> = c0
+        .uses(ctx)
+        .map(|r#use| (r#use.op, r#use.opd_idx))
+        .collect();
+    users.sort_by_key(|(op, _)| other_rets.iter().position(|ret| ret == op));
+    assert_eq!(users, vec![(other_rets[0], 0), (other_rets[1], 0)]);
+
+    // Only the use in the last return.
+    for r#use in c0.uses(ctx) {
+        if r#use.op == other_rets[1] {
+            r#use.set(ctx, c1);
+        }
+    }
+    let operands: Vec<_> = other_rets
+        .iter()
+        .map(|ret| ret.deref(ctx).op_operands().next().unwrap())
+        .collect();
+    assert!(operands[0].get(ctx) == c0 && operands[1].get(ctx) == c1);
+    assert_eq!((c0.num_uses(ctx), c1.num_uses(ctx)), (1, 2));
+    Ok(())
+}
+
+// Create an operation before its result types are known, and finalize them later.
+#[test]
+fn pending_result_types() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let op =
+        Operation::new_with_pending_results(ctx, ConstantOp::opid_static(), 1, vec![], vec![], 0);
+    assert!(op.deref(ctx).has_pending_result_types(ctx));
+    assert!(matches!(
+        op.verify(ctx),
+        Err(Error {
+            kind: ErrorKind::VerificationFailed,
+            err,
+            ..
+        })
+        if matches!(err.downcast_ref::<ResultTypesErr>(), Some(ResultTypesErr::Pending))
+    ));
+
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+    assert!(matches!(
+        Operation::finalize_result_types(op, ctx, vec![i64_ty.into(), i64_ty.into()]),
+        Err(Error { err, .. })
+        if matches!(
+            err.downcast_ref::<ResultTypesErr>(),
+            Some(ResultTypesErr::NumMismatch { expected: 1, provided: 2 })
+        )
+    ));
+
+    Operation::finalize_result_types(op, ctx, vec![i64_ty.into()])?;
+    assert!(!op.deref(ctx).has_pending_result_types(ctx));
+    assert!(op.deref(ctx).result(0).get_type(ctx) == i64_ty.into());
+    op.verify(ctx)?;
+
+    assert!(matches!(
+        Operation::finalize_result_types(op, ctx, vec![i64_ty.into()]),
+        Err(Error { err, .. })
+        if matches!(err.downcast_ref::<ResultTypesErr>(), Some(ResultTypesErr::AlreadyFinalized))
+    ));
+    Ok(())
+}
+
+// Report every invalid operation, sorted by location.
+#[test]
+fn verify_all_errors() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, _, const_op, _) = const_ret_in_mod(ctx)?;
+    assert!(module_op.operation().deref(ctx).verify_all(ctx).is_empty());
+
+    let at_line = |line| Location::SrcPos {
+        src: location::Source::InMemory,
+        pos: SourcePosition { line, column: 1 },
+    };
+    for line in [7, 3] {
+        let op = Operation::new_with_pending_results(
+            ctx,
+            ConstantOp::opid_static(),
+            1,
+            vec![],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx).set_loc(at_line(line));
+        op.insert_after(ctx, const_op.operation());
+    }
+    let diagnostics = module_op.operation().deref(ctx).verify_all(ctx);
+    let lines: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic.loc {
+            Location::SrcPos { pos, .. } => pos.line,
+            _ => panic!("Expected a source position"),
+        })
+        .collect();
+    assert_eq!(lines, vec![3, 7]);
+    assert!(
+        diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.message == ResultTypesErr::Pending.to_string())
+    );
+    // Only the first error is reported by verify.
+    assert!(module_op.operation().verify(ctx).is_err());
+    Ok(())
+}
+
+// Values must be defined before they're used, and used only in their scope.
+#[test]
+fn verify_operand_dominance() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, func_op, const_op, _) = const_ret_in_mod(ctx)?;
+    let c0 = const_op.result(ctx);
+
+    // Used before it's defined.
+    let early_ret = ReturnOp::new(ctx, c0).operation();
+    early_ret.insert_before(ctx, const_op.operation());
+    let err = module_op.operation().verify(ctx).unwrap_err();
+    assert!(matches!(
+        err.err.downcast_ref::<OperandDominanceErr>(),
+        Some(OperandDominanceErr { opd_idx: 0, .. })
+    ));
+    Operation::erase(early_ret, ctx);
+    module_op.operation().verify(ctx)?;
+
+    // Used in a block not dominated by its definition.
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+    let block = BasicBlock::new(ctx, None, vec![i64_ty.into()]);
+    block.insert_after(ctx, func_op.get_entry_block(ctx));
+    let arg = block.deref(ctx).argument(0);
+    ReturnOp::new(ctx, arg)
+        .operation()
+        .insert_at_back(block, ctx);
+    let const1_op = ConstantOp::new(ctx, 1);
+    const1_op.operation().insert_at_front(block, ctx);
+    module_op.operation().verify(ctx)?;
+    let late_ret = ReturnOp::new(ctx, const1_op.result(ctx)).operation();
+    let entry_ret = const_op.operation().deref(ctx).next().unwrap();
+    late_ret.insert_before(ctx, entry_ret);
+    assert!(module_op.operation().verify(ctx).is_err());
+    Operation::erase(late_ret, ctx);
+
+    // Used outside the function defining it.
+    let func_ty = FunctionType::get(ctx, vec![], vec![i64_ty.into()]);
+    let other_func = FuncOp::new(ctx, &"other".try_into().unwrap(), func_ty);
+    module_op.append_operation(ctx, other_func.operation(), 0);
+    let other_ret = ReturnOp::new(ctx, c0).operation();
+    other_ret.insert_at_back(other_func.get_entry_block(ctx), ctx);
+    let diagnostics = module_op.operation().deref(ctx).verify_all(ctx);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.starts_with("Operand 0 (c0"));
+    Ok(())
+}
+
+// Verifying on several threads reports the same diagnostics, in the same order, every time.
+#[test]
+fn verify_all_parallel_deterministic() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, _, const_op, _) = const_ret_in_mod(ctx)?;
+    let c0 = const_op.result(ctx);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+    let func_ty = FunctionType::get(ctx, vec![], vec![i64_ty.into()]);
+    for i in 0..16 {
+        let name = format!("f{i}").try_into().unwrap();
+        let func = FuncOp::new(ctx, &name, func_ty);
+        module_op.append_operation(ctx, func.operation(), 0);
+        let entry = func.get_entry_block(ctx);
+        let const_op = ConstantOp::new(ctx, i);
+        const_op.operation().insert_at_back(entry, ctx);
+        // Used outside the function defining it, before it's defined, or with pending results.
+        let ret_op = match i % 3 {
+            0 => ReturnOp::new(ctx, c0).operation(),
+            1 => ReturnOp::new(ctx, const_op.result(ctx)).operation(),
+            _ => Operation::new_with_pending_results(
+                ctx,
+                ConstantOp::opid_static(),
+                1,
+                vec![],
+                vec![],
+                0,
+            ),
+        };
+        if i % 3 == 1 {
+            ret_op.insert_at_front(entry, ctx);
+        } else {
+            ret_op.insert_at_back(entry, ctx);
+        }
+        ret_op.deref_mut(ctx).set_loc(Location::SrcPos {
+            src: location::Source::InMemory,
+            pos: SourcePosition {
+                line: (i as i32 * 7) % 5,
+                column: 1,
+            },
+        });
+    }
+
+    let messages = |ctx: &Context, diagnostics: Vec<pliron::diagnostics::Diagnostic>| {
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.loc.disp(ctx), diagnostic.message))
+            .collect::<Vec<_>>()
+    };
+    let expected = messages(ctx, module_op.operation().deref(ctx).verify_all(ctx));
+    assert_eq!(expected.len(), 16);
+    assert_eq!(
+        expected
+            .iter()
+            .filter(|message| message.contains("Operand 0"))
+            .count(),
+        11
+    );
+    for num_threads in [1, 2, 4, 8, 1, 2, 4, 8] {
+        let diagnostics = Operation::verify_all_parallel(module_op.operation(), ctx, num_threads);
+        assert_eq!(messages(ctx, diagnostics), expected);
+    }
+    Ok(())
+}
+
 #[test]
 /// A test to just print a constructed IR to stdout.
 fn print_simple() -> Result<()> {
@@ -214,10 +699,10 @@ fn print_simple() -> Result<()> {
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
                 c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
                 test.return c0_op_3v1_res0
             }
@@ -252,150 +737,649 @@ fn parse_simple() -> Result<()> {
     Ok(())
 }
 
-fn expect_parse_error(input: &str, expected_err: Expect) {
-    let ctx = &mut setup_context_dialects();
-    let state_stream = state_stream_from_iterator(
-        input.chars(),
-        parsable::State::new(ctx, location::Source::InMemory),
-    );
-    let actual_err = spaced(Operation::parser(()))
-        .parse(state_stream)
-        .err()
-        .unwrap();
-
-    expected_err.assert_eq(&actual_err.to_string());
-}
-
 #[test]
-fn parse_err_multiple_def() {
-    let input_multiple_ssa_defs = r#"
-        builtin.module @bar {
-        ^block_0_0():
-            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
-            ^entry_block_1_0():
-                c0_op_2_0_res0 = test.constant builtin.integer <0 : si64>;
-                c0_op_2_0_res0 = test.constant builtin.integer <0 : si64>;
-                test.return c0_op_2_0_res0
-            ^exit():
-            }
-        }"#;
-
-    let expected_err = expect![[r#"
-        Parse error at line: 7, column: 17
-        Identifier c0_op_2_0_res0 defined more than once in the scope
-    "#]];
-    expect_parse_error(input_multiple_ssa_defs, expected_err);
-
-    let input_multiple_label_defs = r#"
+fn parse_func_attributes() -> Result<()> {
+    let input = r#"
         builtin.module @bar {
         ^block_0_0():
-            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> [(test_attr: builtin.unit )] {
             ^entry_block_1_0():
                 c0_op_2_0_res0 = test.constant builtin.integer <0: si64>;
                 test.return c0_op_2_0_res0
-            ^entry_block_1_0():
             }
         }"#;
 
-    let expected_err = expect![[r#"
-        Parse error at line: 8, column: 13
-        Identifier entry_block_1_0 defined more than once in the scope
-    "#]];
-    expect_parse_error(input_multiple_label_defs, expected_err);
+    let ctx = &mut setup_context_dialects();
+    let op = {
+        let state_stream = state_stream_from_iterator(
+            input.chars(),
+            parsable::State::new(ctx, location::Source::InMemory),
+        );
+        spaced(Operation::parser(())).parse(state_stream).unwrap().0
+    };
+    op.verify(ctx)?;
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> [(test_attr: builtin.unit )] 
+            {
+              ^entry_block_1_0():
+                c0_op_2_0_res0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_2_0_res0_op_3v1_res0
+            }
+        }"#]]
+    .assert_eq(&op.disp(ctx).to_string());
+    Ok(())
 }
 
 #[test]
-fn parse_err_unresolved_def() {
-    let input_multiple_defs = r#"
+fn result_and_arg_attributes() -> Result<()> {
+    let input = r#"
         builtin.module @bar {
         ^block_0_0():
-            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
-            ^entry_block_1_0():
-                test.return c0_op_2_0_res0
+            builtin.func @foo: builtin.function <(builtin.integer si64) -> (builtin.integer si64)> {
+            ^entry(x : builtin.integer si64 [(noundef: builtin.unit )]):
+                c0 [(align: builtin.integer <8: si64>)] = test.constant builtin.integer <0: si64>;
+                test.return x
             }
         }"#;
+    let ctx = &mut setup_context_dialects();
+    let module = parse_source(ctx, input)?;
+    module.operation().verify(ctx)?;
+    let func = module.body(ctx, 0).deref(ctx).head().unwrap();
+    let func = *Operation::op(func, ctx).downcast_ref::<FuncOp>().unwrap();
+    let entry = func.get_entry_block(ctx);
+    let const_op = entry.deref(ctx).head().unwrap();
+    let noundef: Identifier = "noundef".try_into().unwrap();
+    let align: Identifier = "align".try_into().unwrap();
+    assert!(
+        entry
+            .deref(ctx)
+            .argument_attributes(0)
+            .0
+            .contains_key(&noundef)
+    );
+    assert!(
+        const_op
+            .deref(ctx)
+            .result_attributes(0)
+            .0
+            .contains_key(&align)
+    );
 
-    let expected_err = expect![[r#"
-        Parse error at line: 4, column: 80
-        Identifier c0_op_2_0_res0 was not resolved to any definition in the scope
-    "#]];
-    expect_parse_error(input_multiple_defs, expected_err);
-}
-
-#[test]
-fn parse_err_block_label_colon() {
-    let input_label_colon_missing = r#"
-        builtin.module @bar {
-        ^block_0_0():
-            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
-            ^entry_block_1_0():
-                c0_op_2_0_res0 = test.constant builtin.integer <0: si64>;
-                test.return c0_op_2_0_res0
-            ^exit()
+    let printed = module.disp(ctx).to_string();
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+            {
+              ^entry(x_block_1v1_arg0:builtin.integer si64 [(noundef: builtin.unit )]):
+                c0_op_3v1_res0 [(align: builtin.integer <8: si64>)] = test.constant builtin.integer <0: si64>;
+                test.return x_block_1v1_arg0
             }
-        }"#;
+        }"#]]
+    .assert_eq(&printed);
 
-    let expected_err = expect![[r#"
-        Parse error at line: 9, column: 13
-        Unexpected `}`
-        Expected whitespace or `:`
-    "#]];
+    // The generic syntax, clones and bytecode keep them too.
+    let state = printable::State::default();
+    state.set_print_generic(true);
+    let generic = format!("{}", module.operation().print(ctx, &state));
+    let reparsed = parse_source(ctx, generic.as_str())?;
+    let reprinted = reparsed.disp(ctx).to_string();
+    assert!(reprinted.contains("[(noundef: builtin.unit )]):"));
+    assert!(reprinted.contains(" [(align: builtin.integer <8: si64>)] = test.constant"));
 
-    expect_parse_error(input_label_colon_missing, expected_err);
+    let cloned = Operation::clone(const_op, ctx, &mut IRMapping::new());
+    assert!(
+        cloned
+            .deref(ctx)
+            .result_attributes(0)
+            .0
+            .contains_key(&align)
+    );
+    Operation::erase(cloned, ctx);
+
+    let bytes = bytecode::serialize(ctx, module.operation())?;
+    let deserialized = bytecode::deserialize(ctx, &bytes)?;
+    let reprinted = deserialized.disp(ctx).to_string();
+    assert!(reprinted.contains("[(noundef: builtin.unit )]):"));
+    assert!(reprinted.contains(" [(align: builtin.integer <8: si64>)] = test.constant"));
+    Ok(())
 }
 
 #[test]
-fn parse_err_block_args() {
-    let input_label_colon_missing = r#"
+fn block_labels_round_trip() -> Result<()> {
+    let input = r#"
         builtin.module @bar {
-        ^block_0_0(a : builtin.integer si32, b):
+        ^bb3():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            ^entry():
+                c0 = test.constant builtin.integer <0: si64>;
+                test.return c0
+            ^bb7():
+                test.return c0
+            ^exit():
+                test.return c0
+            }
         }"#;
-
-    let expected_err = expect![[r#"
-        Parse error at line: 3, column: 47
-        Unexpected `)`
-        Expected whitespaces or `:`
-    "#]];
-
-    expect_parse_error(input_label_colon_missing, expected_err);
-}
-
-#[test]
-fn test_preorder_forward_walk() {
     let ctx = &mut setup_context_dialects();
-    let module_op = const_ret_in_mod(ctx).unwrap().0.operation();
-
-    let mut state = Vec::new();
-
-    walkers::walk_op(
-        ctx,
-        &mut state,
-        &WALKCONFIG_PREORDER_FORWARD,
-        module_op,
-        |_ctx, state, node| {
-            if let IRNode::Operation(op) = node {
-                state.push(op);
-            }
-        },
-    );
-
-    let ops = state.into_iter().fold("".to_string(), |accum, op| {
-        accum + &op.disp(ctx).to_string() + "\n"
-    });
+    let module = parse_source(ctx, input)?;
+    // Given labels are kept, numbered ones are reassigned by position.
+    let printed = module.disp(ctx).to_string();
     expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^bb0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry():
+                c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_3v1_res0
+              ^bb1():
+                test.return c0_op_3v1_res0
+              ^exit():
+                test.return c0_op_3v1_res0
+            }
+        }"#]]
+    .assert_eq(&printed);
+    let reparsed = parse_source(ctx, printed.as_str())?;
+    let reprinted = reparsed.disp(ctx).to_string();
+    let block_lines = |printed: &str| -> Vec<String> {
+        printed
+            .lines()
+            .filter(|line| line.trim_start().starts_with('^'))
+            .map(str::to_string)
+            .collect()
+    };
+    assert_eq!(block_lines(&printed), block_lines(&reprinted));
+
+    // Duplicate labels in a region fall back to numbering.
+    let func = reparsed.body(ctx, 0).deref(ctx).head().unwrap();
+    let func_entry = func.deref(ctx).region(0).deref(ctx).head().unwrap();
+    let second = func_entry.deref(ctx).next().unwrap();
+    assert!(second.deref(ctx).label().is_none());
+    second
+        .deref_mut(ctx)
+        .set_label(Some("exit".try_into().unwrap()));
+    let labels: Vec<_> = func
+        .deref(ctx)
+        .region(0)
+        .deref(ctx)
+        .iter(ctx)
+        .map(|block| block.deref(ctx).printed_label(ctx).to_string())
+        .collect();
+    assert_eq!(labels, vec!["entry", "bb1", "bb2"]);
+    Ok(())
+}
+
+fn expect_parse_error(input: &str, expected_err: Expect) {
+    let ctx = &mut setup_context_dialects();
+    let state_stream = state_stream_from_iterator(
+        input.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+    let actual_err = spaced(Operation::parser(()))
+        .parse(state_stream)
+        .err()
+        .unwrap();
+
+    expected_err.assert_eq(&actual_err.to_string());
+}
+
+#[test]
+fn parse_source_text_and_file() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let printed = const_ret_in_mod(ctx)?.0.disp(ctx).to_string();
+
+    let module = parse_source(ctx, printed.as_str())?;
+    module.operation().deref(ctx).verify(ctx)?;
+    assert_eq!(module.symbol_name(ctx), "bar".try_into().unwrap());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("module.pliron");
+    std::fs::write(&path, &printed).unwrap();
+    let module = parse_source(ctx, &path)?;
+    module.operation().deref(ctx).verify(ctx)?;
+
+    let missing = dir.path().join("missing.pliron");
+    let err = parse_source(ctx, &missing).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<ParseSourceErr>(),
+        Some(ParseSourceErr::Read { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn parse_source_errors() {
+    let ctx = &mut setup_context_dialects();
+
+    // Misspelt dialect names get suggestions.
+    let err = parse_source(ctx, "bultin.module @m {\n^entry():\n}")
+        .err()
+        .unwrap();
+    let Some(ParseSourceErr::Diagnostics(diagnostics)) = err.err.downcast_ref::<ParseSourceErr>()
+    else {
+        panic!("Expected parse diagnostics");
+    };
+    let unregistered = diagnostics[0]
+        .downcast_ref::<UnregisteredDialectErr>()
+        .expect("Expected an unregistered dialect error");
+    assert_eq!(unregistered.suggestions, vec!["builtin".to_string()]);
+    expect![[r#"
+        <in-memory>: line: 1, column: 1: Compilation error: invalid input program.
+        Unregistered dialect bultin, did you mean builtin?"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+
+    // Syntax errors report what was expected.
+    let err = parse_source(ctx, "builtin.module @m {\n^entry():\n")
+        .err()
+        .unwrap();
+    expect![[r#"
+        <in-memory>: line: 3, column: 1: Compilation error: invalid input program.
+        Unexpected end of input
+        Expected whitespaces or `}`"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+    let diagnostic = ParseDiagnostic::of(&err).expect("Expected a syntax error diagnostic");
+    assert_eq!((diagnostic.pos.line, diagnostic.pos.column), (3, 1));
+    assert_eq!(
+        diagnostic.unexpected,
+        Some(ParseToken::Description("end of input".to_string()))
+    );
+    assert!(
+        diagnostic
+            .expected
+            .contains(&ParseToken::Literal("}".to_string()))
+    );
+
+    // Only a module is accepted at the top level.
+    let err = parse_source(
+        ctx,
+        "builtin.func @f: builtin.function <() -> ()> {\n^entry():\n}",
+    )
+    .err()
+    .unwrap();
+    expect![[r#"
+        <in-memory>: line: 1, column: 1: Compilation error: invalid input program.
+        Expected a builtin.module at the top level, but found builtin.func"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+}
+
+const TARGET_ATTR: &str = "test_target";
+
+/// Record `!test<payload>` as an attribute of the module.
+fn parse_test_target(ctx: &mut Context, module: ModuleOp, payload: &str) -> Result<()> {
+    if payload.is_empty() {
+        return arg_err_noloc!("Empty target");
+    }
+    let key = TARGET_ATTR.try_into().unwrap();
+    module
+        .operation()
+        .deref_mut(ctx)
+        .attributes
+        .set(key, StringAttr::new(payload.to_string()));
+    Ok(())
+}
+
+fn print_test_target(ctx: &Context, module: ModuleOp) -> Vec<String> {
+    let op = module.operation().deref(ctx);
+    let target = op
+        .attributes
+        .get::<StringAttr>(&TARGET_ATTR.try_into().unwrap());
+    target
+        .map(|target| String::from(target.clone()))
+        .into_iter()
+        .collect()
+}
+
+#[test]
+fn parse_top_level_constructs() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let source = "!test<x86_64 <sse4, avx>>\nbuiltin.module @m {\n^entry():\n}";
+
+    // The test dialect doesn't handle top-level constructs yet.
+    let err = parse_source(ctx, source).err().unwrap();
+    let Some(ParseSourceErr::Diagnostics(diagnostics)) = err.err.downcast_ref::<ParseSourceErr>()
+    else {
+        panic!("Expected parse diagnostics");
+    };
+    assert!(diagnostics[0].is::<TopLevelUnsupportedErr>());
+
+    let test_dialect = ctx.dialects.get_mut(&DialectName::new("test")).unwrap();
+    test_dialect.set_top_level_parser(parse_test_target);
+    test_dialect.set_top_level_printer(print_test_target);
+    let printed = testing::round_trip(ctx, source)?;
+    assert!(printed.starts_with("!test<x86_64 <sse4, avx>>\nbuiltin.module @m"));
+
+    // Errors from the dialect are reported at the payload.
+    let err = parse_source(ctx, "\n  !test<>\nbuiltin.module @m {\n^entry():\n}")
+        .err()
+        .unwrap();
+    expect![[r#"
+        <in-memory>: line: 2, column: 9: Compilation error: invalid argument.
+        Empty target"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+    Ok(())
+}
+
+/// `depth` modules, nested in each other.
+fn nested_modules(depth: usize) -> String {
+    let mut text = "builtin.module @m {\n^entry():\n".repeat(depth);
+    text.push_str(&"}\n".repeat(depth));
+    text
+}
+
+#[test]
+fn parse_and_verify_limits() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+
+    // Deeply nested input is rejected, rather than overflowing the stack.
+    let module = parse_source(ctx, nested_modules(20).as_str())?;
+    module.operation().deref(ctx).verify(ctx)?;
+    let err = parse_source(ctx, nested_modules(10_000).as_str())
+        .err()
+        .unwrap();
+    expect![[r#"
+        <in-memory>: line: 65, column: 19: Compilation error: invalid input program.
+        Nesting depth exceeds the limit of 32"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+    ctx.limits.max_nesting_depth = Some(10);
+    let err = module.operation().deref(ctx).verify(ctx).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<LimitErr>(),
+        Some(LimitErr::NestingDepth(10))
+    ));
+
+    // As is input with too many operations.
+    ctx.limits = Limits {
+        max_op_count: Some(10),
+        ..Limits::default()
+    };
+    let err = parse_source(ctx, nested_modules(15).as_str())
+        .err()
+        .unwrap();
+    expect![[r#"
+        <in-memory>: line: 21, column: 1: Compilation error: invalid input program.
+        More than 10 operations"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+    let err = module.operation().deref(ctx).verify(ctx).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<LimitErr>(),
+        Some(LimitErr::OpCount(10))
+    ));
+
+    // And with too large attributes.
+    ctx.limits = Limits::unlimited();
+    let module = const_ret_in_mod(ctx)?.0;
+    let printed = module.disp(ctx).to_string();
+    ctx.limits.max_attribute_size = Some(16);
+    let err = parse_source(ctx, printed.as_str()).err().unwrap();
+    expect![[r#"
+        <in-memory>: line: 7, column: 41: Compilation error: invalid input program.
+        Attribute is larger than the limit of 16 bytes"#]]
+    .assert_eq(&format!("{}: {}", err.loc().disp(ctx), err));
+    let err = module.operation().deref(ctx).verify(ctx).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<LimitErr>(),
+        Some(LimitErr::AttributeSize(16))
+    ));
+    ctx.limits.max_attribute_size = Some(128);
+    parse_source(ctx, printed.as_str())?;
+    module.operation().deref(ctx).verify(ctx)?;
+    Ok(())
+}
+
+#[test]
+fn parse_source_encodings() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let printed = const_ret_in_mod(ctx)?.0.disp(ctx).to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("module.pliron");
+
+    // A byte order mark and CRLF line endings are accepted.
+    let crlf = format!("\u{feff}{}", printed.replace('\n', "\r\n"));
+    std::fs::write(&path, &crlf).unwrap();
+    let module = parse_source(ctx, &path)?;
+    module.operation().deref(ctx).verify(ctx)?;
+
+    // Locations are the same as with LF line endings, and map back to the original bytes.
+    let text = "\u{feff}builtin.module @m {\r\n^entry():\r\n  x\r\n}";
+    let err = parse_source(ctx, text).err().unwrap();
+    let location::Location::SrcPos { pos, .. } = err.loc() else {
+        panic!("Expected a source position");
+    };
+    assert_eq!((pos.line, pos.column), (3, 3));
+    assert_eq!(&text[location::byte_offset(text, pos).unwrap()..], "x\r\n}");
+    assert_eq!(location::end_position(text).line, 4);
+
+    // Invalid UTF-8 is reported where it begins.
+    let mut bytes = b"builtin.module @m {\r\n^entry():\r\n  ".to_vec();
+    bytes.extend([0xC3, 0x28]);
+    std::fs::write(&path, &bytes).unwrap();
+    let err = parse_source(ctx, &path).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<ParseSourceErr>(),
+        Some(ParseSourceErr::NotUtf8 { offset: 34 })
+    ));
+    let location::Location::SrcPos { pos, .. } = err.loc() else {
+        panic!("Expected a source position");
+    };
+    assert_eq!((pos.line, pos.column), (3, 3));
+
+    std::fs::write(&path, [0xFF, 0xFE, b'b', 0]).unwrap();
+    let err = parse_source(ctx, &path).err().unwrap();
+    assert!(matches!(
+        err.err.downcast_ref::<ParseSourceErr>(),
+        Some(ParseSourceErr::Utf16)
+    ));
+    Ok(())
+}
+
+#[test]
+fn parse_err_multiple_def() {
+    let input_multiple_ssa_defs = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            ^entry_block_1_0():
+                c0_op_2_0_res0 = test.constant builtin.integer <0 : si64>;
+                c0_op_2_0_res0 = test.constant builtin.integer <0 : si64>;
+                test.return c0_op_2_0_res0
+            ^exit():
+            }
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 7, column: 17
+        Identifier c0_op_2_0_res0 defined more than once in the scope
+    "#]];
+    expect_parse_error(input_multiple_ssa_defs, expected_err);
+
+    let input_multiple_label_defs = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            ^entry_block_1_0():
+                c0_op_2_0_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_2_0_res0
+            ^entry_block_1_0():
+            }
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 8, column: 13
+        Identifier entry_block_1_0 defined more than once in the scope
+    "#]];
+    expect_parse_error(input_multiple_label_defs, expected_err);
+}
+
+#[test]
+fn parse_err_unresolved_def() {
+    let input_multiple_defs = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            ^entry_block_1_0():
+                test.return c0_op_2_0_res0
+            }
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 4, column: 80
+        Identifier c0_op_2_0_res0 was not resolved to any definition in the scope
+    "#]];
+    expect_parse_error(input_multiple_defs, expected_err);
+}
+
+#[test]
+fn parse_err_block_label_colon() {
+    let input_label_colon_missing = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> {
+            ^entry_block_1_0():
+                c0_op_2_0_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_2_0_res0
+            ^exit()
+            }
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 9, column: 13
+        Unexpected `}`
+        Expected whitespace or `:`
+    "#]];
+
+    expect_parse_error(input_label_colon_missing, expected_err);
+}
+
+#[test]
+fn parse_err_block_args() {
+    let input_label_colon_missing = r#"
+        builtin.module @bar {
+        ^block_0_0(a : builtin.integer si32, b):
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 3, column: 47
+        Unexpected `)`
+        Expected whitespaces or `:`
+    "#]];
+
+    expect_parse_error(input_label_colon_missing, expected_err);
+}
+
+#[test]
+fn parse_aliases() -> Result<()> {
+    // Aliases are used before being defined, and may refer to other aliases.
+    let input = r#"
+        !fn_ty = builtin.function <() -> (!i64)>;
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: !fn_ty [(test_attr: #unit)] {
+            ^entry_block_1_0(a : !i32):
+                c0_op_2_0_res0 = test.constant builtin.integer <0: si64>;
+                !i32 = builtin.integer si32;
+                test.return c0_op_2_0_res0
+            }
+        };
+        !i64 = builtin.integer si64;
+        #unit = builtin.unit"#;
+
+    let ctx = &mut setup_context_dialects();
+    let op = {
+        let state_stream = state_stream_from_iterator(
+            input.chars(),
+            parsable::State::new(ctx, location::Source::InMemory),
+        );
+        spaced(Operation::parser(())).parse(state_stream).unwrap().0
+    };
+    op.verify(ctx)?;
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> [(test_attr: builtin.unit )] 
+            {
+              ^entry_block_1_0(a_block_1v1_arg0:builtin.integer si32):
+                c0_op_2_0_res0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_2_0_res0_op_3v1_res0
+            }
+        }"#]]
+    .assert_eq(&op.disp(ctx).to_string());
+    Ok(())
+}
+
+#[test]
+fn parse_err_unresolved_alias() {
+    let input = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (!i64)> [(test_attr: #unit)] {
+            ^entry_block_1_0(a : !i64):
+                test.return a
+            }
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 4, column: 57
+        Unresolved aliases: !i64, #unit
+    "#]];
+    expect_parse_error(input, expected_err);
+
+    let input = r#"
+        !i64 = builtin.integer si64;
+        !i64 = builtin.integer si64;
+        builtin.module @bar {
+        ^block_0_0():
+        }"#;
+
+    let expected_err = expect![[r#"
+        Parse error at line: 3, column: 9
+        Alias !i64 defined more than once
+    "#]];
+    expect_parse_error(input, expected_err);
+}
+
+#[test]
+fn test_preorder_forward_walk() {
+    let ctx = &mut setup_context_dialects();
+    let module_op = const_ret_in_mod(ctx).unwrap().0.operation();
+
+    let mut state = Vec::new();
+
+    walkers::walk_op(
+        ctx,
+        &mut state,
+        &WALKCONFIG_PREORDER_FORWARD,
+        module_op,
+        |_ctx, state, node| {
+            if let IRNode::Operation(op) = node {
+                state.push(op);
+            }
+        },
+    );
+    assert_eq!(walkers::collect_ops(ctx, module_op), state);
+
+    let ops = state.into_iter().fold("".to_string(), |accum, op| {
+        accum + &op.disp(ctx).to_string() + "\n"
+    });
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
                 c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
                 test.return c0_op_3v1_res0
             }
         }
         builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
         {
-          ^entry_block_2v1():
+          ^entry():
             c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
             test.return c0_op_3v1_res0
         }
@@ -404,97 +1388,933 @@ fn test_preorder_forward_walk() {
     "#]]
     .assert_eq(&ops);
 
-    Operation::erase(module_op, ctx);
-    assert!(ctx.operations.is_empty());
-    assert!(ctx.regions.is_empty());
-    assert!(ctx.basic_blocks.is_empty());
+    Operation::erase(module_op, ctx);
+    assert!(ctx.operations.is_empty());
+    assert!(ctx.regions.is_empty());
+    assert!(ctx.basic_blocks.is_empty());
+}
+
+#[test]
+fn test_postorder_forward_walk() {
+    let ctx = &mut setup_context_dialects();
+    let module_op = const_ret_in_mod(ctx).unwrap().0.operation();
+
+    let mut state = Vec::new();
+
+    walkers::walk_op(
+        ctx,
+        &mut state,
+        &WALKCONFIG_POSTORDER_FORWARD,
+        module_op,
+        |_ctx, state, node| {
+            if let IRNode::Operation(op) = node {
+                state.push(op);
+            }
+        },
+    );
+
+    let ops = state.into_iter().fold("".to_string(), |accum, op| {
+        accum + &op.disp(ctx).to_string() + "\n"
+    });
+    expect![[r#"
+        c0_op_3v1_res0 = test.constant builtin.integer <0: si64>
+        test.return c0_op_3v1_res0
+        builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
+        {
+          ^entry():
+            c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
+            test.return c0_op_3v1_res0
+        }
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
+                test.return c0_op_3v1_res0
+            }
+        }
+    "#]]
+    .assert_eq(&ops);
+}
+
+#[test]
+fn op_index() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx)?;
+
+    let mut index = OpIndex::new(ctx, module_op.operation());
+    assert!(index.ops_of::<ModuleOp>(ctx).eq([module_op]));
+    assert!(index.ops_of::<FuncOp>(ctx).eq([func_op]));
+    assert!(index.ops_of::<ReturnOp>(ctx).eq([ret_op]));
+    let mut counts: Vec<_> = index
+        .counts(ctx)
+        .map(|(opid, count)| (opid.to_string(), count))
+        .collect();
+    counts.sort();
+    expect![[r#"
+        [
+            (
+                "builtin.func",
+                1,
+            ),
+            (
+                "builtin.module",
+                1,
+            ),
+            (
+                "test.constant",
+                1,
+            ),
+            (
+                "test.return",
+                1,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&counts);
+
+    // Erased ops are skipped, and new ops are indexed on insertion.
+    Operation::erase(ret_op.operation(), ctx);
+    assert_eq!(index.count(ctx, ReturnOp::opid_static()), 0);
+    let const1_op = ConstantOp::new(ctx, 1);
+    const1_op
+        .operation()
+        .insert_after(ctx, const_op.operation());
+    index.insert(ctx, const1_op.operation());
+    let consts: Vec<_> = index.ops_of::<ConstantOp>(ctx).collect();
+    assert!(consts.len() == 2 && consts[0] == const_op && consts[1] == const1_op);
+    Ok(())
+}
+
+#[test]
+fn test_walker_find_op() {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, _, const_op, _) = const_ret_in_mod(ctx).unwrap();
+
+    let const1_op = ConstantOp::new(ctx, 1);
+    const1_op
+        .operation()
+        .insert_after(ctx, const_op.operation());
+    set_operation_result_name(ctx, const1_op.operation(), 0, "c1".try_into().unwrap());
+
+    // A function to breaks the walk when a [ConstantOp] is found.
+    fn finder(
+        ctx: &mut Context,
+        _: &mut (),
+        node: IRNode,
+    ) -> interruptible::WalkResult<ConstantOp> {
+        if let IRNode::Operation(op) = node {
+            if let Some(const_op) = Operation::op(op, ctx).downcast_ref::<ConstantOp>() {
+                return walk_break(*const_op);
+            }
+        }
+        walk_advance()
+    }
+
+    let res1 = walkers::interruptible::walk_op(
+        ctx,
+        &mut (),
+        &WALKCONFIG_PREORDER_FORWARD,
+        module_op.operation(),
+        finder,
+    );
+    assert!(matches!(res1, interruptible::WalkResult::Break(c) if c == const_op));
+
+    let res2 = walkers::interruptible::walk_op(
+        ctx,
+        &mut (),
+        &WALKCONFIG_POSTORDER_REVERSE,
+        module_op.operation(),
+        finder,
+    );
+    assert!(matches!(res2, interruptible::WalkResult::Break(c) if c == const1_op));
+}
+
+#[test]
+fn test_visitor_walk() {
+    let ctx = &mut setup_context_dialects();
+    let (module_op, func_op, const_op, ret_op) = const_ret_in_mod(ctx).unwrap();
+
+    // Counts the nodes visited, skipping the bodies of functions.
+    #[derive(Default)]
+    struct Counter {
+        ops: usize,
+        blocks: usize,
+        regions: usize,
+    }
+    impl Visitor for Counter {
+        type Break = ();
+        fn visit_op(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> WalkResult<()> {
+            self.ops += 1;
+            if Operation::op(op, ctx).downcast_ref::<FuncOp>().is_some() {
+                return walk_skip();
+            }
+            walk_advance()
+        }
+        fn visit_block(&mut self, _ctx: &mut Context, _block: Ptr<BasicBlock>) -> WalkResult<()> {
+            self.blocks += 1;
+            walk_advance()
+        }
+        fn visit_region(&mut self, _ctx: &mut Context, _region: Ptr<Region>) -> WalkResult<()> {
+            self.regions += 1;
+            walk_advance()
+        }
+    }
+    let mut counter = Counter::default();
+    let res = Operation::walk(
+        module_op.operation(),
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut counter,
+    );
+    assert!(matches!(res, WalkResult::Continue(_)));
+    assert_eq!((counter.ops, counter.blocks, counter.regions), (2, 1, 1));
+
+    // A closure interrupting the walk at the first operation visited in post-order.
+    let region = func_op.region(ctx);
+    let mut visited = vec![];
+    let res = Region::walk(
+        region,
+        ctx,
+        &WALKCONFIG_POSTORDER_REVERSE,
+        &mut |_ctx: &mut Context, node| {
+            visited.push(matches!(node, IRNode::Operation(_)));
+            match node {
+                IRNode::Operation(op) => walk_break(op),
+                _ => walk_advance(),
+            }
+        },
+    );
+    assert!(matches!(res, WalkResult::Break(op) if op == ret_op.operation()));
+    assert_eq!(visited, vec![true]);
+
+    // Blocks walk their operations.
+    let entry = const_op.operation().deref(ctx).container().unwrap();
+    let mut num_ops = 0;
+    let _ = BasicBlock::walk(
+        entry,
+        ctx,
+        &WALKCONFIG_PREORDER_FORWARD,
+        &mut |_ctx: &mut Context, node| {
+            if let IRNode::Operation(_) = node {
+                num_ops += 1;
+            }
+            walk_advance::<()>()
+        },
+    );
+    assert_eq!(num_ops, 2);
+}
+
+#[test]
+fn modification_epochs() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, foo, const_op, _) = const_ret_in_mod(ctx)?;
+    let bar_ty = FunctionType::get(ctx, vec![], vec![]);
+    let bar = FuncOp::new(ctx, &"bar".try_into().unwrap(), bar_ty);
+    module.append_operation(ctx, bar.operation(), 0);
+    let (module, foo, bar) = (module.operation(), foo.operation(), bar.operation());
+
+    // Reading the IR doesn't modify it.
+    let epoch = ctx.modification_epoch();
+    module.verify(ctx)?;
+    module.disp(ctx).to_string();
+    assert!(!Operation::modified_since(module, ctx, epoch));
+
+    // Changing an attribute modifies the operation, and (transitively) its ancestors.
+    const_op
+        .operation()
+        .deref_mut(ctx)
+        .attributes
+        .set("note".try_into().unwrap(), StringAttr::new("x".to_string()));
+    assert!(const_op.operation().deref(ctx).modified_epoch() > epoch);
+    assert!(foo.deref(ctx).modified_epoch() <= epoch);
+    assert!(Operation::modified_since(foo, ctx, epoch));
+    assert!(Operation::modified_since(module, ctx, epoch));
+    assert!(!Operation::modified_since(bar, ctx, epoch));
+
+    // Erasing the only operation in a block modifies the block.
+    let bar_entry = bar.deref(ctx).region(0).deref(ctx).head().unwrap();
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    c1.insert_at_back(bar_entry, ctx);
+    let epoch = ctx.modification_epoch();
+    Operation::erase(c1, ctx);
+    assert!(bar_entry.deref(ctx).modified_epoch() > epoch);
+    assert!(Operation::modified_since(bar, ctx, epoch));
+    assert!(!Operation::modified_since(foo, ctx, epoch));
+    Ok(())
 }
 
+/// Marks every operation modified (by someone else) with a `touched` attribute.
+#[derive(Default)]
+struct TouchListener {
+    notified: Vec<Ptr<Operation>>,
+}
+
+impl RewriteListener for TouchListener {
+    fn notify_op_modified(&mut self, ctx: &Context, op: Ptr<Operation>) {
+        self.notified.push(op);
+        if let Some(mut op) = op.try_deref_mut(ctx) {
+            op.attributes
+                .set("touched".try_into().unwrap(), UnitAttr::new());
+        }
+    }
+}
+
+// Listeners can modify the IR, and aren't notified of their own modifications.
 #[test]
-fn test_postorder_forward_walk() {
+fn listener_modifies_ir() -> Result<()> {
     let ctx = &mut setup_context_dialects();
-    let module_op = const_ret_in_mod(ctx).unwrap().0.operation();
+    let (_, _, const_op, _) = const_ret_in_mod(ctx)?;
+    let const_op = const_op.operation();
+    let toucher = Rc::new(RefCell::new(TouchListener::default()));
+    let tracker = Rc::new(RefCell::new(ChangeTracker::default()));
+    let toucher_listener: Rc<RefCell<dyn RewriteListener>> = toucher.clone();
+    let tracker_listener: Rc<RefCell<dyn RewriteListener>> = tracker.clone();
+    ctx.add_listener(toucher_listener);
+    ctx.add_listener(tracker_listener);
 
-    let mut state = Vec::new();
+    // Mutable dereferences that are only read through aren't modifications.
+    let epoch = ctx.modification_epoch();
+    let _ = const_op.deref_mut(ctx).attributes.0.len();
+    assert!(const_op.deref(ctx).modified_epoch() <= epoch);
+    assert!(toucher.borrow().notified.is_empty() && tracker.borrow().is_empty());
 
-    walkers::walk_op(
-        ctx,
-        &mut state,
-        &WALKCONFIG_POSTORDER_FORWARD,
-        module_op,
-        |_ctx, state, node| {
-            if let IRNode::Operation(op) = node {
-                state.push(op);
-            }
-        },
+    const_op
+        .deref_mut(ctx)
+        .attributes
+        .set("note".try_into().unwrap(), StringAttr::new("x".to_string()));
+    assert!(const_op.deref(ctx).modified_epoch() > epoch);
+    // The listener was notified once, and not again of its own change.
+    assert_eq!(toucher.borrow().notified, vec![const_op]);
+    let touched: Identifier = "touched".try_into().unwrap();
+    assert!(
+        const_op
+            .deref(ctx)
+            .attributes
+            .get::<UnitAttr>(&touched)
+            .is_some()
     );
+    // Other listeners are notified of both changes.
+    assert_eq!(tracker.borrow().modified_ops(ctx), vec![const_op]);
+    Ok(())
+}
 
-    let ops = state.into_iter().fold("".to_string(), |accum, op| {
-        accum + &op.disp(ctx).to_string() + "\n"
+#[test]
+fn op_builder() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+    let func_ty = FunctionType::get(ctx, vec![], vec![i64_ty.into()]);
+    let func = FuncOp::new(ctx, &"foo".try_into().unwrap(), func_ty);
+
+    // Records what was built.
+    #[derive(Default)]
+    struct Recorder {
+        ops: Vec<Ptr<Operation>>,
+        blocks: Vec<Ptr<BasicBlock>>,
+    }
+    impl BuilderListener for Recorder {
+        fn notify_op_inserted(&mut self, _ctx: &Context, op: Ptr<Operation>) {
+            self.ops.push(op);
+        }
+        fn notify_block_created(&mut self, _ctx: &Context, block: Ptr<BasicBlock>) {
+            self.blocks.push(block);
+        }
+    }
+    let mut recorder = Recorder::default();
+    let mut builder = OpBuilder::new(InsertionPoint::AtBlockEnd(func.get_entry_block(ctx)))
+        .with_listener(&mut recorder);
+
+    // Operations are built in order, even at the start of a block.
+    builder.set_insertion_point_to_region_start(ctx, func.region(ctx));
+    let c0 = builder.create(ctx, |ctx| ConstantOp::new(ctx, 0));
+    let ret = builder.create(ctx, |ctx| {
+        let c0_res = c0.result(ctx);
+        ReturnOp::new(ctx, c0_res)
     });
+    builder.set_insertion_point(InsertionPoint::BeforeOp(ret.operation()));
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    builder.insert(ctx, c1);
+    assert!(builder.insertion_point().block(ctx) == func.get_entry_block(ctx));
+    func.operation().verify(ctx)?;
     expect![[r#"
-        c0_op_3v1_res0 = test.constant builtin.integer <0: si64>
-        test.return c0_op_3v1_res0
         builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
         {
-          ^entry_block_2v1():
-            c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
-            test.return c0_op_3v1_res0
+          ^entry():
+            op_2v1_res0 = test.constant builtin.integer <0: si64>;
+            op_4v1_res0 = test.constant builtin.integer <1: si64>;
+            test.return op_2v1_res0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+
+    // Blocks are created on demand, and built at their end.
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let region = module.region(ctx);
+    let old_entry = region.deref(ctx).head().unwrap();
+    BasicBlock::erase(old_entry, ctx);
+    builder.set_insertion_point_to_region_start(ctx, region);
+    builder.insert(ctx, func.operation());
+    module.operation().verify(ctx)?;
+    let entry = region.deref(ctx).head().unwrap();
+    assert!(recorder.blocks == vec![entry]);
+    assert!(recorder.ops == vec![c0.operation(), ret.operation(), c1, func.operation()]);
+    Ok(())
+}
+
+#[test]
+fn print_generic() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let module_op = const_ret_in_mod(ctx)?.0.operation();
+    let state = printable::State::default();
+    state.set_print_generic(true);
+    let printed = format!("{}", module_op.print(ctx, &state));
+    expect![[r#"
+        "builtin.module" () [] [(builtin_sym_name: builtin.identifier (bar))]: <() -> ()>
+        {
+          ^bb0():
+            "builtin.func" () [] [(builtin_func_type: builtin.type builtin.function <()->(builtin.integer si64)>), (builtin_sym_name: builtin.identifier (foo))]: <() -> ()>
+            {
+              ^entry():
+                c0_op_3v1_res0 = "test.constant" () [] [(builtin_debug_info: builtin.dict {debug_info_name = builtin.vec [builtin.identifier (c0)]}), (constant_value: builtin.integer <0: si64>)]: <() -> (builtin.integer si64)>;
+                "test.return" (c0_op_3v1_res0) [] []: <(builtin.integer si64) -> ()>
+            }
+        }"#]]
+    .assert_eq(&printed);
+    Ok(())
+}
+
+#[test]
+fn print_numbered_values() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, _, const_op, _) = const_ret_in_mod(ctx)?;
+    // Another value named `c0`, and one without a name.
+    let dup_op = ConstantOp::new(ctx, 1);
+    dup_op.operation().insert_after(ctx, const_op.operation());
+    set_operation_result_name(ctx, dup_op.operation(), 0, "c0".try_into().unwrap());
+    let unnamed_op = ConstantOp::new(ctx, 2);
+    unnamed_op.operation().insert_after(ctx, dup_op.operation());
+
+    let state = printable::State::default();
+    state.set_numbered_values(true);
+    let printed = module.operation().print(ctx, &state).to_string();
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                c0 = test.constant builtin.integer <0: si64>;
+                c0_1 = test.constant builtin.integer <1: si64>;
+                v0 = test.constant builtin.integer <2: si64>;
+                test.return c0
+            }
+        }"#]]
+    .assert_eq(&printed);
+
+    // Printed values have the same names, however the IR was built.
+    let reparsed = parse_source(ctx, printed.as_str())?;
+    assert_eq!(reparsed.print(ctx, &state).to_string(), printed);
+    Ok(())
+}
+
+#[test]
+fn print_elided_attrs() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, _, const_op, _) = const_ret_in_mod(ctx)?;
+    let strings = |n: usize| {
+        let strings = (0..n).map(|i| StringAttr::new(i.to_string()).into());
+        VecAttr::new(strings.collect())
+    };
+    let mut op_ref = const_op.operation().deref_mut(ctx);
+    op_ref
+        .attributes
+        .set("large".try_into().unwrap(), strings(5));
+    op_ref
+        .attributes
+        .set("small".try_into().unwrap(), strings(2));
+    drop(op_ref);
+
+    let state = printable::State::default();
+    state.set_print_generic(true);
+    state.set_attr_elision_limit(Some(3));
+    let printed = format!("{}", const_op.operation().print(ctx, &state));
+    expect![[r#"c0_op_3v1_res0 = "test.constant" () [] [(builtin_debug_info: builtin.dict {debug_info_name = builtin.vec [builtin.identifier (c0)]}), (constant_value: builtin.integer <0: si64>), (large: builtin.vec [...5 elements...]), (small: builtin.vec [builtin.string "0", builtin.string "1"])]: <() -> (builtin.integer si64)>"#]]
+    .assert_eq(&printed);
+
+    // Elided attributes are printed as resources after the module, and parsed back.
+    let printed = parsable::print_source_with(ctx, module, &state);
+    assert!(printed.contains("(large: builtin.vec [...5 elements at #resource0...])"));
+    let large = r#"builtin.vec [builtin.string "0", builtin.string "1", builtin.string "2", builtin.string "3", builtin.string "4"]"#;
+    assert!(printed.ends_with(&format!(";\n#resource0 = {large}")));
+    let parsed = parse_source(ctx, printed.as_str())?;
+    state.set_attr_elision_limit(None);
+    let reprinted = format!("{}", parsed.operation().print(ctx, &state));
+    assert!(reprinted.contains(&format!("(large: {large})")));
+    state.set_attr_elision_limit(Some(3));
+
+    // But not without them.
+    let printed = format!("{}", module.operation().print(ctx, &state));
+    let err = parse_source(ctx, printed.as_str()).err().unwrap();
+    let Some(ParseSourceErr::Diagnostics(diagnostics)) = err.err.downcast_ref::<ParseSourceErr>()
+    else {
+        panic!("Expected parse diagnostics");
+    };
+    assert!(matches!(
+        diagnostics[0].downcast_ref::<ElidedAttrErr>(),
+        Some(ElidedAttrErr(5))
+    ));
+    Ok(())
+}
+
+/// Marks the highlighted items, instead of coloring them.
+struct MarkerTheme;
+
+impl printable::Theme for MarkerTheme {
+    fn prefix(&self, highlight: Highlight) -> &str {
+        match highlight {
+            Highlight::OpId => "{op:",
+            Highlight::Type => "{ty:",
+            Highlight::Attribute => "{attr:",
+            Highlight::Value => "{val:",
+            Highlight::BlockLabel => "{label:",
         }
+    }
+
+    fn suffix(&self, _highlight: Highlight) -> &str {
+        "}"
+    }
+}
+
+#[test]
+fn print_themed() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let module_op = const_ret_in_mod(ctx)?.0.operation();
+    let state = printable::State::default();
+    state.set_theme(Some(Rc::new(MarkerTheme)));
+    let printed = format!("{}", module_op.print(ctx, &state));
+    expect![[r#"
+        {op:builtin.module} @bar 
+        {
+          {label:^bb0}():
+            {op:builtin.func} @foo: {ty:builtin.function <()->({ty:builtin.integer si64}{ty:)>} 
+            {
+              {label:^entry}():
+                {val:c0_op_3v1_res0} = {op:test.constant} {attr:builtin.integer <0: si64>};
+                {op:test.return} {val:c0_op_3v1_res0}
+            }
+        }"#]]
+    .assert_eq(&printed);
+
+    state.set_theme(Some(Rc::new(AnsiTheme::default())));
+    let printed = format!("{}", module_op.print(ctx, &state));
+    assert!(printed.contains("\x1b[1;34mtest.constant\x1b[0m"));
+
+    // Printing without a theme is unaffected.
+    state.set_theme(None);
+    let printed = format!("{}", module_op.print(ctx, &state));
+    assert_eq!(printed, format!("{}", module_op.disp(ctx)));
+    Ok(())
+}
+
+#[test]
+fn parse_generic() -> Result<()> {
+    let input = r#"
+        "builtin.module" () [] [(builtin_sym_name: builtin.identifier (bar))]: <() -> ()>
+        {
+          ^block_0_0():
+            "builtin.func" () [] [(builtin_func_type: builtin.type builtin.function <()->(builtin.integer si64)>), (builtin_sym_name: builtin.identifier (foo))]: <() -> ()>
+            {
+              ^entry_block_1_0():
+                c0 = "test.constant" () [] [(constant_value: builtin.integer <0: si64>)]: <() -> (builtin.integer si64)>;
+                test.return c0
+            }
+        }"#;
+
+    let ctx = &mut setup_context_dialects();
+    let op = {
+        let state_stream = state_stream_from_iterator(
+            input.chars(),
+            parsable::State::new(ctx, location::Source::InMemory),
+        );
+        spaced(Operation::parser(())).parse(state_stream).unwrap().0
+    };
+    op.verify(ctx)?;
+    expect![[r#"
         builtin.module @bar 
         {
-          ^block_1v1():
+          ^block_0_0():
             builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
             {
-              ^entry_block_2v1():
+              ^entry_block_1_0():
                 c0_op_3v1_res0 = test.constant builtin.integer <0: si64>;
                 test.return c0_op_3v1_res0
             }
+        }"#]]
+    .assert_eq(&op.disp(ctx).to_string());
+    Ok(())
+}
+
+#[test]
+fn dynamic_entities() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let dialect = DialectName::new("dyn");
+    Dialect::new(dialect).register(ctx);
+    let vec_id = TypeId {
+        dialect,
+        name: TypeName::new("vec"),
+    };
+    let tag_id = AttrId {
+        dialect,
+        name: AttrName::new("tag"),
+    };
+    let combine_id = OpId {
+        dialect,
+        name: OpName::new("combine"),
+    };
+    let vec_def = DynamicTypeDef::register(ctx, vec_id, |ty, _ctx| {
+        if ty.params().len() != 1 {
+            return verify_err_noloc!("dyn.vec must have exactly one parameter");
         }
-    "#]]
-    .assert_eq(&ops);
+        Ok(())
+    })?;
+    DynamicAttrDef::register(ctx, tag_id, |_, _| Ok(()))?;
+    DynamicOpDef::register(ctx, combine_id, |op, ctx| {
+        if op.operation().deref(ctx).num_operands() != 2 {
+            return verify_err_noloc!("dyn.combine must have exactly two operands");
+        }
+        Ok(())
+    })?;
+    assert!(DynamicOpDef::register(ctx, combine_id, |_, _| Ok(())).is_err());
+    assert!(DynamicTypeDef::lookup(ctx, &vec_id).is_some());
+
+    let input = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <(dyn.vec <builtin.integer <4: si32>>) -> (builtin.integer si64)> {
+            ^entry_block_1_0(a : dyn.vec <builtin.integer <4: si32>>):
+                c = dyn.combine (a, a) [] [(kind: dyn.tag <builtin.unit >)]: <(dyn.vec <builtin.integer <4: si32>>, dyn.vec <builtin.integer <4: si32>>) -> (builtin.integer si64)>;
+                test.return c
+            }
+        }"#;
+    let parse = |ctx: &mut Context, input: &str| {
+        let state_stream = state_stream_from_iterator(
+            input.chars(),
+            parsable::State::new(ctx, location::Source::InMemory),
+        );
+        spaced(Operation::parser(())).parse(state_stream).unwrap().0
+    };
+    let op = parse(ctx, input);
+    op.verify(ctx)?;
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <(dyn.vec <builtin.integer <4: si32>>)->(builtin.integer si64)> 
+            {
+              ^entry_block_1_0(a_block_1v1_arg0:dyn.vec <builtin.integer <4: si32>>):
+                c_op_4v1_res0 = dyn.combine (a_block_1v1_arg0, a_block_1v1_arg0) [] [(builtin_debug_info: builtin.dict {debug_info_name = builtin.vec [builtin.identifier (c)]}), (kind: dyn.tag <builtin.unit >)]: <(dyn.vec <builtin.integer <4: si32>>, dyn.vec <builtin.integer <4: si32>>) -> (builtin.integer si64)>;
+                test.return c_op_4v1_res0
+            }
+        }"#]]
+    .assert_eq(&op.disp(ctx).to_string());
+
+    // Dynamic types are checked by their verifiers.
+    let bad_vec = DynamicType::get(ctx, &vec_def, vec![]);
+    assert!(matches!(
+        bad_vec.to_ptr().deref(ctx).verify(ctx),
+        Err(Error {
+            kind: ErrorKind::VerificationFailed,
+            ..
+        })
+    ));
+
+    // Verification of dynamic ops runs their verifiers.
+    let input = input.replace("(a, a)", "(a)").replace(
+        "<(dyn.vec <builtin.integer <4: si32>>, dyn.vec",
+        "<(dyn.vec",
+    );
+    let op = parse(ctx, &input);
+    assert!(matches!(
+        op.verify(ctx),
+        Err(Error {
+            kind: ErrorKind::VerificationFailed,
+            ..
+        })
+    ));
+    Ok(())
 }
 
+// Different entities can't be registered with the same id,
+// and the error lists where each of them is registered.
 #[test]
-fn test_walker_find_op() {
+fn duplicate_registrations() -> Result<()> {
     let ctx = &mut setup_context_dialects();
-    let (module_op, _, const_op, _) = const_ret_in_mod(ctx).unwrap();
+    // Registering the same Op again is fine.
+    DualDefOp::register(ctx, DualDefOp::parser_fn);
+    DualDefOp::register(ctx, DualDefOp::parser_fn);
 
-    let const1_op = ConstantOp::new(ctx, 1);
-    const1_op
-        .operation()
-        .insert_after(ctx, const_op.operation());
-    set_operation_result_name(ctx, const1_op.operation(), 0, "c1".try_into().unwrap());
+    let Err(err) = DynamicOpDef::register(ctx, DualDefOp::opid_static(), |_, _| Ok(())) else {
+        panic!("Dynamic Op registered with the id of a Rust Op");
+    };
+    let re = regex::Regex::new(
+        "Op test.dual_def is registered twice: by `ir_construct::DualDefOp` at \
+         tests/ir_construct.rs:[0-9]+:[0-9]+, and by a dynamic definition at \
+         tests/ir_construct.rs:[0-9]+:[0-9]+",
+    )
+    .unwrap();
+    assert!(re.is_match(&err.to_string()), "{}", err);
 
-    // A function to breaks the walk when a [ConstantOp] is found.
-    fn finder(
-        ctx: &mut Context,
-        _: &mut (),
-        node: IRNode,
-    ) -> interruptible::WalkResult<ConstantOp> {
-        if let IRNode::Operation(op) = node {
-            if let Some(const_op) = Operation::op(op, ctx).downcast_ref::<ConstantOp>() {
-                return walk_break(*const_op);
+    // A dynamic type can't shadow a builtin type.
+    let integer_id = IntegerType::get_type_id_static();
+    let Err(err) = DynamicTypeDef::register(ctx, integer_id, |_, _| Ok(())) else {
+        panic!("Dynamic type registered with the id of a builtin type");
+    };
+    assert!(
+        err.to_string().contains(
+            "Type builtin.integer is registered twice: by `pliron::builtin::types::IntegerType`"
+        ),
+        "{}",
+        err
+    );
+    assert!(DynamicTypeDef::lookup(ctx, &integer_id).is_none());
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Op test.dual_def is registered twice: by `ir_construct::DualDefOp`")]
+fn duplicate_op_registration() {
+    let ctx = &mut setup_context_dialects();
+    DualDefOp::register(ctx, DualDefOp::parser_fn);
+    ShadowingDualDefOp::register(ctx, ShadowingDualDefOp::parser_fn);
+}
+
+// Interfaces are implemented for an id, so another Op with that id
+// can't be registered, even if the Op implementing them isn't.
+#[test]
+#[should_panic(
+    expected = "Op test.interface_def is registered twice: by a Rust type implementing `IsTerminatorInterface` for it, and by `ir_construct::ShadowingInterfaceDefOp`"
+)]
+fn shadowed_op_interfaces() {
+    let ctx = &mut setup_context_dialects();
+    ShadowingInterfaceDefOp::register(ctx, ShadowingInterfaceDefOp::parser_fn);
+}
+
+#[test]
+fn unregistered_entities() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let input = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <(foo.vec <4 x (i32)>) -> (foo.ptr)> {
+            ^entry_block_1_0(a : foo.vec <4 x (i32)>):
+                c = foo.combine (a, a) [] [(kind: foo.tag <"a>b", [1, 2]>)]: <(foo.vec <4 x (i32)>, foo.vec <4 x (i32)>) -> (foo.ptr)>;
+                d = foo.wrap () [] [(sig: builtin.type foo.fn <(i32) -> i64>)]: <() -> (foo.ptr)> {
+                  ^inner():
+                    test.return c
+                };
+                test.return d
             }
-        }
-        walk_advance()
-    }
+        }"#;
+    let parse = |ctx: &mut Context, input: &str| {
+        let state_stream = state_stream_from_iterator(
+            input.chars(),
+            parsable::State::new(ctx, location::Source::InMemory),
+        );
+        spaced(Operation::parser(()))
+            .parse(state_stream)
+            .map(|(op, _)| op)
+    };
+    // Rejected, unless unregistered dialects are allowed.
+    assert!(parse(ctx, input).is_err());
+    ctx.allow_unregistered_dialects(true);
+    let op = parse(ctx, input).unwrap();
+    op.verify(ctx)?;
+    let printed = op.disp(ctx).to_string();
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <(foo.vec <4 x (i32)>)->(foo.ptr )> 
+            {
+              ^entry_block_1_0(a_block_2v1_arg0:foo.vec <4 x (i32)>):
+                c_op_6v1_res0 = foo.combine (a_block_2v1_arg0, a_block_2v1_arg0) [] [(builtin_debug_info: builtin.dict {debug_info_name = builtin.vec [builtin.identifier (c)]}), (kind: foo.tag <"a>b", [1, 2]>)]: <(foo.vec <4 x (i32)>, foo.vec <4 x (i32)>) -> (foo.ptr )>;
+                d_op_7v1_res0 = foo.wrap () [] [(builtin_debug_info: builtin.dict {debug_info_name = builtin.vec [builtin.identifier (d)]}), (sig: builtin.type foo.fn <(i32) -> i64>)]: <() -> (foo.ptr )>
+                {
+                  ^inner():
+                    test.return c_op_6v1_res0
+                };
+                test.return d_op_7v1_res0
+            }
+        }"#]]
+    .assert_eq(&printed);
 
-    let res1 = walkers::interruptible::walk_op(
-        ctx,
-        &mut (),
-        &WALKCONFIG_PREORDER_FORWARD,
-        module_op.operation(),
-        finder,
+    // The entities are kept as is.
+    let module = *Operation::op(op, ctx).downcast_ref::<ModuleOp>().unwrap();
+    let func = module.lookup(ctx, &"foo".try_into().unwrap()).unwrap();
+    let func = *Operation::op(func, ctx).downcast_ref::<FuncOp>().unwrap();
+    let combine = func.get_entry_block(ctx).deref(ctx).head().unwrap();
+    assert!(Operation::op(combine, ctx).is::<UnregisteredOp>());
+    let a_ty = combine.deref(ctx).operand(0).get_type(ctx);
+    let a_payload = a_ty
+        .deref(ctx)
+        .downcast_ref::<OpaqueType>()
+        .unwrap()
+        .payload()
+        .map(String::from);
+    assert_eq!(a_payload.as_deref(), Some("4 x (i32)"));
+    let kind = combine
+        .deref(ctx)
+        .attributes
+        .get::<OpaqueAttr>(&"kind".try_into().unwrap())
+        .unwrap()
+        .payload()
+        .map(String::from);
+    assert_eq!(kind.as_deref(), Some(r#""a>b", [1, 2]"#));
+    // Printed IR parses back, to the same (uniqued) types.
+    let reparsed = parse(ctx, &printed).unwrap();
+    reparsed.verify(ctx)?;
+    let reparsed = *Operation::op(reparsed, ctx)
+        .downcast_ref::<ModuleOp>()
+        .unwrap();
+    let reparsed_func = reparsed.lookup(ctx, &"foo".try_into().unwrap()).unwrap();
+    let reparsed_func = *Operation::op(reparsed_func, ctx)
+        .downcast_ref::<FuncOp>()
+        .unwrap();
+    assert!(reparsed_func.get_type(ctx) == func.get_type(ctx));
+    Ok(())
+}
+
+#[test]
+fn import_and_link() -> Result<()> {
+    let from_ctx = &mut setup_context_dialects();
+    let (module, func, const_op, _) = const_ret_in_mod(from_ctx)?;
+    let src = location::Source::new_from_file(from_ctx, "unit.pliron".into());
+    let loc = location::Location::SrcPos {
+        src,
+        pos: Default::default(),
+    };
+    const_op
+        .operation()
+        .deref_mut(from_ctx)
+        .set_loc(loc.clone());
+
+    // Import into another context: types are re-interned, and locations preserved.
+    let into_ctx = &mut setup_context_dialects();
+    let imported = session::import_op(into_ctx, from_ctx, module.operation())?;
+    imported.verify(into_ctx)?;
+    let imported = *Operation::op(imported, into_ctx)
+        .downcast_ref::<ModuleOp>()
+        .unwrap();
+    let imported_func = imported
+        .lookup(into_ctx, &"foo".try_into().unwrap())
+        .unwrap();
+    let imported_func = *Operation::op(imported_func, into_ctx)
+        .downcast_ref::<FuncOp>()
+        .unwrap();
+    assert_eq!(
+        imported_func.get_type(into_ctx).disp(into_ctx).to_string(),
+        func.get_type(from_ctx).disp(from_ctx).to_string()
+    );
+    let imported_const = imported_func
+        .get_entry_block(into_ctx)
+        .deref(into_ctx)
+        .iter(into_ctx)
+        .next()
+        .unwrap();
+    assert_eq!(
+        imported_const
+            .deref(into_ctx)
+            .loc()
+            .disp(into_ctx)
+            .to_string(),
+        loc.disp(from_ctx).to_string()
     );
-    assert!(matches!(res1, interruptible::WalkResult::Break(c) if c == const_op));
 
-    let res2 = walkers::interruptible::walk_op(
-        ctx,
-        &mut (),
-        &WALKCONFIG_POSTORDER_REVERSE,
-        module_op.operation(),
-        finder,
+    // Link two units that both define `foo`.
+    let mut session = Session::new(setup_context_dialects);
+    for name in ["a", "b"] {
+        let mut ctx = session.new_context();
+        let (module, ..) = const_ret_in_mod(&mut ctx)?;
+        DataLayout::default().set(&ctx, module.operation());
+        session.add_unit(name, ctx, module);
+    }
+    let (ctx, linked) = session.link(&"linked".try_into().unwrap())?;
+    linked.operation().verify(&ctx)?;
+    let names: Vec<_> = linked
+        .body(&ctx, 0)
+        .deref(&ctx)
+        .iter(&ctx)
+        .map(|op| {
+            Operation::op(op, &ctx)
+                .downcast_ref::<FuncOp>()
+                .unwrap()
+                .symbol_name(&ctx)
+                .to_string()
+        })
+        .collect();
+    assert_eq!(names, ["foo", "foo_1_0"]);
+    assert_eq!(
+        DataLayout::of(&ctx, linked.operation())?,
+        DataLayout::default()
     );
-    assert!(matches!(res2, interruptible::WalkResult::Break(c) if c == const1_op));
+
+    // Units with different data layouts can't be linked.
+    let unit = &session.units()[1];
+    DataLayout::parse("p:32:32")?.set(&unit.ctx, unit.module.operation());
+    assert!(session.link(&"linked".try_into().unwrap()).is_err());
+    Ok(())
+}
+
+#[def_attribute("test.flags")]
+#[format_attribute]
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum FlagsAttr {
+    None,
+    Nsw,
+    Nuw,
+}
+impl_verify_succ!(FlagsAttr);
+
+#[test]
+fn completions() {
+    let ctx = &mut setup_context_dialects();
+    FlagsAttr::register_attr_in_dialect(ctx, FlagsAttr::parser_fn);
+    let mut suggest = |input: &str| -> Vec<String> {
+        complete(ctx, input, input.len())
+            .into_iter()
+            .map(|completion| format!("{:?} {}", completion.kind, completion.text))
+            .collect()
+    };
+
+    let block = "builtin.module @m {\n^entry():\n  ";
+    assert_eq!(
+        suggest(&format!("{block}t")),
+        ["Op test.constant", "Op test.return"]
+    );
+    assert!(suggest(block).contains(&"Token }".to_string()));
+    let func = format!("{block}builtin.func @f: ");
+    assert!(suggest(&func).contains(&"Type builtin.function".to_string()));
+    assert_eq!(
+        suggest(&format!(
+            "{func}builtin.function <() -> ()> [(a: test.flags Nu"
+        )),
+        ["Keyword Nuw"]
+    );
+    assert_eq!(
+        suggest(&format!("{func}builtin.function <() -> ()> [(a: test.f")),
+        ["Attribute test.flags"]
+    );
+    assert_eq!(
+        suggest(&format!("{func}builtin.integer ")),
+        ["Keyword i", "Keyword si", "Keyword ui"]
+    );
+
+    // The cursor needn't be at the end.
+    let input = "builtin.module @m {\n^entry():\n  builtin.fu }";
+    let completions = complete(ctx, input, input.len() - 2);
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].kind, CompletionKind::Op);
+    assert_eq!(completions[0].text, "builtin.func");
 }
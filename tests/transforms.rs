@@ -0,0 +1,2942 @@
+#[allow(dead_code)]
+mod common;
+
+use std::{cell::RefCell, rc::Rc, sync::LazyLock};
+
+use awint::bw;
+use combine::{Parser, stream::position::SourcePosition};
+use expect_test::expect;
+use pliron::{
+    attribute::AttrObj,
+    basic_block::BasicBlock,
+    builder::{InsertionPoint, OpBuilder, StructuredOps},
+    builtin::{
+        attributes::{IdentifierAttr, IntegerAttr, TypeAttr, UnitAttr, VecAttr},
+        op_interfaces::{
+            ATTR_KEY_CALLEE_TYPE, BranchOpInterface, CallOpCallable, CallOpInterface,
+            ConditionalBranchInterface, ConstantLikeInterface, ForLoopInterface,
+            HoistableConstantInterface, IsTerminatorInterface, MemoryEffect, MemoryEffectKind,
+            MemoryEffectOn, MemoryEffectOpInterface, OneOpdInterface, OneRegionInterface,
+            OneResultInterface, OpEquivalence, PureInterface, SingleBlockRegionInterface,
+            SymbolOpInterface, SymbolTableInterface, SymbolUserOpInterface, ZeroOpdInterface,
+            has_only_effects, is_pure, memory_effects,
+        },
+        ops::{FuncOp, ModuleOp, UnrealizedConversionCastOp, func_op},
+        types::{FunctionType, IntegerType, Signedness},
+    },
+    common_traits::Verify,
+    context::{Context, Ptr},
+    conversion::{
+        ConversionPattern, ConversionPatternSet, ConversionRewriter, ConversionTarget,
+        TypeConverter, apply_full_conversion, apply_partial_conversion,
+    },
+    derive::{def_op, derive_op_interface_impl, op_interface_impl},
+    dialect::DialectName,
+    identifier::Identifier,
+    impl_canonical_syntax, impl_verify_succ, input_err_noloc, input_error_noloc,
+    irfmt::{
+        parsers::{call_like_parse, spaced},
+        printers::op::{call_like, results},
+    },
+    linked_list::{ContainsLinkedList, LinkedList},
+    listener::{ChangeTracker, RewriteListener},
+    location::{self, Located, Location, Source},
+    op::{Op, OpId, OpObj, op_cast},
+    operation::Operation,
+    parsable::{self, Parsable, state_stream_from_iterator},
+    pass::{
+        Pass, PassManager, VerifyMode, has_attribute, is_op_kind,
+        record::{IrRecorder, MANIFEST_FILE, Recording},
+        symbol_name_matches,
+    },
+    pattern_match::{
+        GreedyRewriteConfig, GreedyRewritePass, PatternRewriter, RewritePattern, RewritePatternSet,
+        apply_patterns_greedily,
+    },
+    printable::{self, Printable},
+    result::{Error, ErrorKind, Result},
+    transforms::{
+        algebraic::{AlgebraicFoldPass, AlgebraicPropertiesInterface, fold_algebraic},
+        canonicalize::{CanonicalizeInterface, CanonicalizePass},
+        cse::CsePass,
+        dce::{DcePass, is_trivially_dead},
+        fold::{Foldable, OpFoldResult, fold_op, try_fold},
+        instrument::{HookPosition, InstrumentErr, InstrumentHook, InstrumentPass, instrument},
+        interpreter::{
+            TransformInterpreterPass, TransformRegistry, TransformScript, TransformScriptErr,
+        },
+        ipsccp::ipsccp,
+        loop_unroll::{LoopUnrollErr, unroll_by_factor, unroll_full},
+        outline::{OutlineErr, outline_ops},
+        promote_constants::promote_constants,
+        reconcile_casts::{ReconcileCastsPass, reconcile_unrealized_casts},
+        signature::{NewArg, append_args, drop_unused_args, rewrite_func_args},
+        strip::{StripStats, strip_attributes, strip_debug_info, strip_locations},
+        structured_cfg::{
+            LowerToCfgInterface, Structured, StructuredCfgErr, inline_region_before,
+            lift_structured, lower_to_cfg, split_block,
+        },
+        tail_call::{eliminate_tail_recursion, mark_tail_calls},
+    },
+    r#type::{TypeObj, TypePtr, Typed},
+    utils::apint::APInt,
+    value::Value,
+};
+use regex::Regex;
+
+use crate::common::{ConstantOp, ReturnOp, setup_context_dialects};
+
+static ATTR_KEY_CALLEE: LazyLock<Identifier> = LazyLock::new(|| "test_callee".try_into().unwrap());
+
+#[def_op("test.call")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct CallOp {}
+impl_verify_succ!(CallOp);
+
+impl Printable for CallOp {
+    fn fmt(
+        &self,
+        ctx: &Context,
+        state: &printable::State,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{} = {} ", results(self).disp(ctx), self.opid())?;
+        call_like(self, std::slice::from_ref(&ATTR_KEY_CALLEE)).fmt(ctx, state, f)
+    }
+}
+
+impl Parsable for CallOp {
+    type Arg = Vec<(Identifier, Location)>;
+    type Parsed = OpObj;
+    fn parse<'a>(
+        state_stream: &mut parsable::StateStream<'a>,
+        results: Self::Arg,
+    ) -> parsable::ParseResult<'a, Self::Parsed> {
+        call_like_parse(state_stream, results, |ctx, callee, callee_ty, args| {
+            let CallOpCallable::Direct(callee) = callee else {
+                return input_err_noloc!("test.call must call a symbol");
+            };
+            Ok(OpObj::new(CallOp::new(ctx, callee, callee_ty, args)))
+        })
+    }
+}
+
+impl CallOp {
+    fn new(
+        ctx: &mut Context,
+        callee: Identifier,
+        callee_ty: TypePtr<FunctionType>,
+        args: Vec<Value>,
+    ) -> CallOp {
+        let res_ty = callee_ty.deref(ctx).results()[0];
+        let op = Operation::new(ctx, Self::opid_static(), vec![res_ty], args, vec![], 0);
+        let mut op_ref = op.deref_mut(ctx);
+        op_ref
+            .attributes
+            .set(*ATTR_KEY_CALLEE, IdentifierAttr::new(callee));
+        op_ref
+            .attributes
+            .set(*ATTR_KEY_CALLEE_TYPE, TypeAttr::new(callee_ty.into()));
+        drop(op_ref);
+        CallOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl CallOpInterface for CallOp {
+    fn callee(&self, ctx: &Context) -> CallOpCallable {
+        let op = self.op.deref(ctx);
+        let callee = op
+            .attributes
+            .get::<IdentifierAttr>(&ATTR_KEY_CALLEE)
+            .unwrap();
+        CallOpCallable::Direct(callee.clone().into())
+    }
+
+    fn args(&self, ctx: &Context) -> Vec<Value> {
+        self.op.deref(ctx).operands().collect()
+    }
+}
+
+#[op_interface_impl]
+impl SymbolUserOpInterface for CallOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        let CallOpCallable::Direct(callee) = self.callee(ctx) else {
+            unreachable!()
+        };
+        vec![callee]
+    }
+
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if self.used_symbols(ctx).contains(from) {
+            self.op
+                .deref_mut(ctx)
+                .attributes
+                .set(*ATTR_KEY_CALLEE, IdentifierAttr::new(*to));
+        }
+    }
+}
+
+static ATTR_KEY_ARRAY_VALUE: LazyLock<Identifier> =
+    LazyLock::new(|| "test_array_value".try_into().unwrap());
+static ATTR_KEY_GLOBAL_NAME: LazyLock<Identifier> =
+    LazyLock::new(|| "test_global_name".try_into().unwrap());
+
+/// A constant array of 64-bit integers.
+#[def_op("test.array_constant")]
+#[derive_op_interface_impl(ZeroOpdInterface, OneResultInterface)]
+struct ArrayConstantOp {}
+impl_canonical_syntax!(ArrayConstantOp);
+impl_verify_succ!(ArrayConstantOp);
+
+impl ArrayConstantOp {
+    fn new(ctx: &mut Context, values: &[u64]) -> ArrayConstantOp {
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed);
+        let elems = values
+            .iter()
+            .map(|v| IntegerAttr::new(i64_ty, APInt::from_u64(*v, bw(64))).into())
+            .collect();
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![i64_ty.into()],
+            vec![],
+            vec![],
+            0,
+        );
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_ARRAY_VALUE, VecAttr::new(elems));
+        ArrayConstantOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl ConstantLikeInterface for ArrayConstantOp {
+    fn constant_value(&self, ctx: &Context) -> AttrObj {
+        self.op.deref(ctx).attributes.0[&*ATTR_KEY_ARRAY_VALUE].clone()
+    }
+}
+
+#[op_interface_impl]
+impl HoistableConstantInterface for ArrayConstantOp {
+    fn constant_size(&self, ctx: &Context) -> usize {
+        let op = self.op.deref(ctx);
+        op.attributes
+            .get::<VecAttr>(&ATTR_KEY_ARRAY_VALUE)
+            .unwrap()
+            .0
+            .len()
+            * 8
+    }
+
+    fn build_global(&self, ctx: &mut Context, name: &Identifier) -> Ptr<Operation> {
+        let value = self.constant_value(ctx);
+        let global = Operation::new(ctx, GlobalOp::opid_static(), vec![], vec![], vec![], 0);
+        global
+            .deref_mut(ctx)
+            .attributes
+            .0
+            .insert(*ATTR_KEY_ARRAY_VALUE, value);
+        GlobalOp { op: global }.set_symbol_name(ctx, name);
+        global
+    }
+
+    fn build_global_load(
+        &self,
+        ctx: &mut Context,
+        name: &Identifier,
+    ) -> (Vec<Ptr<Operation>>, Value) {
+        let ty = self.result_type(ctx);
+        let addr = Operation::new(ctx, AddressOfOp::opid_static(), vec![ty], vec![], vec![], 0);
+        addr.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_GLOBAL_NAME, IdentifierAttr::new(*name));
+        let addr_val = addr.deref(ctx).result(0);
+        let load = Operation::new(
+            ctx,
+            LoadOp::opid_static(),
+            vec![ty],
+            vec![addr_val],
+            vec![],
+            0,
+        );
+        let loaded = load.deref(ctx).result(0);
+        (vec![addr, load], loaded)
+    }
+}
+
+/// A global holding a constant.
+#[def_op("test.global")]
+#[derive_op_interface_impl(SymbolOpInterface)]
+struct GlobalOp {}
+impl_canonical_syntax!(GlobalOp);
+impl_verify_succ!(GlobalOp);
+
+/// The address of a global.
+#[def_op("test.address_of")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct AddressOfOp {}
+impl_canonical_syntax!(AddressOfOp);
+impl_verify_succ!(AddressOfOp);
+
+#[op_interface_impl]
+impl SymbolUserOpInterface for AddressOfOp {
+    fn used_symbols(&self, ctx: &Context) -> Vec<Identifier> {
+        let op = self.op.deref(ctx);
+        let global = op.attributes.get::<IdentifierAttr>(&ATTR_KEY_GLOBAL_NAME);
+        vec![global.unwrap().clone().into()]
+    }
+
+    fn replace_symbol_uses(&self, ctx: &mut Context, from: &Identifier, to: &Identifier) {
+        if self.used_symbols(ctx).contains(from) {
+            self.op
+                .deref_mut(ctx)
+                .attributes
+                .set(*ATTR_KEY_GLOBAL_NAME, IdentifierAttr::new(*to));
+        }
+    }
+}
+
+/// Load a value from an address.
+#[def_op("test.load")]
+#[derive_op_interface_impl(OneResultInterface, OneOpdInterface)]
+struct LoadOp {}
+impl_canonical_syntax!(LoadOp);
+impl_verify_succ!(LoadOp);
+
+#[op_interface_impl]
+impl MemoryEffectOpInterface for LoadOp {
+    fn memory_effects(&self, _ctx: &Context) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::new(
+            MemoryEffectKind::Read,
+            MemoryEffectOn::Operand(0),
+        )]
+    }
+}
+
+/// An unconditional branch, forwarding all its operands. Not named `test.br`,
+/// which is the `test` dialect's (compiled in with the `test-dialect` feature).
+#[def_op("test.jump")]
+#[derive_op_interface_impl(IsTerminatorInterface)]
+struct BrOp {}
+impl_canonical_syntax!(BrOp);
+impl_verify_succ!(BrOp);
+
+impl BrOp {
+    fn new(ctx: &mut Context, dest: Ptr<BasicBlock>, args: Vec<Value>) -> BrOp {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], args, vec![dest], 0);
+        BrOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl BranchOpInterface for BrOp {
+    fn successor_operands(&self, ctx: &Context, _succ_idx: usize) -> Vec<Value> {
+        self.op.deref(ctx).operands().collect()
+    }
+}
+
+/// A counted loop, over 64-bit integers.
+#[def_op("test.for")]
+#[derive_op_interface_impl(OneRegionInterface, SingleBlockRegionInterface)]
+struct ForOp {}
+impl_canonical_syntax!(ForOp);
+impl_verify_succ!(ForOp);
+
+impl ForOp {
+    /// Create a loop with an empty body.
+    fn new(ctx: &mut Context, lb: Value, ub: Value, step: Value, inits: Vec<Value>) -> ForOp {
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+        let result_types = vec![i64_ty; inits.len()];
+        let mut operands = vec![lb, ub, step];
+        operands.extend(inits);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            result_types.clone(),
+            operands,
+            vec![],
+            1,
+        );
+        let mut arg_types = vec![i64_ty];
+        arg_types.extend(result_types);
+        let body = BasicBlock::new(ctx, None, arg_types);
+        body.insert_at_front(op.deref(ctx).region(0), ctx);
+        ForOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl ForLoopInterface for ForOp {
+    fn build_iv_value(
+        &self,
+        ctx: &mut Context,
+        base: Option<Value>,
+        offset: i64,
+    ) -> (Vec<Ptr<Operation>>, Value) {
+        let offset_op = ConstantOp::new(ctx, offset as u64);
+        let offset = offset_op.result(ctx);
+        match base {
+            None => (vec![offset_op.operation()], offset),
+            Some(base) => {
+                let add_op = AddOp::new(ctx, base, offset);
+                let sum = add_op.result(ctx);
+                (vec![offset_op.operation(), add_op.operation()], sum)
+            }
+        }
+    }
+}
+
+/// Yield the loop carried values from a loop body.
+#[def_op("test.yield")]
+#[derive_op_interface_impl(IsTerminatorInterface)]
+struct YieldOp {}
+impl_canonical_syntax!(YieldOp);
+impl_verify_succ!(YieldOp);
+
+impl YieldOp {
+    fn new(ctx: &mut Context, values: Vec<Value>) -> YieldOp {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], values, vec![], 0);
+        YieldOp { op }
+    }
+}
+
+/// Yield from a region, after storing to the address its operand holds,
+/// as a terminator with side effects (like a call that also branches) does.
+#[def_op("test.store_yield")]
+#[derive_op_interface_impl(IsTerminatorInterface, OneOpdInterface)]
+struct StoreYieldOp {}
+impl_canonical_syntax!(StoreYieldOp);
+impl_verify_succ!(StoreYieldOp);
+
+impl StoreYieldOp {
+    fn new(ctx: &mut Context, addr: Value) -> StoreYieldOp {
+        let op = Operation::new(ctx, Self::opid_static(), vec![], vec![addr], vec![], 0);
+        StoreYieldOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl MemoryEffectOpInterface for StoreYieldOp {
+    fn memory_effects(&self, _ctx: &Context) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::new(
+            MemoryEffectKind::Write,
+            MemoryEffectOn::Operand(0),
+        )]
+    }
+}
+
+/// Add two integers.
+#[def_op("test.add")]
+#[derive_op_interface_impl(OneResultInterface, PureInterface)]
+struct AddOp {}
+impl_canonical_syntax!(AddOp);
+impl_verify_succ!(AddOp);
+
+impl AddOp {
+    fn new(ctx: &mut Context, lhs: Value, rhs: Value) -> AddOp {
+        let ty = lhs.get_type(ctx);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![ty],
+            vec![lhs, rhs],
+            vec![],
+            0,
+        );
+        AddOp { op }
+    }
+}
+
+/// A conditional branch to the first successor if the condition
+/// (the first operand) is non-zero, and to the second otherwise.
+/// The remaining operands are forwarded to the successors.
+#[def_op("test.cond_jump")]
+#[derive_op_interface_impl(IsTerminatorInterface)]
+struct CondBrOp {}
+impl_canonical_syntax!(CondBrOp);
+impl_verify_succ!(CondBrOp);
+
+impl CondBrOp {
+    fn new(
+        ctx: &mut Context,
+        cond: Value,
+        true_dest: Ptr<BasicBlock>,
+        true_args: Vec<Value>,
+        false_dest: Ptr<BasicBlock>,
+        false_args: Vec<Value>,
+    ) -> CondBrOp {
+        let num_true_args = true_args.len();
+        let mut operands = vec![cond];
+        operands.extend(true_args);
+        operands.extend(false_args);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![],
+            operands,
+            vec![true_dest, false_dest],
+            0,
+        );
+        let i64_ty = IntegerType::get(ctx, 64, Signedness::Signless);
+        let num_true_args = IntegerAttr::new(i64_ty, APInt::from_u64(num_true_args as u64, bw(64)));
+        op.deref_mut(ctx)
+            .attributes
+            .set(*ATTR_KEY_NUM_TRUE_ARGS, num_true_args);
+        CondBrOp { op }
+    }
+}
+
+static ATTR_KEY_NUM_TRUE_ARGS: LazyLock<Identifier> =
+    LazyLock::new(|| "test_num_true_args".try_into().unwrap());
+
+#[op_interface_impl]
+impl BranchOpInterface for CondBrOp {
+    fn successor_operands(&self, ctx: &Context, succ_idx: usize) -> Vec<Value> {
+        let op = self.op.deref(ctx);
+        let num_true_args = op
+            .attributes
+            .get::<IntegerAttr>(&ATTR_KEY_NUM_TRUE_ARGS)
+            .map(|num| APInt::from(num.clone()).to_u64() as usize)
+            .unwrap();
+        let args = op.operands().skip(1);
+        if succ_idx == 0 {
+            args.take(num_true_args).collect()
+        } else {
+            args.skip(num_true_args).collect()
+        }
+    }
+}
+
+#[op_interface_impl]
+impl ConditionalBranchInterface for CondBrOp {
+    fn taken_successor(&self, _ctx: &Context, operands: &[Option<AttrObj>]) -> Option<usize> {
+        let cond = operands[0].as_ref()?.downcast_ref::<IntegerAttr>()?;
+        Some(usize::from(APInt::from(cond.clone()).is_zero()))
+    }
+
+    fn build_branch_to(&self, ctx: &mut Context, succ_idx: usize) -> Ptr<Operation> {
+        let dest = self.op.deref(ctx).successor(succ_idx);
+        let args = self.successor_operands(ctx, succ_idx);
+        BrOp::new(ctx, dest, args).operation()
+    }
+}
+
+/// If the condition (the only operand) is non-zero, execute the first
+/// region, otherwise the second. Both regions yield the results.
+#[def_op("test.if")]
+#[derive_op_interface_impl(PureInterface)]
+struct IfOp {}
+impl_canonical_syntax!(IfOp);
+impl_verify_succ!(IfOp);
+
+impl IfOp {
+    /// Create an `if` with empty single block regions.
+    fn new(ctx: &mut Context, cond: Value, result_types: Vec<Ptr<TypeObj>>) -> IfOp {
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            result_types,
+            vec![cond],
+            vec![],
+            2,
+        );
+        for region_idx in 0..2 {
+            let block = BasicBlock::new(ctx, None, vec![]);
+            block.insert_at_front(op.deref(ctx).region(region_idx), ctx);
+        }
+        IfOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl LowerToCfgInterface for IfOp {
+    fn lower_to_cfg(&self, ctx: &mut Context) -> Result<()> {
+        let block = self.op.deref(ctx).container().unwrap();
+        let next = self.op.deref(ctx).next().unwrap();
+        let cont = split_block(ctx, next);
+        let results: Vec<_> = self.op.deref(ctx).results().collect();
+        for result in results {
+            let ty = result.get_type(ctx);
+            let arg_idx = cont.deref_mut(ctx).add_argument(ty);
+            let arg = cont.deref(ctx).argument(arg_idx);
+            result.replace_some_uses_with(ctx, |_, _| true, &arg);
+        }
+
+        let mut entries = vec![];
+        for region_idx in 0..2 {
+            let region = self.op.deref(ctx).region(region_idx);
+            let blocks = inline_region_before(ctx, region, cont);
+            for block in &blocks {
+                let term = block.deref(ctx).tail().unwrap();
+                if Operation::op(term, ctx).is::<YieldOp>() {
+                    let yielded = term.deref(ctx).operands().collect();
+                    BrOp::new(ctx, cont, yielded)
+                        .operation()
+                        .insert_before(ctx, term);
+                    Operation::erase(term, ctx);
+                }
+            }
+            entries.push(blocks[0]);
+        }
+
+        let cond = self.op.deref(ctx).operand(0);
+        let cond_br = CondBrOp::new(ctx, cond, entries[0], vec![], entries[1], vec![]);
+        cond_br.operation().insert_at_back(block, ctx);
+        Operation::erase(self.op, ctx);
+        Ok(())
+    }
+}
+
+/// While the first region (given the loop carried values) computes a non-zero
+/// condition, execute the second region (given the values passed by the condition).
+#[def_op("test.while")]
+struct WhileOp {}
+impl_canonical_syntax!(WhileOp);
+impl_verify_succ!(WhileOp);
+
+/// Terminates the condition region of a [WhileOp]: the first operand is the condition,
+/// and the rest are passed to the body (or are the results of the loop).
+#[def_op("test.condition")]
+#[derive_op_interface_impl(IsTerminatorInterface)]
+struct ConditionOp {}
+impl_canonical_syntax!(ConditionOp);
+impl_verify_succ!(ConditionOp);
+
+/// Builds the structured ops of this test dialect.
+struct TestStructuredOps;
+impl StructuredOps for TestStructuredOps {
+    fn create_if(
+        ctx: &mut Context,
+        cond: Value,
+        result_types: Vec<Ptr<TypeObj>>,
+    ) -> Ptr<Operation> {
+        Operation::new(
+            ctx,
+            IfOp::opid_static(),
+            result_types,
+            vec![cond],
+            vec![],
+            2,
+        )
+    }
+
+    fn create_while(ctx: &mut Context, inits: Vec<Value>) -> Ptr<Operation> {
+        let result_types = inits.iter().map(|init| init.get_type(ctx)).collect();
+        Operation::new(ctx, WhileOp::opid_static(), result_types, inits, vec![], 2)
+    }
+
+    fn create_for(
+        ctx: &mut Context,
+        lb: Value,
+        ub: Value,
+        step: Value,
+        inits: Vec<Value>,
+    ) -> Ptr<Operation> {
+        let result_types = inits.iter().map(|init| init.get_type(ctx)).collect();
+        let mut operands = vec![lb, ub, step];
+        operands.extend(inits);
+        Operation::new(ctx, ForOp::opid_static(), result_types, operands, vec![], 1)
+    }
+
+    fn create_yield(ctx: &mut Context, values: Vec<Value>) -> Ptr<Operation> {
+        YieldOp::new(ctx, values).operation()
+    }
+
+    fn create_condition(ctx: &mut Context, cond: Value, values: Vec<Value>) -> Ptr<Operation> {
+        let mut operands = vec![cond];
+        operands.extend(values);
+        Operation::new(ctx, ConditionOp::opid_static(), vec![], operands, vec![], 0)
+    }
+}
+
+/// Add to `module` a function `name` that calls `callee(0, 1, 2)`.
+fn add_caller(ctx: &mut Context, module: ModuleOp, name: &str, callee: FuncOp) -> CallOp {
+    let callee_ty = TypePtr::<FunctionType>::downcast(ctx, callee.get_type(ctx)).unwrap();
+    let ret_ty = callee_ty.deref(ctx).results()[0];
+    let caller_ty = FunctionType::get(ctx, vec![], vec![ret_ty]);
+    let caller = FuncOp::new(ctx, &name.try_into().unwrap(), caller_ty);
+    module.append_operation(ctx, caller.operation(), 0);
+    let entry = caller.get_entry_block(ctx);
+    let args: Vec<_> = (0..3)
+        .map(|i| {
+            let c = ConstantOp::new(ctx, i);
+            c.operation().insert_at_back(entry, ctx);
+            c.result(ctx)
+        })
+        .collect();
+    let call = CallOp::new(ctx, callee.symbol_name(ctx), callee_ty, args);
+    call.operation().insert_at_back(entry, ctx);
+    let ret = ReturnOp::new(ctx, call.result(ctx));
+    ret.operation().insert_at_back(entry, ctx);
+    call
+}
+
+/// Build a module with a function `callee(a, b, c)` that returns `b`,
+/// and a function `caller` that calls `callee(0, 1, 2)`.
+fn callee_caller_mod(ctx: &mut Context) -> Result<(ModuleOp, FuncOp, CallOp)> {
+    CallOp::register(ctx, CallOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+
+    let callee_ty = FunctionType::get(ctx, vec![i64_ty, i64_ty, i64_ty], vec![i64_ty]);
+    let callee = FuncOp::new(ctx, &"callee".try_into().unwrap(), callee_ty);
+    module.append_operation(ctx, callee.operation(), 0);
+    let callee_entry = callee.get_entry_block(ctx);
+    let arg1 = callee_entry.deref(ctx).argument(1);
+    let ret = ReturnOp::new(ctx, arg1);
+    ret.operation().insert_at_back(callee_entry, ctx);
+
+    let call = add_caller(ctx, module, "caller", callee);
+
+    module.operation().verify(ctx)?;
+    Ok((module, callee, call))
+}
+
+#[test]
+fn signature_drop_and_append_args() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, call) = callee_caller_mod(ctx)?;
+
+    let dropped = drop_unused_args(ctx, callee)?;
+    assert_eq!(dropped, vec![0, 2]);
+    module.operation().verify(ctx)?;
+
+    // Append an argument, passing a new constant at each call site.
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    append_args(ctx, callee, &[i64_ty], |ctx, call, _| {
+        let c = ConstantOp::new(ctx, 42);
+        c.operation().insert_before(ctx, call);
+        c.result(ctx)
+    })?;
+    module.operation().verify(ctx)?;
+
+    // Swap the two arguments.
+    rewrite_func_args(
+        ctx,
+        callee,
+        &[NewArg::Existing(1), NewArg::Existing(0)],
+        |_, _, _| unreachable!(),
+    )?;
+    module.operation().verify(ctx)?;
+    assert_eq!(call.args(ctx).len(), 2);
+
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @callee: builtin.function <(builtin.integer si64, builtin.integer si64)->(builtin.integer si64)> 
+            {
+              ^entry(block_4v3_arg0:builtin.integer si64,block_4v3_arg1:builtin.integer si64):
+                test.return block_4v3_arg1
+            };
+            builtin.func @caller: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_5v1_res0 = test.constant builtin.integer <0: si64>;
+                op_6v1_res0 = test.constant builtin.integer <1: si64>;
+                op_7v1_res0 = test.constant builtin.integer <2: si64>;
+                op_10v1_res0 = test.constant builtin.integer <42: si64>;
+                op_8v1_res0 = test.call @callee(op_10v1_res0, op_6v1_res0) : (builtin.integer si64, builtin.integer si64) -> builtin.integer si64;
+                test.return op_8v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+
+    Ok(())
+}
+
+#[test]
+fn signature_rewrite_errs() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, _) = callee_caller_mod(ctx)?;
+    let printed = module.disp(ctx).to_string();
+
+    // Dropping a used argument must fail.
+    let res = rewrite_func_args(
+        ctx,
+        callee,
+        &[NewArg::Existing(0)],
+        |_, _, _| unreachable!(),
+    );
+    assert!(matches!(
+        res,
+        Err(Error {
+            kind: ErrorKind::InvalidArgument,
+            ..
+        })
+    ));
+
+    // Out of bounds argument index.
+    let res = rewrite_func_args(
+        ctx,
+        callee,
+        &[NewArg::Existing(1), NewArg::Existing(3)],
+        |_, _, _| unreachable!(),
+    );
+    assert!(matches!(
+        res,
+        Err(Error {
+            kind: ErrorKind::InvalidArgument,
+            ..
+        })
+    ));
+
+    // The IR must remain unchanged on failures.
+    assert_eq!(module.disp(ctx).to_string(), printed);
+    assert_eq!(callee.get_entry_block(ctx).deref(ctx).iter(ctx).count(), 1);
+    Ok(())
+}
+
+// Calls from nested modules are updated too,
+// unless they call a function of the same name defined in the nested module.
+#[test]
+fn signature_nested_callers() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, call) = callee_caller_mod(ctx)?;
+
+    let inner = ModuleOp::new(ctx, &"inner".try_into().unwrap());
+    module.append_operation(ctx, inner.operation(), 0);
+    let nested_call = add_caller(ctx, inner, "nested_caller", callee);
+
+    let shadowing = ModuleOp::new(ctx, &"shadowing".try_into().unwrap());
+    module.append_operation(ctx, shadowing.operation(), 0);
+    let callee_ty = TypePtr::<FunctionType>::downcast(ctx, callee.get_type(ctx))?;
+    let shadowing_callee = FuncOp::new(ctx, &"callee".try_into().unwrap(), callee_ty);
+    shadowing.append_operation(ctx, shadowing_callee.operation(), 0);
+    let entry = shadowing_callee.get_entry_block(ctx);
+    let arg0 = entry.deref(ctx).argument(0);
+    ReturnOp::new(ctx, arg0)
+        .operation()
+        .insert_at_back(entry, ctx);
+    let shadowed_call = add_caller(ctx, shadowing, "shadowed_caller", shadowing_callee);
+    module.operation().verify(ctx)?;
+
+    assert_eq!(drop_unused_args(ctx, callee)?, vec![0, 2]);
+    module.operation().verify(ctx)?;
+    assert_eq!(call.args(ctx).len(), 1);
+    assert_eq!(nested_call.args(ctx).len(), 1);
+    assert_eq!(shadowed_call.args(ctx).len(), 3);
+    Ok(())
+}
+
+#[test]
+fn ipsccp_args_and_returns() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, _) = callee_caller_mod(ctx)?;
+
+    // `callee` is public, and may be called from elsewhere with other arguments.
+    assert!(!ipsccp(ctx, &module));
+
+    // `callee` is only called with `b = 1`, and so always returns 1.
+    callee.set_private(ctx, true);
+    assert!(ipsccp(ctx, &module));
+    module.operation().verify(ctx)?;
+    assert!(!ipsccp(ctx, &module));
+
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @callee: builtin.function <(builtin.integer si64, builtin.integer si64, builtin.integer si64)->(builtin.integer si64)> [(builtin_sym_private: builtin.unit )] 
+            {
+              ^entry(block_2v1_arg0:builtin.integer si64,block_2v1_arg1:builtin.integer si64,block_2v1_arg2:builtin.integer si64):
+                op_11v1_res0 = test.constant builtin.integer <1: si64>;
+                test.return op_11v1_res0
+            };
+            builtin.func @caller: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_5v1_res0 = test.constant builtin.integer <0: si64>;
+                op_6v1_res0 = test.constant builtin.integer <1: si64>;
+                op_7v1_res0 = test.constant builtin.integer <2: si64>;
+                op_10v1_res0 = test.constant builtin.integer <1: si64>;
+                op_8v1_res0 = test.call @callee(op_5v1_res0, op_6v1_res0, op_7v1_res0) : (builtin.integer si64, builtin.integer si64, builtin.integer si64) -> builtin.integer si64;
+                test.return op_10v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+
+    Ok(())
+}
+
+#[test]
+fn ipsccp_branches() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CallOp::register(ctx, CallOp::parser_fn);
+    BrOp::register(ctx, BrOp::parser_fn);
+    CondBrOp::register(ctx, CondBrOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+
+    // callee(a, b, c) { if b { return a } else { return c } }
+    let callee_ty = FunctionType::get(ctx, vec![i64_ty, i64_ty, i64_ty], vec![i64_ty]);
+    let callee = FuncOp::new(ctx, &"callee".try_into().unwrap(), callee_ty);
+    callee.set_private(ctx, true);
+    module.append_operation(ctx, callee.operation(), 0);
+    let region = callee.region(ctx);
+    let entry = callee.get_entry_block(ctx);
+    let args: Vec<_> = entry.deref(ctx).arguments().collect();
+    let (then_block, else_block) = (
+        BasicBlock::new(ctx, None, vec![]),
+        BasicBlock::new(ctx, None, vec![]),
+    );
+    then_block.insert_at_back(region, ctx);
+    else_block.insert_at_back(region, ctx);
+    let cond_br = CondBrOp::new(ctx, args[1], then_block, vec![], else_block, vec![]);
+    cond_br.operation().insert_at_back(entry, ctx);
+    ReturnOp::new(ctx, args[0])
+        .operation()
+        .insert_at_back(then_block, ctx);
+    ReturnOp::new(ctx, args[2])
+        .operation()
+        .insert_at_back(else_block, ctx);
+
+    // Called with `b = 1`, only the `then` branch is taken.
+    add_caller(ctx, module, "caller", callee);
+    module.operation().verify(ctx)?;
+    assert!(ipsccp(ctx, &module));
+    module.operation().verify(ctx)?;
+    assert!(!ipsccp(ctx, &module));
+
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @callee: builtin.function <(builtin.integer si64, builtin.integer si64, builtin.integer si64)->(builtin.integer si64)> [(builtin_sym_private: builtin.unit )] 
+            {
+              ^entry(block_2v1_arg0:builtin.integer si64,block_2v1_arg1:builtin.integer si64,block_2v1_arg2:builtin.integer si64):
+                op_5v3_res0 = test.constant builtin.integer <0: si64>;
+                test.jump () [^bb1] []: <() -> ()>
+              ^bb1():
+                test.return op_5v3_res0
+            };
+            builtin.func @caller: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_7v1_res0 = test.constant builtin.integer <0: si64>;
+                op_8v1_res0 = test.constant builtin.integer <1: si64>;
+                op_9v1_res0 = test.constant builtin.integer <2: si64>;
+                op_12v1_res0 = test.constant builtin.integer <0: si64>;
+                op_10v1_res0 = test.call @callee(op_7v1_res0, op_8v1_res0, op_9v1_res0) : (builtin.integer si64, builtin.integer si64, builtin.integer si64) -> builtin.integer si64;
+                test.return op_12v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+
+    Ok(())
+}
+
+#[test]
+fn promote_large_constants() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    ArrayConstantOp::register(ctx, ArrayConstantOp::parser_fn);
+    GlobalOp::register(ctx, GlobalOp::parser_fn);
+    AddressOfOp::register(ctx, AddressOfOp::parser_fn);
+    LoadOp::register(ctx, LoadOp::parser_fn);
+
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    for (func_name, array) in [("f", &[1, 2, 3][..]), ("g", &[4]), ("h", &[1, 2, 3])] {
+        let func_ty = FunctionType::get(ctx, vec![], vec![i64_ty]);
+        let func = FuncOp::new(ctx, &func_name.try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+        let entry = func.get_entry_block(ctx);
+        let array_op = ArrayConstantOp::new(ctx, array);
+        array_op.operation().insert_at_back(entry, ctx);
+        let ret = ReturnOp::new(ctx, array_op.result(ctx));
+        ret.operation().insert_at_back(entry, ctx);
+    }
+
+    // The small (8 byte) array stays, the equal large arrays share a global.
+    assert_eq!(promote_constants(ctx, &module, 16), 2);
+    module.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            test.global () [] [(builtin_sym_name: builtin.identifier (promoted_const_0)), (test_array_value: builtin.vec [builtin.integer <1: si64>, builtin.integer <2: si64>, builtin.integer <3: si64>])]: <() -> ()>;
+            builtin.func @f: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_12v1_res0 = test.address_of () [] [(test_global_name: builtin.identifier (promoted_const_0))]: <() -> (builtin.integer si64)>;
+                op_13v1_res0 = test.load (op_12v1_res0) [] []: <(builtin.integer si64) -> (builtin.integer si64)>;
+                test.return op_13v1_res0
+            };
+            builtin.func @g: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_6v1_res0 = test.array_constant () [] [(test_array_value: builtin.vec [builtin.integer <4: si64>])]: <() -> (builtin.integer si64)>;
+                test.return op_6v1_res0
+            };
+            builtin.func @h: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry():
+                op_3v3_res0 = test.address_of () [] [(test_global_name: builtin.identifier (promoted_const_0))]: <() -> (builtin.integer si64)>;
+                op_14v1_res0 = test.load (op_3v3_res0) [] []: <(builtin.integer si64) -> (builtin.integer si64)>;
+                test.return op_14v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+    Ok(())
+}
+
+#[test]
+fn tail_calls() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CallOp::register(ctx, CallOp::parser_fn);
+    BrOp::register(ctx, BrOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let mut funcs = vec![];
+    for func_name in ["rec", "via_branch", "not_tail"] {
+        let func = FuncOp::new(ctx, &func_name.try_into().unwrap(), func_ty);
+        module.append_operation(ctx, func.operation(), 0);
+        funcs.push(func);
+    }
+    let [rec, via_branch, not_tail] = funcs[..] else {
+        unreachable!()
+    };
+
+    // rec(a) { return rec(a); }
+    let entry = rec.get_entry_block(ctx);
+    let arg = entry.deref(ctx).argument(0);
+    let rec_call = CallOp::new(ctx, "rec".try_into().unwrap(), func_ty, vec![arg]);
+    rec_call.operation().insert_at_back(entry, ctx);
+    let ret = ReturnOp::new(ctx, rec_call.result(ctx));
+    ret.operation().insert_at_back(entry, ctx);
+
+    // via_branch(a) { c = rec(a); br ^exit(c); ^exit(x): return x; }
+    let entry = via_branch.get_entry_block(ctx);
+    let arg = entry.deref(ctx).argument(0);
+    let exit = BasicBlock::new(ctx, Some("exit".try_into().unwrap()), vec![i64_ty]);
+    exit.insert_after(ctx, entry);
+    let exit_arg = exit.deref(ctx).argument(0);
+    let ret = ReturnOp::new(ctx, exit_arg);
+    ret.operation().insert_at_back(exit, ctx);
+    let branch_call = CallOp::new(ctx, "rec".try_into().unwrap(), func_ty, vec![arg]);
+    branch_call.operation().insert_at_back(entry, ctx);
+    let br = BrOp::new(ctx, exit, vec![branch_call.result(ctx)]);
+    br.operation().insert_at_back(entry, ctx);
+
+    // not_tail(a) { c = not_tail(a); return a; }
+    let entry = not_tail.get_entry_block(ctx);
+    let arg = entry.deref(ctx).argument(0);
+    let not_tail_call = CallOp::new(ctx, "not_tail".try_into().unwrap(), func_ty, vec![arg]);
+    not_tail_call.operation().insert_at_back(entry, ctx);
+    let ret = ReturnOp::new(ctx, arg);
+    ret.operation().insert_at_back(entry, ctx);
+
+    assert_eq!(mark_tail_calls(ctx, rec), 1);
+    assert_eq!(mark_tail_calls(ctx, via_branch), 1);
+    assert_eq!(mark_tail_calls(ctx, not_tail), 0);
+    assert!(rec_call.is_tail_call(ctx));
+    assert!(branch_call.is_tail_call(ctx));
+    assert!(!not_tail_call.is_tail_call(ctx));
+
+    let build_branch = |ctx: &mut Context, dest, args| BrOp::new(ctx, dest, args).operation();
+    assert_eq!(eliminate_tail_recursion(ctx, via_branch, &build_branch), 0);
+    assert_eq!(eliminate_tail_recursion(ctx, not_tail, &build_branch), 0);
+    assert_eq!(eliminate_tail_recursion(ctx, rec, &build_branch), 1);
+    module.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.func @rec: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_6v1_arg0:builtin.integer si64):
+            test.jump (block_6v1_arg0) [^tail_loop] []: <(builtin.integer si64) -> ()>
+          ^tail_loop(block_2v1_arg0:builtin.integer si64):
+            test.jump (block_2v1_arg0) [^tail_loop] []: <(builtin.integer si64) -> ()>
+        }"#]]
+    .assert_eq(&rec.disp(ctx).to_string());
+    Ok(())
+}
+
+#[test]
+fn outline() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CallOp::register(ctx, CallOp::parser_fn);
+    AddOp::register(ctx, AddOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    module.append_operation(ctx, func.operation(), 0);
+
+    // main(a) { c = 1; s = a + c; t = s + s; return t; }
+    let entry = func.get_entry_block(ctx);
+    let arg = entry.deref(ctx).argument(0);
+    let c = ConstantOp::new(ctx, 1);
+    c.operation().insert_at_back(entry, ctx);
+    let s = AddOp::new(ctx, arg, c.result(ctx));
+    s.operation().insert_at_back(entry, ctx);
+    let t = AddOp::new(ctx, s.result(ctx), s.result(ctx));
+    t.operation().insert_at_back(entry, ctx);
+    let ret = ReturnOp::new(ctx, t.result(ctx));
+    ret.operation().insert_at_back(entry, ctx);
+
+    let build_call = |ctx: &mut Context, callee: FuncOp, args| {
+        let callee_ty = TypePtr::from_ptr(callee.get_type(ctx), ctx).unwrap();
+        CallOp::new(ctx, callee.symbol_name(ctx), callee_ty, args).operation()
+    };
+    let build_return = |ctx: &mut Context, values: Vec<Value>| {
+        assert_eq!(values.len(), 1);
+        ReturnOp::new(ctx, values[0]).operation()
+    };
+    let name: Identifier = "kernel".try_into().unwrap();
+    let ops = [c.operation(), s.operation()];
+    let (kernel, call) = outline_ops(ctx, &ops, &name, &build_call, &build_return)?;
+    module.operation().verify(ctx)?;
+    assert!(module.lookup(ctx, &name) == Some(kernel.operation()));
+    assert!(t.operation().deref(ctx).operand(0) == call.deref(ctx).result(0));
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^bb0():
+            builtin.func @main: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+            {
+              ^entry(block_2v1_arg0:builtin.integer si64):
+                op_8v1_res0 = test.call @kernel(block_2v1_arg0) : (builtin.integer si64) -> builtin.integer si64;
+                op_5v1_res0 = test.add (op_8v1_res0, op_8v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                test.return op_5v1_res0
+            };
+            builtin.func @kernel: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+            {
+              ^entry(block_3v1_arg0:builtin.integer si64):
+                op_3v1_res0 = test.constant builtin.integer <1: si64>;
+                op_4v1_res0 = test.add (block_3v1_arg0, op_3v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                test.return op_4v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+
+    // Errors, which leave the IR unchanged.
+    let printed = module.disp(ctx).to_string();
+    let other: Identifier = "other".try_into().unwrap();
+    let fails_with = |res: Result<_>, expected: fn(&OutlineErr) -> bool| {
+        matches!(
+            res,
+            Err(Error { kind: ErrorKind::InvalidArgument, err, .. })
+                if err.downcast_ref::<OutlineErr>().is_some_and(expected)
+        )
+    };
+    let res = outline_ops(ctx, &[c.operation()], &name, &build_call, &build_return);
+    assert!(fails_with(res, |err| matches!(
+        err,
+        OutlineErr::SymbolExists(_)
+    )));
+    let ops = [call, ret.operation()];
+    let res = outline_ops(ctx, &ops, &other, &build_call, &build_return);
+    assert!(fails_with(res, |err| matches!(
+        err,
+        OutlineErr::NotConsecutive
+    )));
+    let ops = [t.operation(), ret.operation()];
+    let res = outline_ops(ctx, &ops, &other, &build_call, &build_return);
+    assert!(fails_with(res, |err| matches!(err, OutlineErr::Terminator)));
+    let res = outline_ops(ctx, &[], &other, &build_call, &build_return);
+    assert!(fails_with(res, |err| matches!(err, OutlineErr::Empty)));
+    assert_eq!(module.disp(ctx).to_string(), printed);
+    Ok(())
+}
+
+#[test]
+fn instrument_ops() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    CallOp::register(ctx, CallOp::parser_fn);
+    AddOp::register(ctx, AddOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    module.append_operation(ctx, func.operation(), 0);
+
+    // main(a) { c = 1; s = a + c; t = s + s; return t; }
+    let entry = func.get_entry_block(ctx);
+    let arg = entry.deref(ctx).argument(0);
+    let c = ConstantOp::new(ctx, 1);
+    c.operation().insert_at_back(entry, ctx);
+    let s = AddOp::new(ctx, arg, c.result(ctx));
+    s.operation().insert_at_back(entry, ctx);
+    let t = AddOp::new(ctx, s.result(ctx), s.result(ctx));
+    t.operation().insert_at_back(entry, ctx);
+    let ret = ReturnOp::new(ctx, t.result(ctx));
+    ret.operation().insert_at_back(entry, ctx);
+
+    // `exit` is already declared, and is reused.
+    let hook_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let exit: Identifier = "exit".try_into().unwrap();
+    let exit_decl = FuncOp::new(ctx, &exit, hook_ty);
+    module.append_operation(ctx, exit_decl.operation(), 0);
+
+    let build_call = |ctx: &mut Context, callee: FuncOp, args| {
+        let callee_ty = TypePtr::from_ptr(callee.get_type(ctx), ctx).unwrap();
+        CallOp::new(ctx, callee.symbol_name(ctx), callee_ty, args).operation()
+    };
+    let enter_hook = || {
+        InstrumentHook::new("enter".try_into().unwrap(), hook_ty, HookPosition::Before)
+            .with_args(|ctx, op| vec![op.deref(ctx).operand(0)])
+    };
+    let exit_hook = || {
+        InstrumentHook::new(exit, hook_ty, HookPosition::After)
+            .with_args(|ctx, op| vec![op.deref(ctx).result(0)])
+    };
+    let mut pm = PassManager::new();
+    pm.add_pass(InstrumentPass::new(
+        is_op_kind(AddOp::opid_static()),
+        vec![enter_hook(), exit_hook()],
+        build_call,
+    ));
+    pm.run(ctx, module.operation())?;
+    module.operation().verify(ctx)?;
+    assert_eq!(pm.statistics()[0].1.counter("calls"), 4);
+    // `enter` is declared before `main`.
+    let enter = module.lookup(ctx, &"enter".try_into().unwrap()).unwrap();
+    assert!(enter.deref(ctx).next() == Some(func.operation()));
+    assert!(module.lookup(ctx, &exit) == Some(exit_decl.operation()));
+    expect![[r#"
+        builtin.func @main: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_2v1_arg0:builtin.integer si64):
+            op_3v1_res0 = test.constant builtin.integer <1: si64>;
+            op_9v1_res0 = test.call @enter(block_2v1_arg0) : (builtin.integer si64) -> builtin.integer si64;
+            op_4v1_res0 = test.add (block_2v1_arg0, op_3v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            op_10v1_res0 = test.call @exit(op_4v1_res0) : (builtin.integer si64) -> builtin.integer si64;
+            op_11v1_res0 = test.call @enter(op_4v1_res0) : (builtin.integer si64) -> builtin.integer si64;
+            op_5v1_res0 = test.add (op_4v1_res0, op_4v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            op_12v1_res0 = test.call @exit(op_5v1_res0) : (builtin.integer si64) -> builtin.integer si64;
+            test.return op_5v1_res0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+
+    // Terminators aren't instrumented after.
+    let ret_filter = is_op_kind(ReturnOp::opid_static());
+    let hooks = [enter_hook(), exit_hook()];
+    let num_calls = instrument(ctx, module.operation(), &ret_filter, &hooks, &build_call)?;
+    assert_eq!(num_calls, 1);
+    let before_ret = ret.operation().deref(ctx).prev().unwrap();
+    assert!(Operation::op(before_ret, ctx).is::<CallOp>());
+    assert!(before_ret.deref(ctx).operand(0) == t.result(ctx));
+
+    // A hook whose callee is declared with another type.
+    let other_ty = FunctionType::get(ctx, vec![], vec![i64_ty]);
+    let hooks = [InstrumentHook::new(exit, other_ty, HookPosition::Before)];
+    let res = instrument(ctx, module.operation(), &ret_filter, &hooks, &build_call);
+    assert!(matches!(
+        res,
+        Err(Error { kind: ErrorKind::InvalidArgument, err, .. })
+            if err.downcast_ref::<InstrumentErr>().is_some_and(|err| matches!(
+                err,
+                InstrumentErr::SymbolMismatch(_)
+            ))
+    ));
+    Ok(())
+}
+
+/// Build a function `sum(n)` returning the sum of `lb, lb + step, ...` (upto `n`),
+/// if `ub` is `None`, and upto `ub` otherwise, computed by a loop.
+fn sum_loop_func(ctx: &mut Context, lb: u64, ub: Option<u64>, step: u64) -> (FuncOp, ForOp) {
+    ForOp::register(ctx, ForOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    AddOp::register(ctx, AddOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"sum".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+
+    let bound = |ctx: &mut Context, value: Option<u64>| match value {
+        Some(value) => {
+            let const_op = ConstantOp::new(ctx, value);
+            const_op.operation().insert_at_back(entry, ctx);
+            const_op.result(ctx)
+        }
+        None => entry.deref(ctx).argument(0),
+    };
+    let lb = bound(ctx, Some(lb));
+    let ub = bound(ctx, ub);
+    let step = bound(ctx, Some(step));
+    let zero = bound(ctx, Some(0));
+    let for_op = ForOp::new(ctx, lb, ub, step, vec![zero]);
+    for_op.operation().insert_at_back(entry, ctx);
+    let sum = for_op.operation().deref(ctx).result(0);
+    let ret = ReturnOp::new(ctx, sum);
+    ret.operation().insert_at_back(entry, ctx);
+
+    let iv = for_op.induction_var(ctx);
+    let acc = for_op.iter_args(ctx)[0];
+    let add_op = AddOp::new(ctx, acc, iv);
+    for_op.append_operation(ctx, add_op.operation(), 0);
+    let yield_op = YieldOp::new(ctx, vec![add_op.result(ctx)]);
+    for_op.append_operation(ctx, yield_op.operation(), 0);
+    (func, for_op)
+}
+
+#[test]
+fn loop_unroll() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+
+    // Iterations 0, 3, 6, 9: The first three unrolled into the loop, the last into the epilogue.
+    let (func, for_op) = sum_loop_func(ctx, 0, Some(10), 3);
+    unroll_by_factor(ctx, for_op.operation(), 3)?;
+    func.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.func @sum: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_1v1_arg0:builtin.integer si64):
+            op_2v1_res0 = test.constant builtin.integer <0: si64>;
+            op_3v1_res0 = test.constant builtin.integer <10: si64>;
+            op_4v1_res0 = test.constant builtin.integer <3: si64>;
+            op_5v1_res0 = test.constant builtin.integer <0: si64>;
+            op_10v1_res0 = test.constant builtin.integer <9: si64>;
+            op_11v1_res0 = test.constant builtin.integer <9: si64>;
+            op_6v1_res0 = test.for (op_2v1_res0, op_10v1_res0, op_11v1_res0, op_5v1_res0) [] []: <(builtin.integer si64, builtin.integer si64, builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>
+            {
+              ^bb0(block_2v1_arg0:builtin.integer si64,block_2v1_arg1:builtin.integer si64):
+                op_8v1_res0 = test.add (block_2v1_arg1, block_2v1_arg0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                op_12v1_res0 = test.constant builtin.integer <3: si64>;
+                op_13v1_res0 = test.add (block_2v1_arg0, op_12v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                op_14v1_res0 = test.add (op_8v1_res0, op_13v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                op_15v1_res0 = test.constant builtin.integer <6: si64>;
+                op_16v1_res0 = test.add (block_2v1_arg0, op_15v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                op_17v1_res0 = test.add (op_14v1_res0, op_16v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                test.yield (op_17v1_res0) [] []: <(builtin.integer si64) -> ()>
+            };
+            op_18v1_res0 = test.constant builtin.integer <9: si64>;
+            op_19v1_res0 = test.add (op_6v1_res0, op_18v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            test.return op_19v1_res0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+
+    let (func, for_op) = sum_loop_func(ctx, 2, Some(5), 1);
+    unroll_full(ctx, for_op.operation())?;
+    func.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.func @sum: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_3v1_arg0:builtin.integer si64):
+            op_21v1_res0 = test.constant builtin.integer <2: si64>;
+            op_22v1_res0 = test.constant builtin.integer <5: si64>;
+            op_23v1_res0 = test.constant builtin.integer <1: si64>;
+            op_24v1_res0 = test.constant builtin.integer <0: si64>;
+            op_29v1_res0 = test.constant builtin.integer <2: si64>;
+            op_30v1_res0 = test.add (op_24v1_res0, op_29v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            op_31v1_res0 = test.constant builtin.integer <3: si64>;
+            op_32v1_res0 = test.add (op_30v1_res0, op_31v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            op_33v1_res0 = test.constant builtin.integer <4: si64>;
+            op_34v1_res0 = test.add (op_32v1_res0, op_33v1_res0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+            test.return op_34v1_res0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+
+    let (_, for_op) = sum_loop_func(ctx, 0, None, 1);
+    let res = unroll_full(ctx, for_op.operation());
+    assert!(matches!(
+        res,
+        Err(Error { kind: ErrorKind::InvalidArgument, err, .. })
+            if matches!(err.downcast_ref::<LoopUnrollErr>(), Some(LoopUnrollErr::NonConstantBounds))
+    ));
+    Ok(())
+}
+
+#[test]
+fn structured_builders() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    IfOp::register(ctx, IfOp::parser_fn);
+    ForOp::register(ctx, ForOp::parser_fn);
+    WhileOp::register(ctx, WhileOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    ConditionOp::register(ctx, ConditionOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"f".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let a = entry.deref(ctx).argument(0);
+
+    // f(a) {
+    //   r = if a { 1 } else { a };
+    //   s = for i in 0..10 (acc = r) { acc + i };
+    //   w = while (x = s) { cond(x, x) } do (y) { y };
+    //   return w;
+    // }
+    let mut builder = OpBuilder::new(InsertionPoint::AtBlockEnd(entry));
+    let r = builder.build_if::<TestStructuredOps>(
+        ctx,
+        a,
+        vec![i64_ty],
+        |ctx, builder| {
+            let one = builder.create(ctx, |ctx| ConstantOp::new(ctx, 1));
+            vec![one.result(ctx)]
+        },
+        |_ctx, _builder| vec![a],
+    );
+    let r = r.deref(ctx).result(0);
+    let [lb, ub, step] = [0, 10, 1].map(|value| {
+        let c = builder.create(ctx, |ctx| ConstantOp::new(ctx, value));
+        c.result(ctx)
+    });
+    let s =
+        builder.build_for::<TestStructuredOps>(ctx, lb, ub, step, vec![r], |ctx, builder, args| {
+            let sum = builder.create(ctx, |ctx| AddOp::new(ctx, args[1], args[0]));
+            vec![sum.result(ctx)]
+        });
+    let s = s.deref(ctx).result(0);
+    let w = builder.build_while::<TestStructuredOps>(
+        ctx,
+        vec![s],
+        |_ctx, _builder, args| (args[0], args.to_vec()),
+        |_ctx, _builder, args| args.to_vec(),
+    );
+    let w = w.deref(ctx).result(0);
+    builder.create(ctx, |ctx| ReturnOp::new(ctx, w));
+    func.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.func @f: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_1v1_arg0:builtin.integer si64):
+            op_2v1_res0 = test.if (block_1v1_arg0) [] []: <(builtin.integer si64) -> (builtin.integer si64)>
+            {
+              ^bb0():
+                op_3v1_res0 = test.constant builtin.integer <1: si64>;
+                test.yield (op_3v1_res0) [] []: <(builtin.integer si64) -> ()>
+            }
+            
+            {
+              ^bb0():
+                test.yield (block_1v1_arg0) [] []: <(builtin.integer si64) -> ()>
+            };
+            op_6v1_res0 = test.constant builtin.integer <0: si64>;
+            op_7v1_res0 = test.constant builtin.integer <10: si64>;
+            op_8v1_res0 = test.constant builtin.integer <1: si64>;
+            op_9v1_res0 = test.for (op_6v1_res0, op_7v1_res0, op_8v1_res0, op_2v1_res0) [] []: <(builtin.integer si64, builtin.integer si64, builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>
+            {
+              ^bb0(block_4v1_arg0:builtin.integer si64,block_4v1_arg1:builtin.integer si64):
+                op_10v1_res0 = test.add (block_4v1_arg1, block_4v1_arg0) [] []: <(builtin.integer si64, builtin.integer si64) -> (builtin.integer si64)>;
+                test.yield (op_10v1_res0) [] []: <(builtin.integer si64) -> ()>
+            };
+            op_12v1_res0 = test.while (op_9v1_res0) [] []: <(builtin.integer si64) -> (builtin.integer si64)>
+            {
+              ^bb0(block_5v1_arg0:builtin.integer si64):
+                test.condition (block_5v1_arg0, block_5v1_arg0) [] []: <(builtin.integer si64, builtin.integer si64) -> ()>
+            }
+            
+            {
+              ^bb0(block_6v1_arg0:builtin.integer si64):
+                test.yield (block_6v1_arg0) [] []: <(builtin.integer si64) -> ()>
+            };
+            test.return op_12v1_res0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+    Ok(())
+}
+
+#[test]
+fn structured_cfg_lower_and_lift() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    BrOp::register(ctx, BrOp::parser_fn);
+    CondBrOp::register(ctx, CondBrOp::parser_fn);
+    IfOp::register(ctx, IfOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+
+    // select(c) { return if c { 1 } else { 2 }; }
+    let func = FuncOp::new(ctx, &"select".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let cond = entry.deref(ctx).argument(0);
+    let if_op = IfOp::new(ctx, cond, vec![i64_ty]);
+    if_op.operation().insert_at_back(entry, ctx);
+    for (region_idx, value) in [(0, 1), (1, 2)] {
+        let block = if_op.operation().deref(ctx).region(region_idx);
+        let block = block.deref(ctx).head().unwrap();
+        let const_op = ConstantOp::new(ctx, value);
+        const_op.operation().insert_at_back(block, ctx);
+        let yield_op = YieldOp::new(ctx, vec![const_op.result(ctx)]);
+        yield_op.operation().insert_at_back(block, ctx);
+    }
+    let if_result = if_op.operation().deref(ctx).result(0);
+    let ret = ReturnOp::new(ctx, if_result);
+    ret.operation().insert_at_back(entry, ctx);
+
+    assert_eq!(lower_to_cfg(ctx, func.operation())?, 1);
+    func.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.func @select: builtin.function <(builtin.integer si64)->(builtin.integer si64)> 
+        {
+          ^entry(block_1v1_arg0:builtin.integer si64):
+            test.cond_jump (block_1v1_arg0) [^bb1, ^bb2] [(test_num_true_args: builtin.integer <0: i64>)]: <(builtin.integer si64) -> ()>
+          ^bb1():
+            op_3v1_res0 = test.constant builtin.integer <1: si64>;
+            test.jump (op_3v1_res0) [^bb3] []: <(builtin.integer si64) -> ()>
+          ^bb2():
+            op_5v1_res0 = test.constant builtin.integer <2: si64>;
+            test.jump (op_5v1_res0) [^bb3] []: <(builtin.integer si64) -> ()>
+          ^bb3(block_4v1_arg0:builtin.integer si64):
+            test.return block_4v1_arg0
+        }"#]]
+    .assert_eq(&func.disp(ctx).to_string());
+
+    let blocks: Vec<_> = func.region(ctx).deref(ctx).iter(ctx).collect();
+    let [entry, then, else_, cont] = blocks[..] else {
+        panic!("Expected four blocks after lowering");
+    };
+    assert!(
+        lift_structured(ctx, func.region(ctx))?
+            == vec![
+                Structured::If {
+                    cond: entry,
+                    then: vec![Structured::Block(then)],
+                    else_: vec![Structured::Block(else_)],
+                },
+                Structured::Block(cont),
+            ]
+    );
+
+    // loop(c) { while c { } return c; }
+    let func = FuncOp::new(ctx, &"loop".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let cond = entry.deref(ctx).argument(0);
+    let [header, body, exit] = ["header", "body", "exit"]
+        .map(|label| BasicBlock::new(ctx, Some(label.try_into().unwrap()), vec![]));
+    for block in [exit, body, header] {
+        block.insert_after(ctx, entry);
+    }
+    BrOp::new(ctx, header, vec![])
+        .operation()
+        .insert_at_back(entry, ctx);
+    CondBrOp::new(ctx, cond, body, vec![], exit, vec![])
+        .operation()
+        .insert_at_back(header, ctx);
+    BrOp::new(ctx, header, vec![])
+        .operation()
+        .insert_at_back(body, ctx);
+    ReturnOp::new(ctx, cond)
+        .operation()
+        .insert_at_back(exit, ctx);
+    func.operation().verify(ctx)?;
+    assert!(
+        lift_structured(ctx, func.region(ctx))?
+            == vec![
+                Structured::Block(entry),
+                Structured::While {
+                    header,
+                    body: vec![Structured::Block(body)],
+                },
+                Structured::Block(exit),
+            ]
+    );
+
+    // A loop with two entries: `a` and `b` branch to each other, and are both branched to from entry.
+    let func = FuncOp::new(ctx, &"irreducible".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let cond = entry.deref(ctx).argument(0);
+    let [a, b] =
+        ["a", "b"].map(|label| BasicBlock::new(ctx, Some(label.try_into().unwrap()), vec![]));
+    for block in [b, a] {
+        block.insert_after(ctx, entry);
+    }
+    CondBrOp::new(ctx, cond, a, vec![], b, vec![])
+        .operation()
+        .insert_at_back(entry, ctx);
+    BrOp::new(ctx, b, vec![]).operation().insert_at_back(a, ctx);
+    BrOp::new(ctx, a, vec![]).operation().insert_at_back(b, ctx);
+    let res = lift_structured(ctx, func.region(ctx));
+    assert!(matches!(
+        res,
+        Err(Error { kind: ErrorKind::InvalidArgument, err, .. })
+            if matches!(err.downcast_ref::<StructuredCfgErr>(), Some(StructuredCfgErr::Irreducible(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn strip_locations_and_debug_info() -> Result<()> {
+    let input = r#"
+        builtin.module @bar {
+        ^block_0_0():
+            builtin.func @foo: builtin.function <() -> (builtin.integer si64)> [(test_attr: builtin.unit )] {
+            ^entry_block_1_0():
+                c0 = test.constant builtin.integer <0: si64>;
+                test.return c0
+            }
+        }"#;
+
+    let ctx = &mut setup_context_dialects();
+    let state_stream = state_stream_from_iterator(
+        input.chars(),
+        parsable::State::new(ctx, location::Source::InMemory),
+    );
+    let module = spaced(Operation::parser(()))
+        .parse(state_stream)
+        .map_err(|err| input_error_noloc!(err))?
+        .0;
+
+    // The locations of the four operations and the two blocks.
+    let stats = strip_locations(ctx, module);
+    assert_eq!(stats.num_removed, 6);
+    assert!(stats.bytes_removed > 0);
+    assert!(module.deref(ctx).loc() == Location::Unknown);
+    assert_eq!(strip_locations(ctx, module), StripStats::default());
+
+    let stats = strip_debug_info(ctx, module);
+    assert_eq!(stats.num_removed, 1);
+    let stats = strip_attributes(ctx, module, &["test_attr".try_into().unwrap()]);
+    assert_eq!(
+        stats,
+        StripStats {
+            num_removed: 1,
+            bytes_removed: "builtin.unit ".len()
+        }
+    );
+    module.verify(ctx)?;
+
+    expect![[r#"
+        builtin.module @bar 
+        {
+          ^block_0_0():
+            builtin.func @foo: builtin.function <()->(builtin.integer si64)> 
+            {
+              ^entry_block_1_0():
+                op_3v1_res0 = test.constant builtin.integer <0: si64>;
+                test.return op_3v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+    Ok(())
+}
+
+/// A pass that records the symbols of the operations it is run on.
+struct RecordSymbolPass(Rc<RefCell<Vec<String>>>);
+
+impl Pass for RecordSymbolPass {
+    fn name(&self) -> &str {
+        "record-symbol"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        let op = Operation::op(op, ctx);
+        let sym_op = op_cast::<dyn SymbolOpInterface>(&*op).unwrap();
+        self.0
+            .borrow_mut()
+            .push(sym_op.symbol_name(ctx).to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn pass_manager_nesting_and_filters() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, _) = callee_caller_mod(ctx)?;
+    let visited = Rc::new(RefCell::new(vec![]));
+
+    let mut pm = PassManager::new_anchored::<ModuleOp>();
+    pm.add_pass(RecordSymbolPass(visited.clone()));
+    pm.nest::<FuncOp>()
+        .add_pass(RecordSymbolPass(visited.clone()));
+    pm.add_nested(
+        PassManager::new_anchored::<FuncOp>()
+            .with_filter(symbol_name_matches(Regex::new("^call").unwrap())),
+    )
+    .add_pass(RecordSymbolPass(visited.clone()));
+    pm.run(ctx, module.operation())?;
+    assert_eq!(
+        *visited.borrow(),
+        vec!["bar", "callee", "caller", "callee", "caller"]
+    );
+
+    // Running on an operation that isn't an anchor does nothing.
+    visited.borrow_mut().clear();
+    pm.run(ctx, callee.operation())?;
+    assert!(visited.borrow().is_empty());
+
+    let test_attr: Identifier = "test_attr".try_into().unwrap();
+    callee
+        .operation()
+        .deref_mut(ctx)
+        .attributes
+        .set(test_attr, UnitAttr::new());
+    let mut pm = PassManager::new();
+    pm.add_nested(PassManager::new().with_filter(has_attribute(test_attr)))
+        .add_pass(RecordSymbolPass(visited.clone()));
+    pm.run(ctx, module.operation())?;
+    assert_eq!(*visited.borrow(), vec!["callee"]);
+    Ok(())
+}
+
+/// A pass that sets a [UnitAttr] on the operations it is run on.
+struct SetAttrPass(Identifier);
+
+impl Pass for SetAttrPass {
+    fn name(&self) -> &str {
+        "set-attr"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        op.deref_mut(ctx).attributes.set(self.0, UnitAttr::new());
+        Ok(())
+    }
+}
+
+/// A pass that always fails.
+struct FailPass;
+
+impl Pass for FailPass {
+    fn name(&self) -> &str {
+        "fail"
+    }
+
+    fn run(&mut self, _ctx: &mut Context, _op: Ptr<Operation>) -> Result<()> {
+        input_err_noloc!("Failing pass")
+    }
+}
+
+#[test]
+fn record_ir_after_passes() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, _, _) = callee_caller_mod(ctx)?;
+    let test_attr: Identifier = "test_attr".try_into().unwrap();
+    let tmp_dir = tempfile::tempdir().unwrap();
+
+    let mut pm = PassManager::new_anchored::<ModuleOp>();
+    pm.add_instrumentation(IrRecorder::new(tmp_dir.path())?);
+    pm.nest::<FuncOp>().add_pass(SetAttrPass(test_attr));
+    pm.add_pass(FailPass);
+    assert!(pm.run(ctx, module.operation()).is_err());
+
+    let manifest = std::fs::read_to_string(tmp_dir.path().join(MANIFEST_FILE)).unwrap();
+    expect![[r#"
+        0		ok	000-input.pliron
+        1	set-attr	ok	001-set_attr.pliron
+        2	set-attr	ok	002-set_attr.pliron
+        3	fail	failed	003-fail.pliron
+    "#]]
+    .assert_eq(&manifest);
+
+    // Resume from after the first pass, and find the first recording with the attribute set.
+    let recording = Recording::load(tmp_dir.path())?;
+    assert_eq!(recording.recorded().len(), 4);
+    let resumed = recording.parse(ctx, 1)?;
+    let funcs_with_attr = |ctx: &Context, module: ModuleOp| {
+        module
+            .body(ctx, 0)
+            .deref(ctx)
+            .iter(ctx)
+            .filter(|op| op.deref(ctx).attributes.0.contains_key(&test_attr))
+            .count()
+    };
+    assert_eq!(funcs_with_attr(ctx, resumed), 1);
+    let first_bad = recording.bisect(ctx, |ctx, module| funcs_with_attr(ctx, module) == 0);
+    assert_eq!(first_bad.map(|recorded| recorded.seq), Some(1));
+    let first_bad = recording.bisect(ctx, |ctx, module| funcs_with_attr(ctx, module) < 2);
+    assert_eq!(
+        first_bad.and_then(|recorded| recorded.pass.as_deref()),
+        Some("set-attr")
+    );
+    assert!(recording.bisect(ctx, |_, _| true).is_none());
+    Ok(())
+}
+
+type PassFn = Box<dyn FnMut(&mut Context, Ptr<Operation>) -> Result<()>>;
+
+/// A pass running a closure.
+struct ClosurePass(PassFn);
+
+impl Pass for ClosurePass {
+    fn name(&self) -> &str {
+        "closure"
+    }
+
+    fn run(&mut self, ctx: &mut Context, op: Ptr<Operation>) -> Result<()> {
+        (self.0)(ctx, op)
+    }
+}
+
+/// An (unlinked) `test.constant` with two results, which is invalid.
+fn invalid_constant(ctx: &mut Context) -> Ptr<Operation> {
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    Operation::new(
+        ctx,
+        ConstantOp::opid_static(),
+        vec![i64_ty, i64_ty],
+        vec![],
+        vec![],
+        0,
+    )
+}
+
+#[test]
+fn verify_after_passes() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let (module, callee, call) = callee_caller_mod(ctx)?;
+    let caller = callee.operation().deref(ctx).next().unwrap();
+    let test_attr: Identifier = "test_attr".try_into().unwrap();
+
+    // Break the callee, outside of any pass.
+    let callee_ret = callee.get_entry_block(ctx).deref(ctx).tail().unwrap();
+    invalid_constant(ctx).insert_before(ctx, callee_ret);
+    assert!(module.operation().verify(ctx).is_err());
+
+    // A pass that modifies only the caller.
+    let set_caller_attr = || {
+        ClosurePass(Box::new(move |ctx, _| {
+            caller
+                .deref_mut(ctx)
+                .attributes
+                .set(test_attr, UnitAttr::new());
+            Ok(())
+        }))
+    };
+    let mut pm = PassManager::new_anchored::<ModuleOp>().with_verify_mode(VerifyMode::Full);
+    pm.add_pass(set_caller_attr());
+    assert!(pm.run(ctx, module.operation()).is_err());
+    let mut pm = PassManager::new_anchored::<ModuleOp>().with_verify_mode(VerifyMode::Changed);
+    pm.add_pass(set_caller_attr());
+    pm.run(ctx, module.operation())?;
+    // Nested pass managers inherit the verification mode.
+    pm.nest::<FuncOp>().add_pass(SetAttrPass(test_attr));
+    assert!(pm.run(ctx, module.operation()).is_err());
+
+    // A pass breaking the caller is caught.
+    let mut pm = PassManager::new_anchored::<ModuleOp>().with_verify_mode(VerifyMode::Changed);
+    pm.add_pass(ClosurePass(Box::new(move |ctx, _| {
+        let caller_entry = call.operation().deref(ctx).container().unwrap();
+        let caller_ret = caller_entry.deref(ctx).tail().unwrap();
+        invalid_constant(ctx).insert_before(ctx, caller_ret);
+        Ok(())
+    })));
+    let err = pm.run(ctx, module.operation()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::VerificationFailed));
+
+    // Only the call (fully), and its user and ancestors (shallow), are verified,
+    // so the invalid operations elsewhere in the caller go unnoticed.
+    let tracker = Rc::new(RefCell::new(ChangeTracker::default()));
+    let listener: Rc<RefCell<dyn RewriteListener>> = tracker.clone();
+    ctx.add_listener(listener.clone());
+    call.operation()
+        .deref_mut(ctx)
+        .attributes
+        .set(test_attr, UnitAttr::new());
+    ctx.remove_listener(&listener);
+    assert!(tracker.borrow().modified_ops(ctx) == vec![call.operation()]);
+    assert_eq!(tracker.borrow().verify_changed(ctx, module.operation())?, 4);
+    Ok(())
+}
+
+/// A 64-bit integer constant, of the type of the (single) result of `op`.
+fn int_element(ctx: &Context, op: &dyn OneResultInterface, value: u64) -> AttrObj {
+    let ty = TypePtr::from_ptr(op.result_type(ctx), ctx).unwrap();
+    IntegerAttr::new(ty, APInt::from_u64(value, bw(64))).into()
+}
+
+#[op_interface_impl]
+impl AlgebraicPropertiesInterface for AddOp {
+    fn identity_element(&self, ctx: &Context) -> Option<AttrObj> {
+        Some(int_element(ctx, self, 0))
+    }
+}
+
+/// Multiply two integers.
+#[def_op("test.mul")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct MulOp {}
+impl_canonical_syntax!(MulOp);
+impl_verify_succ!(MulOp);
+
+impl MulOp {
+    fn new(ctx: &mut Context, lhs: Value, rhs: Value) -> MulOp {
+        let ty = lhs.get_type(ctx);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![ty],
+            vec![lhs, rhs],
+            vec![],
+            0,
+        );
+        MulOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl OpEquivalence for MulOp {
+    fn is_commutative(&self) -> bool {
+        true
+    }
+}
+
+#[op_interface_impl]
+impl AlgebraicPropertiesInterface for MulOp {
+    fn identity_element(&self, ctx: &Context) -> Option<AttrObj> {
+        Some(int_element(ctx, self, 1))
+    }
+
+    fn absorbing_element(&self, ctx: &Context) -> Option<AttrObj> {
+        Some(int_element(ctx, self, 0))
+    }
+}
+
+/// Maximum of two integers.
+#[def_op("test.max")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct MaxOp {}
+impl_canonical_syntax!(MaxOp);
+impl_verify_succ!(MaxOp);
+
+impl MaxOp {
+    fn new(ctx: &mut Context, lhs: Value, rhs: Value) -> MaxOp {
+        let ty = lhs.get_type(ctx);
+        let op = Operation::new(
+            ctx,
+            Self::opid_static(),
+            vec![ty],
+            vec![lhs, rhs],
+            vec![],
+            0,
+        );
+        MaxOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl AlgebraicPropertiesInterface for MaxOp {
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+/// Negate an integer.
+#[def_op("test.neg")]
+#[derive_op_interface_impl(OneResultInterface)]
+struct NegOp {}
+impl_canonical_syntax!(NegOp);
+impl_verify_succ!(NegOp);
+
+impl NegOp {
+    fn new(ctx: &mut Context, opd: Value) -> NegOp {
+        let ty = opd.get_type(ctx);
+        let op = Operation::new(ctx, Self::opid_static(), vec![ty], vec![opd], vec![], 0);
+        NegOp { op }
+    }
+}
+
+#[op_interface_impl]
+impl AlgebraicPropertiesInterface for NegOp {
+    fn is_involution(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn algebraic_folds() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    MulOp::register(ctx, MulOp::parser_fn);
+    MaxOp::register(ctx, MaxOp::parser_fn);
+    NegOp::register(ctx, NegOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    module.append_operation(ctx, func.operation(), 0);
+
+    let entry = func.get_entry_block(ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+        op.insert_at_back(entry, ctx);
+        op.deref(ctx).result(0)
+    };
+    let c0 = ConstantOp::new(ctx, 0).operation();
+    let c0 = insert(ctx, c0);
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    let c1 = insert(ctx, c1);
+    // s = a + 0 = a
+    let s = AddOp::new(ctx, a, c0).operation();
+    let s = insert(ctx, s);
+    // 0 + a isn't folded, addition isn't declared commutative.
+    let s2 = AddOp::new(ctx, c0, a).operation();
+    let s2 = insert(ctx, s2);
+    // m = 1 * s = s
+    let m = MulOp::new(ctx, c1, s).operation();
+    let m = insert(ctx, m);
+    // z = m * 0 = 0
+    let z = MulOp::new(ctx, m, c0).operation();
+    let z = insert(ctx, z);
+    // n2 = -(-z) = z
+    let n1 = NegOp::new(ctx, z).operation();
+    let n1 = insert(ctx, n1);
+    let n2 = NegOp::new(ctx, n1).operation();
+    let n2 = insert(ctx, n2);
+    // x = max(n2, n2) = n2
+    let x = MaxOp::new(ctx, n2, n2).operation();
+    let x = insert(ctx, x);
+    let r = AddOp::new(ctx, x, s2).operation();
+    let r_res = insert(ctx, r);
+    ReturnOp::new(ctx, r_res)
+        .operation()
+        .insert_at_back(entry, ctx);
+
+    let mut pm = PassManager::new();
+    pm.nest::<FuncOp>().add_pass(AlgebraicFoldPass::default());
+    pm.run(ctx, module.operation())?;
+    module.operation().verify(ctx)?;
+    let statistics = pm.statistics();
+    assert_eq!(statistics.len(), 1);
+    assert_eq!(statistics[0].0, "algebraic-fold");
+    assert_eq!(statistics[0].1.to_string(), "folded: 5\n");
+    assert!(r.deref(ctx).operand(0) == c0);
+    assert!(r.deref(ctx).operand(1) == s2);
+    let Value::OpResult { op: n1, .. } = n1 else {
+        panic!("Expected an op result");
+    };
+    assert!(n1.deref(ctx).operand(0) == c0);
+
+    // Nothing more to fold.
+    pm.run(ctx, module.operation())?;
+    assert_eq!(pm.statistics()[0].1.counter("folded"), 5);
+    assert_eq!(fold_algebraic(ctx, module.operation()), 0);
+    Ok(())
+}
+
+/// The value of `value`, if it's defined by a [ConstantOp].
+fn constant_u64(ctx: &Context, value: Value) -> Option<u64> {
+    let Value::OpResult { op, .. } = value else {
+        return None;
+    };
+    let constant = Operation::op(op, ctx)
+        .downcast_ref::<ConstantOp>()?
+        .get_value(ctx);
+    let constant = constant.downcast_ref::<IntegerAttr>()?.clone();
+    Some(APInt::from(constant).to_u64())
+}
+
+/// Replace the sum of two constants with a constant.
+struct FoldConstantAdd;
+impl RewritePattern for FoldConstantAdd {
+    fn name(&self) -> &str {
+        "fold-constant-add"
+    }
+
+    fn root_opid(&self) -> Option<OpId> {
+        Some(AddOp::opid_static())
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        let (lhs, rhs) = (op.deref(ctx).operand(0), op.deref(ctx).operand(1));
+        let (Some(lhs), Some(rhs)) = (constant_u64(ctx, lhs), constant_u64(ctx, rhs)) else {
+            return Ok(false);
+        };
+        let sum = ConstantOp::new(ctx, lhs + rhs).operation();
+        rewriter.replace_op(ctx, op, sum);
+        Ok(true)
+    }
+}
+
+/// Erase unused constants.
+struct EraseDeadConstant;
+impl RewritePattern for EraseDeadConstant {
+    fn name(&self) -> &str {
+        "erase-dead-constant"
+    }
+
+    fn benefit(&self) -> u32 {
+        2
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        if !Operation::op(op, ctx).is::<ConstantOp>() || op.deref(ctx).result(0).is_used(ctx) {
+            return Ok(false);
+        }
+        rewriter.erase_op(ctx, op);
+        Ok(true)
+    }
+}
+
+/// Never applies, but records the order in which it's tried.
+struct NeverMatches(Rc<RefCell<Vec<&'static str>>>);
+impl RewritePattern for NeverMatches {
+    fn name(&self) -> &str {
+        "never-matches"
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        _rewriter: &mut PatternRewriter,
+        op: Ptr<Operation>,
+    ) -> Result<bool> {
+        if Operation::op(op, ctx).is::<AddOp>() {
+            self.0.borrow_mut().push("never-matches");
+        }
+        Ok(false)
+    }
+}
+
+/// main(a) = ((1 + 2) + 3) + a
+fn constant_adds_func(ctx: &mut Context) -> (FuncOp, Ptr<Operation>) {
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+        op.insert_at_back(entry, ctx);
+        op.deref(ctx).result(0)
+    };
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    let c1 = insert(ctx, c1);
+    let c2 = ConstantOp::new(ctx, 2).operation();
+    let c2 = insert(ctx, c2);
+    let c3 = ConstantOp::new(ctx, 3).operation();
+    let c3 = insert(ctx, c3);
+    let s1 = AddOp::new(ctx, c1, c2).operation();
+    let s1 = insert(ctx, s1);
+    let s2 = AddOp::new(ctx, s1, c3).operation();
+    let s2 = insert(ctx, s2);
+    let r = AddOp::new(ctx, s2, a).operation();
+    let r_res = insert(ctx, r);
+    ReturnOp::new(ctx, r_res)
+        .operation()
+        .insert_at_back(entry, ctx);
+    (func, r)
+}
+
+#[test]
+fn greedy_pattern_rewrites() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    let (func, r) = constant_adds_func(ctx);
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(FoldConstantAdd).add(EraseDeadConstant);
+    let mut pass = GreedyRewritePass::new("fold-constants", patterns);
+    pass.run(ctx, func.operation())?;
+    func.operation().verify(ctx)?;
+
+    // Only the constant 6, the addition of the argument and the return remain.
+    let entry = func.get_entry_block(ctx);
+    assert_eq!(entry.deref(ctx).iter(ctx).count(), 3);
+    assert_eq!(constant_u64(ctx, r.deref(ctx).operand(0)), Some(6));
+    let fold_stats = pass
+        .patterns_mut()
+        .pattern_statistics("fold-constant-add")
+        .unwrap();
+    assert_eq!(fold_stats.applied, 2);
+    let statistics = pass.statistics();
+    assert_eq!(statistics.counter("fold-constant-add.applied"), 2);
+    // The three initial constants, and the partial sum 3, were erased.
+    assert_eq!(statistics.counter("erase-dead-constant.applied"), 4);
+
+    // With folding disabled, the additions stay.
+    let (func, r) = constant_adds_func(ctx);
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(FoldConstantAdd).add(EraseDeadConstant);
+    assert!(patterns.set_enabled("fold-constant-add", false));
+    assert!(!patterns.is_enabled("fold-constant-add"));
+    assert!(!patterns.set_enabled("no-such-pattern", false));
+    let region = func.operation().deref(ctx).region(0);
+    let converged =
+        apply_patterns_greedily(ctx, region, &mut patterns, &GreedyRewriteConfig::default())?;
+    assert!(converged);
+    assert_eq!(func.get_entry_block(ctx).deref(ctx).iter(ctx).count(), 7);
+    assert!(
+        patterns
+            .pattern_statistics("fold-constant-add")
+            .unwrap()
+            .attempted
+            == 0
+    );
+    assert!(constant_u64(ctx, r.deref(ctx).operand(0)).is_none());
+
+    // Patterns are tried by decreasing benefit, which can be overridden.
+    let order = Rc::new(RefCell::new(vec![]));
+    let mut patterns = RewritePatternSet::new();
+    patterns
+        .add(FoldConstantAdd)
+        .add(NeverMatches(order.clone()));
+    let (func, _) = constant_adds_func(ctx);
+    let region = func.operation().deref(ctx).region(0);
+    let config = GreedyRewriteConfig {
+        max_rewrites: Some(1),
+        ..GreedyRewriteConfig::default()
+    };
+    // The limit of rewrites is hit before converging.
+    assert!(!apply_patterns_greedily(
+        ctx,
+        region,
+        &mut patterns,
+        &config
+    )?);
+    assert!(order.borrow().is_empty());
+    assert!(patterns.set_benefit("never-matches", 5));
+    let (func, _) = constant_adds_func(ctx);
+    let region = func.operation().deref(ctx).region(0);
+    assert!(!apply_patterns_greedily(
+        ctx,
+        region,
+        &mut patterns,
+        &config
+    )?);
+    assert_eq!(*order.borrow(), vec!["never-matches"]);
+    Ok(())
+}
+
+#[op_interface_impl]
+impl CanonicalizeInterface for MaxOp {
+    fn canonicalize(&self, ctx: &mut Context, rewriter: &mut PatternRewriter) -> Result<bool> {
+        let op = self.operation();
+        let (lhs, rhs) = (op.deref(ctx).operand(0), op.deref(ctx).operand(1));
+        let (Some(lhs), Some(rhs)) = (constant_u64(ctx, lhs), constant_u64(ctx, rhs)) else {
+            return Ok(false);
+        };
+        let max = ConstantOp::new(ctx, lhs.max(rhs)).operation();
+        rewriter.replace_op(ctx, op, max);
+        Ok(true)
+    }
+}
+
+#[test]
+fn canonicalize() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    MulOp::register(ctx, MulOp::parser_fn);
+    MaxOp::register(ctx, MaxOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let module = ModuleOp::new(ctx, &"bar".try_into().unwrap());
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    module.append_operation(ctx, func.operation(), 0);
+
+    let entry = func.get_entry_block(ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+        op.insert_at_back(entry, ctx);
+        op.deref(ctx).result(0)
+    };
+    let c0 = ConstantOp::new(ctx, 0).operation();
+    let c0 = insert(ctx, c0);
+    let c2 = ConstantOp::new(ctx, 2).operation();
+    let c2 = insert(ctx, c2);
+    let c3 = ConstantOp::new(ctx, 3).operation();
+    let c3 = insert(ctx, c3);
+    // Unused, folded to 3 by MaxOp's canonicalization, and then erased.
+    let m = MaxOp::new(ctx, c2, c3).operation();
+    insert(ctx, m);
+    // s = a + 0 = a
+    let s = AddOp::new(ctx, a, c0).operation();
+    let s = insert(ctx, s);
+    // p = 3 * s = s * 3 = a * 3
+    let p = MulOp::new(ctx, c3, s).operation();
+    let p_res = insert(ctx, p);
+    ReturnOp::new(ctx, p_res)
+        .operation()
+        .insert_at_back(entry, ctx);
+
+    let mut pm = PassManager::new();
+    pm.nest::<FuncOp>().add_pass(CanonicalizePass::default());
+    pm.run(ctx, module.operation())?;
+    module.operation().verify(ctx)?;
+    assert_eq!(entry.deref(ctx).iter(ctx).count(), 3);
+    assert!(p.deref(ctx).operand(0) == a);
+    assert!(p.deref(ctx).operand(1) == c3);
+
+    let statistics = &pm.statistics()[0];
+    assert_eq!(statistics.0, "canonicalize");
+    for (pattern, applied) in [
+        ("op-canonicalize", 1),
+        ("algebraic-fold", 1),
+        ("constant-to-rhs", 1),
+        ("erase-dead-constant", 3),
+    ] {
+        assert_eq!(statistics.1.counter(&format!("{pattern}.applied")), applied);
+    }
+
+    // Nothing more to canonicalize.
+    let mut pass = CanonicalizePass::default();
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("op-canonicalize.applied"), 0);
+    assert_eq!(entry.deref(ctx).iter(ctx).count(), 3);
+    Ok(())
+}
+
+#[op_interface_impl]
+impl Foldable for MulOp {
+    fn fold(&self, ctx: &Context, operands: &[Option<AttrObj>]) -> Vec<OpFoldResult> {
+        let int_value = |operand: &Option<AttrObj>| {
+            let operand = operand.as_ref()?.downcast_ref::<IntegerAttr>()?;
+            Some(APInt::from(operand.clone()).to_u64())
+        };
+        match (int_value(&operands[0]), int_value(&operands[1])) {
+            (Some(lhs), Some(rhs)) => {
+                let ty = TypePtr::<IntegerType>::from_ptr(self.result_type(ctx), ctx).unwrap();
+                let product = IntegerAttr::new(ty, APInt::from_u64(lhs * rhs, bw(64)));
+                vec![OpFoldResult::Attribute(product.into())]
+            }
+            (_, Some(1)) => {
+                let lhs = self.operation().deref(ctx).operand(0);
+                vec![OpFoldResult::Value(lhs)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Materialize [IntegerAttr] constants as [ConstantOp]s.
+fn materialize_test_constant(
+    ctx: &mut Context,
+    value: AttrObj,
+    _ty: Ptr<TypeObj>,
+) -> Option<Ptr<Operation>> {
+    let value = APInt::from(value.downcast_ref::<IntegerAttr>()?.clone());
+    Some(ConstantOp::new(ctx, value.to_u64()).operation())
+}
+
+#[test]
+fn fold() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    MulOp::register(ctx, MulOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    let entry = func.get_entry_block(ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>| {
+        op.insert_at_back(entry, ctx);
+        op.deref(ctx).result(0)
+    };
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    let c1 = insert(ctx, c1);
+    let c2 = ConstantOp::new(ctx, 2).operation();
+    let c2 = insert(ctx, c2);
+    let c3 = ConstantOp::new(ctx, 3).operation();
+    let c3 = insert(ctx, c3);
+    // p = 2 * 3 = 6
+    let p = MulOp::new(ctx, c2, c3).operation();
+    let p_res = insert(ctx, p);
+    // q = a * 1 = a
+    let q = MulOp::new(ctx, a, c1).operation();
+    let q_res = insert(ctx, q);
+    // r = p * q
+    let r = MulOp::new(ctx, p_res, q_res).operation();
+    insert(ctx, r);
+    assert!(try_fold(ctx, r).is_none());
+
+    // Without a materializer, constants can't be created.
+    let rewriter = &mut PatternRewriter::default();
+    assert!(matches!(
+        &try_fold(ctx, p).unwrap()[..],
+        [OpFoldResult::Attribute(_)]
+    ));
+    assert!(!fold_op(ctx, rewriter, p));
+    assert!(r.deref(ctx).operand(0) == p_res);
+
+    ctx.dialects
+        .get_mut(&DialectName::new("test"))
+        .unwrap()
+        .set_constant_materializer(materialize_test_constant);
+    assert!(fold_op(ctx, rewriter, p));
+    assert!(!p.is_live(ctx));
+    assert_eq!(constant_u64(ctx, r.deref(ctx).operand(0)), Some(6));
+    assert!(fold_op(ctx, rewriter, q));
+    assert!(r.deref(ctx).operand(1) == a);
+
+    // Folding is part of canonicalization.
+    let r_res = r.deref(ctx).result(0);
+    let s = MulOp::new(ctx, r_res, c1).operation();
+    let s_res = insert(ctx, s);
+    ReturnOp::new(ctx, s_res)
+        .operation()
+        .insert_at_back(entry, ctx);
+    let mut pass = CanonicalizePass::default();
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("fold.applied"), 1);
+    assert!(!s.is_live(ctx));
+    Ok(())
+}
+
+#[test]
+fn nested_memory_effects() {
+    let ctx = &mut setup_context_dialects();
+    IfOp::register(ctx, IfOp::parser_fn);
+    LoadOp::register(ctx, LoadOp::parser_fn);
+    StoreYieldOp::register(ctx, StoreYieldOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let addr = ConstantOp::new(ctx, 8);
+    let addr = addr.result(ctx);
+
+    // if addr { load addr; yield } else { store_yield addr }
+    let if_op = IfOp::new(ctx, addr, vec![]).operation();
+    let blocks: Vec<_> = (0..2)
+        .map(|idx| {
+            let region = if_op.deref(ctx).region(idx);
+            region.deref(ctx).head().unwrap()
+        })
+        .collect();
+    let load = Operation::new(
+        ctx,
+        LoadOp::opid_static(),
+        vec![i64_ty],
+        vec![addr],
+        vec![],
+        0,
+    );
+    load.insert_at_back(blocks[0], ctx);
+    let yield_op = YieldOp::new(ctx, vec![]).operation();
+    yield_op.insert_at_back(blocks[0], ctx);
+    let store_yield = StoreYieldOp::new(ctx, addr).operation();
+    store_yield.insert_at_back(blocks[1], ctx);
+
+    let read = MemoryEffect::new(MemoryEffectKind::Read, MemoryEffectOn::Operand(0));
+    let write = MemoryEffect::new(MemoryEffectKind::Write, MemoryEffectOn::Operand(0));
+    assert_eq!(memory_effects(ctx, load), Some(vec![read]));
+    assert_eq!(memory_effects(ctx, store_yield), Some(vec![write]));
+    assert_eq!(memory_effects(ctx, if_op), Some(vec![]));
+    assert_eq!(memory_effects(ctx, yield_op), None);
+
+    // The effects of the side-effecting terminator count, the plain `yield` doesn't.
+    let only_reads =
+        |_: Ptr<Operation>, effect: &MemoryEffect| effect.kind == MemoryEffectKind::Read;
+    assert!(!is_pure(ctx, if_op) && !is_pure(ctx, store_yield));
+    assert!(!has_only_effects(ctx, if_op, &only_reads));
+    assert!(has_only_effects(ctx, if_op, &|_, _| true));
+    assert!(!has_only_effects(ctx, if_op, &|op, _| op == load));
+    assert!(has_only_effects(ctx, if_op, &|op, effect| {
+        op == load || (op == store_yield && *effect == write)
+    }));
+
+    // With a plain `yield` instead, only the load has effects.
+    Operation::erase(store_yield, ctx);
+    let yield_op = YieldOp::new(ctx, vec![]).operation();
+    yield_op.insert_at_back(blocks[1], ctx);
+    assert!(has_only_effects(ctx, if_op, &only_reads) && !is_pure(ctx, if_op));
+    Operation::erase(load, ctx);
+    assert!(is_pure(ctx, if_op));
+}
+
+#[test]
+fn dce() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    BrOp::register(ctx, BrOp::parser_fn);
+    IfOp::register(ctx, IfOp::parser_fn);
+    LoadOp::register(ctx, LoadOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    let region = func.operation().deref(ctx).region(0);
+    let entry = func.get_entry_block(ctx);
+    let dead = BasicBlock::new(ctx, None, vec![]);
+    dead.insert_at_back(region, ctx);
+    let exit = BasicBlock::new(ctx, None, vec![i64_ty]);
+    exit.insert_at_back(region, ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>, block: Ptr<BasicBlock>| {
+        op.insert_at_back(block, ctx);
+        op
+    };
+    let result = |ctx: &Context, op: Ptr<Operation>| op.deref(ctx).result(0);
+
+    // entry: x = a + 1; y = x + 1; load a; if a { 2 } else { 3 }; br exit(a + 1)
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    let c1 = insert(ctx, c1, entry);
+    let c1 = result(ctx, c1);
+    let x = AddOp::new(ctx, a, c1).operation();
+    let x = insert(ctx, x, entry);
+    let y = AddOp::new(ctx, result(ctx, x), c1).operation();
+    let y = insert(ctx, y, entry);
+    let load = Operation::new(ctx, LoadOp::opid_static(), vec![i64_ty], vec![a], vec![], 0);
+    let load = insert(ctx, load, entry);
+    let if_op = IfOp::new(ctx, a, vec![i64_ty]).operation();
+    let if_op = insert(ctx, if_op, entry);
+    for (region_idx, value) in [(0, 2), (1, 3)] {
+        let block = if_op.deref(ctx).region(region_idx);
+        let block = block.deref(ctx).head().unwrap();
+        let const_op = ConstantOp::new(ctx, value);
+        const_op.operation().insert_at_back(block, ctx);
+        let yield_op = YieldOp::new(ctx, vec![const_op.result(ctx)]);
+        yield_op.operation().insert_at_back(block, ctx);
+    }
+    let z = AddOp::new(ctx, a, c1).operation();
+    let z = insert(ctx, z, entry);
+    let br = BrOp::new(ctx, exit, vec![result(ctx, z)]).operation();
+    insert(ctx, br, entry);
+    // dead: br exit(a + 1)
+    let w = AddOp::new(ctx, a, c1).operation();
+    let w = insert(ctx, w, dead);
+    let br = BrOp::new(ctx, exit, vec![result(ctx, w)]).operation();
+    insert(ctx, br, dead);
+    // exit(v): return v
+    let v = exit.deref(ctx).argument(0);
+    let ret = ReturnOp::new(ctx, v).operation();
+    insert(ctx, ret, exit);
+
+    // x is used, and loads aren't pure, but only read memory.
+    assert!(is_trivially_dead(ctx, y) && is_trivially_dead(ctx, if_op));
+    assert!(!is_trivially_dead(ctx, x) && is_trivially_dead(ctx, load));
+    assert!(!is_trivially_dead(ctx, ret));
+    assert!(is_pure(ctx, if_op) && !is_pure(ctx, load));
+
+    let mut pass = DcePass::new();
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("blocks"), 1);
+    assert_eq!(pass.statistics().counter("ops"), 4);
+    assert!(!dead.is_live(ctx) && !w.is_live(ctx));
+    assert!(!x.is_live(ctx) && !y.is_live(ctx) && !if_op.is_live(ctx));
+    assert!(!load.is_live(ctx) && z.is_live(ctx));
+    assert_eq!(region.deref(ctx).iter(ctx).count(), 2);
+    func.operation().verify(ctx)?;
+
+    // Nothing is left to erase.
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("ops"), 4);
+    Ok(())
+}
+
+#[test]
+fn cse() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    BrOp::register(ctx, BrOp::parser_fn);
+    IfOp::register(ctx, IfOp::parser_fn);
+    LoadOp::register(ctx, LoadOp::parser_fn);
+    YieldOp::register(ctx, YieldOp::parser_fn);
+    let i64_ty = IntegerType::get(ctx, 64, Signedness::Signed).into();
+    let func_ty = FunctionType::get(ctx, vec![i64_ty], vec![i64_ty]);
+    let func = FuncOp::new(ctx, &"main".try_into().unwrap(), func_ty);
+    let region = func.operation().deref(ctx).region(0);
+    let entry = func.get_entry_block(ctx);
+    let exit = BasicBlock::new(ctx, None, vec![]);
+    exit.insert_at_back(region, ctx);
+    let a = entry.deref(ctx).argument(0);
+    let insert = |ctx: &mut Context, op: Ptr<Operation>, block: Ptr<BasicBlock>| {
+        op.insert_at_back(block, ctx);
+        op
+    };
+    let result = |ctx: &Context, op: Ptr<Operation>| op.deref(ctx).result(0);
+
+    // entry: x = a + 1; y = a + 1; load a; load a; if a { a + 1 } else { 1 }; br exit
+    let c1 = ConstantOp::new(ctx, 1).operation();
+    let c1 = insert(ctx, c1, entry);
+    let x = AddOp::new(ctx, a, result(ctx, c1)).operation();
+    let x = insert(ctx, x, entry);
+    let y = AddOp::new(ctx, a, result(ctx, c1)).operation();
+    let y = insert(ctx, y, entry);
+    let loads: Vec<_> = (0..2)
+        .map(|_| {
+            let load = Operation::new(ctx, LoadOp::opid_static(), vec![i64_ty], vec![a], vec![], 0);
+            insert(ctx, load, entry)
+        })
+        .collect();
+    let if_op = IfOp::new(ctx, a, vec![i64_ty]).operation();
+    let if_op = insert(ctx, if_op, entry);
+    let then_block = if_op.deref(ctx).region(0).deref(ctx).head().unwrap();
+    let nested = AddOp::new(ctx, a, result(ctx, c1)).operation();
+    let nested = insert(ctx, nested, then_block);
+    let yield_op = YieldOp::new(ctx, vec![result(ctx, nested)]).operation();
+    insert(ctx, yield_op, then_block);
+    let else_block = if_op.deref(ctx).region(1).deref(ctx).head().unwrap();
+    let else_c1 = ConstantOp::new(ctx, 1).operation();
+    let else_c1 = insert(ctx, else_c1, else_block);
+    let yield_op = YieldOp::new(ctx, vec![result(ctx, else_c1)]).operation();
+    insert(ctx, yield_op, else_block);
+    let br = BrOp::new(ctx, exit, vec![]).operation();
+    insert(ctx, br, entry);
+    // exit: return (a + 1) + y
+    let z = AddOp::new(ctx, a, result(ctx, c1)).operation();
+    let z = insert(ctx, z, exit);
+    let sum = AddOp::new(ctx, result(ctx, z), result(ctx, y)).operation();
+    let sum = insert(ctx, sum, exit);
+    let ret = ReturnOp::new(ctx, result(ctx, sum)).operation();
+    insert(ctx, ret, exit);
+
+    let mut pass = CsePass::new();
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("ops"), 4);
+    // All additions of a and 1, and the constants 1, are replaced by the first.
+    assert!(x.is_live(ctx) && c1.is_live(ctx));
+    assert!(!y.is_live(ctx) && !nested.is_live(ctx) && !z.is_live(ctx));
+    assert!(!else_c1.is_live(ctx));
+    assert!(sum.deref(ctx).operands().all(|opd| opd == result(ctx, x)));
+    // Loads aren't pure.
+    assert!(loads.iter().all(|load| load.is_live(ctx)));
+    func.operation().verify(ctx)?;
+
+    // Nothing is left to replace.
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("ops"), 4);
+    Ok(())
+}
+
+/// Converts signed integer types to signless ones.
+fn signless_converter() -> TypeConverter {
+    let mut type_converter = TypeConverter::new();
+    type_converter.add_conversion(|ctx, ty| {
+        let width = match ty.deref(ctx).downcast_ref::<IntegerType>() {
+            Some(int_ty) if int_ty.signedness() == Signedness::Signed => int_ty.width(),
+            _ => return Some(ty),
+        };
+        Some(IntegerType::get(ctx, width, Signedness::Signless).into())
+    });
+    type_converter
+}
+
+fn is_signless(ctx: &Context, ty: Ptr<TypeObj>) -> bool {
+    ty.deref(ctx)
+        .downcast_ref::<IntegerType>()
+        .is_none_or(|int_ty| int_ty.signedness() != Signedness::Signed)
+}
+
+/// Convert additions of signed integers to additions of signless ones.
+struct SignlessAdd;
+impl ConversionPattern for SignlessAdd {
+    fn name(&self) -> &str {
+        "signless-add"
+    }
+
+    fn root_opid(&self) -> Option<OpId> {
+        Some(AddOp::opid_static())
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut ConversionRewriter,
+        op: Ptr<Operation>,
+        operands: &[Value],
+    ) -> Result<bool> {
+        let add = AddOp::new(ctx, operands[0], operands[1]).operation();
+        rewriter.replace_op(ctx, op, add);
+        Ok(true)
+    }
+}
+
+/// Convert the arguments of functions to signless integers.
+struct SignlessFuncArgs;
+impl ConversionPattern for SignlessFuncArgs {
+    fn name(&self) -> &str {
+        "signless-func-args"
+    }
+
+    fn root_opid(&self) -> Option<OpId> {
+        Some(FuncOp::opid_static())
+    }
+
+    fn match_and_rewrite(
+        &self,
+        ctx: &mut Context,
+        rewriter: &mut ConversionRewriter,
+        op: Ptr<Operation>,
+        _operands: &[Value],
+    ) -> Result<bool> {
+        let func = Operation::op(op, ctx)
+            .downcast_ref::<FuncOp>()
+            .copied()
+            .unwrap();
+        let func_ty = func.get_type(ctx);
+        let (inputs, results) = {
+            let func_ty = func_ty.deref(ctx);
+            let func_ty = func_ty.downcast_ref::<FunctionType>().unwrap();
+            (func_ty.inputs().clone(), func_ty.results().clone())
+        };
+        let Some(inputs) = rewriter.type_converter().convert_types(ctx, &inputs) else {
+            return Ok(false);
+        };
+        rewriter.convert_region_types(ctx, func.region(ctx))?;
+        let new_func_ty = FunctionType::get(ctx, inputs, results);
+        rewriter.modify_op_in_place(ctx, op, |ctx| {
+            op.deref_mut(ctx).attributes.set(
+                *func_op::ATTR_KEY_FUNC_TYPE,
+                TypeAttr::new(new_func_ty.into()),
+            );
+        });
+        Ok(true)
+    }
+}
+
+fn signless_target() -> ConversionTarget {
+    let mut target = ConversionTarget::new();
+    target
+        .add_legal_op(ReturnOp::opid_static())
+        .add_dynamically_legal_op(AddOp::opid_static(), |ctx, op| {
+            is_signless(ctx, op.deref(ctx).result(0).get_type(ctx))
+        })
+        .add_dynamically_legal_op(FuncOp::opid_static(), |ctx, op| {
+            let func_ty = Operation::op(op, ctx)
+                .downcast_ref::<FuncOp>()
+                .copied()
+                .unwrap()
+                .get_type(ctx);
+            let func_ty = func_ty.deref(ctx);
+            let func_ty = func_ty.downcast_ref::<FunctionType>().unwrap();
+            func_ty.inputs().iter().all(|ty| is_signless(ctx, *ty))
+        });
+    target
+}
+
+#[test]
+fn dialect_conversion() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    let type_converter = signless_converter();
+    let mut patterns = ConversionPatternSet::new();
+    patterns.add(SignlessAdd).add(SignlessFuncArgs);
+
+    // Constants aren't converted, and are cast where the additions use them.
+    // The result is cast back for the return, while the casts between the
+    // converted additions, and of the converted argument, are erased.
+    let module = ModuleOp::new(ctx, &"conv".try_into().unwrap());
+    let (func, _) = constant_adds_func(ctx);
+    module.append_operation(ctx, func.operation(), 0);
+    let target = signless_target();
+    apply_partial_conversion(ctx, module.operation(), &target, &patterns, &type_converter)?;
+    module.operation().verify(ctx)?;
+    expect![[r#"
+        builtin.module @conv 
+        {
+          ^bb0():
+            builtin.func @main: builtin.function <(builtin.integer i64)->(builtin.integer si64)> 
+            {
+              ^entry(block_2v1_arg0:builtin.integer i64):
+                op_3v1_res0 = test.constant builtin.integer <1: si64>;
+                op_4v1_res0 = test.constant builtin.integer <2: si64>;
+                op_5v1_res0 = test.constant builtin.integer <3: si64>;
+                op_11v1_res0 = builtin.unrealized_conversion_cast op_3v1_res0 to builtin.integer i64;
+                op_12v1_res0 = builtin.unrealized_conversion_cast op_4v1_res0 to builtin.integer i64;
+                op_13v1_res0 = test.add (op_11v1_res0, op_12v1_res0) [] []: <(builtin.integer i64, builtin.integer i64) -> (builtin.integer i64)>;
+                op_6v3_res0 = builtin.unrealized_conversion_cast op_5v1_res0 to builtin.integer i64;
+                op_15v1_res0 = test.add (op_13v1_res0, op_6v3_res0) [] []: <(builtin.integer i64, builtin.integer i64) -> (builtin.integer i64)>;
+                op_7v3_res0 = test.add (op_15v1_res0, block_2v1_arg0) [] []: <(builtin.integer i64, builtin.integer i64) -> (builtin.integer i64)>;
+                op_17v1_res0 = builtin.unrealized_conversion_cast op_7v3_res0 to builtin.integer si64;
+                test.return op_17v1_res0
+            }
+        }"#]]
+    .assert_eq(&module.disp(ctx).to_string());
+
+    // Constants are neither legal nor illegal, so a full conversion fails on them.
+    let (func, _) = constant_adds_func(ctx);
+    let err = apply_full_conversion(ctx, func.operation(), &target, &patterns, &type_converter)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Failed to legalize operation test.constant")
+    );
+
+    // A partial conversion fails on operations left illegal.
+    let mut target = signless_target();
+    target.add_illegal_op(ConstantOp::opid_static());
+    let (func, _) = constant_adds_func(ctx);
+    let err = apply_partial_conversion(ctx, func.operation(), &target, &patterns, &type_converter)
+        .unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidInput));
+    assert!(
+        err.to_string()
+            .contains("Failed to legalize operation test.constant")
+    );
+
+    // The constants are still cast where the additions use them, so the
+    // full conversion succeeds only if both are legal.
+    let mut target = signless_target();
+    target.add_legal_dialect(DialectName::new("test"));
+    let (func, _) = constant_adds_func(ctx);
+    let err = apply_full_conversion(ctx, func.operation(), &target, &patterns, &type_converter)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Failed to legalize operation builtin.unrealized_conversion_cast")
+    );
+    target.add_legal_op(UnrealizedConversionCastOp::opid_static());
+    let (func, _) = constant_adds_func(ctx);
+    apply_full_conversion(ctx, func.operation(), &target, &patterns, &type_converter)?;
+    Ok(())
+}
+
+#[test]
+fn reconcile_casts() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    AddOp::register(ctx, AddOp::parser_fn);
+    let signless_ty = IntegerType::get(ctx, 64, Signedness::Signless).into();
+
+    // The result is cast to signless and back for the return.
+    let (func, r) = constant_adds_func(ctx);
+    let r_res = r.deref(ctx).result(0);
+    let r_ty = r_res.get_type(ctx);
+    let to_signless = UnrealizedConversionCastOp::new(ctx, r_res, signless_ty).operation();
+    to_signless.insert_after(ctx, r);
+    let signless_res = to_signless.deref(ctx).result(0);
+    let to_signed = UnrealizedConversionCastOp::new(ctx, signless_res, r_ty).operation();
+    to_signed.insert_after(ctx, to_signless);
+    let ret = func.get_entry_block(ctx).deref(ctx).tail().unwrap();
+    let signed_res = to_signed.deref(ctx).result(0);
+    Operation::replace_operand(ret, ctx, 0, signed_res);
+    // An unused cast is erased too.
+    let unused = UnrealizedConversionCastOp::new(ctx, r_res, signless_ty).operation();
+    unused.insert_after(ctx, r);
+
+    let mut pass = ReconcileCastsPass::new();
+    pass.run(ctx, func.operation())?;
+    assert_eq!(pass.statistics().counter("casts"), 3);
+    assert!(ret.deref(ctx).operand(0) == r_res);
+    assert!(!to_signless.is_live(ctx) && !to_signed.is_live(ctx) && !unused.is_live(ctx));
+
+    // The casts left by a partial conversion of the function remain used.
+    let mut patterns = ConversionPatternSet::new();
+    patterns.add(SignlessAdd).add(SignlessFuncArgs);
+    let (func, _) = constant_adds_func(ctx);
+    let loc = Location::Named {
+        name: "c1_cast".into(),
+        child_loc: Box::new(Location::Unknown),
+    };
+    apply_partial_conversion(
+        ctx,
+        func.operation(),
+        &signless_target(),
+        &patterns,
+        &signless_converter(),
+    )?;
+    let first_cast = func
+        .get_entry_block(ctx)
+        .deref(ctx)
+        .iter(ctx)
+        .find(|op| {
+            Operation::op(*op, ctx)
+                .downcast_ref::<UnrealizedConversionCastOp>()
+                .is_some()
+        })
+        .unwrap();
+    first_cast.deref_mut(ctx).set_loc(loc.clone());
+    let err = reconcile_unrealized_casts(ctx, func.operation()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidInput));
+    assert!(err.loc == loc);
+    expect!["Cast from builtin.integer si64 to builtin.integer i64 remains after conversion (4 other casts remain)"]
+        .assert_eq(&err.err.to_string());
+    Ok(())
+}
+
+#[test]
+fn transform_interpreter() -> Result<()> {
+    let ctx = &mut setup_context_dialects();
+    let marked: Identifier = "test_marked".try_into().unwrap();
+    let num_counted = Rc::new(RefCell::new(0));
+    let mut registry = TransformRegistry::with_builtins();
+    registry
+        .register("mark", |ctx, op, params| {
+            let key: String = params.required("key")?;
+            op.deref_mut(ctx)
+                .attributes
+                .set(key.try_into().unwrap(), UnitAttr::new());
+            Ok(())
+        })
+        .register("count", {
+            let num_counted = num_counted.clone();
+            move |_, _, _| {
+                *num_counted.borrow_mut() += 1;
+                Ok(())
+            }
+        });
+
+    // Fully unroll the loop, and mark and count the additions it leaves.
+    let (func, _) = sum_loop_func(ctx, 2, Some(5), 1);
+    let script = TransformScript::parse(
+        Source::InMemory,
+        "# Unroll first.\nunroll on test.for\n\nmark(key = test_marked) on test.add; count with test_marked # Done.",
+    )?;
+    assert_eq!(script.steps().len(), 3);
+    assert_eq!(script.steps()[1].params.get("key"), Some("test_marked"));
+    assert_eq!(script.apply(ctx, &registry, func.operation())?, 7);
+    assert_eq!(*num_counted.borrow(), 3);
+    let entry = func.get_entry_block(ctx);
+    assert!(entry.deref(ctx).iter(ctx).all(|op| {
+        op.deref(ctx).attributes.0.contains_key(&marked)
+            == (op.deref(ctx).opid() == AddOp::opid_static())
+    }));
+
+    // As a pass, unrolling by a factor.
+    let (func, for_op) = sum_loop_func(ctx, 0, Some(10), 3);
+    let script = TransformScript::parse(Source::InMemory, r#"unroll(factor = "2") on test.for"#)?;
+    let mut pm = PassManager::new();
+    pm.add_pass(TransformInterpreterPass::new(
+        script,
+        TransformRegistry::with_builtins(),
+    ));
+    pm.run(ctx, func.operation())?;
+    func.operation().verify(ctx)?;
+    assert_eq!(for_op.body(ctx, 0).deref(ctx).iter(ctx).count(), 5);
+    let statistics = &pm.statistics()[0];
+    assert_eq!(statistics.0, "transform-interpreter");
+    assert_eq!(statistics.1.counter("transformed"), 1);
+
+    // Errors, located in the script.
+    let fails_with =
+        |err: Error, kind: ErrorKind, line, column, expected: fn(&TransformScriptErr) -> bool| {
+            let loc = Location::SrcPos {
+                src: Source::InMemory,
+                pos: SourcePosition { line, column },
+            };
+            std::mem::discriminant(&err.kind) == std::mem::discriminant(&kind)
+                && err.loc == loc
+                && err
+                    .err
+                    .downcast_ref::<TransformScriptErr>()
+                    .is_some_and(expected)
+        };
+    let res = TransformScript::parse(Source::InMemory, "canonicalize\nunroll(factor 2)");
+    assert!(fails_with(
+        res.unwrap_err(),
+        ErrorKind::InvalidInput,
+        2,
+        15,
+        |err| matches!(err, TransformScriptErr::Expected { .. })
+    ));
+    let res = TransformScript::parse(Source::InMemory, "unroll at test.for");
+    assert!(fails_with(
+        res.unwrap_err(),
+        ErrorKind::InvalidInput,
+        1,
+        8,
+        |err| matches!(err, TransformScriptErr::UnknownClause(_))
+    ));
+
+    let printed = func.disp(ctx).to_string();
+    let apply = |ctx: &mut Context, text: &str| {
+        let script = TransformScript::parse(Source::InMemory, text)?;
+        script.apply(ctx, &registry, func.operation())
+    };
+    let res = apply(ctx, "strip-locations\n  canonicalise");
+    assert!(fails_with(
+        res.unwrap_err(),
+        ErrorKind::InvalidInput,
+        2,
+        3,
+        |err| matches!(err, TransformScriptErr::UnknownTransform(_))
+    ));
+    let res = apply(ctx, "unroll on test.return");
+    assert!(fails_with(
+        res.unwrap_err(),
+        ErrorKind::InvalidArgument,
+        1,
+        1,
+        |err| matches!(err, TransformScriptErr::UnsupportedOp { .. })
+    ));
+    let res = apply(ctx, "unroll(factor = two) on test.for");
+    assert!(fails_with(
+        res.unwrap_err(),
+        ErrorKind::InvalidArgument,
+        1,
+        1,
+        |err| matches!(err, TransformScriptErr::InvalidParam { .. })
+    ));
+    assert_eq!(func.disp(ctx).to_string(), printed);
+    Ok(())
+}